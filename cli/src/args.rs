@@ -15,6 +15,10 @@ pub struct Arguments {
     /// Defaults to `~/.tari/wallets`.
     #[structopt(short, long, env = "VALIDATION_NODE_WALLETS")]
     pub wallets_keys_path: Option<std::path::PathBuf>,
+    /// Passphrase for encrypting wallets key files.
+    /// Can be overloaded via env `VALIDATION_NODE_WALLETS_PASSPHRASE`.
+    #[structopt(long, env = "VALIDATION_NODE_WALLETS_PASSPHRASE", hide_env_values = true)]
+    pub wallets_passphrase: Option<String>,
     #[structopt(subcommand)]
     pub command: Commands,
 }
@@ -44,6 +48,13 @@ impl Arguments {
                 .get_or_insert(default_path("wallets", Some(&self.bootstrap.base_path)));
             config.set("validator.wallets_keys_path", wallet_path.to_str())?;
         };
+        if let Some(passphrase) = self.wallets_passphrase.as_ref() {
+            config.set("validator.wallets_passphrase", passphrase.as_str())?;
+        };
+        if config.get_str("validator.identity_path").is_err() {
+            let identity_path = default_path("validator_node_id.json", Some(&self.bootstrap.base_path));
+            config.set("validator.identity_path", identity_path.to_str())?;
+        };
         Ok(config)
     }
 }
@@ -1,20 +1,45 @@
+use chrono::{DateTime, Utc};
 use structopt::StructOpt;
 use tari_validator_node::{
+    api::models::{AccessToken, JwtSecret, ACCESS_TOKEN_ISSUER},
     config::NodeConfig,
     db::{
         models::{Access, AccessResource, NewAccess, SelectAccess},
+        retention::parse_date,
         utils::db::db_client,
     },
+    types::AssetID,
 };
 
 #[derive(StructOpt, Debug)]
 pub enum AccessCommands {
-    /// Allow access for public key
-    Grant(AccessType),
-    /// List access tokens
-    List,
-    /// Revoke access for public key
-    Revoke(AccessType),
+    /// Allow access for public key, API and admin access prints new access token
+    Grant {
+        /// Access stops authorizing requests after the date (RFC3339 or YYYY-MM-DD), never expires when omitted
+        #[structopt(long, parse(try_from_str = parse_date))]
+        expires: Option<DateTime<Utc>>,
+        #[structopt(subcommand)]
+        access_type: AccessType,
+    },
+    /// Issue new API access token for public key with granted API access
+    Token {
+        /// Public key of api user
+        #[structopt(short = "k", long)]
+        pubkey: String,
+    },
+    /// List access records including expired and revoked ones
+    List {
+        /// List only access records authorizing requests
+        #[structopt(long)]
+        active: bool,
+    },
+    /// Revoke access key by access record id, e.g. after a leak, or revoke access for public key
+    Revoke {
+        /// Access record id, see `access list`
+        id: Option<uuid::Uuid>,
+        #[structopt(subcommand)]
+        access_type: Option<AccessType>,
+    },
 }
 
 #[derive(StructOpt, Debug)]
@@ -24,6 +49,19 @@ pub enum AccessType {
         /// Public key of api user
         #[structopt(short = "k", long)]
         pubkey: String,
+        /// Limit access to template `<template_id>` or contract `<template_id>/<contract>`, repeatable.
+        /// Grants access to all templates when omitted
+        #[structopt(short = "s", long = "scope")]
+        scopes: Vec<String>,
+        /// Limit access to the asset, repeatable. Grants access to all assets when omitted
+        #[structopt(short = "a", long = "asset")]
+        assets: Vec<AssetID>,
+    },
+    /// Access to admin API, e.g. contract switches
+    Admin {
+        /// Public key of admin user
+        #[structopt(short = "k", long)]
+        pubkey: String,
     },
     /// Access to Wallet funds
     Wallet {
@@ -37,34 +75,84 @@ pub enum AccessType {
 }
 
 impl AccessCommands {
-    pub async fn run(self, node_config: NodeConfig) -> anyhow::Result<()> {
+    pub async fn run(self, mut node_config: NodeConfig) -> anyhow::Result<()> {
         let client = db_client(&node_config).await?;
         match self {
-            Self::Grant(access_type) => {
-                let updated = Access::grant(NewAccess::from(access_type), &client).await?;
+            Self::Grant { expires, access_type } => {
+                let access = NewAccess {
+                    expires_at: expires,
+                    ..NewAccess::from(access_type)
+                };
+                let updated = Access::grant(access.clone(), &client).await?;
                 println!("Granted {}", updated);
+                if access.resource != AccessResource::Wallet {
+                    println!("Access token: {}", issue_token(access.pub_key, &mut node_config)?);
+                }
             },
-            Self::List => {
-                let access = Access::select(SelectAccess::default(), &client).await?;
-                for rec in access {
+            Self::Token { pubkey } => {
+                let query = SelectAccess {
+                    pub_key: Some(pubkey.clone()),
+                    resource: AccessResource::Api,
+                    ..SelectAccess::default()
+                };
+                if Access::select(query, &client).await?.is_empty() {
+                    anyhow::bail!("API access is not granted for {}", pubkey);
+                }
+                println!("Access token: {}", issue_token(pubkey, &mut node_config)?);
+            },
+            Self::List { active } => {
+                let query = SelectAccess {
+                    include_inactive: Some(!active),
+                    ..SelectAccess::default()
+                };
+                for rec in Access::select(query, &client).await? {
                     println!("{}", rec)
                 }
             },
-            Self::Revoke(access_type) => {
+            Self::Revoke {
+                id: Some(id),
+                access_type: None,
+            } => {
+                let updated = Access::revoke_key(id, &client).await?;
+                println!("Revoked {}", updated);
+            },
+            Self::Revoke {
+                id: None,
+                access_type: Some(access_type),
+            } => {
                 let updated = Access::revoke(SelectAccess::from(access_type), &client).await?;
                 println!("Revoked {}", updated);
             },
+            Self::Revoke { .. } => anyhow::bail!("Either access record id or access type is required"),
         };
         Ok(())
     }
 }
 
+/// Signed access token valid for `auth.token_expiry_minutes`
+pub(crate) fn issue_token(pubkey: String, node_config: &mut NodeConfig) -> anyhow::Result<String> {
+    let secret = JwtSecret(node_config.jwt_secret()?);
+    let token = AccessToken::new(
+        pubkey,
+        ACCESS_TOKEN_ISSUER.into(),
+        node_config.auth.token_expiry_minutes,
+    );
+    Ok(token.encode(&secret)?)
+}
+
 impl From<AccessType> for NewAccess {
     fn from(access: AccessType) -> Self {
         match access {
-            AccessType::Api { pubkey } => NewAccess {
+            AccessType::Api { pubkey, scopes, assets } => NewAccess {
                 pub_key: pubkey,
                 resource: AccessResource::Api,
+                scopes,
+                assets,
+                ..NewAccess::default()
+            },
+            AccessType::Admin { pubkey } => NewAccess {
+                pub_key: pubkey,
+                resource: AccessResource::Admin,
                 ..NewAccess::default()
             },
             AccessType::Wallet { pubkey, wallet } => NewAccess {
@@ -80,11 +168,16 @@ impl From<AccessType> for NewAccess {
 impl From<AccessType> for SelectAccess {
     fn from(access: AccessType) -> Self {
         match access {
-            AccessType::Api { pubkey } => SelectAccess {
+            AccessType::Api { pubkey, .. } => SelectAccess {
                 pub_key: Some(pubkey),
                 resource: AccessResource::Api,
                 ..SelectAccess::default()
             },
+            AccessType::Admin { pubkey } => SelectAccess {
+                pub_key: Some(pubkey),
+                resource: AccessResource::Admin,
+                ..SelectAccess::default()
+            },
             AccessType::Wallet { pubkey, wallet } => SelectAccess {
                 pub_key: Some(pubkey),
                 resource: AccessResource::Wallet,
@@ -1,15 +1,30 @@
-use super::MakeItRain;
+use super::{templates::installed_templates, InstructionCommands, MakeItRain};
 use crate::console::Terminal;
 use deadpool_postgres::Client;
-use serde_json::json;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde_json::{json, Value};
+use std::path::PathBuf;
 use structopt::StructOpt;
 use tari_validator_node::{
     config::NodeConfig,
+    consensus::CommitteeCandidate,
     db::{
-        models::{asset_states::*, digital_assets::*},
-        utils::db::db_client,
+        lifecycle::{create_asset, handover_asset, verify_raid, NewAsset},
+        models::{
+            asset_states::*,
+            consensus::instructions::NewInstruction,
+            contract_permissions::*,
+            digital_assets::*,
+            webhooks::*,
+            ContractPermissionRule,
+        },
+        utils::{
+            db::{db_client, db_client_raw},
+            statements::CachedClient,
+        },
     },
-    types::{AssetID, Pubkey, RaidID, TemplateID},
+    sync::{export_snapshot, import_batches, read_snapshot, sync_asset, write_snapshot},
+    types::{AssetID, CommitteeMode, InstructionID, Pubkey, RaidID, TemplateID},
 };
 
 #[derive(StructOpt, Debug)]
@@ -26,23 +41,84 @@ pub enum AssetCommands {
         /// Work with tokens of asset
         asset_id: AssetID,
     },
+    /// Permit calling asset contract by rule, contract is open to any caller until first rule is added
+    Permit(ContractPermissionArgs),
+    /// Remove contract permission rule
+    Unpermit(ContractPermissionArgs),
+    /// List contract permission rules of asset
+    Permissions {
+        /// Asset ID
+        asset_id: AssetID,
+    },
+    /// Register webhook notified about asset instructions and proposals, secret is generated if not provided
+    WebhookAdd {
+        /// Asset ID
+        asset_id: AssetID,
+        /// URL receiving events as JSON POST requests
+        url: String,
+        /// Secret signing payloads
+        #[structopt(short = "s", long)]
+        secret: Option<String>,
+    },
+    /// Remove webhook along with its delivery log
+    WebhookRemove {
+        /// Webhook ID
+        id: uuid::Uuid,
+    },
+    /// List webhooks of asset
+    Webhooks {
+        /// Asset ID
+        asset_id: AssetID,
+    },
+    /// List latest deliveries of webhook
+    WebhookDeliveries {
+        /// Webhook ID
+        id: uuid::Uuid,
+        /// Max number of deliveries
+        #[structopt(short = "n", long, default_value = "20")]
+        limit: i64,
+    },
+    /// Migrate asset to a new committee: asset is frozen until consensus commits the handover
+    Handover {
+        /// Asset ID
+        asset_id: AssetID,
+        /// NodeIDs of the new committee, collateral is taken from committee candidates of the node config
+        #[structopt(required = true)]
+        nodes: Vec<String>,
+    },
     /// Runs load scenario on a Single Use Token asset:
     ///
-    /// 1. Issue `tokens` quantity of tokens
-    /// 2. Start `concurrency` parallel users
-    /// 3. For every user create unique pubkey and get chunk of tokens
-    /// 4. issue sell_token
-    /// 5. Once instruction goes to Commit - send redeem_token
-    /// 6. Repeat step 4 with next token from chunk
+    /// sell-redeem (default): every user issues own chunk of tokens, sells every token and redeems it back
+    ///
+    /// transfer-churn: every user gets one token and transfers it back and forth `tokens / concurrency` times
     MakeItRain(MakeItRain),
+    /// Exports asset state, tokens, append only history and instructions into zstd compressed tar archive
+    Export {
+        /// Asset ID
+        asset_id: AssetID,
+        /// Archive path, defaults to <asset-id>.tar.zst
+        #[structopt(short = "o", long)]
+        out: Option<PathBuf>,
+    },
+    /// Imports asset from archive written by `export`, asset must not be present on the node
+    Import {
+        /// Archive path
+        file: PathBuf,
+    },
+    /// Imports asset state from peer nodes configured in [validator.sync], asset must not be present on the node
+    Sync {
+        /// Asset ID
+        asset_id: AssetID,
+    },
 }
 
 #[derive(StructOpt, Debug)]
 pub struct CreateAsset {
-    /// TemplateID in the form {type}.{version}
+    /// TemplateID in the form {type}.{version}, template has to be installed on the node
+    #[structopt(short = "t", long)]
     pub template: TemplateID,
     /// Name of the asset
-    #[structopt(empty_values = false)]
+    #[structopt(short = "n", long, empty_values = false)]
     pub name: String,
     /// Description
     #[structopt(short = "d", long, default_value)]
@@ -59,6 +135,34 @@ pub struct CreateAsset {
     /// Additional data as a JSON in a string
     #[structopt(long)]
     pub data: Option<String>,
+    /// Maximum number of tokens of the asset owned by a single wallet
+    #[structopt(long)]
+    pub limit_per_wallet: Option<u32>,
+    /// Maximum number of tokens issued for the asset
+    #[structopt(long)]
+    pub max_supply: Option<u32>,
+    /// Asset contract called via node API once asset is created, e.g. issue_tokens
+    #[structopt(long)]
+    pub register: Option<String>,
+    /// Params of the registration contract call as a JSON in a string
+    #[structopt(long, default_value = "{}")]
+    pub register_data: Value,
+    /// Committee mode as a JSON in a string, e.g. '{"Creator":{"trusted_node_set":[]}}' (Default: this node)
+    #[structopt(long, parse(try_from_str = serde_json::from_str))]
+    pub committee_mode: Option<CommitteeMode>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ContractPermissionArgs {
+    /// Asset ID
+    pub asset_id: AssetID,
+    /// Contract name, e.g. issue_tokens
+    pub contract: String,
+    /// Rule: AssetIssuer, TokenOwner or PubKey
+    pub rule: ContractPermissionRule,
+    /// Permitted pubkey, required by PubKey rule
+    #[structopt(short = "k", long)]
+    pub pubkey: Option<String>,
 }
 
 impl AssetCommands {
@@ -66,8 +170,28 @@ impl AssetCommands {
         let client = db_client(&node_config).await?;
         match self {
             Self::Create(create) => {
-                let asset = create.run(&client).await?;
+                let register = create.register.clone();
+                let register_data = create.register_data.clone();
+                let asset = create.run(&node_config, &client).await?;
+                let raid_status = verify_raid(&asset, &node_config.raid, &client).await?;
+                let asset_id = asset.asset_id.clone();
                 Terminal::basic().render_object("Asset created! Details:", asset);
+                println!("AssetID: {}", asset_id);
+                println!("AssetID (bech32): {:#}", asset_id);
+                println!("RAID: {}", raid_status);
+                if let Some(contract_name) = register {
+                    let client = CachedClient::new(db_client_raw(&node_config).await?);
+                    InstructionCommands::Asset {
+                        asset_id,
+                        contract_name,
+                        data: register_data,
+                        silent: false,
+                        wait_commit: false,
+                        dry_run: false,
+                    }
+                    .run(node_config, &client)
+                    .await?;
+                }
             },
             Self::List { template } => {
                 let assets = AssetState::find_by_template_id(&template, &client).await?;
@@ -97,52 +221,194 @@ impl AssetCommands {
                     println!("Asset not found!");
                 }
             },
+            Self::Permit(args) => {
+                ContractPermission::insert(
+                    NewContractPermission {
+                        asset_id: args.asset_id,
+                        contract_name: args.contract,
+                        rule: args.rule,
+                        pub_key: args.pubkey,
+                    },
+                    &client,
+                )
+                .await?;
+                println!("Permission added");
+            },
+            Self::Unpermit(args) => {
+                let rules = ContractPermission::find_by_contract(&args.asset_id, &args.contract, &client).await?;
+                let mut removed = 0;
+                for rule in rules {
+                    if rule.rule == args.rule && rule.pub_key == args.pubkey {
+                        removed += ContractPermission::delete(rule.id, &client).await?;
+                    }
+                }
+                println!("Removed {} permissions", removed);
+            },
+            Self::Permissions { asset_id } => {
+                let output: Vec<_> = ContractPermission::find_by_asset_id(&asset_id, &client)
+                    .await?
+                    .into_iter()
+                    .map(|p| json!({"Contract": p.contract_name, "Rule": p.rule, "Pubkey": p.pub_key}))
+                    .collect();
+                Terminal::basic().render_list(
+                    format!("Contract permissions of asset {}", asset_id).as_str(),
+                    output,
+                    &["Contract", "Rule", "Pubkey"],
+                    &[20, 12, 64],
+                );
+            },
+            Self::WebhookAdd { asset_id, url, secret } => {
+                let secret = secret.unwrap_or_else(|| thread_rng().sample_iter(&Alphanumeric).take(32).collect());
+                let webhook = Webhook::insert(
+                    NewWebhook {
+                        asset_id,
+                        url,
+                        secret: secret.clone(),
+                    },
+                    &client,
+                )
+                .await?;
+                println!("Webhook {} added, payloads are signed with secret: {}", webhook.id, secret);
+            },
+            Self::WebhookRemove { id } => {
+                println!("Removed {} webhooks", Webhook::delete(id, &client).await?);
+            },
+            Self::Webhooks { asset_id } => {
+                let output: Vec<_> = Webhook::find_by_asset_id(&asset_id, &client)
+                    .await?
+                    .into_iter()
+                    .map(|w| json!({"Id": w.id, "Url": w.url, "Created": w.created_at}))
+                    .collect();
+                Terminal::basic().render_list(
+                    format!("Webhooks of asset {}", asset_id).as_str(),
+                    output,
+                    &["Id", "Url", "Created"],
+                    &[36, 50, 32],
+                );
+            },
+            Self::WebhookDeliveries { id, limit } => {
+                let output: Vec<_> = WebhookDelivery::find_by_webhook_id(id, limit, &client)
+                    .await?
+                    .into_iter()
+                    .map(|d| {
+                        json!({
+                            "Id": d.id,
+                            "Event": d.event,
+                            "Status": d.status,
+                            "Attempts": d.attempts,
+                            "Error": d.last_error,
+                            "Created": d.created_at,
+                        })
+                    })
+                    .collect();
+                Terminal::basic().render_list(
+                    format!("Deliveries of webhook {}", id).as_str(),
+                    output,
+                    &["Id", "Event", "Status", "Attempts", "Error", "Created"],
+                    &[36, 20, 10, 8, 30, 32],
+                );
+            },
+            Self::Handover { asset_id, nodes } => {
+                let asset = AssetState::find_by_asset_id(&asset_id, &client)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Asset {} not found", asset_id))?;
+                let candidates = &node_config.consensus.committee_candidates;
+                let members = nodes
+                    .into_iter()
+                    .map(|node_id| CommitteeCandidate {
+                        collateral: candidates
+                            .iter()
+                            .find(|candidate| candidate.node_id == node_id)
+                            .map(|candidate| candidate.collateral)
+                            .unwrap_or(0),
+                        node_id,
+                    })
+                    .collect();
+                let node_id = node_config.clone().load_signer()?.node_id();
+                let instruction = NewInstruction {
+                    id: InstructionID::new(node_id)?,
+                    initiating_node_id: node_id,
+                    ..NewInstruction::default()
+                };
+                let instruction = handover_asset(&asset, members, instruction, &client).await?;
+                println!("Asset {} is frozen, handover instruction {} is pending", asset_id, instruction.id);
+            },
             Self::MakeItRain(mir) => {
                 mir.run(node_config).await?;
             },
+            Self::Export { asset_id, out } => {
+                let node_id = node_config.clone().load_signer()?.node_id();
+                let batch = export_snapshot(&asset_id, node_id, &client).await?;
+                let path = out.unwrap_or_else(|| PathBuf::from(format!("{}.tar.zst", asset_id)));
+                let manifest = write_snapshot(&batch, &path)?;
+                println!(
+                    "Asset {} exported to {}: {} tokens, {} instructions, {} records",
+                    asset_id,
+                    path.display(),
+                    manifest.tokens,
+                    manifest.instructions,
+                    manifest.records
+                );
+            },
+            Self::Import { file } => {
+                let (manifest, batch) = read_snapshot(&file)?;
+                let records = import_batches(vec![batch], &client).await?;
+                println!(
+                    "Asset {} exported by node {} at {} imported, {} records",
+                    manifest.asset_id, manifest.node_id, manifest.exported_at, records
+                );
+            },
+            Self::Sync { asset_id } => {
+                let records = sync_asset(
+                    &node_config.sync,
+                    node_config.actix.tls.as_ref(),
+                    &asset_id,
+                    None,
+                    &client,
+                )
+                .await?;
+                println!("Asset {} synced, {} records imported", asset_id, records);
+            },
         };
         Ok(())
     }
 }
 
 impl CreateAsset {
-    async fn run(self, client: &Client) -> anyhow::Result<AssetState> {
-        let da_id = DigitalAsset::insert(
-            NewDigitalAsset {
-                template_type: self.template.template_type(),
-                fqdn: self.fqdn.clone(),
-                raid_id: self.raid_id.clone(),
-                ..Default::default()
-            },
-            &client,
-        )
-        .await?;
-        let raid_id: RaidID = self
-            .raid_id
-            .map(|rid| rid.parse().unwrap())
-            .unwrap_or(RaidID::default());
+    /// Creates digital asset and asset state atomically
+    async fn run(self, node_config: &NodeConfig, client: &Client) -> anyhow::Result<AssetState> {
+        if !installed_templates().contains(&self.template) {
+            anyhow::bail!("Template {} is not installed", self.template);
+        }
+        let raid_id: RaidID = match self.raid_id.as_ref() {
+            Some(raid_id) => raid_id.parse()?,
+            None => RaidID::default(),
+        };
         // TODO: this is a stub:
         let hash = AssetID::generate_hash(format!(
             "{}{}{:?}{:?}{:?}",
             self.name, self.description, self.fqdn, raid_id, self.data
         ));
-        let id = AssetState::insert(
-            NewAssetState {
-                name: self.name,
-                description: self.description,
-                asset_id: AssetID::new(self.template, 0, raid_id, hash),
-                asset_issuer_pub_key: self.issuer,
-                digital_asset_id: da_id,
-                initial_data_json: self
-                    .data
-                    .map(|data| serde_json::from_str(&data).unwrap())
-                    .unwrap_or(json!({})),
-                ..Default::default()
-            },
-            &client,
-        )
-        .await?;
-        Ok(AssetState::load(id, &client).await?)
+        let initial_data_json = match self.data {
+            Some(data) => serde_json::from_str(&data)?,
+            None => json!({}),
+        };
+        let params = NewAsset {
+            asset_id: AssetID::new(self.template, 0, raid_id, hash),
+            name: self.name,
+            description: self.description,
+            fqdn: self.fqdn,
+            limit_per_wallet: self.limit_per_wallet,
+            max_supply: self.max_supply,
+            allow_transfers: false,
+            authorized_signers: vec![],
+            expiry_date: None,
+            initial_data_json,
+            committee_mode: self.committee_mode.unwrap_or_default(),
+        };
+        let node_id = node_config.clone().load_signer().ok().map(|signer| signer.node_id());
+        let candidates = &node_config.consensus.committee_candidates;
+        Ok(create_asset(params, self.issuer, node_id, candidates, client).await?)
     }
 }
 
@@ -151,6 +417,7 @@ mod test {
     use super::*;
     use crate::test_utils::build_test_config;
     use tari_test_utils::random::string;
+    use tari_validator_node::db::models::CommitteeMember;
 
     #[actix_rt::test]
     async fn test_asset_create() {
@@ -164,10 +431,45 @@ mod test {
             raid_id: None,
             issuer: "user_pub_key".into(),
             data: Some(format!(r#"{{ "custom": "{}" }}"#, string(8))),
+            limit_per_wallet: Some(2),
+            max_supply: Some(100),
+            register: None,
+            register_data: json!({}),
+            committee_mode: Some(CommitteeMode::Creator {
+                trusted_node_set: vec!["node".into()],
+            }),
         }
-        .run(&client)
+        .run(&config, &client)
         .await
         .unwrap();
         assert_eq!(asset.name, "may rocket launch".to_string());
+        assert_eq!(asset.asset_id.template_id(), 1.into());
+        assert_eq!(asset.limit_per_wallet, Some(2));
+        assert_eq!(asset.max_supply, Some(100));
+        let committee = CommitteeMember::find_by_asset_id(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(committee[0].node_id, "node");
+    }
+
+    #[actix_rt::test]
+    async fn test_asset_create_unknown_template() {
+        let config = build_test_config().unwrap();
+        let client = db_client(&config).await.unwrap();
+        let res = CreateAsset {
+            template: 999.into(),
+            name: "unknown".into(),
+            description: "".into(),
+            fqdn: None,
+            raid_id: None,
+            issuer: "user_pub_key".into(),
+            data: None,
+            limit_per_wallet: None,
+            max_supply: None,
+            register: None,
+            register_data: json!({}),
+            committee_mode: None,
+        }
+        .run(&config, &client)
+        .await;
+        assert!(res.is_err());
     }
 }
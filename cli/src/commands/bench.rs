@@ -0,0 +1,380 @@
+use super::make_it_rain::{fill_wallet, wait_wallet};
+use crate::console::Terminal;
+use deadpool::managed::PoolConfig;
+use deadpool_postgres::Pool;
+use futures::future::{join_all, Future};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    ops::AddAssign,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tari_validator_client::ValidatorClient;
+use tari_validator_node::{
+    config::NodeConfig,
+    db::utils::db::build_pool,
+    template::single_use_tokens::{IssueTokensParams, SellTokenParams, TransferTokenParams},
+    types::{AssetID, TokenID},
+};
+
+const MAX_RETRIES: usize = 300;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(StructOpt, Debug, Clone)]
+/// Measures instruction throughput and latencies on a Single Use Token asset:
+///
+/// 1. Every of `concurrency` users issues `iterations` tokens one by one with issue_tokens
+/// 2. Once all users are done - every user sells own tokens with sell_token
+/// 3. Once all users are done - every user transfers sold tokens with transfer_token
+///
+/// Latency is measured from the API call until instruction is committed,
+/// report is saved to `output` and compared with `baseline` if provided
+pub struct Bench {
+    /// Target asset in the Single Use Token template
+    asset_id: AssetID,
+    /// How many parallel users to run
+    #[structopt(short = "c", long, default_value = "4")]
+    concurrency: u16,
+    /// How many calls of every contract to make per user
+    #[structopt(short = "n", long, default_value = "25")]
+    iterations: u16,
+    /// Timeout for sell_token instruction
+    #[structopt(long, default_value = "30")]
+    timeout: u64,
+    /// API access token, required unless auth is disabled on the node
+    #[structopt(long)]
+    access_token: Option<String>,
+    /// Save JSON report to the file, defaults to bench-<version>.json
+    #[structopt(short = "o", long)]
+    output: Option<PathBuf>,
+    /// Compare results with JSON report of previous run
+    #[structopt(short = "b", long)]
+    baseline: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Contract {
+    IssueTokens,
+    SellToken,
+    TransferToken,
+}
+
+impl Contract {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::IssueTokens => "issue_tokens",
+            Self::SellToken => "sell_token",
+            Self::TransferToken => "transfer_token",
+        }
+    }
+}
+
+impl Bench {
+    pub async fn run(self, mut node_config: NodeConfig) -> anyhow::Result<()> {
+        node_config.postgres.pool = Some(PoolConfig {
+            max_size: self.concurrency as usize,
+            ..Default::default()
+        });
+        let pool = build_pool(&node_config.postgres)?;
+        let url = format!("http://localhost:{}", node_config.actix.port);
+        let mut api =
+            ValidatorClient::new(&url, node_config.load_identity()?.clone()).with_polling(POLL_INTERVAL, MAX_RETRIES);
+        if let Some(token) = self.access_token.clone() {
+            api = api.with_access_token(token);
+        }
+        let baseline = match self.baseline.as_ref() {
+            Some(path) => Some(BenchReport::load(path)?),
+            None => None,
+        };
+
+        let mut report = BenchReport {
+            version: env!("CARGO_PKG_VERSION").into(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            concurrency: self.concurrency,
+            iterations: self.iterations,
+            contracts: vec![],
+        };
+        // every phase works on tokens produced by the previous one
+        let mut tokens: Vec<Vec<TokenID>> = (0..self.concurrency).map(|_| vec![]).collect();
+        for contract in &[Contract::IssueTokens, Contract::SellToken, Contract::TransferToken] {
+            let users = tokens
+                .into_iter()
+                .enumerate()
+                .map(|(user, tokens)| self.user_phase(*contract, user, tokens, &api, &pool));
+            let (next, stats) = Self::phase(*contract, users).await;
+            tokens = next;
+            report.contracts.push(stats);
+        }
+
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("bench-{}.json", report.version)));
+        report.save(&output)?;
+        report.render(baseline.as_ref());
+        println!("Report saved to {}", output.display());
+        Ok(())
+    }
+
+    /// Runs users in parallel and collects stats of the phase
+    async fn phase<F>(contract: Contract, users: impl Iterator<Item = F>) -> (Vec<Vec<TokenID>>, ContractStats)
+    where F: Future<Output = (Vec<TokenID>, Samples)> {
+        let time = Instant::now();
+        let results = join_all(users).await;
+        let elapsed = time.elapsed();
+        let mut samples = Samples::default();
+        let tokens = results
+            .into_iter()
+            .map(|(tokens, user_samples)| {
+                samples += user_samples;
+                tokens
+            })
+            .collect();
+        (tokens, ContractStats::new(contract, samples, elapsed))
+    }
+
+    /// Calls `contract` `iterations` times one by one, returns tokens processed successfully
+    async fn user_phase(
+        &self,
+        contract: Contract,
+        user: usize,
+        tokens: Vec<TokenID>,
+        api: &ValidatorClient,
+        pool: &Pool,
+    ) -> (Vec<TokenID>, Samples)
+    {
+        let mut samples = Samples::default();
+        let mut processed = vec![];
+        let targets: Vec<Option<TokenID>> = match contract {
+            Contract::IssueTokens => (0..self.iterations).map(|_| None).collect(),
+            _ => tokens.into_iter().map(Some).collect(),
+        };
+        for token_id in targets.into_iter() {
+            let time = Instant::now();
+            match self.call(contract, user, token_id, api, pool).await {
+                Ok(token_id) => {
+                    samples.latencies.push(time.elapsed());
+                    processed.push(token_id);
+                },
+                Err(err) => {
+                    samples.failed += 1;
+                    println!("User {} failed to call {}: {}", user, contract.name(), err);
+                },
+            }
+        }
+        (processed, samples)
+    }
+
+    /// Calls contract and waits for commit of the instruction
+    async fn call(
+        &self,
+        contract: Contract,
+        user: usize,
+        token_id: Option<TokenID>,
+        api: &ValidatorClient,
+        pool: &Pool,
+    ) -> anyhow::Result<TokenID>
+    {
+        match (contract, token_id) {
+            (Contract::IssueTokens, _) => {
+                let params = IssueTokensParams {
+                    token_ids: None,
+                    quantity: Some(1),
+                };
+                let instruction = api.issue_tokens(&self.asset_id, params).await?;
+                let instruction = api.wait_for_commit(instruction.id).await?;
+                let mut token_ids: Vec<TokenID> = serde_json::from_value(instruction.result)?;
+                token_ids
+                    .pop()
+                    .ok_or_else(|| anyhow::anyhow!("issue_tokens returned no tokens"))
+            },
+            (Contract::SellToken, Some(token_id)) => {
+                let params = SellTokenParams {
+                    price: 1,
+                    timeout_secs: self.timeout,
+                    user_pubkey: format!("bench user {}", user),
+                };
+                let instruction = api.sell_token(&token_id, params).await?;
+                let wallet = wait_wallet(&instruction, api, POLL_INTERVAL).await?;
+                fill_wallet(wallet, &pool.get().await?).await?;
+                api.wait_for_commit(instruction.id).await?;
+                Ok(token_id)
+            },
+            (Contract::TransferToken, Some(token_id)) => {
+                let params = TransferTokenParams {
+                    user_pubkey: format!("bench receiver {}", user),
+                };
+                let instruction = api.transfer_token(&token_id, params).await?;
+                api.wait_for_commit(instruction.id).await?;
+                Ok(token_id)
+            },
+            (contract, None) => Err(anyhow::anyhow!("{} requires token", contract.name())),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+struct Samples {
+    latencies: Vec<Duration>,
+    failed: u64,
+}
+
+impl AddAssign for Samples {
+    fn add_assign(&mut self, rhs: Self) {
+        self.latencies.extend(rhs.latencies);
+        self.failed += rhs.failed;
+    }
+}
+
+/// Results of a contract phase
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContractStats {
+    pub contract: String,
+    pub success: u64,
+    pub failed: u64,
+    /// Committed instructions per second
+    pub throughput: f64,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+}
+
+impl ContractStats {
+    fn new(contract: Contract, samples: Samples, elapsed: Duration) -> Self {
+        let mut latencies: Vec<u64> = samples.latencies.iter().map(|d| d.as_millis() as u64).collect();
+        latencies.sort();
+        let secs = elapsed.as_secs_f64();
+        Self {
+            contract: contract.name().into(),
+            success: latencies.len() as u64,
+            failed: samples.failed,
+            throughput: if secs > 0.0 { latencies.len() as f64 / secs } else { 0.0 },
+            p50_ms: percentile(&latencies, 50.0),
+            p95_ms: percentile(&latencies, 95.0),
+            p99_ms: percentile(&latencies, 99.0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.max(1).min(sorted.len()) - 1])
+}
+
+/// Benchmark results persisted for comparison between releases
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BenchReport {
+    pub version: String,
+    pub started_at: String,
+    pub concurrency: u16,
+    pub iterations: u16,
+    pub contracts: Vec<ContractStats>,
+}
+
+impl BenchReport {
+    const FIELDS: &'static [&'static str] = &[
+        "Contract",
+        "Success",
+        "Failed",
+        "Instr/sec",
+        "p50 ms",
+        "p95 ms",
+        "p99 ms",
+        "vs baseline",
+    ];
+    const SIZES: &'static [u16] = &[16, 10, 10, 12, 10, 10, 10, 24];
+
+    pub fn load(path: &PathBuf) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &PathBuf) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Throughput and p95 change relative to the same contract of `baseline`
+    fn compare(&self, stats: &ContractStats, baseline: &BenchReport) -> Option<String> {
+        let base = baseline.contracts.iter().find(|base| base.contract == stats.contract)?;
+        let change = |new: f64, old: f64| if old > 0.0 { (new - old) / old * 100.0 } else { 0.0 };
+        let throughput = change(stats.throughput, base.throughput);
+        let p95 = match (stats.p95_ms, base.p95_ms) {
+            (Some(new), Some(old)) => change(new as f64, old as f64),
+            _ => 0.0,
+        };
+        Some(format!("{:+.1}% i/s, {:+.1}% p95", throughput, p95))
+    }
+
+    fn render(&self, baseline: Option<&BenchReport>) {
+        let rows: Vec<Value> = self
+            .contracts
+            .iter()
+            .map(|stats| {
+                json!({
+                    "Contract": stats.contract,
+                    "Success": stats.success,
+                    "Failed": stats.failed,
+                    "Instr/sec": format!("{:.2}", stats.throughput),
+                    "p50 ms": stats.p50_ms,
+                    "p95 ms": stats.p95_ms,
+                    "p99 ms": stats.p99_ms,
+                    "vs baseline": baseline.and_then(|baseline| self.compare(stats, baseline)),
+                })
+            })
+            .collect();
+        let title = match baseline {
+            Some(baseline) => format!("Bench {} vs baseline {}", self.version, baseline.version),
+            None => format!("Bench {}", self.version),
+        };
+        Terminal::basic().render_list(&title, rows, Self::FIELDS, Self::SIZES);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        assert_eq!(percentile(&[], 50.0), None);
+        let latencies: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&latencies, 50.0), Some(50));
+        assert_eq!(percentile(&latencies, 95.0), Some(95));
+        assert_eq!(percentile(&latencies, 99.0), Some(99));
+        assert_eq!(percentile(&[7], 99.0), Some(7));
+
+        let samples = Samples {
+            latencies: (1..=10).map(Duration::from_millis).collect(),
+            failed: 2,
+        };
+        let stats = ContractStats::new(Contract::SellToken, samples, Duration::from_secs(2));
+        assert_eq!(stats.contract, "sell_token");
+        assert_eq!(stats.success, 10);
+        assert_eq!(stats.failed, 2);
+        assert_eq!(stats.throughput, 5.0);
+        assert_eq!(stats.p50_ms, Some(5));
+
+        let report = BenchReport {
+            version: "0.1.0".into(),
+            started_at: "".into(),
+            concurrency: 1,
+            iterations: 10,
+            contracts: vec![stats.clone()],
+        };
+        let faster = ContractStats {
+            throughput: 10.0,
+            p95_ms: Some(5),
+            ..stats
+        };
+        assert_eq!(
+            report.compare(&faster, &report),
+            Some("+100.0% i/s, -50.0% p95".to_string())
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use config::Config;
+use crossterm::style::{style, Color, StyledContent};
+use std::{fs::OpenOptions, io::Write};
+use structopt::StructOpt;
+use tari_common::GlobalConfig;
+use tari_validator_node::config::{CheckLevel, NodeConfig};
+
+#[derive(StructOpt, Debug)]
+pub enum ConfigCommands {
+    /// Validate loaded configuration and the environment: public address, wallets path, DB and API port,
+    /// exits with non-zero code if any check fails
+    Check,
+    /// Append `[validator]` section with all settings and their defaults to the config file
+    Init {
+        /// Precede every setting with its description and env var overloading it
+        #[structopt(long)]
+        with_comments: bool,
+        /// Print the section instead of writing it to the config file
+        #[structopt(long)]
+        print: bool,
+    },
+}
+
+impl ConfigCommands {
+    pub async fn run(self, node_config: NodeConfig, global_config: GlobalConfig) -> anyhow::Result<()> {
+        match self {
+            Self::Check => {
+                if let Some(path) = node_config.config_path.as_ref() {
+                    println!("Checking configuration {}", path.display());
+                }
+                let checks = node_config.diagnose().await;
+                for check in checks.iter() {
+                    println!("{:>5}  {:<28} {}", colored_level(check.level), check.key, check.message);
+                }
+                let errors = checks.iter().filter(|check| check.level == CheckLevel::Error).count();
+                let warnings = checks.iter().filter(|check| check.level == CheckLevel::Warning).count();
+                println!("{} errors, {} warnings", errors, warnings);
+                if errors > 0 {
+                    anyhow::bail!("Configuration check failed with {} errors", errors);
+                }
+            },
+            Self::Init { with_comments, print } => {
+                // defaults rather than loaded values, except for paths resolved against base path
+                let mut config = Config::new();
+                config.set("validator.wallets_keys_path", node_config.wallets_keys_path.to_str())?;
+                if let Some(identity_path) = node_config.identity_path.as_ref() {
+                    config.set("validator.identity_path", identity_path.to_str())?;
+                }
+                let section = NodeConfig::load_from(&config, &global_config, false)?.to_toml(with_comments);
+                let path = match (print, node_config.config_path.as_ref()) {
+                    (false, Some(path)) => path,
+                    _ => {
+                        print!("{}", section);
+                        return Ok(());
+                    },
+                };
+                let current = std::fs::read_to_string(path).unwrap_or_default();
+                if current.lines().any(|line| line.trim_start().starts_with("[validator")) {
+                    anyhow::bail!(
+                        "{} already has [validator] section, use --print to review the defaults",
+                        path.display()
+                    );
+                }
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                write!(file, "\n{}", section)?;
+                println!("Added [validator] section to {}", path.display());
+            },
+        }
+        Ok(())
+    }
+}
+
+fn colored_level(level: CheckLevel) -> StyledContent<String> {
+    let color = match level {
+        CheckLevel::Ok => Color::Green,
+        CheckLevel::Warning => Color::Yellow,
+        CheckLevel::Error => Color::Red,
+    };
+    style(level.to_string()).with(color)
+}
@@ -0,0 +1,39 @@
+use crate::console::Terminal;
+use serde_json::json;
+use structopt::StructOpt;
+use tari_validator_node::{config::NodeConfig, identity::NodeIdentity};
+
+#[derive(StructOpt, Debug)]
+pub enum IdentityCommands {
+    /// Generate node keypair and NodeID, stored at `identity_path`
+    Init {
+        /// Overwrite existing identity, *DANGER!* node will lose its previous NodeID
+        #[structopt(long)]
+        force: bool,
+    },
+    /// Show NodeID and public key of this node
+    Show,
+}
+
+impl IdentityCommands {
+    pub async fn run(self, node_config: NodeConfig) -> anyhow::Result<()> {
+        let path = node_config
+            .identity_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("validator.identity_path is not configured"))?;
+        let identity = match self {
+            Self::Init { force } => {
+                let identity = NodeIdentity::random();
+                identity.save(&path, force)?;
+                println!("Node identity stored to {}", path.display());
+                identity
+            },
+            Self::Show => NodeIdentity::load(&path)?,
+        };
+        Terminal::basic().render_object(
+            "Node identity",
+            json!({"NodeID": identity.node_id().to_string(), "Pubkey": identity.public_key_hex()}),
+        );
+        Ok(())
+    }
+}
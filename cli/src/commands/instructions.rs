@@ -1,19 +1,38 @@
 use crate::console::Terminal;
 use awc::Client as WebClient;
-use serde_json::Value;
-use std::time::Duration;
+use chrono::{DateTime, Utc};
+use crossterm::style::{style, Color, StyledContent};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use structopt::StructOpt;
 use tari_validator_node::{
+    api::models::{RequestSignature, SignedCall},
     config::NodeConfig,
-    db::models::consensus::instructions::*,
-    template::{asset_call_path, token_call_path},
+    db::{
+        models::consensus::instructions::*,
+        utils::{db::build_pool, statements::CachedClient},
+    },
+    identity::NodeIdentity,
+    template::{
+        asset_call_path,
+        escrow::EscrowTemplate,
+        nft::NftTemplate,
+        replay::{replay_instructions, ReplayReport},
+        single_use_tokens::SingleUseTokenTemplate,
+        token_call_path,
+        DryRunResult,
+        Template,
+        TemplateRunner,
+    },
     types::{AssetID, InstructionID, TokenID},
 };
 use tokio::time::delay_for;
-use tokio_postgres::Client;
 
 const WAIT: Duration = Duration::from_millis(1000);
 const MAX_RETRIES: usize = 60;
+/// Updates of long running transactions become visible with `updated_at` in the past,
+/// so watch is looking back on every poll
+const WATCH_LOOKBACK_SECS: i64 = 60;
 
 #[derive(StructOpt, Debug)]
 pub enum InstructionCommands {
@@ -27,6 +46,12 @@ pub enum InstructionCommands {
         /// Wait for Commit (by default is waiting for Pending)
         #[structopt(long)]
         wait_commit: bool,
+        /// Execute contract without creating an instruction, output result and state it would append
+        #[structopt(long, conflicts_with = "wait-commit")]
+        dry_run: bool,
+        /// Submit to the priority lane, node identity needs admin access
+        #[structopt(long)]
+        priority: Option<i32>,
     },
     Token {
         token_id: TokenID,
@@ -38,6 +63,12 @@ pub enum InstructionCommands {
         /// Wait for Commit (by default is waiting for Pending)
         #[structopt(long)]
         wait_commit: bool,
+        /// Execute contract without creating an instruction, output result and state it would append
+        #[structopt(long, conflicts_with = "wait-commit")]
+        dry_run: bool,
+        /// Submit to the priority lane, node identity needs admin access
+        #[structopt(long)]
+        priority: Option<i32>,
     },
     // Status of instruction and all subinstructions
     Status {
@@ -47,10 +78,33 @@ pub enum InstructionCommands {
     View {
         instruction_id: InstructionID,
     },
+    /// Stream status changes of the asset instructions until interrupted
+    Watch {
+        asset_id: AssetID,
+        /// Print every change as JSON line
+        #[structopt(long)]
+        json: bool,
+        /// Polling interval in milliseconds
+        #[structopt(long, default_value = "500")]
+        interval: u64,
+    },
+    /// Re-execute committed instructions of the asset and compare resulting state with stored state
+    Replay {
+        asset_id: AssetID,
+        /// Replay instructions created at or after RFC3339 time, e.g. 2020-06-01T00:00:00Z
+        #[structopt(long)]
+        from: DateTime<Utc>,
+        /// Seconds given to every instruction, contracts waiting for payment time out
+        #[structopt(long, default_value = "30")]
+        timeout: u64,
+        /// Print report as JSON
+        #[structopt(long)]
+        json: bool,
+    },
 }
 
 impl InstructionCommands {
-    pub async fn run(self, node_config: NodeConfig, client: &Client) -> anyhow::Result<Instruction> {
+    pub async fn run(self, mut node_config: NodeConfig, client: &CachedClient) -> anyhow::Result<()> {
         match self {
             Self::Asset {
                 asset_id,
@@ -58,10 +112,19 @@ impl InstructionCommands {
                 data,
                 silent,
                 wait_commit,
+                dry_run,
+                priority,
             } => {
                 let url = asset_call_path(&asset_id, contract_name.as_str());
                 let url = format!("http://localhost:{}{}", node_config.actix.port, url);
-                Self::call(url, data, silent, wait_commit, client).await
+                let call = SignedCall::asset(&asset_id, contract_name.as_str());
+                let signer = node_config.load_identity()?;
+                let data = Self::with_priority(data, priority)?;
+                if dry_run {
+                    return Self::dry_run(url, call, data, signer).await;
+                }
+                Self::call(url, call, data, signer, silent, wait_commit, client).await?;
+                Ok(())
             },
             Self::Token {
                 token_id,
@@ -69,34 +132,139 @@ impl InstructionCommands {
                 data,
                 silent,
                 wait_commit,
+                dry_run,
+                priority,
             } => {
                 let url = token_call_path(&token_id, contract_name.as_str());
                 let url = format!("http://localhost:{}{}", node_config.actix.port, url);
-                Self::call(url, data, silent, wait_commit, client).await
+                let call = SignedCall::token(&token_id, contract_name.as_str());
+                let signer = node_config.load_identity()?;
+                let data = Self::with_priority(data, priority)?;
+                if dry_run {
+                    return Self::dry_run(url, call, data, signer).await;
+                }
+                Self::call(url, call, data, signer, silent, wait_commit, client).await?;
+                Ok(())
             },
             Self::Status { instruction_id } => {
                 let instruction = Instruction::load(instruction_id, &client).await?;
                 Self::display_instruction_status(&instruction, client).await?;
-                Ok(instruction)
+                Ok(())
             },
             Self::View { instruction_id } => {
                 let instruction = Instruction::load(instruction_id, client).await?;
-                Terminal::basic().render_object("Instruction details", instruction.clone());
-                Ok(instruction)
+                Terminal::basic().render_object("Instruction details", instruction);
+                Ok(())
+            },
+            Self::Watch {
+                asset_id,
+                json,
+                interval,
+            } => Self::watch(asset_id, json, Duration::from_millis(interval), client).await,
+            Self::Replay {
+                asset_id,
+                from,
+                timeout,
+                json,
+            } => {
+                node_config.load_identity()?;
+                let report = Self::replay(node_config, asset_id, from, Duration::from_secs(timeout)).await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    Self::display_replay(&report);
+                }
+                if report.divergences.is_empty() {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!("{} instructions diverged", report.divergences.len()))
+                }
             },
         }
     }
 
+    /// Adds `priority` field to contract call params, it's signed along with the rest of params
+    fn with_priority(mut data: Value, priority: Option<i32>) -> anyhow::Result<Value> {
+        if let Some(priority) = priority {
+            data.as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("--priority requires contract params to be an object"))?
+                .insert(PRIORITY_PARAM.into(), priority.into());
+        }
+        Ok(data)
+    }
+
+    /// Polls asset instructions printing status transitions which happened after watch started
+    async fn watch(
+        asset_id: AssetID,
+        json: bool,
+        interval: Duration,
+        client: &CachedClient,
+    ) -> anyhow::Result<()>
+    {
+        let lookback = chrono::Duration::seconds(WATCH_LOOKBACK_SECS);
+        let mut since = Utc::now();
+        let mut seen: HashMap<InstructionID, InstructionStatus> = HashMap::new();
+        if !json {
+            println!("Watching instructions of asset {}, press Ctrl-C to stop", asset_id);
+        }
+        loop {
+            let instructions = Instruction::find_updated_since(&asset_id, since - lookback, client).await?;
+            for instruction in instructions {
+                since = since.max(instruction.updated_at);
+                let previous = seen.insert(instruction.id, instruction.status);
+                if previous != Some(instruction.status) {
+                    Self::display_transition(&instruction, previous, json);
+                }
+            }
+            delay_for(interval).await;
+        }
+    }
+
+    fn display_transition(instruction: &Instruction, previous: Option<InstructionStatus>, json: bool) {
+        if json {
+            let event = json!({
+                "id": instruction.id,
+                "parent_id": instruction.parent_id,
+                "token_id": instruction.token_id,
+                "contract_name": instruction.contract_name,
+                "from": previous,
+                "to": instruction.status,
+                "result": instruction.result,
+                "updated_at": instruction.updated_at,
+            });
+            println!("{}", event);
+            return;
+        }
+        let from = match previous {
+            Some(status) => colored_status(status).to_string(),
+            None => "new".to_string(),
+        };
+        let sub = if instruction.parent_id.is_some() { " (sub)" } else { "" };
+        println!(
+            "{} {} {}{} {} -> {}",
+            instruction.updated_at.format("%H:%M:%S%.3f"),
+            instruction.id,
+            instruction.contract_name,
+            sub,
+            from,
+            colored_status(instruction.status)
+        );
+    }
+
+    /// Post contract call, `call` and params are signed by the `signer` key
     pub async fn call(
         url: String,
+        call: SignedCall,
         data: Value,
+        signer: &NodeIdentity,
         silent: bool,
         wait_commit: bool,
-        client: &Client,
+        client: &CachedClient,
     ) -> anyhow::Result<Instruction>
     {
         let web = WebClient::default();
-        let mut resp = web.post(&url).send_json(&data).await.unwrap();
+        let signature = RequestSignature::sign(signer, &call, &data)?;
+        let mut resp = signature.apply(web.post(&url)).send_json(&data).await.unwrap();
         if resp.status().is_success() {
             let instruction: Instruction = match resp.json::<Value>().await {
                 Ok(val) => {
@@ -121,10 +289,79 @@ impl InstructionCommands {
         }
     }
 
+    /// Replays instructions with the template of the asset, the node doesn't need to be running
+    async fn replay(
+        node_config: NodeConfig,
+        asset_id: AssetID,
+        from: DateTime<Utc>,
+        timeout: Duration,
+    ) -> anyhow::Result<ReplayReport>
+    {
+        let pool = Arc::new(build_pool(&node_config.postgres)?);
+        let template_id = asset_id.template_id();
+        let report = if template_id == SingleUseTokenTemplate::id() {
+            let context = TemplateRunner::<SingleUseTokenTemplate>::create(pool, node_config, None).context();
+            replay_instructions(context, &asset_id, from, timeout).await?
+        } else if template_id == EscrowTemplate::id() {
+            let context = TemplateRunner::<EscrowTemplate>::create(pool, node_config, None).context();
+            replay_instructions(context, &asset_id, from, timeout).await?
+        } else if template_id == NftTemplate::id() {
+            let context = TemplateRunner::<NftTemplate>::create(pool, node_config, None).context();
+            replay_instructions(context, &asset_id, from, timeout).await?
+        } else {
+            return Err(anyhow::anyhow!("Template {} is not installed", template_id));
+        };
+        Ok(report)
+    }
+
+    fn display_replay(report: &ReplayReport) {
+        println!(
+            "Replayed {} instructions of asset {} since {}",
+            report.instructions, report.asset_id, report.from
+        );
+        let divergences = report
+            .divergences
+            .iter()
+            .map(|divergence| {
+                json!({
+                    "Id": divergence.instruction_id,
+                    "Contract": divergence.contract_name,
+                    "Stored": divergence.stored,
+                    "Replayed": divergence.error.clone().map(Value::from).unwrap_or_else(|| json!(divergence.replayed)),
+                })
+            })
+            .collect();
+        Terminal::basic().render_list("Divergences", divergences, &["Id", "Contract", "Stored", "Replayed"], &[
+            36, 20, 60, 60,
+        ]);
+    }
+
+    /// Post contract call with `?dry_run=true`, prints would-be result and state without creating an instruction
+    async fn dry_run(url: String, call: SignedCall, data: Value, signer: &NodeIdentity) -> anyhow::Result<()> {
+        let web = WebClient::default();
+        let signature = RequestSignature::sign(signer, &call, &data)?;
+        let mut resp = signature
+            .apply(web.post(&url).query(&[("dry_run", true)])?)
+            .send_json(&data)
+            .await
+            .map_err(|err| anyhow::anyhow!("POST {} failed: {}", url, err))?;
+        let val = resp
+            .json::<Value>()
+            .await
+            .map_err(|err| anyhow::anyhow!("POST {} failed: {}", url, err))?;
+        if let Some(err) = val.get("error") {
+            return Err(anyhow::anyhow!("POST {} failed: {}", url, err));
+        }
+        let result: DryRunResult = serde_json::from_value(val)?;
+        println!("Dry run result: {}", serde_json::to_string_pretty(&result.result)?);
+        Terminal::basic().render_object("State to be appended:", result.append_only_state);
+        Ok(())
+    }
+
     pub async fn wait_status(
         instruction: &Instruction,
         status: InstructionStatus,
-        client: &Client,
+        client: &CachedClient,
         silent: bool,
         refresh_interval: Duration,
     ) -> anyhow::Result<Instruction>
@@ -155,7 +392,7 @@ impl InstructionCommands {
         }
     }
 
-    pub async fn display_instruction_status(instruction: &Instruction, client: &Client) -> anyhow::Result<()> {
+    pub async fn display_instruction_status(instruction: &Instruction, client: &CachedClient) -> anyhow::Result<()> {
         let subinstructions = instruction.load_subinstructions(&client).await?;
         let mut instructions = vec![instruction_view(instruction, true)];
         instructions.extend(subinstructions.iter().map(|i| instruction_view(i, false)));
@@ -164,11 +401,22 @@ impl InstructionCommands {
     }
 }
 
+fn colored_status(status: InstructionStatus) -> StyledContent<String> {
+    let color = match status {
+        InstructionStatus::Scheduled => Color::Grey,
+        InstructionStatus::Processing => Color::Yellow,
+        InstructionStatus::Pending => Color::Cyan,
+        InstructionStatus::Commit => Color::Green,
+        InstructionStatus::Invalid => Color::Red,
+    };
+    style(status.to_string()).with(color)
+}
+
 const COLUMNS: &[&str] = &["Root", "Id", "Status", "Params", "Result"];
 const SIZES: &[u16] = &[4, 36, 10, 100];
 
 fn instruction_view(instruction: &Instruction, root: bool) -> serde_json::Value {
-    serde_json::json!({
+    json!({
         "Root": if root { " ** " } else { "" },
         "Id": instruction.id,
         "Status": instruction.status,
@@ -0,0 +1,458 @@
+//! Load scenarios on a Single Use Token asset
+//!
+//! [MakeItRain] runs a [Scenario]: `setup` once, then `user` workload of every of `concurrency` users in
+//! parallel and `teardown` once all users are done. [Counters] of every user are rendered live and can be
+//! saved to CSV or JSON file once the run is complete.
+
+use crate::console::Terminal;
+use deadpool::managed::PoolConfig;
+use deadpool_postgres::{Client, Pool};
+use futures::future::{FutureExt, LocalBoxFuture};
+use rand::Rng;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    ops::AddAssign,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tari_validator_client::ValidatorClient;
+use tari_validator_node::{
+    config::NodeConfig,
+    db::{
+        models::{consensus::instructions::*, wallet::*},
+        utils::db::build_pool,
+    },
+    template::single_use_tokens::{IssueTokensParams, SellTokenLockParams, SellTokenParams, TokenContracts},
+    types::{AssetID, Pubkey, TokenID},
+};
+use tokio::{sync::Mutex, time::delay_for};
+
+mod sell_redeem;
+pub use sell_redeem::SellRedeem;
+mod transfer_churn;
+pub use transfer_churn::TransferChurn;
+
+const MAX_RETRIES: usize = 60;
+
+lazy_static::lazy_static! {
+    static ref TERMINAL: Mutex<Terminal> = Mutex::new(Terminal::basic());
+    static ref COUNTERS: Mutex<HashMap<String, Counters>> = Mutex::new(HashMap::new());
+}
+
+/// Workload run by [MakeItRain]
+pub trait Scenario {
+    /// Names of durations reported by `user` via [Counters::success]
+    fn metrics(&self) -> &'static [&'static str];
+
+    /// Prepares scenario before users are started
+    fn setup<'a>(&'a mut self, _ctx: &'a ScenarioContext) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+        async { Ok(()) }.boxed_local()
+    }
+
+    /// Workload of a single user, users are run in parallel
+    fn user<'a>(
+        &'a self,
+        user: usize,
+        ctx: &'a ScenarioContext,
+        counters: &'a mut Counters,
+    ) -> LocalBoxFuture<'a, anyhow::Result<()>>;
+
+    /// Cleans up once all users are done
+    fn teardown<'a>(&'a mut self, _ctx: &'a ScenarioContext) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+        async { Ok(()) }.boxed_local()
+    }
+}
+
+/// Built-in scenarios selectable with `--scenario`
+#[derive(Debug, Clone, Copy)]
+pub enum ScenarioKind {
+    SellRedeem,
+    TransferChurn,
+}
+
+impl ScenarioKind {
+    fn build(&self) -> Box<dyn Scenario> {
+        match self {
+            Self::SellRedeem => Box::new(SellRedeem::default()),
+            Self::TransferChurn => Box::new(TransferChurn::default()),
+        }
+    }
+}
+
+impl FromStr for ScenarioKind {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "sell-redeem" => Ok(Self::SellRedeem),
+            "transfer-churn" => Ok(Self::TransferChurn),
+            _ => Err(anyhow::anyhow!("Unknown scenario {}, expected sell-redeem or transfer-churn", input)),
+        }
+    }
+}
+
+/// Format of counters file
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow::anyhow!("Unknown format {}, expected csv or json", input)),
+        }
+    }
+}
+
+impl OutputFormat {
+    fn render(&self, counters: &[Counters]) -> anyhow::Result<String> {
+        let total = Counters::total(counters);
+        let rows = std::iter::once(&total).chain(counters.iter());
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(&rows.map(Counters::to_display).collect::<Vec<_>>())?,
+            Self::Csv => {
+                let header = Counters::fields(total.metrics).join(",");
+                let lines: Vec<String> = std::iter::once(header).chain(rows.map(Counters::to_csv)).collect();
+                lines.join("\n") + "\n"
+            },
+        })
+    }
+}
+
+/// Shared environment of the scenario run
+pub struct ScenarioContext {
+    /// Target asset in the Single Use Token template
+    pub asset_id: AssetID,
+    pub concurrency: u16,
+    /// Total number of requests
+    pub tokens: u16,
+    /// Timeout for sell_token instruction
+    pub timeout: u64,
+    pub api: ValidatorClient,
+    pub pool: Pool,
+    pub refresh: Duration,
+}
+
+impl ScenarioContext {
+    /// Number of requests made by every user
+    pub fn per_user(&self) -> u16 {
+        self.tokens / self.concurrency
+    }
+
+    pub async fn issue_tokens(&self, quantity: u16) -> anyhow::Result<Vec<TokenID>> {
+        let params = IssueTokensParams {
+            token_ids: None,
+            quantity: Some(quantity),
+        };
+        let instruction = self.api.issue_tokens(&self.asset_id, params).await?;
+        let instruction = self.api.wait_for_commit(instruction.id).await?;
+        Ok(serde_json::from_value(instruction.result)?)
+    }
+
+    /// Issues sell_token to `user_pubkey` and pays for it, returns instruction and time of waiting for wallet
+    pub async fn sell_token(
+        &self,
+        token_id: &TokenID,
+        user_pubkey: Pubkey,
+        client: &Client,
+    ) -> anyhow::Result<(Instruction, Duration)>
+    {
+        let time = Instant::now();
+        let params = SellTokenParams {
+            price: 1,
+            timeout_secs: self.timeout,
+            user_pubkey,
+        };
+        let instruction = self.api.sell_token(&token_id, params).await?;
+        let wallet = wait_wallet(&instruction, &self.api, self.refresh).await?;
+        let wait_wallet_time = time.elapsed();
+        fill_wallet(wallet, &client).await?;
+        Ok((instruction, wait_wallet_time))
+    }
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// Runs load scenario on a Single Use Token asset:
+///
+/// sell-redeem (default): every user issues own chunk of tokens, sells every token and redeems it back
+///
+/// transfer-churn: every user gets one token and transfers it back and forth `tokens / concurrency` times
+pub struct MakeItRain {
+    /// Target asset in the Single Use Token template
+    asset_id: AssetID,
+    ///// Timeout in seconds for sell_token contract
+    //#[structopt(short="s", long, default_value="10")]
+    // timeout: u16,
+    /// How many parallel threads to run
+    #[structopt(short = "c", long, default_value = "4")]
+    concurrency: u16,
+    /// How many tokens to issue for test (total requests)
+    #[structopt(short = "t", long, default_value = "100")]
+    tokens: u16,
+    /// Timeout for sell_token instruction
+    #[structopt(long, default_value = "30")]
+    timeout: u64,
+    /// API access token, required unless auth is disabled on the node
+    #[structopt(long)]
+    access_token: Option<String>,
+    /// Scenario to run: sell-redeem or transfer-churn
+    #[structopt(long, default_value = "sell-redeem")]
+    scenario: ScenarioKind,
+    /// Save counters of every user to the file once done
+    #[structopt(short = "o", long)]
+    output: Option<PathBuf>,
+    /// Format of the output file: csv or json
+    #[structopt(long, default_value = "json")]
+    format: OutputFormat,
+}
+
+impl MakeItRain {
+    pub async fn run(self, mut node_config: NodeConfig) -> anyhow::Result<()> {
+        node_config.postgres.pool = Some(PoolConfig {
+            max_size: self.concurrency as usize,
+            ..Default::default()
+        });
+        let pool = build_pool(&node_config.postgres)?;
+        let url = format!("http://localhost:{}", node_config.actix.port);
+        let refresh = Duration::from_millis(20 * self.concurrency as u64);
+        let mut api =
+            ValidatorClient::new(&url, node_config.load_identity()?.clone()).with_polling(refresh, MAX_RETRIES);
+        if let Some(token) = self.access_token.clone() {
+            api = api.with_access_token(token);
+        }
+        let ctx = ScenarioContext {
+            asset_id: self.asset_id.clone(),
+            concurrency: self.concurrency,
+            tokens: self.tokens,
+            timeout: self.timeout,
+            api,
+            pool,
+            refresh,
+        };
+        let mut scenario = self.scenario.build();
+        scenario.setup(&ctx).await?;
+        // split by concurrent streams
+        let user_futures = (0..self.concurrency)
+            .into_iter()
+            .map(|i| Self::user(i as usize, scenario.as_ref(), &ctx));
+        // run user emulations in parallel
+        let results = futures::future::join_all(user_futures).await;
+        scenario.teardown(&ctx).await?;
+        delay_for(Duration::from_millis(1000)).await;
+        println!("Errors (if any):");
+        let mut counters = Vec::with_capacity(results.len());
+        for (i, (result, user_counters)) in results.into_iter().enumerate() {
+            if let Err(err) = result {
+                println!("{}. {}", i, err)
+            }
+            counters.push(user_counters);
+        }
+        if let Some(output) = self.output.as_ref() {
+            std::fs::write(output, self.format.render(&counters)?)?;
+            println!("Counters saved to {}", output.display());
+        }
+        Ok(())
+    }
+
+    async fn user(user: usize, scenario: &dyn Scenario, ctx: &ScenarioContext) -> (anyhow::Result<()>, Counters) {
+        let delay_ms: u16 = rand::thread_rng().gen::<u16>() / 128 * ctx.concurrency;
+        delay_for(Duration::from_millis(delay_ms as u64)).await;
+
+        let mut counters = Counters::new(&format!("user {}", user), scenario.metrics());
+        let result = scenario.user(user, ctx, &mut counters).await;
+        (result, counters)
+    }
+}
+
+/// Waits for sell_token_lock subinstruction and returns its temporary wallet key
+pub(crate) async fn wait_wallet(
+    instruction: &Instruction,
+    api: &ValidatorClient,
+    refresh_interval: Duration,
+) -> anyhow::Result<Pubkey>
+{
+    for _ in 0..MAX_RETRIES {
+        let subinstructions = api.get_subinstructions(instruction.id).await?;
+        if subinstructions.len() > 0 {
+            let contract: TokenContracts = serde_json::from_value(subinstructions[0].params.clone()).unwrap();
+            if let TokenContracts::SellTokenLock(SellTokenLockParams { wallet_key }) = contract {
+                return Ok(wallet_key);
+            } else {
+                panic!("Expected SellTokenLock subinstruction");
+            }
+        }
+        delay_for(refresh_interval).await;
+    }
+    Err(anyhow::anyhow!(
+        "Timeout waiting for subinstruction of {}",
+        instruction.id
+    ))
+}
+
+/// Pays for the token by setting temporary wallet balance directly in DB
+pub(crate) async fn fill_wallet(wallet_key: Pubkey, client: &Client) -> anyhow::Result<()> {
+    let wallet = Wallet::select_by_key(&wallet_key, &client).await?;
+    wallet.set_balance(1, &client).await?;
+    Ok(())
+}
+
+/// Per user results, average of every scenario metric is kept in milliseconds
+#[derive(Clone, Default, Debug)]
+pub struct Counters {
+    name: String,
+    metrics: &'static [&'static str],
+    success: u64,
+    failed: u64,
+    averages: Vec<Option<u64>>,
+}
+
+impl Counters {
+    const SIZES: &'static [u16] = &[10, 10, 10];
+    const METRIC_SIZE: u16 = 16;
+
+    fn new(name: &str, metrics: &'static [&'static str]) -> Self {
+        Self {
+            name: name.to_string(),
+            metrics,
+            averages: vec![None; metrics.len()],
+            ..Default::default()
+        }
+    }
+
+    /// Records successful iteration with duration of every scenario metric
+    pub fn success(&mut self, durations: &[Duration]) {
+        *self += Counters {
+            name: "".into(),
+            metrics: self.metrics,
+            success: 1,
+            failed: 0,
+            averages: durations.iter().map(|d| Some(d.as_millis() as u64)).collect(),
+        };
+        actix_rt::spawn(Self::update_display(self.clone()));
+    }
+
+    pub fn failed(&mut self) {
+        self.failed += 1;
+        actix_rt::spawn(Self::update_display(self.clone()));
+    }
+
+    fn total<'a>(counters: impl IntoIterator<Item = &'a Counters>) -> Counters {
+        let mut counters = counters.into_iter().peekable();
+        let metrics = counters.peek().map(|next| next.metrics).unwrap_or_default();
+        let mut total = Counters::new("Total", metrics);
+        for next in counters {
+            total += next.clone();
+        }
+        total
+    }
+}
+
+impl AddAssign for Counters {
+    fn add_assign(&mut self, rhs: Self) {
+        let total_success = self.success + rhs.success;
+        self.averages.resize(rhs.averages.len().max(self.averages.len()), None);
+        for (avg, rhs_avg) in self.averages.iter_mut().zip(rhs.averages.into_iter()) {
+            *avg = match (*avg, rhs_avg) {
+                (Some(avg), Some(rhs_avg)) => Some((avg * self.success + rhs_avg * rhs.success) / total_success),
+                (None, rhs_avg) => rhs_avg,
+                (avg, None) => avg,
+            };
+        }
+        self.success = total_success;
+        self.failed += rhs.failed;
+    }
+}
+
+impl Counters {
+    fn fields(metrics: &[&'static str]) -> Vec<&'static str> {
+        ["User", "Success", "Failed"].iter().chain(metrics.iter()).cloned().collect()
+    }
+
+    async fn update_display(record: Counters) {
+        let mut counters = COUNTERS.lock().await;
+        counters.insert(record.name.clone(), record.clone());
+        let total = Counters::total(counters.values());
+        let mut counters: Vec<Value> = counters.values().map(Counters::to_display).collect();
+        counters.insert(0, total.to_display());
+        let fields = Self::fields(record.metrics);
+        let sizes: Vec<u16> = Self::SIZES
+            .iter()
+            .cloned()
+            .chain(record.metrics.iter().map(|_| Self::METRIC_SIZE))
+            .collect();
+        TERMINAL
+            .lock()
+            .await
+            .render_list("Make it rain: stats by threads", counters, &fields, &sizes);
+    }
+
+    fn to_display(&self) -> Value {
+        let mut display = Map::new();
+        display.insert("User".into(), json!(self.name));
+        display.insert("Success".into(), json!(self.success));
+        display.insert("Failed".into(), json!(self.failed));
+        for (metric, avg) in self.metrics.iter().zip(self.averages.iter()) {
+            display.insert(metric.to_string(), json!(avg));
+        }
+        Value::Object(display)
+    }
+
+    fn to_csv(&self) -> String {
+        let averages = self
+            .averages
+            .iter()
+            .map(|avg| avg.map(|avg| avg.to_string()).unwrap_or_default());
+        vec![self.name.clone(), self.success.to_string(), self.failed.to_string()]
+            .into_iter()
+            .chain(averages)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const METRICS: &'static [&'static str] = &["Avg sell ms", "Avg redeem ms"];
+
+    #[test]
+    fn counters_output() {
+        let mut first = Counters::new("user 0", METRICS);
+        first += Counters {
+            success: 1,
+            averages: vec![Some(10), Some(20)],
+            ..Counters::new("", METRICS)
+        };
+        first.failed += 1;
+        let mut second = Counters::new("user 1", METRICS);
+        second += Counters {
+            success: 3,
+            averages: vec![Some(30), Some(40)],
+            ..Counters::new("", METRICS)
+        };
+
+        let total = Counters::total(&[first.clone(), second.clone()]);
+        assert_eq!(total.success, 4);
+        assert_eq!(total.failed, 1);
+        assert_eq!(total.averages, vec![Some(25), Some(35)]);
+
+        let csv = OutputFormat::Csv.render(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(
+            csv,
+            "User,Success,Failed,Avg sell ms,Avg redeem ms\nTotal,4,1,25,35\nuser 0,1,1,10,20\nuser 1,3,0,30,40\n"
+        );
+        let json: Value = serde_json::from_str(&OutputFormat::Json.render(&[first, second]).unwrap()).unwrap();
+        assert_eq!(json[0]["User"], "Total");
+        assert_eq!(json[2]["Avg redeem ms"], 40);
+    }
+}
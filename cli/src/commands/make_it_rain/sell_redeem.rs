@@ -0,0 +1,79 @@
+use super::{Counters, Scenario, ScenarioContext};
+use deadpool_postgres::Client;
+use futures::future::{FutureExt, LocalBoxFuture};
+use std::time::{Duration, Instant};
+use tari_validator_node::{db::models::consensus::instructions::InstructionStatus, types::TokenID};
+
+/// Sell and redeem flow of Single Use Token:
+///
+/// 1. Every user issues `tokens / concurrency` tokens
+/// 2. Pick next token, issue sell_token
+/// 3. Once temporary wallet is created - send money to sell_token wallet
+/// 4. Once instruction goes to Pending - send redeem_token
+/// 5. Repeat for other tokens
+#[derive(Default)]
+pub struct SellRedeem;
+
+impl SellRedeem {
+    async fn process_token(
+        key: &String,
+        token_id: &TokenID,
+        ctx: &ScenarioContext,
+        client: &Client,
+    ) -> anyhow::Result<(Duration, Duration, Duration)>
+    {
+        let time = Instant::now();
+        let (instruction, wait_wallet_time) = ctx.sell_token(token_id, key.clone(), client).await?;
+        ctx.api.wait_for_status(instruction.id, InstructionStatus::Pending).await?;
+        let sell_time = time.elapsed();
+        let time = Instant::now();
+        let instruction = ctx.api.redeem_token(&token_id).await?;
+        ctx.api.wait_for_status(instruction.id, InstructionStatus::Pending).await?;
+        let redeem_time = time.elapsed();
+        Ok((wait_wallet_time, sell_time, redeem_time))
+    }
+}
+
+impl Scenario for SellRedeem {
+    fn metrics(&self) -> &'static [&'static str] {
+        &["Wait wallet ms", "Avg sell ms", "Avg redeem ms"]
+    }
+
+    fn user<'a>(
+        &'a self,
+        user: usize,
+        ctx: &'a ScenarioContext,
+        counters: &'a mut Counters,
+    ) -> LocalBoxFuture<'a, anyhow::Result<()>>
+    {
+        async move {
+            let key = format!("user {}", user);
+            let client = ctx.pool.get().await?;
+            // issue tokens
+            let token_ids = match ctx.issue_tokens(ctx.per_user()).await {
+                Ok(token_ids) => token_ids,
+                Err(err) => {
+                    counters.failed();
+                    println!("User {} failed to issue tokens: {}", key, err);
+                    return Err(err);
+                },
+            };
+
+            // run scenario for every token one by one
+            for token_id in token_ids.into_iter() {
+                match Self::process_token(&key, &token_id, ctx, &client).await {
+                    Ok((wallet_duration, sell_duration, redeem_duration)) => {
+                        counters.success(&[wallet_duration, sell_duration, redeem_duration]);
+                    },
+                    Err(err) => {
+                        counters.failed();
+                        println!("User {} failed to process token {}: {}", key, token_id, err);
+                        return Err(err);
+                    },
+                };
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
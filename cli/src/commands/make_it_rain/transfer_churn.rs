@@ -0,0 +1,79 @@
+use super::{Counters, Scenario, ScenarioContext};
+use futures::future::{FutureExt, LocalBoxFuture};
+use std::time::Instant;
+use tari_validator_node::{
+    template::single_use_tokens::TransferTokenParams,
+    types::{Pubkey, TokenID},
+};
+
+/// Pure transfer_token churn:
+///
+/// 1. Setup issues a token per user and sells it to the user
+/// 2. Every user transfers own token `tokens / concurrency` times, alternating between two owners
+#[derive(Default)]
+pub struct TransferChurn {
+    tokens: Vec<TokenID>,
+}
+
+impl TransferChurn {
+    fn owner(user: usize, round: u16) -> Pubkey {
+        format!("user {} owner {}", user, round % 2)
+    }
+
+    async fn transfer(ctx: &ScenarioContext, token_id: &TokenID, user_pubkey: Pubkey) -> anyhow::Result<()> {
+        let instruction = ctx
+            .api
+            .transfer_token(token_id, TransferTokenParams { user_pubkey })
+            .await?;
+        ctx.api.wait_for_commit(instruction.id).await?;
+        Ok(())
+    }
+}
+
+impl Scenario for TransferChurn {
+    fn metrics(&self) -> &'static [&'static str] {
+        &["Avg transfer ms"]
+    }
+
+    fn setup<'a>(&'a mut self, ctx: &'a ScenarioContext) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+        async move {
+            let client = ctx.pool.get().await?;
+            self.tokens = ctx.issue_tokens(ctx.concurrency).await?;
+            // only sold (Active) tokens can be transferred
+            for (user, token_id) in self.tokens.iter().enumerate() {
+                let (instruction, _) = ctx.sell_token(token_id, Self::owner(user, 0), &client).await?;
+                ctx.api.wait_for_commit(instruction.id).await?;
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+
+    fn user<'a>(
+        &'a self,
+        user: usize,
+        ctx: &'a ScenarioContext,
+        counters: &'a mut Counters,
+    ) -> LocalBoxFuture<'a, anyhow::Result<()>>
+    {
+        async move {
+            let token_id = self
+                .tokens
+                .get(user)
+                .ok_or_else(|| anyhow::anyhow!("No token issued for user {}", user))?;
+            for round in 0..ctx.per_user() {
+                let time = Instant::now();
+                match Self::transfer(ctx, token_id, Self::owner(user, round + 1)).await {
+                    Ok(()) => counters.success(&[time.elapsed()]),
+                    Err(err) => {
+                        counters.failed();
+                        println!("User {} failed to transfer token {}: {}", user, token_id, err);
+                        return Err(err);
+                    },
+                }
+            }
+            Ok(())
+        }
+        .boxed_local()
+    }
+}
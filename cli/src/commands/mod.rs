@@ -1,9 +1,20 @@
+use chrono::{DateTime, Utc};
 use structopt::StructOpt;
+use tari_validator_node::{
+    db::retention::parse_date,
+    types::{AssetID, TemplateID},
+};
 
 pub mod access;
 pub use access::AccessCommands;
+pub mod bench;
+pub use bench::Bench;
 pub mod assets;
 pub use assets::AssetCommands;
+pub mod config;
+pub use config::ConfigCommands;
+pub mod identity;
+pub use identity::IdentityCommands;
 pub mod instructions;
 pub use instructions::InstructionCommands;
 pub mod templates;
@@ -14,6 +25,38 @@ pub mod wallets;
 pub use wallets::WalletCommands;
 pub mod make_it_rain;
 pub use make_it_rain::MakeItRain;
+pub mod status;
+pub use status::StatusCommand;
+
+/// Fault injection settings for chaos testing, probabilities are from 0.0 to 1.0
+#[cfg(feature = "chaos")]
+#[derive(StructOpt, Debug, Default)]
+pub struct ChaosArgs {
+    /// Probability of dropping outgoing consensus message
+    #[structopt(long = "chaos-drop-messages", default_value = "0")]
+    pub drop_messages: f64,
+    /// Probability of delaying DB query
+    #[structopt(long = "chaos-delay-queries", default_value = "0")]
+    pub delay_queries: f64,
+    /// Delay of DB query in milliseconds
+    #[structopt(long = "chaos-query-delay-ms", default_value = "1000")]
+    pub query_delay_ms: u64,
+    /// Probability of failing DB transaction
+    #[structopt(long = "chaos-fail-transactions", default_value = "0")]
+    pub fail_transactions: f64,
+}
+
+#[cfg(feature = "chaos")]
+impl From<ChaosArgs> for tari_validator_node::chaos::ChaosConfig {
+    fn from(args: ChaosArgs) -> Self {
+        Self {
+            drop_messages: args.drop_messages,
+            delay_queries: args.delay_queries,
+            query_delay: std::time::Duration::from_millis(args.query_delay_ms),
+            fail_transactions: args.fail_transactions,
+        }
+    }
+}
 
 #[derive(StructOpt, Debug)]
 pub enum Commands {
@@ -24,9 +67,29 @@ pub enum Commands {
         /// Disable interactive server dashboard
         #[structopt(long)]
         no_dashboard: bool,
+        /// Single node development mode on local Postgres: separate dev database, no access tokens,
+        /// seeded demo asset
+        #[structopt(long)]
+        dev: bool,
+        #[cfg(feature = "chaos")]
+        #[structopt(flatten)]
+        chaos: ChaosArgs,
     },
     /// Run the migrations
-    Migrate,
+    Migrate {
+        /// List applied and pending migrations without applying
+        #[structopt(long)]
+        status: bool,
+        /// Print SQL of pending migrations without applying
+        #[structopt(long)]
+        dry_run: bool,
+    },
+    /// Node configuration diagnostics
+    Config(ConfigCommands),
+    /// Print status of the running node, e.g. to attach to a bug report
+    Status(StatusCommand),
+    /// Node keypair and NodeID management
+    Identity(IdentityCommands),
     /// API access management
     Access(AccessCommands),
     /// Manage wallets
@@ -39,21 +102,47 @@ pub enum Commands {
     Instruction(InstructionCommands),
     /// Token commands
     Token(TokenCommands),
+    /// Measure instructions throughput and latencies on a Single Use Token asset
+    Bench(Bench),
     // TODO: Demo: cargo run  -- instruction asset 0000000100000000000000000000000.0000000000000000000000 issue_tokens
     // --data '{"number": 6}' TODO: Demo: cargo run  -- instruction token sell_token --data '{"owner_pubkey":
     // pubkey, "price": 100.0, "timeout": }' --autopick walletPubkey, token_id
     // TODO: Demo: cargo run  -- instruction wallet set_balance walletPubkey 100.0
     // TODO: Demo: cargo run  -- instruction token redeem_token
     // 0000000100000000000000000000000.0000000000000000000000.000000000..01
+    /// Roll up old append only state of tokens and assets into snapshots
+    Prune {
+        /// Prune records created before the date (RFC3339 or YYYY-MM-DD),
+        /// defaults to `max_age_days` of retention config
+        #[structopt(long, parse(try_from_str = parse_date))]
+        before: Option<DateTime<Utc>>,
+        /// Number of latest records kept for every token and asset,
+        /// defaults to `keep_checkpoints` of retention config
+        #[structopt(long)]
+        keep: Option<u32>,
+    },
     /// Recreate and migrate database,  *DANGER!* it will wipe all data
+    ///
+    /// With --asset or --template only data of the asset or assets of the template is deleted
     Wipe {
         /// Don't prompt for confirmation
         #[structopt(short)]
         y: bool,
+        /// Delete asset along with its tokens, state, instructions, views and proposals
+        #[structopt(long, conflicts_with = "template")]
+        asset: Option<AssetID>,
+        /// Delete all assets of the template
+        #[structopt(long)]
+        template: Option<TemplateID>,
     },
 }
 impl Default for Commands {
     fn default() -> Self {
-        Commands::Start { no_dashboard: false }
+        Commands::Start {
+            no_dashboard: false,
+            dev: false,
+            #[cfg(feature = "chaos")]
+            chaos: ChaosArgs::default(),
+        }
     }
 }
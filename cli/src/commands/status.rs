@@ -0,0 +1,149 @@
+use super::access::issue_token;
+use structopt::StructOpt;
+use tari_validator_client::ValidatorClient;
+use tari_validator_node::{api::models::NodeStatus, config::NodeConfig, db::models::InstructionStatus};
+
+const INSTRUCTION_STATUSES: [InstructionStatus; 5] = [
+    InstructionStatus::Scheduled,
+    InstructionStatus::Processing,
+    InstructionStatus::Pending,
+    InstructionStatus::Invalid,
+    InstructionStatus::Commit,
+];
+
+#[derive(StructOpt, Debug)]
+/// Prints status of the running node: uptime, templates, instructions, consensus rounds, DB pool and wallets.
+///
+/// Admin access token is issued for the node key unless `--access-token` is passed,
+/// node key needs admin access then, see `tvnc access grant admin`
+pub struct StatusCommand {
+    /// Node API url, defaults to the local node
+    #[structopt(long)]
+    url: Option<String>,
+    /// Admin access token
+    #[structopt(long)]
+    access_token: Option<String>,
+    /// Print status as JSON
+    #[structopt(long)]
+    json: bool,
+}
+
+impl StatusCommand {
+    pub async fn run(self, mut node_config: NodeConfig) -> anyhow::Result<()> {
+        let url = self
+            .url
+            .clone()
+            .unwrap_or_else(|| format!("http://localhost:{}", node_config.actix.port));
+        let identity = node_config.load_identity()?.clone();
+        let token = match self.access_token {
+            Some(token) => token,
+            None => issue_token(identity.public_key_hex(), &mut node_config)?,
+        };
+        let api = ValidatorClient::new(&url, identity).with_access_token(token);
+        let status = api
+            .node_status()
+            .await
+            .map_err(|err| anyhow::anyhow!("Node at {} is not available: {}", url, err))?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        } else {
+            print!("{}", report(&status));
+        }
+        Ok(())
+    }
+}
+
+fn report(status: &NodeStatus) -> String {
+    let templates: Vec<String> = status
+        .templates
+        .iter()
+        .map(|template| {
+            format!(
+                "{} ({} asset, {} token contracts)",
+                template.template_id, template.asset_contracts, template.token_contracts
+            )
+        })
+        .collect();
+    let instructions: Vec<String> = INSTRUCTION_STATUSES
+        .iter()
+        .map(|state| format!("{} {}", state, status.instructions.get(state).copied().unwrap_or(0)))
+        .collect();
+    let mut states: Vec<String> = status
+        .consensus_states
+        .iter()
+        .map(|(state, assets)| format!("{} {}", state, assets))
+        .collect();
+    states.sort();
+    let mut out = String::new();
+    out.push_str(&format!("Node:          {} v{}\n", status.node_id, status.version));
+    out.push_str(&format!(
+        "Uptime:        {} (since {})\n",
+        uptime(status.uptime_secs),
+        status.started_at.to_rfc3339()
+    ));
+    out.push_str(&format!("Templates:     {}\n", templates.join(", ")));
+    out.push_str(&format!("Instructions:  {}\n", instructions.join(", ")));
+    out.push_str(&format!("Consensus:     {} rounds in progress", status.consensus_rounds));
+    if !states.is_empty() {
+        out.push_str(&format!(", assets: {}", states.join(", ")));
+    }
+    out.push('\n');
+    out.push_str(&format!(
+        "DB pool:       {}/{} connections, {} available\n",
+        status.pool.size, status.pool.max_size, status.pool.available
+    ));
+    out.push_str(&format!("Wallets:       {}\n", status.wallets));
+    out
+}
+
+fn uptime(secs: i64) -> String {
+    let (days, secs) = (secs / 86400, secs % 86400);
+    format!("{}d {:02}:{:02}:{:02}", days, secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use tari_validator_node::api::{controllers::status::Status, models::MountedTemplate};
+
+    #[test]
+    fn status_report() {
+        assert_eq!(uptime(0), "0d 00:00:00");
+        assert_eq!(uptime(90061), "1d 01:01:01");
+
+        let mut instructions = HashMap::new();
+        instructions.insert(InstructionStatus::Commit, 10);
+        instructions.insert(InstructionStatus::Pending, 2);
+        let mut consensus_states = HashMap::new();
+        consensus_states.insert("PreparingView".to_string(), 3);
+        let status = NodeStatus {
+            version: "0.1.0".into(),
+            node_id: Default::default(),
+            started_at: Utc::now(),
+            uptime_secs: 3600,
+            templates: vec![MountedTemplate {
+                template_id: 1.into(),
+                asset_contracts: 2,
+                token_contracts: 4,
+            }],
+            instructions,
+            consensus_rounds: 1,
+            consensus_states,
+            pool: Status {
+                max_size: 16,
+                size: 4,
+                available: 3,
+            },
+            wallets: 5,
+        };
+        let report = report(&status);
+        assert!(report.contains("Uptime:        0d 01:00:00"));
+        assert!(report.contains("(2 asset, 4 token contracts)"));
+        assert!(report.contains("Scheduled 0, Processing 0, Pending 2, Invalid 0, Commit 10\n"));
+        assert!(report.contains("1 rounds in progress, assets: PreparingView 3\n"));
+        assert!(report.contains("4/16 connections, 3 available\n"));
+        assert!(report.contains("Wallets:       5\n"));
+    }
+}
@@ -4,7 +4,8 @@ use structopt::StructOpt;
 use tari_validator_node::{
     config::NodeConfig,
     db::{models::DigitalAsset, utils::db::db_client},
-    template::{single_use_tokens::SingleUseTokenTemplate, Template},
+    template::{escrow::EscrowTemplate, nft::NftTemplate, single_use_tokens::SingleUseTokenTemplate, Template},
+    types::TemplateID,
 };
 
 #[derive(StructOpt, Debug)]
@@ -13,6 +14,12 @@ pub enum TemplateCommands {
     List,
 }
 
+/// Templates installed on the node
+// TODO: templates are hardcoded for now, at later stage should come from config
+pub fn installed_templates() -> Vec<TemplateID> {
+    vec![SingleUseTokenTemplate::id(), EscrowTemplate::id(), NftTemplate::id()]
+}
+
 impl TemplateCommands {
     pub async fn run(self, node_config: NodeConfig) -> anyhow::Result<()> {
         let client = db_client(&node_config).await?;
@@ -20,7 +27,12 @@ impl TemplateCommands {
             TemplateCommands::List => {
                 // TODO: templates are hardcoded for now, at later stage should come from config
                 let mut templates = vec![];
-                for (id, name) in &[(SingleUseTokenTemplate::id(), "Single Use Tokens")] {
+                let installed = [
+                    (SingleUseTokenTemplate::id(), "Single Use Tokens"),
+                    (EscrowTemplate::id(), "Escrow"),
+                    (NftTemplate::id(), "NFT Collectibles"),
+                ];
+                for (id, name) in &installed {
                     let assets_len = DigitalAsset::find_by_template_id(&id, &client).await?.len();
                     templates.push(json!({
                         "Id": id.to_string(),
@@ -38,15 +38,16 @@ impl TokenCommands {
                                 output.push(json!({
                                     "Id": token.token_id,
                                     "IssueNumber": token.issue_number,
-                                    "Status": token.status
+                                    "Status": token.status,
+                                    "Owner": token.owner_pubkey,
                                 }))
                             }
 
                             Terminal::basic().render_list(
                                 format!("Tokens of asset ID {}", asset_id.to_string()).as_str(),
                                 output,
-                                &["Id", "IssueNumber", "Status"],
-                                &[96, 20, 20],
+                                &["Id", "IssueNumber", "Status", "Owner"],
+                                &[96, 20, 20, 64],
                             );
                         }
                     },
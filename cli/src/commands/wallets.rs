@@ -4,7 +4,7 @@ use structopt::StructOpt;
 use tari_common::GlobalConfig;
 use tari_validator_node::{
     config::NodeConfig,
-    db::utils::db::db_client,
+    db::{models::wallet::NATIVE_CURRENCY, utils::db::db_client},
     wallet::{NodeWallet, WalletStore},
 };
 
@@ -22,19 +22,42 @@ pub enum WalletCommands {
         /// Public key of a wallet
         pubkey: String,
     },
-    /// Set wallet's balance to amount of micro-XTR
+    /// Show wallet's balance or set it to amount of micro-XTR
     Balance {
         /// Public key of a wallet
         pubkey: String,
-        /// New balance
-        balance: i64,
+        /// New balance, balance is only shown if omitted
+        balance: Option<i64>,
+        /// Currency code or asset ID of the balance, XTR if omitted
+        #[structopt(long)]
+        currency: Option<String>,
+    },
+    /// Move amount of micro-XTR between wallets of this node
+    Transfer {
+        /// Public key of the wallet being debited
+        from: String,
+        /// Public key of the wallet being credited
+        to: String,
+        amount: i64,
+    },
+    /// Replace wallet's key with a new one, balance moves to the new wallet
+    Rotate {
+        /// Public key of a wallet
+        pubkey: String,
     },
 }
 
 impl WalletCommands {
-    pub async fn run(self, node_config: NodeConfig, global_config: GlobalConfig) -> anyhow::Result<()> {
+    pub async fn run(self, mut node_config: NodeConfig, global_config: GlobalConfig) -> anyhow::Result<()> {
         let mut client = db_client(&node_config).await?;
-        let mut store = WalletStore::init(node_config.wallets_keys_path.clone())?;
+        let mut store = WalletStore::init(
+            node_config.wallets_keys_path.clone(),
+            node_config.wallets_passphrase.clone(),
+        )?;
+        // temp wallets derived from node master key can't be loaded without node identity
+        if let Ok(master_key) = node_config.wallets_master_key() {
+            store = store.with_master_key(master_key);
+        }
 
         match self {
             Self::Create { name } => {
@@ -56,11 +79,41 @@ impl WalletCommands {
                 let wallet = store.get(pubkey, &client).await?;
                 Terminal::basic().render_object("Wallet details", wallet.data().clone());
             },
-            Self::Balance { pubkey, balance } => {
+            Self::Balance {
+                pubkey,
+                balance: None,
+                currency,
+            } => {
+                let wallet = store.get(pubkey.clone(), &client).await?;
+                let currency = currency.unwrap_or_else(|| NATIVE_CURRENCY.into());
+                let balance = wallet.data().balance_in(&currency, &client).await?;
+                let output = json!({"Pubkey": pubkey, "Name": wallet.name(), "Currency": currency, "Balance": balance});
+                Terminal::basic().render_object("Wallet balance", output);
+            },
+            Self::Balance {
+                pubkey,
+                balance: Some(balance),
+                currency,
+            } => {
                 let wallet = store.get(pubkey, &client).await?;
-                let wallet = wallet.data().set_balance(balance, &client).await?;
+                let currency = currency.unwrap_or_else(|| NATIVE_CURRENCY.into());
+                let wallet = wallet.data().set_balance_in(&currency, balance, &client).await?;
                 Terminal::basic().render_object("Wallet details", wallet);
             },
+            Self::Transfer { from, to, amount } => {
+                let (from, to) = store.transfer(from, to, amount, &mut client).await?;
+                let output: Vec<_> = [from, to]
+                    .iter()
+                    .map(|w| json!({"Pubkey": w.public_key_hex(), "Name": w.name(), "Balance": w.balance()}))
+                    .collect();
+                Terminal::basic().render_list("Transfer", output, &["Pubkey", "Name", "Balance"], &[20, 40, 16]);
+            },
+            Self::Rotate { pubkey } => {
+                let wallet = store
+                    .rotate(pubkey, global_config.public_address.clone(), &mut client)
+                    .await?;
+                Terminal::basic().render_object("Wallet details", wallet.data().clone());
+            },
         };
         Ok(())
     }
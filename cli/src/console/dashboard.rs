@@ -1,21 +1,50 @@
 use super::Terminal;
-use tari_validator_node::metrics::{Metrics, MetricsSnapshot};
+use crossterm::event::KeyCode;
+use tari_validator_node::{
+    db::models::InstructionStatus,
+    metrics::{AssetMetrics, Metrics, MetricsSnapshot},
+    types::AssetID,
+};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders, Gauge, Paragraph, Sparkline, Text},
+    style::{Color, Modifier, Style},
+    widgets::{BarChart, Block, Borders, Gauge, Paragraph, Row, Sparkline, Table, Text},
     Frame,
 };
 
+const STATUSES: &[(InstructionStatus, Color)] = &[
+    (InstructionStatus::Scheduled, Color::Yellow),
+    (InstructionStatus::Processing, Color::Blue),
+    (InstructionStatus::Pending, Color::Gray),
+    (InstructionStatus::Invalid, Color::Red),
+    (InstructionStatus::Commit, Color::Green),
+];
+
+/// Dashboard screens, switched with keyboard
+#[derive(Clone, Debug, PartialEq)]
+enum View {
+    /// Instructions sparklines, counters, DB pool and actor mailboxes
+    Overview,
+    /// List of assets with instructions and consensus state
+    Assets,
+    /// Drill-down into single asset
+    Asset(AssetID),
+}
+
 pub struct Dashboard {
     metrics: MetricsSnapshot,
+    view: View,
+    // index of selected asset on Assets view
+    selected: usize,
 }
 
 impl Default for Dashboard {
     fn default() -> Self {
         Self {
             metrics: MetricsSnapshot::from(&Metrics::default()),
+            view: View::Overview,
+            selected: 0,
         }
     }
 }
@@ -33,9 +62,41 @@ impl Dashboard {
 
     pub fn update_metrics(&mut self, metrics: MetricsSnapshot) {
         self.metrics = metrics;
+        self.selected = self.selected.min(self.metrics.assets.len().saturating_sub(1));
+    }
+
+    /// Navigation: Tab switches Overview and Assets, Up/Down (k/j) select asset,
+    /// Enter drills down into selected asset, Esc goes back
+    pub fn process_key(&mut self, code: KeyCode) {
+        match (&self.view, code) {
+            (_, KeyCode::Char('o')) => self.view = View::Overview,
+            (_, KeyCode::Char('a')) | (View::Overview, KeyCode::Tab) => self.view = View::Assets,
+            (View::Assets, KeyCode::Tab) | (View::Assets, KeyCode::Esc) => self.view = View::Overview,
+            (View::Assets, KeyCode::Up) | (View::Assets, KeyCode::Char('k')) => {
+                self.selected = self.selected.saturating_sub(1);
+            },
+            (View::Assets, KeyCode::Down) | (View::Assets, KeyCode::Char('j')) => {
+                self.selected = (self.selected + 1).min(self.metrics.assets.len().saturating_sub(1));
+            },
+            (View::Assets, KeyCode::Enter) => {
+                if let Some((asset_id, _)) = self.metrics.assets.get(self.selected) {
+                    self.view = View::Asset(asset_id.clone());
+                }
+            },
+            (View::Asset(_), KeyCode::Esc) | (View::Asset(_), KeyCode::Backspace) => self.view = View::Assets,
+            _ => {},
+        }
     }
 
     pub fn draw(&self, terminal: &mut Terminal) {
+        match &self.view {
+            View::Overview => self.draw_overview(terminal),
+            View::Assets => self.draw_assets(terminal),
+            View::Asset(asset_id) => self.draw_asset(terminal, asset_id),
+        }
+    }
+
+    fn draw_overview(&self, terminal: &mut Terminal) {
         terminal.draw(|mut f| {
             let rows = Layout::default()
             .direction(Direction::Vertical)
@@ -43,6 +104,7 @@ impl Dashboard {
                 [
                     Constraint::Length(17),
                     Constraint::Length(7),
+                    Constraint::Length(1),
                 ]
                 .as_ref(),
             )
@@ -73,11 +135,132 @@ impl Dashboard {
             self.draw_instruction_sparklines(&mut f, r1_columns[0]);
             self.draw_counters_info(&mut f, counters_area[0]);
             self.draw_pool_status(&mut f, counters_area[1]);
+            self.draw_mailboxes(&mut f, rows[1]);
+            Self::draw_help(&mut f, rows[2], "Tab/a: assets  Ctrl+C: quit");
         })
         // TODO: this should process errors - but ok for demo
         .unwrap();
     }
 
+    fn draw_assets(&self, terminal: &mut Terminal) {
+        terminal
+            .draw(|mut f| {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(5), Constraint::Length(1)].as_ref())
+                    .split(f.size());
+
+                let header = ["Asset", "Scheduled", "Processing", "Pending", "Invalid", "Commit", "Consensus"];
+                let data: Vec<Vec<String>> = self
+                    .metrics
+                    .assets
+                    .iter()
+                    .map(|(asset_id, asset)| {
+                        let mut row = vec![asset_id.to_string()];
+                        row.extend(STATUSES.iter().map(|(status, _)| asset.count(*status).to_string()));
+                        row.push(asset.consensus_state.clone().unwrap_or_default());
+                        row
+                    })
+                    .collect();
+                let selected = self.selected;
+                let table_rows = data.iter().enumerate().map(|(i, row)| {
+                    let style = if i == selected {
+                        Style::default().fg(Color::Black).bg(Color::White)
+                    } else {
+                        Style::default().fg(Color::White)
+                    };
+                    Row::StyledData(row.iter(), style)
+                });
+                let widths = [
+                    Constraint::Length(66),
+                    Constraint::Length(10),
+                    Constraint::Length(11),
+                    Constraint::Length(9),
+                    Constraint::Length(9),
+                    Constraint::Length(9),
+                    Constraint::Min(20),
+                ];
+                let table = Table::new(header.iter(), table_rows)
+                    .block(Block::default().borders(Borders::ALL).title("Assets"))
+                    .header_style(Style::default().fg(Color::Yellow))
+                    .widths(&widths);
+                f.render_widget(table, rows[0]);
+                Self::draw_help(&mut f, rows[1], "Up/Down: select  Enter: details  Tab/Esc: overview");
+            })
+            .unwrap();
+    }
+
+    fn draw_asset(&self, terminal: &mut Terminal, asset_id: &AssetID) {
+        let default = AssetMetrics::default();
+        let asset = self
+            .metrics
+            .assets
+            .iter()
+            .find(|(id, _)| id == asset_id)
+            .map(|(_, asset)| asset)
+            .unwrap_or(&default);
+        terminal
+            .draw(|mut f| {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(10), Constraint::Length(5), Constraint::Length(1)].as_ref())
+                    .split(f.size());
+
+                let labels: Vec<String> = STATUSES.iter().map(|(status, _)| status.to_string()).collect();
+                let data: Vec<(&str, u64)> = labels
+                    .iter()
+                    .zip(STATUSES.iter())
+                    .map(|(label, (status, _))| (label.as_str(), asset.count(*status)))
+                    .collect();
+                let title = format!("Instructions of asset {}", asset_id);
+                let chart = BarChart::default()
+                    .block(Block::default().borders(Borders::ALL).title(&title))
+                    .data(&data)
+                    .bar_width(12)
+                    .bar_gap(2)
+                    .style(Style::default().fg(Color::Green))
+                    .value_style(Style::default().fg(Color::Black).bg(Color::Green).modifier(Modifier::BOLD));
+                f.render_widget(chart, rows[0]);
+
+                let consensus = [
+                    Text::raw(format!(
+                        "State: {}\n",
+                        asset.consensus_state.as_ref().map(String::as_str).unwrap_or("idle")
+                    )),
+                    Text::raw(format!("Steps: {}\n", asset.consensus_steps)),
+                    Text::raw(format!("Instructions: {}", asset.total())),
+                ];
+                let consensus = Paragraph::new(consensus.iter())
+                    .block(Block::default().borders(Borders::ALL).title("Consensus round"))
+                    .style(Style::default().fg(Color::White));
+                f.render_widget(consensus, rows[1]);
+                Self::draw_help(&mut f, rows[2], "Esc: back to assets  o: overview");
+            })
+            .unwrap();
+    }
+
+    fn draw_mailboxes<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let text: Vec<Text> = if self.metrics.mailboxes.is_empty() {
+            vec![Text::raw("No actors reported")]
+        } else {
+            self.metrics
+                .mailboxes
+                .iter()
+                .map(|(actor, depth)| Text::raw(format!("{}: {}\n", actor, depth)))
+                .collect()
+        };
+        let mailboxes = Paragraph::new(text.iter())
+            .block(Block::default().borders(Borders::ALL).title("Actor mailboxes"))
+            .style(Style::default().fg(Color::White));
+        f.render_widget(mailboxes, area);
+    }
+
+    fn draw_help<B: Backend>(f: &mut Frame<B>, area: Rect, help: &str) {
+        let help = [Text::raw(help)];
+        let help = Paragraph::new(help.iter()).style(Style::default().fg(Color::Gray));
+        f.render_widget(help, area);
+    }
+
     fn draw_instruction_sparklines<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -170,7 +353,8 @@ impl Dashboard {
         if waiting_ratio > 1f64 {
             waiting_ratio = 1f64;
         }
-        let title = format!("Waiting {}", waiting);
+        let wait = &self.metrics.pool_wait;
+        let title = format!("Waiting {}, wait avg {}ms max {}ms", waiting, wait.avg_ms, wait.max_ms);
         let connections = Gauge::default()
             .block(Block::default().borders(Borders::ALL).title(title.as_str()))
             .style(Style::default().fg(Color::Red).bg(Color::Gray))
@@ -178,3 +362,39 @@ impl Dashboard {
         f.render_widget(connections, chunks[1]);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn navigation() {
+        let mut dashboard = Dashboard::default();
+        dashboard.process_key(KeyCode::Enter);
+        assert_eq!(dashboard.view, View::Overview);
+
+        let mut metrics = MetricsSnapshot::from(&Metrics::default());
+        metrics.assets = vec![
+            (AssetID::default(), AssetMetrics::default()),
+            (AssetID::default(), AssetMetrics::default()),
+        ];
+        dashboard.update_metrics(metrics);
+        dashboard.process_key(KeyCode::Tab);
+        assert_eq!(dashboard.view, View::Assets);
+        for _ in 0..3 {
+            dashboard.process_key(KeyCode::Down);
+        }
+        assert_eq!(dashboard.selected, 1);
+        dashboard.process_key(KeyCode::Enter);
+        assert_eq!(dashboard.view, View::Asset(AssetID::default()));
+        dashboard.process_key(KeyCode::Esc);
+        assert_eq!(dashboard.view, View::Assets);
+        dashboard.process_key(KeyCode::Char('k'));
+        assert_eq!(dashboard.selected, 0);
+
+        dashboard.update_metrics(MetricsSnapshot::from(&Metrics::default()));
+        assert_eq!(dashboard.selected, 0);
+        dashboard.process_key(KeyCode::Esc);
+        assert_eq!(dashboard.view, View::Overview);
+    }
+}
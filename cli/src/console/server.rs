@@ -104,7 +104,11 @@ impl ServerConsole {
                 self.kill_signal.close();
                 // std::process::exit(1)
             },
-            _ => {},
+            (code, _) => {
+                if let Some(dashboard) = &mut self.dashboard {
+                    dashboard.process_key(code);
+                }
+            },
         }
     }
 }
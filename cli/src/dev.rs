@@ -0,0 +1,144 @@
+//! Single node development mode, `tvnc start --dev`
+//!
+//! Prepares everything needed to try the node with a single command:
+//! - node identity is generated if `identity_path` doesn't exist yet
+//! - separate `<dbname>_dev` database is created and migrated, read replica is not used
+//! - API access tokens are not required
+//! - demo asset of SingleUseToken template issued by the node key is seeded once
+//!
+//! Dev mode is not the zero-dependency mode on embedded storage: templates and consensus run Postgres specific SQL
+//! (triggers, views, pgcrypto) within transactions and `db::store` backends only serve API lookups, so dev mode
+//! still needs a local Postgres server. It fails right away with a hint instead of waiting for the connect timeout
+//! when the server is not running.
+//!
+//! TODO: P2: run dev mode on embedded storage once templates and consensus are ported off tokio-postgres
+
+use anyhow::anyhow;
+use serde_json::json;
+use std::time::Duration;
+use tari_validator_node::{
+    config::NodeConfig,
+    db::{
+        lifecycle::{create_asset, NewAsset},
+        models::AssetState,
+        utils::db::{create_database, db_client, wait_for_postgres},
+    },
+    identity::NodeIdentity,
+    template::{single_use_tokens::SingleUseTokenTemplate, Template},
+    types::{AssetID, CommitteeMode, RaidID},
+};
+
+pub const DEMO_ASSET_NAME: &'static str = "Demo asset";
+pub const DEV_DB_SUFFIX: &'static str = "_dev";
+/// Dev mode doesn't wait for Postgres to start as it is expected to be running already
+const DEV_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Switches config to dev mode: separate database without read replica, access tokens are not required
+pub fn dev_config(node_config: &mut NodeConfig) {
+    let dbname = node_config.postgres.dbname.clone().unwrap_or_else(|| "validator".into());
+    if !dbname.ends_with(DEV_DB_SUFFIX) {
+        node_config.postgres.dbname = Some(format!("{}{}", dbname, DEV_DB_SUFFIX));
+    }
+    node_config.postgres_read = None;
+    node_config.auth.enabled = false;
+}
+
+/// ID of the demo asset is derived from its name, so it is the same on every start
+pub fn demo_asset_id() -> AssetID {
+    AssetID::new(
+        SingleUseTokenTemplate::id(),
+        0,
+        RaidID::default(),
+        AssetID::generate_hash(DEMO_ASSET_NAME.into()),
+    )
+}
+
+/// Switches config to dev mode and prepares identity, database and demo asset
+pub async fn prepare(node_config: &mut NodeConfig) -> anyhow::Result<AssetState> {
+    dev_config(node_config);
+
+    let path = node_config
+        .identity_path
+        .clone()
+        .ok_or_else(|| anyhow!("validator.identity_path is not configured"))?;
+    if !path.exists() {
+        NodeIdentity::random().save(&path, false)?;
+        println!("Node identity stored to {}", path.display());
+    }
+    let identity = node_config.load_identity()?.clone();
+
+    // dev database might not exist yet, so the server is checked via maintenance database
+    let mut pg = node_config.postgres.get_pg_config()?;
+    pg.dbname("postgres");
+    wait_for_postgres(pg, DEV_CONNECT_TIMEOUT).await.map_err(|err| {
+        anyhow!(
+            "Dev mode needs a local Postgres server configured by PG_* variables, e.g. started with \
+             `docker run -d -p 5432:5432 -e POSTGRES_PASSWORD=password postgres`: {}",
+            err
+        )
+    })?;
+    println!("Initializing dev database {:?}", node_config.postgres.dbname);
+    create_database(node_config.clone()).await?;
+    let client = db_client(&node_config).await?;
+    let asset_id = demo_asset_id();
+    if let Some(asset) = AssetState::find_by_asset_id(&asset_id, &client).await? {
+        return Ok(asset);
+    }
+    let params = NewAsset {
+        asset_id,
+        name: DEMO_ASSET_NAME.into(),
+        description: "Seeded by dev mode".into(),
+        fqdn: None,
+        limit_per_wallet: None,
+        max_supply: None,
+        allow_transfers: true,
+        authorized_signers: vec![],
+        expiry_date: None,
+        initial_data_json: json!({}),
+        committee_mode: CommitteeMode::default(),
+    };
+    Ok(create_asset(
+        params,
+        identity.public_key_hex(),
+        Some(identity.node_id()),
+        &node_config.consensus.committee_candidates,
+        &client,
+    )
+    .await?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{build_test_config, Test};
+    use tempdir::TempDir;
+
+    #[test]
+    fn switch_to_dev_config() {
+        let mut config = build_test_config().unwrap();
+        config.postgres.dbname = Some("validator".into());
+        config.auth.enabled = true;
+        dev_config(&mut config);
+        assert_eq!(config.postgres.dbname.as_deref(), Some("validator_dev"));
+        assert!(config.postgres_read.is_none());
+        assert!(!config.auth.enabled);
+        // restarting in dev mode keeps the same database
+        dev_config(&mut config);
+        assert_eq!(config.postgres.dbname.as_deref(), Some("validator_dev"));
+    }
+
+    #[actix_rt::test]
+    async fn prepare_seeds_demo_asset_once() {
+        let mut config = build_test_config().unwrap();
+        config.identity_path = Some(Test::<TempDir>::get_path_buf().join("dev_identity.json"));
+        let asset = prepare(&mut config.clone()).await.unwrap();
+        assert_eq!(asset.asset_id, demo_asset_id());
+        assert_eq!(asset.name, DEMO_ASSET_NAME);
+        assert!(config.identity_path.as_ref().unwrap().exists());
+
+        let issuer = config.load_identity().unwrap().public_key_hex();
+        assert_eq!(asset.asset_issuer_pub_key, issuer);
+        let again = prepare(&mut config).await.unwrap();
+        assert_eq!(again.id, asset.id);
+    }
+}
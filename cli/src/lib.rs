@@ -16,6 +16,7 @@ pub use args::Arguments;
 pub mod commands;
 pub use commands::Commands;
 pub mod console;
+pub mod dev;
 
 #[cfg(test)]
 pub(crate) mod test_utils;
@@ -1,17 +1,36 @@
 use actix::Actor;
 use dotenv::dotenv;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use structopt::StructOpt;
 use tari_common::GlobalConfig;
 use tari_validator_node::{
     api::server::actix_main,
     config::NodeConfig,
-    db::{migrations, utils::db},
+    db::{
+        lifecycle,
+        migrations,
+        models::AssetState,
+        retention,
+        utils::{db, statements::CachedClient},
+    },
     metrics::Metrics,
+    telemetry::init_tracing,
 };
 use tvnc::{console::ServerConsole, Arguments, Commands};
 
-async fn start_server(node_config: NodeConfig, no_dashboard: bool) -> anyhow::Result<()> {
+async fn start_server(mut node_config: NodeConfig, no_dashboard: bool, dev: bool) -> anyhow::Result<()> {
+    if dev {
+        let asset = tvnc::dev::prepare(&mut node_config).await?;
+        println!("Dev mode, API access tokens are not required. Demo asset: {}", asset.asset_id);
+    }
+    node_config.load_signer()?;
+    let connect_timeout = Duration::from_secs(node_config.postgres_connect_timeout);
+    db::wait_for_postgres(node_config.postgres.get_pg_config()?, connect_timeout).await?;
+    if let Some(pg_read) = node_config.postgres_read.as_ref() {
+        db::wait_for_postgres(pg_read.get_pg_config()?, connect_timeout).await?;
+    }
+    // spans are flushed to collector when guard is dropped on server termination
+    let _tracing = init_tracing(&node_config.tracing)?;
     let pool = Arc::new(db::build_pool(&node_config.postgres)?);
     let metrics_addr = Metrics::new(pool.clone()).start();
     let kill_console = ServerConsole::init(metrics_addr.clone(), !no_dashboard).await;
@@ -29,18 +48,55 @@ async fn main() -> anyhow::Result<()> {
     args.init_configs()?;
     let config = args.load_configuration()?;
     let global_config = GlobalConfig::convert_from(config.clone())?;
-    let node_config = NodeConfig::load_from(&config, &global_config, true)?;
+    let mut node_config = NodeConfig::load_from(&config, &global_config, true)?;
+    node_config.config_path = Some(args.bootstrap.config.clone());
 
     match args.command {
-        Commands::Start { no_dashboard } => start_server(node_config, no_dashboard).await?,
+        #[cfg(feature = "chaos")]
+        Commands::Start {
+            no_dashboard,
+            dev,
+            chaos,
+        } => {
+            tari_validator_node::chaos::configure(chaos.into());
+            start_server(node_config, no_dashboard, dev).await?
+        },
+        #[cfg(not(feature = "chaos"))]
+        Commands::Start { no_dashboard, dev } => start_server(node_config, no_dashboard, dev).await?,
         Commands::Init => {
+            let mut pg = node_config.postgres.get_pg_config()?;
+            pg.dbname("postgres");
+            db::wait_for_postgres(pg, Duration::from_secs(node_config.postgres_connect_timeout)).await?;
             println!("Initializing database {:?}", node_config.postgres.dbname);
             db::create_database(node_config).await?;
         },
-        Commands::Migrate => {
+        Commands::Migrate { status: true, .. } => {
+            let status = migrations::status(&node_config).await?;
+            println!("Migrations of database {:?}", node_config.postgres.dbname);
+            for migration in status.applied {
+                println!("applied  V{}__{} (on {})", migration.version, migration.name, migration.applied_on);
+            }
+            for migration in status.pending {
+                println!("pending  V{}__{}", migration.version, migration.name);
+            }
+        },
+        Commands::Migrate { dry_run: true, .. } => {
+            let pending = migrations::dry_run(&node_config).await?;
+            println!("Dry run: {} pending migrations on database {:?}", pending.len(), node_config.postgres.dbname);
+            for migration in pending {
+                println!("-- V{}__{}\n{}", migration.version, migration.name, migration.sql);
+            }
+        },
+        Commands::Migrate { .. } => {
             println!("Running migrations on database {:?}", node_config.postgres.dbname);
             migrations::migrate(node_config).await?;
         },
+        Commands::Config(cmd) => cmd.run(node_config, global_config).await?,
+        Commands::Status(cmd) => cmd.run(node_config).await?,
+        Commands::Identity(cmd) => {
+            println!("Identity -> {:?}", cmd);
+            cmd.run(node_config).await?;
+        },
         Commands::Access(cmd) => {
             println!("Access -> {:?}", cmd);
             cmd.run(node_config).await?;
@@ -49,20 +105,61 @@ async fn main() -> anyhow::Result<()> {
             println!("Wallet -> {:?}", cmd);
             cmd.run(node_config, global_config).await?;
         },
-        Commands::Wipe { y } => {
+        Commands::Prune { before, keep } => {
+            let before = before.unwrap_or_else(|| node_config.retention.before());
+            let keep = keep.unwrap_or(node_config.retention.keep_checkpoints);
+            println!("Pruning append only state created before {}, keeping {} latest records", before, keep);
+            let client = db::db_client(&node_config).await?;
+            let stats = retention::prune(before, keep, &client).await?;
+            println!(
+                "Rolled up {} token records into {} snapshots, {} asset records into {} snapshots",
+                stats.token_records, stats.token_snapshots, stats.asset_records, stats.asset_snapshots
+            );
+        },
+        Commands::Wipe {
+            y,
+            asset: None,
+            template: None,
+        } => {
             if !y && !prompt("Do you really want to wipe all data (Y/n)?") {
                 return Ok(());
             }
             println!("Resetting database {:?}", node_config.postgres.dbname);
             db::reset_database(node_config).await?;
         },
+        Commands::Wipe { y, asset, template } => {
+            let client = db::db_client(&node_config).await?;
+            let asset_ids = match (asset, template) {
+                (Some(asset_id), _) => vec![asset_id],
+                (None, Some(template_id)) => AssetState::find_by_template_id(&template_id, &client)
+                    .await?
+                    .into_iter()
+                    .map(|asset| asset.asset_id)
+                    .collect(),
+                (None, None) => vec![],
+            };
+            if asset_ids.is_empty() {
+                println!("No assets to wipe");
+                return Ok(());
+            }
+            let question = format!("Do you really want to wipe {} asset(s) {:?} (Y/n)?", asset_ids.len(), asset_ids);
+            if !y && !prompt(&question) {
+                return Ok(());
+            }
+            let stats = lifecycle::wipe_assets(&asset_ids, &client).await?;
+            println!(
+                "Wiped {} assets: {} tokens, {} instructions, {} proposals",
+                stats.assets, stats.tokens, stats.instructions, stats.proposals
+            );
+        },
         Commands::Template(cmd) => {
             println!("Template -> {:?}", cmd);
             cmd.run(node_config).await?;
         },
         Commands::Instruction(cmd) => {
             println!("Instruction -> {:?}", cmd);
-            let client = db::db_client_raw(&node_config).await?;
+            // watch and wait commands keep polling the same queries
+            let client = CachedClient::new(db::db_client_raw(&node_config).await?);
             cmd.run(node_config, &client).await?;
         },
         Commands::Asset(cmd) => {
@@ -73,6 +170,10 @@ async fn main() -> anyhow::Result<()> {
             println!("Token -> {:?}", cmd);
             cmd.run(node_config).await?;
         },
+        Commands::Bench(cmd) => {
+            println!("Bench -> {:?}", cmd);
+            cmd.run(node_config).await?;
+        },
     };
 
     Ok(())
@@ -0,0 +1,18 @@
+use tari_validator_node::{identity::IdentityError, types::InstructionID};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("API error {status}: {message}")]
+    Api { status: u16, message: String },
+    #[error("Json parsing error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Request signing failed: {0}")]
+    Signature(#[from] IdentityError),
+    #[error("Instruction {id} is Invalid: {result}")]
+    InvalidInstruction { id: InstructionID, result: serde_json::Value },
+    #[error("Timeout waiting for instruction {0}")]
+    Timeout(InstructionID),
+}
@@ -0,0 +1,219 @@
+//! Client SDK for the validator node API
+//!
+//! [ValidatorClient] wraps template routes with typed async methods, signs contract params
+//! with the client key (see [RequestSignature]) and polls instructions until they settle.
+//!
+//! ```ignore
+//! let client = ValidatorClient::new("http://localhost:3001", NodeIdentity::random()).with_access_token(token);
+//! let instruction = client.issue_tokens(&asset_id, IssueTokensParams { quantity: Some(10), token_ids: None }).await?;
+//! let instruction = client.wait_for_commit(instruction.id).await?;
+//! ```
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+use tari_validator_node::{
+    api::models::{NodeStatus, RequestSignature, SignedCall, SIGNATURE_HEADER, SIGNER_PUBKEY_HEADER},
+    db::models::consensus::instructions::{Instruction, InstructionStatus},
+    identity::NodeIdentity,
+    template::{
+        asset_call_path,
+        single_use_tokens::{IssueTokensParams, RedeemTokenParams, SellTokenParams, TransferTokenParams},
+        token_call_path,
+    },
+    types::{AssetID, InstructionID, TokenID},
+};
+use tokio::time::delay_for;
+
+mod errors;
+pub use errors::ClientError;
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+pub const DEFAULT_POLL_RETRIES: usize = 120;
+
+pub struct ValidatorClient {
+    base_url: String,
+    http: reqwest::Client,
+    signer: NodeIdentity,
+    access_token: Option<String>,
+    poll_interval: Duration,
+    poll_retries: usize,
+}
+
+impl ValidatorClient {
+    /// Client of the node at `base_url`, contract calls are signed by `signer`
+    pub fn new(base_url: &str, signer: NodeIdentity) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            signer,
+            access_token: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            poll_retries: DEFAULT_POLL_RETRIES,
+        }
+    }
+
+    /// Access token issued by `tvnc access grant api`, required unless node disabled auth
+    pub fn with_access_token(mut self, token: String) -> Self {
+        self.access_token = Some(token);
+        self
+    }
+
+    /// Interval and max number of polls of [ValidatorClient::wait_for_status]
+    pub fn with_polling(mut self, interval: Duration, retries: usize) -> Self {
+        self.poll_interval = interval;
+        self.poll_retries = retries;
+        self
+    }
+
+    pub fn signer(&self) -> &NodeIdentity {
+        &self.signer
+    }
+
+    pub async fn issue_tokens(
+        &self,
+        asset_id: &AssetID,
+        params: IssueTokensParams,
+    ) -> Result<Instruction, ClientError>
+    {
+        self.asset_call(asset_id, "issue_tokens", &params).await
+    }
+
+    pub async fn sell_token(&self, token_id: &TokenID, params: SellTokenParams) -> Result<Instruction, ClientError> {
+        self.token_call(token_id, "sell_token", &params).await
+    }
+
+    pub async fn transfer_token(
+        &self,
+        token_id: &TokenID,
+        params: TransferTokenParams,
+    ) -> Result<Instruction, ClientError>
+    {
+        self.token_call(token_id, "transfer_token", &params).await
+    }
+
+    pub async fn redeem_token(&self, token_id: &TokenID) -> Result<Instruction, ClientError> {
+        self.token_call(token_id, "redeem_token", &RedeemTokenParams).await
+    }
+
+    /// Call any asset contract of the template
+    pub async fn asset_call<P: Serialize>(
+        &self,
+        asset_id: &AssetID,
+        contract: &str,
+        params: &P,
+    ) -> Result<Instruction, ClientError>
+    {
+        self.contract_call(asset_call_path(asset_id, contract), SignedCall::asset(asset_id, contract), params)
+            .await
+    }
+
+    /// Call any token contract of the template
+    pub async fn token_call<P: Serialize>(
+        &self,
+        token_id: &TokenID,
+        contract: &str,
+        params: &P,
+    ) -> Result<Instruction, ClientError>
+    {
+        self.contract_call(token_call_path(token_id, contract), SignedCall::token(token_id, contract), params)
+            .await
+    }
+
+    pub async fn get_instruction(&self, id: InstructionID) -> Result<Instruction, ClientError> {
+        let request = self.http.get(&self.url(&format!("/instructions/{}", id)));
+        self.send(request).await
+    }
+
+    pub async fn get_subinstructions(&self, id: InstructionID) -> Result<Vec<Instruction>, ClientError> {
+        let request = self.http.get(&self.url(&format!("/instructions/{}/subinstructions", id)));
+        self.send(request).await
+    }
+
+    /// Status of the node, requires admin access token
+    pub async fn node_status(&self) -> Result<NodeStatus, ClientError> {
+        let mut request = self.http.get(&self.url("/admin/status"));
+        if let Some(token) = self.access_token.as_ref() {
+            request = request.bearer_auth(token);
+        }
+        self.send(request).await
+    }
+
+    /// Poll instruction until it reaches `status` or Commit, fails on Invalid status
+    pub async fn wait_for_status(
+        &self,
+        id: InstructionID,
+        status: InstructionStatus,
+    ) -> Result<Instruction, ClientError>
+    {
+        for _ in 0..self.poll_retries {
+            let instruction = self.get_instruction(id).await?;
+            if instruction.status == status || instruction.status == InstructionStatus::Commit {
+                return Ok(instruction);
+            } else if instruction.status == InstructionStatus::Invalid {
+                return Err(ClientError::InvalidInstruction {
+                    id,
+                    result: instruction.result,
+                });
+            }
+            delay_for(self.poll_interval).await;
+        }
+        Err(ClientError::Timeout(id))
+    }
+
+    pub async fn wait_for_commit(&self, id: InstructionID) -> Result<Instruction, ClientError> {
+        self.wait_for_status(id, InstructionStatus::Commit).await
+    }
+
+    async fn contract_call<P: Serialize>(
+        &self,
+        path: String,
+        call: SignedCall,
+        params: &P,
+    ) -> Result<Instruction, ClientError>
+    {
+        let params = serde_json::to_value(params)?;
+        let signature = RequestSignature::sign(&self.signer, &call, &params)?;
+        let mut request = self
+            .http
+            .post(&self.url(&path))
+            .header(SIGNER_PUBKEY_HEADER, signature.pubkey)
+            .header(SIGNATURE_HEADER, signature.signature)
+            .json(&params);
+        if let Some(token) = self.access_token.as_ref() {
+            request = request.bearer_auth(token);
+        }
+        self.send(request).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|value| value["error"].as_str().map(String::from))
+            .unwrap_or(body);
+        Err(ClientError::Api {
+            status: status.as_u16(),
+            message,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn urls() {
+        let client = ValidatorClient::new("http://localhost:3001/", NodeIdentity::random());
+        assert_eq!(client.url("/status"), "http://localhost:3001/status");
+    }
+}
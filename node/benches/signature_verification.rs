@@ -0,0 +1,36 @@
+//! Aggregate signature verification of a 64-node committee one by one vs in a single batch.
+//!
+//! Does not require database:
+//! ```bash
+//! cargo bench --bench signature_verification
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tari_validator_node::identity::NodeIdentity;
+
+const COMMITTEE_SIZE: usize = 64;
+const MESSAGE: &'static [u8] = b"proposal signing payload";
+
+fn aggregate_signature(c: &mut Criterion) {
+    let identities: Vec<_> = (0..COMMITTEE_SIZE).map(|_| NodeIdentity::random()).collect();
+    let signatures: Vec<_> = identities.iter().map(|i| i.sign(MESSAGE).unwrap()).collect();
+    let batch: Vec<_> = identities
+        .iter()
+        .zip(signatures.iter())
+        .map(|(i, s)| (i.public_key(), s.as_str(), MESSAGE))
+        .collect();
+
+    let mut group = c.benchmark_group("aggregate_signature_64");
+    group.bench_function("per_signature", |b| {
+        b.iter(|| {
+            assert!(batch
+                .iter()
+                .all(|(public_key, signature, message)| NodeIdentity::verify(public_key, signature, message)))
+        })
+    });
+    group.bench_function("batch", |b| b.iter(|| assert!(NodeIdentity::verify_batch(&batch))));
+    group.finish();
+}
+
+criterion_group!(benches, aggregate_signature);
+criterion_main!(benches);
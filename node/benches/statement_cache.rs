@@ -0,0 +1,59 @@
+//! Instruction polling with statement prepared per query vs reused from the connection cache.
+//!
+//! This is the lookup `make_it_rain` clients hammer the node with while waiting for instructions to commit.
+//! Requires migrated test database configured via PG_TEST_* env vars:
+//! ```bash
+//! cargo bench --bench statement_cache
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tari_validator_node::{
+    db::{
+        models::consensus::Instruction,
+        utils::{db::connect_raw, statements::CachedClient},
+    },
+    types::InstructionID,
+};
+
+const LOOKUPS: usize = 100;
+const QUERY: &'static str = "SELECT * FROM instructions WHERE id = $1::\"InstructionID\"";
+
+/// Test database is configured via PG_TEST_* env vars, same as for `cargo test`
+fn pg_config() -> tokio_postgres::Config {
+    let _ = dotenv::dotenv();
+    let mut env = config::Config::new();
+    env.merge(config::Environment::with_prefix("PG_TEST")).unwrap();
+    let pg: deadpool_postgres::config::Config = env.try_into().unwrap();
+    pg.get_pg_config().expect("Invalid PG_TEST_* config")
+}
+
+fn instruction_polling(c: &mut Criterion) {
+    let mut sys = actix_rt::System::new("statement_cache");
+    let client = CachedClient::new(sys.block_on(connect_raw(pg_config())).expect("Failed to connect test DB"));
+    let id = InstructionID::default();
+
+    let mut group = c.benchmark_group("instruction_polling");
+    group.bench_function("prepare_per_query", |b| {
+        b.iter(|| {
+            sys.block_on(async {
+                for _ in 0..LOOKUPS {
+                    let stmt = client.prepare(QUERY).await.unwrap();
+                    client.query_opt(&stmt, &[&id]).await.unwrap();
+                }
+            })
+        })
+    });
+    group.bench_function("cached_statement", |b| {
+        b.iter(|| {
+            sys.block_on(async {
+                for _ in 0..LOOKUPS {
+                    Instruction::find(id, &client).await.unwrap();
+                }
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, instruction_polling);
+criterion_main!(benches);
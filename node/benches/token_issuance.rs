@@ -0,0 +1,123 @@
+//! Issuing 10k tokens of an asset one insert at a time vs a single batch vs concurrent batches.
+//!
+//! Concurrent batches are inserted the way `issue_tokens` does within an execution transaction,
+//! see `InstructionContext::for_each_concurrent`. Requires migrated test database configured via PG_TEST_* env vars:
+//! ```bash
+//! cargo bench --bench token_issuance
+//! ```
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, Criterion};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::json;
+use tari_validator_node::{
+    db::{
+        models::{AssetState, DigitalAsset, NewAssetState, NewDigitalAsset, NewToken, Token},
+        utils::db::build_pool,
+    },
+    identity::NodeIdentity,
+    types::{AssetID, CommitteeMode, NodeID, RaidID, TemplateID, TokenID},
+};
+
+const TOKENS: usize = 10_000;
+const BATCH_SIZE: usize = 100;
+const CONCURRENCY: usize = 4;
+
+/// Test database is configured via PG_TEST_* env vars, same as for `cargo test`
+fn pg_config() -> deadpool_postgres::config::Config {
+    let _ = dotenv::dotenv();
+    let mut env = config::Config::new();
+    env.merge(config::Environment::with_prefix("PG_TEST")).unwrap();
+    env.try_into().expect("Invalid PG_TEST_* config")
+}
+
+fn new_tokens(asset: &AssetState, node_id: &NodeID) -> Vec<NewToken> {
+    (0..TOKENS)
+        .map(|_| NewToken {
+            token_id: TokenID::new(&asset.asset_id, node_id).unwrap(),
+            asset_state_id: asset.id,
+            initial_data_json: json!({ "owner_pubkey": asset.asset_issuer_pub_key, "used": false }),
+        })
+        .collect()
+}
+
+fn issue_tokens(c: &mut Criterion) {
+    let mut sys = actix_rt::System::new("token_issuance");
+    let pool = build_pool(&pg_config()).expect("Failed to create test DB pool");
+    let client = sys.block_on(pool.get()).expect("Failed to connect test DB");
+    let issuer = NodeIdentity::random();
+    let node_id = issuer.node_id();
+    let asset = sys.block_on(async {
+        let digital_asset_id = DigitalAsset::insert(
+            NewDigitalAsset {
+                template_type: 1,
+                committee_mode: CommitteeMode::Creator {
+                    trusted_node_set: Vec::new(),
+                },
+                fqdn: None,
+                raid_id: None,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let hash = format!("{:032X}", Utc::now().timestamp_nanos());
+        let asset_id = AssetID::new(TemplateID::from(1u64), 0, RaidID::default(), hash);
+        let id = AssetState::insert(
+            NewAssetState {
+                name: "token_issuance bench".into(),
+                description: String::new(),
+                limit_per_wallet: None,
+                max_supply: None,
+                allow_transfers: true,
+                asset_issuer_pub_key: issuer.public_key_hex(),
+                authorized_signers: Vec::new(),
+                expiry_date: None,
+                initial_permission_bitflag: 0,
+                initial_data_json: json!({}),
+                asset_id,
+                digital_asset_id,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        AssetState::load(id, &client).await.unwrap()
+    });
+
+    let mut group = c.benchmark_group("issue_10k_tokens");
+    group.sample_size(10);
+    group.bench_function("per_token", |b| {
+        b.iter(|| {
+            sys.block_on(async {
+                for token in new_tokens(&asset, &node_id) {
+                    Token::insert(token, &client).await.unwrap();
+                }
+            })
+        })
+    });
+    group.bench_function("single_batch", |b| {
+        b.iter(|| sys.block_on(Token::insert_batch(new_tokens(&asset, &node_id), &client)).unwrap())
+    });
+    group.bench_function("concurrent_batches", |b| {
+        b.iter(|| {
+            let batches = new_tokens(&asset, &node_id)
+                .chunks(BATCH_SIZE)
+                .map(<[NewToken]>::to_vec)
+                .collect::<Vec<_>>();
+            let inserted: Vec<_> = sys
+                .block_on(
+                    stream::iter(batches)
+                        .map(|batch| Token::insert_batch(batch, &client))
+                        .buffered(CONCURRENCY)
+                        .try_collect(),
+                )
+                .unwrap();
+            assert_eq!(inserted.len(), TOKENS / BATCH_SIZE);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, issue_tokens);
+criterion_main!(benches);
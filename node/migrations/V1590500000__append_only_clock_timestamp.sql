@@ -0,0 +1,6 @@
+-- Proposal is executed within single transaction, where now() is frozen at transaction start.
+-- Append only state is ordered by created_at, so every record should get actual insertion time.
+ALTER TABLE token_state_append_only ALTER COLUMN created_at SET DEFAULT clock_timestamp();
+ALTER TABLE token_state_append_only ALTER COLUMN updated_at SET DEFAULT clock_timestamp();
+ALTER TABLE asset_state_append_only ALTER COLUMN created_at SET DEFAULT clock_timestamp();
+ALTER TABLE asset_state_append_only ALTER COLUMN updated_at SET DEFAULT clock_timestamp();
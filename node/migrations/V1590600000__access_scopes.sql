@@ -0,0 +1,3 @@
+-- Scopes restrict API access to templates or contracts:
+-- '*' - any template, '<template_id>' - any contract of template, '<template_id>/<contract>' - single contract
+ALTER TABLE access ADD COLUMN scopes TEXT[] NOT NULL DEFAULT '{*}';
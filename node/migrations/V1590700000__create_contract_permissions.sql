@@ -0,0 +1,13 @@
+CREATE TABLE contract_permissions (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       asset_id char(64) NOT NULL references asset_states(asset_id),
+                       contract_name TEXT NOT NULL,
+                       rule TEXT NOT NULL,
+                       pub_key TEXT NULL DEFAULT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       UNIQUE (asset_id, contract_name, rule, pub_key)
+);
+
+-- Indices
+CREATE INDEX index_contract_permissions_asset_contract ON contract_permissions (asset_id, contract_name);
@@ -0,0 +1,90 @@
+-- Latest append only state per token and asset, maintained by triggers on append only tables.
+-- tokens_view and asset_states_view are kept for auditing current state against full history.
+CREATE TABLE token_current_states (
+    token_id char(96) PRIMARY KEY references tokens(token_id),
+    append_only_id uuid NOT NULL references token_state_append_only(id),
+    status TEXT NOT NULL,
+    state_data_json JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE asset_current_states (
+    asset_id char(64) PRIMARY KEY,
+    append_only_id uuid NOT NULL references asset_state_append_only(id),
+    status TEXT NOT NULL,
+    state_data_json JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE FUNCTION set_token_current_state()
+RETURNS trigger AS $$
+BEGIN
+  INSERT INTO token_current_states (token_id, append_only_id, status, state_data_json, created_at)
+  VALUES (NEW.token_id, NEW.id, NEW.status, NEW.state_data_json, NEW.created_at)
+  ON CONFLICT (token_id) DO UPDATE SET
+    append_only_id = EXCLUDED.append_only_id,
+    status = EXCLUDED.status,
+    state_data_json = EXCLUDED.state_data_json,
+    created_at = EXCLUDED.created_at
+  WHERE token_current_states.created_at <= EXCLUDED.created_at;
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
+
+CREATE FUNCTION set_asset_current_state()
+RETURNS trigger AS $$
+BEGIN
+  INSERT INTO asset_current_states (asset_id, append_only_id, status, state_data_json, created_at)
+  VALUES (NEW.asset_id, NEW.id, NEW.status, NEW.state_data_json, NEW.created_at)
+  ON CONFLICT (asset_id) DO UPDATE SET
+    append_only_id = EXCLUDED.append_only_id,
+    status = EXCLUDED.status,
+    state_data_json = EXCLUDED.state_data_json,
+    created_at = EXCLUDED.created_at
+  WHERE asset_current_states.created_at <= EXCLUDED.created_at;
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
+
+CREATE TRIGGER set_token_current_state_trigger
+AFTER INSERT ON token_state_append_only
+FOR EACH ROW
+EXECUTE PROCEDURE set_token_current_state();
+
+CREATE TRIGGER set_asset_current_state_trigger
+AFTER INSERT ON asset_state_append_only
+FOR EACH ROW
+EXECUTE PROCEDURE set_asset_current_state();
+
+-- Backfill from existing history
+INSERT INTO token_current_states (token_id, append_only_id, status, state_data_json, created_at)
+SELECT DISTINCT ON(token_id) token_id, id, status, state_data_json, created_at
+FROM token_state_append_only
+ORDER BY token_id, created_at DESC;
+
+INSERT INTO asset_current_states (asset_id, append_only_id, status, state_data_json, created_at)
+SELECT DISTINCT ON(asset_id) asset_id, id, status, state_data_json, created_at
+FROM asset_state_append_only
+ORDER BY asset_id, created_at DESC;
+
+CREATE OR REPLACE VIEW tokens_current_view AS
+SELECT
+    t.*,
+    COALESCE(tcs.state_data_json, t.initial_data_json) as additional_data_json,
+    COALESCE(tcs.status, 'Available') as status
+FROM
+  tokens t
+LEFT JOIN token_current_states tcs
+ON
+    t.token_id = tcs.token_id;
+
+CREATE OR REPLACE VIEW asset_states_current_view AS
+SELECT
+    ast.*,
+    COALESCE(acs.state_data_json, ast.initial_data_json) as additional_data_json,
+    COALESCE(acs.status, 'Active') as status
+FROM
+  asset_states ast
+LEFT JOIN asset_current_states acs
+ON
+    ast.asset_id = acs.asset_id;
@@ -0,0 +1,18 @@
+-- Compacted history of append only state, see `tvnc prune`.
+-- Every snapshot rolls up consecutive pruned append only records of a token or asset:
+-- latest pruned state is kept, while checkpoint_hash chains hashes of all records pruned so far.
+CREATE TABLE state_snapshots (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    kind TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    rows_count BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    state_data_json JSONB NOT NULL,
+    checkpoint_hash TEXT NOT NULL,
+    first_created_at TIMESTAMPTZ NOT NULL,
+    last_created_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_state_snapshots_kind_entity_id_last_created_at ON state_snapshots (kind, entity_id, last_created_at);
@@ -0,0 +1,9 @@
+-- Contract switches set by operators via admin API, override template.contracts.<name>.enabled config
+CREATE TABLE contract_switches (
+                       template_id BIGINT NOT NULL,
+                       contract_name TEXT NOT NULL,
+                       enabled BOOLEAN NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       PRIMARY KEY (template_id, contract_name)
+);
@@ -0,0 +1,5 @@
+-- Wallet key rotation: new wallet references the wallet it replaces, replaced wallet is marked as rotated
+ALTER TABLE wallet ADD COLUMN rotated_from uuid NULL REFERENCES wallet (id);
+ALTER TABLE wallet ADD COLUMN rotated_at TIMESTAMPTZ NULL;
+
+CREATE INDEX index_wallet_rotated_from ON wallet (rotated_from);
@@ -0,0 +1,6 @@
+-- Temporary wallets created by instructions are swept into node's main wallet and retired once instruction completes
+ALTER TABLE wallet ADD COLUMN instruction_id "InstructionID" NULL REFERENCES instructions (id);
+ALTER TABLE wallet ADD COLUMN retired_at TIMESTAMPTZ NULL;
+ALTER TABLE wallet ADD COLUMN keys_removed_at TIMESTAMPTZ NULL;
+
+CREATE INDEX index_wallet_instruction_id ON wallet (instruction_id);
@@ -0,0 +1,30 @@
+-- Webhooks notified about asset instructions and proposals, registered per asset by node operator
+CREATE TABLE webhooks (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       asset_id char(64) NOT NULL references asset_states(asset_id),
+                       url TEXT NOT NULL,
+                       secret TEXT NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       UNIQUE (asset_id, url)
+);
+
+-- Delivery log: every event is enqueued within the transaction changing the state and retried until delivered
+CREATE TABLE webhook_deliveries (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       webhook_id uuid NOT NULL references webhooks(id) ON DELETE CASCADE,
+                       event TEXT NOT NULL,
+                       payload JSONB NOT NULL,
+                       status TEXT NOT NULL DEFAULT 'Pending',
+                       attempts INTEGER NOT NULL DEFAULT 0,
+                       last_error TEXT NULL,
+                       next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       delivered_at TIMESTAMPTZ NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_webhooks_asset_id ON webhooks (asset_id);
+CREATE INDEX index_webhook_deliveries_pending ON webhook_deliveries (next_attempt_at) WHERE status = 'Pending';
+CREATE INDEX index_webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id);
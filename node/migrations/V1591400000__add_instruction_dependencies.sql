@@ -0,0 +1,2 @@
+-- Instructions which have to be committed before the instruction is executed, see Instruction::depends_on
+ALTER TABLE instructions ADD COLUMN depends_on UUID[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+-- Token search by current owner, see Token::search
+-- owner is taken from the current state falling back to initial data, so both are indexed
+CREATE INDEX index_token_current_states_owner_pubkey ON token_current_states ((state_data_json->>'owner_pubkey'));
+CREATE INDEX index_tokens_owner_pubkey ON tokens ((initial_data_json->>'owner_pubkey'));
+CREATE INDEX index_token_current_states_created_at ON token_current_states (created_at);
@@ -0,0 +1,60 @@
+-- First-class token ownership, see Token::owner_pubkey
+-- tokens.owner_pubkey is the initial owner, append only state owner falls back to it if state data has no owner,
+-- both are set by write queries from `owner_pubkey` field of token data
+ALTER TABLE tokens ADD COLUMN owner_pubkey TEXT;
+ALTER TABLE token_state_append_only ADD COLUMN owner_pubkey TEXT;
+ALTER TABLE token_current_states ADD COLUMN owner_pubkey TEXT;
+
+-- Backfill from token data
+UPDATE tokens SET owner_pubkey = initial_data_json->>'owner_pubkey';
+
+UPDATE token_state_append_only tsao
+SET owner_pubkey = COALESCE(tsao.state_data_json->>'owner_pubkey', t.owner_pubkey)
+FROM tokens t
+WHERE t.token_id = tsao.token_id;
+
+UPDATE token_current_states tcs
+SET owner_pubkey = tsao.owner_pubkey
+FROM token_state_append_only tsao
+WHERE tsao.id = tcs.append_only_id;
+
+CREATE OR REPLACE FUNCTION set_token_current_state()
+RETURNS trigger AS $$
+BEGIN
+  INSERT INTO token_current_states (token_id, append_only_id, status, state_data_json, owner_pubkey, created_at)
+  VALUES (NEW.token_id, NEW.id, NEW.status, NEW.state_data_json, NEW.owner_pubkey, NEW.created_at)
+  ON CONFLICT (token_id) DO UPDATE SET
+    append_only_id = EXCLUDED.append_only_id,
+    status = EXCLUDED.status,
+    state_data_json = EXCLUDED.state_data_json,
+    owner_pubkey = EXCLUDED.owner_pubkey,
+    created_at = EXCLUDED.created_at
+  WHERE token_current_states.created_at <= EXCLUDED.created_at;
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
+
+-- tokens columns are listed explicitly, as t.* would include initial owner now
+CREATE OR REPLACE VIEW tokens_current_view AS
+SELECT
+    t.id,
+    t.issue_number,
+    t.token_id,
+    t.asset_state_id,
+    t.initial_data_json,
+    t.created_at,
+    t.updated_at,
+    COALESCE(tcs.state_data_json, t.initial_data_json) as additional_data_json,
+    COALESCE(tcs.status, 'Available') as status,
+    COALESCE(tcs.owner_pubkey, t.owner_pubkey) as owner_pubkey
+FROM
+  tokens t
+LEFT JOIN token_current_states tcs
+ON
+    t.token_id = tcs.token_id;
+
+-- Replace JSON expression indexes of token search with column ones
+DROP INDEX index_token_current_states_owner_pubkey;
+DROP INDEX index_tokens_owner_pubkey;
+CREATE INDEX index_token_current_states_owner_pubkey ON token_current_states (owner_pubkey);
+CREATE INDEX index_tokens_owner_pubkey ON tokens (owner_pubkey);
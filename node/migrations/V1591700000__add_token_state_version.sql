@@ -0,0 +1,82 @@
+-- Optimistic locking of token state, see Token::update
+-- Every append only state gets the next version of the token, unique constraint rejects concurrent writers
+-- which appended state on top of the same version
+ALTER TABLE token_state_append_only ADD COLUMN version BIGINT;
+ALTER TABLE token_current_states ADD COLUMN version BIGINT NOT NULL DEFAULT 0;
+
+UPDATE token_state_append_only tsao
+SET version = numbered.version
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY token_id ORDER BY created_at) AS version
+    FROM token_state_append_only
+) numbered
+WHERE numbered.id = tsao.id;
+
+UPDATE token_current_states tcs
+SET version = tsao.version
+FROM token_state_append_only tsao
+WHERE tsao.id = tcs.append_only_id;
+
+ALTER TABLE token_state_append_only ALTER COLUMN version SET NOT NULL;
+ALTER TABLE token_state_append_only ADD CONSTRAINT token_state_append_only_token_id_version UNIQUE (token_id, version);
+
+CREATE OR REPLACE FUNCTION set_token_current_state()
+RETURNS trigger AS $$
+BEGIN
+  INSERT INTO token_current_states (token_id, append_only_id, status, state_data_json, owner_pubkey, version, created_at)
+  VALUES (NEW.token_id, NEW.id, NEW.status, NEW.state_data_json, NEW.owner_pubkey, NEW.version, NEW.created_at)
+  ON CONFLICT (token_id) DO UPDATE SET
+    append_only_id = EXCLUDED.append_only_id,
+    status = EXCLUDED.status,
+    state_data_json = EXCLUDED.state_data_json,
+    owner_pubkey = EXCLUDED.owner_pubkey,
+    version = EXCLUDED.version,
+    created_at = EXCLUDED.created_at
+  WHERE token_current_states.created_at <= EXCLUDED.created_at;
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
+
+CREATE OR REPLACE VIEW tokens_current_view AS
+SELECT
+    t.id,
+    t.issue_number,
+    t.token_id,
+    t.asset_state_id,
+    t.initial_data_json,
+    t.created_at,
+    t.updated_at,
+    COALESCE(tcs.state_data_json, t.initial_data_json) as additional_data_json,
+    COALESCE(tcs.status, 'Available') as status,
+    COALESCE(tcs.owner_pubkey, t.owner_pubkey) as owner_pubkey,
+    COALESCE(tcs.version, 0) as version
+FROM
+  tokens t
+LEFT JOIN token_current_states tcs
+ON
+    t.token_id = tcs.token_id;
+
+-- Auditing view mirrors columns of tokens_current_view
+CREATE OR REPLACE VIEW tokens_view AS
+SELECT
+    t.id,
+    t.issue_number,
+    t.token_id,
+    t.asset_state_id,
+    t.initial_data_json,
+    t.created_at,
+    t.updated_at,
+    COALESCE(tsao.state_data_json, t.initial_data_json) as additional_data_json,
+    COALESCE(tsao.status,'Available') as status,
+    COALESCE(tsao.owner_pubkey, t.owner_pubkey) as owner_pubkey,
+    COALESCE(tsao.version, 0) as version
+FROM
+  tokens t
+LEFT JOIN
+(
+    SELECT DISTINCT ON(tsao.token_id) tsao.*
+    FROM token_state_append_only AS tsao
+    ORDER BY tsao.token_id, tsao.created_at DESC
+) tsao
+ON
+    t.token_id = tsao.token_id;
@@ -0,0 +1,2 @@
+-- Token and asset state buffered by contract execution, persisted by consensus when instruction is committed
+ALTER TABLE instructions ADD COLUMN append_only_state JSONB;
@@ -0,0 +1,4 @@
+-- Optional per signer sequence of instructions, see TemplateContext::create_instruction
+ALTER TABLE instructions ADD COLUMN signer_pubkey TEXT;
+ALTER TABLE instructions ADD COLUMN nonce BIGINT;
+CREATE UNIQUE INDEX index_instructions_signer_pubkey_nonce ON instructions (signer_pubkey, nonce) WHERE nonce IS NOT NULL;
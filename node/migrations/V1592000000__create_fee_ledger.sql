@@ -0,0 +1,17 @@
+-- Fees charged for committed instructions, at most one entry per instruction
+CREATE TABLE fee_ledger (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       instruction_id uuid NOT NULL UNIQUE references instructions(id),
+                       asset_id char(64) NOT NULL references asset_states(asset_id),
+                       asset_issuer_pub_key TEXT NOT NULL,
+                       payer_pubkey TEXT NULL,
+                       template_id BIGINT NOT NULL,
+                       contract_name TEXT NOT NULL,
+                       amount BIGINT NOT NULL,
+                       -- 'Charged' if payer wallet was debited, 'Unpaid' if payer has no wallet or not enough balance
+                       status TEXT NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_fee_ledger_asset_issuer_pub_key ON fee_ledger (asset_issuer_pub_key);
@@ -0,0 +1,8 @@
+-- Committee nodes selected on asset registration according to committee mode of the digital asset
+CREATE TABLE committee_members (
+                       asset_id char(64) NOT NULL references asset_states(asset_id),
+                       node_id TEXT NOT NULL,
+                       collateral BIGINT NOT NULL DEFAULT 0,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       PRIMARY KEY (asset_id, node_id)
+);
@@ -0,0 +1,3 @@
+-- Result of RAID TXT record verification of asset domain, see raid module
+ALTER TABLE digital_assets ADD COLUMN raid_status TEXT NOT NULL DEFAULT 'Unverified';
+ALTER TABLE digital_assets ADD COLUMN raid_verified_at TIMESTAMPTZ NULL;
@@ -0,0 +1,21 @@
+-- Mutations initiated via API, kept for compliance so records don't reference asset or instruction tables
+-- and survive asset wipes and retention
+CREATE TABLE audit_log (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       -- Pubkey which signed the request
+                       signer_pubkey TEXT NULL,
+                       -- Subject of the access token the request was authorized with
+                       access_pubkey TEXT NULL,
+                       action TEXT NOT NULL,
+                       asset_id char(64) NULL,
+                       instruction_id uuid NULL,
+                       template_id BIGINT NULL,
+                       contract_name TEXT NULL,
+                       -- hex SHA256 of request params JSON
+                       params_hash TEXT NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_audit_log_created_at ON audit_log (created_at);
+CREATE INDEX index_audit_log_asset_id ON audit_log (asset_id);
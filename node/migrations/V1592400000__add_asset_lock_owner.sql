@@ -0,0 +1,4 @@
+-- Owner of asset lock and its last heartbeat, locks of owners which stopped heartbeating are reclaimed,
+-- see consensus::locks
+ALTER TABLE asset_states ADD COLUMN locked_by TEXT NULL;
+ALTER TABLE asset_states ADD COLUMN lock_heartbeat_at TIMESTAMPTZ NULL;
@@ -0,0 +1,15 @@
+-- Misbehavior of committee members observed by the node, groundwork for slashing and committee eviction
+CREATE TABLE node_reputation (
+                       -- NodeID hex, same as committee_members.node_id
+                       node_id TEXT PRIMARY KEY NOT NULL,
+                       invalid_proposals BIGINT NOT NULL DEFAULT 0,
+                       bad_signatures BIGINT NOT NULL DEFAULT 0,
+                       missed_rounds BIGINT NOT NULL DEFAULT 0,
+                       -- Starts at 100 and is decreased by penalty of every misbehavior down to 0
+                       score BIGINT NOT NULL DEFAULT 100,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_node_reputation_score ON node_reputation (score);
@@ -0,0 +1,2 @@
+-- Client priority of the instruction, higher is picked into consensus round first with `priority` mempool ordering
+ALTER TABLE instructions ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Access records stop authorizing requests after expires_at, NULL never expires.
+-- Revoked records stay in the table, so requests with leaked keys are rejected with a distinct error
+ALTER TABLE access ADD COLUMN expires_at TIMESTAMPTZ NULL;
+ALTER TABLE access ADD COLUMN revoked_at TIMESTAMPTZ NULL;
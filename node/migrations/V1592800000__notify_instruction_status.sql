@@ -0,0 +1,14 @@
+-- Notifies listeners of the instruction_status channel with id of inserted instruction or instruction changing status,
+-- used by tests to await instruction processing without polling
+CREATE OR REPLACE FUNCTION notify_instruction_status()
+RETURNS trigger AS $$
+BEGIN
+  PERFORM pg_notify('instruction_status', NEW.id::text);
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
+
+CREATE TRIGGER notify_instruction_status_trigger
+AFTER INSERT OR UPDATE OF status ON instructions
+FOR EACH ROW
+EXECUTE PROCEDURE notify_instruction_status();
@@ -0,0 +1,13 @@
+-- Instruction notifications carry status along with template and asset, see db::notify::InstructionNotification
+CREATE OR REPLACE FUNCTION notify_instruction_status()
+RETURNS trigger AS $$
+BEGIN
+  PERFORM pg_notify('instruction_status', json_build_object(
+    'id', NEW.id,
+    'template_id', NEW.template_id,
+    'asset_id', NEW.asset_id,
+    'status', NEW.status
+  )::text);
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
@@ -0,0 +1,15 @@
+-- Upper bound of tokens issued for the asset, enforced along with limit_per_wallet by token issuance
+ALTER TABLE asset_states ADD COLUMN max_supply OID NULL;
+
+-- ast.* is expanded on view creation, so the view is recreated to pick the new column up
+DROP VIEW asset_states_current_view;
+CREATE VIEW asset_states_current_view AS
+SELECT
+    ast.*,
+    COALESCE(acs.state_data_json, ast.initial_data_json) as additional_data_json,
+    COALESCE(acs.status, 'Active') as status
+FROM
+  asset_states ast
+LEFT JOIN asset_current_states acs
+ON
+    ast.asset_id = acs.asset_id;
@@ -0,0 +1,34 @@
+-- Append only asset state links to the state replacing it, so versions of asset metadata can be followed
+ALTER TABLE asset_state_append_only ADD COLUMN superseded_by UUID NULL
+    REFERENCES asset_state_append_only(id) ON DELETE SET NULL;
+CREATE INDEX index_asset_state_append_only_superseded_by ON asset_state_append_only (superseded_by);
+
+-- Backfill from existing history
+UPDATE asset_state_append_only asao
+SET superseded_by = versions.next_id
+FROM (
+    SELECT id, LEAD(id) OVER (PARTITION BY asset_id ORDER BY created_at) AS next_id
+    FROM asset_state_append_only
+) versions
+WHERE versions.id = asao.id AND versions.next_id IS NOT NULL;
+
+-- State replacing the current one supersedes it, states replicated out of order are left unlinked
+CREATE OR REPLACE FUNCTION set_asset_current_state()
+RETURNS trigger AS $$
+BEGIN
+  UPDATE asset_state_append_only SET superseded_by = NEW.id
+  WHERE id = (
+    SELECT append_only_id FROM asset_current_states
+    WHERE asset_id = NEW.asset_id AND created_at <= NEW.created_at
+  );
+  INSERT INTO asset_current_states (asset_id, append_only_id, status, state_data_json, created_at)
+  VALUES (NEW.asset_id, NEW.id, NEW.status, NEW.state_data_json, NEW.created_at)
+  ON CONFLICT (asset_id) DO UPDATE SET
+    append_only_id = EXCLUDED.append_only_id,
+    status = EXCLUDED.status,
+    state_data_json = EXCLUDED.state_data_json,
+    created_at = EXCLUDED.created_at
+  WHERE asset_current_states.created_at <= EXCLUDED.created_at;
+  RETURN NULL;
+END
+$$ LANGUAGE 'plpgsql';
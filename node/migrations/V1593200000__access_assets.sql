@@ -0,0 +1,3 @@
+-- Assets API access is restricted to, so a node serves several independent issuers with scoped keys.
+-- Empty array grants access to any asset, templates and contracts are still restricted by scopes
+ALTER TABLE access ADD COLUMN assets TEXT[] NOT NULL DEFAULT '{}';
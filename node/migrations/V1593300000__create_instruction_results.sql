@@ -0,0 +1,16 @@
+-- Complete results of instructions exceeding `template.max_result_size`, instructions keep truncated result
+-- referencing the complete one by result_ref, so instructions table is not bloated by multi-megabyte rows
+CREATE TABLE instruction_results (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       instruction_id "InstructionID" NOT NULL REFERENCES instructions(id) ON DELETE CASCADE,
+                       result JSONB NOT NULL,
+                       -- Size of serialized result in bytes
+                       size BIGINT NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_instruction_results_instruction_id ON instruction_results (instruction_id);
+
+-- Not a foreign key, complete results are removed along with their instruction
+ALTER TABLE instructions ADD COLUMN result_ref uuid NULL;
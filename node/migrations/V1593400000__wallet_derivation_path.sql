@@ -0,0 +1,3 @@
+-- Temporary wallets with keys derived from the node master key, keys are re-derived along the path instead of
+-- being stored in key files, see wallet::DerivationPath
+ALTER TABLE wallet ADD COLUMN derivation_path TEXT NULL;
@@ -0,0 +1,27 @@
+-- Validator processes sharing the DB as one logical node, assets are partitioned over live workers,
+-- see consensus::cluster
+CREATE TABLE workers (
+                       id TEXT PRIMARY KEY NOT NULL,
+                       -- NodeID hex of the logical node
+                       node_id TEXT NOT NULL,
+                       heartbeat_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       -- Worker leaves the partitioning when it stops heartbeating past this time
+                       expires_at TIMESTAMPTZ NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_workers_expires_at ON workers (expires_at);
+
+-- Rendezvous hashing of assets over live workers: the worker with the highest hash of (worker id, asset id) owns
+-- the asset, so only assets of a joining or leaving worker move to other workers.
+-- NULL worker (clustering disabled) or no live workers at all own every asset
+CREATE FUNCTION worker_owns_asset(worker TEXT, asset TEXT) RETURNS BOOLEAN AS $$
+    SELECT worker IS NULL OR COALESCE((
+        SELECT w.id = worker
+        FROM workers w
+        WHERE w.expires_at > now()
+        ORDER BY md5(w.id || asset) DESC, w.id
+        LIMIT 1
+    ), TRUE)
+$$ LANGUAGE SQL STABLE;
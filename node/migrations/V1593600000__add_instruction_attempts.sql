@@ -0,0 +1,2 @@
+-- Failed executions of the instruction, counted by the template retry policy
+ALTER TABLE instructions ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+-- Partial progress reported by running contracts, entries are appended without changing instruction status
+CREATE TABLE instruction_progress (
+                       id BIGSERIAL PRIMARY KEY,
+                       instruction_id "InstructionID" NOT NULL REFERENCES instructions(id) ON DELETE CASCADE,
+                       data JSONB NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_instruction_progress_instruction_id ON instruction_progress (instruction_id, id);
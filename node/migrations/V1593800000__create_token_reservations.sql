@@ -0,0 +1,13 @@
+-- Tokens reserved by running instructions for the duration of multi-step flows, at most one reservation per token,
+-- expired reservations are ignored and taken over by the next instruction reserving the token
+CREATE TABLE token_reservations (
+                       id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+                       -- Not a foreign key, tokens issued within running transaction can be reserved too
+                       token_id char(96) NOT NULL UNIQUE,
+                       instruction_id "InstructionID" NOT NULL REFERENCES instructions(id) ON DELETE CASCADE,
+                       expires_at TIMESTAMPTZ NOT NULL,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Indices
+CREATE INDEX index_token_reservations_instruction_id ON token_reservations (instruction_id);
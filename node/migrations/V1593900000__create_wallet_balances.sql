@@ -0,0 +1,9 @@
+-- Wallet balances in currencies other than native XTR kept in wallet.balance, currency is an arbitrary code,
+-- e.g. asset ID of tokens paid with in token-for-token swaps
+CREATE TABLE wallet_balances (
+                       wallet_id uuid NOT NULL REFERENCES wallet(id) ON DELETE CASCADE,
+                       currency TEXT NOT NULL,
+                       balance BIGINT NOT NULL DEFAULT 0,
+                       updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       PRIMARY KEY (wallet_id, currency)
+);
@@ -0,0 +1,2 @@
+-- Position of the call in its batch, calls of a batch share the signature their content IDs are derived from
+ALTER TABLE instructions ADD COLUMN batch_index INTEGER;
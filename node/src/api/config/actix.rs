@@ -1,17 +1,22 @@
+use super::TlsConfig;
 use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
 
 pub const DEFAULT_PORT: u16 = 3001;
 pub const DEFAULT_ADDR: Ipv4Addr = Ipv4Addr::LOCALHOST;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ActixConfig {
+    #[schemars(with = "String")]
     pub host: IpAddr,
     pub port: u16,
     pub workers: Option<usize>,
     pub backlog: Option<usize>,
     pub maxconn: Option<usize>,
+    /// HTTPS with HTTP/2 instead of plain HTTP, see [TlsConfig]
+    pub tls: Option<TlsConfig>,
 }
 impl Default for ActixConfig {
     fn default() -> Self {
@@ -21,6 +26,7 @@ impl Default for ActixConfig {
             workers: None,
             backlog: None,
             maxconn: None,
+            tls: None,
         }
     }
 }
@@ -28,4 +34,12 @@ impl ActixConfig {
     pub fn addr(&self) -> impl ToSocketAddrs {
         (self.host, self.port)
     }
+
+    /// Port of the listener authenticating peers by client certificates, peer endpoints are served only on it
+    pub fn peer_port(&self) -> Option<u16> {
+        self.tls
+            .as_ref()
+            .filter(|tls| tls.client_ca_path.is_some())
+            .and_then(|tls| tls.peer_port)
+    }
 }
@@ -0,0 +1,24 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Default access token lifetime, 30 days
+pub const DEFAULT_TOKEN_EXPIRY_MINUTES: i64 = 60 * 24 * 30;
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AuthConfig {
+    /// Require access token on /asset_call and /token_call routes
+    pub enabled: bool,
+    /// Secret for signing access tokens, derived from node identity if not set
+    pub secret: Option<String>,
+    /// Lifetime of access tokens issued via `tvnc access grant`
+    pub token_expiry_minutes: i64,
+}
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            secret: None,
+            token_expiry_minutes: DEFAULT_TOKEN_EXPIRY_MINUTES,
+        }
+    }
+}
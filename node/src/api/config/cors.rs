@@ -1,13 +1,113 @@
-use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// CORS policy of the API server
+///
+/// Lists accept TOML arrays as well as comma separated strings, so they can be set via CORS_* env vars
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CorsConfig {
-    pub allowed_origins: String,
+    /// `*`, exact origins `https://app.tari.com` or wildcard subdomains `https://*.tari.com`
+    #[serde(deserialize_with = "string_or_list")]
+    #[schemars(with = "Vec<String>")]
+    pub allowed_origins: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
+    #[schemars(with = "Vec<String>")]
+    pub allowed_methods: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
+    #[schemars(with = "Vec<String>")]
+    pub allowed_headers: Vec<String>,
+    /// Seconds browser is allowed to cache preflight response
+    pub max_age: Option<usize>,
 }
 impl Default for CorsConfig {
     fn default() -> Self {
         Self {
-            allowed_origins: "*".to_string(),
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .iter()
+                .map(|method| method.to_string())
+                .collect(),
+            allowed_headers: [
+                "Authorization",
+                "Accept",
+                "Content-Type",
+                "X-API-Client-Version",
+                "X-Signer-Pubkey",
+                "X-Signature",
+            ]
+            .iter()
+            .map(|header| header.to_string())
+            .collect(),
+            max_age: Some(3600),
         }
     }
 }
+
+impl CorsConfig {
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+
+    /// Checks request Origin against allowed origins, wildcard `*.` matches any depth of subdomains
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        let origin = origin.to_lowercase();
+        self.allowed_origins.iter().any(|allowed| {
+            let allowed = allowed.to_lowercase();
+            if allowed == "*" || allowed == origin {
+                return true;
+            }
+            match allowed.find("://*.") {
+                Some(pos) => {
+                    let (scheme, domain) = (&allowed[..pos + 3], &allowed[pos + 4..]);
+                    origin.starts_with(scheme) &&
+                        origin.ends_with(domain) &&
+                        origin.len() > scheme.len() + domain.len()
+                },
+                None => false,
+            }
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+fn string_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where D: Deserializer<'de> {
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::String(value) => value
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        StringOrList::List(list) => list,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allows_origin() {
+        let config = CorsConfig {
+            allowed_origins: vec!["https://app.tari.com".into(), "https://*.dapps.io".into()],
+            ..CorsConfig::default()
+        };
+        assert!(!config.allows_any_origin());
+        assert!(config.allows_origin("https://app.tari.com"));
+        assert!(config.allows_origin("https://App.Tari.com"));
+        assert!(config.allows_origin("https://wallet.dapps.io"));
+        assert!(config.allows_origin("https://a.b.dapps.io"));
+        assert!(!config.allows_origin("https://dapps.io"));
+        assert!(!config.allows_origin("https://.dapps.io"));
+        assert!(!config.allows_origin("http://wallet.dapps.io"));
+        assert!(!config.allows_origin("https://evildapps.io"));
+        assert!(!config.allows_origin("https://www.tari.com"));
+        assert!(CorsConfig::default().allows_origin("https://www.tari.com"));
+    }
+}
@@ -1,4 +1,13 @@
-pub use self::{actix::ActixConfig, cors::CorsConfig};
+pub use self::{
+    actix::ActixConfig,
+    auth::AuthConfig,
+    cors::CorsConfig,
+    payload::{check_json, PayloadConfig},
+    tls::{TlsConfig, TlsError},
+};
 
 pub(crate) mod actix;
+pub(crate) mod auth;
 pub(crate) mod cors;
+pub(crate) mod payload;
+pub(crate) mod tls;
@@ -0,0 +1,123 @@
+use crate::{
+    api::errors::{ApiError, ApplicationError},
+    db::utils::validation::ValidationErrors,
+};
+use actix_web::{
+    error::JsonPayloadError,
+    web::{self, Data},
+    HttpRequest,
+};
+use bytes::BytesMut;
+use futures::StreamExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Limits of JSON request bodies, oversized bodies are rejected with 413 `payload_too_large`,
+/// too deep JSON or too long arrays with 422 `validation` error of the `body` field
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PayloadConfig {
+    /// Bytes of request body, lowered per contract by `[validator.template.contracts.<name>] max_body_bytes`
+    pub max_body_bytes: usize,
+    /// Nesting levels of objects and arrays
+    pub max_json_depth: usize,
+    /// Items of a single array, e.g. `token_ids`
+    pub max_array_len: usize,
+}
+impl Default for PayloadConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 1024 * 1024,
+            max_json_depth: 32,
+            max_array_len: 10_000,
+        }
+    }
+}
+
+impl PayloadConfig {
+    /// Config of [web::Json] extractors of the app
+    pub fn json_config(&self) -> web::JsonConfig {
+        web::JsonConfig::default()
+            .limit(self.max_body_bytes)
+            .error_handler(|err, _| match err {
+                JsonPayloadError::Overflow => payload_too_large().into(),
+                err => ApiError::from(ApplicationError::bad_request(&format!("Json error: {}", err))).into(),
+            })
+    }
+
+    /// Reads JSON body of the request up to `max_body_bytes` and guards it with [PayloadConfig::check_json]
+    pub async fn read_json(&self, mut body: web::Payload) -> Result<Value, ApiError> {
+        let mut bytes = BytesMut::new();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|err| ApplicationError::bad_request(&format!("Payload error: {}", err)))?;
+            if bytes.len() + chunk.len() > self.max_body_bytes {
+                return Err(payload_too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        let value = serde_json::from_slice(&bytes)
+            .map_err(|err| ApplicationError::bad_request(&format!("Json error: {}", err)))?;
+        self.check_json(&value)?;
+        Ok(value)
+    }
+
+    /// Rejects JSON nested deeper than `max_json_depth` or with arrays longer than `max_array_len`
+    pub fn check_json(&self, value: &Value) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Some((code, message)) = self.violation(value, 1) {
+            errors.append_validation_error(code, "body", message);
+        }
+        errors.validate()
+    }
+
+    /// First violated limit as validation error code and message
+    fn violation(&self, value: &Value, depth: usize) -> Option<(&'static str, &'static str)> {
+        let children: Vec<&Value> = match value {
+            Value::Array(items) if items.len() > self.max_array_len => {
+                return Some(("array_len", "Array in request body exceeds max_array_len items"));
+            },
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(map) => map.values().collect(),
+            _ => return None,
+        };
+        if depth > self.max_json_depth {
+            return Some(("json_depth", "Request body is nested deeper than max_json_depth levels"));
+        }
+        children.into_iter().find_map(|child| self.violation(child, depth + 1))
+    }
+}
+
+/// Guards JSON body with [PayloadConfig] of the app, defaults are used if the app has none
+pub fn check_json(req: &HttpRequest, value: &Value) -> Result<(), ApiError> {
+    match req.app_data::<Data<PayloadConfig>>() {
+        Some(config) => config.check_json(value)?,
+        None => PayloadConfig::default().check_json(value)?,
+    }
+    Ok(())
+}
+
+fn payload_too_large() -> ApiError {
+    ApplicationError::payload_too_large("Request body is too large").into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json_guards() {
+        let config = PayloadConfig {
+            max_json_depth: 3,
+            max_array_len: 2,
+            ..PayloadConfig::default()
+        };
+        assert!(config.check_json(&json!({ "token_ids": ["a", "b"], "data": { "x": [1] } })).is_ok());
+        assert!(config.check_json(&json!("scalar")).is_ok());
+
+        let code = |value: Value| config.check_json(&value).unwrap_err().0["body"][0].code.clone();
+        assert_eq!(code(json!({ "token_ids": ["a", "b", "c"] })), "array_len");
+        assert_eq!(code(json!({ "data": { "x": [{ "y": 1 }] } })), "json_depth");
+        assert_eq!(code(json!([[[[]]]])), "json_depth");
+    }
+}
@@ -0,0 +1,175 @@
+//! HTTPS termination of the node API, HTTP/2 is negotiated via ALPN along with HTTP/1.1
+//!
+//! Committee peers fetching state batches (see [crate::sync]) can be required to authenticate with client
+//! certificates signed by `client_ca_path`: peer endpoints are then served only on `peer_port`,
+//! which rejects connections without a valid client certificate:
+//! ```toml
+//! [validator.actix.tls]
+//! cert_path = "/etc/tvnc/node.crt"
+//! key_path = "/etc/tvnc/node.key"
+//! client_ca_path = "/etc/tvnc/committee-ca.crt"
+//! peer_port = 3443
+//! ```
+//! Sync client presents `cert_path` to peers and trusts `client_ca_path` in addition to public roots.
+
+use rustls::{
+    internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
+    AllowAnyAuthenticatedClient,
+    Certificate,
+    ClientConfig,
+    NoClientAuth,
+    PrivateKey,
+    RootCertStore,
+    ServerConfig,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read {path}: {source}")]
+    IO { path: String, source: std::io::Error },
+    #[error("Invalid PEM in {path}: {reason}")]
+    Pem { path: String, reason: &'static str },
+    #[error("TLS configuration error: {0}")]
+    Config(#[from] rustls::TLSError),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TlsConfig {
+    /// PEM certificate chain of the node, leaf certificate first
+    pub cert_path: PathBuf,
+    /// PEM private key of the certificate, PKCS#8 or RSA
+    pub key_path: PathBuf,
+    /// PEM CA certificates signing client certificates of committee peers
+    pub client_ca_path: Option<PathBuf>,
+    /// Port serving peer endpoints to clients with certificates signed by `client_ca_path`
+    pub peer_port: Option<u16>,
+}
+
+impl TlsConfig {
+    /// Server config of the API listener, client certificates are not requested
+    pub fn server_config(&self) -> Result<ServerConfig, TlsError> {
+        let mut config = ServerConfig::new(NoClientAuth::new());
+        config.set_single_cert(self.certs()?, self.private_key()?)?;
+        Ok(config)
+    }
+
+    /// Server config of the peer listener, connections without client certificate signed by `client_ca_path`
+    /// are rejected during handshake
+    pub fn peer_server_config(&self) -> Result<ServerConfig, TlsError> {
+        let mut config = ServerConfig::new(AllowAnyAuthenticatedClient::new(self.client_roots()?));
+        config.set_single_cert(self.certs()?, self.private_key()?)?;
+        Ok(config)
+    }
+
+    /// Client config of requests to committee peers, authenticates with the node certificate
+    pub fn client_config(&self) -> Result<Arc<ClientConfig>, TlsError> {
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        if self.client_ca_path.is_some() {
+            for root in self.client_roots()?.roots {
+                config.root_store.roots.push(root);
+            }
+        }
+        config.set_single_client_cert(self.certs()?, self.private_key()?);
+        Ok(Arc::new(config))
+    }
+
+    fn certs(&self) -> Result<Vec<Certificate>, TlsError> {
+        let certs = certs(&mut open(&self.cert_path)?).map_err(|_| pem_error(&self.cert_path, "malformed"))?;
+        if certs.is_empty() {
+            return Err(pem_error(&self.cert_path, "no certificates found"));
+        }
+        Ok(certs)
+    }
+
+    fn private_key(&self) -> Result<PrivateKey, TlsError> {
+        let malformed = |_| pem_error(&self.key_path, "malformed");
+        let mut keys = pkcs8_private_keys(&mut open(&self.key_path)?).map_err(malformed)?;
+        if keys.is_empty() {
+            keys = rsa_private_keys(&mut open(&self.key_path)?).map_err(malformed)?;
+        }
+        keys.into_iter()
+            .next()
+            .ok_or_else(|| pem_error(&self.key_path, "no private key found"))
+    }
+
+    fn client_roots(&self) -> Result<RootCertStore, TlsError> {
+        let path = match self.client_ca_path.as_ref() {
+            Some(path) => path,
+            None => return Err(pem_error(Path::new("client_ca_path"), "not configured")),
+        };
+        let mut roots = RootCertStore::empty();
+        match roots.add_pem_file(&mut open(path)?) {
+            Ok((added, _)) if added > 0 => Ok(roots),
+            _ => Err(pem_error(path, "no CA certificates found")),
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path).map(BufReader::new).map_err(|source| TlsError::IO {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn pem_error(path: &Path, reason: &'static str) -> TlsError {
+    TlsError::Pem {
+        path: path.display().to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::Test;
+    use tempdir::TempDir;
+
+    #[test]
+    fn load_tls_config() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let dir = Test::<TempDir>::get_path_buf().join(format!("tls-{}", rand::random::<u32>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("node.crt"), cert.serialize_pem().unwrap()).unwrap();
+        std::fs::write(dir.join("node.key"), cert.serialize_private_key_pem()).unwrap();
+        let config = TlsConfig {
+            cert_path: dir.join("node.crt"),
+            key_path: dir.join("node.key"),
+            client_ca_path: None,
+            peer_port: None,
+        };
+        assert!(config.server_config().is_ok());
+        assert!(config.client_config().is_ok());
+        assert!(matches!(config.peer_server_config(), Err(TlsError::Pem { .. })));
+
+        // self-signed node certificate is its own CA
+        let config = TlsConfig {
+            client_ca_path: Some(dir.join("node.crt")),
+            ..config
+        };
+        assert!(config.peer_server_config().is_ok());
+
+        let invalid = TlsConfig {
+            key_path: dir.join("node.crt"),
+            ..config.clone()
+        };
+        assert!(matches!(invalid.server_config(), Err(TlsError::Pem { .. })));
+        let missing = TlsConfig {
+            cert_path: dir.join("missing.crt"),
+            ..config
+        };
+        assert!(matches!(missing.server_config(), Err(TlsError::IO { .. })));
+    }
+}
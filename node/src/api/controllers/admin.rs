@@ -0,0 +1,304 @@
+use super::{status::Status, templates::find_template};
+use crate::{
+    api::{
+        config::ActixConfig,
+        errors::{ApiError, ApplicationError, AuthError},
+        middleware::RequestAuthenticationContext,
+        models::{MountedTemplate, NodeStatus, StartedAt},
+        LOG_TARGET,
+    },
+    db::{
+        models::{
+            consensus::{Instruction, Proposal},
+            wallet::Wallet,
+            AuditAction,
+            AuditLog,
+            ContractSwitch,
+            FeeLedger,
+            NewAuditLog,
+            NodeReputation,
+            SelectAuditLog,
+        },
+        utils::{db::ReadPool, errors::DBError},
+    },
+    metrics::{GetMetrics, Metrics, MetricsSnapshot},
+    sync::{export_batch, SyncError},
+    template::TemplateRoutes,
+    types::{AssetID, NodeID},
+    wallet::Signer,
+};
+use actix::Addr;
+use actix_web::{
+    web::{Data, Json, Path, Query},
+    HttpRequest,
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::HashMap, sync::Arc};
+
+const DASHBOARD_HTML: &'static str = include_str!("../../../static/admin_dashboard.html");
+const RECENT_LIMIT_DEFAULT: i64 = 50;
+const RECENT_LIMIT_MAX: i64 = 500;
+const SYNC_LIMIT_DEFAULT: i64 = 500;
+const SYNC_LIMIT_MAX: i64 = 5_000;
+const AUDIT_LIMIT_DEFAULT: i64 = 500;
+const AUDIT_LIMIT_MAX: i64 = 5_000;
+
+#[derive(Deserialize)]
+pub struct ContractSwitchParams {
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RecentParams {
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct FeesParams {
+    /// Asset issuer pubkey, fees of all issuers are returned if not set
+    pub issuer: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AuditParams {
+    /// Records created after this moment, export is paged by passing `created_at` of the last record
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub asset_id: Option<AssetID>,
+    /// Records signed or authorized by the pubkey
+    pub pubkey: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct SyncParams {
+    /// Records created after this moment, checkpoint is returned if not set
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl RecentParams {
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(RECENT_LIMIT_DEFAULT).max(1).min(RECENT_LIMIT_MAX)
+    }
+}
+
+/// Contract switches set for the template, contracts without switch follow template config
+pub async fn contracts(
+    template_id: Path<String>,
+    templates: Data<Vec<TemplateRoutes>>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let template = find_template(template_id.as_str(), &templates)?;
+    let client = db.get().await.map_err(DBError::from)?;
+    Ok(HttpResponse::Ok().json(ContractSwitch::find_by_template(&template.template_id, &client).await?))
+}
+
+/// Enables or disables contract, setting is stored and survives node restart
+pub async fn switch_contract(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    params: Json<ContractSwitchParams>,
+    templates: Data<Vec<TemplateRoutes>>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let (template_id, contract_name) = path.into_inner();
+    let template = find_template(template_id.as_str(), &templates)?;
+    if !template
+        .asset
+        .iter()
+        .chain(template.token.iter())
+        .any(|route| route.name == contract_name)
+    {
+        return Err(ApplicationError::bad_request(&format!("Unknown contract {}", contract_name)).into());
+    }
+    let client = db.get().await.map_err(DBError::from)?;
+    let switch = ContractSwitch::set(&template.template_id, &contract_name, params.enabled, &client).await?;
+    AuditLog::record(
+        NewAuditLog {
+            access_pubkey: req.access_pubkey(),
+            action: AuditAction::ContractSwitch,
+            template_id: Some(template.template_id),
+            contract_name: Some(contract_name.clone()),
+            params: json!({ "enabled": params.enabled }),
+            ..NewAuditLog::default()
+        },
+        &client,
+    )
+    .await?;
+    log::info!(
+        target: LOG_TARGET,
+        "Contract {} of template {} is {}",
+        contract_name,
+        template.template_id,
+        if switch.enabled { "enabled" } else { "disabled" }
+    );
+    Ok(HttpResponse::Ok().json(switch))
+}
+
+/// Static dashboard page, data is fetched by the page from access token protected JSON endpoints below
+pub async fn dashboard() -> HttpResponse {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(DASHBOARD_HTML)
+}
+
+async fn snapshot(metrics: Option<Data<Addr<Metrics>>>) -> Result<Option<MetricsSnapshot>, ApiError> {
+    match metrics {
+        Some(addr) => Ok(Some(
+            addr.send(GetMetrics)
+                .await
+                .map_err(|err| ApplicationError::new(format!("Metrics are not available: {}", err)))?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Metrics snapshot along with DB pool status, metrics are null if node runs without metrics collector
+pub async fn metrics(metrics: Option<Data<Addr<Metrics>>>, db: Data<Arc<Pool>>) -> Result<HttpResponse, ApiError> {
+    let pool: Status = db.status().into();
+    Ok(HttpResponse::Ok().json(json!({
+        "metrics": snapshot(metrics).await?,
+        "pool": pool,
+    })))
+}
+
+/// Uptime, mounted templates, instruction counts, consensus rounds, DB pool and wallets, see `tvnc status`
+pub async fn status(
+    started_at: Data<StartedAt>,
+    node_id: Data<NodeID>,
+    templates: Data<Vec<TemplateRoutes>>,
+    metrics: Option<Data<Addr<Metrics>>>,
+    db: Data<Arc<Pool>>,
+    read_db: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError>
+{
+    let client = read_db.get().await?;
+    let mut consensus_states = HashMap::new();
+    for (_, asset) in snapshot(metrics).await?.map(|snapshot| snapshot.assets).unwrap_or_default() {
+        if let Some(state) = asset.consensus_state {
+            *consensus_states.entry(state).or_insert(0) += 1;
+        }
+    }
+    let started_at = started_at.0;
+    Ok(HttpResponse::Ok().json(NodeStatus {
+        version: env!("CARGO_PKG_VERSION").into(),
+        node_id: *node_id.get_ref(),
+        started_at,
+        uptime_secs: (Utc::now() - started_at).num_seconds(),
+        templates: templates.iter().map(MountedTemplate::from).collect(),
+        instructions: Instruction::count_by_status(&client).await?,
+        consensus_rounds: Proposal::count_in_progress(&client).await?,
+        consensus_states,
+        pool: db.status().into(),
+        wallets: Wallet::count_active(&client).await?,
+    }))
+}
+
+/// Most recently updated instructions
+pub async fn instructions(params: Query<RecentParams>, db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let client = db.get().await?;
+    Ok(HttpResponse::Ok().json(Instruction::find_recent(params.limit(), &client).await?))
+}
+
+/// Fees accumulated by committed instructions per asset issuer
+pub async fn fees(params: Query<FeesParams>, db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let client = db.get().await?;
+    Ok(HttpResponse::Ok().json(FeeLedger::totals_by_issuer(params.issuer.as_deref(), &client).await?))
+}
+
+/// Misbehavior counters and reputation scores of nodes, lowest score first.
+/// Nodes without recorded misbehavior are not listed.
+pub async fn reputation(db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let client = db.get().await?;
+    Ok(HttpResponse::Ok().json(NodeReputation::find_all(&client).await?))
+}
+
+/// Most recently updated proposals along with the last consensus state of every asset
+pub async fn consensus(
+    params: Query<RecentParams>,
+    metrics: Option<Data<Addr<Metrics>>>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let client = db.get().await.map_err(DBError::from)?;
+    let proposals = Proposal::find_recent(params.limit(), &client).await?;
+    let states: Vec<_> = snapshot(metrics)
+        .await?
+        .map(|snapshot| snapshot.assets)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(asset_id, asset)| {
+            json!({
+                "asset_id": asset_id,
+                "state": asset.consensus_state,
+                "steps": asset.consensus_steps,
+                "lock_conflicts": asset.lock_conflicts,
+                "locks_reclaimed": asset.locks_reclaimed,
+            })
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(json!({
+        "assets": states,
+        "proposals": proposals,
+    })))
+}
+
+/// Audit log export in order of creation, see [AuditLog]
+pub async fn audit(params: Query<AuditParams>, db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let params = params.into_inner();
+    let client = db.get().await?;
+    let select = SelectAuditLog {
+        since: params.since,
+        until: params.until,
+        asset_id: params.asset_id,
+        pubkey: params.pubkey,
+        limit: Some(params.limit.unwrap_or(AUDIT_LIMIT_DEFAULT).max(1).min(AUDIT_LIMIT_MAX)),
+    };
+    Ok(HttpResponse::Ok().json(AuditLog::select(select, &client).await?))
+}
+
+/// Signed batch of asset state for peers catching up, see [crate::sync]
+///
+/// With peer listener configured batches are served only to peers authenticated by client certificates,
+/// see [TlsConfig](crate::api::config::TlsConfig)
+pub async fn sync_asset(
+    req: HttpRequest,
+    id: Path<String>,
+    params: Query<SyncParams>,
+    signer: Data<Arc<dyn Signer>>,
+    actix: Option<Data<ActixConfig>>,
+    db: Data<ReadPool>,
+) -> Result<HttpResponse, ApiError>
+{
+    if let Some(peer_port) = actix.and_then(|actix| actix.peer_port()) {
+        if req.app_config().local_addr().port() != peer_port {
+            return Err(AuthError::forbidden("State sync is served on the peer port only").into());
+        }
+    }
+    let asset_id: AssetID = id.parse()?;
+    let limit = params.limit.unwrap_or(SYNC_LIMIT_DEFAULT).max(1).min(SYNC_LIMIT_MAX);
+    let client = db.get().await?;
+    match export_batch(&asset_id, params.since, limit, &**signer, &client).await {
+        Ok(batch) => Ok(HttpResponse::Ok().json(batch)),
+        Err(SyncError::DB(err)) => Err(err.into()),
+        Err(err) => Err(ApplicationError::new(err.to_string()).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recent_limit() {
+        assert_eq!(RecentParams { limit: None }.limit(), RECENT_LIMIT_DEFAULT);
+        assert_eq!(RecentParams { limit: Some(0) }.limit(), 1);
+        assert_eq!(RecentParams { limit: Some(10) }.limit(), 10);
+        assert_eq!(RecentParams { limit: Some(10_000) }.limit(), RECENT_LIMIT_MAX);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{
+    api::{
+        config::check_json,
+        errors::{ApiError, ApplicationError, AuthError},
+        middleware::{authorize_new_asset, RequestAuthenticationContext},
+        models::{RequestSignature, SignedCall},
+    },
+    consensus::{CommitteeCandidate, ConsensusStatus},
+    db::{
+        lifecycle::{
+            create_asset,
+            retire_asset,
+            update_metadata as update_asset_metadata,
+            verify_raid,
+            NewAsset,
+            CREATE_ASSET_CONTRACT,
+            RETIRE_ASSET_CONTRACT,
+            UPDATE_METADATA_CONTRACT,
+        },
+        models::{
+            consensus::instructions::NewInstruction,
+            AssetMetadata,
+            AssetState,
+            AssetStatus,
+            AuditAction,
+            AuditLog,
+            CommitteeMember,
+            DigitalAsset,
+            DisplayToken,
+            NewAuditLog,
+            SelectToken,
+            Token,
+            TokenStatus,
+        },
+        utils::{db::ReadPool, errors::DBError},
+    },
+    raid::RaidConfig,
+    types::{AssetID, NodeID},
+};
+use actix_web::{
+    web::{Data, Json, Path, Query},
+    HttpRequest,
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const TOKENS_LIMIT_DEFAULT: i64 = 50;
+const TOKENS_LIMIT_MAX: i64 = 500;
+
+/// Filters of asset tokens search, see [SelectToken]
+#[derive(Deserialize, Default)]
+pub struct TokensQuery {
+    pub owner_pubkey: Option<String>,
+    pub status: Option<TokenStatus>,
+    pub updated_since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl TokensQuery {
+    fn into_select(self, asset_id: AssetID) -> SelectToken {
+        SelectToken {
+            owner_pubkey: self.owner_pubkey,
+            status: self.status,
+            asset_id: Some(asset_id),
+            updated_since: self.updated_since,
+            limit: Some(self.limit.unwrap_or(TOKENS_LIMIT_DEFAULT).max(1).min(TOKENS_LIMIT_MAX)),
+            offset: self.offset.map(|offset| offset.max(0)),
+        }
+    }
+}
+
+/// Creates asset issued by the signer of the request, committee is selected according to `committee_mode`.
+/// Domain of the asset is verified against RAID TXT record if verification is enabled
+pub async fn create(
+    req: HttpRequest,
+    data: Json<Value>,
+    node_id: Data<NodeID>,
+    candidates: Data<Vec<CommitteeCandidate>>,
+    raid_config: Data<RaidConfig>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let data = data.into_inner();
+    check_json(&req, &data)?;
+    let signature = RequestSignature::verify(&req, &SignedCall::new(CREATE_ASSET_CONTRACT), &data)?;
+    let params: NewAsset = serde_json::from_value(data.clone())
+        .map_err(|err| ApplicationError::bad_request(format!("Asset params error: {}", err).as_str()))?;
+    let client = db.get().await.map_err(DBError::from)?;
+    authorize_new_asset(&req, &params.asset_id, &client).await?;
+    let asset = create_asset(
+        params,
+        signature.pubkey.clone(),
+        Some(*node_id.get_ref()),
+        candidates.get_ref(),
+        &client,
+    )
+    .await?;
+    AuditLog::record(
+        NewAuditLog {
+            signer_pubkey: Some(signature.pubkey),
+            access_pubkey: req.access_pubkey(),
+            action: AuditAction::AssetCreate,
+            asset_id: Some(asset.asset_id.clone()),
+            template_id: Some(asset.asset_id.template_id()),
+            params: data,
+            ..NewAuditLog::default()
+        },
+        &client,
+    )
+    .await?;
+    verify_raid(&asset, raid_config.get_ref(), &client).await?;
+    Ok(HttpResponse::Created().json(asset))
+}
+
+/// Retires asset, request has to be signed by the asset issuer
+pub async fn retire(
+    req: HttpRequest,
+    id: Path<String>,
+    data: Json<Value>,
+    node_id: Data<NodeID>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let asset_id: AssetID = id.parse()?;
+    let data = data.into_inner();
+    check_json(&req, &data)?;
+    let signature = RequestSignature::verify(&req, &SignedCall::asset(&asset_id, RETIRE_ASSET_CONTRACT), &data)?;
+    let client = db.get().await.map_err(DBError::from)?;
+    let asset = AssetState::find_by_asset_id(&asset_id, &client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    if asset.asset_issuer_pub_key != signature.pubkey {
+        return Err(AuthError::forbidden("Asset can be retired by its issuer only").into());
+    }
+    if asset.status == AssetStatus::Retired {
+        return Err(ApplicationError::bad_request("Asset is already retired").into());
+    }
+    let instruction = NewInstruction {
+        asset_id: asset_id.clone(),
+        initiating_node_id: *node_id.get_ref(),
+        contract_name: RETIRE_ASSET_CONTRACT.into(),
+        params: data,
+        signature: signature.signature,
+        ..NewInstruction::default()
+    };
+    let instruction = NewInstruction {
+        id: instruction.content_id().ok_or(DBError::bad_query("Retire instruction must be signed"))?,
+        ..instruction
+    };
+    let audit = NewAuditLog {
+        signer_pubkey: Some(signature.pubkey),
+        access_pubkey: req.access_pubkey(),
+        action: AuditAction::AssetRetire,
+        asset_id: Some(asset_id.clone()),
+        instruction_id: Some(instruction.id),
+        template_id: Some(asset_id.template_id()),
+        contract_name: Some(instruction.contract_name.clone()),
+        params: instruction.params.clone(),
+    };
+    let asset = retire_asset(&asset, instruction, &client).await?;
+    AuditLog::record(audit, &client).await?;
+    Ok(HttpResponse::Ok().json(asset))
+}
+
+/// Updates description and URIs of the asset, signed by the asset issuer.
+/// Returns Pending instruction, metadata is updated once the instruction is committed by the committee
+pub async fn update_metadata(
+    req: HttpRequest,
+    id: Path<String>,
+    data: Json<Value>,
+    node_id: Data<NodeID>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let asset_id: AssetID = id.parse()?;
+    let data = data.into_inner();
+    check_json(&req, &data)?;
+    let signature = RequestSignature::verify(&req, &SignedCall::asset(&asset_id, UPDATE_METADATA_CONTRACT), &data)?;
+    let metadata: AssetMetadata = serde_json::from_value(data.clone())
+        .map_err(|err| ApplicationError::bad_request(format!("Asset metadata error: {}", err).as_str()))?;
+    let client = db.get().await.map_err(DBError::from)?;
+    let asset = AssetState::find_by_asset_id(&asset_id, &client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    if asset.asset_issuer_pub_key != signature.pubkey {
+        return Err(AuthError::forbidden("Asset metadata can be updated by its issuer only").into());
+    }
+    let instruction = NewInstruction {
+        asset_id: asset_id.clone(),
+        initiating_node_id: *node_id.get_ref(),
+        contract_name: UPDATE_METADATA_CONTRACT.into(),
+        params: data,
+        signature: signature.signature,
+        ..NewInstruction::default()
+    };
+    let instruction = NewInstruction {
+        id: instruction
+            .content_id()
+            .ok_or(DBError::bad_query("Update metadata instruction must be signed"))?,
+        ..instruction
+    };
+    let audit = NewAuditLog {
+        signer_pubkey: Some(signature.pubkey),
+        access_pubkey: req.access_pubkey(),
+        action: AuditAction::AssetUpdate,
+        asset_id: Some(asset_id.clone()),
+        instruction_id: Some(instruction.id),
+        template_id: Some(asset_id.template_id()),
+        contract_name: Some(instruction.contract_name.clone()),
+        params: instruction.params.clone(),
+    };
+    let instruction = update_asset_metadata(&asset, metadata, instruction, &client).await?;
+    AuditLog::record(audit, &client).await?;
+    Ok(HttpResponse::Ok().json(instruction))
+}
+
+/// Append only states of the asset starting from the oldest one kept, each linked to the state superseding it
+pub async fn versions(id: Path<String>, db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let asset_id: AssetID = id.parse()?;
+    let client = db.get().await?;
+    AssetState::find_by_asset_id(&asset_id, &client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    Ok(HttpResponse::Ok().json(AssetState::versions(&asset_id, &client).await?))
+}
+
+/// Committee mode of the asset along with committee nodes selected on asset registration
+pub async fn committee(id: Path<String>, db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let asset_id: AssetID = id.parse()?;
+    let client = db.get().await?;
+    let asset = AssetState::find_by_asset_id(&asset_id, &client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    let digital_asset = DigitalAsset::load(asset.digital_asset_id, &client).await?;
+    Ok(HttpResponse::Ok().json(json!({
+        "committee_mode": digital_asset.committee_mode,
+        "members": CommitteeMember::find_by_asset_id(&asset_id, &client).await?,
+    })))
+}
+
+/// Live committee state of the asset assembled from consensus tables, see [ConsensusStatus]
+pub async fn consensus(
+    id: Path<String>,
+    node_id: Data<NodeID>,
+    db: Data<Arc<Pool>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let asset_id: AssetID = id.parse()?;
+    let client = db.get().await.map_err(DBError::from)?;
+    let asset = AssetState::find_by_asset_id(&asset_id, &client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    let status = ConsensusStatus::load(&asset, *node_id.get_ref(), &client)
+        .await
+        .map_err(|err| ApplicationError::new(err.to_string()))?;
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Tokens of the asset filtered by current owner, status and update time, e.g. tokens owned by a wallet
+pub async fn tokens(id: Path<String>, query: Query<TokensQuery>, db: Data<ReadPool>) -> Result<HttpResponse, ApiError> {
+    let asset_id: AssetID = id.parse()?;
+    let client = db.get().await?;
+    AssetState::find_by_asset_id(&asset_id, &client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    let tokens = Token::search(query.into_inner().into_select(asset_id), &client).await?;
+    let tokens: Vec<DisplayToken> = tokens.into_iter().map(DisplayToken::from).collect();
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tokens_query_limits() {
+        let select = TokensQuery::default().into_select(AssetID::default());
+        assert_eq!(select.limit, Some(TOKENS_LIMIT_DEFAULT));
+        assert_eq!(select.offset, None);
+        assert_eq!(select.asset_id, Some(AssetID::default()));
+        let select = TokensQuery {
+            limit: Some(10_000),
+            offset: Some(-1),
+            ..TokensQuery::default()
+        }
+        .into_select(AssetID::default());
+        assert_eq!(select.limit, Some(TOKENS_LIMIT_MAX));
+        assert_eq!(select.offset, Some(0));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    api::errors::{ApiError, ApplicationError},
+    db::{
+        models::consensus::{instructions::Instruction, InstructionProgress},
+        store::{InstructionStore, Store},
+        utils::errors::DBError,
+    },
+    types::InstructionID,
+};
+use actix_web::{
+    web::{Data, Path, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const RESULT_LIMIT_DEFAULT: usize = 100;
+const RESULT_LIMIT_MAX: usize = 1000;
+
+/// Page of the instruction result, `field` is dot separated path to the list within result, e.g. `tokens`
+#[derive(Deserialize, Default)]
+pub struct ResultQuery {
+    pub field: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl ResultQuery {
+    /// Lists are paginated as `{ total, offset, limit, result }`, other values are returned as `{ result }`
+    fn page(self, result: Value) -> Result<Value, ApiError> {
+        let value = match self.field.as_ref() {
+            Some(field) => {
+                let pointer: String = field.split('.').map(|key| format!("/{}", key)).collect();
+                result
+                    .pointer(pointer.as_str())
+                    .cloned()
+                    .ok_or_else(|| ApplicationError::bad_request(format!("Result has no field {}", field).as_str()))?
+            },
+            None => result,
+        };
+        match value {
+            Value::Array(items) => {
+                let offset = self.offset.unwrap_or_default();
+                let limit = self.limit.unwrap_or(RESULT_LIMIT_DEFAULT).max(1).min(RESULT_LIMIT_MAX);
+                let total = items.len();
+                let items: Vec<Value> = items.into_iter().skip(offset).take(limit).collect();
+                Ok(json!({ "total": total, "offset": offset, "limit": limit, "result": items }))
+            },
+            value => Ok(json!({ "result": value })),
+        }
+    }
+}
+
+// Instructions are polled by clients heavily, so the store is backed by read replica if configured
+async fn find_instruction(id: &str, store: &dyn Store) -> Result<Instruction, ApiError> {
+    let id: InstructionID = id
+        .parse()
+        .map_err(|_| ApplicationError::bad_request("Invalid instruction id"))?;
+    Ok(store.find_instruction(id).await?.ok_or(DBError::NotFound)?)
+}
+
+/// Instruction along with partial progress reported by its contract so far
+#[derive(Serialize)]
+struct InstructionWithProgress {
+    #[serde(flatten)]
+    instruction: Instruction,
+    progress: Vec<InstructionProgress>,
+}
+
+pub async fn show(id: Path<String>, store: Data<Arc<dyn Store>>) -> Result<HttpResponse, ApiError> {
+    let instruction = find_instruction(id.as_str(), store.get_ref().as_ref()).await?;
+    let progress = store.find_instruction_progress(instruction.id).await?;
+    Ok(HttpResponse::Ok().json(InstructionWithProgress { instruction, progress }))
+}
+
+pub async fn subinstructions(id: Path<String>, store: Data<Arc<dyn Store>>) -> Result<HttpResponse, ApiError> {
+    let instruction = find_instruction(id.as_str(), store.get_ref().as_ref()).await?;
+    Ok(HttpResponse::Ok().json(store.find_subinstructions(&instruction).await?))
+}
+
+/// Complete result of the instruction, including results truncated in the instruction itself, see [InstructionResult]
+///
+/// [InstructionResult]: crate::db::models::consensus::InstructionResult
+pub async fn result(
+    id: Path<String>,
+    query: Query<ResultQuery>,
+    store: Data<Arc<dyn Store>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let instruction = find_instruction(id.as_str(), store.get_ref().as_ref()).await?;
+    let result = store.complete_result(&instruction).await?;
+    Ok(HttpResponse::Ok().json(query.into_inner().page(result)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn result_page() {
+        let result = json!({ "issued": 3, "tokens": ["a", "b", "c"] });
+        let page = ResultQuery::default().page(result.clone()).unwrap();
+        assert_eq!(page, json!({ "result": result }));
+
+        let query = ResultQuery {
+            field: Some("tokens".into()),
+            offset: Some(1),
+            limit: Some(1),
+        };
+        let page = query.page(result.clone()).unwrap();
+        assert_eq!(page, json!({ "total": 3, "offset": 1, "limit": 1, "result": ["b"] }));
+
+        let query = ResultQuery {
+            field: Some("tokens".into()),
+            limit: Some(10_000),
+            ..ResultQuery::default()
+        };
+        let page = query.page(result.clone()).unwrap();
+        assert_eq!(page["limit"], RESULT_LIMIT_MAX);
+        assert_eq!(page["result"], json!(["a", "b", "c"]));
+
+        let query = ResultQuery {
+            field: Some("missing".into()),
+            ..ResultQuery::default()
+        };
+        assert!(query.page(result).is_err());
+    }
+}
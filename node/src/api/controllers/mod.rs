@@ -1 +1,7 @@
+pub mod admin;
+pub mod assets;
+pub mod instructions;
+pub mod spec;
 pub mod status;
+pub mod templates;
+pub mod tokens;
@@ -0,0 +1,14 @@
+use crate::api::{
+    errors::{ApiError, ErrorCode},
+    openapi::ApiSpec,
+};
+use actix_web::{web::Data, HttpResponse};
+
+pub async fn openapi(spec: Data<ApiSpec>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(&spec.0))
+}
+
+/// Catalog of error codes returned in `code` field of error responses
+pub async fn errors() -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(ErrorCode::catalog()))
+}
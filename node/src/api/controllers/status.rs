@@ -7,7 +7,7 @@ use serde_json::json;
 use std::sync::Arc;
 
 #[derive(Serialize, Deserialize)]
-struct Status {
+pub struct Status {
     pub max_size: usize,
     pub size: usize,
     pub available: isize,
@@ -0,0 +1,154 @@
+use crate::{
+    api::errors::ApiError,
+    db::utils::errors::DBError,
+    template::TemplateRoutes,
+    types::TemplateID,
+};
+use actix_web::{
+    web::{Data, Path},
+    HttpResponse,
+};
+use schemars::gen::SchemaGenerator;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Contract route mounted by the template
+#[derive(Serialize, Debug)]
+pub struct ContractInfo {
+    pub name: &'static str,
+    /// Either `asset` or `token`
+    pub kind: &'static str,
+    pub method: &'static str,
+    pub path: String,
+    pub view: bool,
+    /// JSON schema of params, might reference `definitions` of the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+pub(crate) fn find_template(template_id: &str, templates: &[TemplateRoutes]) -> Result<TemplateRoutes, ApiError> {
+    let template_id: TemplateID = template_id.parse()?;
+    Ok(templates
+        .iter()
+        .find(|routes| routes.template_id == template_id)
+        .cloned()
+        .ok_or(DBError::NotFound)?)
+}
+
+/// Contracts of the template, params schemas are registered in `gen` if provided
+fn contracts_info(template: &TemplateRoutes, mut gen: Option<&mut SchemaGenerator>) -> Vec<ContractInfo> {
+    let routes = [
+        ("asset", template.asset_root(), &template.asset),
+        ("token", template.token_root(), &template.token),
+    ];
+    let mut contracts = vec![];
+    for (kind, root, routes) in routes.iter() {
+        for route in routes.iter() {
+            contracts.push(ContractInfo {
+                name: route.name,
+                kind: *kind,
+                method: if route.view { "GET" } else { "POST" },
+                path: format!("{}/{}", root, route.name),
+                view: route.view,
+                params: gen
+                    .as_deref_mut()
+                    .and_then(|gen| serde_json::to_value((route.params_schema)(gen)).ok()),
+            });
+        }
+    }
+    contracts
+}
+
+/// Templates mounted on the node with their contract routes
+pub async fn index(templates: Data<Vec<TemplateRoutes>>) -> Result<HttpResponse, ApiError> {
+    let templates: Vec<_> = templates
+        .iter()
+        .map(|template| {
+            json!({
+                "id": template.template_id,
+                "contracts": contracts_info(template, None),
+            })
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+/// Contract routes of the template along with params schemas
+pub async fn contracts(
+    template_id: Path<String>,
+    templates: Data<Vec<TemplateRoutes>>,
+) -> Result<HttpResponse, ApiError>
+{
+    let template = find_template(template_id.as_str(), &templates)?;
+    let mut gen = SchemaGenerator::default();
+    let contracts = contracts_info(&template, Some(&mut gen));
+    Ok(HttpResponse::Ok().json(json!({
+        "id": template.template_id,
+        "contracts": contracts,
+        "definitions": gen.definitions(),
+    })))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::template::{nft::NftTemplate, single_use_tokens::SingleUseTokenTemplate, Template};
+    use actix_web::{http::StatusCode, test, web, App};
+
+    fn templates() -> Vec<TemplateRoutes> {
+        vec![
+            TemplateRoutes::of::<SingleUseTokenTemplate>(),
+            TemplateRoutes::of::<NftTemplate>(),
+        ]
+    }
+
+    #[actix_rt::test]
+    async fn list_templates() {
+        let mut app = test::init_service(
+            App::new()
+                .data(templates())
+                .route("/templates", web::get().to(index))
+                .route("/templates/{id}/contracts", web::get().to(contracts)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/templates").to_request();
+        let res: Value = test::read_response_json(&mut app, req).await;
+        assert_eq!(res.as_array().unwrap().len(), 2);
+        assert_eq!(res[0]["id"], json!(SingleUseTokenTemplate::id()));
+        let issue_tokens = res[0]["contracts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|contract| contract["name"] == "issue_tokens")
+            .unwrap();
+        assert_eq!(issue_tokens["kind"], "asset");
+        assert_eq!(issue_tokens["method"], "POST");
+        assert_eq!(
+            issue_tokens["path"],
+            format!(
+                "/asset_call/{}/{{features}}/{{raid_id}}/{{hash}}/issue_tokens",
+                SingleUseTokenTemplate::id()
+            )
+        );
+        assert!(issue_tokens["params"].is_null());
+
+        let uri = format!("/templates/{}/contracts", NftTemplate::id());
+        let req = test::TestRequest::get().uri(uri.as_str()).to_request();
+        let res: Value = test::read_response_json(&mut app, req).await;
+        let token_metadata = res["contracts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|contract| contract["name"] == "token_metadata")
+            .unwrap();
+        assert_eq!(token_metadata["kind"], "token");
+        assert_eq!(token_metadata["method"], "GET");
+        assert_eq!(token_metadata["params"]["$ref"], "#/definitions/TokenMetadataParams");
+        assert!(res["definitions"]["TokenMetadataParams"].is_object());
+
+        let req = test::TestRequest::get().uri("/templates/999/contracts").to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
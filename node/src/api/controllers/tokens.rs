@@ -0,0 +1,72 @@
+use crate::{
+    api::errors::{ApiError, ApplicationError},
+    db::{
+        store::{Store, TokenStore},
+        utils::errors::DBError,
+    },
+    template::nft::NftTemplate,
+    types::TokenID,
+};
+use actix_web::{
+    web::{Data, Path},
+    HttpResponse,
+};
+use std::sync::Arc;
+
+/// Metadata of NFT resolved by [NftTemplate::token_metadata] read-only query
+pub async fn metadata(id: Path<String>, store: Data<Arc<dyn Store>>) -> Result<HttpResponse, ApiError> {
+    let token_id: TokenID = id
+        .parse()
+        .map_err(|_| ApplicationError::bad_request("Invalid token id"))?;
+    let token = store.find_token(&token_id).await?.ok_or(DBError::NotFound)?;
+    match NftTemplate::token_metadata(&token) {
+        Some(metadata) => Ok(HttpResponse::Ok().json(metadata)),
+        None => Err(ApplicationError::bad_request("Token has no NFT metadata").into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::{models::NewToken, store::MemoryStore},
+        template::Template,
+        test::utils::Test,
+        types::{AssetID, Pubkey},
+    };
+    use actix_web::{http::StatusCode, test, web, App};
+    use serde_json::{json, Value};
+
+    #[actix_rt::test]
+    async fn metadata_from_store() {
+        let memory = MemoryStore::new();
+        let token_id = Test::<TokenID>::from_asset(&Test::<AssetID>::from_template(NftTemplate::id()));
+        let owner_pubkey = Test::<Pubkey>::new();
+        memory
+            .insert_token(NewToken {
+                token_id: token_id.clone(),
+                asset_state_id: uuid::Uuid::nil(),
+                initial_data_json: json!({
+                    "name": "Barbarian",
+                    "uri": "ipfs://barbarian",
+                    "content_hash": "00",
+                    "owner_pubkey": owner_pubkey,
+                }),
+            })
+            .unwrap();
+        let store: Arc<dyn Store> = Arc::new(memory);
+        let mut app =
+            test::init_service(App::new().data(store).route("/tokens/{id}/metadata", web::get().to(metadata))).await;
+
+        let uri = format!("/tokens/{}/metadata", token_id);
+        let req = test::TestRequest::get().uri(uri.as_str()).to_request();
+        let res: Value = test::read_response_json(&mut app, req).await;
+        assert_eq!(res["name"], "Barbarian");
+        assert_eq!(res["owner_pubkey"], json!(owner_pubkey));
+
+        let uri = format!("/tokens/{}/metadata", Test::<TokenID>::new());
+        let req = test::TestRequest::get().uri(uri.as_str()).to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
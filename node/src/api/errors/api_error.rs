@@ -1,7 +1,11 @@
 use super::*;
-use crate::{db::utils::errors::DBError, template::errors::TemplateError, types::errors::TypeError};
+use crate::{
+    db::utils::{errors::DBError, validation::ValidationErrors},
+    template::errors::TemplateError,
+    types::errors::TypeError,
+};
 use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::backtrace::Backtrace;
 use thiserror::Error;
 
@@ -23,6 +27,8 @@ pub enum ApiError {
     },
     #[error("Auth error: {0}")]
     AuthError(#[from] AuthError),
+    #[error("{0}")]
+    Validation(#[from] ValidationErrors),
     #[error("Template error: {source}: {backtrace:?}")]
     Template {
         #[from]
@@ -36,100 +42,105 @@ pub struct ResponseData {
     pub error_response: HttpResponse,
 }
 
+impl ResponseData {
+    /// JSON error response with `code`, `message` and `details`, see [ErrorCode::body]
+    fn new(status_code: StatusCode, code: ErrorCode, message: &str, details: Value) -> Self {
+        Self {
+            status_code,
+            error_response: HttpResponse::build(status_code).json(code.body(message, details)),
+        }
+    }
+}
+
 // TODO: move this to individual modules, impl ResponseError to DBError and TemplateError
 impl ApiError {
-    pub fn load_response_data(&self) -> ResponseData {
-        let generic_error_response_data = ResponseData {
-            status_code: StatusCode::INTERNAL_SERVER_ERROR,
-            error_response: HttpResponse::InternalServerError().json(json!({"error": "An error has occurred"})),
-        };
+    /// Machine readable code of the error, see [ErrorCode]
+    pub fn code(&self) -> ErrorCode {
         match self {
-            ApiError::ApplicationError{ source: ApplicationError {
-                error_type, ..
-            }, ..} => {
-                match error_type {
-                    ApplicationErrorType::Unprocessable => ResponseData {
-                        status_code: StatusCode::UNPROCESSABLE_ENTITY,
-                        error_response: HttpResponse::UnprocessableEntity()
-                            .json(json!({"error": "Application failed to process request"})),
-                    },
-                    ApplicationErrorType::Internal => ResponseData {
-                        status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                        error_response: HttpResponse::InternalServerError()
-                            .json(json!({"error": "An internal error has occurred."})),
-                    },
-                    ApplicationErrorType::BadRequest => ResponseData {
-                        status_code: StatusCode::BAD_REQUEST,
-                        error_response: HttpResponse::BadRequest()
-                            .json(json!({"error": "An error has occurred processing your request, please check your input and try again."})),
-                    },
-                }
+            ApiError::ApplicationError {
+                source: ApplicationError { error_type, .. },
+                ..
+            } => match error_type {
+                ApplicationErrorType::Internal => ErrorCode::Internal,
+                ApplicationErrorType::PayloadTooLarge => ErrorCode::PayloadTooLarge,
+                ApplicationErrorType::Unprocessable => ErrorCode::Processing,
+                ApplicationErrorType::BadRequest => ErrorCode::Validation,
             },
-            ApiError::AuthError(AuthError { reason: _, error_type }) => {
-                if *error_type == AuthErrorType::Forbidden {
-                    ResponseData {
-                        status_code: StatusCode::FORBIDDEN,
-                        error_response: HttpResponse::build(StatusCode::FORBIDDEN)
-                            .json(json!({"error": "Forbidden".to_string()})),
-                    }
-                } else {
-                    ResponseData {
-                        status_code: StatusCode::UNAUTHORIZED,
-                        error_response: HttpResponse::build(StatusCode::UNAUTHORIZED)
-                            .json(json!({"error": "Unauthorized".to_string()})),
-                    }
-                }
+            ApiError::AuthError(AuthError { error_type, .. }) => match error_type {
+                AuthErrorType::Forbidden => ErrorCode::Forbidden,
+                AuthErrorType::Unauthorized => ErrorCode::Unauthorized,
+                AuthErrorType::AccessExpired => ErrorCode::AccessExpired,
+                AuthErrorType::AccessRevoked => ErrorCode::AccessRevoked,
             },
-            ApiError::DBError{source, ..} |
-            ApiError::Template{source: TemplateError::DB { source, .. }, .. }
-            => match source {
-                DBError::Postgres(postgres_error) => {
-                    if let Some(code) = postgres_error.code() {
-                        let (status_code, message) = match code.code() {
-                            "01000" => (StatusCode::BAD_REQUEST, "Invalid input"),
-                            "02000" => (StatusCode::NOT_FOUND, "No results"),
-                            "23505" => (StatusCode::CONFLICT, "Duplicate record exists"),
-                            _ => (StatusCode::INTERNAL_SERVER_ERROR, "Unknown error"),
-                        };
+            ApiError::DBError { source, .. } => source.into(),
+            ApiError::Type(_) | ApiError::Validation(_) => ErrorCode::Validation,
+            ApiError::Template { source, .. } => source.code(),
+        }
+    }
 
-                        let error_response =
-                            HttpResponse::build(status_code).json(json!({"error": message.to_string()}));
-                        ResponseData {
-                            status_code,
-                            error_response,
-                        }
-                    } else {
-                        generic_error_response_data
-                    }
-                },
-                DBError::NotFound => ResponseData {
-                    status_code: StatusCode::NOT_FOUND,
-                    error_response: HttpResponse::build(StatusCode::NOT_FOUND)
-                        .json(json!({"error": "No results".to_string()})),
-                },
-                DBError::Validation(validation_errors) => ResponseData {
-                    status_code: StatusCode::UNPROCESSABLE_ENTITY,
-                    error_response: HttpResponse::UnprocessableEntity()
-                        .json(json!({"error": "Validation error".to_string(), "fields": validation_errors})),
-                },
-                _ => generic_error_response_data,
-            },
-            ApiError::Type(err) => ResponseData {
-                status_code: StatusCode::BAD_REQUEST,
-                error_response: HttpResponse::build(StatusCode::BAD_REQUEST)
-                    .json(json!({ "error": err.to_string() })),
+    pub fn load_response_data(&self) -> ResponseData {
+        let code = self.code();
+        let (status_code, error, details) = match self {
+            ApiError::ApplicationError {
+                source: ApplicationError { error_type, reason },
+                ..
+            } => match error_type {
+                ApplicationErrorType::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, reason.clone(), Value::Null),
+                ApplicationErrorType::Unprocessable => (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Application failed to process request".to_string(),
+                    Value::Null,
+                ),
+                ApplicationErrorType::Internal => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "An internal error has occurred.".to_string(),
+                    Value::Null,
+                ),
+                ApplicationErrorType::BadRequest => (
+                    StatusCode::BAD_REQUEST,
+                    "An error has occurred processing your request, please check your input and try again.".to_string(),
+                    json!({ "reason": reason }),
+                ),
             },
-            ApiError::Template{source: TemplateError::Validation(err), .. } => ResponseData {
-                status_code: StatusCode::BAD_REQUEST,
-                error_response: HttpResponse::build(StatusCode::BAD_REQUEST)
-                    .json(json!({ "error": err.to_string() })),
+            ApiError::AuthError(AuthError { error_type, .. }) => {
+                let error = match error_type {
+                    AuthErrorType::Forbidden => "Forbidden",
+                    AuthErrorType::Unauthorized => "Unauthorized",
+                    AuthErrorType::AccessExpired => "Access expired",
+                    AuthErrorType::AccessRevoked => "Access revoked",
+                };
+                (code.status_code(), error.to_string(), Value::Null)
             },
-            ApiError::Template{ source, .. } => ResponseData {
-                status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                error_response: HttpResponse::build(StatusCode::INTERNAL_SERVER_ERROR)
-                    .json(json!({ "error": source.to_string() })),
+            ApiError::DBError { source, .. } |
+            ApiError::Template {
+                source: TemplateError::DB { source, .. },
+                ..
+            } => match source {
+                DBError::Postgres(postgres_error) => match postgres_error.code().map(|code| code.code()) {
+                    Some("01000") => (StatusCode::BAD_REQUEST, "Invalid input".to_string(), Value::Null),
+                    Some("02000") => (StatusCode::NOT_FOUND, "No results".to_string(), Value::Null),
+                    Some("23505") => (StatusCode::CONFLICT, "Duplicate record exists".to_string(), Value::Null),
+                    Some(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Unknown error".to_string(), Value::Null),
+                    None => (StatusCode::INTERNAL_SERVER_ERROR, "An error has occurred".to_string(), Value::Null),
+                },
+                DBError::NotFound => (StatusCode::NOT_FOUND, "No results".to_string(), Value::Null),
+                DBError::Conflict { msg } => (StatusCode::CONFLICT, msg.clone(), Value::Null),
+                DBError::Validation(validation_errors) => (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Validation error".to_string(),
+                    json!({ "fields": validation_errors }),
+                ),
+                _ => (code.status_code(), "An error has occurred".to_string(), Value::Null),
             },
-        }
+            ApiError::Validation(validation_errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Validation error".to_string(),
+                json!({ "fields": validation_errors }),
+            ),
+            ApiError::Type(err) => (StatusCode::BAD_REQUEST, err.to_string(), err.details()),
+            ApiError::Template { source, .. } => (code.status_code(), source.to_string(), Value::Null),
+        };
+        ResponseData::new(status_code, code, &error, details)
     }
 }
 
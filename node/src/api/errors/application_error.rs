@@ -5,6 +5,7 @@ pub enum ApplicationErrorType {
     Unprocessable,
     Internal,
     BadRequest,
+    PayloadTooLarge,
 }
 
 #[derive(Debug)]
@@ -32,6 +33,10 @@ impl ApplicationError {
     pub fn bad_request(reason: &str) -> Self {
         Self::new_with_type(ApplicationErrorType::BadRequest, reason.to_string())
     }
+
+    pub fn payload_too_large(reason: &str) -> Self {
+        Self::new_with_type(ApplicationErrorType::PayloadTooLarge, reason.to_string())
+    }
 }
 
 impl fmt::Display for ApplicationError {
@@ -4,6 +4,10 @@ use std::{error::Error, fmt};
 pub enum AuthErrorType {
     Forbidden,
     Unauthorized,
+    /// Access record of the token subject has expired
+    AccessExpired,
+    /// Access key of the token subject has been revoked
+    AccessRevoked,
 }
 
 #[derive(Debug)]
@@ -23,6 +27,27 @@ impl AuthError {
             error_type: AuthErrorType::Unauthorized,
         }
     }
+
+    pub fn forbidden(reason: &str) -> Self {
+        Self {
+            reason: reason.to_string(),
+            error_type: AuthErrorType::Forbidden,
+        }
+    }
+
+    pub fn expired(reason: &str) -> Self {
+        Self {
+            reason: reason.to_string(),
+            error_type: AuthErrorType::AccessExpired,
+        }
+    }
+
+    pub fn revoked(reason: &str) -> Self {
+        Self {
+            reason: reason.to_string(),
+            error_type: AuthErrorType::AccessRevoked,
+        }
+    }
 }
 
 impl fmt::Display for AuthError {
@@ -0,0 +1,157 @@
+use crate::db::utils::errors::DBError;
+use actix_web::http::StatusCode;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Catalog of machine readable error codes, served at [CATALOG_PATH]
+///
+/// Codes are stable identifiers: they are returned in `code` field of JSON error bodies and in results
+/// of failed instructions, so existing codes are never renamed, new ones are appended
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Validation,
+    Unauthorized,
+    AccessExpired,
+    AccessRevoked,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    Timeout,
+    Processing,
+    Internal,
+    Unavailable,
+}
+
+pub const CATALOG_PATH: &'static str = "/api/errors";
+
+/// Entry of the error catalog
+#[derive(Serialize, Debug)]
+pub struct ErrorCodeInfo {
+    pub code: ErrorCode,
+    pub status: u16,
+    pub description: &'static str,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 12] = [
+        Self::Validation,
+        Self::Unauthorized,
+        Self::AccessExpired,
+        Self::AccessRevoked,
+        Self::Forbidden,
+        Self::NotFound,
+        Self::Conflict,
+        Self::PayloadTooLarge,
+        Self::Timeout,
+        Self::Processing,
+        Self::Internal,
+        Self::Unavailable,
+    ];
+
+    /// All error codes with descriptions, see [CATALOG_PATH]
+    pub fn catalog() -> Vec<ErrorCodeInfo> {
+        Self::ALL
+            .iter()
+            .map(|code| ErrorCodeInfo {
+                code: *code,
+                status: code.status_code().as_u16(),
+                description: code.description(),
+            })
+            .collect()
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Validation => "Request is malformed or parameters are invalid, `details.fields` lists invalid fields",
+            Self::Unauthorized => "Access token is missing or invalid",
+            Self::AccessExpired => "Access record of the token subject has expired",
+            Self::AccessRevoked => "Access key of the token subject has been revoked",
+            Self::Forbidden => "Caller is not permitted to perform the request",
+            Self::NotFound => "Requested resource does not exist",
+            Self::Conflict => "Request conflicts with current state, e.g. concurrent update or duplicate record",
+            Self::PayloadTooLarge => "Request body exceeds configured size limit",
+            Self::Timeout => "Request or instruction did not complete in time",
+            Self::Processing => "Instruction or request could not be processed",
+            Self::Internal => "Unexpected node error",
+            Self::Unavailable => "Node dependency, e.g. database or template actor, is temporarily unavailable",
+        }
+    }
+
+    /// JSON error body: `code`, human readable `message` and structured `details`,
+    /// `error` duplicates `message` for clients predating the catalog
+    pub fn body(&self, message: &str, details: Value) -> Value {
+        json!({
+            "code": self,
+            "message": message,
+            "details": details,
+            "error": message,
+        })
+    }
+
+    /// Default HTTP status of the error code
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Validation => StatusCode::BAD_REQUEST,
+            Self::Unauthorized | Self::AccessExpired | Self::AccessRevoked => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Processing => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+impl From<&DBError> for ErrorCode {
+    fn from(err: &DBError) -> Self {
+        match err {
+            DBError::Postgres(err) => match err.code().map(|code| code.code()) {
+                Some("01000") => Self::Validation,
+                Some("02000") => Self::NotFound,
+                Some("23505") => Self::Conflict,
+                _ => Self::Internal,
+            },
+            DBError::Pool(_) | DBError::Unreachable { .. } => Self::Unavailable,
+            DBError::NotFound => Self::NotFound,
+            DBError::Conflict { .. } => Self::Conflict,
+            DBError::Validation(_) => Self::Validation,
+            _ => Self::Internal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serialize() {
+        assert_eq!(json!(ErrorCode::NotFound), json!("not_found"));
+        assert_eq!(json!(ErrorCode::Validation), json!("validation"));
+        assert_eq!(json!(ErrorCode::AccessRevoked), json!("access_revoked"));
+        assert_eq!(ErrorCode::from(&DBError::NotFound), ErrorCode::NotFound);
+        assert_eq!(ErrorCode::from(&DBError::conflict("version")), ErrorCode::Conflict);
+        assert_eq!(ErrorCode::Conflict.status_code(), StatusCode::CONFLICT);
+        assert_eq!(json!(ErrorCode::PayloadTooLarge), json!("payload_too_large"));
+    }
+
+    #[test]
+    fn catalog() {
+        let catalog = json!(ErrorCode::catalog());
+        assert_eq!(catalog.as_array().unwrap().len(), ErrorCode::ALL.len());
+        assert_eq!(catalog[11]["code"], "unavailable");
+        assert_eq!(catalog[11]["status"], 503);
+
+        let body = ErrorCode::Conflict.body("Duplicate record exists", Value::Null);
+        assert_eq!(body["code"], "conflict");
+        assert_eq!(body["message"], "Duplicate record exists");
+        assert_eq!(body["error"], body["message"]);
+        assert!(body["details"].is_null());
+    }
+}
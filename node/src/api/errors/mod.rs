@@ -1,7 +1,8 @@
-pub use self::{api_error::*, application_error::*, auth_error::*};
+pub use self::{api_error::*, application_error::*, auth_error::*, error_code::{ErrorCode, ErrorCodeInfo, CATALOG_PATH}};
 
 mod api_error;
 mod application_error;
 mod auth_error;
+mod error_code;
 
 pub(crate) use super::LOG_TARGET;
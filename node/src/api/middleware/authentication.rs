@@ -1,29 +1,46 @@
-use crate::api::{errors::ApiError, models::AccessToken};
+use crate::{
+    api::{
+        errors::{ApiError, AuthError},
+        models::AccessToken,
+    },
+    db::{
+        models::{consensus::instructions::Instruction, Access, AccessResource, SelectAccess},
+        utils::errors::DBError,
+    },
+    types::{AssetID, InstructionID, RaidID, TemplateID, TokenID},
+};
 use actix_http::error::ResponseError;
 use actix_service::Service;
 use actix_web::{
     dev::{MessageBody, Payload, ServiceRequest, ServiceResponse, Transform},
     error,
+    web,
     FromRequest,
     HttpRequest,
 };
+use deadpool_postgres::{Client, Pool};
 use futures::future::{ok, Ready};
 use std::{
-    borrow::BorrowMut,
     cell::RefCell,
     future::Future,
     pin::Pin,
     rc::Rc,
+    sync::Arc,
     task::{Context, Poll},
 };
 
+/// Last segment of the batch route of asset contracts, see [protected_contract]
+const BATCH_CONTRACT: &'static str = "batch";
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct AuthenticationContext {
-    pubkey: String,
+    pub pubkey: String,
 }
 
 pub trait RequestAuthenticationContext {
     fn authentication_context(&self) -> Result<AuthenticationContext, ApiError>;
+    /// Subject of the access token the request was authorized with by [Authentication] middleware
+    fn access_pubkey(&self) -> Option<String>;
 }
 
 impl RequestAuthenticationContext for HttpRequest {
@@ -33,8 +50,245 @@ impl RequestAuthenticationContext for HttpRequest {
             pubkey: access_token.sub,
         })
     }
+
+    fn access_pubkey(&self) -> Option<String> {
+        self.extensions()
+            .get::<AuthenticationContext>()
+            .map(|context| context.pubkey.clone())
+    }
+}
+
+/// Template ID and contract name of the protected /asset_call and /token_call routes,
+/// contracts of the batch route are authorized by its handler, see [authorize_batch_call]
+fn protected_contract(path: &str) -> Option<(String, Option<String>)> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        [root, template_id, .., contract] if *root == "asset_call" && *contract == BATCH_CONTRACT => {
+            Some((template_id.to_string(), None))
+        },
+        [root, template_id, .., contract] if *root == "asset_call" || *root == "token_call" => {
+            Some((template_id.to_string(), Some(contract.to_string())))
+        },
+        _ => None,
+    }
+}
+
+/// Asset targeted by the route, access keys might be restricted to assets
+#[derive(Debug, PartialEq)]
+enum RouteAsset {
+    /// Route doesn't target an existing asset, e.g. asset creation
+    None,
+    Asset(AssetID),
+    /// Asset of the instruction, which is looked up by [resolve_asset]
+    Instruction(InstructionID),
+    /// Asset or token route with asset which can't be parsed, denied to access restricted to assets
+    Unknown,
+}
+
+/// Asset of the /asset_call, /token_call, /assets/{id}, /tokens/{id} and /instructions/{id} routes
+fn protected_asset(path: &str) -> RouteAsset {
+    let parse_call = |segments: &[&str]| -> Option<AssetID> {
+        match segments {
+            [template_id, features, raid_id, hash, _, ..] => {
+                let template_id: TemplateID = template_id.parse().ok()?;
+                let features = u16::from_str_radix(features, 16).ok()?;
+                let raid_id = RaidID::from_base58(raid_id).ok()?;
+                Some(AssetID::new(template_id, features, raid_id, hash.to_string()))
+            },
+            _ => None,
+        }
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let asset_id = match segments.as_slice() {
+        [root, call @ ..] if *root == "asset_call" || *root == "token_call" => parse_call(call),
+        ["assets", asset_id, ..] => asset_id.parse().ok(),
+        ["tokens", token_id, ..] => token_id.parse::<TokenID>().ok().map(|token_id| token_id.asset_id()),
+        ["instructions", id, ..] => match id.parse() {
+            Ok(id) => return RouteAsset::Instruction(id),
+            Err(_) => None,
+        },
+        _ => return RouteAsset::None,
+    };
+    asset_id.map(RouteAsset::Asset).unwrap_or(RouteAsset::Unknown)
+}
+
+/// Access restricted to assets permits only routes of those assets
+fn check_asset(access: &[&Access], asset: &RouteAsset) -> Result<(), ApiError> {
+    let permitted = match asset {
+        RouteAsset::None => true,
+        RouteAsset::Asset(asset_id) => access.iter().any(|access| access.allows_asset(asset_id)),
+        RouteAsset::Instruction(_) | RouteAsset::Unknown => access.iter().all(|access| access.assets.is_empty()),
+    };
+    if permitted {
+        Ok(())
+    } else {
+        Err(AuthError::forbidden("Access is not granted for the asset").into())
+    }
+}
+
+/// Looks up asset of the instruction route, instruction which doesn't exist has unknown asset
+async fn resolve_asset(asset: RouteAsset, client: &Client) -> Result<RouteAsset, ApiError> {
+    match asset {
+        RouteAsset::Instruction(id) => Ok(match Instruction::find(id, client).await? {
+            Some(instruction) => RouteAsset::Asset(instruction.asset_id),
+            None => RouteAsset::Unknown,
+        }),
+        asset => Ok(asset),
+    }
+}
+
+/// Rejects creation of `asset_id` by the access key restricted to other assets, creation route doesn't carry
+/// asset in the path, so it is checked by the handler. Passes if authentication is disabled
+pub async fn authorize_new_asset(request: &HttpRequest, asset_id: &AssetID, client: &Client) -> Result<(), ApiError> {
+    let pubkey = match request.access_pubkey() {
+        Some(pubkey) => pubkey,
+        None => return Ok(()),
+    };
+    let query = SelectAccess {
+        pub_key: Some(pubkey),
+        resource: AccessResource::Api,
+        ..SelectAccess::default()
+    };
+    let access = Access::select(query, client).await?;
+    check_asset(&access.iter().collect::<Vec<_>>(), &RouteAsset::Asset(asset_id.clone()))
+}
+
+/// Rejects call of `contract` in the batch of `asset_id` calls unless scopes of the access key permit it,
+/// batch route doesn't carry contracts in the path, so they are checked by the handler.
+/// Passes if authentication is disabled
+pub async fn authorize_batch_call(
+    request: &HttpRequest,
+    template_id: TemplateID,
+    contract: &str,
+    asset_id: &AssetID,
+    client: &Client,
+) -> Result<(), ApiError>
+{
+    let pubkey = match request.access_pubkey() {
+        Some(pubkey) => pubkey,
+        None => return Ok(()),
+    };
+    let query = SelectAccess {
+        pub_key: Some(pubkey),
+        resource: AccessResource::Api,
+        ..SelectAccess::default()
+    };
+    let access = Access::select(query, client).await?;
+    let permitted: Vec<_> = access
+        .iter()
+        .filter(|access| access.allows(&template_id.to_string(), contract))
+        .collect();
+    if permitted.is_empty() {
+        return Err(AuthError::forbidden("Access token scopes do not permit the contract").into());
+    }
+    check_asset(&permitted, &RouteAsset::Asset(asset_id.clone()))
+}
+
+/// Route requiring access record of the resource, other than contract calls
+fn protected_resource(path: &str) -> Option<AccessResource> {
+    let path = path.trim_matches('/');
+    // Dashboard page is a static shell without data, browser navigation can't carry Bearer token,
+    // so the page asks for the token and passes it to the protected /admin JSON endpoints itself
+    if path == "admin/dashboard" {
+        return None;
+    }
+    let root = path.split('/').next().unwrap_or_default();
+    match root {
+        "admin" => Some(AccessResource::Admin),
+        "assets" | "instructions" | "tokens" => Some(AccessResource::Api),
+        _ => None,
+    }
+}
+
+/// Error for the token subject without active access record to the resource,
+/// revoked and expired access keys are reported distinctly from missing access
+async fn inactive_access_error(
+    pubkey: &str,
+    resource: AccessResource,
+    missing: AuthError,
+    client: &Client,
+) -> Result<AuthError, ApiError>
+{
+    let query = SelectAccess {
+        pub_key: Some(pubkey.to_string()),
+        resource,
+        include_inactive: Some(true),
+        ..SelectAccess::default()
+    };
+    let access = Access::select(query, client).await?;
+    Ok(if access.iter().any(|access| access.revoked_at.is_some()) {
+        AuthError::revoked(&format!("{} access key is revoked", resource))
+    } else if access.iter().any(|access| access.is_expired()) {
+        AuthError::expired(&format!("{} access has expired", resource))
+    } else {
+        missing
+    })
+}
+
+/// Validates access token and checks access record to the resource of the token subject,
+/// API access restricted to assets permits only routes of those assets
+async fn authorize_resource(
+    request: &HttpRequest,
+    resource: AccessResource,
+    asset: RouteAsset,
+) -> Result<AuthenticationContext, ApiError>
+{
+    let context = request.authentication_context()?;
+    let pool = request
+        .app_data::<web::Data<Arc<Pool>>>()
+        .ok_or_else(|| AuthError::unauthorized("Access records are not available"))?;
+    let client = pool.get().await.map_err(DBError::from)?;
+    let query = SelectAccess {
+        pub_key: Some(context.pubkey.clone()),
+        resource,
+        ..SelectAccess::default()
+    };
+    let access = Access::select(query, &client).await?;
+    if access.is_empty() {
+        let missing = AuthError::forbidden(&format!("{} access is not granted", resource));
+        return Err(inactive_access_error(&context.pubkey, resource, missing, &client).await?.into());
+    }
+    let asset = resolve_asset(asset, &client).await?;
+    check_asset(&access.iter().collect::<Vec<_>>(), &asset)?;
+    Ok(context)
 }
 
+/// Validates access token and checks API access record of the token subject permits calling the contract
+/// of the asset, scopes of the batch route are checked for every call by its handler
+async fn authorize(
+    request: &HttpRequest,
+    template_id: &str,
+    contract: Option<&str>,
+    asset: RouteAsset,
+) -> Result<AuthenticationContext, ApiError>
+{
+    let context = request.authentication_context()?;
+    let pool = request
+        .app_data::<web::Data<Arc<Pool>>>()
+        .ok_or_else(|| AuthError::unauthorized("Access records are not available"))?;
+    let client = pool.get().await.map_err(DBError::from)?;
+    let query = SelectAccess {
+        pub_key: Some(context.pubkey.clone()),
+        resource: AccessResource::Api,
+        ..SelectAccess::default()
+    };
+    let access = Access::select(query, &client).await?;
+    if access.is_empty() {
+        let missing = AuthError::unauthorized("Access revoked");
+        return Err(inactive_access_error(&context.pubkey, AccessResource::Api, missing, &client).await?.into());
+    }
+    let permitted: Vec<_> = access
+        .iter()
+        .filter(|access| contract.map_or(true, |contract| access.allows(template_id, contract)))
+        .collect();
+    if permitted.is_empty() {
+        return Err(AuthError::forbidden("Access token scopes do not permit the contract").into());
+    }
+    check_asset(&permitted, &asset)?;
+    Ok(context)
+}
+
+/// Requires valid access token on template routes, see [AccessToken]
 pub struct Authentication;
 
 impl Authentication {
@@ -84,36 +338,333 @@ where
     type Response = S::Response;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(cx).map_err(error::Error::from)
+        self.service.borrow_mut().poll_ready(cx).map_err(error::Error::from)
     }
 
     fn call(&mut self, request: Self::Request) -> Self::Future {
-        let mut service = self.service.clone();
+        let service = self.service.clone();
 
-        // Ignore requests to the status endpoint
-        if request.uri() == "/status" {
+        // Only template, asset and admin routes are protected
+        let contract = protected_contract(request.path());
+        let resource = protected_resource(request.path());
+        let asset = protected_asset(request.path());
+        if contract.is_none() && resource.is_none() {
             let fut = service.borrow_mut().call(request);
-            Box::pin(async move { fut.await })
-        } else {
-            let (http_request, payload) = request.into_parts();
-
-            let authentication_context: Result<AuthenticationContext, ApiError> = http_request.authentication_context();
+            return Box::pin(async move { fut.await });
+        }
 
-            match authentication_context {
+        let (http_request, payload) = request.into_parts();
+        Box::pin(async move {
+            let authorized = match (contract, resource) {
+                (Some((template_id, contract)), _) => {
+                    authorize(&http_request, &template_id, contract.as_deref(), asset).await
+                },
+                (None, Some(resource)) => authorize_resource(&http_request, resource, asset).await,
+                (None, None) => unreachable!(),
+            };
+            match authorized {
                 Ok(authentication_context) => {
                     http_request.extensions_mut().insert(authentication_context);
                     let request = ServiceRequest::from_parts(http_request, payload)
                         .unwrap_or_else(|_| unreachable!("Failed to recompose request in AuthenticationService::call"));
                     let fut = service.borrow_mut().call(request);
-                    Box::pin(async move { fut.await })
+                    fut.await
                 },
-                Err(error) => Box::pin(async move {
-                    Ok(ServiceResponse::<B>::new(
-                        http_request,
-                        error.error_response().into_body(),
-                    ))
-                }),
+                Err(error) => Ok(ServiceResponse::<B>::new(
+                    http_request,
+                    error.error_response().into_body(),
+                )),
             }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        api::models::{JwtSecret, ACCESS_TOKEN_ISSUER},
+        db::models::NewAccess,
+        template::asset_call_path,
+        test::utils::{
+            actix_test_pool,
+            builders::{consensus::InstructionBuilder, AssetStateBuilder},
+            test_db_client,
+            Test,
+        },
+    };
+    use actix_web::{http::StatusCode, test, App, HttpResponse};
+
+    const PUBKEY: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
+
+    #[derive(serde::Deserialize)]
+    struct CreateAsset {
+        asset_id: AssetID,
+    }
+
+    async fn create_asset(
+        req: HttpRequest,
+        query: web::Query<CreateAsset>,
+        pool: web::Data<Arc<Pool>>,
+    ) -> Result<HttpResponse, ApiError>
+    {
+        let client = pool.get().await.map_err(DBError::from)?;
+        authorize_new_asset(&req, &query.asset_id, &client).await?;
+        Ok(HttpResponse::Created().finish())
+    }
+
+    #[test]
+    fn protected_routes() {
+        assert_eq!(
+            protected_contract("/asset_call/1/0000/2/3/issue_tokens"),
+            Some(("1".into(), Some("issue_tokens".into())))
+        );
+        assert_eq!(
+            protected_contract("/token_call/1/0000/2/3/4/sell_token"),
+            Some(("1".into(), Some("sell_token".into())))
+        );
+        assert_eq!(protected_contract("/asset_call/1/0000/2/3/batch"), Some(("1".into(), None)));
+        assert_eq!(protected_contract("/status"), None);
+        assert_eq!(protected_contract("/asset_call"), None);
+        assert_eq!(protected_resource("/admin/templates/1/contracts"), Some(AccessResource::Admin));
+        assert_eq!(protected_resource("/assets/1/retire"), Some(AccessResource::Api));
+        assert_eq!(protected_resource("/admin/metrics"), Some(AccessResource::Admin));
+        assert_eq!(protected_resource("/instructions/1/result"), Some(AccessResource::Api));
+        assert_eq!(protected_resource("/tokens/1/metadata"), Some(AccessResource::Api));
+        assert_eq!(protected_resource("/admin/dashboard"), None);
+        assert_eq!(protected_resource("/administrator"), None);
+        assert_eq!(protected_resource("/status"), None);
+
+        let asset_id = AssetID::new(1.into(), 2, RaidID::default(), format!("{:032X}", 3));
+        let path = asset_call_path(&asset_id, "issue_tokens");
+        assert_eq!(protected_asset(&path), RouteAsset::Asset(asset_id.clone()));
+        let path = format!("/assets/{}/retire", asset_id);
+        assert_eq!(protected_asset(&path), RouteAsset::Asset(asset_id));
+        assert_eq!(protected_asset("/asset_call/1/0000/2/3/issue_tokens"), RouteAsset::Unknown);
+        assert_eq!(protected_asset("/token_call/1/issue_tokens"), RouteAsset::Unknown);
+        assert_eq!(protected_asset("/assets/ZZZZ/retire"), RouteAsset::Unknown);
+        assert_eq!(protected_asset("/assets"), RouteAsset::None);
+        assert_eq!(protected_asset("/admin/sync/assets/1"), RouteAsset::None);
+    }
+
+    #[actix_rt::test]
+    async fn authentication() {
+        let (client, _lock) = test_db_client().await;
+        let secret = JwtSecret(b"secret".to_vec());
+        let mut app = test::init_service(
+            App::new()
+                .data(actix_test_pool())
+                .data(secret.clone())
+                .wrap(Authentication::new())
+                .route("/status", web::get().to(|| HttpResponse::Ok()))
+                .route("/admin/templates/{tpl}/contracts", web::get().to(|| HttpResponse::Ok()))
+                .route(
+                    "/asset_call/{tpl}/{features}/{raid_id}/{hash}/{contract}",
+                    web::post().to(|| HttpResponse::Ok()),
+                )
+                .route("/assets", web::post().to(create_asset)),
+        )
+        .await;
+        let token = AccessToken::new(PUBKEY.into(), ACCESS_TOKEN_ISSUER.into(), 10)
+            .encode(&secret)
+            .unwrap();
+        let call = |contract: &str, token: Option<&str>| {
+            let mut req = test::TestRequest::post().uri(&format!("/asset_call/1/0000/2/3/{}", contract));
+            if let Some(token) = token {
+                req = req.header("authorization", format!("Bearer {}", token));
+            }
+            req.to_request()
+        };
+
+        let res = test::call_service(&mut app, test::TestRequest::get().uri("/status").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&mut app, call("issue_tokens", None)).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        // Valid token, but no access record
+        let res = test::call_service(&mut app, call("issue_tokens", Some(&token))).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Access::grant(
+            NewAccess {
+                pub_key: PUBKEY.into(),
+                scopes: vec!["1/issue_tokens".into()],
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let res = test::call_service(&mut app, call("issue_tokens", Some(&token))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&mut app, call("sell_token", Some(&token))).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // Access restricted to an asset
+        let asset_id = AssetID::new(1.into(), 0, RaidID::default(), format!("{:032X}", 1));
+        let other = AssetID::new(1.into(), 0, RaidID::default(), format!("{:032X}", 2));
+        Access::grant(
+            NewAccess {
+                pub_key: PUBKEY.into(),
+                scopes: vec!["1/issue_tokens".into()],
+                assets: vec![asset_id.clone()],
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let asset_call = |asset_id: &AssetID| {
+            let uri = format!(
+                "/asset_call/1/{:04X}/{}/{}/issue_tokens",
+                asset_id.features(),
+                asset_id.raid_id().to_base58(),
+                asset_id.hash()
+            );
+            test::TestRequest::post()
+                .uri(&uri)
+                .header("authorization", format!("Bearer {}", token))
+                .to_request()
+        };
+        let res = test::call_service(&mut app, asset_call(&asset_id)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&mut app, asset_call(&other)).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        // asset of the route can't be parsed, so restricted access doesn't permit it
+        let res = test::call_service(&mut app, call("issue_tokens", Some(&token))).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        // asset created by restricted access key has to be one of its assets
+        let create = |asset_id: &AssetID| {
+            test::TestRequest::post()
+                .uri(&format!("/assets?asset_id={}", asset_id))
+                .header("authorization", format!("Bearer {}", token))
+                .to_request()
+        };
+        let res = test::call_service(&mut app, create(&asset_id)).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let res = test::call_service(&mut app, create(&other)).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let admin = || {
+            test::TestRequest::get()
+                .uri("/admin/templates/1/contracts")
+                .header("authorization", format!("Bearer {}", token))
+                .to_request()
+        };
+        // API access does not grant admin access
+        let res = test::call_service(&mut app, admin()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        Access::grant(
+            NewAccess {
+                pub_key: PUBKEY.into(),
+                resource: AccessResource::Admin,
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let res = test::call_service(&mut app, admin()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let query = SelectAccess {
+            pub_key: Some(PUBKEY.into()),
+            resource: AccessResource::Admin,
+            ..SelectAccess::default()
+        };
+        let access = Access::select(query, &client).await.unwrap().pop().unwrap();
+        Access::revoke_key(access.id, &client).await.unwrap();
+        let res = test::call_service(&mut app, admin()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "access_revoked");
+
+        Access::grant(
+            NewAccess {
+                pub_key: PUBKEY.into(),
+                expires_at: Some(chrono::Utc::now() - chrono::Duration::minutes(1)),
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let res = test::call_service(&mut app, call("issue_tokens", Some(&token))).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["code"], "access_expired");
+    }
+
+    #[actix_rt::test]
+    async fn asset_reads() {
+        let (client, _lock) = test_db_client().await;
+        let secret = JwtSecret(b"secret".to_vec());
+        let mut app = test::init_service(
+            App::new()
+                .data(actix_test_pool())
+                .data(secret.clone())
+                .wrap(Authentication::new())
+                .route("/instructions/{id}", web::get().to(|| HttpResponse::Ok()))
+                .route("/instructions/{id}/result", web::get().to(|| HttpResponse::Ok()))
+                .route("/tokens/{id}/metadata", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+        let token = AccessToken::new(PUBKEY.into(), ACCESS_TOKEN_ISSUER.into(), 10)
+            .encode(&secret)
+            .unwrap();
+        let get = |uri: String, token: Option<&str>| {
+            let mut req = test::TestRequest::get().uri(&uri);
+            if let Some(token) = token {
+                req = req.header("authorization", format!("Bearer {}", token));
+            }
+            req.to_request()
+        };
+
+        let mut instructions = vec![];
+        let mut assets = vec![];
+        for _ in 0..2 {
+            let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+            let instruction = InstructionBuilder {
+                asset_id: Some(asset.asset_id.clone()),
+                ..InstructionBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+            instructions.push(instruction.id);
+            assets.push(asset.asset_id);
+        }
+        let res = test::call_service(&mut app, get(format!("/instructions/{}", instructions[0]), None)).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Access::grant(
+            NewAccess {
+                pub_key: PUBKEY.into(),
+                assets: vec![assets[0].clone()],
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let token = Some(token.as_str());
+        for (uri, status) in vec![
+            (format!("/instructions/{}", instructions[0]), StatusCode::OK),
+            (format!("/instructions/{}/result", instructions[0]), StatusCode::OK),
+            (format!("/instructions/{}", instructions[1]), StatusCode::FORBIDDEN),
+            (format!("/instructions/{}/result", instructions[1]), StatusCode::FORBIDDEN),
+            // instruction which doesn't exist has unknown asset
+            (format!("/instructions/{}", Test::<InstructionID>::new()), StatusCode::FORBIDDEN),
+            (
+                format!("/tokens/{}/metadata", Test::<TokenID>::from_asset(&assets[0])),
+                StatusCode::OK,
+            ),
+            (
+                format!("/tokens/{}/metadata", Test::<TokenID>::from_asset(&assets[1])),
+                StatusCode::FORBIDDEN,
+            ),
+        ] {
+            let res = test::call_service(&mut app, get(uri.clone(), token)).await;
+            assert_eq!(res.status(), status, "{}", uri);
         }
     }
 }
@@ -0,0 +1,152 @@
+use crate::api::{
+    config::CorsConfig,
+    errors::{ApiError, ApplicationError},
+};
+use actix_cors::{Cors, CorsFactory};
+use actix_http::error::ResponseError;
+use actix_service::Service;
+use actix_web::{
+    dev::{MessageBody, ServiceRequest, ServiceResponse, Transform},
+    error,
+    http::header,
+};
+use futures::future::{ok, Either, Ready};
+use std::{
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+/// Builds actix CORS middleware from [CorsConfig]
+///
+/// Unless any origin is allowed, request Origin is echoed back by the middleware,
+/// it has to be guarded with [AllowedOrigins] to support wildcard subdomains
+pub fn cors_middleware(config: &CorsConfig) -> CorsFactory {
+    let mut cors = Cors::new();
+    if config.allows_any_origin() {
+        cors = cors.send_wildcard();
+    }
+    cors = cors.allowed_methods(config.allowed_methods.iter().map(String::as_str));
+    for allowed_header in config.allowed_headers.iter() {
+        cors = cors.allowed_header(allowed_header.as_str());
+    }
+    if let Some(max_age) = config.max_age {
+        cors = cors.max_age(max_age);
+    }
+    cors.expose_headers(vec!["x-app-version"]).finish()
+}
+
+/// Rejects requests from origins not matching [CorsConfig::allowed_origins]
+pub struct AllowedOrigins {
+    config: Rc<CorsConfig>,
+}
+
+impl AllowedOrigins {
+    pub fn new(config: CorsConfig) -> Self {
+        Self {
+            config: Rc::new(config),
+        }
+    }
+}
+
+impl<S, B> Transform<S> for AllowedOrigins
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = error::Error>,
+    S::Future: 'static,
+    B: MessageBody,
+{
+    type Error = S::Error;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    type InitError = ();
+    type Request = S::Request;
+    type Response = S::Response;
+    type Transform = AllowedOriginsService<S>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AllowedOriginsService {
+            service,
+            config: self.config.clone(),
+        })
+    }
+}
+
+pub struct AllowedOriginsService<S> {
+    service: S,
+    config: Rc<CorsConfig>,
+}
+
+impl<S, B> Service for AllowedOriginsService<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = error::Error>,
+    S::Future: 'static,
+    B: MessageBody,
+{
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+    type Request = S::Request;
+    type Response = S::Response;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Self::Request) -> Self::Future {
+        let allowed = match request.headers().get(header::ORIGIN).map(|origin| origin.to_str()) {
+            None => true,
+            Some(Ok(origin)) => self.config.allows_origin(origin),
+            Some(Err(_)) => false,
+        };
+        if allowed {
+            return Either::Left(self.service.call(request));
+        }
+        let error: ApiError = ApplicationError::bad_request("Origin is not allowed").into();
+        Either::Right(ok(request.into_response(error.error_response().into_body())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn allowed_origins() {
+        let config = CorsConfig {
+            allowed_origins: vec!["https://app.tari.com".into(), "https://*.dapps.io".into()],
+            max_age: Some(600),
+            ..CorsConfig::default()
+        };
+        let mut app = test::init_service(
+            App::new()
+                .wrap(cors_middleware(&config))
+                .wrap(AllowedOrigins::new(config.clone()))
+                .route("/status", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+        let get = |origin: &str| {
+            test::TestRequest::get()
+                .uri("/status")
+                .header("origin", origin)
+                .to_request()
+        };
+
+        let res = test::call_service(&mut app, test::TestRequest::get().uri("/status").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&mut app, get("https://wallet.dapps.io")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://wallet.dapps.io"
+        );
+        let res = test::call_service(&mut app, get("https://evil.com")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let preflight = test::TestRequest::with_uri("/status")
+            .method(actix_web::http::Method::OPTIONS)
+            .header("origin", "https://app.tari.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .to_request();
+        let res = test::call_service(&mut app, preflight).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+    }
+}
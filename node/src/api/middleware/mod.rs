@@ -1,4 +1,5 @@
-pub use self::{app_version_header::*, authentication::*};
+pub use self::{app_version_header::*, authentication::*, cors::*};
 
 mod app_version_header;
 mod authentication;
+mod cors;
@@ -4,6 +4,7 @@ pub mod errors;
 pub mod helpers;
 pub mod middleware;
 pub mod models;
+pub mod openapi;
 pub mod routing;
 pub mod server;
 
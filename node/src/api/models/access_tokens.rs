@@ -1,12 +1,18 @@
 use crate::api::errors::{ApiError, *};
 use actix_http::http::header::Header;
-use actix_web::{dev, FromRequest, HttpRequest};
+use actix_web::{dev, web, FromRequest, HttpRequest};
 use actix_web_httpauth::headers::authorization::{Authorization, Bearer};
 use futures::future::{err, ok, Ready};
-use jsonwebtoken::{dangerous_unsafe_decode, decode, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header as JwtHeader, Validation};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+pub const ACCESS_TOKEN_ISSUER: &'static str = "tari_validator_node";
+
+/// Secret signing access tokens, installed as app data to the API server
+#[derive(Clone)]
+pub struct JwtSecret(pub Vec<u8>);
+
 #[derive(PartialEq, Debug, Serialize, Deserialize)]
 pub struct AccessToken {
     pub sub: String,
@@ -29,6 +35,27 @@ impl AccessToken {
             issued,
         }
     }
+
+    /// Signed JWT representation of the token
+    pub fn encode(&self, secret: &JwtSecret) -> Result<String, AuthError> {
+        encode(
+            &JwtHeader::new(Algorithm::HS512),
+            self,
+            &EncodingKey::from_secret(&secret.0),
+        )
+        .map_err(|_| AuthError::unauthorized("Failed to sign auth token"))
+    }
+
+    /// Verifies signature and expiry of JWT
+    pub fn decode(token: &str, secret: &JwtSecret) -> Result<Self, AuthError> {
+        decode::<AccessToken>(
+            token,
+            &DecodingKey::from_secret(&secret.0),
+            &Validation::new(Algorithm::HS512),
+        )
+        .map(|token| token.claims)
+        .map_err(|_| AuthError::unauthorized("Invalid auth token: unable to verify signature"))
+    }
 }
 
 impl FromRequest for AccessToken {
@@ -37,24 +64,15 @@ impl FromRequest for AccessToken {
     type Future = Ready<Result<AccessToken, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        let secret = match req.app_data::<web::Data<JwtSecret>>() {
+            Some(secret) => secret,
+            None => return err(AuthError::unauthorized("Auth token secret is not configured").into()),
+        };
         if let Ok(bearer_token) = Authorization::<Bearer>::parse(req) {
             let token = bearer_token.into_scheme();
-            match dangerous_unsafe_decode::<AccessToken>(&token.token()) {
-                Ok(token_without_verification) => {
-                    match DecodingKey::from_rsa_pem(token_without_verification.claims.sub.as_bytes()) {
-                        Ok(decoding_key) => {
-                            match decode(&token.token(), &decoding_key, &Validation::new(Algorithm::RS512)) {
-                                Ok(token) => ok(token.claims),
-                                Err(_) => err(AuthError::unauthorized(
-                                    "Invalid auth token: unable to verify signature",
-                                )
-                                .into()),
-                            }
-                        },
-                        Err(_) => err(AuthError::unauthorized("Invalid auth token: invalid public key format").into()),
-                    }
-                },
-                Err(_) => err(AuthError::unauthorized("Invalid auth token").into()),
+            match AccessToken::decode(&token.token(), &secret) {
+                Ok(token) => ok(token),
+                Err(error) => err(error.into()),
             }
         } else {
             err(AuthError::unauthorized("Missing auth token").into())
@@ -66,34 +84,36 @@ impl FromRequest for AccessToken {
 mod test {
     use super::*;
     use actix_web::test::TestRequest;
-    use jsonwebtoken::{encode, EncodingKey, Header as JwtHeader};
 
     #[actix_rt::test]
     async fn from_request() -> anyhow::Result<()> {
-        let access_token = AccessToken::new(
-            include_str!("../../test/data/example-public-key.pem").to_string(),
-            "tari".to_string(),
-            6000,
-        );
-        let token = encode(
-            &JwtHeader::new(Algorithm::RS512),
-            &access_token,
-            &EncodingKey::from_rsa_pem(include_bytes!("../../test/data/example-private-key.pem")).unwrap(),
-        )
-        .unwrap();
+        let secret = JwtSecret(b"secret".to_vec());
+        let access_token = AccessToken::new("pubkey".to_string(), ACCESS_TOKEN_ISSUER.to_string(), 6000);
+        let token = access_token.encode(&secret)?;
 
-        let request = TestRequest::with_header("authorization", format!("Bearer {}", token)).to_http_request();
+        let request = TestRequest::with_header("authorization", format!("Bearer {}", token))
+            .data(secret.clone())
+            .to_http_request();
         let access_token_from_request = AccessToken::from_request(&request, &mut dev::Payload::None).await?;
         assert_eq!(access_token_from_request, access_token);
 
-        // Incorrectly signed JWT fails validation
-        let token = encode(
-            &JwtHeader::new(Algorithm::RS512),
-            &access_token,
-            &EncodingKey::from_rsa_pem(include_bytes!("../../test/data/example-private-key-invalid.pem")).unwrap(),
-        )
-        .unwrap();
-        let request = TestRequest::with_header("authorization", format!("Bearer {}", token)).to_http_request();
+        // JWT signed with another secret fails validation
+        let token = access_token.encode(&JwtSecret(b"invalid".to_vec()))?;
+        let request = TestRequest::with_header("authorization", format!("Bearer {}", token))
+            .data(secret.clone())
+            .to_http_request();
+        let response = AccessToken::from_request(&request, &mut dev::Payload::None).await;
+        assert!(response.is_err());
+
+        // Expired JWT fails validation
+        let token = AccessToken {
+            exp: access_token.issued - 600,
+            ..AccessToken::new("pubkey".to_string(), ACCESS_TOKEN_ISSUER.to_string(), 0)
+        }
+        .encode(&secret)?;
+        let request = TestRequest::with_header("authorization", format!("Bearer {}", token))
+            .data(secret)
+            .to_http_request();
         let response = AccessToken::from_request(&request, &mut dev::Payload::None).await;
         assert!(response.is_err());
 
@@ -1,3 +1,5 @@
-pub use self::access_tokens::*;
+pub use self::{access_tokens::*, node_status::*, request_signature::*};
 
 mod access_tokens;
+mod node_status;
+mod request_signature;
@@ -0,0 +1,51 @@
+//! Status of the running node served by `GET /admin/status`, printed by `tvnc status`
+
+use crate::{
+    api::controllers::status::Status,
+    db::models::InstructionStatus,
+    template::TemplateRoutes,
+    types::{NodeID, TemplateID},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Moment the API server was started
+#[derive(Clone, Copy, Debug)]
+pub struct StartedAt(pub DateTime<Utc>);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeStatus {
+    pub version: String,
+    pub node_id: NodeID,
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: i64,
+    pub templates: Vec<MountedTemplate>,
+    /// Number of instructions by status
+    pub instructions: HashMap<InstructionStatus, i64>,
+    /// Proposals neither finalized nor rejected
+    pub consensus_rounds: i64,
+    /// Number of assets by their last consensus state, empty if node runs without metrics collector
+    pub consensus_states: HashMap<String, u64>,
+    pub pool: Status,
+    /// Wallets not retired yet
+    pub wallets: i64,
+}
+
+/// Template served by the node with number of its contracts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MountedTemplate {
+    pub template_id: TemplateID,
+    pub asset_contracts: usize,
+    pub token_contracts: usize,
+}
+
+impl From<&TemplateRoutes> for MountedTemplate {
+    fn from(routes: &TemplateRoutes) -> Self {
+        Self {
+            template_id: routes.template_id,
+            asset_contracts: routes.asset.len(),
+            token_contracts: routes.token.len(),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Signature of contract params submitted to asset and token calls
+//!
+//! Client signs canonical JSON (object keys sorted, no whitespace) of the request body wrapped into
+//! the call it targets, see [SignedCall], with [NodeIdentity::sign] scheme and passes hex pubkey
+//! and signature in headers. Verified signature is stored on the instruction along with the params.
+
+use crate::{
+    api::errors::{ApiError, AuthError},
+    identity::{IdentityError, NodeIdentity},
+    types::{AssetID, Pubkey, TokenID},
+};
+use actix_web::{client::ClientRequest, HttpRequest};
+use serde_json::{json, Value};
+use tari_core::transactions::{crypto::tari_utilities::hex::Hex, types::PublicKey};
+
+pub const SIGNER_PUBKEY_HEADER: &'static str = "X-Signer-Pubkey";
+pub const SIGNATURE_HEADER: &'static str = "X-Signature";
+
+/// Target of the signed call, signature covers it along with params,
+/// so signed body can't be replayed against another asset, token or contract
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SignedCall {
+    pub asset_id: Option<AssetID>,
+    pub token_id: Option<TokenID>,
+    /// Contract name, `batch` for batches of asset calls
+    pub contract: String,
+}
+
+impl SignedCall {
+    /// Call which doesn't target existing asset, e.g. asset creation
+    pub fn new(contract: &str) -> Self {
+        Self {
+            contract: contract.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn asset(asset_id: &AssetID, contract: &str) -> Self {
+        Self {
+            asset_id: Some(asset_id.clone()),
+            token_id: None,
+            contract: contract.into(),
+        }
+    }
+
+    pub fn token(token_id: &TokenID, contract: &str) -> Self {
+        Self {
+            asset_id: Some(token_id.asset_id()),
+            token_id: Some(token_id.clone()),
+            contract: contract.into(),
+        }
+    }
+
+    /// Canonical JSON of `{asset_id, token_id, contract, params}` signed by the client
+    pub fn message(&self, params: &Value) -> String {
+        canonical_json(&json!({
+            "asset_id": self.asset_id,
+            "token_id": self.token_id,
+            "contract": self.contract,
+            "params": params,
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestSignature {
+    pub pubkey: Pubkey,
+    pub signature: String,
+}
+
+impl RequestSignature {
+    /// Sign contract params of the `call` with the client key
+    pub fn sign(signer: &NodeIdentity, call: &SignedCall, params: &Value) -> Result<Self, IdentityError> {
+        let signature = signer.sign(call.message(params).as_bytes())?;
+        Ok(Self {
+            pubkey: signer.public_key_hex(),
+            signature,
+        })
+    }
+
+    /// Verify signature headers of the request against contract params of the `call`,
+    /// unsigned or tampered requests and requests signed for another call are rejected as unauthorized
+    pub fn verify(req: &HttpRequest, call: &SignedCall, params: &Value) -> Result<Self, ApiError> {
+        let header = |name: &'static str| req.headers().get(name).and_then(|value| value.to_str().ok());
+        let (pubkey, signature) = match (header(SIGNER_PUBKEY_HEADER), header(SIGNATURE_HEADER)) {
+            (Some(pubkey), Some(signature)) => (pubkey, signature),
+            _ => return Err(AuthError::unauthorized("Request signature is required").into()),
+        };
+        let public_key = PublicKey::from_hex(pubkey).map_err(|_| AuthError::unauthorized("Invalid signer pubkey"))?;
+        if !NodeIdentity::verify(&public_key, signature, call.message(params).as_bytes()) {
+            return Err(AuthError::unauthorized("Invalid request signature").into());
+        }
+        Ok(Self {
+            pubkey: pubkey.to_string(),
+            signature: signature.to_string(),
+        })
+    }
+
+    /// Attach signature headers to the client request
+    pub fn apply(&self, request: ClientRequest) -> ClientRequest {
+        request
+            .header(SIGNER_PUBKEY_HEADER, self.pubkey.as_str())
+            .header(SIGNATURE_HEADER, self.signature.as_str())
+    }
+}
+
+/// Compact JSON with object keys sorted recursively
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<_> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonical_json(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        },
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::Test;
+    use actix_web::test::TestRequest;
+    use serde_json::json;
+
+    #[test]
+    fn canonical() {
+        let value = json!({"b": [{"z": 1, "a": null}], "a": "x y"});
+        assert_eq!(canonical_json(&value), r#"{"a":"x y","b":[{"a":null,"z":1}]}"#);
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let signer = NodeIdentity::random();
+        let call = SignedCall::asset(&Test::<AssetID>::new(), "issue_tokens");
+        let params = json!({"token_ids": ["a", "b"], "quantity": null});
+        let signed = RequestSignature::sign(&signer, &call, &params).unwrap();
+
+        let req = TestRequest::default()
+            .header(SIGNER_PUBKEY_HEADER, signed.pubkey.as_str())
+            .header(SIGNATURE_HEADER, signed.signature.as_str())
+            .to_http_request();
+        let verified = RequestSignature::verify(&req, &call, &params).unwrap();
+        assert_eq!(verified.pubkey, signer.public_key_hex());
+        assert_eq!(verified.signature, signed.signature);
+        // key order does not affect signature
+        let reordered: Value = serde_json::from_str(r#"{"quantity":null,"token_ids":["a","b"]}"#).unwrap();
+        assert!(RequestSignature::verify(&req, &call, &reordered).is_ok());
+        // tampered params
+        let tampered = json!({"token_ids": ["a"], "quantity": null});
+        assert!(RequestSignature::verify(&req, &call, &tampered).is_err());
+
+        let other = NodeIdentity::random();
+        let req = TestRequest::default()
+            .header(SIGNER_PUBKEY_HEADER, other.public_key_hex())
+            .header(SIGNATURE_HEADER, signed.signature.as_str())
+            .to_http_request();
+        assert!(RequestSignature::verify(&req, &call, &params).is_err());
+
+        let req = TestRequest::default().to_http_request();
+        assert!(RequestSignature::verify(&req, &call, &params).is_err());
+    }
+
+    #[test]
+    fn replay_to_another_call() {
+        let signer = NodeIdentity::random();
+        let token_id = Test::<TokenID>::new();
+        let call = SignedCall::token(&token_id, "transfer_token");
+        let params = json!({"user_pubkey": "buyer"});
+        let signed = RequestSignature::sign(&signer, &call, &params).unwrap();
+        let req = TestRequest::default()
+            .header(SIGNER_PUBKEY_HEADER, signed.pubkey.as_str())
+            .header(SIGNATURE_HEADER, signed.signature.as_str())
+            .to_http_request();
+        assert!(RequestSignature::verify(&req, &call, &params).is_ok());
+
+        let other_token = Test::<TokenID>::from_asset(&token_id.asset_id());
+        for replayed in vec![
+            SignedCall::token(&other_token, "transfer_token"),
+            SignedCall::token(&Test::<TokenID>::new(), "transfer_token"),
+            SignedCall::token(&token_id, "redeem_token"),
+            SignedCall::asset(&token_id.asset_id(), "transfer_token"),
+        ] {
+            assert!(
+                RequestSignature::verify(&req, &replayed, &params).is_err(),
+                "{:?}",
+                replayed
+            );
+        }
+    }
+}
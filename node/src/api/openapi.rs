@@ -0,0 +1,165 @@
+//! OpenAPI 3 document describing template routes, served at `/api/spec.json`
+//!
+//! Params schemas come from [TemplateRoutes] catalog registered by `#[derive(Contracts)]`
+
+use crate::{
+    api::models::{SIGNATURE_HEADER, SIGNER_PUBKEY_HEADER},
+    template::{ContractRoute, TemplateRoutes},
+};
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+pub const SPEC_PATH: &'static str = "/api/spec.json";
+const APP_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// OpenAPI document, built once on server start
+#[derive(Clone)]
+pub struct ApiSpec(pub Value);
+
+impl ApiSpec {
+    pub fn build(templates: &[TemplateRoutes]) -> Self {
+        let mut gen = SchemaSettings::openapi3().into_generator();
+        let mut paths = Map::new();
+        for template in templates {
+            let routes = [
+                (template.asset_root(), &template.asset),
+                (template.token_root(), &template.token),
+            ];
+            for (root, routes) in &routes {
+                for route in routes.iter() {
+                    let params = serde_json::to_value((route.params_schema)(&mut gen)).unwrap_or(Value::Null);
+                    paths.insert(format!("{}/{}", root, route.name), Self::operation(root, route, params));
+                }
+            }
+        }
+        let schemas = serde_json::to_value(gen.definitions()).unwrap_or_else(|_| json!({}));
+        Self(json!({
+            "openapi": "3.0.0",
+            "info": {
+                "title": "Tari Validator Node",
+                "version": APP_VERSION,
+            },
+            "paths": paths,
+            "components": {
+                "schemas": schemas,
+            },
+        }))
+    }
+
+    fn operation(root: &str, route: &ContractRoute, params: Value) -> Value {
+        let mut parameters: Vec<Value> = root
+            .split('/')
+            .filter(|segment| segment.starts_with('{'))
+            .map(|segment| {
+                json!({
+                    "name": segment.trim_matches(|c| c == '{' || c == '}'),
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+        if route.view {
+            // view params are flattened into query string, nothing is signed as no instruction is created
+            parameters.push(json!({
+                "name": "params",
+                "in": "query",
+                "style": "form",
+                "explode": true,
+                "schema": params,
+            }));
+            return json!({
+                "get": {
+                    "operationId": route.name,
+                    "parameters": parameters,
+                    "responses": {
+                        "200": {
+                            "description": "Contract result",
+                            "content": { "application/json": { "schema": {} } },
+                        },
+                        "400": { "description": "Invalid params" },
+                        "401": { "description": "Missing or invalid access token" },
+                        "403": { "description": "Contract is disabled or not permitted" },
+                    },
+                }
+            });
+        }
+        for header in &[SIGNER_PUBKEY_HEADER, SIGNATURE_HEADER] {
+            parameters.push(json!({
+                "name": header,
+                "in": "header",
+                "required": true,
+                "schema": { "type": "string" },
+            }));
+        }
+        parameters.push(json!({
+            "name": "dry_run",
+            "in": "query",
+            "required": false,
+            "description": "Execute contract without creating an instruction, returns result and state it would append",
+            "schema": { "type": "boolean", "default": false },
+        }));
+        json!({
+            "post": {
+                "operationId": route.name,
+                "parameters": parameters,
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": params } },
+                },
+                "responses": {
+                    "200": {
+                        "description": "Scheduled instruction or dry run result",
+                        "content": { "application/json": { "schema": { "type": "object" } } },
+                    },
+                    "400": { "description": "Invalid params" },
+                    "401": { "description": "Missing or invalid signature or access token" },
+                    "403": { "description": "Caller is not permitted to call the contract" },
+                },
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::template::{nft::NftTemplate, single_use_tokens::SingleUseTokenTemplate, Template};
+
+    #[test]
+    fn single_use_tokens_spec() {
+        let ApiSpec(spec) = ApiSpec::build(&[TemplateRoutes::of::<SingleUseTokenTemplate>()]);
+        let tpl = SingleUseTokenTemplate::id();
+        let issue_tokens = &spec["paths"][format!(
+            "/asset_call/{}/{{features}}/{{raid_id}}/{{hash}}/issue_tokens",
+            tpl
+        )];
+        assert_eq!(issue_tokens["post"]["operationId"], "issue_tokens");
+        assert_eq!(
+            issue_tokens["post"]["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/IssueTokensParams"
+        );
+        let sell_token = &spec["paths"][format!(
+            "/token_call/{}/{{features}}/{{raid_id}}/{{hash}}/{{uid}}/sell_token",
+            tpl
+        )];
+        assert_eq!(sell_token["post"]["parameters"].as_array().unwrap().len(), 7);
+        assert_eq!(sell_token["post"]["parameters"][6]["name"], "dry_run");
+        let schema = &spec["components"]["schemas"]["SellTokenParams"];
+        assert_eq!(schema["required"], json!(["price", "user_pubkey"]));
+    }
+
+    #[test]
+    fn view_spec() {
+        let ApiSpec(spec) = ApiSpec::build(&[TemplateRoutes::of::<NftTemplate>()]);
+        let token_metadata = &spec["paths"][format!(
+            "/token_call/{}/{{features}}/{{raid_id}}/{{hash}}/{{uid}}/token_metadata",
+            NftTemplate::id()
+        )];
+        assert!(token_metadata["post"].is_null());
+        assert_eq!(token_metadata["get"]["operationId"], "token_metadata");
+        let parameters = token_metadata["get"]["parameters"].as_array().unwrap();
+        assert_eq!(parameters.len(), 5);
+        assert_eq!(parameters[4]["in"], "query");
+    }
+}
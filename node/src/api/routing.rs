@@ -1,7 +1,42 @@
-use crate::api::controllers::status;
+use crate::api::{
+    controllers::{admin, assets, instructions, spec, status, templates, tokens},
+    errors::CATALOG_PATH,
+    openapi::SPEC_PATH,
+};
 use actix_web::web;
 
 pub fn routes(app: &mut web::ServiceConfig) {
     // Please try to keep in alphabetical order
+    app.service(web::resource("/admin/audit").route(web::get().to(admin::audit)));
+    app.service(web::resource("/admin/consensus").route(web::get().to(admin::consensus)));
+    app.service(web::resource("/admin/dashboard").route(web::get().to(admin::dashboard)));
+    app.service(web::resource("/admin/fees").route(web::get().to(admin::fees)));
+    app.service(web::resource("/admin/instructions").route(web::get().to(admin::instructions)));
+    app.service(web::resource("/admin/metrics").route(web::get().to(admin::metrics)));
+    app.service(web::resource("/admin/reputation").route(web::get().to(admin::reputation)));
+    app.service(web::resource("/admin/status").route(web::get().to(admin::status)));
+    app.service(web::resource("/admin/sync/assets/{id}").route(web::get().to(admin::sync_asset)));
+    app.service(web::resource("/admin/templates/{template_id}/contracts").route(web::get().to(admin::contracts)));
+    app.service(
+        web::resource("/admin/templates/{template_id}/contracts/{contract_name}")
+            .route(web::put().to(admin::switch_contract)),
+    );
+    app.service(web::resource("/assets").route(web::post().to(assets::create)));
+    app.service(web::resource("/assets/{id}/committee").route(web::get().to(assets::committee)));
+    app.service(web::resource("/assets/{id}/consensus").route(web::get().to(assets::consensus)));
+    app.service(web::resource("/assets/{id}/metadata").route(web::post().to(assets::update_metadata)));
+    app.service(web::resource("/assets/{id}/retire").route(web::post().to(assets::retire)));
+    app.service(web::resource("/assets/{id}/tokens").route(web::get().to(assets::tokens)));
+    app.service(web::resource("/assets/{id}/versions").route(web::get().to(assets::versions)));
+    app.service(web::resource(CATALOG_PATH).route(web::get().to(spec::errors)));
+    app.service(web::resource(SPEC_PATH).route(web::get().to(spec::openapi)));
+    app.service(web::resource("/instructions/{id}").route(web::get().to(instructions::show)));
+    app.service(
+        web::resource("/instructions/{id}/subinstructions").route(web::get().to(instructions::subinstructions)),
+    );
+    app.service(web::resource("/instructions/{id}/result").route(web::get().to(instructions::result)));
     app.service(web::resource("/status").route(web::get().to(status::check)));
+    app.service(web::resource("/templates").route(web::get().to(templates::index)));
+    app.service(web::resource("/templates/{id}/contracts").route(web::get().to(templates::contracts)));
+    app.service(web::resource("/tokens/{id}/metadata").route(web::get().to(tokens::metadata)));
 }
@@ -1,19 +1,52 @@
 use crate::{
-    api::{middleware::*, routing},
+    api::{
+        errors::ErrorCode,
+        middleware::*,
+        models::{JwtSecret, StartedAt},
+        openapi::ApiSpec,
+        routing,
+        LOG_TARGET,
+    },
     config::NodeConfig,
-    consensus::ConsensusProcessor,
+    consensus::{ConsensusJanitor, ConsensusProcessor},
+    db::{
+        lifecycle::AssetExpiryJanitor,
+        models::{Access, AccessResource, SelectAccess},
+        notify::InstructionNotifier,
+        retention::RetentionJanitor,
+        store::{PgStore, Store},
+        utils::db::ReadPool,
+    },
     metrics::Metrics,
-    template::{actix_web_impl::ActixTemplate, single_use_tokens::SingleUseTokenTemplate, TemplateRunner},
+    sync,
+    telemetry::TelemetryReporter,
+    template::{
+        actix_web_impl::ActixTemplate,
+        config::TemplateConfigWatcher,
+        escrow::EscrowTemplate,
+        nft::NftTemplate,
+        single_use_tokens::SingleUseTokenTemplate,
+        TemplateRoutes,
+        TemplateRunner,
+    },
+    wallet::{janitor::WalletJanitor, WalletStore},
+    webhooks::WebhookDispatcher,
 };
-use actix::Addr;
-use actix_cors::Cors;
-use actix_web::{http, middleware::Logger, web, App, HttpResponse, HttpServer};
+use actix::{Actor, Addr};
+use actix_web::{
+    middleware::{Condition, Logger},
+    web,
+    App,
+    HttpResponse,
+    HttpServer,
+};
+use chrono::Utc;
 use deadpool_postgres::Pool;
 use futures::{
     future::{select, Either},
     pin_mut,
 };
-use serde_json::json;
+use serde_json::Value;
 use std::{
     net::ToSocketAddrs,
     sync::{mpsc, Arc},
@@ -23,69 +56,198 @@ use tokio::sync::oneshot::Sender;
 // Must be valid JSON
 const LOGGER_FORMAT: &'static str = r#"{"level": "INFO", "target":"api::request", "remote_ip":"%a", "user_agent": "%{User-Agent}i", "request": "%r", "uri": "%U", "status_code": %s, "response_time": %D, "api_version":"%{x-app-version}o", "client_version": "%{X-API-Client-Version}i" }"#;
 
+/// Auth is enabled by default, so node upgraded without granting API access rejects every contract call
+async fn warn_no_access(pool: &Pool) {
+    let query = SelectAccess {
+        resource: AccessResource::Api,
+        ..SelectAccess::default()
+    };
+    let access = match pool.get().await {
+        Ok(client) => Access::select(query, &client).await,
+        Err(err) => {
+            log::warn!(target: LOG_TARGET, "Failed to check API access records: {}", err);
+            return;
+        },
+    };
+    match access {
+        Ok(access) if access.is_empty() => log::warn!(
+            target: LOG_TARGET,
+            "Auth is enabled but no API access is granted, contract calls will be rejected with 401: grant access \
+             with `tvnc access grant api -k <pubkey>` or set [validator.auth] enabled = false"
+        ),
+        Ok(_) => {},
+        Err(err) => log::warn!(target: LOG_TARGET, "Failed to check API access records: {}", err),
+    }
+}
+
 pub async fn actix_main(
-    config: NodeConfig,
+    mut config: NodeConfig,
     metrics_addr: Option<Addr<Metrics>>,
     pool: Arc<Pool>,
     mut kill_console: Sender<()>,
 ) -> anyhow::Result<()>
 {
     println!(
-        "Server starting at {}",
+        "Server starting at {}://{}",
+        if config.actix.tls.is_some() { "https" } else { "http" },
         config.actix.addr().to_socket_addrs()?.next().unwrap()
     );
 
+    config.slow_query.apply();
+
+    // asset state has to be caught up before node participates in consensus
+    if config.sync.enabled {
+        sync::catch_up(&config.sync, config.actix.tls.as_ref(), metrics_addr.clone(), &pool).await;
+    }
+
+    // components fall back to polling instructions table without notifier
+    let notifier = if config.notify.enabled {
+        let notifier = InstructionNotifier::new();
+        notifier.spawn(
+            config.notify.clone(),
+            config.postgres.get_pg_config()?,
+            metrics_addr.clone(),
+        );
+        Some(notifier)
+    } else {
+        None
+    };
+
     let mut consensus_processor = ConsensusProcessor::new(config.clone(), metrics_addr.clone());
+    if let Some(notifier) = notifier.clone() {
+        consensus_processor = consensus_processor.with_notifier(notifier);
+    }
     let (kill_sender, kill_receiver) = mpsc::channel::<()>();
     // TODO: spawn consensus processors in separate Runtime
     actix_rt::spawn(async move {
         consensus_processor.start(kill_receiver).await;
     });
 
+    if let Some(metrics_addr) = metrics_addr.clone() {
+        if config.telemetry.enabled {
+            TelemetryReporter::new(config.telemetry.clone(), metrics_addr).start();
+        }
+    }
+
+    if config.retention.enabled {
+        RetentionJanitor::new(config.retention.clone(), pool.clone()).start();
+    }
+
+    if config.consensus.gc.enabled {
+        ConsensusJanitor::new(config.consensus.gc.clone(), pool.clone(), metrics_addr.clone()).start();
+    }
+
+    AssetExpiryJanitor::new(config.node_id(), pool.clone()).start();
+
+    if config.wallet_janitor.enabled {
+        let store = WalletStore::init(config.wallets_keys_path.clone(), config.wallets_passphrase.clone())?;
+        WalletJanitor::new(config.wallet_janitor.clone(), store, pool.clone()).start();
+    }
+
+    if config.webhooks.enabled {
+        WebhookDispatcher::new(config.webhooks.clone(), pool.clone()).start();
+    }
+
     // TODO: so far predefined templates only... make templates runners configurable from main
     // TODO: make distinct pool per template, though /status endpoint will need to provide status of all pools in that
     // case
-    let sut_runner = TemplateRunner::<SingleUseTokenTemplate>::create(pool.clone(), config.clone(), metrics_addr);
+    let mut sut_runner =
+        TemplateRunner::<SingleUseTokenTemplate>::create(pool.clone(), config.clone(), metrics_addr.clone());
+    let mut escrow_runner =
+        TemplateRunner::<EscrowTemplate>::create(pool.clone(), config.clone(), metrics_addr.clone());
+    let mut nft_runner = TemplateRunner::<NftTemplate>::create(pool.clone(), config.clone(), metrics_addr.clone());
+    if let Some(notifier) = notifier {
+        sut_runner = sut_runner.with_notifier(notifier.clone());
+        escrow_runner = escrow_runner.with_notifier(notifier.clone());
+        nft_runner = nft_runner.with_notifier(notifier);
+    }
     let sut_context = sut_runner.start();
+    let escrow_context = escrow_runner.start();
+    let nft_context = nft_runner.start();
+    if let Some(path) = config.config_path.clone() {
+        let runners = vec![
+            sut_context.addr().clone().recipient(),
+            escrow_context.addr().clone().recipient(),
+            nft_context.addr().clone().recipient(),
+        ];
+        TemplateConfigWatcher::new(path, runners).start();
+    }
 
+    let auth_enabled = config.auth.enabled;
+    if auth_enabled {
+        warn_no_access(&pool).await;
+    }
+    let jwt_secret = JwtSecret(config.jwt_secret()?);
     let cors_config = config.cors.clone();
-    let mut server = HttpServer::new(move || {
-        let app = App::new()
+    let read_pool = ReadPool::build(&config, pool.clone())?;
+    let store: Arc<dyn Store> = Arc::new(PgStore::new(read_pool.0.clone()));
+    let node_id = config.node_id();
+    let signer = config.signer().expect("Node identity is not loaded");
+    let committee_candidates = config.consensus.committee_candidates.clone();
+    let raid_config = config.raid.clone();
+    let actix_config = config.actix.clone();
+    let payload_config = config.payload.clone();
+    let templates = vec![
+        TemplateRoutes::of::<SingleUseTokenTemplate>(),
+        TemplateRoutes::of::<EscrowTemplate>(),
+        TemplateRoutes::of::<NftTemplate>(),
+    ];
+    let api_spec = ApiSpec::build(&templates);
+    let started_at = StartedAt(Utc::now());
+    let server = HttpServer::new(move || {
+        let mut app = App::new();
+        // metrics are optional, admin endpoints return no metrics if collector is not running
+        if let Some(metrics_addr) = metrics_addr.clone() {
+            app = app.app_data(web::Data::new(metrics_addr));
+        }
+        let app = app
             .app_data(web::Data::new(pool.clone()))
-            .wrap({
-                let mut cors = Cors::new();
-                cors = match cors_config.allowed_origins.as_str() {
-                    "*" => cors.send_wildcard(),
-                    _ => cors.allowed_origin(&cors_config.allowed_origins),
-                };
-                cors.allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
-                    .allowed_headers(vec![
-                        http::header::AUTHORIZATION,
-                        http::header::ACCEPT,
-                        "X-API-Client-Version".parse::<http::header::HeaderName>().unwrap(),
-                    ])
-                    .allowed_header(http::header::CONTENT_TYPE)
-                    .expose_headers(vec!["x-app-version"])
-                    .max_age(3600)
-                    .finish()
-            })
+            .app_data(web::Data::new(read_pool.clone()))
+            .app_data(web::Data::new(store.clone()))
+            .app_data(web::Data::new(jwt_secret.clone()))
+            .app_data(web::Data::new(api_spec.clone()))
+            .app_data(web::Data::new(templates.clone()))
+            .app_data(web::Data::new(node_id))
+            .app_data(web::Data::new(signer.clone()))
+            .app_data(web::Data::new(committee_candidates.clone()))
+            .app_data(web::Data::new(raid_config.clone()))
+            .app_data(web::Data::new(actix_config.clone()))
+            .app_data(web::Data::new(payload_config.clone()))
+            .app_data(payload_config.json_config())
+            .app_data(web::Data::new(started_at))
+            .wrap(Condition::new(auth_enabled, Authentication::new()))
+            // CORS wraps authentication, so preflight requests don't require access token
+            .wrap(cors_middleware(&cors_config))
+            .wrap(AllowedOrigins::new(cors_config.clone()))
             .wrap(Logger::new(LOGGER_FORMAT).exclude("/status"))
-            // TODO: Should we not be using a JWT but rather something more custom?
-            //.wrap(Authentication::new())
             .wrap(AppVersionHeader::new());
 
         // the problem we solving here is for every template scope we need to install distinct app_data with DB pool
         // TODO: abstract this configuration, make it reusable in tests too
-        let scopes = SingleUseTokenTemplate::actix_scopes();
-        let with_templates = scopes
+        let app = SingleUseTokenTemplate::actix_scopes()
             .into_iter()
             .fold(app, |app, scope| app.service(scope.data(sut_context.clone())));
+        let app = EscrowTemplate::actix_scopes()
+            .into_iter()
+            .fold(app, |app, scope| app.service(scope.data(escrow_context.clone())));
+        let with_templates = NftTemplate::actix_scopes()
+            .into_iter()
+            .fold(app, |app, scope| app.service(scope.data(nft_context.clone())));
 
         with_templates
             .configure(routing::routes)
-            .default_service(web::get().to(|| HttpResponse::NotFound().json(json!({"error": "Not found"}))))
-    })
-    .bind(config.actix.addr())?;
+            .default_service(
+                web::get().to(|| HttpResponse::NotFound().json(ErrorCode::NotFound.body("Not found", Value::Null))),
+            )
+    });
+    // rustls listeners negotiate HTTP/2 via ALPN
+    let mut server = match config.actix.tls.as_ref() {
+        Some(tls) => server.bind_rustls(config.actix.addr(), tls.server_config()?)?,
+        None => server.bind(config.actix.addr())?,
+    };
+    if let (Some(tls), Some(peer_port)) = (config.actix.tls.as_ref(), config.actix.peer_port()) {
+        server = server.bind_rustls((config.actix.host, peer_port), tls.peer_server_config()?)?;
+    }
 
     if let Some(workers) = config.actix.workers {
         server = server.workers(workers);
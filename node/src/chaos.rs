@@ -0,0 +1,82 @@
+//! Fault injection for chaos testing, compiled with `chaos` feature only
+//!
+//! Faults are injected with configured probabilities: outgoing consensus messages are dropped by
+//! [communications](crate::consensus::communications), DB queries are delayed on statements preparation
+//! and transactions of [in_transaction](crate::db::utils::db::in_transaction) are rolled back instead of commit.
+//! Faults are configured by `tvnc start --chaos-*` flags, all of them are disabled by default.
+
+use rand::Rng;
+use std::{sync::RwLock, time::Duration};
+
+const LOG_TARGET: &'static str = "tari_validator_node::chaos";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChaosConfig {
+    /// Probability of dropping outgoing consensus message
+    pub drop_messages: f64,
+    /// Probability of delaying DB query by `query_delay`
+    pub delay_queries: f64,
+    pub query_delay: Duration,
+    /// Probability of failing DB transaction instead of commit
+    pub fail_transactions: f64,
+}
+
+lazy_static::lazy_static! {
+    static ref CHAOS: RwLock<ChaosConfig> = RwLock::new(ChaosConfig::default());
+}
+
+/// Replace process-wide fault injection settings
+pub fn configure(config: ChaosConfig) {
+    log::warn!(target: LOG_TARGET, "Fault injection enabled: {:?}", config);
+    *CHAOS.write().unwrap() = config;
+}
+
+/// Current fault injection settings
+pub fn config() -> ChaosConfig {
+    CHAOS.read().unwrap().clone()
+}
+
+fn roll(probability: f64) -> bool {
+    probability > 0.0 && rand::thread_rng().gen_bool(probability.min(1.0))
+}
+
+/// Whether outgoing consensus message should be dropped
+pub fn drop_message() -> bool {
+    let drop = roll(config().drop_messages);
+    if drop {
+        log::warn!(target: LOG_TARGET, "Dropping consensus message");
+    }
+    drop
+}
+
+/// Whether DB transaction should fail instead of commit
+pub fn fail_transaction() -> bool {
+    let fail = roll(config().fail_transactions);
+    if fail {
+        log::warn!(target: LOG_TARGET, "Failing DB transaction");
+    }
+    fail
+}
+
+/// Delays DB query with configured probability
+pub async fn delay_query() {
+    let config = config();
+    if roll(config.delay_queries) {
+        log::warn!(target: LOG_TARGET, "Delaying DB query for {:?}", config.query_delay);
+        tokio::time::delay_for(config.query_delay).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn probabilities() {
+        assert!(!roll(0.0));
+        assert!(!roll(-1.0));
+        assert!(roll(1.0));
+        assert!(roll(2.0));
+        assert_eq!(config(), ChaosConfig::default());
+    }
+}
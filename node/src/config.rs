@@ -1,34 +1,123 @@
 use crate::{
-    api::config::{ActixConfig, CorsConfig},
+    api::config::{ActixConfig, AuthConfig, CorsConfig, PayloadConfig},
     consensus::ConsensusConfig,
+    db::{notify::NotifyConfig, retention::RetentionConfig, utils::instrument::SlowQueryConfig},
+    fees::FeesConfig,
+    identity::{IdentityError, NodeIdentity},
+    raid::RaidConfig,
+    sync::SyncConfig,
+    telemetry::{TelemetryConfig, TracingConfig},
     template::config::TemplateConfig,
+    types::NodeID,
+    wallet::{janitor::WalletJanitorConfig, ExternalSigner, Signer, SignerConfig, SignerKind, MASTER_KEY_DOMAIN},
+    webhooks::WebhooksConfig,
 };
 use config::{Config, Environment, Source, Value};
 use deadpool::managed::PoolConfig;
 use deadpool_postgres::config::Config as DeadpoolConfig;
+use schemars::JsonSchema;
 use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use std::{fmt, net::TcpListener, path::Path, sync::Arc, time::Duration};
 use tari_common::{ConfigurationError, DefaultConfigLoader, GlobalConfig, NetworkConfigPath};
 
+pub mod annotated;
+pub use annotated::PostgresConfigSchema;
+
 pub const DEFAULT_DBNAME: &'static str = "validator";
+pub const DEFAULT_CONNECT_TIMEOUT: u64 = 30;
+/// Max seconds `tvnc config check` waits for Postgres
+pub const CHECK_CONNECT_TIMEOUT: u64 = 5;
+/// Sections of `[validator]` config overloaded by env vars with the prefix, e.g. ACTIX_PORT
+pub const ENV_PREFIXES: &[(&str, &str)] = &[
+    ("actix", "ACTIX"),
+    ("postgres", "PG"),
+    ("slow_query", "SLOW_QUERY"),
+    ("cors", "CORS"),
+    ("auth", "AUTH"),
+    ("consensus", "CONSENSUS"),
+    ("template", "TEMPLATE"),
+    ("telemetry", "TELEMETRY"),
+    ("tracing", "TRACING"),
+    ("retention", "RETENTION"),
+    ("wallet_janitor", "WALLET_JANITOR"),
+    ("webhooks", "WEBHOOKS"),
+    ("notify", "NOTIFY"),
+    ("fees", "FEES"),
+    ("raid", "RAID"),
+    ("sync", "SYNC"),
+    ("signer", "SIGNER"),
+    ("payload", "PAYLOAD"),
+];
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct NodeConfig {
     /// will load from [validator.actix], overloaded with ACTIX_* env vars
     pub actix: ActixConfig,
     /// will load from [validator.postgres], overloaded with PG_* env vars
     /// see [deadpool_postgres::config::Config] on env + config vars details
     #[serde(serialize_with = "default_postgres_config")]
+    #[schemars(with = "PostgresConfigSchema")]
     pub postgres: DeadpoolConfig,
+    /// will load from [validator.postgres_read], overloaded with PG_READ_* env vars
+    /// Optional read replica serving load/list/find queries, all reads go to primary if not configured
+    #[serde(skip_serializing)]
+    #[schemars(with = "Option<PostgresConfigSchema>")]
+    pub postgres_read: Option<DeadpoolConfig>,
+    /// Seconds to keep retrying initial connection to Postgres on startup, loaded from
+    /// [validator.postgres.connect_timeout] or PG_CONNECT_TIMEOUT env var. Defaults to 30, 0 disables retries
+    #[serde(skip)]
+    pub postgres_connect_timeout: u64,
+    /// will load from [validator.slow_query], overloaded with SLOW_QUERY_* env vars
+    pub slow_query: SlowQueryConfig,
     /// will load from [validator.cors], overloaded with CORS_* env vars
     pub cors: CorsConfig,
+    /// will load from [validator.auth], overloaded with AUTH_* env vars
+    pub auth: AuthConfig,
+    /// will load from [validator.payload], overloaded with PAYLOAD_* env vars
+    pub payload: PayloadConfig,
     /// Path to directory for storing wallets keys. Defaults to `~/.tari/wallets`
     pub wallets_keys_path: std::path::PathBuf,
+    /// Passphrase encrypting wallets key files, key files are stored as plaintext if not set
+    #[serde(skip_serializing)]
+    pub wallets_passphrase: Option<String>,
     /// Node's public address. Defaults to [tari.public_address]
+    #[schemars(with = "Option<String>")]
     pub public_address: Option<multiaddr::Multiaddr>,
     /// will load from [validator.consensus], overloaded with CONSENSUS_* env vars
     pub consensus: ConsensusConfig,
-    /// will load from [validator.consensus], overloaded with CONSENSUS_* env vars
+    /// will load from [validator.template], overloaded with TEMPLATE_* env vars
     pub template: TemplateConfig,
+    /// will load from [validator.telemetry], overloaded with TELEMETRY_* env vars
+    pub telemetry: TelemetryConfig,
+    /// will load from [validator.tracing], overloaded with TRACING_* env vars
+    pub tracing: TracingConfig,
+    /// will load from [validator.retention], overloaded with RETENTION_* env vars
+    pub retention: RetentionConfig,
+    /// will load from [validator.wallet_janitor], overloaded with WALLET_JANITOR_* env vars
+    pub wallet_janitor: WalletJanitorConfig,
+    /// will load from [validator.webhooks], overloaded with WEBHOOKS_* env vars
+    pub webhooks: WebhooksConfig,
+    /// will load from [validator.notify], overloaded with NOTIFY_* env vars
+    pub notify: NotifyConfig,
+    /// will load from [validator.fees], overloaded with FEES_* env vars
+    pub fees: FeesConfig,
+    /// will load from [validator.raid], overloaded with RAID_* env vars
+    pub raid: RaidConfig,
+    /// will load from [validator.sync], overloaded with SYNC_* env vars
+    pub sync: SyncConfig,
+    /// will load from [validator.signer], overloaded with SIGNER_* env vars
+    pub signer: SignerConfig,
+    /// Path to node identity file. Defaults to `~/.tari/validator_node_id.json`
+    pub identity_path: Option<std::path::PathBuf>,
+    /// Node identity, loaded from `identity_path` via [NodeConfig::load_identity]
+    #[serde(skip)]
+    pub identity: Option<NodeIdentity>,
+    /// Signer of consensus messages, loaded via [NodeConfig::load_signer]
+    #[serde(skip)]
+    pub node_signer: Option<Arc<dyn Signer>>,
+    /// Path to the config file node was loaded from, watched for template config changes
+    #[serde(skip)]
+    pub config_path: Option<std::path::PathBuf>,
 }
 
 impl NetworkConfigPath for NodeConfig {
@@ -41,16 +130,14 @@ impl NodeConfig {
     pub fn load_from(config: &Config, global: &GlobalConfig, env: bool) -> Result<Self, ConfigurationError> {
         let mut config = config.clone();
         if env {
-            let actix = Environment::with_prefix("ACTIX").collect()?;
-            let pg = Environment::with_prefix("PG").collect()?;
-            let cors = Environment::with_prefix("CORS").collect()?;
-            let consensus = Environment::with_prefix("CONSENSUS").collect()?;
-            let template = Environment::with_prefix("TEMPLATE").collect()?;
-            config.set("validator.actix", actix).unwrap();
-            config.set("validator.postgres", pg).unwrap();
-            config.set("validator.cors", cors).unwrap();
-            config.set("validator.consensus", consensus).unwrap();
-            config.set("validator.template", template).unwrap();
+            for (section, prefix) in ENV_PREFIXES {
+                let env = Environment::with_prefix(prefix).collect()?;
+                config.set(&format!("validator.{}", section), env).unwrap();
+            }
+            let pg_read = Environment::with_prefix("PG_READ").collect()?;
+            if pg_read.len() > 0 {
+                config.set("validator.postgres_read", pg_read).unwrap();
+            }
             if let Some(pg_pool) = Self::pg_pool_from_env()? {
                 config.set("validator.postgres.pool", pg_pool.collect()?).unwrap();
             }
@@ -66,7 +153,160 @@ impl NodeConfig {
             "validator.postgres.pool.max_size",
             PoolConfig::default().max_size as i64,
         );
-        <Self as DefaultConfigLoader>::load_from(&config)
+        // connect_timeout is startup retry period in seconds rather than deadpool's per connection Duration
+        let connect_timeout = config
+            .get::<u64>("validator.postgres.connect_timeout")
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+        config.set("validator.postgres.connect_timeout", None::<i64>).unwrap();
+        let mut node_config = <Self as DefaultConfigLoader>::load_from(&config)?;
+        node_config.postgres_connect_timeout = connect_timeout;
+        if let Ok(template) = config.get("validator.template") {
+            node_config.template.set_sections(template);
+        }
+        Ok(node_config)
+    }
+
+    /// Loads [NodeIdentity] from `identity_path`
+    pub fn load_identity(&mut self) -> Result<&NodeIdentity, IdentityError> {
+        if self.identity.is_none() {
+            let path = self.identity_path.clone().ok_or_else(|| IdentityError::NotFound {
+                path: "<identity_path is not configured>".into(),
+            })?;
+            self.identity = Some(NodeIdentity::load(&path)?);
+        }
+        Ok(self.identity.as_ref().unwrap())
+    }
+
+    /// Loads [Signer] configured in `[validator.signer]`, node identity is not required for external signer
+    pub fn load_signer(&mut self) -> Result<Arc<dyn Signer>, IdentityError> {
+        if self.node_signer.is_none() {
+            let signer: Arc<dyn Signer> = match self.signer.kind {
+                SignerKind::Local => Arc::new(self.load_identity()?.clone()),
+                SignerKind::External => Arc::new(ExternalSigner::from_config(&self.signer)?),
+            };
+            self.node_signer = Some(signer);
+        }
+        Ok(self.node_signer.clone().unwrap())
+    }
+
+    /// Loaded signer, falls back to node identity, None if neither is loaded
+    pub fn signer(&self) -> Option<Arc<dyn Signer>> {
+        self.node_signer.clone().or_else(|| {
+            self.identity
+                .clone()
+                .map(|identity| Arc::new(identity) as Arc<dyn Signer>)
+        })
+    }
+
+    /// Secret for signing API access tokens: [AuthConfig::secret] or derived from node identity
+    pub fn jwt_secret(&mut self) -> Result<Vec<u8>, IdentityError> {
+        match self.auth.secret.clone() {
+            Some(secret) => Ok(secret.into_bytes()),
+            None => Ok(self.load_identity()?.derive_secret("api-access-token")),
+        }
+    }
+
+    /// Node master key temporary wallets are derived from, bound to node identity
+    pub fn wallets_master_key(&mut self) -> Result<Vec<u8>, IdentityError> {
+        Ok(self.load_identity()?.derive_secret(MASTER_KEY_DOMAIN))
+    }
+
+    /// [NodeID] of this node
+    ///
+    /// ## Panics
+    /// It will panic if neither signer nor identity was loaded, see [NodeConfig::load_signer]
+    pub fn node_id(&self) -> NodeID {
+        self.signer()
+            .map(|signer| signer.node_id())
+            .expect("Node identity is not loaded, run `tvnc identity init` first")
+    }
+
+    /// Cross-field constraints of the configuration which are checked without touching the environment
+    pub fn validate(&self) -> Vec<ConfigCheck> {
+        let mut checks = vec![];
+        let committee = !self.consensus.committee_candidates.is_empty();
+        checks.push(match (committee, self.public_address.as_ref()) {
+            (true, None) => ConfigCheck::error("public_address", "required when committee_candidates are configured"),
+            (_, Some(address)) => ConfigCheck::ok("public_address", address.to_string()),
+            (false, None) => ConfigCheck::warning("public_address", "not set, node can't join public committees"),
+        });
+        checks.push(match self.postgres.dbname.as_ref() {
+            Some(dbname) => ConfigCheck::ok("postgres.dbname", dbname.clone()),
+            None => ConfigCheck::error("postgres.dbname", "database name is not set"),
+        });
+        if self.auth.enabled && self.auth.secret.is_none() && self.identity_path.is_none() {
+            checks.push(ConfigCheck::error(
+                "auth",
+                "either auth.secret or identity_path is required to sign access tokens",
+            ));
+        }
+        if self.signer.kind == SignerKind::External {
+            if let Err(err) = ExternalSigner::from_config(&self.signer) {
+                checks.push(ConfigCheck::error("signer", err.to_string()));
+            }
+        }
+        if let Some(tls) = self.actix.tls.as_ref() {
+            match (tls.peer_port, tls.client_ca_path.as_ref()) {
+                (Some(_), None) => checks.push(ConfigCheck::error("actix.tls.peer_port", "requires client_ca_path")),
+                (Some(port), _) if port == self.actix.port => {
+                    checks.push(ConfigCheck::error("actix.tls.peer_port", "should differ from actix.port"))
+                },
+                _ => {},
+            }
+        }
+        if self.telemetry.enabled && self.telemetry.endpoint.is_none() {
+            checks.push(ConfigCheck::error("telemetry.endpoint", "required when telemetry is enabled"));
+        }
+        if self.tracing.enabled && !(0.0..=1.0).contains(&self.tracing.sample_ratio) {
+            checks.push(ConfigCheck::error("tracing.sample_ratio", "should be from 0.0 to 1.0"));
+        }
+        if self.sync.enabled && self.sync.peers.is_empty() {
+            checks.push(ConfigCheck::error("sync.peers", "required when sync is enabled"));
+        }
+        if self.wallet_janitor.enabled && self.wallet_janitor.main_wallet.is_none() {
+            checks.push(ConfigCheck::warning(
+                "wallet_janitor.main_wallet",
+                "not set, balances of removed wallets are not swept",
+            ));
+        }
+        checks
+    }
+
+    /// [NodeConfig::validate] along with checks of the environment node is about to run in:
+    /// wallets path is writable, identity exists, Postgres is reachable and API port is free
+    pub async fn diagnose(&self) -> Vec<ConfigCheck> {
+        let mut checks = self.validate();
+        checks.push(check_writable("wallets_keys_path", &self.wallets_keys_path));
+        checks.push(match self.identity_path.as_ref() {
+            _ if self.signer.kind == SignerKind::External && self.auth.secret.is_some() => {
+                ConfigCheck::ok("identity_path", "not used, node signs with external signer")
+            },
+            Some(path) if path.exists() => ConfigCheck::ok("identity_path", path.display().to_string()),
+            Some(path) => ConfigCheck::error(
+                "identity_path",
+                format!("{} not found, run `tvnc identity init`", path.display()),
+            ),
+            None => ConfigCheck::error("identity_path", "not configured"),
+        });
+        checks.push(check_postgres("postgres", &self.postgres).await);
+        if let Some(postgres_read) = self.postgres_read.as_ref() {
+            checks.push(check_postgres("postgres_read", postgres_read).await);
+        }
+        checks.push(match TcpListener::bind((self.actix.host, self.actix.port)) {
+            Ok(_) => ConfigCheck::ok("actix", format!("{}:{} is free", self.actix.host, self.actix.port)),
+            Err(err) => ConfigCheck::error("actix", format!("{}:{} {}", self.actix.host, self.actix.port, err)),
+        });
+        if let Some(tls) = self.actix.tls.as_ref() {
+            let peer_tls = match self.actix.peer_port() {
+                Some(_) => tls.peer_server_config().map(|_| ()),
+                None => Ok(()),
+            };
+            checks.push(match tls.server_config().and(peer_tls) {
+                Ok(_) => ConfigCheck::ok("actix.tls", tls.cert_path.display().to_string()),
+                Err(err) => ConfigCheck::error("actix.tls", err.to_string()),
+            });
+        }
+        checks
     }
 
     fn set_default<T: Into<Value>>(config: &mut Config, key: &str, value: T) {
@@ -115,6 +355,86 @@ impl NodeConfig {
     }
 }
 
+/// Severity of [ConfigCheck], node is not expected to run with errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckLevel {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for CheckLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ok => write!(f, "OK"),
+            Self::Warning => write!(f, "WARN"),
+            Self::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// Result of a configuration check, see [NodeConfig::diagnose]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigCheck {
+    /// Config key the check relates to
+    pub key: String,
+    pub level: CheckLevel,
+    pub message: String,
+}
+
+impl ConfigCheck {
+    pub fn ok<K: Into<String>, M: Into<String>>(key: K, message: M) -> Self {
+        Self::new(key, CheckLevel::Ok, message)
+    }
+
+    pub fn warning<K: Into<String>, M: Into<String>>(key: K, message: M) -> Self {
+        Self::new(key, CheckLevel::Warning, message)
+    }
+
+    pub fn error<K: Into<String>, M: Into<String>>(key: K, message: M) -> Self {
+        Self::new(key, CheckLevel::Error, message)
+    }
+
+    fn new<K: Into<String>, M: Into<String>>(key: K, level: CheckLevel, message: M) -> Self {
+        Self {
+            key: key.into(),
+            level,
+            message: message.into(),
+        }
+    }
+}
+
+/// Directory is writable if a file can be created in it
+fn check_writable(key: &str, path: &Path) -> ConfigCheck {
+    if !path.is_dir() {
+        return ConfigCheck::error(key, format!("{} is not a directory", path.display()));
+    }
+    let probe = path.join(".tvnc_config_check");
+    match std::fs::write(&probe, b"") {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            ConfigCheck::ok(key, path.display().to_string())
+        },
+        Err(err) => ConfigCheck::error(key, format!("{} is not writable: {}", path.display(), err)),
+    }
+}
+
+async fn check_postgres(key: &str, config: &DeadpoolConfig) -> ConfigCheck {
+    let mut pg = match config.get_pg_config() {
+        Ok(pg) => pg,
+        Err(err) => return ConfigCheck::error(key, err.to_string()),
+    };
+    pg.connect_timeout(Duration::from_secs(CHECK_CONNECT_TIMEOUT));
+    match pg.connect(tokio_postgres::NoTls).await {
+        Ok(_) => ConfigCheck::ok(key, format!("connected to {}", config.dbname.as_deref().unwrap_or_default())),
+        // connection errors are wrapped, source tells refused connection from failed DNS lookup
+        Err(err) => match std::error::Error::source(&err) {
+            Some(source) => ConfigCheck::error(key, format!("{}: {}", err, source)),
+            None => ConfigCheck::error(key, err.to_string()),
+        },
+    }
+}
+
 // Database default parameters
 fn default_postgres_config<S: Serializer>(_: &DeadpoolConfig, s: S) -> Result<S::Ok, S::Error> {
     let mut db = s.serialize_map(None)?;
@@ -126,8 +446,11 @@ fn default_postgres_config<S: Serializer>(_: &DeadpoolConfig, s: S) -> Result<S:
 mod test {
     use super::*;
     use crate::{
-        api::config::actix::{DEFAULT_ADDR, DEFAULT_PORT},
-        test::utils::build_test_global_config,
+        api::config::{
+            actix::{DEFAULT_ADDR, DEFAULT_PORT},
+            TlsConfig,
+        },
+        test::utils::{build_test_global_config, Test},
     };
     use config::{Config, File, FileFormat::Toml};
     use deadpool_postgres::config::*;
@@ -145,7 +468,32 @@ mod test {
         assert_eq!(cfg.actix.host, DEFAULT_ADDR);
         assert_eq!(cfg.postgres.host, None);
         assert_eq!(cfg.postgres.dbname, Some(DEFAULT_DBNAME.into()));
-        assert_eq!(cfg.cors.allowed_origins, "*");
+        assert!(cfg.postgres_read.is_none());
+        assert_eq!(cfg.postgres_connect_timeout, DEFAULT_CONNECT_TIMEOUT);
+        assert!(cfg.slow_query.enabled);
+        assert_eq!(cfg.slow_query.threshold_ms, 500);
+        assert_eq!(cfg.cors.allowed_origins, vec!["*".to_string()]);
+        assert_eq!(cfg.cors.max_age, Some(3600));
+        assert!(!cfg.telemetry.enabled);
+        assert_eq!(cfg.telemetry.endpoint, None);
+        assert!(!cfg.tracing.enabled);
+        assert_eq!(cfg.tracing.sample_ratio, 1.0);
+        assert!(!cfg.retention.enabled);
+        assert_eq!(cfg.retention.keep_checkpoints, 10);
+        assert!(!cfg.consensus.gc.enabled);
+        assert_eq!(cfg.consensus.gc.retention_days, 30);
+        assert!(!cfg.consensus.cluster.enabled);
+        assert_eq!(cfg.consensus.cluster.stale_after, 15);
+        assert!(!cfg.wallet_janitor.enabled);
+        assert_eq!(cfg.wallet_janitor.retention_hours, 168);
+        assert!(cfg.webhooks.enabled);
+        assert_eq!(cfg.webhooks.max_attempts, 10);
+        assert!(cfg.notify.enabled);
+        assert!(!cfg.fees.enabled);
+        assert_eq!(cfg.fees.default_fee, 0);
+        assert!(!cfg.raid.enabled);
+        assert!(!cfg.sync.enabled);
+        assert_eq!(cfg.sync.batch_size, 500);
         assert_eq!(
             cfg.postgres.manager.map(|m| m.recycling_method),
             Some(RecyclingMethod::Fast)
@@ -156,12 +504,27 @@ mod test {
     [validator.postgres]
     host = "localhost"
     user = "postgres"
+    connect_timeout = 5
     pool = { timeouts = { wait = {secs = 5, nanos = 0} } }
+    [validator.postgres_read]
+    host = "replica"
+    user = "reader"
     [validator]
     actix = { workers = 3, port = 9999 }
-    cors = { allowed_origins = "https://www.tari.com"}
-    consensus = { workers = 10 }
-    template = { runner_max_jobs = 10 }
+    slow_query = { threshold_ms = 100 }
+    cors = { allowed_origins = "https://www.tari.com, https://*.tari.io", allowed_methods = ["GET", "POST"], max_age = 60 }
+    auth = { enabled = false, token_expiry_minutes = 10 }
+    consensus = { workers = 10, committee_candidates = [{ node_id = "000102030405", collateral = 100 }] }
+    template = { runner_max_jobs = 10, contracts = { sell_token = { enabled = false } }, single_use_tokens = { sell_timeout_secs = 30 } }
+    telemetry = { enabled = true, endpoint = "http://localhost:8080/report", report_period = 60 }
+    tracing = { enabled = true, endpoint = "otel-collector:55680", service_name = "validator_1", sample_ratio = 0.1 }
+    retention = { enabled = true, keep_checkpoints = 3, max_age_days = 7, period = 600 }
+    wallet_janitor = { enabled = true, main_wallet = "7e6f4b80", retention_hours = 24, period = 60 }
+    webhooks = { enabled = false, period = 1, batch_size = 10, max_attempts = 3, backoff = 30, timeout = 5 }
+    notify = { enabled = false, reconnect_period = 10 }
+    raid = { enabled = true, timeout = 2 }
+    sync = { enabled = true, batch_size = 100, timeout = 3, peers = [{ url = "http://10.0.0.2:3001", pubkey = "aa", token = "t" }] }
+    fees = { enabled = true, default_fee = 1, templates = { 1 = { fee = 5, contracts = { transfer_token = 10 } } } }
     "#;
 
     #[test]
@@ -178,13 +541,68 @@ mod test {
         assert_eq!(cfg.postgres.dbname, Some(DEFAULT_DBNAME.into()));
         assert_eq!(cfg.postgres.user, Some("postgres".into()));
         assert_eq!(cfg.postgres.password, None);
+        assert_eq!(cfg.postgres_connect_timeout, 5);
+        assert!(cfg.slow_query.enabled);
+        assert_eq!(cfg.slow_query.threshold_ms, 100);
+        let postgres_read = cfg.postgres_read.clone().unwrap();
+        assert_eq!(postgres_read.host, Some("replica".into()));
+        assert_eq!(postgres_read.user, Some("reader".into()));
         assert_eq!(
             cfg.postgres.pool.map(|p| p.timeouts.wait).flatten(),
             Some(Duration::from_secs(5))
         );
-        assert_eq!(cfg.cors.allowed_origins, "https://www.tari.com".to_string());
+        assert_eq!(cfg.cors.allowed_origins, vec![
+            "https://www.tari.com".to_string(),
+            "https://*.tari.io".to_string()
+        ]);
+        assert_eq!(cfg.cors.allowed_methods, vec!["GET".to_string(), "POST".to_string()]);
+        assert_eq!(cfg.cors.max_age, Some(60));
+        assert!(!cfg.auth.enabled);
+        assert_eq!(cfg.auth.secret, None);
+        assert_eq!(cfg.auth.token_expiry_minutes, 10);
         assert_eq!(cfg.consensus.workers, Some(10));
+        assert_eq!(cfg.consensus.committee_candidates[0].node_id, "000102030405");
+        assert_eq!(cfg.consensus.committee_candidates[0].collateral, 100);
+        assert_eq!(cfg.consensus.lock_stale_after(), 15);
         assert_eq!(cfg.template.runner_max_jobs, 10);
+        assert!(!cfg.template.is_enabled("sell_token"));
+        assert!(cfg.template.is_enabled("transfer_token"));
+        assert_eq!(cfg.template.templates.len(), 1);
+        assert_eq!(cfg.template.templates["single_use_tokens"]["sell_timeout_secs"], 30);
+        assert!(cfg.telemetry.enabled);
+        assert_eq!(cfg.telemetry.endpoint, Some("http://localhost:8080/report".into()));
+        assert_eq!(cfg.telemetry.report_period, 60);
+        assert!(cfg.tracing.enabled);
+        assert_eq!(cfg.tracing.endpoint, "otel-collector:55680");
+        assert_eq!(cfg.tracing.service_name, "validator_1");
+        assert_eq!(cfg.tracing.sample_ratio, 0.1);
+        assert!(cfg.retention.enabled);
+        assert_eq!(cfg.retention.keep_checkpoints, 3);
+        assert_eq!(cfg.retention.max_age_days, 7);
+        assert_eq!(cfg.retention.period, 600);
+        assert!(cfg.wallet_janitor.enabled);
+        assert_eq!(cfg.wallet_janitor.main_wallet, Some("7e6f4b80".into()));
+        assert_eq!(cfg.wallet_janitor.retention_hours, 24);
+        assert_eq!(cfg.wallet_janitor.period, 60);
+        assert!(!cfg.webhooks.enabled);
+        assert_eq!(cfg.webhooks.period, 1);
+        assert_eq!(cfg.webhooks.batch_size, 10);
+        assert_eq!(cfg.webhooks.max_attempts, 3);
+        assert_eq!(cfg.webhooks.backoff, 30);
+        assert_eq!(cfg.webhooks.timeout, 5);
+        assert!(!cfg.notify.enabled);
+        assert_eq!(cfg.notify.reconnect_period, 10);
+        assert!(cfg.raid.enabled);
+        assert_eq!(cfg.raid.timeout, 2);
+        assert!(cfg.sync.enabled);
+        assert_eq!(cfg.sync.batch_size, 100);
+        assert_eq!(cfg.sync.timeout, 3);
+        assert_eq!(cfg.sync.peers[0].url, "http://10.0.0.2:3001");
+        assert!(cfg.sync.assets.is_empty());
+        assert!(cfg.fees.enabled);
+        assert_eq!(cfg.fees.fee(&1.into(), "transfer_token"), 10);
+        assert_eq!(cfg.fees.fee(&1.into(), "issue_tokens"), 5);
+        assert_eq!(cfg.fees.fee(&2.into(), "issue_tokens"), 1);
     }
 
     const TEST_CONFIG_NETWORK: &'static str = r#"
@@ -221,6 +639,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn validate_config() {
+        let global = build_test_global_config().unwrap();
+        let mut settings = Config::new();
+        settings.merge(File::from_str(TEST_CONFIG, Toml)).unwrap();
+        let mut cfg = NodeConfig::load_from(&settings, &global, false).unwrap();
+        let errors = |cfg: &NodeConfig| -> Vec<String> {
+            cfg.validate()
+                .into_iter()
+                .filter(|check| check.level == CheckLevel::Error)
+                .map(|check| check.key)
+                .collect()
+        };
+        assert!(errors(&cfg).is_empty(), "{:?}", cfg.validate());
+
+        cfg.public_address = None;
+        cfg.sync.peers = vec![];
+        cfg.telemetry.endpoint = None;
+        cfg.tracing.sample_ratio = 1.5;
+        assert_eq!(errors(&cfg), vec![
+            "public_address".to_string(),
+            "telemetry.endpoint".to_string(),
+            "tracing.sample_ratio".to_string(),
+            "sync.peers".to_string(),
+        ]);
+        // public address is optional unless node is a committee candidate
+        cfg.consensus.committee_candidates = vec![];
+        let check = cfg.validate().into_iter().find(|check| check.key == "public_address").unwrap();
+        assert_eq!(check.level, CheckLevel::Warning);
+
+        cfg.actix.tls = Some(TlsConfig {
+            cert_path: "node.crt".into(),
+            key_path: "node.key".into(),
+            client_ca_path: None,
+            peer_port: Some(cfg.actix.port),
+        });
+        assert!(errors(&cfg).contains(&"actix.tls.peer_port".to_string()));
+        cfg.actix.tls.as_mut().unwrap().client_ca_path = Some("committee-ca.crt".into());
+        assert!(errors(&cfg).contains(&"actix.tls.peer_port".to_string()));
+        cfg.actix.tls.as_mut().unwrap().peer_port = Some(cfg.actix.port + 1);
+        assert!(!errors(&cfg).contains(&"actix.tls.peer_port".to_string()));
+
+        cfg.signer.kind = SignerKind::External;
+        assert!(errors(&cfg).contains(&"signer".to_string()));
+        cfg.signer.url = Some("http://127.0.0.1:7000/sign".into());
+        cfg.signer.public_key = Some(Test::<NodeIdentity>::new().public_key_hex());
+        assert!(!errors(&cfg).contains(&"signer".to_string()));
+    }
+
     #[test]
     fn env_overload_config() {
         // make sure that env settings do not interfere with other tests
@@ -234,6 +701,8 @@ mod test {
         std::env::set_var("PG_PASSWORD", "pass");
         std::env::set_var("ACTIX_WORKERS", "5");
         std::env::set_var("ACTIX_PORT", "5000");
+        std::env::set_var("PG_READ_HOST", "replica-2");
+        std::env::set_var("PG_CONNECT_TIMEOUT", "60");
 
         let cfg = NodeConfig::load_from(&settings, &global, true).unwrap();
         assert_eq!(cfg.actix.port, 5000);
@@ -243,8 +712,12 @@ mod test {
         assert_eq!(cfg.postgres.dbname, Some(DEFAULT_DBNAME.into()));
         assert_eq!(cfg.postgres.user, Some("postgres".into()));
         assert_eq!(cfg.postgres.password, Some("pass".into()));
+        assert_eq!(cfg.postgres_read.map(|pg| pg.host).flatten(), Some("replica-2".into()));
+        assert_eq!(cfg.postgres_connect_timeout, 60);
 
+        std::env::remove_var("PG_CONNECT_TIMEOUT");
         std::env::remove_var("PG_PASSWORD");
+        std::env::remove_var("PG_READ_HOST");
         std::env::remove_var("PG_HOST");
         std::env::remove_var("ACTIX_WORKERS");
         std::env::remove_var("ACTIX_PORT");
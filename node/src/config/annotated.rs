@@ -0,0 +1,226 @@
+//! `[validator]` section of the Tari config file generated from [NodeConfig] itself:
+//! values are serialized config, comments are doc comments of config structs collected via [JsonSchema],
+//! env vars are derived from [ENV_PREFIXES], see `tvnc config init`
+
+use super::{NodeConfig, ENV_PREFIXES};
+use schemars::{schema_for, JsonSchema};
+use serde_json::{Map, Value};
+
+/// Documents [deadpool_postgres::config::Config] keys of `[validator.postgres]`, which doesn't implement [JsonSchema]
+#[derive(JsonSchema)]
+pub struct PostgresConfigSchema {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub dbname: Option<String>,
+    /// Seconds to keep retrying initial connection on node start, 0 disables retries
+    pub connect_timeout: Option<u64>,
+    /// Connection pool, wait/create/recycle timeouts are set as `{ secs = 3, nanos = 0 }`
+    pub pool: Option<PoolConfigSchema>,
+}
+
+#[derive(JsonSchema)]
+pub struct PoolConfigSchema {
+    pub max_size: Option<usize>,
+}
+
+impl NodeConfig {
+    /// Renders the config as TOML `[validator]` section, keys without value are commented out.
+    /// With `comments` every key is preceded by its doc comment and env var overloading it
+    pub fn to_toml(&self, comments: bool) -> String {
+        let schema = serde_json::to_value(schema_for!(NodeConfig)).unwrap_or(Value::Null);
+        let values = serde_json::to_value(self).unwrap_or(Value::Null);
+        let writer = TomlWriter {
+            definitions: schema["definitions"].as_object().cloned().unwrap_or_default(),
+            comments,
+        };
+        let mut out = String::new();
+        writer.table(&mut out, "validator", None, &schema, &values, false);
+        out
+    }
+}
+
+struct TomlWriter {
+    definitions: Map<String, Value>,
+    comments: bool,
+}
+
+impl TomlWriter {
+    /// Writes table header and its keys, nested tables are written after keys of the table.
+    /// Whole table is commented out when `off`, e.g. not configured read replica
+    fn table(
+        &self,
+        out: &mut String,
+        path: &str,
+        env_prefix: Option<&str>,
+        schema: &Value,
+        values: &Value,
+        off: bool,
+    )
+    {
+        let properties = match self.resolve(schema)["properties"].as_object() {
+            Some(properties) => properties,
+            None => return,
+        };
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        self.doc(out, schema);
+        out.push_str(&format!("{}[{}]\n", comment_out(off), path));
+        let mut tables = vec![];
+        for (key, property) in properties {
+            let value = values.get(key).unwrap_or(&Value::Null);
+            if self.resolve(property)["properties"].is_object() {
+                tables.push((key, property, value));
+                continue;
+            }
+            self.doc(out, property);
+            if let (true, Some(prefix)) = (self.comments, env_prefix) {
+                out.push_str(&format!("# env: {}_{}\n", prefix, key.to_uppercase()));
+            }
+            match toml_value(value) {
+                Some(value) if !off => out.push_str(&format!("{} = {}\n", toml_key(key), value)),
+                _ => out.push_str(&format!("# {} =\n", toml_key(key))),
+            }
+        }
+        for (key, property, value) in tables {
+            let env_prefix = match path {
+                "validator" => env_prefix_of(key),
+                _ => None,
+            };
+            let path = format!("{}.{}", path, toml_key(key));
+            self.table(out, &path, env_prefix, property, value, off || value.is_null());
+        }
+    }
+
+    /// Doc of the field, falls back to doc of its type
+    fn doc(&self, out: &mut String, schema: &Value) {
+        if !self.comments {
+            return;
+        }
+        let description = self
+            .description(schema)
+            .or_else(|| self.description(self.resolve(schema)));
+        if let Some(description) = description {
+            for line in description.lines() {
+                out.push_str(format!("# {}", line).trim_end());
+                out.push('\n');
+            }
+        }
+    }
+
+    /// Title and description of the schema, docs split into paragraphs are split into both by schemars
+    fn description(&self, schema: &Value) -> Option<String> {
+        let doc: Vec<&str> = vec![schema["title"].as_str(), schema["description"].as_str()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if doc.is_empty() {
+            None
+        } else {
+            Some(doc.join("\n\n"))
+        }
+    }
+
+    /// Follows `$ref`s, `allOf` wrapping a documented `$ref` and `anyOf` of optional values
+    fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        if let Some(name) = schema["$ref"].as_str().and_then(|path| path.rsplit('/').next()) {
+            return match self.definitions.get(name) {
+                Some(definition) => self.resolve(definition),
+                None => schema,
+            };
+        }
+        for key in &["allOf", "anyOf"] {
+            let not_null = schema[*key]
+                .as_array()
+                .and_then(|schemas| schemas.iter().find(|schema| schema["type"] != "null"));
+            if let Some(schema) = not_null {
+                return self.resolve(schema);
+            }
+        }
+        schema
+    }
+}
+
+fn env_prefix_of(section: &str) -> Option<&'static str> {
+    match section {
+        "postgres_read" => Some("PG_READ"),
+        _ => ENV_PREFIXES
+            .iter()
+            .find(|(name, _)| *name == section)
+            .map(|(_, prefix)| *prefix),
+    }
+}
+
+fn comment_out(off: bool) -> &'static str {
+    if off {
+        "# "
+    } else {
+        ""
+    }
+}
+
+fn toml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+/// Inline TOML value, None for null
+fn toml_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Array(items) => Some(format!(
+            "[{}]",
+            items.iter().filter_map(toml_value).collect::<Vec<_>>().join(", ")
+        )),
+        Value::Object(map) if map.is_empty() => Some("{}".into()),
+        Value::Object(map) => Some(format!(
+            "{{ {} }}",
+            map.iter()
+                .filter_map(|(key, value)| toml_value(value).map(|value| format!("{} = {}", toml_key(key), value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        // JSON escapes of strings, numbers and booleans are valid TOML
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::build_test_global_config;
+    use config::{Config, File, FileFormat::Toml};
+
+    #[test]
+    fn annotated_defaults() {
+        let global = build_test_global_config().unwrap();
+        let defaults = NodeConfig::load_from(&Config::new(), &global, false).unwrap();
+        let toml = defaults.to_toml(true);
+        assert!(toml.starts_with("[validator]\n"));
+        assert!(toml.contains("\n[validator.actix]\n"));
+        assert!(toml.contains("# env: ACTIX_PORT\nport = 3001\n"));
+        assert!(toml.contains("# Seconds browser is allowed to cache preflight response\n# env: CORS_MAX_AGE\n"));
+        assert!(toml.contains("\n[validator.consensus.mempool]\n"));
+        assert!(toml.contains("dbname = \"validator\"\n"));
+        // not configured read replica and secrets are commented out
+        assert!(toml.contains("\n# [validator.postgres_read]\n"));
+        assert!(toml.contains("# wallets_passphrase =\n"));
+
+        // generated config loads back into the same config
+        for comments in &[true, false] {
+            let mut settings = Config::new();
+            settings.merge(File::from_str(&defaults.to_toml(*comments), Toml)).unwrap();
+            let cfg = NodeConfig::load_from(&settings, &global, false).unwrap();
+            assert_eq!(
+                serde_json::to_value(&cfg).unwrap(),
+                serde_json::to_value(&defaults).unwrap()
+            );
+        }
+        assert!(!defaults.to_toml(false).contains("# env:"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Horizontal scaling of one logical node: validator processes pointed at the same Postgres
+//!
+//! Every process registers as a worker in the `workers` table and heartbeats once per consensus poll.
+//! Assets are partitioned over live workers by rendezvous hashing of asset_id (see `worker_owns_asset`
+//! in the migrations), so every process looks up pending consensus work of its own assets only and processes
+//! never race on the same asset. When a worker joins or stops heartbeating only its share of assets moves,
+//! asset locks (asset_states.blocked_until) still guard the handover:
+//! ```toml
+//! [validator.consensus.cluster]
+//! enabled = true
+//! worker_id = "validator-1"
+//! ```
+
+use crate::{
+    db::{models::Worker, utils::errors::DBError},
+    types::NodeID,
+};
+use deadpool_postgres::Client;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ClusterConfig {
+    /// Partition assets over processes sharing the DB, every process processes all assets if disabled
+    pub enabled: bool,
+    /// Stable identity of the process, defaults to NodeID with process id and start time,
+    /// so assets of a restarted process move until the previous identity expires
+    pub worker_id: Option<String>,
+    /// Workers not heartbeating for this many seconds leave the partitioning
+    pub stale_after: u64,
+}
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            worker_id: None,
+            stale_after: 15,
+        }
+    }
+}
+
+/// Membership of this process in the cluster, see [module docs](self)
+#[derive(Debug)]
+pub struct ClusterWorker {
+    pub id: String,
+    node_id: NodeID,
+    stale_after: u64,
+}
+
+impl ClusterWorker {
+    /// `stale_after` is at least 3 poll periods, as workers heartbeat once per poll
+    pub fn new(node_id: NodeID, config: &ClusterConfig, poll_period: u64) -> Self {
+        let id = config.worker_id.clone().unwrap_or_else(|| {
+            format!(
+                "{}/{}/{}",
+                node_id,
+                std::process::id(),
+                chrono::Utc::now().timestamp_millis()
+            )
+        });
+        Self {
+            id,
+            node_id,
+            stale_after: config.stale_after.max(3 * poll_period),
+        }
+    }
+
+    /// Registers the worker or prolongs its membership, forgets workers which stopped heartbeating
+    pub async fn heartbeat(&self, client: &Client) -> Result<(), DBError> {
+        Worker::heartbeat(&self.id, &self.node_id.to_string(), self.stale_after, client).await?;
+        Worker::reap(client).await?;
+        Ok(())
+    }
+
+    /// Hands assets of the worker over to the other workers right away, e.g. on shutdown
+    pub async fn leave(&self, client: &Client) -> Result<(), DBError> {
+        Worker::deregister(&self.id, client).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::consensus::Instruction,
+        test::utils::{builders::consensus::InstructionBuilder, test_db_client},
+    };
+
+    #[actix_rt::test]
+    async fn partition_pending_instructions() {
+        let (client, _lock) = test_db_client().await;
+        let config = ClusterConfig {
+            enabled: true,
+            worker_id: Some("worker1".into()),
+            ..ClusterConfig::default()
+        };
+        let worker1 = ClusterWorker::new(NodeID::stub(), &config, 1);
+        let config = ClusterConfig {
+            worker_id: Some("worker2".into()),
+            ..config
+        };
+        let worker2 = ClusterWorker::new(NodeID::stub(), &config, 1);
+        assert_eq!(worker2.stale_after, 15);
+        worker1.heartbeat(&client).await.unwrap();
+        worker2.heartbeat(&client).await.unwrap();
+
+        let mut instructions = vec![];
+        for _ in 0..10 {
+            instructions.push(InstructionBuilder::default().build(&client).await.unwrap());
+        }
+        // every worker picks assets partitioned to it only
+        for worker in [&worker1, &worker2].iter() {
+            if let Some((asset_id, _)) = Instruction::find_pending(&[], Some(worker.id.as_str()), &client)
+                .await
+                .unwrap()
+            {
+                assert_eq!(Worker::owner_of(&asset_id, &client).await.unwrap(), Some(worker.id.clone()));
+            }
+        }
+        let (asset_id, _) = Instruction::find_pending(&[], None, &client).await.unwrap().unwrap();
+        assert!(instructions.iter().any(|i| i.asset_id == asset_id));
+
+        // assets of the leaving worker are handed over
+        worker2.leave(&client).await.unwrap();
+        for instruction in instructions.iter() {
+            let owner = Worker::owner_of(&instruction.asset_id, &client).await.unwrap();
+            assert_eq!(owner.as_deref(), Some("worker1"));
+        }
+        assert!(Instruction::find_pending(&[], Some(worker2.id.as_str()), &client)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    db::utils::validation::ValidationErrors,
+    types::{CommitteeMode, NodeID, NodeSelectionStrategy},
+};
+use rand::seq::SliceRandom;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Validator node known to the node along with its collateral, see [super::ConsensusConfig::committee_candidates]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct CommitteeCandidate {
+    pub node_id: String,
+    #[serde(default)]
+    pub collateral: i64,
+}
+
+/// Migration of the asset to a new committee, carried by the proposal committing the handover instruction
+///
+/// `checkpoint_hash` is SHA256 of asset and token state taken once the asset was frozen,
+/// so the new committee can verify it continues from the same state
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CommitteeHandover {
+    pub members: Vec<CommitteeCandidate>,
+    pub checkpoint_hash: String,
+}
+
+/// Selects committee of newly registered asset according to its [CommitteeMode]
+///
+/// `node_id` is the node registering the asset, it forms committee of Creator mode with empty trusted set
+pub fn select_committee(
+    mode: &CommitteeMode,
+    node_id: Option<NodeID>,
+    candidates: &[CommitteeCandidate],
+) -> Result<Vec<CommitteeCandidate>, ValidationErrors>
+{
+    let mut errors = ValidationErrors::default();
+    let collateral = |node_id: &str| {
+        candidates
+            .iter()
+            .find(|candidate| candidate.node_id == node_id)
+            .map(|candidate| candidate.collateral)
+            .unwrap_or(0)
+    };
+    let committee = match mode {
+        CommitteeMode::Creator { trusted_node_set } if trusted_node_set.is_empty() => match node_id {
+            Some(node_id) => vec![node_id.to_string()],
+            None => {
+                errors.append_validation_error(
+                    "committee",
+                    "trusted_node_set",
+                    "Trusted node set is empty and node identity is not loaded",
+                );
+                vec![]
+            },
+        },
+        CommitteeMode::Creator { trusted_node_set } => trusted_node_set.clone(),
+        CommitteeMode::Public {
+            node_threshold,
+            minimum_collateral,
+            node_selection_strategy,
+        } => {
+            let eligible: Vec<_> = candidates
+                .iter()
+                .filter(|candidate| candidate.collateral >= *minimum_collateral)
+                .filter(|candidate| match node_selection_strategy {
+                    NodeSelectionStrategy::RegisterAll => true,
+                    NodeSelectionStrategy::RandomFromWhitelist { whitelist } => whitelist.contains(&candidate.node_id),
+                })
+                .map(|candidate| candidate.node_id.clone())
+                .collect();
+            let threshold = *node_threshold as usize;
+            if eligible.len() < threshold.max(1) {
+                errors.append_validation_error(
+                    "committee",
+                    "node_threshold",
+                    "Not enough nodes with minimum collateral to reach node threshold",
+                );
+            }
+            match node_selection_strategy {
+                NodeSelectionStrategy::RegisterAll => eligible,
+                NodeSelectionStrategy::RandomFromWhitelist { .. } => eligible
+                    .choose_multiple(&mut rand::thread_rng(), threshold)
+                    .cloned()
+                    .collect(),
+            }
+        },
+    };
+    errors.validate()?;
+    Ok(committee
+        .into_iter()
+        .map(|node_id| CommitteeCandidate {
+            collateral: collateral(&node_id),
+            node_id,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidates() -> Vec<CommitteeCandidate> {
+        (1..=4)
+            .map(|i| CommitteeCandidate {
+                node_id: format!("node{}", i),
+                collateral: i * 100,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn creator() {
+        let own = select_committee(&CommitteeMode::default(), Some(NodeID::stub()), &candidates()).unwrap();
+        assert_eq!(own, vec![CommitteeCandidate {
+            node_id: NodeID::stub().to_string(),
+            collateral: 0,
+        }]);
+        assert!(select_committee(&CommitteeMode::default(), None, &candidates()).is_err());
+
+        let mode = CommitteeMode::Creator {
+            trusted_node_set: vec!["node2".into(), "other".into()],
+        };
+        let trusted = select_committee(&mode, Some(NodeID::stub()), &candidates()).unwrap();
+        assert_eq!(trusted, vec![
+            CommitteeCandidate {
+                node_id: "node2".into(),
+                collateral: 200,
+            },
+            CommitteeCandidate {
+                node_id: "other".into(),
+                collateral: 0,
+            },
+        ]);
+    }
+
+    #[test]
+    fn public() {
+        let mode = CommitteeMode::Public {
+            node_threshold: 2,
+            minimum_collateral: 200,
+            node_selection_strategy: NodeSelectionStrategy::RegisterAll,
+        };
+        let committee = select_committee(&mode, None, &candidates()).unwrap();
+        let nodes: Vec<_> = committee.iter().map(|member| member.node_id.as_str()).collect();
+        assert_eq!(nodes, vec!["node2", "node3", "node4"]);
+
+        let mode = CommitteeMode::Public {
+            node_threshold: 4,
+            minimum_collateral: 200,
+            node_selection_strategy: NodeSelectionStrategy::RegisterAll,
+        };
+        assert!(select_committee(&mode, None, &candidates()).is_err());
+
+        let mode = CommitteeMode::Public {
+            node_threshold: 2,
+            minimum_collateral: 200,
+            node_selection_strategy: NodeSelectionStrategy::RandomFromWhitelist {
+                whitelist: vec!["node1".into(), "node2".into(), "node3".into(), "node4".into()],
+            },
+        };
+        let committee = select_committee(&mode, None, &candidates()).unwrap();
+        assert_eq!(committee.len(), 2);
+        assert!(committee.iter().all(|member| member.collateral >= 200));
+
+        let mode = CommitteeMode::Public {
+            node_threshold: 2,
+            minimum_collateral: 200,
+            node_selection_strategy: NodeSelectionStrategy::RandomFromWhitelist {
+                whitelist: vec!["node1".into(), "node2".into()],
+            },
+        };
+        assert!(select_committee(&mode, None, &candidates()).is_err());
+    }
+}
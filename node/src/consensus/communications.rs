@@ -1,40 +1,189 @@
-use super::errors::ConsensusError;
+use super::{errors::ConsensusError, LOG_TARGET};
 use crate::{
     consensus::ConsensusCommittee,
-    db::models::consensus::{NewAggregateSignatureMessage, NewView, Proposal, SignedProposal},
+    db::{
+        models::{
+            consensus::{
+                AggregateSignatureMessage,
+                Instruction,
+                NewAggregateSignatureMessage,
+                NewInstruction,
+                NewProposal,
+                NewView,
+                Proposal,
+                SignedProposal,
+            },
+            AggregateSignatureMessageStatus,
+            InstructionStatus,
+        },
+        utils::db::in_transaction,
+    },
+    types::InstructionID,
 };
+use deadpool_postgres::Client;
 
-// TODO: these stubbed methods just exists to flesh out the consensus worker logic
+// TODO: these methods only deliver messages via in-process transport (see test cluster) so far
 //       we will need to further iterate as we hook in the tari comms layer / flesh out node communication
 
-pub async fn submit_new_view(_committee: &ConsensusCommittee, _new_view: &NewView) -> Result<(), ConsensusError> {
-    Ok(())
+/// Message exchanged between committee members
+#[derive(Clone, Debug)]
+pub enum ConsensusMessage {
+    NewView(NewView),
+    Proposal(Proposal),
+    SignedProposal(SignedProposal),
+    /// Finalized proposal along with instructions it commits, so members can apply it
+    AggregateSignature {
+        proposal: Proposal,
+        message: NewAggregateSignatureMessage,
+        instructions: Vec<Instruction>,
+    },
+    PartialSignature(String),
 }
 
-pub async fn broadcast_proposal(_committee: &ConsensusCommittee, _proposal: &Proposal) -> Result<(), ConsensusError> {
-    Ok(())
+/// Delivers consensus messages to other committee members
+pub trait ConsensusTransport: Send + Sync {
+    fn send(&self, committee: &ConsensusCommittee, message: ConsensusMessage) -> Result<(), ConsensusError>;
+}
+
+fn send(
+    transport: Option<&dyn ConsensusTransport>,
+    committee: &ConsensusCommittee,
+    message: ConsensusMessage,
+) -> Result<(), ConsensusError>
+{
+    #[cfg(feature = "chaos")]
+    if crate::chaos::drop_message() {
+        return Ok(());
+    }
+    match transport {
+        Some(transport) => transport.send(committee, message),
+        None => Ok(()),
+    }
+}
+
+pub async fn submit_new_view(
+    committee: &ConsensusCommittee,
+    transport: Option<&dyn ConsensusTransport>,
+    new_view: &NewView,
+) -> Result<(), ConsensusError>
+{
+    send(transport, committee, ConsensusMessage::NewView(new_view.clone()))
+}
+
+pub async fn broadcast_proposal(
+    committee: &ConsensusCommittee,
+    transport: Option<&dyn ConsensusTransport>,
+    proposal: &Proposal,
+) -> Result<(), ConsensusError>
+{
+    send(transport, committee, ConsensusMessage::Proposal(proposal.clone()))
 }
 
 pub async fn submit_signed_proposal(
-    _committee: &ConsensusCommittee,
-    _signed_proposal: &SignedProposal,
+    committee: &ConsensusCommittee,
+    transport: Option<&dyn ConsensusTransport>,
+    signed_proposal: &SignedProposal,
 ) -> Result<(), ConsensusError>
 {
-    Ok(())
+    send(transport, committee, ConsensusMessage::SignedProposal(signed_proposal.clone()))
 }
 
 pub async fn broadcast_aggregate_signature_message(
-    _committee: &ConsensusCommittee,
-    _aggregate_signature_message: &NewAggregateSignatureMessage,
+    committee: &ConsensusCommittee,
+    transport: Option<&dyn ConsensusTransport>,
+    proposal: &Proposal,
+    aggregate_signature_message: &NewAggregateSignatureMessage,
+    client: &Client,
 ) -> Result<(), ConsensusError>
 {
-    Ok(())
+    if transport.is_none() {
+        return Ok(());
+    }
+    let new_view = &proposal.new_view;
+    let mut instructions = Vec::new();
+    for id in new_view.instruction_set.iter().chain(new_view.invalid_instruction_set.iter()) {
+        instructions.push(Instruction::load(InstructionID(*id), client).await?);
+    }
+    send(transport, committee, ConsensusMessage::AggregateSignature {
+        proposal: proposal.clone(),
+        message: aggregate_signature_message.clone(),
+        instructions,
+    })
 }
 
 pub async fn submit_partial_signature(
-    _committee: &ConsensusCommittee,
-    _signature: String,
+    committee: &ConsensusCommittee,
+    transport: Option<&dyn ConsensusTransport>,
+    signature: String,
 ) -> Result<(), ConsensusError>
 {
-    Ok(())
+    send(transport, committee, ConsensusMessage::PartialSignature(signature))
+}
+
+/// Stores message received from another committee member, so it is picked up by consensus workers
+///
+/// Committee of 1 is hardcoded, so local node is always the leader and only finalized proposals
+/// are applied, the rest of messages are ignored
+pub async fn receive(message: ConsensusMessage, client: &Client) -> Result<(), ConsensusError> {
+    match message {
+        ConsensusMessage::AggregateSignature {
+            proposal,
+            message,
+            mut instructions,
+        } => {
+            // parent instructions go first
+            instructions.sort_by_key(|instruction| instruction.parent_id.is_some());
+            in_transaction(client, async move {
+                for instruction in instructions {
+                    if Instruction::find(instruction.id, client).await?.is_none() {
+                        Instruction::insert(
+                            NewInstruction {
+                                id: instruction.id,
+                                parent_id: instruction.parent_id,
+                                initiating_node_id: instruction.initiating_node_id,
+                                signature: instruction.signature,
+                                asset_id: instruction.asset_id,
+                                token_id: instruction.token_id,
+                                template_id: instruction.template_id,
+                                contract_name: instruction.contract_name,
+                                status: InstructionStatus::Pending,
+                                params: instruction.params,
+                                depends_on: instruction.depends_on,
+                                signer_pubkey: instruction.signer_pubkey,
+                                nonce: instruction.nonce,
+                                priority: instruction.priority,
+                                batch_index: instruction.batch_index,
+                            },
+                            client,
+                        )
+                        .await?;
+                    }
+                }
+                Proposal::insert(
+                    NewProposal {
+                        id: proposal.id,
+                        new_view: proposal.new_view,
+                        asset_id: proposal.asset_id,
+                        node_id: proposal.node_id,
+                    },
+                    client,
+                )
+                .await?;
+                AggregateSignatureMessage::insert(
+                    NewAggregateSignatureMessage {
+                        status: AggregateSignatureMessageStatus::Pending,
+                        ..message
+                    },
+                    client,
+                )
+                .await?;
+                Ok(())
+            })
+            .await
+        },
+        message => {
+            log::trace!(target: LOG_TARGET, "Ignoring consensus message {:?}", message);
+            Ok(())
+        },
+    }
 }
@@ -1,15 +1,53 @@
+use super::{ClusterConfig, CommitteeCandidate, ConsensusGcConfig, MempoolConfig};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ConsensusConfig {
     pub workers: Option<usize>,
     pub poll_period: usize,
+    /// Validator nodes eligible for committees of Public assets along with their collateral
+    #[serde(default)]
+    pub committee_candidates: Vec<CommitteeCandidate>,
+    /// Asset locks not heartbeated by their owner for this many seconds are reclaimed
+    pub lock_stale_after: u64,
+    /// Nodes with reputation score below this are skipped by leader election, see [NodeReputation]
+    ///
+    /// [NodeReputation]: crate::db::models::NodeReputation
+    pub min_reputation: i64,
+    /// Ordering and batching of pending instructions, see [Mempool](super::Mempool)
+    #[serde(default)]
+    pub mempool: MempoolConfig,
+    /// Garbage collection of terminal consensus records, see [ConsensusJanitor](super::ConsensusJanitor)
+    #[serde(default)]
+    pub gc: ConsensusGcConfig,
+    /// Partitioning of assets over processes sharing the DB, see [cluster](super::cluster)
+    #[serde(default)]
+    pub cluster: ClusterConfig,
 }
 impl Default for ConsensusConfig {
     fn default() -> Self {
         Self {
             workers: None,
             poll_period: 1,
+            committee_candidates: vec![],
+            lock_stale_after: 15,
+            min_reputation: 50,
+            mempool: MempoolConfig::default(),
+            gc: ConsensusGcConfig::default(),
+            cluster: ClusterConfig::default(),
         }
     }
 }
+
+impl ConsensusConfig {
+    /// Number of assets processed concurrently, defaults to number of CPUs
+    pub fn workers(&self) -> usize {
+        self.workers.unwrap_or_else(num_cpus::get).max(1)
+    }
+
+    /// Stale lock period, at least 3 poll periods as locks are heartbeated once per poll
+    pub fn lock_stale_after(&self) -> u64 {
+        self.lock_stale_after.max(3 * self.poll_period as u64)
+    }
+}
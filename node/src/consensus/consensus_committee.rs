@@ -1,9 +1,23 @@
-use super::errors::ConsensusError;
+use super::{errors::ConsensusError, Mempool, LOG_TARGET};
 use crate::{
-    db::models::{consensus::*, AggregateSignatureMessageStatus, AssetState, SignedProposalStatus, ViewStatus},
+    db::models::{
+        consensus::*,
+        AggregateSignatureMessageStatus,
+        AssetState,
+        CommitteeMember,
+        Misbehavior,
+        NodeReputation,
+        SignedProposalStatus,
+        Token,
+        ViewStatus,
+    },
+    identity::IdentityError,
     types::{consensus::*, AssetID, NodeID, ProposalID},
+    wallet::Signer,
 };
 use deadpool_postgres::Client;
+use std::collections::HashMap;
+use tari_core::transactions::types::PublicKey;
 use uuid::Uuid;
 
 #[derive(Debug, PartialEq)]
@@ -15,10 +29,15 @@ pub struct ConsensusCommittee {
 
 impl ConsensusCommittee {
     /// Returns next pending committee data for the purposes of the consensus state processing
+    /// Only assets partitioned to `worker` are considered when clustering is enabled, see [ClusterWorker]
     /// TODO: This is currently hardcoded for a committee of 1
     ///       We will need further build this out as we expand into real committees / just a stub
+    ///
+    /// [ClusterWorker]: super::ClusterWorker
     pub async fn find_next_pending_committee(
         node_id: NodeID,
+        worker: Option<&str>,
+        mempool: &Mempool,
         client: &Client,
     ) -> Result<Option<ConsensusCommittee>, ConsensusError>
     {
@@ -27,9 +46,9 @@ impl ConsensusCommittee {
         // Note: Logic favors handling an in progress consensus step prior to processing new instructions
 
         // Find any pending signature messages indicating a state is pending finalization
-        if let Some(aggregate_signature_message) = AggregateSignatureMessage::find_pending(&client).await? {
+        if let Some(aggregate_signature_message) = AggregateSignatureMessage::find_pending(worker, &client).await? {
             let proposal = aggregate_signature_message.proposal(&client).await?;
-            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&proposal.asset_id).await?;
+            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&proposal.asset_id, node_id).await?;
 
             return Ok(Some(ConsensusCommittee {
                 leader_node_id,
@@ -44,9 +63,9 @@ impl ConsensusCommittee {
         // Find any mappings of asset id to signed proposals where the threshold is met
         // This node must the current leader to accept these signed proposals or they are thrown out
         // Only the first valid asset ID where the current node is the leader is returned
-        let asset_id_signed_proposal_mapping = SignedProposal::threshold_met(&client).await?;
+        let asset_id_signed_proposal_mapping = SignedProposal::threshold_met(worker, &client).await?;
         for (asset_id, signed_proposals) in asset_id_signed_proposal_mapping {
-            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset_id).await?;
+            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset_id, node_id).await?;
             let proposal_id = signed_proposals[0].proposal_id;
             let proposal = Proposal::load(proposal_id, &client).await?;
 
@@ -66,8 +85,8 @@ impl ConsensusCommittee {
         }
 
         // Find any pending proposal
-        if let Some(proposal) = Proposal::find_pending(&client).await? {
-            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&proposal.asset_id).await?;
+        if let Some(proposal) = Proposal::find_pending(worker, &client).await? {
+            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&proposal.asset_id, node_id).await?;
 
             if proposal.node_id == leader_node_id {
                 return Ok(Some(ConsensusCommittee {
@@ -77,16 +96,17 @@ impl ConsensusCommittee {
                 }));
             } else {
                 // This proposal came from a node not currently viewed as the leader, mark it invalid
-                proposal.mark_invalid(&client).await?
+                proposal.mark_invalid(&client).await?;
+                NodeReputation::record(&proposal.node_id.to_string(), Misbehavior::InvalidProposal, &client).await?;
             }
         }
 
         // Find any mappings of asset id to new views where the threshold is met
         // This node must the current leader to accept these views or they are thrown out
         // Only the first valid asset ID where the current node is the leader is returned
-        let asset_id_view_mapping = View::threshold_met(&client).await?;
+        let asset_id_view_mapping = View::threshold_met(worker, &client).await?;
         for (asset_id, views) in asset_id_view_mapping {
-            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset_id).await?;
+            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset_id, node_id).await?;
 
             if leader_node_id == node_id {
                 return Ok(Some(ConsensusCommittee {
@@ -100,8 +120,8 @@ impl ConsensusCommittee {
             }
         }
 
-        if let Some((asset_id, pending_instructions)) = Instruction::find_pending(&client).await? {
-            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset_id).await?;
+        if let Some((asset_id, pending_instructions)) = mempool.next_batch(worker, &client).await? {
+            let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset_id, node_id).await?;
             return Ok(Some(ConsensusCommittee {
                 asset_id,
                 leader_node_id,
@@ -113,15 +133,34 @@ impl ConsensusCommittee {
     }
 
     // Determines leader node ID for this round of consensus
-    pub async fn determine_leader_node_id(_asset_id: &AssetID) -> Result<NodeID, ConsensusError> {
-        Ok(NodeID::stub())
+    // TODO: committee of 1 is hardcoded, so current node is always the leader
+    pub async fn determine_leader_node_id(_asset_id: &AssetID, node_id: NodeID) -> Result<NodeID, ConsensusError> {
+        Ok(node_id)
+    }
+
+    /// Elects leader of the asset committee skipping members with reputation score below `min_reputation`,
+    /// the first eligible member ordered by NodeID is elected. Returns None if no member is eligible.
+    // TODO: not used by consensus until committees of more than 1 node are supported
+    pub async fn elect_leader(
+        asset_id: &AssetID,
+        min_reputation: i64,
+        client: &Client,
+    ) -> Result<Option<String>, ConsensusError>
+    {
+        let members: Vec<String> = CommitteeMember::find_by_asset_id(asset_id, client)
+            .await?
+            .into_iter()
+            .map(|member| member.node_id)
+            .collect();
+        let scores = NodeReputation::scores(&members, client).await?;
+        Ok(members.into_iter().find(|node_id| scores[node_id] >= min_reputation))
     }
 
     /// Aquires a lock on the asset state table preventing other consensus workers from working on these
-    /// instructions in tandem
-    pub async fn acquire_lock(&self, lock_period: u64, client: &Client) -> Result<(), ConsensusError> {
+    /// instructions in tandem, returns false if another worker holds the lock already
+    pub async fn acquire_lock(&self, lock_period: u64, owner: &str, client: &Client) -> Result<bool, ConsensusError> {
         match AssetState::find_by_asset_id(&self.asset_id, &client).await? {
-            Some(mut asset_state) => Ok(asset_state.acquire_lock(lock_period, &client).await?),
+            Some(mut asset_state) => Ok(asset_state.acquire_owned_lock(lock_period, Some(owner), &client).await?),
             None => Err(ConsensusError::error("Failed to load asset state")),
         }
     }
@@ -137,22 +176,33 @@ impl ConsensusCommittee {
     /// Prepares new view that includes append only state data for the purpose of broadcasting to the leader
     pub async fn prepare_new_view(
         &self,
-        node_id: NodeID,
+        signer: &dyn Signer,
         pending_instructions: &[Instruction],
         client: &Client,
     ) -> Result<NewView, ConsensusError>
     {
         let mut instruction_set = Vec::new();
         let mut invalid_instruction_set = Vec::new();
-        let mut asset_state = Vec::new();
-        let mut token_state = Vec::new();
+        let mut append_only_state = AppendOnlyState::default();
+        let mut token_versions = HashMap::new();
 
         for pending_instruction in pending_instructions {
+            if !pending_instruction.has_valid_id() {
+                let id = pending_instruction.id;
+                log::warn!(target: LOG_TARGET, "Instruction {} ID does not match its content", id);
+                invalid_instruction_set.push(pending_instruction.id.0);
+                continue;
+            }
             match pending_instruction.execute(&client).await {
-                Ok((mut new_asset_state, mut new_token_state)) => {
+                Ok(mut state) => {
+                    if !Self::check_token_versions(&state, &mut token_versions, client).await? {
+                        let id = pending_instruction.id;
+                        log::warn!(target: LOG_TARGET, "Instruction {} state was built on outdated token", id);
+                        invalid_instruction_set.push(pending_instruction.id.0);
+                        continue;
+                    }
                     instruction_set.push(pending_instruction.id.0);
-                    asset_state.append(&mut new_asset_state);
-                    token_state.append(&mut new_token_state);
+                    append_only_state.append(&mut state);
                 },
                 Err(_) => {
                     // Instruction failed to execute
@@ -160,45 +210,78 @@ impl ConsensusCommittee {
                 },
             }
         }
-        let new_view = NewView {
+        let mut new_view = NewView {
             instruction_set,
             invalid_instruction_set,
-            append_only_state: AppendOnlyState {
-                asset_state,
-                token_state,
-            },
+            append_only_state,
             asset_id: self.asset_id.clone(),
-            initiating_node_id: NodeID::stub(),
-            signature: "stub-signature".into(),
+            initiating_node_id: signer.node_id(),
+            signature: String::new(),
         };
+        new_view.signature = signer
+            .sign(&new_view.signing_payload().map_err(IdentityError::from)?)
+            .await?;
 
         // Leader stores the view
-        if self.is_leader(node_id) {
+        if self.is_leader(signer.node_id()) {
             View::insert(new_view.clone(), NewViewAdditionalParameters::default(), &client).await?;
         }
 
         Ok(new_view)
     }
 
+    /// Checks that buffered token state of the instruction was built on current versions of the tokens,
+    /// taking state of instructions earlier in the view into account, `versions` are advanced if it was.
+    /// Otherwise state would be rejected on commit along with the whole view, see [Token::store_append_only_state]
+    async fn check_token_versions(
+        state: &AppendOnlyState,
+        versions: &mut HashMap<String, i64>,
+        client: &Client,
+    ) -> Result<bool, ConsensusError>
+    {
+        let mut advanced = HashMap::new();
+        for token_state in state.token_state.iter() {
+            let expected = match token_state.expected_version {
+                Some(version) => version,
+                None => continue,
+            };
+            let key = token_state.token_id.to_string();
+            let current = match advanced.get(&key).or_else(|| versions.get(&key)) {
+                Some(version) => *version,
+                None => Token::find_by_token_id(&token_state.token_id, client)
+                    .await?
+                    .map(|token| token.version)
+                    .unwrap_or_default(),
+            };
+            if current != expected {
+                return Ok(false);
+            }
+            advanced.insert(key, expected + 1);
+        }
+        versions.extend(advanced);
+        Ok(true)
+    }
+
     /// Leader creates proposal
     pub async fn create_proposal(
         &self,
-        node_id: NodeID,
+        signer: &dyn Signer,
         views: &mut [View],
         client: &Client,
     ) -> Result<Proposal, ConsensusError>
     {
         let view = self.select_view(views, &client).await?;
         let params = NewProposal {
-            id: ProposalID::new(node_id).await?,
-            node_id: NodeID::stub(),
+            id: ProposalID::new(signer.node_id()).await?,
+            node_id: signer.node_id(),
             asset_id: view.asset_id.clone(),
             new_view: view.into(),
         };
-        let proposal = Proposal::insert(params, &client).await.unwrap();
+        let proposal = Proposal::insert(params, &client).await?;
 
         // Leader signs proposal and stores record so their approval is included in the supermajority
-        proposal.sign(node_id, &client).await?;
+        let signature = proposal.create_partial_signature(signer).await?;
+        proposal.sign(signer.node_id(), signature, &client).await?;
 
         Ok(proposal)
     }
@@ -255,6 +338,14 @@ impl ConsensusCommittee {
                 )
                 .await?;
         }
+        // Committee members who did not sign before threshold was reached missed the round
+        for member in CommitteeMember::find_by_asset_id(&self.asset_id, client).await? {
+            if member.node_id != self.leader_node_id.to_string() &&
+                !signatures.iter().any(|(node_id, _)| node_id.to_string() == member.node_id)
+            {
+                NodeReputation::record(&member.node_id, Misbehavior::MissedRound, client).await?;
+            }
+        }
         let new_message = NewAggregateSignatureMessage {
             proposal_id: proposal.id,
             signature_data: SignatureData { signatures },
@@ -269,14 +360,26 @@ impl ConsensusCommittee {
         Ok(new_message)
     }
 
-    /// Validates aggregate signature message contents confirming signatures
+    /// Validates aggregate signature message contents confirming signatures of committee members
+    /// with `public_keys`, signatures are verified in a single batch.
+    /// Bad signatures are recorded to reputation of their signers.
     pub async fn validate_aggregate_signature_message(
         &self,
-        _proposal: &Proposal,
-        _aggregate_signature_message: &AggregateSignatureMessage,
+        proposal: &Proposal,
+        aggregate_signature_message: &AggregateSignatureMessage,
+        public_keys: &HashMap<NodeID, PublicKey>,
+        client: &Client,
     ) -> Result<(), ConsensusError>
     {
-        Ok(())
+        let payload = proposal.signing_payload().map_err(IdentityError::from)?;
+        let signature_data = &aggregate_signature_message.signature_data;
+        if signature_data.verify(&payload, public_keys) {
+            return Ok(());
+        }
+        for node_id in signature_data.invalid_signers(&payload, public_keys) {
+            NodeReputation::record(&node_id.to_string(), Misbehavior::BadSignature, client).await?;
+        }
+        Err(ConsensusError::error("Aggregate signature message has invalid signatures"))
     }
 
     /// Checks if this node is the current leader
@@ -289,7 +392,9 @@ impl ConsensusCommittee {
 mod test {
     use super::*;
     use crate::{
+        consensus::CommitteeCandidate,
         db::models::*,
+        identity::NodeIdentity,
         test::utils::{
             builders::{
                 consensus::{
@@ -300,8 +405,10 @@ mod test {
                     ViewBuilder,
                 },
                 AssetStateBuilder,
+                TokenBuilder,
             },
             test_db_client,
+            Test,
         },
     };
     use chrono::Utc;
@@ -309,6 +416,7 @@ mod test {
     #[actix_rt::test]
     async fn find_next_pending_committee() {
         let (client, _lock) = test_db_client().await;
+        let mempool = Mempool::default();
         // Given all model instances exist pending: AggregateSignatureMessage, SignedProposal, Proposal, View,
         // Instruction Committee work finalizing a round always takes precidence over new work in that order
         // Test emphasizes two things:
@@ -361,9 +469,10 @@ mod test {
         .unwrap();
 
         // Leader finalized proposal received state
-        let found_pending_committee = ConsensusCommittee::find_next_pending_committee(NodeID::stub(), &client)
-            .await
-            .unwrap();
+        let found_pending_committee =
+            ConsensusCommittee::find_next_pending_committee(NodeID::stub(), None, &mempool, &client)
+                .await
+                .unwrap();
         assert!(found_pending_committee.is_some());
         let found_pending_committee = found_pending_committee.unwrap();
         let found_proposal = aggregate_signature_message.proposal(&client).await.unwrap();
@@ -382,9 +491,10 @@ mod test {
         aggregate_signature_message.update(data, &client).await.unwrap();
 
         // Signed proposal threshold reached
-        let found_pending_committee = ConsensusCommittee::find_next_pending_committee(NodeID::stub(), &client)
-            .await
-            .unwrap();
+        let found_pending_committee =
+            ConsensusCommittee::find_next_pending_committee(NodeID::stub(), None, &mempool, &client)
+                .await
+                .unwrap();
         assert!(found_pending_committee.is_some());
         let found_pending_committee = found_pending_committee.unwrap();
         let found_proposal = Proposal::load(signed_proposal.proposal_id, &client).await.unwrap();
@@ -403,9 +513,10 @@ mod test {
         signed_proposal.update(data, &client).await.unwrap();
 
         // Proposal pending
-        let found_pending_committee = ConsensusCommittee::find_next_pending_committee(NodeID::stub(), &client)
-            .await
-            .unwrap();
+        let found_pending_committee =
+            ConsensusCommittee::find_next_pending_committee(NodeID::stub(), None, &mempool, &client)
+                .await
+                .unwrap();
         assert!(found_pending_committee.is_some());
         let found_pending_committee = found_pending_committee.unwrap();
         assert_eq!(found_pending_committee.state, CommitteeState::ReceivedLeaderProposal {
@@ -419,9 +530,10 @@ mod test {
         proposal.update(data, &client).await.unwrap();
 
         // View pending
-        let found_pending_committee = ConsensusCommittee::find_next_pending_committee(NodeID::stub(), &client)
-            .await
-            .unwrap();
+        let found_pending_committee =
+            ConsensusCommittee::find_next_pending_committee(NodeID::stub(), None, &mempool, &client)
+                .await
+                .unwrap();
         assert!(found_pending_committee.is_some());
         let found_pending_committee = found_pending_committee.unwrap();
         assert_eq!(found_pending_committee.state, CommitteeState::ViewThresholdReached {
@@ -435,9 +547,10 @@ mod test {
         view.update(data, &client).await.unwrap();
 
         // Instruction pending
-        let found_pending_committee = ConsensusCommittee::find_next_pending_committee(NodeID::stub(), &client)
-            .await
-            .unwrap();
+        let found_pending_committee =
+            ConsensusCommittee::find_next_pending_committee(NodeID::stub(), None, &mempool, &client)
+                .await
+                .unwrap();
         assert!(found_pending_committee.is_some());
         let found_pending_committee = found_pending_committee.unwrap();
         assert_eq!(found_pending_committee.state, CommitteeState::PreparingView {
@@ -450,9 +563,10 @@ mod test {
         };
         instruction.update(data, &client).await.unwrap();
 
-        let found_pending_committee = ConsensusCommittee::find_next_pending_committee(NodeID::stub(), &client)
-            .await
-            .unwrap();
+        let found_pending_committee =
+            ConsensusCommittee::find_next_pending_committee(NodeID::stub(), None, &mempool, &client)
+                .await
+                .unwrap();
         assert!(found_pending_committee.is_none());
     }
 
@@ -460,7 +574,7 @@ mod test {
     async fn determine_leader_node_id() {
         let (client, _lock) = test_db_client().await;
         let asset = AssetStateBuilder::default().build(&client).await.unwrap();
-        let leader_node = ConsensusCommittee::determine_leader_node_id(&asset.asset_id)
+        let leader_node = ConsensusCommittee::determine_leader_node_id(&asset.asset_id, NodeID::stub())
             .await
             .unwrap();
         assert_eq!(leader_node, NodeID::stub());
@@ -485,7 +599,8 @@ mod test {
             Utc::now()
         );
 
-        consensus_committee.acquire_lock(10, &client).await.unwrap();
+        assert!(consensus_committee.acquire_lock(10, "worker1", &client).await.unwrap());
+        assert!(!consensus_committee.acquire_lock(10, "worker2", &client).await.unwrap());
         let asset = AssetState::load(asset.id, &client).await.unwrap();
         let asset2 = AssetState::load(asset2.id, &client).await.unwrap();
         assert!(asset.blocked_until > Utc::now());
@@ -504,18 +619,60 @@ mod test {
         let instruction = InstructionBuilder::default().build(&client).await.unwrap();
         let instructions = vec![instruction.clone()];
         let consensus_committee = test_committee(None, NodeID::stub(), &client).await;
+        let identity = Test::<NodeIdentity>::new();
         let new_view = consensus_committee
-            .prepare_new_view(NodeID::stub(), &instructions, &client)
+            .prepare_new_view(&identity, &instructions, &client)
             .await
             .unwrap();
         assert_eq!(new_view.asset_id, consensus_committee.asset_id);
         assert_eq!(new_view.instruction_set, vec![instruction.id.0]);
         assert_eq!(new_view.invalid_instruction_set, Vec::new());
-        assert_eq!(new_view.append_only_state, AppendOnlyState {
-            asset_state: Vec::new(),
-            token_state: Vec::new(),
-        });
+        assert_eq!(new_view.append_only_state, AppendOnlyState::default());
         assert_eq!(new_view.initiating_node_id, NodeID::stub());
+        assert!(NodeIdentity::verify(
+            identity.public_key(),
+            &new_view.signature,
+            &new_view.signing_payload().unwrap()
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn prepare_new_view_outdated_token_state() {
+        let (client, _lock) = test_db_client().await;
+        let token = TokenBuilder::default().build(&client).await.unwrap();
+        let asset = AssetState::load(token.asset_state_id, &client).await.unwrap();
+        let consensus_committee = test_committee(Some(asset.asset_id.clone()), NodeID::stub(), &client).await;
+        let mut instructions = Vec::new();
+        // both transfers buffered their state on top of the same token version
+        for owner in &["bob", "carol"] {
+            let instruction = InstructionBuilder {
+                asset_id: Some(asset.asset_id.clone()),
+                ..InstructionBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+            let update = UpdateToken {
+                append_state_data_json: Some(serde_json::json!({ "owner_pubkey": owner })),
+                ..UpdateToken::default()
+            };
+            let (_, token_state) = token.clone().append_state(update, instruction.id);
+            let state = AppendOnlyState {
+                token_state: vec![token_state],
+                ..AppendOnlyState::default()
+            };
+            instruction.store_append_only_state(&state, &client).await.unwrap();
+            instructions.push(instruction);
+        }
+
+        let identity = Test::<NodeIdentity>::new();
+        let new_view = consensus_committee
+            .prepare_new_view(&identity, &instructions, &client)
+            .await
+            .unwrap();
+        assert_eq!(new_view.instruction_set, vec![instructions[0].id.0]);
+        assert_eq!(new_view.invalid_instruction_set, vec![instructions[1].id.0]);
+        assert_eq!(new_view.append_only_state.token_state.len(), 1);
     }
 
     #[actix_rt::test]
@@ -526,11 +683,12 @@ mod test {
 
         let consensus_committee = test_committee(None, NodeID::stub(), &client).await;
         let mut views = vec![view.clone()];
-        let node_id = NodeID::stub();
+        let identity = Test::<NodeIdentity>::new();
+        let node_id = identity.node_id();
 
         // Create proposal selects the view, saves a new proposal, and signs a copy
         let proposal = consensus_committee
-            .create_proposal(NodeID::stub(), &mut views, &client)
+            .create_proposal(&identity, &mut views, &client)
             .await
             .unwrap();
         assert_eq!(proposal.status, ProposalStatus::Pending);
@@ -579,15 +737,78 @@ mod test {
     async fn validate_aggregate_signature_message() {
         let (client, _lock) = test_db_client().await;
         let proposal = ProposalBuilder::default().build(&client).await.unwrap();
-        let aggregate_signature_message = AggregateSignatureMessageBuilder::default()
-            .build(&client)
-            .await
-            .unwrap();
+        let identities: Vec<_> = (0..3).map(|_| Test::<NodeIdentity>::new()).collect();
+        let mut signatures = vec![];
+        for identity in identities.iter() {
+            signatures.push((
+                identity.node_id(),
+                proposal.create_partial_signature(identity).await.unwrap(),
+            ));
+        }
+        let public_keys: HashMap<_, _> = identities
+            .iter()
+            .map(|i| (i.node_id(), i.public_key().clone()))
+            .collect();
+        let mut aggregate_signature_message = AggregateSignatureMessageBuilder {
+            proposal_id: Some(proposal.id),
+            signature_data: SignatureData { signatures },
+            ..AggregateSignatureMessageBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
         let consensus_committee = test_committee(None, NodeID::stub(), &client).await;
         assert!(consensus_committee
-            .validate_aggregate_signature_message(&proposal, &aggregate_signature_message)
+            .validate_aggregate_signature_message(&proposal, &aggregate_signature_message, &public_keys, &client)
             .await
             .is_ok());
+
+        // Signature of another message
+        aggregate_signature_message.signature_data.signatures[1].1 = identities[1].sign(b"other").unwrap();
+        assert!(consensus_committee
+            .validate_aggregate_signature_message(&proposal, &aggregate_signature_message, &public_keys, &client)
+            .await
+            .is_err());
+        let reputation = NodeReputation::find_by_node_id(&identities[1].node_id().to_string(), &client)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reputation.bad_signatures, 1);
+        let reputations = NodeReputation::find_all(&client).await.unwrap();
+        assert_eq!(reputations.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn elect_leader() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let members: Vec<_> = ["node1", "node2"]
+            .iter()
+            .map(|node_id| CommitteeCandidate {
+                node_id: node_id.to_string(),
+                collateral: 0,
+            })
+            .collect();
+        CommitteeMember::insert_all(&asset.asset_id, &members, &client)
+            .await
+            .unwrap();
+        let leader = ConsensusCommittee::elect_leader(&asset.asset_id, 50, &client).await.unwrap();
+        assert_eq!(leader, Some("node1".to_string()));
+
+        for _ in 0..3 {
+            NodeReputation::record("node1", Misbehavior::BadSignature, &client)
+                .await
+                .unwrap();
+        }
+        let leader = ConsensusCommittee::elect_leader(&asset.asset_id, 50, &client).await.unwrap();
+        assert_eq!(leader, Some("node2".to_string()));
+        let leader = ConsensusCommittee::elect_leader(&asset.asset_id, 100, &client).await.unwrap();
+        assert_eq!(leader, Some("node2".to_string()));
+        NodeReputation::record("node2", Misbehavior::MissedRound, &client)
+            .await
+            .unwrap();
+        let leader = ConsensusCommittee::elect_leader(&asset.asset_id, 100, &client).await.unwrap();
+        assert_eq!(leader, None);
     }
 
     #[actix_rt::test]
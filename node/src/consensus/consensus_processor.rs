@@ -1,5 +1,10 @@
 use super::ConsensusWorker;
-use crate::{config::NodeConfig, consensus::LOG_TARGET, metrics::Metrics, types::NodeID};
+use crate::{
+    config::NodeConfig,
+    consensus::LOG_TARGET,
+    db::{models::InstructionStatus, notify::InstructionNotifier},
+    metrics::Metrics,
+};
 use actix::Addr;
 use log::{error, info};
 use std::{sync::mpsc::Receiver, time::Duration};
@@ -7,35 +12,67 @@ use tokio::time::delay_for;
 
 pub struct ConsensusProcessor {
     node_config: NodeConfig,
-    node_id: NodeID,
     metrics_addr: Option<Addr<Metrics>>,
+    notifier: Option<InstructionNotifier>,
 }
 
 impl ConsensusProcessor {
     pub fn new(node_config: NodeConfig, metrics_addr: Option<Addr<Metrics>>) -> Self {
         Self {
             node_config: node_config.clone(),
-            node_id: NodeID::stub(),
             metrics_addr,
+            notifier: None,
         }
     }
 
+    /// Starts the next poll as soon as instruction becomes Pending instead of waiting for `poll_period`
+    pub fn with_notifier(mut self, notifier: InstructionNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
     pub async fn start(&mut self, kill_receiver: Receiver<()>) {
         info!(target: LOG_TARGET, "Starting consensus processor");
         let interval = self.node_config.consensus.poll_period as u64;
         let consensus_worker = ConsensusWorker::new(self.node_config.clone(), self.metrics_addr.clone()).unwrap();
+        let mut notifications = self.notifier.as_ref().map(InstructionNotifier::subscribe);
+        match consensus_worker.recover().await {
+            Ok(0) => {},
+            Ok(count) => info!(target: LOG_TARGET, "Recovered {} interrupted proposals", count),
+            Err(e) => error!(target: LOG_TARGET, "Failed to recover interrupted proposals: {}", e),
+        }
 
         loop {
             if kill_receiver.try_recv().is_ok() {
                 info!(target: LOG_TARGET, "Stopping consensus processor");
+                if let Err(e) = consensus_worker.leave_cluster().await {
+                    error!(target: LOG_TARGET, "Failed to leave the cluster: {}", e);
+                }
                 break;
             }
+            if let Err(e) = consensus_worker.maintain_cluster().await {
+                error!(target: LOG_TARGET, "Failed to heartbeat cluster membership: {}", e);
+            }
+            if let Err(e) = consensus_worker.maintain_locks().await {
+                error!(target: LOG_TARGET, "Failed to maintain asset locks: {}", e);
+            }
+            if let Err(e) = consensus_worker.report_mempool().await {
+                error!(target: LOG_TARGET, "Failed to report mempool stats: {}", e);
+            }
             // Poll for any updates to consensus state
-            if let Err(e) = consensus_worker.work(self.node_id).await {
+            if let Err(e) = consensus_worker.work().await {
                 error!(target: LOG_TARGET, "Consensus error: {}", e);
             };
 
-            delay_for(Duration::from_secs(interval)).await;
+            match notifications.as_mut() {
+                Some(notifications) => {
+                    InstructionNotifier::wait(notifications, Duration::from_secs(interval), |notification| {
+                        notification.status == InstructionStatus::Pending
+                    })
+                    .await;
+                },
+                None => delay_for(Duration::from_secs(interval)).await,
+            }
         }
     }
 }
@@ -1,54 +1,271 @@
-use super::{communications::*, errors::ConsensusError, ConsensusCommittee};
+use super::{communications::*, errors::ConsensusError, ClusterWorker, ConsensusCommittee};
 use crate::{
     config::NodeConfig,
-    consensus::{instruction_state, instruction_state::InstructionTransitionContext, LOG_TARGET},
+    consensus::{instruction_state, instruction_state::InstructionTransitionContext, LockRegistry, Mempool, LOG_TARGET},
     db::{
-        models::{consensus::*, AssetState, ProposalStatus, Token, ViewStatus},
-        utils::{db::db_client, errors::DBError},
+        models::{
+            consensus::*,
+            AssetState,
+            CommitteeMember,
+            Misbehavior,
+            NewToken,
+            NodeReputation,
+            ProposalStatus,
+            Token,
+            ViewStatus,
+        },
+        utils::{
+            db::{build_pool, in_transaction},
+            errors::DBError,
+            instrument::get_client,
+        },
     },
-    metrics::Metrics,
-    types::{consensus::CommitteeState, InstructionID, NodeID},
+    fees::{self, FeesConfig},
+    metrics::{ConsensusEvent, LockEvent, MetricEvent, Metrics},
+    types::{consensus::CommitteeState, InstructionID},
+    wallet::Signer,
+    webhooks::{self, WebhookEvent},
 };
 
 use actix::Addr;
-use deadpool_postgres::Client;
-use log::{error, warn};
-
+use deadpool_postgres::{Client, Pool};
+use log::{error, info, trace, warn};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing_futures::Instrument;
+
+/// Pool of consensus workers processing different assets concurrently
+///
+/// Every worker holds a DB client and keeps picking pending committees until there is no work left.
+/// Asset lock (asset_states.blocked_until) guarantees the same asset is never processed by 2 workers,
+/// while committees are picked least recently locked asset first, so busy assets do not starve the others.
+/// Locks are owned by workers, locks of workers which stopped heartbeating are reclaimed by [Self::maintain_locks].
+/// With clustering enabled workers pick committees of assets partitioned to this process only, see [ClusterWorker].
 pub struct ConsensusWorker {
     node_config: NodeConfig,
+    signer: Arc<dyn Signer>,
     metrics_addr: Option<Addr<Metrics>>,
+    pool: Arc<Pool>,
+    slots: Arc<Semaphore>,
+    /// Pending instructions picked into consensus rounds
+    mempool: Arc<Mempool>,
+    /// Fee schedule charged for committed instructions
+    fees: Arc<FeesConfig>,
+    /// Delivers consensus messages to committee members, messages are not sent if not set
+    transport: Option<Arc<dyn ConsensusTransport>>,
+    locks: Arc<LockRegistry>,
+    /// Membership in the cluster of processes sharing the DB, every asset is processed if not set
+    cluster: Option<Arc<ClusterWorker>>,
 }
 
 impl ConsensusWorker {
     pub fn new(node_config: NodeConfig, metrics_addr: Option<Addr<Metrics>>) -> Result<Self, ConsensusError> {
+        let signer = node_config
+            .signer()
+            .ok_or_else(|| ConsensusError::error("Node identity is not loaded"))?;
+        let pool = Arc::new(build_pool(&node_config.postgres)?);
+        let slots = Arc::new(Semaphore::new(node_config.consensus.workers()));
+        let fees = Arc::new(node_config.fees.clone());
+        let mempool = Arc::new(Mempool::new(
+            node_config.consensus.mempool.clone(),
+            fees.clone(),
+            Arc::new(node_config.template.disabled_contracts()),
+        ));
+        let locks = Arc::new(LockRegistry::new(signer.node_id()));
+        let cluster = if node_config.consensus.cluster.enabled {
+            Some(Arc::new(ClusterWorker::new(
+                signer.node_id(),
+                &node_config.consensus.cluster,
+                node_config.consensus.poll_period as u64,
+            )))
+        } else {
+            None
+        };
         Ok(ConsensusWorker {
             node_config,
+            signer,
             metrics_addr,
+            pool,
+            slots,
+            mempool,
+            fees,
+            transport: None,
+            locks,
+            cluster,
         })
     }
 
-    pub async fn work(&self, node_id: NodeID) -> Result<(), ConsensusError> {
-        let config = self.node_config.clone();
-        let metrics_address = self.metrics_addr.clone();
-        let client = db_client(&config)
-            .await
-            .expect("Validator node unable to load db client");
-        actix_rt::spawn(async move {
-            if let Err(e) = ConsensusWorker::task(node_id, metrics_address, &client).await {
-                error!("ConsensusWorker work error: {}", e)
-            };
-        });
+    /// Sets transport delivering consensus messages to committee members
+    pub fn with_transport(self, transport: Arc<dyn ConsensusTransport>) -> Self {
+        Self {
+            transport: Some(transport),
+            ..self
+        }
+    }
+
+    /// Spawns worker per every free slot, workers from previous polls might still be running
+    pub async fn work(&self) -> Result<(), ConsensusError> {
+        let free_slots = self.slots.available_permits();
+        trace!(
+            target: LOG_TARGET,
+            "Spawning {} of {} consensus workers",
+            free_slots,
+            self.node_config.consensus.workers()
+        );
+        for _ in 0..free_slots {
+            let permit = self.slots.clone().acquire_owned().await;
+            let pool = self.pool.clone();
+            let signer = self.signer.clone();
+            let metrics_addr = self.metrics_addr.clone();
+            let mempool = self.mempool.clone();
+            let fees = self.fees.clone();
+            let transport = self.transport.clone();
+            let owner = self.locks.register();
+            let cluster = self.cluster.clone();
+            actix_rt::spawn(async move {
+                let _permit = permit;
+                let client = match get_client(&pool).await {
+                    Ok(client) => client,
+                    Err(err) => {
+                        error!(target: LOG_TARGET, "ConsensusWorker unable to load db client: {}", err);
+                        return;
+                    },
+                };
+                loop {
+                    let task = ConsensusWorker::task(
+                        &*signer,
+                        &mempool,
+                        &fees,
+                        transport.as_deref(),
+                        metrics_addr.clone(),
+                        &owner.id,
+                        cluster.as_ref().map(|cluster| cluster.id.as_str()),
+                        &client,
+                    );
+                    match task.await {
+                        Ok(true) => continue,
+                        Ok(false) => break,
+                        Err(err) => {
+                            error!(target: LOG_TARGET, "ConsensusWorker work error: {}", err);
+                            break;
+                        },
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Heartbeats locks of running workers and releases locks left behind by dead workers of any node
+    pub async fn maintain_locks(&self) -> Result<(), ConsensusError> {
+        let client = get_client(&self.pool).await.map_err(DBError::from)?;
+        AssetState::heartbeat_locks(&self.locks.owners(), &client).await?;
+        let stale_after = self.node_config.consensus.lock_stale_after();
+        for (asset_id, owner) in AssetState::reclaim_stale_locks(stale_after, &client).await? {
+            warn!(
+                target: LOG_TARGET,
+                "Reclaimed lock of asset {} held by {}, no heartbeat for {}s", asset_id, owner, stale_after
+            );
+            if let Some(metrics_addr) = self.metrics_addr.as_ref() {
+                let msg: MetricEvent = LockEvent {
+                    asset_id,
+                    reclaimed: true,
+                }
+                .into();
+                metrics_addr.do_send(msg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Heartbeats membership of this process in the cluster, no-op when clustering is disabled
+    pub async fn maintain_cluster(&self) -> Result<(), ConsensusError> {
+        if let Some(cluster) = self.cluster.as_ref() {
+            let client = get_client(&self.pool).await.map_err(DBError::from)?;
+            cluster.heartbeat(&client).await?;
+        }
+        Ok(())
+    }
 
+    /// Hands assets of this process over to the other processes of the cluster
+    pub async fn leave_cluster(&self) -> Result<(), ConsensusError> {
+        if let Some(cluster) = self.cluster.as_ref() {
+            let client = get_client(&self.pool).await.map_err(DBError::from)?;
+            cluster.leave(&client).await?;
+            info!(target: LOG_TARGET, "Worker {} left the cluster", cluster.id);
+        }
         Ok(())
     }
 
+    /// Sends mempool depth and age to metrics, no-op when metrics are disabled
+    pub async fn report_mempool(&self) -> Result<(), ConsensusError> {
+        if let Some(metrics_addr) = self.metrics_addr.as_ref() {
+            let client = get_client(&self.pool).await.map_err(DBError::from)?;
+            let msg: MetricEvent = Mempool::stats(&client).await?.into();
+            metrics_addr.do_send(msg);
+        }
+        Ok(())
+    }
+
+    /// Completes proposals which execution was interrupted, e.g. by node crash.
+    /// Should run before workers are started, as it ignores asset locks.
+    pub async fn recover(&self) -> Result<usize, ConsensusError> {
+        let client = get_client(&self.pool).await.map_err(DBError::from)?;
+        let interrupted = Proposal::find_interrupted(&client).await?;
+        let count = interrupted.len();
+        for proposal in interrupted {
+            let node_id = self.signer.node_id();
+            let leader = ConsensusCommittee::determine_leader_node_id(&proposal.asset_id, node_id).await? == node_id;
+            warn!(
+                target: LOG_TARGET,
+                "Recovering interrupted execution of proposal {:?} for asset {}", proposal.id, proposal.asset_id
+            );
+            ConsensusWorker::execute_proposal(proposal, leader, &self.fees, self.metrics_addr.clone(), &client).await?;
+        }
+        Ok(count)
+    }
+
+    /// Applies proposal state atomically, so the proposal is either fully executed or not at all.
+    /// Execution is idempotent, re-delivered or concurrently executed proposal is applied once.
     pub(crate) async fn execute_proposal(
         proposal: Proposal,
         leader: bool,
+        fees: &FeesConfig,
         metrics_addr: Option<Addr<Metrics>>,
         client: &Client,
     ) -> Result<(), ConsensusError>
     {
+        let span = tracing::info_span!(
+            "execute_proposal",
+            proposal_id = ?proposal.id,
+            asset_id = %proposal.asset_id,
+            leader
+        );
+        in_transaction(
+            client,
+            ConsensusWorker::apply_proposal(proposal, leader, fees, metrics_addr, client),
+        )
+        .instrument(span)
+        .await
+    }
+
+    async fn apply_proposal(
+        proposal: Proposal,
+        leader: bool,
+        fees: &FeesConfig,
+        metrics_addr: Option<Addr<Metrics>>,
+        client: &Client,
+    ) -> Result<(), ConsensusError>
+    {
+        if !proposal.lock_for_execution(&client).await? {
+            info!(
+                target: LOG_TARGET,
+                "Proposal {:?} for asset {} is already executed, skipping", proposal.id, proposal.asset_id
+            );
+            return Ok(());
+        }
+
         let view = if leader {
             // Find pending view for asset, switch to commit
             let asset_id = proposal.new_view.asset_id.clone();
@@ -80,10 +297,33 @@ impl ConsensusWorker {
             .await?
         };
 
+        if let Some(handover) = view.append_only_state.committee_handover.as_ref() {
+            CommitteeMember::replace_all(&view.asset_id, &handover.members, &client).await?;
+            info!(
+                target: LOG_TARGET,
+                "Asset {} handed over to committee {:?}, checkpoint {}",
+                view.asset_id,
+                handover.members,
+                handover.checkpoint_hash
+            );
+        }
+
         for asset_state_append_only in &*view.append_only_state.asset_state {
             AssetState::store_append_only_state(&asset_state_append_only, &client).await?;
         }
 
+        for token in view.append_only_state.tokens.iter() {
+            // asset state ID is local to the node
+            let asset_state = AssetState::find_by_asset_id(&token.token_id.asset_id(), &client)
+                .await?
+                .ok_or_else(|| DBError::NotFound)?;
+            let token = NewToken {
+                asset_state_id: asset_state.id,
+                ..token.clone()
+            };
+            Token::insert(token, &client).await?;
+        }
+
         for token_state_append_only in &*view.append_only_state.token_state {
             Token::store_append_only_state(&token_state_append_only, &client).await?;
         }
@@ -105,7 +345,8 @@ impl ConsensusWorker {
         instruction_state::transition(
             InstructionTransitionContext {
                 template_id: proposal.asset_id.template_id(),
-                instruction_ids: instruction_set,
+                asset_id: proposal.asset_id.clone(),
+                instruction_ids: instruction_set.clone(),
                 proposal_id: Some(proposal.id),
                 current_status: InstructionStatus::Pending,
                 status: InstructionStatus::Commit,
@@ -119,6 +360,7 @@ impl ConsensusWorker {
         instruction_state::transition(
             InstructionTransitionContext {
                 template_id: proposal.asset_id.template_id(),
+                asset_id: proposal.asset_id.clone(),
                 instruction_ids: invalid_instruction_set,
                 proposal_id: Some(proposal.id),
                 current_status: InstructionStatus::Pending,
@@ -130,80 +372,66 @@ impl ConsensusWorker {
         )
         .await?;
 
+        fees::charge_instructions(fees, &proposal.asset_id, &instruction_set, &client).await?;
+        webhooks::notify(WebhookEvent::proposal_finalized(&proposal), &client).await?;
+
         Ok(())
     }
 
+    /// Processes single consensus step of the next pending committee
+    /// Returns false when there is no pending work left, true if worker should look for more work
     async fn task(
-        node_id: NodeID,
+        signer: &dyn Signer,
+        mempool: &Mempool,
+        fees: &FeesConfig,
+        transport: Option<&dyn ConsensusTransport>,
         metrics_addr: Option<Addr<Metrics>>,
+        owner: &str,
+        worker: Option<&str>,
         client: &Client,
     ) -> Result<bool, ConsensusError>
     {
-        let committee = ConsensusCommittee::find_next_pending_committee(node_id, &client).await?;
+        let node_id = signer.node_id();
+        let committee = ConsensusCommittee::find_next_pending_committee(node_id, worker, mempool, &client).await?;
         match committee {
             Some(committee) => {
-                match &mut committee.acquire_lock(60 as u64, &client).await {
-                    Ok(_) => {
-                        match committee.state.clone() {
-                            // All nodes prepare new view, all but leader send to the leader node
-                            CommitteeState::PreparingView { pending_instructions } => {
-                                let new_view = committee
-                                    .prepare_new_view(node_id, &pending_instructions, &client)
-                                    .await?;
-                                if !committee.is_leader(node_id) {
-                                    submit_new_view(&committee, &new_view).await?;
-                                }
-                            },
-                            // Leader listens for view threshold being reached
-                            CommitteeState::ViewThresholdReached { mut views } => {
-                                let proposal = committee.create_proposal(node_id, &mut views, &client).await?;
-                                broadcast_proposal(&committee, &proposal).await?;
-                            },
-                            // All but leader receive proposal, confirm instruction set, and sign proposal if accepted
-                            CommitteeState::ReceivedLeaderProposal { proposal } => {
-                                if committee.confirm_proposal(&proposal).await? {
-                                    let signed_proposal = proposal.sign(node_id, &client).await?;
-                                    submit_signed_proposal(&committee, &signed_proposal).await?;
-                                } else {
-                                    warn!(
-                                        target: LOG_TARGET,
-                                        "Committee proposal failed consensus, asset_id: {}", committee.asset_id
-                                    );
-                                }
-                            },
-                            // Leader has supermajority threshold met for signatures, prepare aggregate signature and
-                            // send to other nodes
-                            CommitteeState::SignedProposalThresholdReached {
-                                proposal,
-                                signed_proposals,
-                            } => {
-                                let aggregate_signature_message = committee
-                                    .prepare_aggregate_signature_message(&proposal, &signed_proposals, &client)
-                                    .await?;
-                                broadcast_aggregate_signature_message(&committee, &aggregate_signature_message).await?;
-
-                                // Execute proposal for leader (other nodes will receive signed proposal and execute
-                                // upon validating supermajority signatures)
-                                ConsensusWorker::execute_proposal(proposal, true, metrics_addr, &client).await?;
-                            },
-                            // Leader finalized proposal received, nodes confirm signatures, and apply state.
-                            CommitteeState::LeaderFinalizedProposalReceived {
-                                proposal,
-                                aggregate_signature_message,
-                            } => {
-                                aggregate_signature_message.validate(&client).await?;
-
-                                // Execute proposal for non leader nodes
-                                ConsensusWorker::execute_proposal(proposal, false, metrics_addr, &client).await?;
-                            },
+                match committee.acquire_lock(60 as u64, owner, &client).await {
+                    Ok(true) => {
+                        if let Some(metrics_addr) = metrics_addr.as_ref() {
+                            let msg: MetricEvent = ConsensusEvent {
+                                asset_id: committee.asset_id.clone(),
+                                state: committee.state.name().into(),
+                            }
+                            .into();
+                            metrics_addr.do_send(msg);
                         }
-
+                        let span = tracing::info_span!(
+                            "consensus_step",
+                            asset_id = %committee.asset_id,
+                            state = committee.state.name()
+                        );
+                        ConsensusWorker::step(&committee, signer, fees, transport, metrics_addr, client)
+                            .instrument(span)
+                            .await?;
                         committee.release_lock(&client).await?;
                     },
-                    _ => {
-                        // Failed to acquire lock
-                        return Ok(false);
+                    Ok(false) => {
+                        // Another worker picked the same asset, locked assets are skipped on the next lookup
+                        trace!(
+                            target: LOG_TARGET,
+                            "Asset {} is locked by another worker",
+                            committee.asset_id
+                        );
+                        if let Some(metrics_addr) = metrics_addr.as_ref() {
+                            let msg: MetricEvent = LockEvent {
+                                asset_id: committee.asset_id.clone(),
+                                reclaimed: false,
+                            }
+                            .into();
+                            metrics_addr.do_send(msg);
+                        }
                     },
+                    Err(err) => return Err(err),
                 }
 
                 Ok(true)
@@ -211,6 +439,81 @@ impl ConsensusWorker {
             None => Ok(false),
         }
     }
+
+    /// Performs consensus step depending on committee state, committee asset should be locked by the worker
+    async fn step(
+        committee: &ConsensusCommittee,
+        signer: &dyn Signer,
+        fees: &FeesConfig,
+        transport: Option<&dyn ConsensusTransport>,
+        metrics_addr: Option<Addr<Metrics>>,
+        client: &Client,
+    ) -> Result<(), ConsensusError>
+    {
+        let node_id = signer.node_id();
+        match committee.state.clone() {
+            // All nodes prepare new view, all but leader send to the leader node
+            CommitteeState::PreparingView { pending_instructions } => {
+                let new_view = committee.prepare_new_view(signer, &pending_instructions, &client).await?;
+                if !committee.is_leader(node_id) {
+                    submit_new_view(&committee, transport, &new_view).await?;
+                }
+            },
+            // Leader listens for view threshold being reached
+            CommitteeState::ViewThresholdReached { mut views } => {
+                let proposal = committee.create_proposal(signer, &mut views, &client).await?;
+                broadcast_proposal(&committee, transport, &proposal).await?;
+            },
+            // All but leader receive proposal, confirm instruction set, and sign proposal if accepted
+            CommitteeState::ReceivedLeaderProposal { proposal } => {
+                if committee.confirm_proposal(&proposal).await? {
+                    let signature = proposal.create_partial_signature(signer).await?;
+                    let signed_proposal = proposal.sign(node_id, signature, &client).await?;
+                    submit_signed_proposal(&committee, transport, &signed_proposal).await?;
+                } else {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Committee proposal failed consensus, asset_id: {}", committee.asset_id
+                    );
+                    NodeReputation::record(&proposal.node_id.to_string(), Misbehavior::InvalidProposal, &client)
+                        .await?;
+                }
+            },
+            // Leader has supermajority threshold met for signatures, prepare aggregate signature and
+            // send to other nodes
+            CommitteeState::SignedProposalThresholdReached {
+                proposal,
+                signed_proposals,
+            } => {
+                let aggregate_signature_message = committee
+                    .prepare_aggregate_signature_message(&proposal, &signed_proposals, &client)
+                    .await?;
+                broadcast_aggregate_signature_message(
+                    &committee,
+                    transport,
+                    &proposal,
+                    &aggregate_signature_message,
+                    &client,
+                )
+                .await?;
+
+                // Execute proposal for leader (other nodes will receive signed proposal and execute
+                // upon validating supermajority signatures)
+                ConsensusWorker::execute_proposal(proposal, true, fees, metrics_addr, &client).await?;
+            },
+            // Leader finalized proposal received, nodes confirm signatures, and apply state.
+            CommitteeState::LeaderFinalizedProposalReceived {
+                proposal,
+                aggregate_signature_message,
+            } => {
+                aggregate_signature_message.validate(&client).await?;
+
+                // Execute proposal for non leader nodes
+                ConsensusWorker::execute_proposal(proposal, false, fees, metrics_addr, &client).await?;
+            },
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -225,6 +528,7 @@ mod test {
             TokenStatus,
             *,
         },
+        identity::NodeIdentity,
         test::utils::{
             builders::{
                 consensus::{
@@ -234,13 +538,18 @@ mod test {
                     SignedProposalBuilder,
                     ViewBuilder,
                 },
+                AssetStateBuilder,
                 TokenBuilder,
             },
+            build_test_config,
             test_db_client,
+            Test,
         },
-        types::consensus::AppendOnlyState,
+        types::{consensus::AppendOnlyState, TokenID},
     };
     use serde_json::json;
+    use std::time::Duration;
+    use tokio::time::delay_for;
 
     #[actix_rt::test]
     async fn execute_proposal() {
@@ -258,6 +567,13 @@ mod test {
         .await
         .unwrap();
 
+        // asset state ID of created token is resolved by the node
+        let new_token = NewToken {
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            asset_state_id: uuid::Uuid::nil(),
+            initial_data_json: json!({"created": true}),
+        };
+
         proposal.new_view.instruction_set = vec![instruction.id.0];
         proposal.new_view.append_only_state = AppendOnlyState {
             asset_state: vec![NewAssetStateAppendOnly {
@@ -271,12 +587,15 @@ mod test {
                 instruction_id: instruction.id,
                 status: TokenStatus::Active,
                 state_data_json: json!({"token-value": true, "token-value2": 1}),
+                expected_version: None,
             }],
+            tokens: vec![new_token.clone()],
+            ..AppendOnlyState::default()
         };
 
         // Execute as non leader triggering new view commit along with persistence of append only data
         let proposal_id = proposal.id.clone();
-        ConsensusWorker::execute_proposal(proposal, false, None, &client)
+        ConsensusWorker::execute_proposal(proposal, false, &FeesConfig::default(), None, &client)
             .await
             .unwrap();
 
@@ -290,19 +609,89 @@ mod test {
             token.additional_data_json,
             json!({"token-value": true, "token-value2": 1})
         );
+        let created = Token::find_by_token_id(&new_token.token_id, &client)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(created.asset_state_id, asset.id);
+        assert_eq!(created.additional_data_json, json!({"created": true}));
         let proposal = Proposal::load(proposal_id, &client).await.unwrap();
         assert_eq!(proposal.status, ProposalStatus::Finalized);
         let view = View::load_for_proposal(proposal.id, &client).await.unwrap();
         assert_eq!(view.status, ViewStatus::Commit);
     }
 
+    #[actix_rt::test]
+    async fn execute_proposal_once() {
+        let (client, _lock) = test_db_client().await;
+        let mut proposal = ProposalBuilder::default().build(&client).await.unwrap();
+        let asset = AssetState::find_by_asset_id(&proposal.asset_id, &client)
+            .await
+            .unwrap()
+            .unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        proposal.new_view.instruction_set = vec![instruction.id.0];
+        proposal.new_view.append_only_state = AppendOnlyState {
+            asset_state: vec![NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                status: AssetStatus::Active,
+                state_data_json: json!({"value": 1}),
+            }],
+            ..AppendOnlyState::default()
+        };
+
+        // Re-delivered aggregate signature message executes the same proposal again
+        for _ in 0..2 {
+            ConsensusWorker::execute_proposal(proposal.clone(), false, &FeesConfig::default(), None, &client)
+                .await
+                .unwrap();
+        }
+
+        let row = client
+            .query_one("SELECT COUNT(*) FROM asset_state_append_only WHERE asset_id = $1", &[
+                &asset.asset_id,
+            ])
+            .await
+            .unwrap();
+        assert_eq!(row.get::<_, i64>(0), 1);
+        let row = client
+            .query_one("SELECT COUNT(*) FROM views WHERE proposal_id = $1::\"ProposalID\"", &[
+                &proposal.id,
+            ])
+            .await
+            .unwrap();
+        assert_eq!(row.get::<_, i64>(0), 1);
+        let proposal = Proposal::load(proposal.id, &client).await.unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Finalized);
+    }
+
     #[actix_rt::test]
     async fn task_preparing_view() {
         let (client, _lock) = test_db_client().await;
         let instruction = InstructionBuilder::default().build(&client).await.unwrap();
-        assert!(ConsensusWorker::task(NodeID::stub(), None, &client).await.unwrap());
+        assert!(
+            ConsensusWorker::task(
+                &Test::<NodeIdentity>::new(),
+                &Mempool::default(),
+                &FeesConfig::default(),
+                None,
+                None,
+                "test",
+                None,
+                &client
+            )
+            .await
+            .unwrap()
+        );
 
-        let view_response = View::threshold_met(&client).await.unwrap();
+        let view_response = View::threshold_met(None, &client).await.unwrap();
         let (_, views) = view_response.iter().next().unwrap();
         assert_eq!(views.len(), 1);
         let view = &views[0];
@@ -316,10 +705,23 @@ mod test {
     async fn task_view_threshold_reached() {
         let (client, _lock) = test_db_client().await;
         let view = ViewBuilder::default().build(&client).await.unwrap();
-        assert!(ConsensusWorker::task(NodeID::stub(), None, &client).await.unwrap());
+        assert!(
+            ConsensusWorker::task(
+                &Test::<NodeIdentity>::new(),
+                &Mempool::default(),
+                &FeesConfig::default(),
+                None,
+                None,
+                "test",
+                None,
+                &client
+            )
+            .await
+            .unwrap()
+        );
 
         // Leader signs proposal immediately so fetch proposal through signed proposal pending
-        let signed_proposal_data = SignedProposal::threshold_met(&client).await.unwrap();
+        let signed_proposal_data = SignedProposal::threshold_met(None, &client).await.unwrap();
         let (_, signed_proposals) = signed_proposal_data.iter().next().unwrap();
         let signed_proposal = &signed_proposals[0];
 
@@ -332,9 +734,22 @@ mod test {
     async fn task_received_leader_proposal() {
         let (client, _lock) = test_db_client().await;
         let proposal = ProposalBuilder::default().build(&client).await.unwrap();
-        assert!(ConsensusWorker::task(NodeID::stub(), None, &client).await.unwrap());
+        assert!(
+            ConsensusWorker::task(
+                &Test::<NodeIdentity>::new(),
+                &Mempool::default(),
+                &FeesConfig::default(),
+                None,
+                None,
+                "test",
+                None,
+                &client
+            )
+            .await
+            .unwrap()
+        );
 
-        let signed_proposal_data = SignedProposal::threshold_met(&client).await.unwrap();
+        let signed_proposal_data = SignedProposal::threshold_met(None, &client).await.unwrap();
         let (_, signed_proposals) = signed_proposal_data.iter().next().unwrap();
         let signed_proposal = &signed_proposals[0];
         assert_eq!(signed_proposal.status, SignedProposalStatus::Pending);
@@ -370,7 +785,20 @@ mod test {
         .build(&client)
         .await
         .unwrap();
-        assert!(ConsensusWorker::task(NodeID::stub(), None, &client).await.unwrap());
+        assert!(
+            ConsensusWorker::task(
+                &Test::<NodeIdentity>::new(),
+                &Mempool::default(),
+                &FeesConfig::default(),
+                None,
+                None,
+                "test",
+                None,
+                &client
+            )
+            .await
+            .unwrap()
+        );
 
         let aggregate_signature_messages = AggregateSignatureMessage::load_by_proposal_id(proposal.id, &client)
             .await
@@ -413,7 +841,20 @@ mod test {
         .build(&client)
         .await
         .unwrap();
-        assert!(ConsensusWorker::task(NodeID::stub(), None, &client).await.unwrap());
+        assert!(
+            ConsensusWorker::task(
+                &Test::<NodeIdentity>::new(),
+                &Mempool::default(),
+                &FeesConfig::default(),
+                None,
+                None,
+                "test",
+                None,
+                &client
+            )
+            .await
+            .unwrap()
+        );
 
         let aggregate_signature_message = AggregateSignatureMessage::load(aggregate_signature_message.id, &client)
             .await
@@ -427,4 +868,118 @@ mod test {
         let instruction = Instruction::load(instruction.id, &client).await.unwrap();
         assert_eq!(instruction.status, InstructionStatus::Commit);
     }
+
+    #[actix_rt::test]
+    async fn work_multiple_assets() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let instruction2 = InstructionBuilder::default().build(&client).await.unwrap();
+        assert_ne!(instruction.asset_id, instruction2.asset_id);
+
+        let mut config = build_test_config().unwrap();
+        config.consensus.workers = Some(2);
+        let worker = ConsensusWorker::new(config, None).unwrap();
+        worker.work().await.unwrap();
+
+        // Every asset is driven through the whole consensus round by the spawned workers
+        for id in &[instruction.id, instruction2.id] {
+            let mut status = InstructionStatus::Pending;
+            for _ in 0..50u8 {
+                status = Instruction::load(*id, &client).await.unwrap().status;
+                if status == InstructionStatus::Commit {
+                    break;
+                }
+                delay_for(Duration::from_millis(100)).await;
+            }
+            assert_eq!(status, InstructionStatus::Commit);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn recover_interrupted_proposal() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let view = ViewBuilder {
+            status: Some(ViewStatus::PreCommit),
+            instruction_set: vec![instruction.id.0],
+            ..ViewBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let proposal = ProposalBuilder {
+            new_view: Some(view.clone().into()),
+            status: Some(ProposalStatus::Signed),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        // Node stopped right after accepting aggregate signature, before the proposal got executed
+        AggregateSignatureMessageBuilder {
+            proposal_id: Some(proposal.id),
+            status: AggregateSignatureMessageStatus::Accepted,
+            ..AggregateSignatureMessageBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let worker = ConsensusWorker::new(build_test_config().unwrap(), None).unwrap();
+        assert_eq!(worker.recover().await.unwrap(), 1);
+
+        let proposal = Proposal::load(proposal.id, &client).await.unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Finalized);
+        let view = View::load(view.id, &client).await.unwrap();
+        assert_eq!(view.status, ViewStatus::Commit);
+        let instruction = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!(instruction.status, InstructionStatus::Commit);
+        assert_eq!(worker.recover().await.unwrap(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn execute_proposal_rollback() {
+        let (client, _lock) = test_db_client().await;
+        let mut proposal = ProposalBuilder::default().build(&client).await.unwrap();
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        // Asset state is stored first, then token state fails as token does not exist
+        proposal.new_view.instruction_set = vec![instruction.id.0];
+        proposal.new_view.append_only_state = AppendOnlyState {
+            asset_state: vec![NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                status: AssetStatus::Active,
+                state_data_json: json!({"asset-value": true}),
+            }],
+            token_state: vec![NewTokenStateAppendOnly {
+                token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+                instruction_id: instruction.id,
+                status: TokenStatus::Active,
+                state_data_json: json!({"token-value": true}),
+                expected_version: None,
+            }],
+            ..AppendOnlyState::default()
+        };
+
+        let proposal_id = proposal.id;
+        assert!(ConsensusWorker::execute_proposal(proposal, false, &FeesConfig::default(), None, &client)
+            .await
+            .is_err());
+
+        let proposal = Proposal::load(proposal_id, &client).await.unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Pending);
+        assert!(View::load_for_proposal(proposal_id, &client).await.is_err());
+        let asset_reloaded = AssetState::load(asset.id, &client).await.unwrap();
+        assert_eq!(asset_reloaded.additional_data_json, asset.additional_data_json);
+        let instruction = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!(instruction.status, InstructionStatus::Pending);
+    }
 }
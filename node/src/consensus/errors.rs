@@ -1,4 +1,4 @@
-use crate::{db::utils::errors::DBError, types::errors::TypeError};
+use crate::{db::utils::errors::DBError, identity::IdentityError, types::errors::TypeError};
 use std::{io::Error as IOError, sync::mpsc::SendError};
 use thiserror::Error;
 
@@ -6,6 +6,8 @@ use thiserror::Error;
 pub enum ConsensusError {
     #[error("DB error: {0}")]
     DBError(#[from] DBError),
+    #[error("Identity error: {0}")]
+    IdentityError(#[from] IdentityError),
     #[error("Type error: {0}")]
     TypeError(#[from] TypeError),
     #[error("SendError: {0}")]
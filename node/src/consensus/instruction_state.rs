@@ -6,6 +6,7 @@ use crate::{
         metrics::Metrics,
     },
     types::*,
+    webhooks,
 };
 use actix::Addr;
 use deadpool_postgres::Client;
@@ -15,6 +16,7 @@ const LOG_TARGET: &'static str = "tari_validator_node::consensus";
 
 pub struct InstructionTransitionContext {
     pub template_id: TemplateID,
+    pub asset_id: AssetID,
     pub instruction_ids: Vec<InstructionID>,
     pub proposal_id: Option<ProposalID>,
     pub current_status: InstructionStatus,
@@ -31,6 +33,7 @@ impl InstructionTransitionContext {
                 let msg: MetricEvent = InstructionEvent {
                     id: instruction_id.clone(),
                     template_id: self.template_id,
+                    asset_id: self.asset_id.clone(),
                     status: self.status,
                 }
                 .into();
@@ -40,6 +43,15 @@ impl InstructionTransitionContext {
     }
 }
 
+#[tracing::instrument(
+    skip(context, client),
+    fields(
+        template = %context.template_id,
+        instructions = ?context.instruction_ids,
+        from = %context.current_status,
+        to = %context.status
+    )
+)]
 pub async fn transition(context: InstructionTransitionContext, client: &Client) -> Result<(), ConsensusError> {
     log::trace!(
         target: LOG_TARGET,
@@ -51,7 +63,10 @@ pub async fn transition(context: InstructionTransitionContext, client: &Client)
     // Valid state transitions
     match (context.current_status, context.status) {
         (InstructionStatus::Scheduled, InstructionStatus::Processing) |
+        // instruction was created but never dispatched to the actor
+        (InstructionStatus::Scheduled, InstructionStatus::Invalid) |
         (InstructionStatus::Processing, InstructionStatus::Pending) |
+        (InstructionStatus::Processing, InstructionStatus::Scheduled) |
         (InstructionStatus::Processing, InstructionStatus::Invalid) |
         (InstructionStatus::Pending, InstructionStatus::Invalid) |
         (InstructionStatus::Pending, InstructionStatus::Commit) => {},
@@ -71,6 +86,9 @@ pub async fn transition(context: InstructionTransitionContext, client: &Client)
         &client,
     )
     .await?;
+    if context.status == InstructionStatus::Commit || context.status == InstructionStatus::Invalid {
+        webhooks::notify_instructions(&context.asset_id, &context.instruction_ids, client).await?;
+    }
     context.metrics_update();
     Ok(())
 }
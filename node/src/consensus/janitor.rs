@@ -0,0 +1,245 @@
+//! Garbage collection of consensus records
+//!
+//! Every consensus round leaves views, proposals, partial signatures and aggregate messages behind.
+//! Records which reached terminal state (NotChosen and Invalid views, signatures of completed proposals,
+//! rejected aggregate messages, invalid and declined proposals) are never read again, [ConsensusJanitor]
+//! deletes those older than `retention_days`. Committed views, finalized proposals and their accepted
+//! aggregate messages are kept as the history of the asset.
+//! Janitor is enabled in `[validator.consensus.gc]` section of config.
+
+use crate::{
+    db::{
+        models::consensus::{AggregateSignatureMessage, Proposal, SignedProposal, View},
+        utils::{db::in_transaction, errors::DBError},
+    },
+    metrics::{MetricEvent, Metrics},
+};
+use actix::prelude::*;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use deadpool_postgres::{Client, Pool};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{ops::AddAssign, sync::Arc, time::Duration};
+
+const LOG_TARGET: &'static str = "tari_validator_node::consensus::janitor";
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ConsensusGcConfig {
+    /// Garbage collection is disabled unless explicitly enabled by node operator
+    pub enabled: bool,
+    /// Terminal consensus records last updated more than this number of days ago are deleted
+    pub retention_days: u32,
+    /// Period between collection runs in seconds
+    pub period: u64,
+}
+impl Default for ConsensusGcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: 30,
+            period: 3600,
+        }
+    }
+}
+
+impl ConsensusGcConfig {
+    /// Cut-off date of collection
+    pub fn before(&self) -> DateTime<Utc> {
+        Utc::now() - ChronoDuration::days(self.retention_days as i64)
+    }
+}
+
+/// Number of deleted records per table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConsensusGcStats {
+    pub views: u64,
+    pub signed_proposals: u64,
+    pub aggregate_signature_messages: u64,
+    pub proposals: u64,
+}
+
+impl ConsensusGcStats {
+    pub fn total(&self) -> u64 {
+        self.views + self.signed_proposals + self.aggregate_signature_messages + self.proposals
+    }
+}
+
+impl AddAssign for ConsensusGcStats {
+    fn add_assign(&mut self, other: Self) {
+        self.views += other.views;
+        self.signed_proposals += other.signed_proposals;
+        self.aggregate_signature_messages += other.aggregate_signature_messages;
+        self.proposals += other.proposals;
+    }
+}
+
+/// Deletes terminal consensus records last updated before `before` within single transaction,
+/// proposals are deleted last once nothing references them
+pub async fn collect(before: DateTime<Utc>, client: &Client) -> Result<ConsensusGcStats, DBError> {
+    in_transaction(client, async {
+        let aggregate_signature_messages = AggregateSignatureMessage::delete_orphaned(before, client).await?;
+        let signed_proposals = SignedProposal::delete_stale(before, client).await?;
+        let views = View::delete_terminal(before, client).await?;
+        let proposals = Proposal::delete_terminal(before, client).await?;
+        Ok(ConsensusGcStats {
+            views,
+            signed_proposals,
+            aggregate_signature_messages,
+            proposals,
+        })
+    })
+    .await
+}
+
+/// Actor collecting consensus records every `period` seconds, stops immediately if collection is disabled.
+/// Reclaimed records are reported to [Metrics] when metrics are enabled
+pub struct ConsensusJanitor {
+    config: ConsensusGcConfig,
+    pool: Arc<Pool>,
+    metrics_addr: Option<Addr<Metrics>>,
+}
+
+impl ConsensusJanitor {
+    pub fn new(config: ConsensusGcConfig, pool: Arc<Pool>, metrics_addr: Option<Addr<Metrics>>) -> Self {
+        Self {
+            config,
+            pool,
+            metrics_addr,
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let before = self.config.before();
+        let pool = self.pool.clone();
+        let metrics_addr = self.metrics_addr.clone();
+        ctx.spawn(
+            async move {
+                let res = match pool.get().await {
+                    Ok(client) => collect(before, &client).await,
+                    Err(err) => Err(err.into()),
+                };
+                match res {
+                    Ok(stats) => {
+                        log::info!(target: LOG_TARGET, "Collected consensus records: {:?}", stats);
+                        if let Some(metrics_addr) = metrics_addr {
+                            let msg: MetricEvent = stats.into();
+                            metrics_addr.do_send(msg);
+                        }
+                    },
+                    Err(err) => log::error!(target: LOG_TARGET, "Failed to collect consensus records: {}", err),
+                }
+            }
+            .into_actor(self),
+        );
+    }
+}
+
+impl Actor for ConsensusJanitor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.config.enabled {
+            ctx.stop();
+            return;
+        }
+        log::info!(
+            target: LOG_TARGET,
+            "Consensus GC enabled, deleting terminal records older than {} days every {}s",
+            self.config.retention_days,
+            self.config.period
+        );
+        ctx.run_interval(Duration::from_secs(self.config.period.max(1)), Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{AggregateSignatureMessageStatus, ProposalStatus, ViewStatus},
+        test::utils::{
+            builders::consensus::{
+                AggregateSignatureMessageBuilder,
+                ProposalBuilder,
+                SignedProposalBuilder,
+                ViewBuilder,
+            },
+            test_db_client,
+        },
+    };
+
+    #[actix_rt::test]
+    async fn collect_records() {
+        let (client, _lock) = test_db_client().await;
+        let not_chosen = ViewBuilder {
+            status: Some(ViewStatus::NotChosen),
+            ..ViewBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let committed = ViewBuilder {
+            status: Some(ViewStatus::Commit),
+            ..ViewBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let declined = ProposalBuilder {
+            status: Some(ProposalStatus::Declined),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let declined_signature = SignedProposalBuilder {
+            proposal_id: Some(declined.id),
+            ..SignedProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let finalized = ProposalBuilder {
+            status: Some(ProposalStatus::Finalized),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let accepted = AggregateSignatureMessageBuilder {
+            proposal_id: Some(finalized.id),
+            status: AggregateSignatureMessageStatus::Accepted,
+            ..AggregateSignatureMessageBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let rejected = AggregateSignatureMessageBuilder {
+            proposal_id: Some(finalized.id),
+            status: AggregateSignatureMessageStatus::Rejected,
+            ..AggregateSignatureMessageBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let before = Utc::now() - ChronoDuration::days(1);
+        assert_eq!(collect(before, &client).await.unwrap(), ConsensusGcStats::default());
+
+        let stats = collect(Utc::now(), &client).await.unwrap();
+        assert_eq!(stats, ConsensusGcStats {
+            views: 1,
+            signed_proposals: 1,
+            aggregate_signature_messages: 1,
+            proposals: 1,
+        });
+        assert_eq!(stats.total(), 4);
+        assert!(View::load(not_chosen.id, &client).await.is_err());
+        assert!(View::load(committed.id, &client).await.is_ok());
+        assert!(Proposal::load(declined.id, &client).await.is_err());
+        assert!(SignedProposal::load(declined_signature.id, &client).await.is_err());
+        assert!(Proposal::load(finalized.id, &client).await.is_ok());
+        assert!(AggregateSignatureMessage::load(accepted.id, &client).await.is_ok());
+        assert!(AggregateSignatureMessage::load(rejected.id, &client).await.is_err());
+    }
+}
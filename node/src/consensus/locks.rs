@@ -0,0 +1,80 @@
+use crate::types::NodeID;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+};
+
+/// Owners of asset locks held by consensus workers of this process
+///
+/// Every worker registers an owner which is recorded in asset_states.locked_by on lock, live owners are
+/// heartbeated by [ConsensusWorker::maintain_locks](super::ConsensusWorker::maintain_locks). Locks of owners
+/// which stopped heartbeating, e.g. node crashed or worker task panicked, are reclaimed by any node sharing the DB.
+#[derive(Debug)]
+pub struct LockRegistry {
+    /// Distinguishes restarts of the same node, so locks of the previous run are not heartbeated
+    prefix: String,
+    next: AtomicU64,
+    owners: Mutex<HashSet<String>>,
+}
+
+impl LockRegistry {
+    pub fn new(node_id: NodeID) -> Self {
+        Self {
+            prefix: format!("{}/{}", node_id, chrono::Utc::now().timestamp_millis()),
+            next: AtomicU64::new(1),
+            owners: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Registers new lock owner, owner is unregistered when returned guard is dropped
+    pub fn register(self: &Arc<Self>) -> LockOwner {
+        let id = format!("{}/{}", self.prefix, self.next.fetch_add(1, Ordering::Relaxed));
+        self.owners.lock().unwrap().insert(id.clone());
+        LockOwner {
+            id,
+            registry: self.clone(),
+        }
+    }
+
+    /// Owners which are still alive
+    pub fn owners(&self) -> Vec<String> {
+        self.owners.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Lock owner registered in [LockRegistry]
+#[derive(Debug)]
+pub struct LockOwner {
+    pub id: String,
+    registry: Arc<LockRegistry>,
+}
+
+impl Drop for LockOwner {
+    fn drop(&mut self) {
+        if let Ok(mut owners) = self.registry.owners.lock() {
+            owners.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn owners_unregister_on_drop() {
+        let registry = Arc::new(LockRegistry::new(NodeID::stub()));
+        let owner1 = registry.register();
+        let owner2 = registry.register();
+        assert_ne!(owner1.id, owner2.id);
+        assert!(owner1.id.starts_with(&format!("{}/", NodeID::stub())));
+        assert_eq!(registry.owners().len(), 2);
+
+        drop(owner1);
+        assert_eq!(registry.owners(), vec![owner2.id.clone()]);
+    }
+}
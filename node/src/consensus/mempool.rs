@@ -0,0 +1,246 @@
+use crate::{
+    db::{models::consensus::Instruction, utils::errors::DBError},
+    fees::FeesConfig,
+    types::AssetID,
+};
+use deadpool_postgres::Client;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, sync::Arc};
+
+/// Order of pending instructions of the asset picked into consensus round
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolOrdering {
+    /// Oldest first
+    Age,
+    /// Highest fee of the contract first, see [FeesConfig::fee]
+    Fee,
+    /// Highest priority first, set by `priority` field of contract call body
+    Priority,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct MempoolConfig {
+    pub ordering: MempoolOrdering,
+    /// Max instructions of an asset picked into a single round, 0 is unlimited.
+    /// Rest of instructions wait for the next round, while other assets get their turn.
+    pub max_batch: usize,
+}
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            ordering: MempoolOrdering::Age,
+            max_batch: 1000,
+        }
+    }
+}
+
+/// Pending instructions queue sitting between instruction creation and consensus round assembly
+///
+/// Assets get their turn fairly, least recently locked asset first, see [Instruction::find_pending],
+/// instructions of the asset are ordered according to [MempoolOrdering] and cut to `max_batch`.
+///
+/// Instructions with `priority` above 0 form the priority lane, reserved for callers with admin access,
+/// e.g. redeem issued by the operator after an incident: their assets get the turn before other assets,
+/// they are never cut from the batch and the runner executes them without waiting for `max_jobs` bandwidth.
+#[derive(Clone, Debug, Default)]
+pub struct Mempool {
+    config: MempoolConfig,
+    fees: Arc<FeesConfig>,
+    /// Contracts disabled by template config, instructions of disabled contracts stay pending
+    disabled_contracts: Arc<Vec<String>>,
+}
+
+/// Pending instructions waiting for consensus
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MempoolStats {
+    pub depth: i64,
+    pub assets: i64,
+    /// Age of the oldest pending instruction in milliseconds
+    pub max_age_ms: i64,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig, fees: Arc<FeesConfig>, disabled_contracts: Arc<Vec<String>>) -> Self {
+        Self {
+            config,
+            fees,
+            disabled_contracts,
+        }
+    }
+
+    /// Instructions of the asset whose turn it is to be processed, None if there are no pending instructions
+    pub async fn next_batch(
+        &self,
+        worker: Option<&str>,
+        client: &Client,
+    ) -> Result<Option<(AssetID, Vec<Instruction>)>, DBError>
+    {
+        Ok(Instruction::find_pending(&self.disabled_contracts, worker, client)
+            .await?
+            .map(|(asset_id, instructions)| (asset_id, self.batch(instructions))))
+    }
+
+    /// Orders instructions and cuts them to `max_batch`, ties are broken by age.
+    /// Priority lane takes slots of the batch first, the rest of slots is filled in the configured order.
+    fn batch(&self, mut instructions: Vec<Instruction>) -> Vec<Instruction> {
+        match self.config.ordering {
+            MempoolOrdering::Age => instructions.sort_by_key(|i| i.created_at),
+            MempoolOrdering::Fee => {
+                instructions.sort_by_key(|i| (Reverse(self.fees.fee(&i.template_id, &i.contract_name)), i.created_at))
+            },
+            MempoolOrdering::Priority => instructions.sort_by_key(|i| (Reverse(i.priority), i.created_at)),
+        }
+        if self.config.max_batch > 0 {
+            let lane = instructions.iter().filter(|i| i.priority > 0).count();
+            let mut slots = self.config.max_batch.saturating_sub(lane);
+            instructions.retain(|i| {
+                if i.priority > 0 {
+                    return true;
+                }
+                let keep = slots > 0;
+                slots = slots.saturating_sub(1);
+                keep
+            });
+        }
+        instructions
+    }
+
+    /// Queue depth and age across all assets
+    pub async fn stats(client: &Client) -> Result<MempoolStats, DBError> {
+        const QUERY: &'static str = "
+            SELECT
+                COUNT(*) AS depth,
+                COUNT(DISTINCT asset_id) AS assets,
+                COALESCE(EXTRACT(EPOCH FROM now() - MIN(created_at)) * 1000, 0)::BIGINT AS max_age_ms
+            FROM instructions
+            WHERE status = 'Pending'";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[]).await?;
+        Ok(MempoolStats {
+            depth: row.get("depth"),
+            assets: row.get("assets"),
+            max_age_ms: row.get("max_age_ms"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        fees::TemplateFees,
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetStateBuilder},
+            test_db_client,
+        },
+        types::InstructionID,
+    };
+
+    async fn batch_ids(mempool: Mempool, asset_id: &AssetID, client: &Client) -> Vec<InstructionID> {
+        let (batch_asset_id, batch) = mempool.next_batch(None, client).await.unwrap().unwrap();
+        assert_eq!(&batch_asset_id, asset_id);
+        batch.into_iter().map(|i| i.id).collect()
+    }
+
+    async fn set_priority(id: InstructionID, priority: i32, client: &Client) {
+        client
+            .execute("UPDATE instructions SET priority = $1 WHERE id = $2::\"InstructionID\"", &[
+                &priority, &id,
+            ])
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn next_batch_ordering() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let mut ids = vec![];
+        for (contract_name, priority) in &[("cheap", 5), ("expensive", 0), ("cheap", 10)] {
+            let instruction = InstructionBuilder {
+                asset_id: Some(asset.asset_id.clone()),
+                contract_name: contract_name.to_string(),
+                ..InstructionBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+            client
+                .execute("UPDATE instructions SET priority = $1 WHERE id = $2::\"InstructionID\"", &[
+                    priority,
+                    &instruction.id,
+                ])
+                .await
+                .unwrap();
+            ids.push(instruction.id);
+        }
+        let mut fees = FeesConfig::default();
+        fees.templates.insert("999".into(), TemplateFees {
+            fee: Some(1),
+            contracts: vec![("expensive".to_string(), 10)].into_iter().collect(),
+        });
+        let fees = Arc::new(fees);
+        let mempool = |ordering, max_batch| {
+            Mempool::new(MempoolConfig { ordering, max_batch }, fees.clone(), Arc::new(vec![]))
+        };
+
+        let batch = batch_ids(mempool(MempoolOrdering::Age, 0), &asset.asset_id, &client).await;
+        assert_eq!(batch, ids);
+        let batch = batch_ids(mempool(MempoolOrdering::Fee, 0), &asset.asset_id, &client).await;
+        assert_eq!(batch, vec![ids[1], ids[0], ids[2]]);
+        let batch = batch_ids(mempool(MempoolOrdering::Priority, 2), &asset.asset_id, &client).await;
+        assert_eq!(batch, vec![ids[2], ids[0]]);
+
+        let stats = Mempool::stats(&client).await.unwrap();
+        assert_eq!(stats.depth, 3);
+        assert_eq!(stats.assets, 1);
+        assert!(stats.max_age_ms >= 0);
+    }
+
+    #[actix_rt::test]
+    async fn priority_lane() {
+        let (client, _lock) = test_db_client().await;
+        let user_asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        InstructionBuilder {
+            asset_id: Some(user_asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let operator_asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let mut ids = vec![];
+        for priority in &[0, 0, 10] {
+            let instruction = InstructionBuilder {
+                asset_id: Some(operator_asset.asset_id.clone()),
+                ..InstructionBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+            set_priority(instruction.id, *priority, &client).await;
+            ids.push(instruction.id);
+        }
+        let mempool = |max_batch| {
+            let config = MempoolConfig {
+                ordering: MempoolOrdering::Age,
+                max_batch,
+            };
+            Mempool::new(config, Arc::new(FeesConfig::default()), Arc::new(vec![]))
+        };
+
+        // asset of the priority lane gets the turn first, lane is never cut from the batch
+        let batch = batch_ids(mempool(0), &operator_asset.asset_id, &client).await;
+        assert_eq!(batch, ids);
+        let batch = batch_ids(mempool(2), &operator_asset.asset_id, &client).await;
+        assert_eq!(batch, vec![ids[0], ids[2]]);
+        let batch = batch_ids(mempool(1), &operator_asset.asset_id, &client).await;
+        assert_eq!(batch, vec![ids[2]]);
+
+        set_priority(ids[2], 0, &client).await;
+        let (asset_id, _) = mempool(0).next_batch(None, &client).await.unwrap().unwrap();
+        assert_eq!(asset_id, user_asset.asset_id);
+    }
+}
@@ -1,10 +1,18 @@
 pub use self::{
+    cluster::{ClusterConfig, ClusterWorker},
+    committee_selection::{select_committee, CommitteeCandidate, CommitteeHandover},
     config::ConsensusConfig,
     consensus_committee::ConsensusCommittee,
     consensus_processor::ConsensusProcessor,
     consensus_worker::ConsensusWorker,
+    janitor::{ConsensusGcConfig, ConsensusGcStats, ConsensusJanitor},
+    locks::{LockOwner, LockRegistry},
+    mempool::{Mempool, MempoolConfig, MempoolOrdering, MempoolStats},
+    status::ConsensusStatus,
 };
 
+pub mod cluster;
+mod committee_selection;
 pub mod communications;
 mod config;
 mod consensus_committee;
@@ -12,5 +20,9 @@ mod consensus_processor;
 mod consensus_worker;
 pub mod errors;
 pub mod instruction_state;
+pub mod janitor;
+mod locks;
+mod mempool;
+mod status;
 
 const LOG_TARGET: &'static str = "tari_validator_node::consensus";
@@ -0,0 +1,184 @@
+use super::{errors::ConsensusError, ConsensusCommittee};
+use crate::{
+    db::{
+        models::{AggregateSignatureMessageStatus, AssetState, ProposalStatus},
+        utils::errors::DBError,
+    },
+    types::{AssetID, NodeID, ProposalID},
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+
+/// Committee state of an asset assembled from consensus tables, for debugging stalled rounds
+///
+/// `state` is the [CommitteeState](crate::types::consensus::CommitteeState) variant consensus worker would process
+/// next for the asset, following the precedence of [ConsensusCommittee::find_next_pending_committee],
+/// None if there is nothing to process
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConsensusStatus {
+    pub asset_id: AssetID,
+    pub state: Option<&'static str>,
+    pub leader_node_id: NodeID,
+    /// Asset is locked by a consensus worker until `blocked_until`
+    pub locked: bool,
+    /// Worker holding the lock, see [LockRegistry](super::LockRegistry)
+    pub lock_holder: Option<String>,
+    pub blocked_until: DateTime<Utc>,
+    pub pending_instructions: i64,
+    /// Views waiting for the leader to reach threshold
+    pub prepared_views: Vec<uuid::Uuid>,
+    /// Latest proposal which is not finalized, declined or invalid
+    pub proposal_id: Option<ProposalID>,
+    pub proposal_status: Option<ProposalStatus>,
+    /// Signatures of `proposal_id` pending and total
+    pub pending_signatures: i64,
+    pub signatures: i64,
+    pub aggregate_signature_status: Option<AggregateSignatureMessageStatus>,
+}
+
+impl ConsensusStatus {
+    pub async fn load(asset: &AssetState, node_id: NodeID, client: &Client) -> Result<Self, ConsensusError> {
+        const QUERY: &'static str = "
+            WITH open_proposal AS (
+                SELECT id, status
+                FROM proposals
+                WHERE asset_id = $1 AND status NOT IN ('Finalized', 'Invalid', 'Declined')
+                ORDER BY created_at DESC
+                LIMIT 1
+            )
+            SELECT
+                (SELECT COUNT(*) FROM instructions WHERE asset_id = $1 AND status = 'Pending') AS pending_instructions,
+                (SELECT COALESCE(array_agg(id ORDER BY created_at), '{}') FROM views
+                    WHERE asset_id = $1 AND status = 'Prepare') AS prepared_views,
+                (SELECT id FROM open_proposal) AS proposal_id,
+                (SELECT status FROM open_proposal) AS proposal_status,
+                (SELECT COUNT(*) FILTER (WHERE sp.status = 'Pending') FROM signed_proposals sp
+                    JOIN open_proposal p ON sp.proposal_id = p.id) AS pending_signatures,
+                (SELECT COUNT(*) FROM signed_proposals sp
+                    JOIN open_proposal p ON sp.proposal_id = p.id) AS signatures,
+                (SELECT asm.status FROM aggregate_signature_messages asm
+                    JOIN open_proposal p ON asm.proposal_id = p.id
+                    ORDER BY asm.created_at DESC LIMIT 1) AS aggregate_signature_status,
+                (SELECT blocked_until > now() FROM asset_states WHERE asset_id = $1) AS locked,
+                (SELECT locked_by FROM asset_states WHERE asset_id = $1 AND blocked_until > now()) AS lock_holder";
+        let stmt = client.prepare(QUERY).await.map_err(DBError::from)?;
+        let row = client.query_one(&stmt, &[&asset.asset_id]).await.map_err(DBError::from)?;
+        let leader_node_id = ConsensusCommittee::determine_leader_node_id(&asset.asset_id, node_id).await?;
+        let mut status = Self {
+            asset_id: asset.asset_id.clone(),
+            state: None,
+            leader_node_id,
+            locked: row.get("locked"),
+            lock_holder: row.get("lock_holder"),
+            blocked_until: asset.blocked_until,
+            pending_instructions: row.get("pending_instructions"),
+            prepared_views: row.get("prepared_views"),
+            proposal_id: row.get("proposal_id"),
+            proposal_status: row.get("proposal_status"),
+            pending_signatures: row.get("pending_signatures"),
+            signatures: row.get("signatures"),
+            aggregate_signature_status: row.get("aggregate_signature_status"),
+        };
+        status.state = status.committee_state();
+        Ok(status)
+    }
+
+    fn committee_state(&self) -> Option<&'static str> {
+        if self.aggregate_signature_status == Some(AggregateSignatureMessageStatus::Pending) {
+            Some("LeaderFinalizedProposalReceived")
+        } else if self.pending_signatures > 0 {
+            Some("SignedProposalThresholdReached")
+        } else if self.proposal_status == Some(ProposalStatus::Pending) {
+            Some("ReceivedLeaderProposal")
+        } else if !self.prepared_views.is_empty() {
+            Some("ViewThresholdReached")
+        } else if self.pending_instructions > 0 {
+            Some("PreparingView")
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{InstructionStatus, ViewStatus},
+        test::utils::{
+            builders::{
+                consensus::{InstructionBuilder, ProposalBuilder, SignedProposalBuilder, ViewBuilder},
+                AssetStateBuilder,
+            },
+            test_db_client,
+            Test,
+        },
+    };
+
+    #[actix_rt::test]
+    async fn consensus_status() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let node_id = Test::<NodeID>::new();
+
+        let status = ConsensusStatus::load(&asset, node_id, &client).await.unwrap();
+        assert_eq!(status.state, None);
+        assert!(!status.locked);
+        assert_eq!(status.lock_holder, None);
+        assert_eq!(status.leader_node_id, node_id);
+
+        InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            status: InstructionStatus::Pending,
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let status = ConsensusStatus::load(&asset, node_id, &client).await.unwrap();
+        assert_eq!(status.state, Some("PreparingView"));
+        assert_eq!(status.pending_instructions, 1);
+
+        let view = ViewBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            status: Some(ViewStatus::Prepare),
+            ..ViewBuilder::default()
+        };
+        let new_view = view.prepare(&client).await.unwrap();
+        let view = view.build(&client).await.unwrap();
+        let status = ConsensusStatus::load(&asset, node_id, &client).await.unwrap();
+        assert_eq!(status.state, Some("ViewThresholdReached"));
+        assert_eq!(status.prepared_views, vec![view.id]);
+
+        let proposal = ProposalBuilder {
+            new_view: Some(new_view),
+            node_id: Some(node_id),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let mut asset = AssetState::load(asset.id, &client).await.unwrap();
+        assert!(asset.acquire_owned_lock(60, Some("worker"), &client).await.unwrap());
+        let status = ConsensusStatus::load(&asset, node_id, &client).await.unwrap();
+        assert_eq!(status.state, Some("ReceivedLeaderProposal"));
+        assert_eq!(status.proposal_id, Some(proposal.id));
+        assert_eq!(status.proposal_status, Some(ProposalStatus::Pending));
+        assert_eq!(status.blocked_until, asset.blocked_until);
+        assert!(status.locked);
+        assert_eq!(status.lock_holder, Some("worker".to_string()));
+
+        SignedProposalBuilder {
+            proposal_id: Some(proposal.id),
+            ..SignedProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let status = ConsensusStatus::load(&asset, node_id, &client).await.unwrap();
+        assert_eq!(status.state, Some("SignedProposalThresholdReached"));
+        assert_eq!(status.pending_signatures, 1);
+        assert_eq!(status.signatures, 1);
+    }
+}
@@ -0,0 +1,709 @@
+//! Asset lifecycle
+//!
+//! Assets are created and retired by their issuer via `/assets` API, retirement appends Retired state
+//! of the asset with a committed `retire_asset` instruction as a reference.
+//! Asset committee is selected on creation according to `committee_mode` and stored in `committee_members`.
+//! Domain of the asset is optionally verified against its RAID TXT record, see [verify_raid].
+//! Long-lived assets migrate to a new committee with `handover` instruction, see [handover_asset].
+//! Issuer updates asset metadata with `update_metadata` instruction going through consensus, see [update_metadata].
+//! Assets past their `expiry_date` are retired by [AssetExpiryJanitor] with `expire_asset` instruction.
+//! Test assets are removed from the node along with all their data by [wipe_assets] (`tvnc wipe --asset`).
+
+use super::{
+    models::{
+        consensus::instructions::{Instruction, NewInstruction},
+        AssetMetadata,
+        AssetState,
+        AssetStatus,
+        CommitteeMember,
+        DigitalAsset,
+        InstructionStatus,
+        NewAssetState,
+        NewAssetStateAppendOnly,
+        NewDigitalAsset,
+        RaidStatus,
+        METADATA_FIELD,
+    },
+    utils::{db::in_transaction, errors::DBError, validation::ValidationErrors},
+};
+use crate::{
+    consensus::{select_committee, CommitteeCandidate, CommitteeHandover},
+    raid::{self, RaidConfig},
+    types::{consensus::AppendOnlyState, AssetID, CommitteeMode, InstructionID, NodeID, Pubkey},
+    webhooks,
+};
+use actix::prelude::*;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, Pool};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{sync::Arc, time::Duration};
+use tokio_postgres::types::Type;
+
+const LOG_TARGET: &'static str = "tari_validator_node::db::lifecycle";
+/// Period between expiry checks
+pub const EXPIRY_CHECK_PERIOD: Duration = Duration::from_secs(60);
+/// Contract name asset creation requests are signed for, no instruction is created
+pub const CREATE_ASSET_CONTRACT: &'static str = "create_asset";
+pub const RETIRE_ASSET_CONTRACT: &'static str = "retire_asset";
+pub const EXPIRE_ASSET_CONTRACT: &'static str = "expire_asset";
+pub const HANDOVER_CONTRACT: &'static str = "handover";
+pub const UPDATE_METADATA_CONTRACT: &'static str = "update_metadata";
+
+/// Asset params submitted by issuer
+#[derive(Deserialize, Clone, Debug)]
+pub struct NewAsset {
+    pub asset_id: AssetID,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub fqdn: Option<String>,
+    pub limit_per_wallet: Option<u32>,
+    /// Upper bound of tokens issued for the asset, unlimited if not set
+    pub max_supply: Option<u32>,
+    #[serde(default)]
+    pub allow_transfers: bool,
+    #[serde(default)]
+    pub authorized_signers: Vec<String>,
+    pub expiry_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub initial_data_json: Value,
+    /// Creator mode with empty trusted set by default, i.e. committee of the node registering the asset
+    #[serde(default)]
+    pub committee_mode: CommitteeMode,
+}
+
+/// Creates digital asset and asset state issued by `issuer`, committee is selected among `candidates`
+/// or is formed by the node `node_id` registering the asset, see [select_committee]
+pub async fn create_asset(
+    params: NewAsset,
+    issuer: Pubkey,
+    node_id: Option<NodeID>,
+    candidates: &[CommitteeCandidate],
+    client: &Client,
+) -> Result<AssetState, DBError>
+{
+    let committee = select_committee(&params.committee_mode, node_id, candidates)?;
+    in_transaction(client, async move {
+        let raid_id = params.asset_id.raid_id().to_base58();
+        let digital_asset_id = DigitalAsset::insert(
+            NewDigitalAsset {
+                template_type: params.asset_id.template_id().template_type(),
+                committee_mode: params.committee_mode.clone(),
+                fqdn: params.fqdn.clone(),
+                raid_id: Some(raid_id),
+                ..NewDigitalAsset::default()
+            },
+            client,
+        )
+        .await?;
+        let id = AssetState::insert(
+            NewAssetState {
+                name: params.name,
+                description: params.description,
+                limit_per_wallet: params.limit_per_wallet,
+                max_supply: params.max_supply,
+                allow_transfers: params.allow_transfers,
+                asset_issuer_pub_key: issuer,
+                authorized_signers: params.authorized_signers,
+                expiry_date: params.expiry_date,
+                initial_data_json: match params.initial_data_json {
+                    Value::Null => json!({}),
+                    data => data,
+                },
+                asset_id: params.asset_id.clone(),
+                digital_asset_id,
+                ..NewAssetState::default()
+            },
+            client,
+        )
+        .await?;
+        CommitteeMember::insert_all(&params.asset_id, &committee, client).await?;
+        AssetState::load(id, client).await
+    })
+    .await
+}
+
+/// Verifies RAID TXT record of the asset domain and stores the result with the digital asset,
+/// assets without `fqdn` or created while verification is disabled stay Unverified
+pub async fn verify_raid(asset: &AssetState, config: &RaidConfig, client: &Client) -> Result<RaidStatus, DBError> {
+    let digital_asset = DigitalAsset::load(asset.digital_asset_id, client).await?;
+    let fqdn = match digital_asset.fqdn.as_ref() {
+        Some(fqdn) => fqdn,
+        None => return Ok(RaidStatus::Unverified),
+    };
+    let status = raid::verify(config, fqdn, &asset.asset_id.raid_id(), &asset.asset_issuer_pub_key).await;
+    if status != RaidStatus::Unverified {
+        DigitalAsset::set_raid_status(digital_asset.id, status, client).await?;
+    }
+    Ok(status)
+}
+
+/// Retires asset, `instruction` is committed as a reference of Retired state
+pub async fn retire_asset(
+    asset: &AssetState,
+    instruction: NewInstruction,
+    client: &Client,
+) -> Result<AssetState, DBError>
+{
+    if asset.status == AssetStatus::Retired {
+        return Err(DBError::bad_query("Asset is already retired"));
+    }
+    in_transaction(client, async move {
+        let instruction = Instruction::insert(
+            NewInstruction {
+                asset_id: asset.asset_id.clone(),
+                token_id: None,
+                template_id: asset.asset_id.template_id(),
+                status: InstructionStatus::Commit,
+                ..instruction
+            },
+            client,
+        )
+        .await?;
+        AssetState::store_append_only_state(
+            &NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                state_data_json: asset.additional_data_json.clone(),
+                status: AssetStatus::Retired,
+            },
+            client,
+        )
+        .await?;
+        webhooks::notify_instructions(&asset.asset_id, &[instruction.id], client).await?;
+        AssetState::load(asset.id, client).await
+    })
+    .await
+}
+
+/// Starts handover of the asset to the committee of `members`
+///
+/// Asset is frozen right away, so no new instructions are accepted, and its state is checkpointed.
+/// Pending `instruction` carries the new committee through consensus, committing its proposal
+/// replaces committee members and unfreezes the asset.
+pub async fn handover_asset(
+    asset: &AssetState,
+    members: Vec<CommitteeCandidate>,
+    instruction: NewInstruction,
+    client: &Client,
+) -> Result<Instruction, DBError>
+{
+    if asset.status != AssetStatus::Active {
+        return Err(DBError::bad_query("Only active asset can be handed over"));
+    }
+    if members.is_empty() {
+        let mut errors = ValidationErrors::default();
+        errors.append_validation_error("committee", "members", "New committee can not be empty");
+        errors.validate()?;
+    }
+    in_transaction(client, async move {
+        let instruction = Instruction::insert(
+            NewInstruction {
+                asset_id: asset.asset_id.clone(),
+                token_id: None,
+                template_id: asset.asset_id.template_id(),
+                contract_name: HANDOVER_CONTRACT.into(),
+                params: json!({ "members": members }),
+                status: InstructionStatus::Pending,
+                ..instruction
+            },
+            client,
+        )
+        .await?;
+        AssetState::store_append_only_state(
+            &NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                state_data_json: asset.additional_data_json.clone(),
+                status: AssetStatus::Frozen,
+            },
+            client,
+        )
+        .await?;
+        let checkpoint_hash = asset.checkpoint_hash(client).await?;
+        let state = AppendOnlyState {
+            asset_state: vec![NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                state_data_json: asset.additional_data_json.clone(),
+                status: AssetStatus::Active,
+            }],
+            committee_handover: Some(CommitteeHandover {
+                members,
+                checkpoint_hash,
+            }),
+            ..AppendOnlyState::default()
+        };
+        instruction.store_append_only_state(&state, client).await?;
+        Ok(instruction)
+    })
+    .await
+}
+
+/// Updates metadata of the asset, fields of `metadata` which are not set are kept
+///
+/// Pending `instruction` carries the new asset state through consensus, the state replaces the current one
+/// once committed, so metadata changes are kept in the append only history of the asset, see [AssetState::versions]
+pub async fn update_metadata(
+    asset: &AssetState,
+    metadata: AssetMetadata,
+    instruction: NewInstruction,
+    client: &Client,
+) -> Result<Instruction, DBError>
+{
+    if asset.status != AssetStatus::Active {
+        return Err(DBError::bad_query("Only active asset metadata can be updated"));
+    }
+    let current = asset.metadata();
+    let metadata = AssetMetadata {
+        description: metadata.description.or(current.description),
+        uris: if metadata.uris.is_empty() { current.uris } else { metadata.uris },
+    };
+    let mut errors = ValidationErrors::default();
+    if metadata.description.as_ref().map(|description| description.len() > 216).unwrap_or(false) {
+        errors.append_validation_error("length", "description", "description should not exceed 216 characters");
+    }
+    if metadata.uris.iter().any(|uri| uri.trim().is_empty()) {
+        errors.append_validation_error("required", "uris", "uris should not be empty");
+    }
+    errors.validate()?;
+    let mut state_data_json = match asset.additional_data_json.clone() {
+        Value::Object(data) => Value::Object(data),
+        _ => json!({}),
+    };
+    state_data_json[METADATA_FIELD] = json!(metadata);
+    in_transaction(client, async move {
+        let instruction = Instruction::insert(
+            NewInstruction {
+                asset_id: asset.asset_id.clone(),
+                token_id: None,
+                template_id: asset.asset_id.template_id(),
+                contract_name: UPDATE_METADATA_CONTRACT.into(),
+                status: InstructionStatus::Pending,
+                ..instruction
+            },
+            client,
+        )
+        .await?;
+        let state = AppendOnlyState {
+            asset_state: vec![NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                state_data_json,
+                status: asset.status,
+            }],
+            ..AppendOnlyState::default()
+        };
+        instruction.store_append_only_state(&state, client).await?;
+        Ok(instruction)
+    })
+    .await
+}
+
+/// Retires assets past their expiry date, returns expired assets
+pub async fn expire_assets(node_id: NodeID, client: &Client) -> Result<Vec<AssetID>, DBError> {
+    let mut expired = vec![];
+    for asset in AssetState::find_expired(client).await? {
+        let id = InstructionID::new(node_id).map_err(|err| DBError::bad_query(&err.to_string()))?;
+        let instruction = NewInstruction {
+            id,
+            initiating_node_id: node_id,
+            contract_name: EXPIRE_ASSET_CONTRACT.into(),
+            params: json!({ "expiry_date": asset.expiry_date }),
+            ..NewInstruction::default()
+        };
+        retire_asset(&asset, instruction, client).await?;
+        expired.push(asset.asset_id);
+    }
+    Ok(expired)
+}
+
+/// Number of records removed by [wipe_assets]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WipeStats {
+    pub assets: usize,
+    pub tokens: u64,
+    pub instructions: u64,
+    pub proposals: u64,
+}
+
+async fn execute_for_asset(query: &str, asset_id: &AssetID, client: &Client) -> Result<u64, DBError> {
+    let stmt = client.prepare_typed(query, &[Type::BPCHAR]).await?;
+    Ok(client.execute(&stmt, &[asset_id]).await?)
+}
+
+/// Deletes assets along with their tokens, state, instructions, views and proposals within single transaction,
+/// dependent records go first. Temporary wallets of wiped instructions and subinstructions of other assets
+/// are detached rather than deleted. Assets missing on the node are skipped.
+pub async fn wipe_assets(asset_ids: &[AssetID], client: &Client) -> Result<WipeStats, DBError> {
+    const TOKENS: &'static str =
+        "SELECT t.token_id FROM tokens t JOIN asset_states ast ON ast.id = t.asset_state_id WHERE ast.asset_id = $1";
+    const INSTRUCTIONS: &'static str = "SELECT id FROM instructions WHERE asset_id = $1";
+    const PROPOSALS: &'static str = "SELECT id FROM proposals WHERE asset_id = $1";
+    in_transaction(client, async {
+        let mut stats = WipeStats::default();
+        for asset_id in asset_ids {
+            let asset = match AssetState::find_by_asset_id(asset_id, client).await? {
+                Some(asset) => asset,
+                None => continue,
+            };
+            let detach = [
+                format!("UPDATE wallet SET instruction_id = NULL WHERE instruction_id IN ({})", INSTRUCTIONS),
+                format!(
+                    "UPDATE instructions SET parent_id = NULL WHERE asset_id <> $1 AND parent_id IN ({})",
+                    INSTRUCTIONS
+                ),
+            ];
+            let state = [
+                "DELETE FROM fee_ledger WHERE asset_id = $1".to_string(),
+                format!("DELETE FROM token_current_states WHERE token_id IN ({})", TOKENS),
+                "DELETE FROM asset_current_states WHERE asset_id = $1".into(),
+                format!("DELETE FROM token_state_append_only WHERE token_id IN ({})", TOKENS),
+                "DELETE FROM asset_state_append_only WHERE asset_id = $1".into(),
+                format!(
+                    "DELETE FROM state_snapshots WHERE entity_id = $1::TEXT
+                        OR entity_id IN (SELECT token_id::TEXT FROM ({}) t)",
+                    TOKENS
+                ),
+                "DELETE FROM views WHERE asset_id = $1".into(),
+            ];
+            for query in detach.iter().chain(state.iter()) {
+                execute_for_asset(query, asset_id, client).await?;
+            }
+            stats.instructions +=
+                execute_for_asset("DELETE FROM instructions WHERE asset_id = $1", asset_id, client).await?;
+            for query in &[
+                format!("DELETE FROM aggregate_signature_messages WHERE proposal_id IN ({})", PROPOSALS),
+                format!("DELETE FROM signed_proposals WHERE proposal_id IN ({})", PROPOSALS),
+            ] {
+                execute_for_asset(query, asset_id, client).await?;
+            }
+            stats.proposals += execute_for_asset("DELETE FROM proposals WHERE asset_id = $1", asset_id, client).await?;
+            stats.tokens += execute_for_asset(
+                "DELETE FROM tokens WHERE asset_state_id IN (SELECT id FROM asset_states WHERE asset_id = $1)",
+                asset_id,
+                client,
+            )
+            .await?;
+            for query in &[
+                "DELETE FROM contract_permissions WHERE asset_id = $1",
+                "DELETE FROM webhooks WHERE asset_id = $1",
+                "DELETE FROM committee_members WHERE asset_id = $1",
+                "DELETE FROM asset_states WHERE asset_id = $1",
+            ] {
+                execute_for_asset(query, asset_id, client).await?;
+            }
+            const DIGITAL_ASSET: &'static str = "DELETE FROM digital_assets da WHERE id = $1
+                AND NOT EXISTS (SELECT 1 FROM asset_states WHERE digital_asset_id = da.id)";
+            let stmt = client.prepare(DIGITAL_ASSET).await?;
+            client.execute(&stmt, &[&asset.digital_asset_id]).await?;
+            stats.assets += 1;
+        }
+        Ok(stats)
+    })
+    .await
+}
+
+/// Periodically retires expired assets
+pub struct AssetExpiryJanitor {
+    node_id: NodeID,
+    pool: Arc<Pool>,
+}
+
+impl AssetExpiryJanitor {
+    pub fn new(node_id: NodeID, pool: Arc<Pool>) -> Self {
+        Self { node_id, pool }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let node_id = self.node_id;
+        let pool = self.pool.clone();
+        ctx.spawn(
+            async move {
+                let res = match pool.get().await {
+                    Ok(client) => expire_assets(node_id, &client).await,
+                    Err(err) => Err(err.into()),
+                };
+                match res {
+                    Ok(expired) if expired.is_empty() => {},
+                    Ok(expired) => log::info!(target: LOG_TARGET, "Expired assets: {:?}", expired),
+                    Err(err) => log::error!(target: LOG_TARGET, "Failed to expire assets: {}", err),
+                }
+            }
+            .into_actor(self),
+        );
+    }
+}
+
+impl Actor for AssetExpiryJanitor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(EXPIRY_CHECK_PERIOD, Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{NewTokenStateAppendOnly, Token, TokenStatus},
+        test::utils::{
+            builders::{
+                consensus::{InstructionBuilder, ProposalBuilder, ViewBuilder},
+                AssetStateBuilder,
+                TokenBuilder,
+            },
+            test_db_client,
+            Test,
+        },
+        types::TokenID,
+    };
+    use chrono::Duration as ChronoDuration;
+
+    const PUBKEY: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
+
+    #[actix_rt::test]
+    async fn create_and_retire() {
+        let (client, _lock) = test_db_client().await;
+        let params = NewAsset {
+            asset_id: Test::<AssetID>::new(),
+            name: "Asset".into(),
+            description: "".into(),
+            fqdn: None,
+            limit_per_wallet: None,
+            max_supply: None,
+            allow_transfers: true,
+            authorized_signers: vec![],
+            expiry_date: None,
+            initial_data_json: Value::Null,
+            committee_mode: CommitteeMode::default(),
+        };
+        let node_id = Some(NodeID::stub());
+        let asset = create_asset(params.clone(), PUBKEY.into(), node_id, &[], &client)
+            .await
+            .unwrap();
+        assert_eq!(asset.asset_issuer_pub_key, PUBKEY);
+        assert_eq!(asset.status, AssetStatus::Active);
+        assert_eq!(asset.initial_data_json, json!({}));
+        let committee = CommitteeMember::find_by_asset_id(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(committee.len(), 1);
+        assert_eq!(committee[0].node_id, NodeID::stub().to_string());
+        // asset ID is unique
+        assert!(create_asset(params, PUBKEY.into(), node_id, &[], &client).await.is_err());
+        let raid_status = verify_raid(&asset, &RaidConfig::default(), &client).await.unwrap();
+        assert_eq!(raid_status, RaidStatus::Unverified);
+
+        let instruction = instruction_params();
+        let retired = retire_asset(&asset, instruction.clone(), &client).await.unwrap();
+        assert_eq!(retired.status, AssetStatus::Retired);
+        let instruction = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!(instruction.status, InstructionStatus::Commit);
+        assert!(retire_asset(&retired, instruction_params(), &client).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn expire() {
+        let (client, _lock) = test_db_client().await;
+        let expired = AssetStateBuilder {
+            expiry_date: Some(Utc::now() - ChronoDuration::minutes(1)),
+            ..AssetStateBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let active = AssetStateBuilder {
+            expiry_date: Some(Utc::now() + ChronoDuration::days(1)),
+            ..AssetStateBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let ids = expire_assets(NodeID::stub(), &client).await.unwrap();
+        assert_eq!(ids, vec![expired.asset_id.clone()]);
+        let expired = AssetState::load(expired.id, &client).await.unwrap();
+        assert_eq!(expired.status, AssetStatus::Retired);
+        let active = AssetState::load(active.id, &client).await.unwrap();
+        assert_eq!(active.status, AssetStatus::Active);
+        assert!(expire_assets(NodeID::stub(), &client).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn handover() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let members = vec![CommitteeCandidate {
+            node_id: "new-node".into(),
+            collateral: 5,
+        }];
+        assert!(handover_asset(&asset, vec![], instruction_params(), &client).await.is_err());
+
+        let instruction = handover_asset(&asset, members.clone(), instruction_params(), &client)
+            .await
+            .unwrap();
+        assert_eq!(instruction.status, InstructionStatus::Pending);
+        assert_eq!(instruction.contract_name, HANDOVER_CONTRACT);
+        let frozen = AssetState::load(asset.id, &client).await.unwrap();
+        assert_eq!(frozen.status, AssetStatus::Frozen);
+        assert!(handover_asset(&frozen, members.clone(), instruction_params(), &client)
+            .await
+            .is_err());
+
+        let state = instruction.execute(&client).await.unwrap();
+        let handover = state.committee_handover.unwrap();
+        assert_eq!(handover.members, members);
+        assert_eq!(handover.checkpoint_hash, frozen.checkpoint_hash(&client).await.unwrap());
+        assert_eq!(state.asset_state[0].status, AssetStatus::Active);
+    }
+
+    #[actix_rt::test]
+    async fn metadata() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder {
+            initial_data_json: json!({ "value": 1 }),
+            ..AssetStateBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        assert_eq!(asset.metadata(), AssetMetadata {
+            description: Some(asset.description.clone()),
+            uris: vec![],
+        });
+        let invalid = AssetMetadata {
+            uris: vec!["".into()],
+            ..AssetMetadata::default()
+        };
+        assert!(update_metadata(&asset, invalid, instruction_params(), &client).await.is_err());
+
+        let metadata = AssetMetadata {
+            description: Some("Updated".into()),
+            uris: vec!["https://example.com/asset.json".into()],
+        };
+        let instruction = update_metadata(&asset, metadata.clone(), instruction_params(), &client)
+            .await
+            .unwrap();
+        assert_eq!(instruction.status, InstructionStatus::Pending);
+        assert_eq!(instruction.contract_name, UPDATE_METADATA_CONTRACT);
+        // metadata is updated once instruction is committed
+        let state = instruction.execute(&client).await.unwrap();
+        assert_eq!(AssetState::load(asset.id, &client).await.unwrap().metadata(), asset.metadata());
+        for asset_state in state.asset_state.iter() {
+            AssetState::store_append_only_state(asset_state, &client).await.unwrap();
+        }
+        let updated = AssetState::load(asset.id, &client).await.unwrap();
+        assert_eq!(updated.metadata(), metadata);
+        assert_eq!(updated.additional_data_json["value"], 1);
+
+        // unset fields are kept
+        let description = AssetMetadata {
+            description: Some("Described".into()),
+            ..AssetMetadata::default()
+        };
+        let instruction = update_metadata(&updated, description, instruction_params(), &client)
+            .await
+            .unwrap();
+        let state = instruction.execute(&client).await.unwrap();
+        for asset_state in state.asset_state.iter() {
+            AssetState::store_append_only_state(asset_state, &client).await.unwrap();
+        }
+        let updated = AssetState::load(asset.id, &client).await.unwrap();
+        assert_eq!(updated.metadata().description, Some("Described".into()));
+        assert_eq!(updated.metadata().uris, metadata.uris);
+
+        let versions = AssetState::versions(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].superseded_by, Some(versions[1].id));
+        assert_eq!(versions[1].superseded_by, None);
+        assert_eq!(versions[1].instruction_id, instruction.id);
+    }
+
+    #[actix_rt::test]
+    async fn wipe() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let other = AssetStateBuilder::default().build(&client).await.unwrap();
+        let token = TokenBuilder {
+            asset_state_id: Some(asset.id),
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        Token::store_append_only_state(
+            &NewTokenStateAppendOnly {
+                token_id: token.token_id.clone(),
+                instruction_id: instruction.id,
+                status: TokenStatus::Active,
+                state_data_json: json!({ "value": 1 }),
+                expected_version: None,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let subinstruction = InstructionBuilder {
+            asset_id: Some(other.asset_id.clone()),
+            parent_id: Some(instruction.id),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        ProposalBuilder {
+            new_view: Some(
+                ViewBuilder {
+                    asset_id: Some(asset.asset_id.clone()),
+                    ..ViewBuilder::default()
+                }
+                .prepare(&client)
+                .await
+                .unwrap(),
+            ),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let stats = wipe_assets(&[asset.asset_id.clone(), Test::<AssetID>::new()], &client)
+            .await
+            .unwrap();
+        assert_eq!(stats, WipeStats {
+            assets: 1,
+            tokens: 1,
+            instructions: 1,
+            proposals: 1,
+        });
+        assert!(AssetState::find_by_asset_id(&asset.asset_id, &client)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(Token::find_by_token_id(&token.token_id, &client).await.unwrap().is_none());
+        assert!(Instruction::find(instruction.id, &client).await.unwrap().is_none());
+        assert!(AssetState::find_by_asset_id(&other.asset_id, &client)
+            .await
+            .unwrap()
+            .is_some());
+        let subinstruction = Instruction::load(subinstruction.id, &client).await.unwrap();
+        assert_eq!(subinstruction.parent_id, None);
+    }
+
+    fn instruction_params() -> NewInstruction {
+        NewInstruction {
+            id: InstructionID::new(NodeID::stub()).unwrap(),
+            contract_name: RETIRE_ASSET_CONTRACT.into(),
+            ..NewInstruction::default()
+        }
+    }
+}
@@ -1,13 +1,144 @@
 use super::utils::errors::DBError;
 use crate::{config::NodeConfig, db::utils::db::db_client_raw};
+use include_dir::{include_dir, Dir};
+use std::path::Path;
 
 mod embedded {
     use refinery::embed_migrations;
     embed_migrations!();
 }
 
+/// Same migrations refinery runner is embedding, kept for status and dry-run reports
+static MIGRATIONS_DIR: Dir = include_dir!("migrations");
+
+/// Bookkeeping table maintained by refinery runner, one row per applied migration
+pub const HISTORY_TABLE: &'static str = "refinery_schema_history";
+
+/// Migration shipped with the node binary
+#[derive(Clone, Debug, PartialEq)]
+pub struct Migration {
+    pub version: i32,
+    pub name: String,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    /// Parses `V{version}__{name}.sql` migration file
+    fn from_file(path: &Path, sql: &'static str) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        if !stem.starts_with('V') {
+            return None;
+        }
+        let mut parts = stem[1..].splitn(2, "__");
+        let version = parts.next()?.parse().ok()?;
+        let name = parts.next()?.to_string();
+        Some(Self { version, name, sql })
+    }
+}
+
+/// Migration recorded in [HISTORY_TABLE]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppliedMigration {
+    pub version: i32,
+    pub name: String,
+    pub applied_on: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrationsStatus {
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<Migration>,
+}
+
 pub async fn migrate(node_config: NodeConfig) -> Result<(), DBError> {
     let mut conn = db_client_raw(&node_config).await?;
     embedded::migrations::runner().run_async(&mut conn).await?;
     Ok(())
 }
+
+/// Embedded migrations ordered by version
+pub fn embedded_migrations() -> Vec<Migration> {
+    let mut migrations: Vec<_> = MIGRATIONS_DIR
+        .files()
+        .iter()
+        .filter_map(|file| Migration::from_file(file.path(), file.contents_utf8()?))
+        .collect();
+    migrations.sort_by_key(|migration| migration.version);
+    migrations
+}
+
+/// Lists applied migrations and embedded migrations pending to be applied
+pub async fn status(node_config: &NodeConfig) -> Result<MigrationsStatus, DBError> {
+    let client = db_client_raw(node_config).await?;
+    status_with_client(&client).await
+}
+
+/// Migrations which `tvnc migrate` would apply, without applying them
+pub async fn dry_run(node_config: &NodeConfig) -> Result<Vec<Migration>, DBError> {
+    Ok(status(node_config).await?.pending)
+}
+
+pub(crate) async fn status_with_client(client: &tokio_postgres::Client) -> Result<MigrationsStatus, DBError> {
+    const QUERY_TABLE: &'static str = "SELECT to_regclass($1::TEXT) IS NOT NULL";
+    const QUERY: &'static str = "SELECT version, name, applied_on FROM refinery_schema_history ORDER BY version";
+
+    let table_exists: bool = client.query_one(QUERY_TABLE, &[&HISTORY_TABLE]).await?.get(0);
+    let applied: Vec<AppliedMigration> = if table_exists {
+        let stmt = client.prepare(QUERY).await?;
+        client
+            .query(&stmt, &[])
+            .await?
+            .into_iter()
+            .map(|row| AppliedMigration {
+                version: row.get(0),
+                name: row.get(1),
+                applied_on: row.get(2),
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    let pending = embedded_migrations()
+        .into_iter()
+        .filter(|migration| !applied.iter().any(|a| a.version == migration.version))
+        .collect();
+    Ok(MigrationsStatus { applied, pending })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{build_test_config, test_db_client};
+
+    #[test]
+    fn embedded() {
+        let migrations = embedded_migrations();
+        assert!(migrations.len() > 0);
+        assert!(migrations.windows(2).all(|pair| pair[0].version < pair[1].version));
+        let contract_permissions = migrations
+            .iter()
+            .find(|migration| migration.version == 1590700000)
+            .unwrap();
+        assert_eq!(contract_permissions.name, "create_contract_permissions");
+        assert!(contract_permissions.sql.contains("contract_permissions"));
+    }
+
+    #[test]
+    fn from_file() {
+        let migration = Migration::from_file(Path::new("migrations/V12__add_table.sql"), "SELECT 1").unwrap();
+        assert_eq!(migration.version, 12);
+        assert_eq!(migration.name, "add_table");
+        assert!(Migration::from_file(Path::new("migrations/README.md"), "").is_none());
+        assert!(Migration::from_file(Path::new("migrations/Vx__broken.sql"), "").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn status_of_migrated_db() {
+        let (_client, _lock) = test_db_client().await;
+        let config = build_test_config().unwrap();
+        let status = status(&config).await.unwrap();
+        assert!(status.pending.is_empty());
+        assert_eq!(status.applied.len(), embedded_migrations().len());
+        assert!(dry_run(&config).await.unwrap().is_empty());
+    }
+}
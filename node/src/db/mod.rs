@@ -1,3 +1,7 @@
+pub mod lifecycle;
 pub mod migrations;
 pub mod models;
+pub mod notify;
+pub mod retention;
+pub mod store;
 pub mod utils;
@@ -1,5 +1,5 @@
 use super::AccessResource;
-use crate::db::utils::errors::DBError;
+use crate::{db::utils::errors::DBError, types::AssetID};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 use std::fmt::{Display, Formatter};
@@ -7,6 +7,9 @@ use tari_wallet::util::emoji::EmojiId;
 use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
 use tokio_postgres::{types::Type, Client};
 
+/// Scope granting access to all templates and contracts
+pub const ACCESS_SCOPE_ALL: &'static str = "*";
+
 /// Access records for tari validation node
 #[derive(Debug, Clone, Serialize, PostgresMapper)]
 #[pg_mapper(table = "access")]
@@ -15,9 +18,16 @@ pub struct Access {
     pub pub_key: String,
     pub resource: AccessResource,
     pub resource_key: Option<String>,
+    pub scopes: Vec<String>,
+    /// Assets the access is restricted to, any asset if empty
+    pub assets: Vec<String>,
     pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Access stops authorizing requests after this time, never expires if None
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Access key is neutralized, e.g. after a leak, see [Access::revoke_key]
+    pub revoked_at: Option<DateTime<Utc>>,
 }
 impl Display for Access {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -26,7 +36,18 @@ impl Display for Access {
             write!(f, " {}", emoji)?;
         }
         let key = self.resource_key.clone().unwrap_or("".into());
-        write!(f, "\n -> {} {}\n", self.resource, key)
+        write!(f, "\n -> {} {} [{}]", self.resource, key, self.scopes.join(", "))?;
+        if !self.assets.is_empty() {
+            write!(f, "\n    assets: {}", self.assets.join(", "))?;
+        }
+        write!(f, "\n    id: {}", self.id)?;
+        if let Some(revoked_at) = self.revoked_at {
+            write!(f, ", revoked at {}", revoked_at)?;
+        } else if let Some(expires_at) = self.expires_at {
+            let status = if self.is_expired() { "expired" } else { "expires" };
+            write!(f, ", {} at {}", status, expires_at)?;
+        }
+        write!(f, "\n")
     }
 }
 
@@ -36,6 +57,11 @@ pub struct NewAccess {
     pub pub_key: String,
     pub resource: AccessResource,
     pub resource_key: Option<String>,
+    /// Defaults to [ACCESS_SCOPE_ALL] when empty
+    pub scopes: Vec<String>,
+    /// Any asset when empty
+    pub assets: Vec<AssetID>,
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl Default for AccessResource {
@@ -44,12 +70,15 @@ impl Default for AccessResource {
     }
 }
 
-/// Query paramteres for searching access records
+/// Query paramteres for searching access records, only active records are returned by default
 #[derive(Default, Clone, Debug)]
 pub struct SelectAccess {
     pub id: Option<uuid::Uuid>,
     pub pub_key: Option<String>,
+    /// Include deleted records, implies `include_inactive`
     pub include_deleted: Option<bool>,
+    /// Include expired and revoked records
+    pub include_inactive: Option<bool>,
     pub resource: AccessResource,
     pub resource_key: Option<String>,
 }
@@ -60,8 +89,39 @@ impl Access {
         Ok(EmojiId::from_hex(&self.pub_key)?)
     }
 
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.map(|expires_at| expires_at <= Utc::now()).unwrap_or(false)
+    }
+
+    /// Access is authorizing requests: not deleted, revoked or expired
+    pub fn is_active(&self) -> bool {
+        self.deleted_at.is_none() && self.revoked_at.is_none() && !self.is_expired()
+    }
+
+    /// Check scopes allow calling contract of the template.
+    /// Scope is either `*`, `<template_id>` or `<template_id>/<contract>`
+    pub fn allows(&self, template_id: &str, contract: &str) -> bool {
+        self.scopes.iter().any(|scope| {
+            let mut parts = scope.splitn(2, '/');
+            match (parts.next(), parts.next()) {
+                (Some(ACCESS_SCOPE_ALL), None) => true,
+                (Some(tpl), None) => tpl == template_id,
+                (Some(tpl), Some(name)) => tpl == template_id && name == contract,
+                _ => false,
+            }
+        })
+    }
+
+    /// Check access is not restricted to other assets
+    pub fn allows_asset(&self, asset_id: &AssetID) -> bool {
+        self.assets.is_empty() || self.assets.iter().any(|asset| asset == &asset_id.to_string())
+    }
+
     /// Add access record
-    pub async fn grant(params: NewAccess, client: &Client) -> Result<u64, DBError> {
+    pub async fn grant(mut params: NewAccess, client: &Client) -> Result<u64, DBError> {
+        if params.scopes.is_empty() {
+            params.scopes.push(ACCESS_SCOPE_ALL.into());
+        }
         let select_existing_user = SelectAccess {
             pub_key: Some(params.pub_key.clone()),
             include_deleted: Some(true),
@@ -69,27 +129,44 @@ impl Access {
             resource_key: params.resource_key.clone(),
             ..SelectAccess::default()
         };
+        let assets: Vec<String> = params.assets.iter().map(|asset_id| asset_id.to_string()).collect();
         let user_exists = Access::select(select_existing_user.clone(), client).await?;
         if user_exists.len() == 1 {
             // Reinstate the user
-            Ok(Access::reinstate(select_existing_user, client).await?)
+            Ok(Access::reinstate(select_existing_user, &params.scopes, &assets, params.expires_at, client).await?)
         } else {
-            const QUERY: &'static str = "INSERT INTO access (pub_key, resource, resource_key) VALUES ($1, $2, $3)";
+            const QUERY: &'static str = "INSERT INTO access (pub_key, resource, resource_key, scopes, assets, \
+                                         expires_at) VALUES ($1, $2, $3, $4, $5, $6)";
             let stmt = client.prepare(QUERY).await?;
             Ok(client
-                .execute(&stmt, &[&params.pub_key, &params.resource, &params.resource_key])
+                .execute(&stmt, &[
+                    &params.pub_key,
+                    &params.resource,
+                    &params.resource_key,
+                    &params.scopes,
+                    &assets,
+                    &params.expires_at,
+                ])
                 .await?)
         }
     }
 
-    /// Search active access records by [`SelectAccess`]
+    /// Search access records by [`SelectAccess`]
     pub async fn select(params: SelectAccess, client: &Client) -> Result<Vec<Access>, DBError> {
         const QUERY: &'static str = "SELECT * FROM access WHERE ($1 IS NULL OR id = $1) AND ($2 IS NULL OR pub_key = \
                                      $2) AND ($3 = true OR deleted_at IS NULL) AND ($4 IS NULL OR resource = $4) AND \
-                                     ($5 IS NULL OR resource_key = $5)";
+                                     ($5 IS NULL OR resource_key = $5) AND ($3 = true OR $6 = true OR (revoked_at IS \
+                                     NULL AND (expires_at IS NULL OR expires_at > NOW())))";
 
         let stmt = client
-            .prepare_typed(QUERY, &[Type::UUID, Type::TEXT, Type::BOOL, Type::TEXT, Type::TEXT])
+            .prepare_typed(QUERY, &[
+                Type::UUID,
+                Type::TEXT,
+                Type::BOOL,
+                Type::TEXT,
+                Type::TEXT,
+                Type::BOOL,
+            ])
             .await?;
         Ok(client
             .query(&stmt, &[
@@ -98,6 +175,7 @@ impl Access {
                 &params.include_deleted,
                 &params.resource,
                 &params.resource_key,
+                &params.include_inactive,
             ])
             .await?
             .into_iter()
@@ -126,16 +204,41 @@ impl Access {
             .await?)
     }
 
-    /// Re-instate access record
-    async fn reinstate(params: SelectAccess, client: &Client) -> Result<u64, DBError> {
-        const QUERY: &'static str = "UPDATE access SET deleted_at = NULL, updated_at = NOW() WHERE ($1 IS NULL OR id \
-                                     = $1) AND (($2 IS NULL OR pub_key = $2) AND (resource = $3) AND (($4 IS NULL AND \
-                                     resource_key IS NULL) OR resource_key = $4))";
+    /// Revoke access key by record id, unlike [Access::revoke] requests with revoked key are rejected
+    /// with a distinct error. Key is reinstated only by granting access again
+    pub async fn revoke_key(id: uuid::Uuid, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str =
+            "UPDATE access SET revoked_at = NOW(), updated_at = NOW() WHERE id = $1 AND revoked_at IS NULL";
+        let stmt = client.prepare_typed(QUERY, &[Type::UUID]).await?;
+        Ok(client.execute(&stmt, &[&id]).await?)
+    }
+
+    /// Re-instate access record, replacing its scopes, assets and expiration
+    async fn reinstate(
+        params: SelectAccess,
+        scopes: &Vec<String>,
+        assets: &Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+        client: &Client,
+    ) -> Result<u64, DBError>
+    {
+        const QUERY: &'static str = "UPDATE access SET deleted_at = NULL, revoked_at = NULL, scopes = $5, expires_at \
+                                     = $6, assets = $7, updated_at = NOW() WHERE ($1 IS NULL OR id = $1) AND (($2 IS \
+                                     NULL OR pub_key = $2) AND (resource = $3) AND (($4 IS NULL AND resource_key IS \
+                                     NULL) OR resource_key = $4))";
         if params.id.is_none() && params.pub_key.is_none() {
             return Err(DBError::bad_query("Re-instate access query requires id or pub_key"));
         }
         let stmt = client
-            .prepare_typed(QUERY, &[Type::UUID, Type::TEXT, Type::TEXT, Type::TEXT])
+            .prepare_typed(QUERY, &[
+                Type::UUID,
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT_ARRAY,
+                Type::TIMESTAMPTZ,
+                Type::TEXT_ARRAY,
+            ])
             .await?;
         Ok(client
             .execute(&stmt, &[
@@ -143,6 +246,9 @@ impl Access {
                 &params.pub_key,
                 &params.resource,
                 &params.resource_key,
+                scopes,
+                &expires_at,
+                assets,
             ])
             .await?)
     }
@@ -150,9 +256,12 @@ impl Access {
 
 #[cfg(test)]
 mod test {
-    use super::{Access, AccessResource, NewAccess, SelectAccess};
-    use crate::test::utils::{load_env, test_db_client};
-    use chrono::Utc;
+    use super::{Access, AccessResource, NewAccess, SelectAccess, ACCESS_SCOPE_ALL};
+    use crate::{
+        test::utils::{load_env, test_db_client},
+        types::{AssetID, RaidID},
+    };
+    use chrono::{Duration, Utc};
 
     const PUBKEY: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
     const EMOJI: &'static str = "🍉🐭👄🍎🙃🐇💻🙄🆘🐫🍫👕🎌👔👽🍫🤝🍷👤💫🐫🌈😍⛺🤑🛸🎤🎾🤴👖🧦😛📡";
@@ -164,13 +273,69 @@ mod test {
             pub_key: PUBKEY.to_owned(),
             resource: AccessResource::Api,
             resource_key: None,
+            scopes: vec![ACCESS_SCOPE_ALL.into()],
+            assets: vec![],
             deleted_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
         };
         assert_eq!(access.emoji_id().unwrap().to_string(), EMOJI.to_owned());
     }
 
+    #[test]
+    fn allows() {
+        let mut access = Access {
+            id: uuid::Uuid::nil(),
+            pub_key: PUBKEY.to_owned(),
+            resource: AccessResource::Api,
+            resource_key: None,
+            scopes: vec![ACCESS_SCOPE_ALL.into()],
+            assets: vec![],
+            deleted_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        };
+        assert!(access.allows("1", "issue_tokens"));
+
+        access.scopes = vec!["1".into(), "2/sell_token".into()];
+        assert!(access.allows("1", "issue_tokens"));
+        assert!(access.allows("2", "sell_token"));
+        assert!(!access.allows("2", "issue_tokens"));
+        assert!(!access.allows("3", "sell_token"));
+
+        access.scopes = vec![];
+        assert!(!access.allows("1", "issue_tokens"));
+    }
+
+    #[test]
+    fn allows_asset() {
+        let asset_id = AssetID::new(1.into(), 0, RaidID::default(), format!("{:032X}", 1));
+        let other = AssetID::new(1.into(), 0, RaidID::default(), format!("{:032X}", 2));
+        let mut access = Access {
+            id: uuid::Uuid::nil(),
+            pub_key: PUBKEY.to_owned(),
+            resource: AccessResource::Api,
+            resource_key: None,
+            scopes: vec![ACCESS_SCOPE_ALL.into()],
+            assets: vec![],
+            deleted_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        };
+        assert!(access.allows_asset(&asset_id));
+        assert!(access.allows_asset(&other));
+
+        access.assets = vec![asset_id.to_string()];
+        assert!(access.allows_asset(&asset_id));
+        assert!(!access.allows_asset(&other));
+    }
+
     #[actix_rt::test]
     async fn crud_api() -> anyhow::Result<()> {
         let (client, _lock) = test_db_client().await;
@@ -189,6 +354,7 @@ mod test {
         let access = Access::select(query_exclude_deleted.clone(), &client).await?;
         assert_eq!(access.len(), 1);
         assert_eq!(access[0].pub_key, PUBKEY.to_owned());
+        assert_eq!(access[0].scopes, vec![ACCESS_SCOPE_ALL.to_string()]);
 
         let deleted = Access::revoke(query_exclude_deleted.clone(), &client).await?;
         assert_eq!(deleted, 1);
@@ -204,11 +370,22 @@ mod test {
         let access = Access::select(query_include_deleted.clone(), &client).await?;
         assert_eq!(access.len(), 1);
 
-        let reinstated = Access::grant(new_access_params, &client).await?;
+        let asset_id = AssetID::new(1.into(), 0, RaidID::default(), format!("{:032X}", 1));
+        let reinstated = Access::grant(
+            NewAccess {
+                scopes: vec!["1/issue_tokens".into()],
+                assets: vec![asset_id.clone()],
+                ..new_access_params
+            },
+            &client,
+        )
+        .await?;
         assert_eq!(reinstated, 1);
 
         let access = Access::select(query_exclude_deleted, &client).await?;
         assert_eq!(access.len(), 1);
+        assert_eq!(access[0].scopes, vec!["1/issue_tokens".to_string()]);
+        assert_eq!(access[0].assets, vec![asset_id.to_string()]);
         Ok(())
     }
 
@@ -267,4 +444,46 @@ mod test {
         let res = Access::revoke(SelectAccess::default(), &client).await;
         assert!(res.is_err());
     }
+
+    #[actix_rt::test]
+    async fn expiration_and_revocation() -> anyhow::Result<()> {
+        let (client, _lock) = test_db_client().await;
+        let params = NewAccess {
+            pub_key: PUBKEY.to_owned(),
+            expires_at: Some(Utc::now() - Duration::minutes(1)),
+            ..NewAccess::default()
+        };
+        Access::grant(params.clone(), &client).await?;
+        let active = SelectAccess {
+            pub_key: Some(PUBKEY.to_owned()),
+            ..SelectAccess::default()
+        };
+        let all = SelectAccess {
+            include_inactive: Some(true),
+            ..active.clone()
+        };
+        assert!(Access::select(active.clone(), &client).await?.is_empty());
+        let access = Access::select(all.clone(), &client).await?.pop().unwrap();
+        assert!(access.is_expired());
+        assert!(!access.is_active());
+
+        Access::grant(
+            NewAccess {
+                expires_at: Some(Utc::now() + Duration::minutes(1)),
+                ..params
+            },
+            &client,
+        )
+        .await?;
+        let access = Access::select(active.clone(), &client).await?.pop().unwrap();
+        assert!(access.is_active());
+
+        assert_eq!(Access::revoke_key(access.id, &client).await?, 1);
+        assert_eq!(Access::revoke_key(access.id, &client).await?, 0);
+        assert!(Access::select(active, &client).await?.is_empty());
+        let access = Access::select(all, &client).await?.pop().unwrap();
+        assert!(access.revoked_at.is_some());
+        assert!(!access.is_active());
+        Ok(())
+    }
 }
@@ -14,14 +14,19 @@ use tokio_postgres::{
     Client,
 };
 
+/// Field of asset state data keeping [AssetMetadata]
+pub const METADATA_FIELD: &'static str = "metadata";
+
 #[derive(Serialize, PostgresMapper, PartialEq, Debug, Clone)]
-#[pg_mapper(table = "asset_states_view")]
+#[pg_mapper(table = "asset_states_current_view")]
 pub struct AssetState {
     pub id: uuid::Uuid,
     pub name: String,
     pub description: String,
     pub status: AssetStatus,
     pub limit_per_wallet: Option<u32>,
+    /// Upper bound of tokens issued for the asset
+    pub max_supply: Option<u32>,
     pub allow_transfers: bool,
     pub asset_issuer_pub_key: String,
     pub authorized_signers: Vec<String>,
@@ -38,12 +43,35 @@ pub struct AssetState {
     pub additional_data_json: Value,
 }
 
+/// State of the asset appended by an instruction, states form versions of the asset
+/// linked by `superseded_by` to the state which replaced them
+#[derive(Serialize, PostgresMapper, PartialEq, Debug, Clone)]
+#[pg_mapper(table = "asset_state_append_only")]
+pub struct AssetStateVersion {
+    pub id: uuid::Uuid,
+    pub asset_id: AssetID,
+    pub instruction_id: InstructionID,
+    pub status: AssetStatus,
+    pub state_data_json: Value,
+    pub superseded_by: Option<uuid::Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Mutable metadata of the asset kept in `metadata` field of asset state data, see [AssetState::metadata]
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
+pub struct AssetMetadata {
+    pub description: Option<String>,
+    #[serde(default)]
+    pub uris: Vec<String>,
+}
+
 /// Query paramteres for adding new asset record
 #[derive(Default, Clone, Debug)]
 pub struct NewAssetState {
     pub name: String,
     pub description: String,
     pub limit_per_wallet: Option<u32>,
+    pub max_supply: Option<u32>,
     pub allow_transfers: bool,
     pub asset_issuer_pub_key: String,
     pub authorized_signers: Vec<String>,
@@ -80,23 +108,40 @@ impl NewAssetState {
 }
 
 impl AssetState {
-    /// Releases lock on asset state
-    pub async fn acquire_lock(&mut self, lock_period: u64, client: &Client) -> Result<(), DBError> {
-        let block_until = Utc::now() + Duration::seconds(lock_period as i64);
+    /// Acquires lock on asset state, returns false if asset is already locked by another worker
+    pub async fn acquire_lock(&mut self, lock_period: u64, client: &Client) -> Result<bool, DBError> {
+        self.acquire_owned_lock(lock_period, None, client).await
+    }
 
-        const QUERY: &'static str =
-            "UPDATE asset_states SET blocked_until = $2, updated_at = now() WHERE id = $1 AND blocked_until <= now()";
-        let stmt = client.prepare(QUERY).await?;
-        client.execute(&stmt, &[&self.id, &block_until]).await?;
+    /// Acquires lock on behalf of `owner`, owner has to heartbeat the lock via [AssetState::heartbeat_locks]
+    /// or the lock is reclaimed by [AssetState::reclaim_stale_locks] before `lock_period` ends
+    pub async fn acquire_owned_lock(
+        &mut self,
+        lock_period: u64,
+        owner: Option<&str>,
+        client: &Client,
+    ) -> Result<bool, DBError>
+    {
+        let block_until = Utc::now() + Duration::seconds(lock_period as i64);
 
-        Ok(())
+        const QUERY: &'static str = "UPDATE asset_states SET blocked_until = $2, locked_by = $3, lock_heartbeat_at = \
+                                     now(), updated_at = now() WHERE id = $1 AND blocked_until <= now() RETURNING \
+                                     blocked_until";
+        let stmt = client.prepare_typed(QUERY, &[Type::UUID, Type::TIMESTAMPTZ, Type::TEXT]).await?;
+        match client.query_opt(&stmt, &[&self.id, &block_until, &owner]).await? {
+            Some(row) => {
+                self.blocked_until = row.get(0);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
     }
 
     /// Releases lock on asset state
     pub async fn release_lock(&self, client: &Client) -> Result<(), DBError> {
         let block_until = Utc::now();
-        const QUERY: &'static str =
-            "UPDATE asset_states SET blocked_until = $3, updated_at = now() WHERE id = $1 AND blocked_until = $2";
+        const QUERY: &'static str = "UPDATE asset_states SET blocked_until = $3, locked_by = NULL, lock_heartbeat_at = \
+                                     NULL, updated_at = now() WHERE id = $1 AND blocked_until = $2";
         let stmt = client.prepare(QUERY).await?;
         client
             .execute(&stmt, &[&self.id, &self.blocked_until, &block_until])
@@ -105,6 +150,40 @@ impl AssetState {
         Ok(())
     }
 
+    /// Prolongs ownership of locks held by `owners`, returns number of locks heartbeated
+    pub async fn heartbeat_locks(owners: &[String], client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "UPDATE asset_states SET lock_heartbeat_at = now() WHERE locked_by = ANY($1) AND \
+                                     blocked_until > now()";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.execute(&stmt, &[&owners]).await?)
+    }
+
+    /// Releases locks whose owner did not heartbeat for `stale_after` seconds, e.g. worker crashed midway,
+    /// returns asset IDs along with the owners of reclaimed locks
+    pub async fn reclaim_stale_locks(stale_after: u64, client: &Client) -> Result<Vec<(AssetID, String)>, DBError> {
+        const QUERY: &'static str = "
+            WITH stale AS (
+                SELECT id, locked_by
+                FROM asset_states
+                WHERE locked_by IS NOT NULL
+                AND blocked_until > now()
+                AND lock_heartbeat_at < now() - make_interval(secs => $1)
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE asset_states ast
+            SET blocked_until = now(), locked_by = NULL, lock_heartbeat_at = NULL, updated_at = now()
+            FROM stale
+            WHERE ast.id = stale.id
+            RETURNING ast.asset_id, stale.locked_by";
+        let stmt = client.prepare_typed(QUERY, &[Type::FLOAT8]).await?;
+        Ok(client
+            .query(&stmt, &[&(stale_after as f64)])
+            .await?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect())
+    }
+
     /// Add asset record
     pub async fn insert(params: NewAssetState, client: &Client) -> Result<uuid::Uuid, DBError> {
         params.validate_record(client).await?;
@@ -122,8 +201,9 @@ impl AssetState {
                 initial_data_json,
                 asset_id,
                 digital_asset_id,
-                blocked_until
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING id";
+                blocked_until,
+                max_supply
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id";
         let stmt = client.prepare(QUERY).await?;
         let result = client
             .query_one(&stmt, &[
@@ -139,6 +219,7 @@ impl AssetState {
                 &params.asset_id,
                 &params.digital_asset_id,
                 &Utc::now(),
+                &params.max_supply,
             ])
             .await?;
 
@@ -147,15 +228,24 @@ impl AssetState {
 
     /// Load asset record
     pub async fn load(id: uuid::Uuid, client: &Client) -> Result<AssetState, DBError> {
-        const QUERY: &'static str = "SELECT * FROM asset_states_view WHERE id = $1";
+        const QUERY: &'static str = "SELECT * FROM asset_states_current_view WHERE id = $1";
         let stmt = client.prepare(QUERY).await?;
         let result = client.query_one(&stmt, &[&id]).await?;
         Ok(AssetState::from_row(result)?)
     }
 
+    /// Locks asset record until the end of the transaction, so concurrent checks of its token limits
+    /// followed by token inserts are serialized
+    pub async fn lock_for_update(id: uuid::Uuid, client: &Client) -> Result<(), DBError> {
+        const QUERY: &'static str = "SELECT id FROM asset_states WHERE id = $1 FOR UPDATE";
+        let stmt = client.prepare(QUERY).await?;
+        client.query_one(&stmt, &[&id]).await?;
+        Ok(())
+    }
+
     /// Find asset state record by asset id
     pub async fn find_by_asset_id(asset_id: &AssetID, client: &Client) -> Result<Option<AssetState>, DBError> {
-        const QUERY: &'static str = "SELECT * FROM asset_states_view WHERE asset_id = $1";
+        const QUERY: &'static str = "SELECT * FROM asset_states_current_view WHERE asset_id = $1";
         let stmt = client.prepare(QUERY).await?;
         let result = client.query_opt(&stmt, &[&asset_id]).await?;
         Ok(result.map(AssetState::from_row).transpose()?)
@@ -163,7 +253,7 @@ impl AssetState {
 
     /// Find asset state records by template id mask
     pub async fn find_by_template_id(template_id: &TemplateID, client: &Client) -> Result<Vec<AssetState>, DBError> {
-        const QUERY: &'static str = "SELECT * FROM asset_states_view WHERE asset_id LIKE $1";
+        const QUERY: &'static str = "SELECT * FROM asset_states_current_view WHERE asset_id LIKE $1";
         let stmt = client.prepare(QUERY).await?;
         let mut mask = template_id.to_hex();
         mask.truncate(12);
@@ -175,6 +265,18 @@ impl AssetState {
             .collect::<Result<Vec<_>, _>>()?)
     }
 
+    /// Active assets with expiry date in the past
+    pub async fn find_expired(client: &Client) -> Result<Vec<AssetState>, DBError> {
+        const QUERY: &'static str =
+            "SELECT * FROM asset_states_current_view WHERE status = 'Active' AND expiry_date <= now()";
+        let stmt = client.prepare(QUERY).await?;
+        let results = client.query(&stmt, &[]).await?;
+        Ok(results
+            .into_iter()
+            .map(AssetState::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
     // Store append only state
     pub async fn store_append_only_state(
         params: &NewAssetStateAppendOnly,
@@ -200,6 +302,51 @@ impl AssetState {
 
         Ok(result.get(0))
     }
+
+    /// Current metadata, description falls back to the one the asset was created with
+    pub fn metadata(&self) -> AssetMetadata {
+        let mut metadata: AssetMetadata = self
+            .additional_data_json
+            .get(METADATA_FIELD)
+            .and_then(|metadata| serde_json::from_value(metadata.clone()).ok())
+            .unwrap_or_default();
+        if metadata.description.is_none() {
+            metadata.description = Some(self.description.clone());
+        }
+        metadata
+    }
+
+    /// Appended states of the asset, oldest first
+    pub async fn versions(asset_id: &AssetID, client: &Client) -> Result<Vec<AssetStateVersion>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM asset_state_append_only WHERE asset_id = $1 ORDER BY created_at, id";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&asset_id])
+            .await?
+            .into_iter()
+            .map(AssetStateVersion::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// SHA256 of the current state of the asset followed by current state of its tokens ordered by token ID
+    pub async fn checkpoint_hash(&self, client: &Client) -> Result<String, DBError> {
+        const QUERY: &'static str = "
+            SELECT encode(digest(
+                COALESCE((
+                    SELECT acs.status || acs.state_data_json::TEXT
+                    FROM asset_current_states acs WHERE acs.asset_id = $1
+                ), '') ||
+                COALESCE((
+                    SELECT string_agg(tcs.token_id || tcs.status || tcs.state_data_json::TEXT, '' ORDER BY tcs.token_id)
+                    FROM token_current_states tcs
+                    JOIN tokens t ON t.token_id = tcs.token_id
+                    WHERE t.asset_state_id = $2
+                ), ''),
+                'sha256'
+            ), 'hex')";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_one(&stmt, &[&self.asset_id, &self.id]).await?.get(0))
+    }
 }
 
 impl<'a> ToSql for NewAssetStateAppendOnly {
@@ -265,6 +412,45 @@ mod test {
         assert_eq!(found_asset, Some(asset));
     }
 
+    #[actix_rt::test]
+    async fn acquire_lock_exclusive() -> anyhow::Result<()> {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await?;
+        let mut worker1 = AssetState::load(asset.id, &client).await?;
+        let mut worker2 = AssetState::load(asset.id, &client).await?;
+
+        assert!(worker1.acquire_lock(60, &client).await?);
+        assert!(!worker2.acquire_lock(60, &client).await?);
+        assert_eq!(worker1.blocked_until, AssetState::load(asset.id, &client).await?.blocked_until);
+
+        worker1.release_lock(&client).await?;
+        assert!(worker2.acquire_lock(60, &client).await?);
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn reclaim_stale_locks() -> anyhow::Result<()> {
+        let (client, _lock) = test_db_client().await;
+        let alive = AssetStateBuilder::default().build(&client).await?;
+        let stale = AssetStateBuilder::default().build(&client).await?;
+        let mut alive = AssetState::load(alive.id, &client).await?;
+        let mut stale = AssetState::load(stale.id, &client).await?;
+        assert!(alive.acquire_owned_lock(60, Some("node/1/1"), &client).await?);
+        assert!(stale.acquire_owned_lock(60, Some("node/1/2"), &client).await?);
+        client.execute("UPDATE asset_states SET lock_heartbeat_at = now() - interval '1 minute'", &[]).await?;
+
+        assert_eq!(AssetState::heartbeat_locks(&["node/1/1".to_string()], &client).await?, 1);
+        let reclaimed = AssetState::reclaim_stale_locks(30, &client).await?;
+        assert_eq!(reclaimed, vec![(stale.asset_id.clone(), "node/1/2".to_string())]);
+        assert!(AssetState::reclaim_stale_locks(30, &client).await?.is_empty());
+
+        let mut worker = AssetState::load(stale.id, &client).await?;
+        assert!(worker.acquire_lock(60, &client).await?);
+        let mut worker = AssetState::load(alive.id, &client).await?;
+        assert!(!worker.acquire_lock(60, &client).await?);
+        Ok(())
+    }
+
     #[actix_rt::test]
     async fn store_append_only_state() -> anyhow::Result<()> {
         let (client, _lock) = test_db_client().await;
@@ -0,0 +1,218 @@
+use super::{consensus::instructions::Instruction, AuditAction};
+use crate::{
+    db::utils::errors::DBError,
+    types::{AssetID, InstructionID, TemplateID},
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::types::Type;
+
+/// Mutation initiated via API: who signed and authorized the request, what was changed and when
+#[derive(Debug, Clone, Serialize, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "audit_log")]
+pub struct AuditLog {
+    pub id: uuid::Uuid,
+    pub signer_pubkey: Option<String>,
+    /// Subject of the access token, None if the route doesn't require access token or auth is disabled
+    pub access_pubkey: Option<String>,
+    pub action: AuditAction,
+    pub asset_id: Option<AssetID>,
+    pub instruction_id: Option<InstructionID>,
+    pub template_id: Option<TemplateID>,
+    pub contract_name: Option<String>,
+    /// Hex SHA256 of request params, params themselves are kept by instructions
+    pub params_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NewAuditLog {
+    pub signer_pubkey: Option<String>,
+    pub access_pubkey: Option<String>,
+    pub action: AuditAction,
+    pub asset_id: Option<AssetID>,
+    pub instruction_id: Option<InstructionID>,
+    pub template_id: Option<TemplateID>,
+    pub contract_name: Option<String>,
+    pub params: Value,
+}
+
+/// Filters of audit log export, records are ordered by creation time
+#[derive(Clone, Debug, Default)]
+pub struct SelectAuditLog {
+    /// Records created after this moment
+    pub since: Option<DateTime<Utc>>,
+    /// Records created before this moment
+    pub until: Option<DateTime<Utc>>,
+    pub asset_id: Option<AssetID>,
+    /// Records signed or authorized by the pubkey
+    pub pubkey: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl NewAuditLog {
+    /// Contract call recorded along with the instruction it has created
+    pub fn contract_call(instruction: &Instruction, access_pubkey: Option<String>) -> Self {
+        Self {
+            signer_pubkey: instruction.signer_pubkey.clone(),
+            access_pubkey,
+            action: AuditAction::ContractCall,
+            asset_id: Some(instruction.asset_id.clone()),
+            instruction_id: Some(instruction.id),
+            template_id: Some(instruction.template_id),
+            contract_name: Some(instruction.contract_name.clone()),
+            params: instruction.params.clone(),
+        }
+    }
+}
+
+impl AuditLog {
+    pub async fn record(params: NewAuditLog, client: &Client) -> Result<AuditLog, DBError> {
+        const QUERY: &'static str = "INSERT INTO audit_log (signer_pubkey, access_pubkey, action, asset_id,
+                instruction_id, template_id, contract_name, params_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, encode(digest($8::TEXT, 'sha256'), 'hex'))
+            RETURNING *";
+        let stmt = client
+            .prepare_typed(QUERY, &[
+                Type::TEXT,
+                Type::TEXT,
+                Type::TEXT,
+                Type::BPCHAR,
+                Type::UUID,
+                Type::INT8,
+                Type::TEXT,
+                Type::JSONB,
+            ])
+            .await?;
+        let row = client
+            .query_one(&stmt, &[
+                &params.signer_pubkey,
+                &params.access_pubkey,
+                &params.action,
+                &params.asset_id,
+                &params.instruction_id,
+                &params.template_id,
+                &params.contract_name,
+                &params.params,
+            ])
+            .await?;
+        Ok(AuditLog::from_row(row)?)
+    }
+
+    pub async fn select(params: SelectAuditLog, client: &Client) -> Result<Vec<AuditLog>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM audit_log WHERE ($1 IS NULL OR created_at > $1) AND ($2 IS NULL OR \
+                                     created_at < $2) AND ($3 IS NULL OR asset_id = $3) AND ($4 IS NULL OR \
+                                     signer_pubkey = $4 OR access_pubkey = $4) ORDER BY created_at LIMIT $5";
+        let stmt = client
+            .prepare_typed(QUERY, &[
+                Type::TIMESTAMPTZ,
+                Type::TIMESTAMPTZ,
+                Type::BPCHAR,
+                Type::TEXT,
+                Type::INT8,
+            ])
+            .await?;
+        Ok(client
+            .query(&stmt, &[
+                &params.since,
+                &params.until,
+                &params.asset_id,
+                &params.pubkey,
+                &params.limit,
+            ])
+            .await?
+            .into_iter()
+            .map(AuditLog::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{
+        builders::{consensus::InstructionBuilder, AssetStateBuilder},
+        test_db_client,
+    };
+    use serde_json::json;
+
+    const SIGNER: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
+    const ACCESS: &'static str = "a6b1e2a9b3ee2e7e8e1d80b0e9c1fa4c5a3b0f1e2d3c4b5a69788796a5b4c3d2";
+
+    #[actix_rt::test]
+    async fn record_and_select() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let instruction = Instruction {
+            signer_pubkey: Some(SIGNER.to_owned()),
+            params: json!({ "quantity": 1 }),
+            ..instruction
+        };
+
+        let call = AuditLog::record(NewAuditLog::contract_call(&instruction, Some(ACCESS.to_owned())), &client)
+            .await
+            .unwrap();
+        assert_eq!(call.action, AuditAction::ContractCall);
+        assert_eq!(call.instruction_id, Some(instruction.id));
+        assert_eq!(call.contract_name, Some(instruction.contract_name.clone()));
+        assert_eq!(call.params_hash.len(), 64);
+        let switch = AuditLog::record(
+            NewAuditLog {
+                access_pubkey: Some(ACCESS.to_owned()),
+                action: AuditAction::ContractSwitch,
+                template_id: Some(instruction.template_id),
+                contract_name: Some(instruction.contract_name.clone()),
+                params: json!({ "enabled": false }),
+                ..NewAuditLog::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_ne!(switch.params_hash, call.params_hash);
+
+        let all = AuditLog::select(SelectAuditLog::default(), &client).await.unwrap();
+        assert_eq!(all, vec![call.clone(), switch.clone()]);
+        let signed = AuditLog::select(
+            SelectAuditLog {
+                pubkey: Some(SIGNER.to_owned()),
+                ..SelectAuditLog::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(signed, vec![call.clone()]);
+        let asset_records = AuditLog::select(
+            SelectAuditLog {
+                asset_id: Some(asset.asset_id.clone()),
+                ..SelectAuditLog::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(asset_records, vec![call.clone()]);
+        let since = AuditLog::select(
+            SelectAuditLog {
+                since: Some(call.created_at),
+                pubkey: Some(ACCESS.to_owned()),
+                ..SelectAuditLog::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(since, vec![switch]);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{consensus::CommitteeCandidate, db::utils::errors::DBError, types::AssetID};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::Client;
+
+/// Node of the asset committee, see [crate::consensus::select_committee]
+#[derive(Serialize, PostgresMapper, PartialEq, Debug, Clone)]
+#[pg_mapper(table = "committee_members")]
+pub struct CommitteeMember {
+    pub asset_id: AssetID,
+    pub node_id: String,
+    pub collateral: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CommitteeMember {
+    /// Stores committee selected for the asset
+    pub async fn insert_all(
+        asset_id: &AssetID,
+        members: &[CommitteeCandidate],
+        client: &Client,
+    ) -> Result<Vec<CommitteeMember>, DBError>
+    {
+        const QUERY: &'static str =
+            "INSERT INTO committee_members (asset_id, node_id, collateral) VALUES ($1, $2, $3) RETURNING *";
+        let stmt = client.prepare(QUERY).await?;
+        let mut inserted = Vec::with_capacity(members.len());
+        for member in members {
+            let row = client
+                .query_one(&stmt, &[asset_id, &member.node_id, &member.collateral])
+                .await?;
+            inserted.push(CommitteeMember::from_row(row)?);
+        }
+        Ok(inserted)
+    }
+
+    /// Replaces committee of the asset, e.g. on committee handover
+    pub async fn replace_all(
+        asset_id: &AssetID,
+        members: &[CommitteeCandidate],
+        client: &Client,
+    ) -> Result<Vec<CommitteeMember>, DBError>
+    {
+        const QUERY: &'static str = "DELETE FROM committee_members WHERE asset_id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        client.execute(&stmt, &[asset_id]).await?;
+        Self::insert_all(asset_id, members, client).await
+    }
+
+    /// Committee of the asset
+    pub async fn find_by_asset_id(asset_id: &AssetID, client: &Client) -> Result<Vec<CommitteeMember>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM committee_members WHERE asset_id = $1 ORDER BY node_id";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&asset_id])
+            .await?
+            .into_iter()
+            .map(CommitteeMember::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
@@ -33,8 +33,10 @@ pub struct UpdateAggregateSignatureMessage {
 }
 
 impl AggregateSignatureMessage {
-    pub async fn find_pending(client: &Client) -> Result<Option<Self>, DBError> {
-        let stmt = "
+    /// Pending message of the least recently locked asset, assets partitioned to other workers than `worker` are
+    /// skipped, see [crate::consensus::ClusterWorker]
+    pub async fn find_pending(worker: Option<&str>, client: &Client) -> Result<Option<Self>, DBError> {
+        const QUERY: &'static str = "
             SELECT asm.*
             FROM aggregate_signature_messages asm
             JOIN (
@@ -44,22 +46,28 @@ impl AggregateSignatureMessage {
                 JOIN asset_states ast ON ast.asset_id = p.asset_id
                 WHERE asm.status = 'Pending'
                 AND ast.blocked_until <= now()
+                AND worker_owns_asset($1, ast.asset_id)
+                ORDER BY ast.blocked_until
+                LIMIT 1
             ) asm2 ON asm.proposal_id = asm2.proposal_id
             AND asm.status = 'Pending'
             LIMIT 1
         ";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
 
-        let aggregate_signature_message: Option<AggregateSignatureMessage> = match client.query_opt(stmt, &[]).await? {
-            Some(row) => Some(AggregateSignatureMessage::from_row(row)?),
-            None => None,
-        };
+        let aggregate_signature_message: Option<AggregateSignatureMessage> =
+            match client.query_opt(&stmt, &[&worker]).await? {
+                Some(row) => Some(AggregateSignatureMessage::from_row(row)?),
+                None => None,
+            };
         Ok(aggregate_signature_message)
     }
 
     /// Load aggregate signature message from database by ID
     pub async fn load(id: uuid::Uuid, client: &Client) -> Result<Self, DBError> {
-        let stmt = "SELECT * FROM aggregate_signature_messages WHERE id = $1";
-        let result = client.query_one(stmt, &[&id]).await?;
+        const QUERY: &'static str = "SELECT * FROM aggregate_signature_messages WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client.query_one(&stmt, &[&id]).await?;
         Ok(Self::from_row(result)?)
     }
 
@@ -94,9 +102,10 @@ impl AggregateSignatureMessage {
 
     /// Load aggregate signature messages from database by ProposalID
     pub async fn load_by_proposal_id(id: ProposalID, client: &Client) -> Result<Vec<Self>, DBError> {
-        let stmt = "SELECT * FROM aggregate_signature_messages WHERE proposal_id = $1::\"ProposalID\"";
+        const QUERY: &'static str = "SELECT * FROM aggregate_signature_messages WHERE proposal_id = $1::\"ProposalID\"";
+        let stmt = client.prepare(QUERY).await?;
         Ok(client
-            .query(stmt, &[&id])
+            .query(&stmt, &[&id])
             .await?
             .into_iter()
             .map(|row| Self::from_row(row))
@@ -120,6 +129,20 @@ impl AggregateSignatureMessage {
             .await?;
         Ok(Self::from_row(row)?)
     }
+
+    /// Deletes Rejected messages and messages of invalid or declined proposals last updated before `before`,
+    /// messages accepted for finalized proposals are kept. Returns number of deleted messages
+    pub async fn delete_orphaned(before: DateTime<Utc>, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "
+            DELETE FROM aggregate_signature_messages asm
+            WHERE asm.updated_at < $1
+            AND (
+                asm.status = 'Rejected'
+                OR asm.proposal_id IN (SELECT id FROM proposals WHERE status IN ('Invalid', 'Declined'))
+            )";
+        let stmt = client.prepare_typed(QUERY, &[Type::TIMESTAMPTZ]).await?;
+        Ok(client.execute(&stmt, &[&before]).await?)
+    }
 }
 
 impl NewAggregateSignatureMessage {
@@ -179,7 +202,7 @@ mod test {
             .unwrap();
         asset_state.acquire_lock(60 as u64, &client).await.unwrap();
 
-        let found_aggregate_signature_messages = AggregateSignatureMessage::find_pending(&client).await.unwrap();
+        let found_aggregate_signature_messages = AggregateSignatureMessage::find_pending(None, &client).await.unwrap();
         assert_eq!(found_aggregate_signature_messages, Some(aggregate_signature_message));
     }
 
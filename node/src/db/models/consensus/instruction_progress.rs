@@ -0,0 +1,74 @@
+use crate::{db::utils::errors::DBError, types::InstructionID};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::types::Type;
+
+/// Partial progress of the running instruction, reported by contract via [ContextEvent::Progress]
+///
+/// [ContextEvent::Progress]: crate::template::ContextEvent::Progress
+#[derive(Debug, Clone, Serialize, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "instruction_progress")]
+pub struct InstructionProgress {
+    pub id: i64,
+    pub instruction_id: InstructionID,
+    pub data: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl InstructionProgress {
+    /// Appends progress entry to the instruction
+    pub async fn append(instruction_id: InstructionID, data: Value, client: &Client) -> Result<Self, DBError> {
+        const QUERY: &'static str =
+            "INSERT INTO instruction_progress (instruction_id, data) VALUES ($1, $2) RETURNING *";
+        let stmt = client
+            .prepare_typed(QUERY, &[InstructionID::SQL_TYPE, Type::JSONB])
+            .await?;
+        Ok(Self::from_row(client.query_one(&stmt, &[&instruction_id, &data]).await?)?)
+    }
+
+    /// Progress entries of the instruction in order they were reported
+    pub async fn find_by_instruction_id(instruction_id: InstructionID, client: &Client) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instruction_progress WHERE instruction_id = $1 ORDER BY id";
+        let stmt = client.prepare_typed(QUERY, &[InstructionID::SQL_TYPE]).await?;
+        let rows = client.query(&stmt, &[&instruction_id]).await?;
+        Ok(rows.into_iter().map(Self::from_row).collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{builders::consensus::InstructionBuilder, test_db_client};
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn append() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let other = InstructionBuilder::default().build(&client).await.unwrap();
+        assert!(InstructionProgress::find_by_instruction_id(instruction.id, &client)
+            .await
+            .unwrap()
+            .is_empty());
+
+        InstructionProgress::append(instruction.id, json!({ "step": 1 }), &client)
+            .await
+            .unwrap();
+        InstructionProgress::append(other.id, json!({ "step": 1 }), &client)
+            .await
+            .unwrap();
+        let last = InstructionProgress::append(instruction.id, json!({ "step": 2 }), &client)
+            .await
+            .unwrap();
+        assert_eq!(last.instruction_id, instruction.id);
+
+        let progress = InstructionProgress::find_by_instruction_id(instruction.id, &client)
+            .await
+            .unwrap();
+        let data: Vec<_> = progress.into_iter().map(|entry| entry.data).collect();
+        assert_eq!(data, vec![json!({ "step": 1 }), json!({ "step": 2 })]);
+    }
+}
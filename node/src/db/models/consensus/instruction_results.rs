@@ -0,0 +1,153 @@
+use super::instructions::Instruction;
+use crate::{db::utils::errors::DBError, types::InstructionID};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::types::Type;
+
+/// Complete result of the instruction exceeding [TemplateConfig::max_result_size], referenced by
+/// [Instruction::result_ref], while instruction keeps truncated result
+///
+/// [TemplateConfig::max_result_size]: crate::template::config::TemplateConfig::max_result_size
+#[derive(Debug, Clone, Serialize, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "instruction_results")]
+pub struct InstructionResult {
+    pub id: uuid::Uuid,
+    pub instruction_id: InstructionID,
+    pub result: Value,
+    /// Size of serialized result in bytes
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl InstructionResult {
+    /// Stores complete `result` of the instruction if it exceeds `max_size` bytes, returns result which fits
+    /// into instruction record: either `result` itself or truncated one, see [truncate].
+    /// Limit is disabled when `max_size` is 0
+    pub async fn limit(
+        instruction_id: InstructionID,
+        result: Value,
+        max_size: usize,
+        client: &Client,
+    ) -> Result<Value, DBError>
+    {
+        let size = json_size(&result);
+        if max_size == 0 || size <= max_size {
+            return Ok(result);
+        }
+        const QUERY: &'static str = "
+            WITH result AS (
+                INSERT INTO instruction_results (instruction_id, result, size) VALUES ($1, $2, $3) RETURNING id
+            )
+            UPDATE instructions SET result_ref = (SELECT id FROM result) WHERE id = $1 RETURNING result_ref";
+        let stmt = client
+            .prepare_typed(QUERY, &[InstructionID::SQL_TYPE, Type::JSONB, Type::INT8])
+            .await?;
+        client
+            .query_one(&stmt, &[&instruction_id, &result, &(size as i64)])
+            .await?;
+        Ok(truncate(result, max_size))
+    }
+
+    pub async fn load(id: uuid::Uuid, client: &Client) -> Result<Self, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instruction_results WHERE id = $1";
+        let stmt = client.prepare_typed(QUERY, &[Type::UUID]).await?;
+        Ok(Self::from_row(client.query_one(&stmt, &[&id]).await?)?)
+    }
+
+    /// Complete result of the instruction, either its own result or the one referenced by `result_ref`
+    pub async fn complete(instruction: &Instruction, client: &Client) -> Result<Value, DBError> {
+        match instruction.result_ref {
+            Some(id) => Ok(Self::load(id, client).await?.result),
+            None => Ok(instruction.result.clone()),
+        }
+    }
+}
+
+/// Cuts `value` down to `max_size` bytes serialized: lists keep leading items which fit,
+/// fields of objects share the size in order, other values not fitting are replaced with null
+pub fn truncate(value: Value, max_size: usize) -> Value {
+    match value {
+        Value::Array(items) => {
+            let mut size = 2;
+            Value::Array(
+                items
+                    .into_iter()
+                    .take_while(|item| {
+                        size += json_size(item) + 1;
+                        size <= max_size
+                    })
+                    .collect(),
+            )
+        },
+        Value::Object(fields) => {
+            let mut remaining = max_size.saturating_sub(2);
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let key_size = key.len() + 4;
+                        let value = truncate(value, remaining.saturating_sub(key_size));
+                        remaining = remaining.saturating_sub(key_size + json_size(&value));
+                        (key, value)
+                    })
+                    .collect(),
+            )
+        },
+        value if json_size(&value) <= max_size => value,
+        _ => Value::Null,
+    }
+}
+
+pub(crate) fn json_size(value: &Value) -> usize {
+    serde_json::to_vec(value).map(|json| json.len()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{builders::consensus::InstructionBuilder, test_db_client};
+    use serde_json::json;
+
+    #[test]
+    fn truncate_result() {
+        let tokens: Vec<Value> = (0..100).map(|i| json!({ "token_id": format!("{:032X}", i) })).collect();
+        let truncated = truncate(json!(tokens), 1000);
+        assert!(json_size(&truncated) <= 1000);
+        let items = truncated.as_array().unwrap();
+        assert!(!items.is_empty());
+        assert_eq!(items[..], tokens[..items.len()]);
+
+        let truncated = truncate(json!({ "issued": 100, "tokens": tokens, "note": "x".repeat(2000) }), 1000);
+        assert!(json_size(&truncated) <= 1000);
+        assert_eq!(truncated["issued"], 100);
+        assert!(!truncated["tokens"].as_array().unwrap().is_empty());
+        assert_eq!(truncated["note"], Value::Null);
+    }
+
+    #[actix_rt::test]
+    async fn limit() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let result = json!((0..100).collect::<Vec<_>>());
+
+        let limited = InstructionResult::limit(instruction.id, result.clone(), 0, &client).await.unwrap();
+        assert_eq!(limited, result);
+        let limited = InstructionResult::limit(instruction.id, result.clone(), 1000, &client).await.unwrap();
+        assert_eq!(limited, result);
+        let instruction = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!(instruction.result_ref, None);
+
+        let limited = InstructionResult::limit(instruction.id, result.clone(), 100, &client).await.unwrap();
+        assert_eq!(limited, truncate(result.clone(), 100));
+        let instruction = Instruction::load(instruction.id, &client).await.unwrap();
+        assert!(instruction.result_ref.is_some());
+        let instruction = Instruction {
+            result: limited,
+            ..instruction
+        };
+        assert_eq!(InstructionResult::complete(&instruction, &client).await.unwrap(), result);
+    }
+}
@@ -1,15 +1,14 @@
 pub use crate::db::models::InstructionStatus;
 use crate::{
-    db::{
-        models::{NewAssetStateAppendOnly, NewTokenStateAppendOnly},
-        utils::errors::DBError,
-    },
-    types::{AssetID, InstructionID, NodeID, ProposalID, TemplateID, TokenID},
+    db::utils::{errors::DBError, statements::CachedStatements},
+    timed_query,
+    types::{consensus::AppendOnlyState, AssetID, InstructionID, NodeID, ProposalID, TemplateID, TokenID},
 };
 use chrono::{DateTime, Utc};
 use deadpool_postgres::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
 use tokio_postgres::types::Type;
 
@@ -26,10 +25,65 @@ pub struct Instruction {
     pub contract_name: String,
     pub status: InstructionStatus,
     pub params: Value,
+    /// Truncated if exceeds [TemplateConfig::max_result_size], complete result is referenced by `result_ref`
+    ///
+    /// [TemplateConfig::max_result_size]: crate::template::config::TemplateConfig::max_result_size
     pub result: Value,
+    /// Complete result of the instruction, see [InstructionResult](super::InstructionResult)
+    #[serde(default)]
+    pub result_ref: Option<uuid::Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub proposal_id: Option<ProposalID>,
+    /// Instructions which have to be committed before this instruction is executed
+    #[serde(default)]
+    pub depends_on: Vec<uuid::Uuid>,
+    /// Pubkey which signed params of API-initiated instruction
+    pub signer_pubkey: Option<String>,
+    /// Sequence number of the instruction among instructions of the signer, if provided by the client
+    pub nonce: Option<i64>,
+    /// Priority lane of operator instructions if above 0, see [crate::consensus::Mempool]
+    #[serde(default)]
+    pub priority: i32,
+    /// Failed executions of the instruction, see [RetryPolicy](crate::template::config::RetryPolicy)
+    #[serde(default)]
+    pub attempts: i32,
+    /// Position of the call in the batch it was submitted with, calls of a batch share the signature
+    #[serde(default)]
+    pub batch_index: Option<i32>,
+}
+
+/// Field of contract call params listing IDs of instructions the call depends on, see [take_depends_on]
+pub const DEPENDS_ON_PARAM: &'static str = "depends_on";
+
+/// Field of contract call params with sequence number of the signer's instruction, see [take_nonce]
+pub const NONCE_PARAM: &'static str = "nonce";
+
+/// Field of contract call params with priority of the instruction, above 0 requires admin access, see [take_priority]
+pub const PRIORITY_PARAM: &'static str = "priority";
+
+/// Removes [DEPENDS_ON_PARAM] from contract call params, so the rest of params is deserialized by the contract
+pub fn take_depends_on(params: &mut Value) -> Result<Vec<uuid::Uuid>, serde_json::Error> {
+    match params.as_object_mut().and_then(|params| params.remove(DEPENDS_ON_PARAM)) {
+        Some(depends_on) => serde_json::from_value(depends_on),
+        None => Ok(vec![]),
+    }
+}
+
+/// Removes [NONCE_PARAM] from contract call params, so the rest of params is deserialized by the contract
+pub fn take_nonce(params: &mut Value) -> Result<Option<i64>, serde_json::Error> {
+    match params.as_object_mut().and_then(|params| params.remove(NONCE_PARAM)) {
+        Some(nonce) => serde_json::from_value(nonce),
+        None => Ok(None),
+    }
+}
+
+/// Removes [PRIORITY_PARAM] from contract call params, so the rest of params is deserialized by the contract
+pub fn take_priority(params: &mut Value) -> Result<i32, serde_json::Error> {
+    match params.as_object_mut().and_then(|params| params.remove(PRIORITY_PARAM)) {
+        Some(priority) => serde_json::from_value(priority),
+        None => Ok(0),
+    }
 }
 
 /// Query parameters for adding new instruction record
@@ -45,6 +99,13 @@ pub struct NewInstruction {
     pub contract_name: String,
     pub status: InstructionStatus,
     pub params: Value,
+    pub depends_on: Vec<uuid::Uuid>,
+    pub signer_pubkey: Option<String>,
+    /// Has to follow the last nonce of `signer_pubkey`, starting from 1
+    pub nonce: Option<i64>,
+    pub priority: i32,
+    /// Position of the call in the batch, calls of a batch share the signature
+    pub batch_index: Option<i32>,
 }
 
 /// Query parameters for optionally updating instruction fields
@@ -55,25 +116,87 @@ pub struct UpdateInstruction {
     pub proposal_id: Option<ProposalID>,
 }
 
+impl NewInstruction {
+    /// Content addressed ID of the instruction, see [InstructionID::derive]
+    ///
+    /// Client signature is the nonce of API-initiated instructions, along with the call index for calls of a batch,
+    /// parent ID is the nonce of subinstructions, instructions having neither can't be told apart by content,
+    /// so they have no content ID
+    pub fn content_id(&self) -> Option<InstructionID> {
+        let nonce = match (self.parent_id, self.batch_index) {
+            (Some(parent_id), _) => parent_id.as_bytes().to_vec(),
+            (None, _) if self.signature.is_empty() => return None,
+            (None, Some(index)) => format!("{}/{}", self.signature, index).into_bytes(),
+            (None, None) => self.signature.as_bytes().to_vec(),
+        };
+        Some(InstructionID::derive(
+            &self.asset_id,
+            self.token_id.as_ref(),
+            &self.contract_name,
+            &self.params,
+            &nonce,
+            self.initiating_node_id,
+        ))
+    }
+}
+
 impl Instruction {
-    pub async fn find_pending(client: &Client) -> Result<Option<(AssetID, Vec<Self>)>, DBError> {
+    /// Verifies content addressed ID matches instruction content, time based IDs are always valid
+    pub fn has_valid_id(&self) -> bool {
+        if self.id.get_version() != Some(uuid::Version::Sha1) {
+            return true;
+        }
+        let content = NewInstruction {
+            parent_id: self.parent_id,
+            initiating_node_id: self.initiating_node_id,
+            signature: self.signature.clone(),
+            asset_id: self.asset_id.clone(),
+            token_id: self.token_id.clone(),
+            contract_name: self.contract_name.clone(),
+            params: self.params.clone(),
+            batch_index: self.batch_index,
+            ..NewInstruction::default()
+        };
+        content.content_id() == Some(self.id)
+    }
+
+    /// Pending instructions of the least recently locked asset, assets with instructions of the priority lane first
+    ///
+    /// Instructions of disabled contracts are skipped, [crate::db::models::ContractSwitch] takes precedence over
+    /// `disabled_contracts` from template configuration. Assets partitioned to other workers than `worker`
+    /// are skipped, see [crate::consensus::ClusterWorker]
+    pub async fn find_pending(
+        disabled_contracts: &[String],
+        worker: Option<&str>,
+        client: &Client,
+    ) -> Result<Option<(AssetID, Vec<Self>)>, DBError>
+    {
         const QUERY: &'static str = "
+            WITH pending AS (
+                SELECT i.*
+                FROM instructions i
+                LEFT JOIN contract_switches cs
+                ON cs.template_id = i.template_id AND cs.contract_name = i.contract_name
+                WHERE i.status = 'Pending'
+                AND COALESCE(cs.enabled, NOT (i.contract_name = ANY($1::TEXT[])))
+            )
             SELECT i.*
-            FROM instructions i
+            FROM pending i
             JOIN (
                 SELECT i.asset_id
-                FROM instructions i
+                FROM pending i
                 JOIN asset_states ast ON ast.asset_id = i.asset_id
-                WHERE i.status = 'Pending'
-                AND ast.blocked_until <= now()
+                WHERE ast.blocked_until <= now()
+                AND worker_owns_asset($2, ast.asset_id)
+                GROUP BY i.asset_id, ast.blocked_until
+                ORDER BY bool_or(i.priority > 0) DESC, ast.blocked_until
                 LIMIT 1
             ) i2 ON i.asset_id = i2.asset_id
-            AND i.status = 'Pending'
         ";
 
         let stmt = client.prepare(QUERY).await?;
-        let instructions: Vec<Instruction> = client
-            .query(&stmt, &[])
+        let instructions: Vec<Instruction> =
+            timed_query!(QUERY, client.query(&stmt, &[&disabled_contracts, &worker]))
             .await?
             .into_iter()
             .map(|row| Instruction::from_row(row))
@@ -99,8 +222,13 @@ impl Instruction {
                 status,
                 params,
                 parent_id,
-                id
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *";
+                id,
+                depends_on,
+                signer_pubkey,
+                nonce,
+                priority,
+                batch_index
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) RETURNING *";
         let stmt = client
             .prepare_typed(QUERY, &[
                 NodeID::SQL_TYPE,
@@ -114,8 +242,9 @@ impl Instruction {
             ])
             .await?;
 
-        let row = client
-            .query_one(&stmt, &[
+        let row = timed_query!(
+            QUERY,
+            client.query_one(&stmt, &[
                 &params.initiating_node_id,
                 &params.signature,
                 &params.asset_id,
@@ -126,11 +255,24 @@ impl Instruction {
                 &params.params,
                 &params.parent_id,
                 &params.id,
+                &params.depends_on,
+                &params.signer_pubkey,
+                &params.nonce,
+                &params.priority,
+                &params.batch_index,
             ])
-            .await?;
+        )
+        .await?;
         Ok(Self::from_row(row)?)
     }
 
+    /// The highest nonce of instructions signed by `signer_pubkey`, None if signer has not used nonces yet
+    pub async fn last_nonce(signer_pubkey: &str, client: &Client) -> Result<Option<i64>, DBError> {
+        const QUERY: &'static str = "SELECT MAX(nonce) FROM instructions WHERE signer_pubkey = $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_one(&stmt, &[&signer_pubkey]).await?.get(0))
+    }
+
     /// Marks set of instructions as given status and sets proposal id for reference if provided
     pub async fn update_instructions_status(
         instruction_ids: &[InstructionID],
@@ -148,18 +290,31 @@ impl Instruction {
                 updated_at = NOW()
             WHERE id::uuid = ANY ($1)";
         let stmt = client.prepare_typed(QUERY, &[Type::UUID_ARRAY, Type::TEXT]).await?;
-        client
-            .execute(&stmt, &[
+        timed_query!(
+            QUERY,
+            client.execute(&stmt, &[
                 &instruction_ids.iter().map(|i| i.0).collect::<Vec<uuid::Uuid>>(),
                 &status,
                 &proposal_id,
                 &result,
             ])
-            .await?;
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Counts failed execution of the instruction, returns failed executions so far
+    pub async fn add_attempt(id: InstructionID, client: &Client) -> Result<i32, DBError> {
+        const QUERY: &'static str = "
+            UPDATE instructions SET attempts = attempts + 1
+            WHERE id = $1::\"InstructionID\"
+            RETURNING attempts";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
+        let row = timed_query!(QUERY, client.query_one(&stmt, &[&id])).await?;
+        Ok(row.get("attempts"))
+    }
+
     /// Update instruction state in the database
     ///
     /// Updates subset of fields:
@@ -175,35 +330,150 @@ impl Instruction {
             WHERE id = $4::\"InstructionID\"
             RETURNING *";
         let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
-        let row = client
-            .query_one(&stmt, &[&data.status, &data.proposal_id, &data.result, &self.id])
-            .await?;
+        let row = timed_query!(
+            QUERY,
+            client.query_one(&stmt, &[&data.status, &data.proposal_id, &data.result, &self.id])
+        )
+        .await?;
         Ok(Self::from_row(row)?)
     }
 
+    /// Whether instruction `id` is reachable from `depends_on` following dependencies of existing instructions,
+    /// i.e. whether instruction with such dependencies would close a dependency cycle
+    pub async fn has_dependency_cycle(
+        id: InstructionID,
+        depends_on: &[uuid::Uuid],
+        client: &Client,
+    ) -> Result<bool, DBError>
+    {
+        const QUERY: &'static str = "
+            WITH RECURSIVE dependencies(id) AS (
+                SELECT unnest($2::UUID[])
+                UNION
+                SELECT d.id
+                FROM dependencies
+                JOIN instructions i ON i.id::uuid = dependencies.id
+                CROSS JOIN LATERAL unnest(i.depends_on) AS d(id)
+            )
+            SELECT EXISTS (SELECT 1 FROM dependencies WHERE id = $1::uuid)";
+        if depends_on.is_empty() {
+            return Ok(false);
+        }
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[&id.0, &depends_on]).await?;
+        Ok(row.get(0))
+    }
+
+    /// Instructions this instruction depends on, dependencies which are not submitted yet are missing
+    pub async fn load_dependencies<C: CachedStatements>(&self, client: &C) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instructions WHERE id::uuid = ANY ($1)";
+        if self.depends_on.is_empty() {
+            return Ok(vec![]);
+        }
+        let stmt = client.prepare_cached(QUERY).await?;
+        Ok(client
+            .connection()
+            .query(&stmt, &[&self.depends_on])
+            .await?
+            .into_iter()
+            .map(Self::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
     /// Load instruction record
-    pub async fn load(id: InstructionID, client: &tokio_postgres::Client) -> Result<Self, DBError> {
+    pub async fn load<C: CachedStatements>(id: InstructionID, client: &C) -> Result<Self, DBError> {
         const QUERY: &'static str = "SELECT * FROM instructions WHERE id = $1::\"InstructionID\"";
-        let stmt = client.prepare(QUERY).await?;
-        let row = client.query_one(&stmt, &[&id]).await?;
+        let stmt = client.prepare_cached(QUERY).await?;
+        let row = client.connection().query_one(&stmt, &[&id]).await?;
         Ok(Self::from_row(row)?)
     }
 
-    /// Execute the instruction returning append only state
-    pub async fn execute(
-        &self,
-        _client: &Client,
-    ) -> Result<(Vec<NewAssetStateAppendOnly>, Vec<NewTokenStateAppendOnly>), DBError>
+    /// Find instruction record, None if it doesn't exist
+    pub async fn find<C: CachedStatements>(id: InstructionID, client: &C) -> Result<Option<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instructions WHERE id = $1::\"InstructionID\"";
+        let stmt = client.prepare_cached(QUERY).await?;
+        let row = client.connection().query_opt(&stmt, &[&id]).await?;
+        Ok(row.map(Self::from_row).transpose()?)
+    }
+
+    /// All instructions of the asset, oldest first
+    pub async fn find_by_asset_id<C: CachedStatements>(asset_id: &AssetID, client: &C) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instructions WHERE asset_id = $1 ORDER BY created_at";
+        let stmt = client.prepare_cached(QUERY).await?;
+        let rows = client.connection().query(&stmt, &[asset_id]).await?;
+        Ok(rows.into_iter().map(Self::from_row).collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Instructions of the asset updated at or after `since`, oldest first
+    pub async fn find_updated_since<C: CachedStatements>(
+        asset_id: &AssetID,
+        since: DateTime<Utc>,
+        client: &C,
+    ) -> Result<Vec<Self>, DBError>
+    {
+        const QUERY: &'static str =
+            "SELECT * FROM instructions WHERE asset_id = $1 AND updated_at >= $2 ORDER BY updated_at, created_at";
+        let stmt = client.prepare_cached(QUERY).await?;
+        let rows = client.connection().query(&stmt, &[asset_id, &since]).await?;
+        Ok(rows.into_iter().map(Self::from_row).collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Committed instructions of the asset created at or after `since`, oldest first
+    pub async fn find_committed_since<C: CachedStatements>(
+        asset_id: &AssetID,
+        since: DateTime<Utc>,
+        client: &C,
+    ) -> Result<Vec<Self>, DBError>
     {
+        const QUERY: &'static str = "SELECT * FROM instructions WHERE asset_id = $1 AND status = 'Commit' AND \
+                                     created_at >= $2 ORDER BY created_at";
+        let stmt = client.prepare_cached(QUERY).await?;
+        let rows = client.connection().query(&stmt, &[asset_id, &since]).await?;
+        Ok(rows.into_iter().map(Self::from_row).collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Most recently updated instructions, newest first
+    pub async fn find_recent<C: CachedStatements>(limit: i64, client: &C) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instructions ORDER BY updated_at DESC LIMIT $1";
+        let stmt = client.prepare_cached(QUERY).await?;
+        let rows = client.connection().query(&stmt, &[&limit]).await?;
+        Ok(rows.into_iter().map(Self::from_row).collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Number of instructions by status, statuses without instructions are omitted
+    pub async fn count_by_status(client: &Client) -> Result<HashMap<InstructionStatus, i64>, DBError> {
+        const QUERY: &'static str = "SELECT status, count(*) FROM instructions GROUP BY status";
+        let stmt = client.prepare(QUERY).await?;
+        let rows = client.query(&stmt, &[]).await?;
+        Ok(rows.into_iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    /// Execute the instruction returning append only state
+    ///
+    /// State is buffered by the contract execution, see [Instruction::store_append_only_state],
+    /// empty for instructions which stored their state directly
+    pub async fn execute(&self, client: &Client) -> Result<AppendOnlyState, DBError> {
         // TODO: we will need to encapsulate the running of an instruction somehow so that nodes can compare view state
         // to expected state
-        Ok((Vec::new(), Vec::new()))
+        const QUERY: &'static str = "SELECT append_only_state FROM instructions WHERE id = $1::\"InstructionID\"";
+        let stmt = client.prepare(QUERY).await?;
+        let state: Option<AppendOnlyState> = client.query_one(&stmt, &[&self.id]).await?.get(0);
+        Ok(state.unwrap_or_default())
     }
 
-    pub async fn load_subinstructions(&self, client: &tokio_postgres::Client) -> Result<Vec<Instruction>, DBError> {
-        const QUERY: &'static str = "SELECT * FROM instructions WHERE parent_id = $1::\"InstructionID\"";
+    /// Stores append only state buffered by the contract execution, it is persisted by consensus on commit
+    pub async fn store_append_only_state(&self, state: &AppendOnlyState, client: &Client) -> Result<(), DBError> {
+        const QUERY: &'static str =
+            "UPDATE instructions SET append_only_state = $2 WHERE id = $1::\"InstructionID\"";
         let stmt = client.prepare(QUERY).await?;
-        let rows = client.query(&stmt, &[&self.id]).await?;
+        client.execute(&stmt, &[&self.id, state]).await?;
+        Ok(())
+    }
+
+    pub async fn load_subinstructions<C: CachedStatements>(&self, client: &C) -> Result<Vec<Instruction>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM instructions WHERE parent_id = $1::\"InstructionID\"";
+        let stmt = client.prepare_cached(QUERY).await?;
+        let rows = client.connection().query(&stmt, &[&self.id]).await?;
         Ok(rows.into_iter().map(Self::from_row).collect::<Result<Vec<_>, _>>()?)
     }
 }
@@ -250,10 +520,50 @@ mod test {
             .await
             .unwrap();
 
-        let instructions = Instruction::find_pending(&client).await.unwrap();
+        let instructions = Instruction::find_pending(&[], None, &client).await.unwrap();
         assert_eq!(instructions, Some((instruction2.asset_id.clone(), vec![instruction2])));
     }
 
+    #[actix_rt::test]
+    async fn count_by_status() {
+        let (client, _lock) = test_db_client().await;
+        InstructionBuilder::default().build(&client).await.unwrap();
+        InstructionBuilder {
+            status: InstructionStatus::Invalid,
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        InstructionBuilder::default().build(&client).await.unwrap();
+
+        let counts = Instruction::count_by_status(&client).await.unwrap();
+        assert_eq!(counts.get(&InstructionStatus::Pending), Some(&2));
+        assert_eq!(counts.get(&InstructionStatus::Invalid), Some(&1));
+        assert_eq!(counts.get(&InstructionStatus::Commit), None);
+    }
+
+    #[actix_rt::test]
+    async fn find_pending_skips_disabled_contracts() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let disabled = vec![instruction.contract_name.clone()];
+
+        assert!(Instruction::find_pending(&disabled, None, &client).await.unwrap().is_none());
+
+        // switch set by operator overrides config
+        ContractSwitch::set(&instruction.template_id, &instruction.contract_name, true, &client)
+            .await
+            .unwrap();
+        let instructions = Instruction::find_pending(&disabled, None, &client).await.unwrap();
+        assert_eq!(instructions, Some((instruction.asset_id.clone(), vec![instruction.clone()])));
+
+        ContractSwitch::set(&instruction.template_id, &instruction.contract_name, false, &client)
+            .await
+            .unwrap();
+        assert!(Instruction::find_pending(&[], None, &client).await.unwrap().is_none());
+    }
+
     #[actix_rt::test]
     async fn update_instructions_status() {
         let (client, _lock) = test_db_client().await;
@@ -287,6 +597,18 @@ mod test {
         assert!(instruction3.proposal_id.is_none());
     }
 
+    #[actix_rt::test]
+    async fn find_recent() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let instruction2 = InstructionBuilder::default().build(&client).await.unwrap();
+        let recent = Instruction::find_recent(2, &client).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].id, instruction2.id);
+        assert_eq!(recent[1].id, instruction.id);
+        assert_eq!(Instruction::find_recent(1, &client).await.unwrap().len(), 1);
+    }
+
     #[actix_rt::test]
     async fn execute() {
         let (client, _lock) = test_db_client().await;
@@ -328,6 +650,24 @@ mod test {
         assert!(instruction2.updated_at > initial_updated_at);
     }
 
+    #[actix_rt::test]
+    async fn find_updated_since() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let asset_id = instruction.asset_id.clone();
+        let other = InstructionBuilder::default().build(&client).await.unwrap();
+        assert_ne!(other.asset_id, asset_id);
+
+        let found = Instruction::find_updated_since(&asset_id, instruction.updated_at, &client)
+            .await
+            .unwrap();
+        assert_eq!(found, vec![instruction.clone()]);
+
+        let since = Utc::now() + chrono::Duration::seconds(1);
+        let found = Instruction::find_updated_since(&asset_id, since, &client).await.unwrap();
+        assert!(found.is_empty());
+    }
+
     #[actix_rt::test]
     async fn subinstruction() {
         let (client, _lock) = test_db_client().await;
@@ -370,4 +710,53 @@ mod test {
         let instruction = Instruction::insert(params, &client).await.unwrap();
         assert_eq!(instruction.status, InstructionStatus::default());
     }
+
+    #[actix_rt::test]
+    async fn dependencies() {
+        let (client, _lock) = test_db_client().await;
+        let lock = InstructionBuilder::default().build(&client).await.unwrap();
+        let pay = InstructionBuilder::default().build(&client).await.unwrap();
+        let transfer = Instruction::insert(
+            NewInstruction {
+                id: InstructionID::new(NodeID::stub()).unwrap(),
+                asset_id: lock.asset_id.clone(),
+                depends_on: vec![lock.id.0, pay.id.0],
+                ..NewInstruction::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let mut dependencies = transfer.load_dependencies(&client).await.unwrap();
+        dependencies.sort_by_key(|dep| dep.id != lock.id);
+        assert_eq!(dependencies, vec![lock.clone(), pay.clone()]);
+        assert!(lock.load_dependencies(&client).await.unwrap().is_empty());
+
+        assert!(Instruction::has_dependency_cycle(lock.id, &[transfer.id.0], &client)
+            .await
+            .unwrap());
+        assert!(!Instruction::has_dependency_cycle(transfer.id, &[lock.id.0], &client)
+            .await
+            .unwrap());
+        assert!(!Instruction::has_dependency_cycle(lock.id, &[], &client).await.unwrap());
+    }
+
+    #[test]
+    fn take_depends_on_param() {
+        let id = InstructionID::new(NodeID::stub()).unwrap().0;
+        let mut params = json!({ "price": 1, "depends_on": [id] });
+        assert_eq!(take_depends_on(&mut params).unwrap(), vec![id]);
+        assert_eq!(params, json!({ "price": 1 }));
+        assert!(take_depends_on(&mut params).unwrap().is_empty());
+        assert!(take_depends_on(&mut json!({ "depends_on": "lock" })).is_err());
+    }
+
+    #[test]
+    fn take_nonce_param() {
+        let mut params = json!({ "price": 1, "nonce": 3 });
+        assert_eq!(take_nonce(&mut params).unwrap(), Some(3));
+        assert_eq!(params, json!({ "price": 1 }));
+        assert_eq!(take_nonce(&mut params).unwrap(), None);
+        assert!(take_nonce(&mut json!({ "nonce": "3" })).is_err());
+    }
 }
@@ -1,6 +1,16 @@
-pub use self::{aggregate_signature_messages::*, instructions::*, proposals::*, signed_proposals::*, views::*};
+pub use self::{
+    aggregate_signature_messages::*,
+    instruction_progress::*,
+    instruction_results::*,
+    instructions::*,
+    proposals::*,
+    signed_proposals::*,
+    views::*,
+};
 
 pub mod aggregate_signature_messages;
+pub mod instruction_progress;
+pub mod instruction_results;
 pub mod instructions;
 pub mod proposals;
 pub mod signed_proposals;
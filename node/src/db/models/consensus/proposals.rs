@@ -3,7 +3,9 @@ use crate::{
         models::{consensus::*, ProposalStatus},
         utils::errors::DBError,
     },
+    identity::IdentityError,
     types::{AssetID, NodeID, ProposalID},
+    wallet::Signer,
 };
 use chrono::{DateTime, Utc};
 use deadpool_postgres::Client;
@@ -38,17 +40,68 @@ pub struct UpdateProposal {
 }
 
 impl Proposal {
-    pub async fn find_pending(client: &Client) -> Result<Option<Self>, DBError> {
-        let stmt = "
+    /// Pending proposal of the least recently locked asset, assets partitioned to other workers than `worker` are
+    /// skipped, see [crate::consensus::ClusterWorker]
+    pub async fn find_pending(worker: Option<&str>, client: &Client) -> Result<Option<Self>, DBError> {
+        const QUERY: &'static str = "
             SELECT p.*
             FROM proposals p
             JOIN asset_states ast ON ast.asset_id = p.asset_id
             WHERE p.status = 'Pending'
             AND ast.blocked_until <= now()
+            AND worker_owns_asset($1, ast.asset_id)
+            ORDER BY ast.blocked_until
             LIMIT 1
         ";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
+
+        Ok(client
+            .query_opt(&stmt, &[&worker])
+            .await?
+            .map(Proposal::from_row)
+            .transpose()?)
+    }
+
+    /// Proposals having accepted aggregate signature but not finalized,
+    /// e.g. node was stopped in the middle of proposal execution
+    pub async fn find_interrupted(client: &Client) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "
+            SELECT p.*
+            FROM proposals p
+            WHERE p.status NOT IN ('Finalized', 'Invalid', 'Declined')
+            AND EXISTS (
+                SELECT 1 FROM aggregate_signature_messages asm
+                WHERE asm.proposal_id = p.id
+                AND asm.status = 'Accepted'
+            )
+            ORDER BY p.created_at
+        ";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[])
+            .await?
+            .into_iter()
+            .map(Proposal::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Number of proposals neither finalized nor rejected, i.e. consensus rounds in progress
+    pub async fn count_in_progress(client: &Client) -> Result<i64, DBError> {
+        const QUERY: &'static str = "SELECT count(*) FROM proposals WHERE status IN ('Pending', 'Signed')";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_one(&stmt, &[]).await?.get(0))
+    }
 
-        Ok(client.query_opt(stmt, &[]).await?.map(Proposal::from_row).transpose()?)
+    /// Most recently updated proposals, newest first
+    pub async fn find_recent(limit: i64, client: &Client) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM proposals ORDER BY updated_at DESC LIMIT $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&limit])
+            .await?
+            .into_iter()
+            .map(Proposal::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
     }
 
     pub async fn mark_invalid(&self, client: &Client) -> Result<(), DBError> {
@@ -100,24 +153,40 @@ impl Proposal {
         Ok(Self::from_row(updated)?)
     }
 
+    /// Locks proposal row until the end of transaction, returns false if proposal is already
+    /// finalized, invalid or declined, i.e. it must not be executed.
+    /// Concurrent executions wait for the lock and see the status committed by the first one.
+    pub async fn lock_for_execution(&self, client: &Client) -> Result<bool, DBError> {
+        const QUERY: &'static str = "SELECT id FROM proposals WHERE id = $1::\"ProposalID\" AND status NOT IN \
+                                     ('Finalized', 'Invalid', 'Declined') FOR UPDATE";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_opt(&stmt, &[&self.id]).await?.is_some())
+    }
+
     /// Load proposal from database by ID
     pub async fn load(id: ProposalID, client: &Client) -> Result<Self, DBError> {
-        let stmt = "SELECT * FROM proposals WHERE id = $1::\"ProposalID\"";
-        let result = client.query_one(stmt, &[&id]).await?;
+        const QUERY: &'static str = "SELECT * FROM proposals WHERE id = $1::\"ProposalID\"";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client.query_one(&stmt, &[&id]).await?;
         Ok(Self::from_row(result)?)
     }
 
-    /// Creates partial signature
-    pub async fn create_partial_signature(&self) -> Result<String, DBError> {
-        Ok("stub-signature".to_string())
+    /// Creates partial signature of the proposal by the node
+    pub async fn create_partial_signature(&self, signer: &dyn Signer) -> Result<String, IdentityError> {
+        signer.sign(&self.signing_payload()?).await
     }
 
-    /// Signs the proposal
-    pub async fn sign(&self, node_id: NodeID, client: &Client) -> Result<SignedProposal, DBError> {
+    /// Message signed by committee members
+    pub fn signing_payload(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(&(self.id, &self.new_view))
+    }
+
+    /// Signs the proposal, storing node's partial signature
+    pub async fn sign(&self, node_id: NodeID, signature: String, client: &Client) -> Result<SignedProposal, DBError> {
         let params = NewSignedProposal {
             node_id,
             proposal_id: self.id,
-            signature: "stub-signature".to_string(),
+            signature,
         };
         self.update(
             UpdateProposal {
@@ -130,16 +199,33 @@ impl Proposal {
 
         Ok(SignedProposal::insert(params, &client).await?)
     }
+
+    /// Deletes invalid and declined proposals last updated before `before` which are no longer referenced
+    /// by instructions, views, signatures or aggregate messages, returns number of deleted proposals
+    pub async fn delete_terminal(before: DateTime<Utc>, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "
+            DELETE FROM proposals p
+            WHERE p.status IN ('Invalid', 'Declined')
+            AND p.updated_at < $1
+            AND NOT EXISTS (SELECT 1 FROM instructions i WHERE i.proposal_id = p.id)
+            AND NOT EXISTS (SELECT 1 FROM views v WHERE v.proposal_id = p.id)
+            AND NOT EXISTS (SELECT 1 FROM signed_proposals sp WHERE sp.proposal_id = p.id)
+            AND NOT EXISTS (SELECT 1 FROM aggregate_signature_messages asm WHERE asm.proposal_id = p.id)";
+        let stmt = client.prepare_typed(QUERY, &[Type::TIMESTAMPTZ]).await?;
+        Ok(client.execute(&stmt, &[&before]).await?)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        db::models::AssetState,
+        db::models::{AggregateSignatureMessageStatus, AssetState},
+        identity::NodeIdentity,
         test::utils::{
-            builders::consensus::{ProposalBuilder, ViewBuilder},
+            builders::consensus::{AggregateSignatureMessageBuilder, ProposalBuilder, ViewBuilder},
             test_db_client,
+            Test,
         },
     };
 
@@ -169,18 +255,66 @@ mod test {
             .await
             .unwrap();
 
-        let proposals = Proposal::find_pending(&client).await.unwrap();
+        let proposals = Proposal::find_pending(None, &client).await.unwrap();
         assert_eq!(proposals, Some(proposal2));
     }
 
+    #[actix_rt::test]
+    async fn find_interrupted() {
+        let (client, _lock) = test_db_client().await;
+        let proposal = ProposalBuilder {
+            status: Some(ProposalStatus::Signed),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let proposal2 = ProposalBuilder {
+            status: Some(ProposalStatus::Signed),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let proposal3 = ProposalBuilder {
+            status: Some(ProposalStatus::Finalized),
+            ..ProposalBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        for proposal_id in &[proposal.id, proposal3.id] {
+            AggregateSignatureMessageBuilder {
+                proposal_id: Some(*proposal_id),
+                status: AggregateSignatureMessageStatus::Accepted,
+                ..AggregateSignatureMessageBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+        }
+        // proposal2 aggregate signature is not validated yet
+        AggregateSignatureMessageBuilder {
+            proposal_id: Some(proposal2.id),
+            ..AggregateSignatureMessageBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let interrupted = Proposal::find_interrupted(&client).await.unwrap();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].id, proposal.id);
+    }
+
     #[actix_rt::test]
     async fn create_partial_signature() {
         let (client, _lock) = test_db_client().await;
         let proposal = ProposalBuilder::default().build(&client).await.unwrap();
-        assert_eq!(
-            proposal.create_partial_signature().await.unwrap(),
-            "stub-signature".to_string()
-        );
+        let identity = Test::<NodeIdentity>::new();
+        let signature = proposal.create_partial_signature(&identity).await.unwrap();
+        let payload = serde_json::to_vec(&(proposal.id, &proposal.new_view)).unwrap();
+        assert!(NodeIdentity::verify(identity.public_key(), &signature, &payload));
     }
 
     #[actix_rt::test]
@@ -197,9 +331,13 @@ mod test {
     async fn sign() {
         let (client, _lock) = test_db_client().await;
         let proposal = ProposalBuilder::default().build(&client).await.unwrap();
-        let signed_proposal = proposal.sign(NodeID::stub(), &client).await.unwrap();
+        let signed_proposal = proposal
+            .sign(NodeID::stub(), "signature".into(), &client)
+            .await
+            .unwrap();
 
         assert_eq!(signed_proposal.proposal_id, proposal.id);
+        assert_eq!(signed_proposal.signature, "signature");
     }
 
     #[actix_rt::test]
@@ -232,4 +370,15 @@ mod test {
         let proposal = Proposal::load(proposal.id, &client).await.unwrap();
         assert_eq!(proposal.status, ProposalStatus::Signed);
     }
+
+    #[actix_rt::test]
+    async fn find_recent() {
+        let (client, _lock) = test_db_client().await;
+        let proposal = ProposalBuilder::default().build(&client).await.unwrap();
+        let proposal2 = ProposalBuilder::default().build(&client).await.unwrap();
+        let recent = Proposal::find_recent(10, &client).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].id, proposal2.id);
+        assert_eq!(recent[1].id, proposal.id);
+    }
 }
@@ -54,8 +54,9 @@ impl SignedProposal {
 
     /// Load signed proposal from database by ID
     pub async fn load(id: uuid::Uuid, client: &Client) -> Result<Self, DBError> {
-        let stmt = "SELECT * FROM signed_proposals WHERE id = $1";
-        let result = client.query_one(stmt, &[&id]).await?;
+        const QUERY: &'static str = "SELECT * FROM signed_proposals WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client.query_one(&stmt, &[&id]).await?;
         Ok(Self::from_row(result)?)
     }
 
@@ -75,21 +76,28 @@ impl SignedProposal {
         Ok(Self::from_row(row)?)
     }
 
-    pub async fn threshold_met(client: &Client) -> Result<HashMap<AssetID, Vec<SignedProposal>>, DBError> {
+    /// Assets partitioned to other workers than `worker` are skipped, see [crate::consensus::ClusterWorker]
+    pub async fn threshold_met(
+        worker: Option<&str>,
+        client: &Client,
+    ) -> Result<HashMap<AssetID, Vec<SignedProposal>>, DBError>
+    {
         // TODO: logic is currently hardcoded / stubbed for a committee of 1 so a single signed proposal meets the
         // threshold       we will need to iterate on this logic in the future to determine a viable threshold
         // dynamically by asset
-        let stmt = "
+        const QUERY: &'static str = "
             SELECT p.asset_id, sp.*
             FROM signed_proposals sp
             JOIN proposals p ON sp.proposal_id = p.id
             JOIN asset_states ast ON ast.asset_id = p.asset_id
             WHERE sp.status = 'Pending'
             AND ast.blocked_until <= now()
+            AND worker_owns_asset($1, ast.asset_id)
             ORDER BY p.asset_id
         ";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
         let mut signed_proposal_data: Vec<(AssetID, SignedProposal)> = Vec::new();
-        for row in client.query(stmt, &[]).await? {
+        for row in client.query(&stmt, &[&worker]).await? {
             signed_proposal_data.push((row.get(0), SignedProposal::from_row(row)?));
         }
 
@@ -106,9 +114,10 @@ impl SignedProposal {
 
     /// Load signed proposals from database by ProposalID
     pub async fn load_by_proposal_id(id: ProposalID, client: &Client) -> Result<Vec<Self>, DBError> {
-        let stmt = "SELECT * FROM signed_proposals WHERE proposal_id = $1::\"ProposalID\"";
+        const QUERY: &'static str = "SELECT * FROM signed_proposals WHERE proposal_id = $1::\"ProposalID\"";
+        let stmt = client.prepare(QUERY).await?;
         Ok(client
-            .query(stmt, &[&id])
+            .query(&stmt, &[&id])
             .await?
             .into_iter()
             .map(|row| Self::from_row(row))
@@ -128,6 +137,21 @@ impl SignedProposal {
             .await?;
         Ok(Self::from_row(row)?)
     }
+
+    /// Deletes Invalid signed proposals and signatures of finalized, invalid or declined proposals
+    /// last updated before `before`, returns number of deleted records
+    pub async fn delete_stale(before: DateTime<Utc>, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "
+            DELETE FROM signed_proposals sp
+            WHERE sp.updated_at < $1
+            AND (
+                sp.status = 'Invalid'
+                OR sp.proposal_id IS NULL
+                OR sp.proposal_id IN (SELECT id FROM proposals WHERE status IN ('Finalized', 'Invalid', 'Declined'))
+            )";
+        let stmt = client.prepare_typed(QUERY, &[Type::TIMESTAMPTZ]).await?;
+        Ok(client.execute(&stmt, &[&before]).await?)
+    }
 }
 
 #[cfg(test)]
@@ -166,7 +190,7 @@ mod test {
             .await
             .unwrap();
 
-        let signed_proposals = SignedProposal::threshold_met(&client).await.unwrap();
+        let signed_proposals = SignedProposal::threshold_met(None, &client).await.unwrap();
         let proposal = Proposal::load(signed_proposal2.proposal_id, &client).await.unwrap();
         assert_eq!(
             json!(signed_proposals),
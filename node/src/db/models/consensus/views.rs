@@ -4,15 +4,13 @@ use crate::{
 };
 use bytes::BytesMut;
 use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{collections::HashMap, error::Error};
 use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
-use tokio_postgres::{
-    types::{accepts, to_sql_checked, FromSql, IsNull, Json, ToSql, Type},
-    Client,
-};
+use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, Json, ToSql, Type};
 
 #[derive(Deserialize, Serialize, Clone, PostgresMapper, PartialEq, Debug)]
 #[pg_mapper(table = "views")]
@@ -53,6 +51,16 @@ pub struct UpdateView {
     pub proposal_id: Option<ProposalID>,
 }
 
+impl NewView {
+    /// Content of the view covered by signature, signature itself is excluded
+    pub fn signing_payload(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(&NewView {
+            signature: String::new(),
+            ..self.clone()
+        })
+    }
+}
+
 impl View {
     pub async fn invalidate(views: Vec<View>, client: &Client) -> Result<(), DBError> {
         let view_ids: Vec<uuid::Uuid> = views.into_iter().map(|s| s.id).collect();
@@ -62,22 +70,25 @@ impl View {
         Ok(())
     }
 
-    pub async fn threshold_met(client: &Client) -> Result<HashMap<AssetID, Vec<View>>, DBError> {
+    /// Assets partitioned to other workers than `worker` are skipped, see [crate::consensus::ClusterWorker]
+    pub async fn threshold_met(worker: Option<&str>, client: &Client) -> Result<HashMap<AssetID, Vec<View>>, DBError> {
         // TODO: logic is currently hardcoded / stubbed for a committee of 1 so a single view meets the
         // threshold... we will need to iterate on this logic in the future to determine a viable threshold
         // dynamically by asset
-        let stmt = "
+        const QUERY: &'static str = "
             SELECT v.*
             FROM views v
             JOIN asset_states ast ON ast.asset_id = v.asset_id
             WHERE v.status = 'Prepare'
             AND ast.blocked_until <= now()
+            AND worker_owns_asset($1, ast.asset_id)
             ORDER BY v.asset_id
         ";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
 
         let mut asset_id_view_mapping = HashMap::new();
         let views: Vec<View> = client
-            .query(stmt, &[])
+            .query(&stmt, &[&worker])
             .await?
             .into_iter()
             .map(|v| View::from_row(v))
@@ -173,15 +184,17 @@ impl View {
 
     /// Load view record
     pub async fn load(id: uuid::Uuid, client: &Client) -> Result<Self, DBError> {
-        let stmt = "SELECT * FROM views WHERE id = $1";
-        let result = client.query_one(stmt, &[&id]).await?;
+        const QUERY: &'static str = "SELECT * FROM views WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client.query_one(&stmt, &[&id]).await?;
         Ok(Self::from_row(result)?)
     }
 
     /// Load view record
     pub async fn load_for_proposal(id: ProposalID, client: &Client) -> Result<Self, DBError> {
-        let stmt = "SELECT * FROM views WHERE proposal_id = $1::\"ProposalID\"";
-        let result = client.query_one(stmt, &[&id]).await?;
+        const QUERY: &'static str = "SELECT * FROM views WHERE proposal_id = $1::\"ProposalID\"";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client.query_one(&stmt, &[&id]).await?;
         Ok(Self::from_row(result)?)
     }
 
@@ -201,6 +214,13 @@ impl View {
             .map(|row| View::from_row(row))
             .collect::<Result<Vec<_>, _>>()?)
     }
+
+    /// Deletes NotChosen and Invalid views last updated before `before`, returns number of deleted views
+    pub async fn delete_terminal(before: DateTime<Utc>, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "DELETE FROM views WHERE status IN ('NotChosen', 'Invalid') AND updated_at < $1";
+        let stmt = client.prepare_typed(QUERY, &[Type::TIMESTAMPTZ]).await?;
+        Ok(client.execute(&stmt, &[&before]).await?)
+    }
 }
 
 impl<'a> ToSql for NewView {
@@ -234,6 +254,8 @@ impl From<View> for NewView {
             append_only_state: AppendOnlyState {
                 asset_state: view.append_only_state.asset_state.to_owned(),
                 token_state: view.append_only_state.token_state.to_owned(),
+                tokens: view.append_only_state.tokens.to_owned(),
+                committee_handover: view.append_only_state.committee_handover.to_owned(),
             },
         }
     }
@@ -373,7 +395,7 @@ mod test {
             .await
             .unwrap();
 
-        let views = View::threshold_met(&client).await.unwrap();
+        let views = View::threshold_met(None, &client).await.unwrap();
         assert_eq!(json!(views), json!({ view2.asset_id.clone(): vec![view2] }));
     }
 
@@ -409,10 +431,7 @@ mod test {
             signature: "stub-signature".to_string(),
             instruction_set: Vec::new(),
             invalid_instruction_set: Vec::new(),
-            append_only_state: AppendOnlyState {
-                asset_state: Vec::new(),
-                token_state: Vec::new(),
-            },
+            append_only_state: AppendOnlyState::default(),
         };
         let view = View::insert(params, NewViewAdditionalParameters::default(), &client)
             .await
@@ -0,0 +1,227 @@
+use super::{AssetState, ContractPermissionRule, Token};
+use crate::{
+    db::utils::errors::DBError,
+    types::{AssetID, TokenID},
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+
+/// Rule permitting pubkeys to call asset contract
+///
+/// Contract without rules is open to any caller,
+/// otherwise caller has to match at least one of the contract rules
+#[derive(Debug, Clone, Serialize, PostgresMapper)]
+#[pg_mapper(table = "contract_permissions")]
+pub struct ContractPermission {
+    pub id: uuid::Uuid,
+    pub asset_id: AssetID,
+    pub contract_name: String,
+    pub rule: ContractPermissionRule,
+    /// Permitted pubkey for [ContractPermissionRule::PubKey] rule
+    pub pub_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewContractPermission {
+    pub asset_id: AssetID,
+    pub contract_name: String,
+    pub rule: ContractPermissionRule,
+    pub pub_key: Option<String>,
+}
+
+impl ContractPermission {
+    /// Add contract permission rule
+    pub async fn insert(params: NewContractPermission, client: &Client) -> Result<uuid::Uuid, DBError> {
+        if (params.rule == ContractPermissionRule::PubKey) != params.pub_key.is_some() {
+            return Err(DBError::bad_query("pub_key is required by PubKey rule only"));
+        }
+        const QUERY: &'static str = "INSERT INTO contract_permissions (asset_id, contract_name, rule, pub_key) VALUES \
+                                     ($1, $2, $3, $4) RETURNING id";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client
+            .query_one(&stmt, &[
+                &params.asset_id,
+                &params.contract_name,
+                &params.rule,
+                &params.pub_key,
+            ])
+            .await?;
+        Ok(result.get(0))
+    }
+
+    /// Remove contract permission rule
+    pub async fn delete(id: uuid::Uuid, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "DELETE FROM contract_permissions WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.execute(&stmt, &[&id]).await?)
+    }
+
+    /// Find permission rules of the asset
+    pub async fn find_by_asset_id(asset_id: &AssetID, client: &Client) -> Result<Vec<ContractPermission>, DBError> {
+        const QUERY: &'static str =
+            "SELECT * FROM contract_permissions WHERE asset_id = $1 ORDER BY contract_name, created_at";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&asset_id])
+            .await?
+            .into_iter()
+            .map(ContractPermission::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Find permission rules of the asset contract
+    pub async fn find_by_contract(
+        asset_id: &AssetID,
+        contract_name: &str,
+        client: &Client,
+    ) -> Result<Vec<ContractPermission>, DBError>
+    {
+        const QUERY: &'static str = "SELECT * FROM contract_permissions WHERE asset_id = $1 AND contract_name = $2";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&asset_id, &contract_name])
+            .await?
+            .into_iter()
+            .map(ContractPermission::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Checks caller is permitted to call the contract on the asset or token.
+    ///
+    /// Token owner is [Token::owner_pubkey] column maintained from `owner_pubkey` field of token data
+    pub async fn is_permitted(
+        caller: Option<&str>,
+        asset_id: &AssetID,
+        token_id: Option<&TokenID>,
+        contract_name: &str,
+        client: &Client,
+    ) -> Result<bool, DBError>
+    {
+        let rules = Self::find_by_contract(asset_id, contract_name, client).await?;
+        if rules.is_empty() {
+            return Ok(true);
+        }
+        let caller = match caller {
+            Some(caller) => caller,
+            None => return Ok(false),
+        };
+        for rule in rules {
+            let permitted = match rule.rule {
+                ContractPermissionRule::PubKey => rule.pub_key.as_deref() == Some(caller),
+                ContractPermissionRule::AssetIssuer => AssetState::find_by_asset_id(asset_id, client)
+                    .await?
+                    .map(|asset| asset.asset_issuer_pub_key == caller)
+                    .unwrap_or(false),
+                ContractPermissionRule::TokenOwner => match token_id {
+                    Some(token_id) => Token::find_by_token_id(token_id, client)
+                        .await?
+                        .map(|token| token.owner_pubkey().as_deref() == Some(caller))
+                        .unwrap_or(false),
+                    None => false,
+                },
+            };
+            if permitted {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{
+        builders::{AssetStateBuilder, TokenBuilder},
+        test_db_client,
+        Test,
+    };
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn crud() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let params = NewContractPermission {
+            asset_id: asset.asset_id.clone(),
+            contract_name: "issue_tokens".into(),
+            rule: ContractPermissionRule::AssetIssuer,
+            pub_key: None,
+        };
+        let id = ContractPermission::insert(params.clone(), &client).await.unwrap();
+        let rules = ContractPermission::find_by_asset_id(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, id);
+        assert_eq!(rules[0].rule, ContractPermissionRule::AssetIssuer);
+
+        let invalid = NewContractPermission {
+            rule: ContractPermissionRule::PubKey,
+            ..params
+        };
+        assert!(ContractPermission::insert(invalid, &client).await.is_err());
+
+        assert_eq!(ContractPermission::delete(id, &client).await.unwrap(), 1);
+        let rules = ContractPermission::find_by_asset_id(&asset.asset_id, &client).await.unwrap();
+        assert!(rules.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn is_permitted() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let issuer = asset.asset_issuer_pub_key.as_str();
+        let token = TokenBuilder {
+            asset_state_id: Some(asset.id),
+            initial_data_json: json!({"owner_pubkey": "owner"}),
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        // Contracts without rules are open
+        assert!(
+            ContractPermission::is_permitted(None, &asset.asset_id, None, "issue_tokens", &client)
+                .await
+                .unwrap()
+        );
+
+        for (rule, pub_key, contract) in &[
+            (ContractPermissionRule::AssetIssuer, None, "issue_tokens"),
+            (ContractPermissionRule::TokenOwner, None, "transfer_token"),
+            (ContractPermissionRule::PubKey, Some("operator"), "transfer_token"),
+        ] {
+            ContractPermission::insert(
+                NewContractPermission {
+                    asset_id: asset.asset_id.clone(),
+                    contract_name: contract.to_string(),
+                    rule: *rule,
+                    pub_key: pub_key.map(String::from),
+                },
+                &client,
+            )
+            .await
+            .unwrap();
+        }
+
+        let token_id = Some(&token.token_id);
+        for (caller, token_id, contract, expected) in &[
+            (Some(issuer), None, "issue_tokens", true),
+            (Some("owner"), None, "issue_tokens", false),
+            (None, None, "issue_tokens", false),
+            (Some("owner"), token_id, "transfer_token", true),
+            (Some("operator"), token_id, "transfer_token", true),
+            (Some(issuer), token_id, "transfer_token", false),
+        ] {
+            let permitted = ContractPermission::is_permitted(*caller, &asset.asset_id, *token_id, contract, &client)
+                .await
+                .unwrap();
+            assert_eq!(permitted, *expected, "{:?} calling {}", caller, contract);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{db::utils::errors::DBError, types::TemplateID};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+
+/// Operator switch enabling or disabling template contract
+///
+/// Switch overrides `template.contracts.<name>.enabled` config and survives node restarts
+#[derive(Debug, Clone, Serialize, PostgresMapper)]
+#[pg_mapper(table = "contract_switches")]
+pub struct ContractSwitch {
+    pub template_id: TemplateID,
+    pub contract_name: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ContractSwitch {
+    /// Enable or disable contract of the template
+    pub async fn set(
+        template_id: &TemplateID,
+        contract_name: &str,
+        enabled: bool,
+        client: &Client,
+    ) -> Result<ContractSwitch, DBError>
+    {
+        const QUERY: &'static str = "INSERT INTO contract_switches (template_id, contract_name, enabled) VALUES ($1, \
+                                     $2, $3) ON CONFLICT (template_id, contract_name) DO UPDATE SET enabled = \
+                                     EXCLUDED.enabled, updated_at = now() RETURNING *";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[template_id, &contract_name, &enabled]).await?;
+        Ok(ContractSwitch::from_row(row)?)
+    }
+
+    /// Remove switch, contract falls back to config
+    pub async fn reset(template_id: &TemplateID, contract_name: &str, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "DELETE FROM contract_switches WHERE template_id = $1 AND contract_name = $2";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.execute(&stmt, &[template_id, &contract_name]).await?)
+    }
+
+    /// Switch of the template contract if it was set
+    pub async fn find(
+        template_id: &TemplateID,
+        contract_name: &str,
+        client: &Client,
+    ) -> Result<Option<ContractSwitch>, DBError>
+    {
+        const QUERY: &'static str = "SELECT * FROM contract_switches WHERE template_id = $1 AND contract_name = $2";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(match client.query_opt(&stmt, &[template_id, &contract_name]).await? {
+            Some(row) => Some(ContractSwitch::from_row(row)?),
+            None => None,
+        })
+    }
+
+    /// Switches of all contracts of the template
+    pub async fn find_by_template(template_id: &TemplateID, client: &Client) -> Result<Vec<ContractSwitch>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM contract_switches WHERE template_id = $1 ORDER BY contract_name";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[template_id])
+            .await?
+            .into_iter()
+            .map(ContractSwitch::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{test_db_client, Test};
+
+    #[actix_rt::test]
+    async fn crud() {
+        let (client, _lock) = test_db_client().await;
+        let template_id = Test::<TemplateID>::new();
+        assert!(ContractSwitch::find(&template_id, "sell_token", &client)
+            .await
+            .unwrap()
+            .is_none());
+
+        let switch = ContractSwitch::set(&template_id, "sell_token", false, &client).await.unwrap();
+        assert!(!switch.enabled);
+        let switch = ContractSwitch::set(&template_id, "sell_token", true, &client).await.unwrap();
+        assert!(switch.enabled);
+        ContractSwitch::set(&template_id, "issue_tokens", false, &client).await.unwrap();
+
+        let switches = ContractSwitch::find_by_template(&template_id, &client).await.unwrap();
+        assert_eq!(switches.len(), 2);
+        assert_eq!(switches[0].contract_name, "issue_tokens");
+        assert!(!switches[0].enabled);
+
+        assert_eq!(ContractSwitch::reset(&template_id, "sell_token", &client).await.unwrap(), 1);
+        assert!(ContractSwitch::find(&template_id, "sell_token", &client)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
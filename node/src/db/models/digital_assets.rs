@@ -1,3 +1,4 @@
+use super::RaidStatus;
 use crate::{
     db::utils::errors::DBError,
     types::{CommitteeMode, TemplateID},
@@ -15,6 +16,9 @@ pub struct DigitalAsset {
     pub committee_mode: CommitteeMode,
     pub fqdn: Option<String>,
     pub raid_id: Option<String>,
+    /// Result of RAID TXT record lookup of `fqdn`, see [crate::raid]
+    pub raid_status: RaidStatus,
+    pub raid_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -51,6 +55,20 @@ impl DigitalAsset {
         Ok(result.get(0))
     }
 
+    /// Stores result of RAID verification
+    pub async fn set_raid_status(
+        id: uuid::Uuid,
+        status: RaidStatus,
+        client: &Client,
+    ) -> Result<DigitalAsset, DBError>
+    {
+        const QUERY: &'static str = "UPDATE digital_assets SET raid_status = $2, raid_verified_at = NOW(), updated_at = \
+                                     NOW() WHERE id = $1 RETURNING *";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[&id, &status]).await?;
+        Ok(DigitalAsset::from_row(row)?)
+    }
+
     /// Find digital asset records by template id
     pub async fn find_by_template_id(template_id: &TemplateID, client: &Client) -> Result<Vec<Self>, DBError> {
         let stmt = "SELECT * FROM digital_assets WHERE template_type = $1";
@@ -91,11 +109,15 @@ mod test {
         let digital_asset_id = DigitalAsset::insert(params, &client).await?;
         let digital_asset = DigitalAsset::load(digital_asset_id, &client).await?;
         assert_eq!(digital_asset.template_type, 1);
+        assert_eq!(digital_asset.raid_status, RaidStatus::Unverified);
         assert_eq!(digital_asset.committee_mode, CommitteeMode::Public {
             node_threshold: 5,
             minimum_collateral: 1000,
             node_selection_strategy: NodeSelectionStrategy::RegisterAll
         });
+        let digital_asset = DigitalAsset::set_raid_status(digital_asset_id, RaidStatus::Verified, &client).await?;
+        assert_eq!(digital_asset.raid_status, RaidStatus::Verified);
+        assert!(digital_asset.raid_verified_at.is_some());
 
         Ok(())
     }
@@ -68,10 +68,16 @@ macro_rules! string_enum {
     }
 }
 
-string_enum! { AccessResource [Api, Wallet]}
+string_enum! { AccessResource [Api, Wallet, Admin]}
 string_enum! { AggregateSignatureMessageStatus [Pending, Rejected, Accepted]}
-string_enum! { AssetStatus [Active, Retired]}
+string_enum! { AssetStatus [Active, Retired, Frozen]}
+string_enum! { AuditAction [ContractCall, AssetCreate, AssetRetire, AssetUpdate, ContractSwitch]}
+string_enum! { ContractPermissionRule [AssetIssuer, TokenOwner, PubKey]}
+string_enum! { FeeStatus [Charged, Unpaid]}
+string_enum! { RaidStatus [Unverified, Verified, Failed]}
+string_enum! { StateSnapshotKind [Token, Asset]}
 string_enum! { TokenStatus [Available, Active, Locked, Retired]}
+string_enum! { WebhookDeliveryStatus [Pending, Delivered, Failed]}
 #[doc(hide)]
 string_enum! { ProposalStatus [Pending, Signed, Invalid, Declined, Finalized]}
 #[doc(hide)]
@@ -93,6 +99,12 @@ impl Default for AssetStatus {
     }
 }
 
+impl Default for AuditAction {
+    fn default() -> Self {
+        Self::ContractCall
+    }
+}
+
 impl Default for InstructionStatus {
     fn default() -> Self {
         Self::Scheduled
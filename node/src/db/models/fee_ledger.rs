@@ -0,0 +1,209 @@
+use super::FeeStatus;
+use crate::{
+    db::utils::errors::DBError,
+    types::{AssetID, InstructionID, TemplateID},
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::types::Type;
+
+/// Fee charged for committed instruction, see [crate::fees]
+#[derive(Debug, Clone, Serialize, PostgresMapper)]
+#[pg_mapper(table = "fee_ledger")]
+pub struct FeeLedger {
+    pub id: uuid::Uuid,
+    pub instruction_id: InstructionID,
+    pub asset_id: AssetID,
+    pub asset_issuer_pub_key: String,
+    /// Signer of the instruction, fee is Unpaid if instruction was not signed
+    pub payer_pubkey: Option<String>,
+    pub template_id: TemplateID,
+    pub contract_name: String,
+    pub amount: i64,
+    pub status: FeeStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewFeeLedger {
+    pub instruction_id: InstructionID,
+    pub asset_id: AssetID,
+    pub asset_issuer_pub_key: String,
+    pub payer_pubkey: Option<String>,
+    pub template_id: TemplateID,
+    pub contract_name: String,
+    pub amount: i64,
+}
+
+/// Fees accumulated by instructions of the assets of the issuer
+#[derive(Debug, Clone, Serialize, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "fee_ledger")]
+pub struct IssuerFees {
+    pub asset_issuer_pub_key: String,
+    /// Total amount debited from payer wallets
+    pub charged: i64,
+    /// Total amount payers could not cover
+    pub unpaid: i64,
+    pub instructions: i64,
+}
+
+impl FeeLedger {
+    /// Debits payer wallet and records the fee, payer wallet is debited only if it covers the whole amount,
+    /// otherwise fee is recorded as Unpaid. Returns None if the instruction was already charged.
+    pub async fn charge(params: NewFeeLedger, client: &Client) -> Result<Option<FeeLedger>, DBError> {
+        const QUERY: &'static str = "WITH debit AS (
+                UPDATE wallet SET balance = balance - $7, updated_at = NOW()
+                WHERE pub_key = $4 AND balance >= $7
+                    AND NOT EXISTS (SELECT 1 FROM fee_ledger WHERE instruction_id = $1)
+                RETURNING id
+            )
+            INSERT INTO fee_ledger (instruction_id, asset_id, asset_issuer_pub_key, payer_pubkey, template_id,
+                contract_name, amount, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7,
+                CASE WHEN EXISTS (SELECT 1 FROM debit) THEN 'Charged' ELSE 'Unpaid' END)
+            ON CONFLICT (instruction_id) DO NOTHING RETURNING *";
+        let stmt = client
+            .prepare_typed(QUERY, &[
+                Type::UUID,
+                Type::BPCHAR,
+                Type::TEXT,
+                Type::TEXT,
+                Type::INT8,
+                Type::TEXT,
+                Type::INT8,
+            ])
+            .await?;
+        let row = client
+            .query_opt(&stmt, &[
+                &params.instruction_id,
+                &params.asset_id,
+                &params.asset_issuer_pub_key,
+                &params.payer_pubkey,
+                &params.template_id,
+                &params.contract_name,
+                &params.amount,
+            ])
+            .await?;
+        Ok(row.map(FeeLedger::from_row).transpose()?)
+    }
+
+    /// Fees of the asset, most recent first
+    pub async fn find_by_asset_id(asset_id: &AssetID, client: &Client) -> Result<Vec<FeeLedger>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM fee_ledger WHERE asset_id = $1 ORDER BY created_at DESC";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&asset_id])
+            .await?
+            .into_iter()
+            .map(FeeLedger::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Accumulated fees grouped by asset issuer, all issuers if `issuer` is None
+    pub async fn totals_by_issuer(issuer: Option<&str>, client: &Client) -> Result<Vec<IssuerFees>, DBError> {
+        const QUERY: &'static str = "SELECT asset_issuer_pub_key,
+                COALESCE(SUM(amount) FILTER (WHERE status = 'Charged'), 0)::BIGINT AS charged,
+                COALESCE(SUM(amount) FILTER (WHERE status = 'Unpaid'), 0)::BIGINT AS unpaid,
+                COUNT(*) AS instructions
+            FROM fee_ledger WHERE ($1 IS NULL OR asset_issuer_pub_key = $1)
+            GROUP BY asset_issuer_pub_key ORDER BY asset_issuer_pub_key";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
+        Ok(client
+            .query(&stmt, &[&issuer])
+            .await?
+            .into_iter()
+            .map(IssuerFees::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::wallet::{NewWallet, SelectWallet, Wallet},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetStateBuilder},
+            test_db_client,
+        },
+    };
+
+    const PAYER: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
+
+    #[actix_rt::test]
+    async fn charge() {
+        let (mut client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let transaction = client.transaction().await.unwrap();
+        let wallet = Wallet::insert(
+            NewWallet {
+                pub_key: PAYER.to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+        wallet.set_balance(100, &client).await.unwrap();
+
+        let mut fees = vec![];
+        for amount in &[30, 30, 200] {
+            let instruction = InstructionBuilder {
+                asset_id: Some(asset.asset_id.clone()),
+                ..InstructionBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+            fees.push(NewFeeLedger {
+                instruction_id: instruction.id,
+                asset_id: asset.asset_id.clone(),
+                asset_issuer_pub_key: asset.asset_issuer_pub_key.clone(),
+                payer_pubkey: Some(PAYER.to_owned()),
+                template_id: instruction.template_id,
+                contract_name: instruction.contract_name,
+                amount: *amount,
+            });
+        }
+
+        let charged = FeeLedger::charge(fees[0].clone(), &client).await.unwrap().unwrap();
+        assert_eq!(charged.status, FeeStatus::Charged);
+        assert_eq!(charged.amount, 30);
+        // same instruction is never charged twice
+        assert!(FeeLedger::charge(fees[0].clone(), &client).await.unwrap().is_none());
+        let charged = FeeLedger::charge(fees[1].clone(), &client).await.unwrap().unwrap();
+        assert_eq!(charged.status, FeeStatus::Charged);
+        let unpaid = FeeLedger::charge(fees[2].clone(), &client).await.unwrap().unwrap();
+        assert_eq!(unpaid.status, FeeStatus::Unpaid);
+
+        let wallet = Wallet::select(
+            SelectWallet {
+                pub_key: Some(PAYER.to_owned()),
+                ..SelectWallet::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(wallet[0].balance, 40);
+        assert_eq!(FeeLedger::find_by_asset_id(&asset.asset_id, &client).await.unwrap().len(), 3);
+
+        let totals = FeeLedger::totals_by_issuer(Some(&asset.asset_issuer_pub_key), &client)
+            .await
+            .unwrap();
+        assert_eq!(totals, vec![IssuerFees {
+            asset_issuer_pub_key: asset.asset_issuer_pub_key.clone(),
+            charged: 60,
+            unpaid: 200,
+            instructions: 3,
+        }]);
+        assert_eq!(FeeLedger::totals_by_issuer(None, &client).await.unwrap().len(), 1);
+        assert!(FeeLedger::totals_by_issuer(Some("other"), &client)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
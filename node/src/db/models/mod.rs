@@ -1,11 +1,39 @@
-pub use self::{access::*, asset_states::*, digital_assets::*, enums::*, tokens::*};
+pub use self::{
+    access::*,
+    asset_states::*,
+    audit_log::*,
+    committee_members::*,
+    contract_permissions::*,
+    contract_switches::*,
+    digital_assets::*,
+    enums::*,
+    fee_ledger::*,
+    node_reputation::*,
+    state_snapshots::*,
+    state_sync::*,
+    token_reservations::*,
+    tokens::*,
+    webhooks::*,
+    workers::*,
+};
 
 pub mod access;
 pub mod asset_states;
+pub mod audit_log;
+pub mod committee_members;
 #[doc(hide)]
 pub mod consensus;
+pub mod contract_permissions;
+pub mod contract_switches;
 pub mod digital_assets;
 pub mod enums;
+pub mod fee_ledger;
+pub mod node_reputation;
+pub mod state_snapshots;
+pub mod state_sync;
+pub mod token_reservations;
 pub mod tokens;
 #[doc(hide)]
 pub mod wallet;
+pub mod webhooks;
+pub mod workers;
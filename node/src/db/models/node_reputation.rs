@@ -0,0 +1,144 @@
+use crate::db::utils::errors::DBError;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+
+/// Score of nodes without recorded misbehavior
+pub const REPUTATION_SCORE_MAX: i64 = 100;
+
+/// Misbehavior of a committee member observed by the node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Misbehavior {
+    /// Proposal failed confirmation or came from a node which is not the leader
+    InvalidProposal,
+    /// Signature of the aggregate signature message does not match the proposal
+    BadSignature,
+    /// Committee member did not sign the proposal before the leader reached threshold
+    MissedRound,
+}
+
+impl Misbehavior {
+    /// Score decrease per occurrence
+    pub fn penalty(&self) -> i64 {
+        match self {
+            Misbehavior::InvalidProposal => 10,
+            Misbehavior::BadSignature => 20,
+            Misbehavior::MissedRound => 1,
+        }
+    }
+}
+
+/// Misbehavior counters and reputation score of a node, nodes without record have [REPUTATION_SCORE_MAX]
+#[derive(Debug, Clone, Serialize, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "node_reputation")]
+pub struct NodeReputation {
+    pub node_id: String,
+    pub invalid_proposals: i64,
+    pub bad_signatures: i64,
+    pub missed_rounds: i64,
+    pub score: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NodeReputation {
+    /// Records misbehavior of the node decreasing its score
+    pub async fn record(node_id: &str, misbehavior: Misbehavior, client: &Client) -> Result<Self, DBError> {
+        const QUERY: &'static str = "
+            INSERT INTO node_reputation (node_id, invalid_proposals, bad_signatures, missed_rounds, score)
+            VALUES ($1, $2, $3, $4, GREATEST($6 - $5, 0))
+            ON CONFLICT (node_id) DO UPDATE SET
+                invalid_proposals = node_reputation.invalid_proposals + EXCLUDED.invalid_proposals,
+                bad_signatures = node_reputation.bad_signatures + EXCLUDED.bad_signatures,
+                missed_rounds = node_reputation.missed_rounds + EXCLUDED.missed_rounds,
+                score = GREATEST(node_reputation.score - $5, 0),
+                updated_at = now()
+            RETURNING *";
+        let count = |kind: Misbehavior| if misbehavior == kind { 1i64 } else { 0 };
+        let stmt = client.prepare(QUERY).await?;
+        let row = client
+            .query_one(&stmt, &[
+                &node_id,
+                &count(Misbehavior::InvalidProposal),
+                &count(Misbehavior::BadSignature),
+                &count(Misbehavior::MissedRound),
+                &misbehavior.penalty(),
+                &REPUTATION_SCORE_MAX,
+            ])
+            .await?;
+        Ok(Self::from_row(row)?)
+    }
+
+    /// Nodes with recorded misbehavior, lowest score first
+    pub async fn find_all(client: &Client) -> Result<Vec<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM node_reputation ORDER BY score, node_id";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[])
+            .await?
+            .into_iter()
+            .map(Self::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn find_by_node_id(node_id: &str, client: &Client) -> Result<Option<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM node_reputation WHERE node_id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_opt(&stmt, &[&node_id]).await?.map(Self::from_row).transpose()?)
+    }
+
+    /// Scores of the nodes, nodes without misbehavior have [REPUTATION_SCORE_MAX]
+    pub async fn scores(node_ids: &[String], client: &Client) -> Result<HashMap<String, i64>, DBError> {
+        const QUERY: &'static str = "SELECT node_id, score FROM node_reputation WHERE node_id = ANY($1)";
+        let stmt = client.prepare(QUERY).await?;
+        let mut scores: HashMap<String, i64> = node_ids.iter().map(|id| (id.clone(), REPUTATION_SCORE_MAX)).collect();
+        for row in client.query(&stmt, &[&node_ids]).await? {
+            scores.insert(row.get(0), row.get(1));
+        }
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::test_db_client;
+
+    #[actix_rt::test]
+    async fn record_misbehavior() {
+        let (client, _lock) = test_db_client().await;
+        let reputation = NodeReputation::record("node1", Misbehavior::MissedRound, &client)
+            .await
+            .unwrap();
+        assert_eq!(reputation.missed_rounds, 1);
+        assert_eq!(reputation.score, 99);
+
+        for _ in 0..5 {
+            NodeReputation::record("node1", Misbehavior::BadSignature, &client)
+                .await
+                .unwrap();
+        }
+        let reputation = NodeReputation::record("node1", Misbehavior::InvalidProposal, &client)
+            .await
+            .unwrap();
+        assert_eq!(reputation.invalid_proposals, 1);
+        assert_eq!(reputation.bad_signatures, 5);
+        assert_eq!(reputation.missed_rounds, 1);
+        assert_eq!(reputation.score, 0);
+
+        NodeReputation::record("node2", Misbehavior::InvalidProposal, &client)
+            .await
+            .unwrap();
+        let all = NodeReputation::find_all(&client).await.unwrap();
+        let node_ids: Vec<_> = all.iter().map(|r| r.node_id.as_str()).collect();
+        assert_eq!(node_ids, vec!["node1", "node2"]);
+        let scores = NodeReputation::scores(&["node2".into(), "node3".into()], &client)
+            .await
+            .unwrap();
+        assert_eq!(scores["node2"], 90);
+        assert_eq!(scores["node3"], REPUTATION_SCORE_MAX);
+        assert!(NodeReputation::find_by_node_id("node3", &client).await.unwrap().is_none());
+    }
+}
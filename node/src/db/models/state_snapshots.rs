@@ -0,0 +1,216 @@
+use super::StateSnapshotKind;
+use crate::db::utils::errors::DBError;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+
+/// Compacted append only history of a token or asset
+///
+/// Snapshot keeps the latest state among rolled up records, while `checkpoint_hash`
+/// is SHA256 of previous snapshot hash followed by ids and state data of every rolled up record,
+/// so history removed by pruning can still be verified against other nodes
+#[derive(Debug, Clone, Serialize, PostgresMapper)]
+#[pg_mapper(table = "state_snapshots")]
+pub struct StateSnapshot {
+    pub id: uuid::Uuid,
+    pub kind: StateSnapshotKind,
+    /// [crate::types::TokenID] or [crate::types::AssetID]
+    pub entity_id: String,
+    /// Number of append only records rolled up into the snapshot
+    pub rows_count: i64,
+    pub status: String,
+    pub state_data_json: Value,
+    pub checkpoint_hash: String,
+    pub first_created_at: DateTime<Utc>,
+    pub last_created_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl StateSnapshot {
+    /// Rolls up append only records created before `before` into snapshots, one per token or asset,
+    /// skipping the latest `keep_checkpoints` records and current state of every token or asset
+    pub async fn compact(
+        kind: StateSnapshotKind,
+        before: DateTime<Utc>,
+        keep_checkpoints: i64,
+        client: &Client,
+    ) -> Result<Vec<StateSnapshot>, DBError>
+    {
+        const QUERY_TOKENS: &'static str = "
+            WITH ranked AS (
+                SELECT id, row_number() OVER (PARTITION BY token_id ORDER BY created_at DESC) AS checkpoint
+                FROM token_state_append_only
+            ), pruned AS (
+                DELETE FROM token_state_append_only tsao
+                USING ranked
+                WHERE tsao.id = ranked.id
+                    AND ranked.checkpoint > $2
+                    AND tsao.created_at < $1
+                    AND NOT EXISTS (SELECT 1 FROM token_current_states tcs WHERE tcs.append_only_id = tsao.id)
+                RETURNING tsao.token_id AS entity_id, tsao.id, tsao.status, tsao.state_data_json, tsao.created_at
+            )";
+        const QUERY_ASSETS: &'static str = "
+            WITH ranked AS (
+                SELECT id, row_number() OVER (PARTITION BY asset_id ORDER BY created_at DESC) AS checkpoint
+                FROM asset_state_append_only
+            ), pruned AS (
+                DELETE FROM asset_state_append_only asao
+                USING ranked
+                WHERE asao.id = ranked.id
+                    AND ranked.checkpoint > $2
+                    AND asao.created_at < $1
+                    AND NOT EXISTS (SELECT 1 FROM asset_current_states acs WHERE acs.append_only_id = asao.id)
+                RETURNING asao.asset_id AS entity_id, asao.id, asao.status, asao.state_data_json, asao.created_at
+            )";
+        const QUERY_SNAPSHOTS: &'static str = ", previous AS (
+                SELECT DISTINCT ON (entity_id) entity_id, checkpoint_hash
+                FROM state_snapshots
+                WHERE kind = $3
+                ORDER BY entity_id, last_created_at DESC
+            )
+            INSERT INTO state_snapshots (
+                kind,
+                entity_id,
+                rows_count,
+                status,
+                state_data_json,
+                checkpoint_hash,
+                first_created_at,
+                last_created_at
+            )
+            SELECT
+                $3,
+                pruned.entity_id,
+                COUNT(*),
+                (array_agg(pruned.status ORDER BY pruned.created_at DESC))[1],
+                (array_agg(pruned.state_data_json ORDER BY pruned.created_at DESC))[1],
+                encode(digest(
+                    COALESCE(previous.checkpoint_hash, '') ||
+                    string_agg(pruned.id::TEXT || pruned.state_data_json::TEXT, '' ORDER BY pruned.created_at),
+                    'sha256'
+                ), 'hex'),
+                MIN(pruned.created_at),
+                MAX(pruned.created_at)
+            FROM pruned
+            LEFT JOIN previous ON previous.entity_id = pruned.entity_id::TEXT
+            GROUP BY pruned.entity_id, previous.checkpoint_hash
+            RETURNING *";
+        let query = match kind {
+            StateSnapshotKind::Token => format!("{}{}", QUERY_TOKENS, QUERY_SNAPSHOTS),
+            StateSnapshotKind::Asset => format!("{}{}", QUERY_ASSETS, QUERY_SNAPSHOTS),
+        };
+        let stmt = client.prepare(&query).await?;
+        // latest record is always kept as it is current state of token or asset
+        let keep_checkpoints = keep_checkpoints.max(1);
+        Ok(client
+            .query(&stmt, &[&before, &keep_checkpoints, &kind])
+            .await?
+            .into_iter()
+            .map(StateSnapshot::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Snapshots of token or asset, oldest first
+    pub async fn find_by_entity(
+        kind: StateSnapshotKind,
+        entity_id: &str,
+        client: &Client,
+    ) -> Result<Vec<StateSnapshot>, DBError>
+    {
+        const QUERY: &'static str =
+            "SELECT * FROM state_snapshots WHERE kind = $1 AND entity_id = $2 ORDER BY last_created_at";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&kind, &entity_id])
+            .await?
+            .into_iter()
+            .map(StateSnapshot::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{AssetState, NewAssetStateAppendOnly, Token, UpdateToken},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetStateBuilder, TokenBuilder},
+            test_db_client,
+        },
+    };
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn compact_tokens() {
+        let (client, _lock) = test_db_client().await;
+        let mut token = TokenBuilder::default().build(&client).await.unwrap();
+        let untouched = TokenBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        for step in 0..5 {
+            let update = UpdateToken {
+                append_state_data_json: Some(json!({ "step": step })),
+                ..UpdateToken::default()
+            };
+            token = token.update(update, &instruction, &client).await.unwrap();
+        }
+        let current = Token::load(token.id, &client).await.unwrap();
+
+        let snapshots = StateSnapshot::compact(StateSnapshotKind::Token, Utc::now(), 2, &client)
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].entity_id, token.token_id.to_string());
+        assert_eq!(snapshots[0].rows_count, 3);
+        assert_eq!(snapshots[0].state_data_json, json!({ "step": 2 }));
+        assert_eq!(Token::load(token.id, &client).await.unwrap(), current);
+        assert_eq!(Token::load(untouched.id, &client).await.unwrap(), untouched);
+
+        // nothing older than `before`
+        let before = snapshots[0].first_created_at;
+        let res = StateSnapshot::compact(StateSnapshotKind::Token, before, 0, &client);
+        assert!(res.await.unwrap().is_empty());
+
+        // current state is preserved even if no checkpoints are kept
+        let snapshots = StateSnapshot::compact(StateSnapshotKind::Token, Utc::now(), 0, &client)
+            .await
+            .unwrap();
+        assert_eq!(snapshots[0].rows_count, 1);
+        assert_eq!(Token::load(token.id, &client).await.unwrap(), current);
+
+        let history = StateSnapshot::find_by_entity(StateSnapshotKind::Token, &token.token_id.to_string(), &client)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].checkpoint_hash.len(), 64);
+        assert_ne!(history[0].checkpoint_hash, history[1].checkpoint_hash);
+    }
+
+    #[actix_rt::test]
+    async fn compact_assets() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        for step in 0..3 {
+            let state = NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                state_data_json: json!({ "step": step }),
+                instruction_id: instruction.id,
+                ..NewAssetStateAppendOnly::default()
+            };
+            AssetState::store_append_only_state(&state, &client).await.unwrap();
+        }
+
+        let snapshots = StateSnapshot::compact(StateSnapshotKind::Asset, Utc::now(), 1, &client)
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].kind, StateSnapshotKind::Asset);
+        assert_eq!(snapshots[0].rows_count, 2);
+        assert_eq!(snapshots[0].state_data_json, json!({ "step": 1 }));
+        let asset = AssetState::load(asset.id, &client).await.unwrap();
+        assert_eq!(asset.additional_data_json, json!({ "step": 2 }));
+    }
+}
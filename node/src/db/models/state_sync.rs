@@ -0,0 +1,270 @@
+use super::{AssetState, AssetStatus, NewAssetStateAppendOnly, NewTokenStateAppendOnly, Token, TokenStatus};
+use crate::{
+    db::utils::errors::DBError,
+    types::{AssetID, InstructionID, TokenID},
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{types::Type, Row};
+
+/// Append only records of the asset and its tokens, exchanged by nodes catching up, see [crate::sync]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct StateDelta {
+    pub asset_state: Vec<NewAssetStateAppendOnly>,
+    pub token_state: Vec<NewTokenStateAppendOnly>,
+    /// Creation time of the latest record, next delta starts after it
+    pub cursor: Option<DateTime<Utc>>,
+    /// Number of records in the delta
+    pub records: i64,
+}
+
+impl StateDelta {
+    /// Current state of the asset and its tokens, i.e. the latest record of every entity
+    pub async fn checkpoint(asset_id: &AssetID, client: &Client) -> Result<Self, DBError> {
+        const QUERY: &'static str = "
+            SELECT 'Asset' AS kind, asao.asset_id, NULL::CHAR(96) AS token_id, asao.instruction_id, asao.status,
+                asao.state_data_json, asao.created_at
+            FROM asset_state_append_only asao
+            JOIN asset_current_states acs ON acs.append_only_id = asao.id
+            WHERE acs.asset_id = $1
+            UNION ALL
+            SELECT 'Token', NULL, tsao.token_id, tsao.instruction_id, tsao.status, tsao.state_data_json, tsao.created_at
+            FROM token_state_append_only tsao
+            JOIN token_current_states tcs ON tcs.append_only_id = tsao.id
+            JOIN tokens t ON t.token_id = tcs.token_id
+            JOIN asset_states ast ON ast.id = t.asset_state_id
+            WHERE ast.asset_id = $1
+            ORDER BY created_at";
+        let stmt = client.prepare(QUERY).await?;
+        Self::from_rows(client.query(&stmt, &[asset_id]).await?)
+    }
+
+    /// Records of the asset and its tokens created after `since`, oldest first, at most `limit` records
+    pub async fn since(
+        asset_id: &AssetID,
+        since: DateTime<Utc>,
+        limit: i64,
+        client: &Client,
+    ) -> Result<Self, DBError>
+    {
+        const QUERY: &'static str = "
+            SELECT 'Asset' AS kind, asao.asset_id, NULL::CHAR(96) AS token_id, asao.instruction_id, asao.status,
+                asao.state_data_json, asao.created_at
+            FROM asset_state_append_only asao
+            WHERE asao.asset_id = $1 AND asao.created_at > $2
+            UNION ALL
+            SELECT 'Token', NULL, tsao.token_id, tsao.instruction_id, tsao.status, tsao.state_data_json, tsao.created_at
+            FROM token_state_append_only tsao
+            JOIN tokens t ON t.token_id = tsao.token_id
+            JOIN asset_states ast ON ast.id = t.asset_state_id
+            WHERE ast.asset_id = $1 AND tsao.created_at > $2
+            ORDER BY created_at
+            LIMIT $3";
+        let stmt = client.prepare(QUERY).await?;
+        Self::from_rows(client.query(&stmt, &[asset_id, &since, &limit]).await?)
+    }
+
+    /// Whole history of the asset and its tokens, oldest first
+    pub async fn history(asset_id: &AssetID, client: &Client) -> Result<Self, DBError> {
+        const QUERY: &'static str = "
+            SELECT 'Asset' AS kind, asao.asset_id, NULL::CHAR(96) AS token_id, asao.instruction_id, asao.status,
+                asao.state_data_json, asao.created_at
+            FROM asset_state_append_only asao
+            WHERE asao.asset_id = $1
+            UNION ALL
+            SELECT 'Token', NULL, tsao.token_id, tsao.instruction_id, tsao.status, tsao.state_data_json, tsao.created_at
+            FROM token_state_append_only tsao
+            JOIN tokens t ON t.token_id = tsao.token_id
+            JOIN asset_states ast ON ast.id = t.asset_state_id
+            WHERE ast.asset_id = $1
+            ORDER BY created_at";
+        let stmt = client.prepare(QUERY).await?;
+        Self::from_rows(client.query(&stmt, &[asset_id]).await?)
+    }
+
+    /// Removes records of `instruction_ids` from the asset history, current state falls back to the latest
+    /// remaining records, returns removed records oldest first
+    ///
+    /// Only meant to run within a transaction which is rolled back, see [crate::template::replay]
+    pub async fn rewind(
+        asset_id: &AssetID,
+        instruction_ids: &[InstructionID],
+        client: &Client,
+    ) -> Result<Self, DBError>
+    {
+        const CLEAR_CURRENT: &'static str = "
+            WITH removed AS (
+                SELECT id FROM token_state_append_only WHERE instruction_id::uuid = ANY ($1)
+            ), removed_assets AS (
+                DELETE FROM asset_current_states
+                WHERE append_only_id IN (SELECT id FROM asset_state_append_only WHERE instruction_id::uuid = ANY ($1))
+            )
+            DELETE FROM token_current_states WHERE append_only_id IN (SELECT id FROM removed)";
+        const REMOVE: &'static str = "
+            WITH assets AS (
+                DELETE FROM asset_state_append_only WHERE instruction_id::uuid = ANY ($1)
+                RETURNING 'Asset' AS kind, asset_id, NULL::CHAR(96) AS token_id, instruction_id, status,
+                    state_data_json, created_at
+            ), tokens AS (
+                DELETE FROM token_state_append_only WHERE instruction_id::uuid = ANY ($1)
+                RETURNING 'Token' AS kind, NULL::CHAR(64) AS asset_id, token_id, instruction_id, status,
+                    state_data_json, created_at
+            )
+            SELECT * FROM assets UNION ALL SELECT * FROM tokens ORDER BY created_at";
+        const RESTORE_TOKENS: &'static str = "
+            INSERT INTO token_current_states (token_id, append_only_id, status, state_data_json, owner_pubkey, version,
+                created_at)
+            SELECT DISTINCT ON (tsao.token_id) tsao.token_id, tsao.id, tsao.status, tsao.state_data_json,
+                tsao.owner_pubkey, tsao.version, tsao.created_at
+            FROM token_state_append_only tsao
+            JOIN tokens t ON t.token_id = tsao.token_id
+            JOIN asset_states ast ON ast.id = t.asset_state_id
+            WHERE ast.asset_id = $1
+            ORDER BY tsao.token_id, tsao.created_at DESC
+            ON CONFLICT (token_id) DO NOTHING";
+        const RESTORE_ASSET: &'static str = "
+            INSERT INTO asset_current_states (asset_id, append_only_id, status, state_data_json, created_at)
+            SELECT asset_id, id, status, state_data_json, created_at
+            FROM asset_state_append_only
+            WHERE asset_id = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            ON CONFLICT (asset_id) DO NOTHING";
+        let ids: Vec<uuid::Uuid> = instruction_ids.iter().map(|id| id.0).collect();
+        let stmt = client.prepare_typed(CLEAR_CURRENT, &[Type::UUID_ARRAY]).await?;
+        client.execute(&stmt, &[&ids]).await?;
+        let stmt = client.prepare_typed(REMOVE, &[Type::UUID_ARRAY]).await?;
+        let removed = Self::from_rows(client.query(&stmt, &[&ids]).await?)?;
+        let stmt = client.prepare(RESTORE_TOKENS).await?;
+        client.execute(&stmt, &[asset_id]).await?;
+        let stmt = client.prepare(RESTORE_ASSET).await?;
+        client.execute(&stmt, &[asset_id]).await?;
+        Ok(removed)
+    }
+
+    fn from_rows(rows: Vec<Row>) -> Result<Self, DBError> {
+        let mut delta = Self::default();
+        for row in rows {
+            match row.try_get::<_, &str>("kind")? {
+                "Asset" => delta.asset_state.push(NewAssetStateAppendOnly {
+                    asset_id: row.try_get("asset_id")?,
+                    instruction_id: row.try_get("instruction_id")?,
+                    status: row.try_get::<_, AssetStatus>("status")?,
+                    state_data_json: row.try_get("state_data_json")?,
+                }),
+                _ => delta.token_state.push(NewTokenStateAppendOnly {
+                    token_id: row.try_get("token_id")?,
+                    instruction_id: row.try_get("instruction_id")?,
+                    status: row.try_get::<_, TokenStatus>("status")?,
+                    state_data_json: row.try_get("state_data_json")?,
+                    expected_version: None,
+                }),
+            }
+            delta.cursor = Some(row.try_get("created_at")?);
+            delta.records += 1;
+        }
+        Ok(delta)
+    }
+
+    /// Tokens referenced by token records
+    pub fn token_ids(&self) -> Vec<TokenID> {
+        let mut token_ids: Vec<_> = self.token_state.iter().map(|state| state.token_id.clone()).collect();
+        token_ids.sort_by(|a, b| a.to_string().cmp(&b.to_string()));
+        token_ids.dedup();
+        token_ids
+    }
+
+    /// Appends records to local state, tokens have to be stored already
+    pub async fn store(&self, client: &Client) -> Result<(), DBError> {
+        for state in self.asset_state.iter() {
+            AssetState::store_append_only_state(state, client).await?;
+        }
+        for state in self.token_state.iter() {
+            Token::store_append_only_state(state, client).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{
+        builders::{consensus::InstructionBuilder, AssetStateBuilder, TokenBuilder},
+        test_db_client,
+        Test,
+    };
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn checkpoint_and_since() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let token = TokenBuilder {
+            asset_state_id: Some(asset.id),
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let start = Utc::now() - Duration::seconds(1);
+        for value in 1..=2 {
+            Token::store_append_only_state(
+                &NewTokenStateAppendOnly {
+                    token_id: token.token_id.clone(),
+                    instruction_id: instruction.id,
+                    status: TokenStatus::Active,
+                    state_data_json: json!({ "value": value }),
+                    expected_version: None,
+                },
+                &client,
+            )
+            .await
+            .unwrap();
+        }
+        AssetState::store_append_only_state(
+            &NewAssetStateAppendOnly {
+                asset_id: asset.asset_id.clone(),
+                instruction_id: instruction.id,
+                status: AssetStatus::Active,
+                state_data_json: json!({ "asset": true }),
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+
+        let checkpoint = StateDelta::checkpoint(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(checkpoint.records, 2);
+        assert_eq!(checkpoint.token_state[0].state_data_json, json!({ "value": 2 }));
+        assert_eq!(checkpoint.asset_state[0].state_data_json, json!({ "asset": true }));
+        assert_eq!(checkpoint.token_ids(), vec![token.token_id.clone()]);
+        let history = StateDelta::history(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(history.records, 3);
+        assert_eq!(history.token_state[0].state_data_json, json!({ "value": 1 }));
+
+        let delta = StateDelta::since(&asset.asset_id, start, 2, &client).await.unwrap();
+        assert_eq!(delta.records, 2);
+        assert_eq!(delta.token_state.len(), 2);
+        let rest = StateDelta::since(&asset.asset_id, delta.cursor.unwrap(), 2, &client)
+            .await
+            .unwrap();
+        assert_eq!(rest.records, 1);
+        assert_eq!(rest.asset_state.len(), 1);
+        let empty = StateDelta::since(&asset.asset_id, rest.cursor.unwrap(), 2, &client)
+            .await
+            .unwrap();
+        assert_eq!(empty.records, 0);
+        assert_eq!(empty.cursor, None);
+    }
+}
@@ -0,0 +1,150 @@
+use super::consensus::Instruction;
+use crate::{
+    db::utils::errors::DBError,
+    types::{InstructionID, TokenID},
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use std::time::Duration;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::types::Type;
+
+/// Token reserved by the instruction until released or expired, see
+/// [TokenInstructionContext::reserve_token](crate::template::TokenInstructionContext::reserve_token)
+#[derive(Debug, Clone, Serialize, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "token_reservations")]
+pub struct TokenReservation {
+    pub id: uuid::Uuid,
+    pub token_id: TokenID,
+    pub instruction_id: InstructionID,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TokenReservation {
+    /// Reserves the token for `ttl`, taking over expired reservation or extending the one held by the instruction,
+    /// None if the token is reserved by another instruction
+    pub async fn reserve(
+        token_id: &TokenID,
+        instruction_id: InstructionID,
+        ttl: Duration,
+        client: &Client,
+    ) -> Result<Option<Self>, DBError>
+    {
+        const QUERY: &'static str = "
+            INSERT INTO token_reservations (token_id, instruction_id, expires_at)
+            VALUES ($1, $2, now() + make_interval(secs => $3))
+            ON CONFLICT (token_id) DO UPDATE SET
+                instruction_id = EXCLUDED.instruction_id,
+                expires_at = EXCLUDED.expires_at,
+                created_at = now()
+            WHERE token_reservations.expires_at <= now() OR token_reservations.instruction_id = EXCLUDED.instruction_id
+            RETURNING *";
+        let stmt = client
+            .prepare_typed(QUERY, &[TokenID::SQL_TYPE, InstructionID::SQL_TYPE, Type::FLOAT8])
+            .await?;
+        let row = client
+            .query_opt(&stmt, &[token_id, &instruction_id, &ttl.as_secs_f64()])
+            .await?;
+        Ok(match row {
+            Some(row) => Some(Self::from_row(row)?),
+            None => None,
+        })
+    }
+
+    /// Releases reservation of the token held by the instruction, false if there was none
+    pub async fn release(token_id: &TokenID, instruction_id: InstructionID, client: &Client) -> Result<bool, DBError> {
+        const QUERY: &'static str = "DELETE FROM token_reservations WHERE token_id = $1 AND instruction_id = $2";
+        let stmt = client
+            .prepare_typed(QUERY, &[TokenID::SQL_TYPE, InstructionID::SQL_TYPE])
+            .await?;
+        Ok(client.execute(&stmt, &[token_id, &instruction_id]).await? > 0)
+    }
+
+    /// Releases all reservations held by the instruction
+    pub async fn release_by_instruction(instruction_id: InstructionID, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "DELETE FROM token_reservations WHERE instruction_id = $1";
+        let stmt = client.prepare_typed(QUERY, &[InstructionID::SQL_TYPE]).await?;
+        Ok(client.execute(&stmt, &[&instruction_id]).await?)
+    }
+
+    /// Reservation of the token unless it has expired
+    pub async fn find_active(token_id: &TokenID, client: &Client) -> Result<Option<Self>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM token_reservations WHERE token_id = $1 AND expires_at > now()";
+        let stmt = client.prepare_typed(QUERY, &[TokenID::SQL_TYPE]).await?;
+        Ok(match client.query_opt(&stmt, &[token_id]).await? {
+            Some(row) => Some(Self::from_row(row)?),
+            None => None,
+        })
+    }
+
+    /// Reservation is held by the instruction itself or by its parent, so subinstructions of the reserving
+    /// instruction can update the token
+    pub fn is_held_by(&self, instruction: &Instruction) -> bool {
+        self.instruction_id == instruction.id || Some(self.instruction_id) == instruction.parent_id
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{
+        builders::{consensus::InstructionBuilder, TokenBuilder},
+        test_db_client,
+    };
+
+    #[actix_rt::test]
+    async fn reserve() {
+        let (client, _lock) = test_db_client().await;
+        let token = TokenBuilder::default().build(&client).await.unwrap();
+        let first = InstructionBuilder::default().build(&client).await.unwrap();
+        let second = InstructionBuilder::default().build(&client).await.unwrap();
+        let ttl = Duration::from_secs(60);
+
+        let reservation = TokenReservation::reserve(&token.token_id, first.id, ttl, &client)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(reservation.is_held_by(&first));
+        assert!(!reservation.is_held_by(&second));
+        // unique per token, held by the first instruction
+        assert!(TokenReservation::reserve(&token.token_id, second.id, ttl, &client)
+            .await
+            .unwrap()
+            .is_none());
+        // extended by the holder
+        let extended = TokenReservation::reserve(&token.token_id, first.id, ttl * 2, &client)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(extended.id, reservation.id);
+        assert!(extended.expires_at > reservation.expires_at);
+
+        assert!(!TokenReservation::release(&token.token_id, second.id, &client).await.unwrap());
+        assert!(TokenReservation::release(&token.token_id, first.id, &client).await.unwrap());
+        assert!(TokenReservation::find_active(&token.token_id, &client)
+            .await
+            .unwrap()
+            .is_none());
+
+        // expired reservation is taken over
+        TokenReservation::reserve(&token.token_id, first.id, Duration::from_millis(0), &client)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(TokenReservation::find_active(&token.token_id, &client)
+            .await
+            .unwrap()
+            .is_none());
+        let reservation = TokenReservation::reserve(&token.token_id, second.id, ttl, &client)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reservation.instruction_id, second.id);
+        assert_eq!(
+            TokenReservation::release_by_instruction(second.id, &client).await.unwrap(),
+            1
+        );
+    }
+}
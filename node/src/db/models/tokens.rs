@@ -1,23 +1,22 @@
 use super::{consensus::Instruction, TokenStatus};
 use crate::{
-    db::utils::errors::DBError,
-    types::{InstructionID, TokenID},
+    db::utils::{errors::DBError, validation::ValidationErrors},
+    types::{AssetID, InstructionID, TokenID},
 };
 use bytes::BytesMut;
 use chrono::{DateTime, Utc};
 use deadpool_postgres::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::{
-    json,
-    map::Map,
-    Value::{self, Object},
-};
+use serde_json::{json, Value};
 use std::error::Error;
 use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
-use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, Json, ToSql, Type};
+use tokio_postgres::{
+    error::SqlState,
+    types::{accepts, to_sql_checked, FromSql, IsNull, Json, ToSql, Type},
+};
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, PostgresMapper)]
-#[pg_mapper(table = "tokens_view")]
+#[pg_mapper(table = "tokens_current_view")]
 pub struct Token {
     pub id: uuid::Uuid,
     pub issue_number: i64,
@@ -29,6 +28,10 @@ pub struct Token {
     // TODO: switch view to use latest of append only or tokens updated_at
     pub updated_at: DateTime<Utc>,
     pub additional_data_json: Value,
+    /// Current owner, maintained from `owner_pubkey` field of token data on every write
+    pub owner_pubkey: Option<String>,
+    /// Number of states appended to the token, expected by [Token::update] to detect concurrent updates
+    pub version: i64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,6 +39,7 @@ pub struct DisplayToken {
     pub token_id: TokenID,
     pub issue_number: i64,
     pub status: TokenStatus,
+    pub owner_pubkey: Option<String>,
     pub initial_data_json: Value,
     pub additional_data_json: Value,
     pub created_at: DateTime<Utc>,
@@ -48,6 +52,7 @@ impl From<Token> for DisplayToken {
             token_id: token.token_id,
             issue_number: token.issue_number,
             status: token.status,
+            owner_pubkey: token.owner_pubkey,
             initial_data_json: token.initial_data_json,
             additional_data_json: token.additional_data_json,
             created_at: token.created_at,
@@ -57,7 +62,7 @@ impl From<Token> for DisplayToken {
 }
 
 /// Query parameters for adding new token record
-#[derive(Default, Clone, Debug)]
+#[derive(PartialEq, Deserialize, Serialize, Default, Clone, Debug)]
 pub struct NewToken {
     pub token_id: TokenID,
     pub asset_state_id: uuid::Uuid,
@@ -71,6 +76,23 @@ pub struct NewTokenStateAppendOnly {
     pub instruction_id: InstructionID,
     pub status: TokenStatus,
     pub state_data_json: Value,
+    /// Version of the token the state was built on, state is rejected if the token was updated since,
+    /// see [Token::store_append_only_state]. Replicated state is stored on top of the current version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<i64>,
+}
+
+/// Query parameters for searching tokens, all filters are optional
+#[derive(Default, Clone, Debug)]
+pub struct SelectToken {
+    /// Current owner of the token, see [Token::owner_pubkey]
+    pub owner_pubkey: Option<String>,
+    pub status: Option<TokenStatus>,
+    pub asset_id: Option<AssetID>,
+    /// Tokens created or changed state since the time
+    pub updated_since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 /// Query parameters for adding new token state append only
@@ -87,8 +109,9 @@ impl Token {
             INSERT INTO tokens (
                 asset_state_id,
                 initial_data_json,
-                token_id
-            ) VALUES ($1, $2, $3) RETURNING id";
+                token_id,
+                owner_pubkey
+            ) VALUES ($1, $2, $3, $2::JSONB->>'owner_pubkey') RETURNING id";
         let stmt = client.prepare(QUERY).await?;
         let result = client
             .query_one(&stmt, &[
@@ -101,52 +124,202 @@ impl Token {
         Ok(result.get(0))
     }
 
-    /// Update token into database
+    /// Add token records with a single multi-row INSERT, returns ids in the order of `tokens`
+    ///
+    /// Being a single statement batch is atomic: either all tokens are added or none,
+    /// issue numbers are assigned sequentially per asset by trigger
+    pub async fn insert_batch(tokens: Vec<NewToken>, client: &Client) -> Result<Vec<uuid::Uuid>, DBError> {
+        const QUERY: &'static str = "
+            INSERT INTO tokens (
+                asset_state_id,
+                initial_data_json,
+                token_id,
+                owner_pubkey
+            )
+            SELECT asset_state_id, initial_data_json, token_id, initial_data_json->>'owner_pubkey'
+            FROM UNNEST($1::UUID[], $2::JSONB[], $3::BPCHAR[]) WITH ORDINALITY
+                AS batch(asset_state_id, initial_data_json, token_id, position)
+            ORDER BY position
+            RETURNING id";
+        if tokens.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut asset_state_ids = Vec::with_capacity(tokens.len());
+        let mut initial_data = Vec::with_capacity(tokens.len());
+        let mut token_ids = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            asset_state_ids.push(token.asset_state_id);
+            initial_data.push(token.initial_data_json);
+            token_ids.push(token.token_id);
+        }
+        let stmt = client.prepare(QUERY).await?;
+        let rows = client.query(&stmt, &[&asset_state_ids, &initial_data, &token_ids]).await?;
+        Ok(rows.into_iter().map(|row| row.get(0)).collect())
+    }
+
+    /// Update token into database, returns updated token
     ///
     /// Merges subset of fields with UpdateToken:
     /// - status
-    /// - additional_data_json merged with UpdateToken::append_state_data_json
-    // TODO: this is very expensive - think on optimization later
-    pub async fn update(
-        self,
-        data: UpdateToken,
-        instruction: &Instruction,
-        client: &Client,
-    ) -> Result<uuid::Uuid, DBError>
-    {
-        let mut token = Self::find_by_token_id(&self.token_id, &client)
-            .await?
-            .ok_or(DBError::NotFound)?;
-        let state_data_json: Value = match data.append_state_data_json {
-            Some(Object(mut update)) => {
-                let mut obj = Map::<String, Value>::new();
-                if let Some(previous) = token.additional_data_json.as_object_mut() {
-                    obj.append(previous);
-                }
-                obj.append(&mut update);
-                obj.into()
+    /// - additional_data_json merged with UpdateToken::append_state_data_json,
+    /// top-level keys of appended object replace previous ones (including nulls and nested objects),
+    /// non-object append keeps previous data as is
+    ///
+    /// Merge happens within a single statement, where current state trigger has not fired yet,
+    /// so updated token is assembled from tokens table and the inserted state.
+    /// Owner is taken from merged data, falling back to the initial owner
+    ///
+    /// Update is optimistically locked: state is appended only on top of `self.version`,
+    /// [DBError::Conflict] is returned if the token was updated since it was loaded,
+    /// caller should reload the token and retry or reject the instruction
+    pub async fn update(self, data: UpdateToken, instruction: &Instruction, client: &Client) -> Result<Token, DBError> {
+        const QUERY: &'static str = "
+            WITH current AS (
+                SELECT token_id, status, additional_data_json, version FROM tokens_current_view WHERE token_id = $1
+            ), merged AS (
+                SELECT
+                    token_id,
+                    status,
+                    version,
+                    CASE
+                        WHEN jsonb_typeof($2::JSONB) IS DISTINCT FROM 'object' THEN additional_data_json
+                        WHEN jsonb_typeof(additional_data_json) = 'object' THEN additional_data_json || $2::JSONB
+                        ELSE $2::JSONB
+                    END AS state_data_json
+                FROM current
+                WHERE version = $5
+            ), appended AS (
+                INSERT INTO token_state_append_only (
+                    token_id,
+                    state_data_json,
+                    instruction_id,
+                    status,
+                    owner_pubkey,
+                    version
+                )
+                SELECT
+                    merged.token_id,
+                    merged.state_data_json,
+                    $3,
+                    COALESCE($4::TEXT, merged.status),
+                    COALESCE(merged.state_data_json->>'owner_pubkey', t.owner_pubkey),
+                    merged.version + 1
+                FROM merged
+                JOIN tokens t ON t.token_id = merged.token_id
+                RETURNING token_id, state_data_json, status, owner_pubkey, version
+            )
+            SELECT
+                t.id,
+                t.issue_number,
+                t.token_id,
+                t.asset_state_id,
+                t.initial_data_json,
+                t.created_at,
+                t.updated_at,
+                appended.state_data_json AS additional_data_json,
+                appended.status,
+                appended.owner_pubkey,
+                appended.version
+            FROM tokens t
+            JOIN appended ON t.token_id = appended.token_id";
+        let stmt = client.prepare(QUERY).await?;
+        let res = client
+            .query_opt(&stmt, &[
+                &self.token_id,
+                &data.append_state_data_json,
+                &instruction.id,
+                &data.status,
+                &self.version,
+            ])
+            .await;
+        let conflict = || DBError::conflict(&format!("token {} was updated concurrently", self.token_id));
+        let row = match res {
+            Ok(Some(row)) => row,
+            // concurrent writer appended the same version first
+            Err(err) if err.code() == Some(&SqlState::UNIQUE_VIOLATION) => return Err(conflict()),
+            Err(err) => return Err(err.into()),
+            Ok(None) => match Token::find_by_token_id(&self.token_id, client).await? {
+                Some(_) => return Err(conflict()),
+                None => return Err(DBError::NotFound),
             },
-            _ => token.additional_data_json.clone(),
         };
+        Ok(Token::from_row(row)?)
+    }
+
+    /// Applies update in memory with the same merge semantics as [Token::update],
+    /// returns updated token along with the state to be appended once instruction is committed
+    pub fn append_state(self, data: UpdateToken, instruction_id: InstructionID) -> (Token, NewTokenStateAppendOnly) {
+        let mut token = self;
+        match (data.append_state_data_json, &mut token.additional_data_json) {
+            (Some(Value::Object(append)), Value::Object(current)) => current.extend(append),
+            (Some(append @ Value::Object(_)), current) => *current = append,
+            _ => {},
+        }
+        if let Some(status) = data.status {
+            token.status = status;
+        }
+        if let Some(owner_pubkey) = token.additional_data_json.get("owner_pubkey").and_then(Value::as_str) {
+            token.owner_pubkey = Some(owner_pubkey.into());
+        }
+        let expected_version = token.version;
+        token.version += 1;
         let state = NewTokenStateAppendOnly {
             token_id: token.token_id.clone(),
-            instruction_id: instruction.id,
-            status: data.status.unwrap_or_else(|| token.status.clone()),
-            state_data_json,
+            instruction_id,
+            status: token.status,
+            state_data_json: token.additional_data_json.clone(),
+            expected_version: Some(expected_version),
         };
-        Ok(Self::store_append_only_state(&state, client).await?)
+        (token, state)
+    }
+
+    /// Token as it would be once `state` built by [Token::append_state] is stored
+    pub fn with_state(self, state: &NewTokenStateAppendOnly) -> Token {
+        let mut token = self;
+        token.status = state.status;
+        token.additional_data_json = state.state_data_json.clone();
+        if let Some(owner_pubkey) = token.additional_data_json.get("owner_pubkey").and_then(Value::as_str) {
+            token.owner_pubkey = Some(owner_pubkey.into());
+        }
+        token.version = state.expected_version.unwrap_or(token.version) + 1;
+        token
+    }
+
+    /// Owner of the token, templates keep it in `owner_pubkey` field of token data
+    pub fn owner_pubkey(&self) -> Option<String> {
+        self.owner_pubkey.clone()
+    }
+
+    /// Validates ownership transfer of the token to `new_owner` requested by `caller`:
+    /// caller should be the current owner, new owner should differ from it
+    /// and token should be neither locked nor retired
+    pub fn validate_transfer(&self, caller: &str, new_owner: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.owner_pubkey.as_deref() != Some(caller) {
+            errors.append_validation_error("owner", "owner_pubkey", "caller is not the owner of the token");
+        }
+        if new_owner.trim().is_empty() {
+            errors.append_validation_error("required", "new_owner", "new owner should not be empty");
+        } else if self.owner_pubkey.as_deref() == Some(new_owner) {
+            errors.append_validation_error("same_owner", "new_owner", "token is already owned by new owner");
+        }
+        if self.status == TokenStatus::Locked || self.status == TokenStatus::Retired {
+            errors.append_validation_error("status", "status", "locked or retired token can't be transferred");
+        }
+        errors.validate()
     }
 
     /// Load token record
     pub async fn load(id: uuid::Uuid, client: &Client) -> Result<Token, DBError> {
-        let stmt = "SELECT * FROM tokens_view WHERE id = $1";
-        let result = client.query_one(stmt, &[&id]).await?;
+        const QUERY: &'static str = "SELECT * FROM tokens_current_view WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        let result = client.query_one(&stmt, &[&id]).await?;
         Ok(Token::from_row(result)?)
     }
 
     /// Find token record by token id
     pub async fn find_by_token_id(token_id: &TokenID, client: &Client) -> Result<Option<Token>, DBError> {
-        const QUERY: &'static str = "SELECT * FROM tokens_view WHERE token_id = $1";
+        const QUERY: &'static str = "SELECT * FROM tokens_current_view WHERE token_id = $1";
         let stmt = client.prepare(QUERY).await?;
         let result = client.query_opt(&stmt, &[&token_id]).await?;
         Ok(result.map(Self::from_row).transpose()?)
@@ -154,7 +327,7 @@ impl Token {
 
     /// Find token records by asset state id
     pub async fn find_by_asset_state_id(asset_state_id: uuid::Uuid, client: &Client) -> Result<Vec<Token>, DBError> {
-        const QUERY: &'static str = "SELECT * FROM tokens_view WHERE asset_state_id = $1";
+        const QUERY: &'static str = "SELECT * FROM tokens_current_view WHERE asset_state_id = $1";
         let stmt = client.prepare(QUERY).await?;
         let results = client.query(&stmt, &[&asset_state_id]).await?;
         Ok(results
@@ -163,9 +336,87 @@ impl Token {
             .collect::<Result<Vec<_>, _>>()?)
     }
 
+    /// Number of tokens issued for the asset
+    pub async fn count_by_asset_state_id(asset_state_id: uuid::Uuid, client: &Client) -> Result<i64, DBError> {
+        const QUERY: &'static str = "SELECT COUNT(*) FROM tokens WHERE asset_state_id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_one(&stmt, &[&asset_state_id]).await?.get(0))
+    }
+
+    /// Number of tokens of the asset currently owned by `owner_pubkey`, narrowed down as by [Token::search]
+    pub async fn count_by_owner(
+        asset_state_id: uuid::Uuid,
+        owner_pubkey: &str,
+        client: &Client,
+    ) -> Result<i64, DBError>
+    {
+        const QUERY: &'static str = "
+            SELECT COUNT(*) FROM tokens_current_view
+            WHERE asset_state_id = $1
+            AND token_id IN (
+                SELECT token_id FROM token_current_states WHERE owner_pubkey = $2
+                UNION
+                SELECT token_id FROM tokens WHERE owner_pubkey = $2
+            )
+            AND owner_pubkey = $2";
+        let stmt = client.prepare_typed(QUERY, &[Type::UUID, Type::TEXT]).await?;
+        Ok(client.query_one(&stmt, &[&asset_state_id, &owner_pubkey]).await?.get(0))
+    }
+
+    /// Search tokens by [`SelectToken`], ordered by creation time
+    ///
+    /// Owner subqueries narrow candidates down via owner_pubkey indexes,
+    /// the final condition matches the current owner only
+    pub async fn search(params: SelectToken, client: &Client) -> Result<Vec<Token>, DBError> {
+        const QUERY: &'static str = "
+            SELECT * FROM tokens_current_view
+            WHERE ($1::BPCHAR IS NULL OR asset_state_id = (SELECT id FROM asset_states WHERE asset_id = $1))
+            AND ($2::TEXT IS NULL OR (
+                token_id IN (
+                    SELECT token_id FROM token_current_states WHERE owner_pubkey = $2
+                    UNION
+                    SELECT token_id FROM tokens WHERE owner_pubkey = $2
+                )
+                AND owner_pubkey = $2
+            ))
+            AND ($3::TEXT IS NULL OR status = $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR updated_at >= $4 OR token_id IN (
+                SELECT token_id FROM token_current_states WHERE created_at >= $4
+            ))
+            ORDER BY created_at, id
+            LIMIT $5 OFFSET $6";
+        let stmt = client
+            .prepare_typed(QUERY, &[
+                AssetID::SQL_TYPE,
+                Type::TEXT,
+                Type::TEXT,
+                Type::TIMESTAMPTZ,
+                Type::INT8,
+                Type::INT8,
+            ])
+            .await?;
+        Ok(client
+            .query(&stmt, &[
+                &params.asset_id,
+                &params.owner_pubkey,
+                &params.status,
+                &params.updated_since,
+                &params.limit,
+                &params.offset,
+            ])
+            .await?
+            .into_iter()
+            .map(Token::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
     /// Store append only state
     ///
-    /// NOTE: This call will not merge new values provided, they are stored as is
+    /// NOTE: This call will not merge new values provided, they are stored as is,
+    /// owner is taken from the state data falling back to the initial owner
+    ///
+    /// State with `expected_version` is optimistically locked the same way as [Token::update],
+    /// [DBError::Conflict] is returned if the token was updated since the state was built
     pub async fn store_append_only_state(
         params: &NewTokenStateAppendOnly,
         client: &Client,
@@ -176,19 +427,37 @@ impl Token {
                 token_id,
                 state_data_json,
                 instruction_id,
-                status
-            ) VALUES ($1, $2, $3, $4) RETURNING id";
+                status,
+                owner_pubkey,
+                version
+            )
+            SELECT
+                $1, $2, $3, $4,
+                COALESCE($2::JSONB->>'owner_pubkey', (SELECT owner_pubkey FROM tokens WHERE token_id = $1)),
+                current.version + 1
+            FROM (
+                SELECT COALESCE(MAX(version), 0) AS version FROM token_state_append_only WHERE token_id = $1
+            ) current
+            WHERE $5::BIGINT IS NULL OR current.version = $5
+            RETURNING id";
         let stmt = client.prepare(QUERY).await?;
-        let result = client
-            .query_one(&stmt, &[
+        let res = client
+            .query_opt(&stmt, &[
                 &params.token_id,
                 &params.state_data_json,
                 &params.instruction_id,
                 &params.status,
+                &params.expected_version,
             ])
-            .await?;
-
-        Ok(result.get(0))
+            .await;
+        let conflict = || DBError::conflict(&format!("token {} was updated concurrently", params.token_id));
+        match res {
+            Ok(Some(row)) => Ok(row.get(0)),
+            Ok(None) => Err(conflict()),
+            // concurrent writer appended the same version first
+            Err(err) if err.code() == Some(&SqlState::UNIQUE_VIOLATION) => Err(conflict()),
+            Err(err) => Err(err.into()),
+        }
     }
 }
 
@@ -216,6 +485,7 @@ impl<'a> FromSql<'a> for NewTokenStateAppendOnly {
 mod test {
     use super::*;
     use crate::{
+        api::errors::ErrorCode,
         db::models::{AssetState, InstructionStatus},
         test::utils::{
             builders::{consensus::InstructionBuilder, AssetStateBuilder, TokenBuilder},
@@ -265,6 +535,55 @@ mod test {
         assert_eq!(token.issue_number, 1);
     }
 
+    #[actix_rt::test]
+    async fn insert_batch() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        TokenBuilder {
+            asset_state_id: Some(asset.id),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let tokens: Vec<_> = (0..3)
+            .map(|i| NewToken {
+                asset_state_id: asset.id,
+                initial_data_json: json!({ "index": i }),
+                token_id: Test::from_asset(&asset.asset_id),
+            })
+            .collect();
+        let ids = Token::insert_batch(tokens.clone(), &client).await.unwrap();
+        assert_eq!(ids.len(), 3);
+        for (i, (id, params)) in ids.into_iter().zip(tokens).enumerate() {
+            let token = Token::load(id, &client).await.unwrap();
+            assert_eq!(token.token_id, params.token_id);
+            assert_eq!(token.initial_data_json, json!({ "index": i }));
+            assert_eq!(token.issue_number, i as i64 + 2);
+        }
+        assert!(Token::insert_batch(vec![], &client).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn insert_batch_atomic() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let token = NewToken {
+            asset_state_id: asset.id,
+            token_id: Test::from_asset(&asset.asset_id),
+            ..NewToken::default()
+        };
+        let unique = NewToken {
+            token_id: Test::from_asset(&asset.asset_id),
+            ..token.clone()
+        };
+        let res = Token::insert_batch(vec![unique, token.clone(), token], &client).await;
+        assert!(res.is_err());
+        let tokens = Token::find_by_asset_state_id(asset.id, &client).await.unwrap();
+        assert!(tokens.is_empty());
+    }
+
     #[actix_rt::test]
     async fn duplicate_token_id() {
         let (client, _lock) = test_db_client().await;
@@ -364,6 +683,7 @@ mod test {
                 state_data_json: state_data_json.clone(),
                 status: token.status,
                 instruction_id: instruction.id.clone(),
+                expected_version: None,
             },
             &client,
         )
@@ -379,6 +699,7 @@ mod test {
                 state_data_json: state_data_json.clone(),
                 status: token.status,
                 instruction_id: instruction.id,
+                expected_version: None,
             },
             &client,
         )
@@ -409,7 +730,7 @@ mod test {
         .unwrap();
 
         let update = UpdateToken::default();
-        token.clone().update(update, &instruction, &client).await.unwrap();
+        let token = token.update(update, &instruction, &client).await.unwrap();
         let token2 = Token::load(token_id.clone(), &client).await.unwrap();
         assert_eq!(token.id, token2.id);
         assert_eq!(token.status, token2.status);
@@ -453,4 +774,316 @@ mod test {
         assert_eq!(token2.status, TokenStatus::Retired);
         assert_eq!(token2.additional_data_json, token.additional_data_json);
     }
+
+    #[actix_rt::test]
+    async fn update_merge_semantics() {
+        let (client, _lock) = test_db_client().await;
+        let token: Token = TokenBuilder {
+            initial_data_json: json!({"nested": {"a": 1, "b": 2}, "keep": "value", "nulled": 1}),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let asset = AssetState::load(token.asset_state_id, &client).await.unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        // top-level keys are replaced: nested objects are not merged, nulls are stored
+        let update = UpdateToken {
+            append_state_data_json: Some(json!({"nested": {"c": 3}, "nulled": null, "new": [1, 2]})),
+            ..UpdateToken::default()
+        };
+        let (applied, state) = token.clone().append_state(update.clone(), instruction.id);
+        let mut updated = token.clone().update(update, &instruction, &client).await.unwrap();
+        let expected = json!({"nested": {"c": 3}, "keep": "value", "nulled": null, "new": [1, 2]});
+        assert_eq!(updated.additional_data_json, expected);
+        assert_eq!(updated, Token::load(token.id, &client).await.unwrap());
+        // in memory update matches stored one
+        assert_eq!(applied.additional_data_json, expected);
+        assert_eq!(applied.version, updated.version);
+        assert_eq!(state.state_data_json, expected);
+
+        // non-object appends keep previous data
+        for append in vec![json!(null), json!([1]), json!("value")] {
+            let update = UpdateToken {
+                append_state_data_json: Some(append),
+                status: Some(TokenStatus::Locked),
+            };
+            let (applied, _) = updated.clone().append_state(update.clone(), instruction.id);
+            updated = updated.update(update, &instruction, &client).await.unwrap();
+            assert_eq!(updated.additional_data_json, expected);
+            assert_eq!(applied.status, updated.status);
+            assert_eq!(updated.status, TokenStatus::Locked);
+        }
+
+        // previous non-object data is replaced by appended object
+        let update = UpdateToken {
+            append_state_data_json: Some(json!({"replaced": true})),
+            ..UpdateToken::default()
+        };
+        let token = TokenBuilder {
+            initial_data_json: json!(null),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let updated = token.clone().update(update, &instruction, &client).await.unwrap();
+        assert_eq!(updated.additional_data_json, json!({"replaced": true}));
+        assert_eq!(updated.status, token.status);
+    }
+
+    #[actix_rt::test]
+    async fn current_state_matches_history() {
+        let (client, _lock) = test_db_client().await;
+        let mut token = TokenBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        for i in 0..3 {
+            let update = UpdateToken {
+                append_state_data_json: Some(json!({ "step": i })),
+                ..UpdateToken::default()
+            };
+            token = token.update(update, &instruction, &client).await.unwrap();
+        }
+        let stmt = client.prepare("SELECT * FROM tokens_view WHERE id = $1").await.unwrap();
+        let audited = Token::from_row(client.query_one(&stmt, &[&token.id]).await.unwrap()).unwrap();
+        let current = Token::load(token.id, &client).await.unwrap();
+        assert_eq!(current, audited);
+        assert_eq!(current.additional_data_json, json!({ "step": 2 }));
+    }
+
+    #[actix_rt::test]
+    async fn update_missing_token() {
+        let (client, _lock) = test_db_client().await;
+        let token = TokenBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let missing = Token {
+            token_id: Test::<TokenID>::new(),
+            ..token
+        };
+        let res = missing.update(UpdateToken::default(), &instruction, &client).await;
+        assert!(matches!(res, Err(DBError::NotFound)));
+    }
+
+    #[actix_rt::test]
+    async fn concurrent_transfers() {
+        let (client, pool) = test_db_client().await;
+        let client2 = pool.get().await.unwrap();
+        let token = TokenBuilder {
+            initial_data_json: json!({"owner_pubkey": "alice"}),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        assert_eq!(token.version, 0);
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let transfer = |owner: &str| UpdateToken {
+            append_state_data_json: Some(json!({ "owner_pubkey": owner })),
+            ..UpdateToken::default()
+        };
+
+        let (res1, res2) = futures::join!(
+            token.clone().update(transfer("bob"), &instruction, &client),
+            token.clone().update(transfer("carol"), &instruction, &client2),
+        );
+        let (committed, rejected) = match (res1, res2) {
+            (Ok(token), Err(err)) | (Err(err), Ok(token)) => (token, err),
+            res => panic!("Expected exactly one transfer to commit, got {:?}", res),
+        };
+        assert_eq!(ErrorCode::from(&rejected), ErrorCode::Conflict);
+        assert_eq!(committed.version, 1);
+        assert_eq!(Token::load(token.id, &client).await.unwrap(), committed);
+
+        // stale token is rejected even without concurrency, reloaded token proceeds
+        let res = token.update(transfer("dave"), &instruction, &client).await;
+        assert!(matches!(res, Err(DBError::Conflict { .. })));
+        let token = Token::load(committed.id, &client).await.unwrap();
+        let token = token.update(transfer("dave"), &instruction, &client).await.unwrap();
+        assert_eq!(token.version, 2);
+        assert_eq!(token.owner_pubkey, Some("dave".into()));
+    }
+
+    #[actix_rt::test]
+    async fn buffered_transfers() {
+        let (client, _lock) = test_db_client().await;
+        let token = TokenBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let transfer = |owner: &str| UpdateToken {
+            append_state_data_json: Some(json!({ "owner_pubkey": owner })),
+            ..UpdateToken::default()
+        };
+        // both instructions buffered their state on top of the same version
+        let (_, state1) = token.clone().append_state(transfer("bob"), instruction.id);
+        let (_, state2) = token.clone().append_state(transfer("carol"), instruction.id);
+        assert_eq!(state1.expected_version, Some(0));
+
+        Token::store_append_only_state(&state1, &client).await.unwrap();
+        let res = Token::store_append_only_state(&state2, &client).await;
+        assert!(matches!(res, Err(DBError::Conflict { .. })), "{:?}", res);
+        let token = Token::load(token.id, &client).await.unwrap();
+        assert_eq!(token.version, 1);
+        assert_eq!(token.owner_pubkey, Some("bob".into()));
+    }
+
+    #[actix_rt::test]
+    async fn ownership() {
+        let (client, _lock) = test_db_client().await;
+        let token = TokenBuilder {
+            initial_data_json: json!({"owner_pubkey": "alice"}),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        assert_eq!(token.owner_pubkey(), Some("alice".into()));
+        assert!(token.validate_transfer("alice", "bob").is_ok());
+        let errors = token.validate_transfer("bob", "alice").unwrap_err();
+        assert!(errors.0.contains_key("owner_pubkey"));
+        assert!(errors.0.contains_key("new_owner"));
+
+        let asset = AssetState::load(token.asset_state_id, &client).await.unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        // data without owner keeps the owner
+        let update = UpdateToken {
+            append_state_data_json: Some(json!({"value": 1})),
+            ..UpdateToken::default()
+        };
+        let token = token.update(update, &instruction, &client).await.unwrap();
+        assert_eq!(token.owner_pubkey, Some("alice".into()));
+        let update = UpdateToken {
+            status: Some(TokenStatus::Locked),
+            append_state_data_json: Some(json!({"owner_pubkey": "bob"})),
+        };
+        let token = token.update(update, &instruction, &client).await.unwrap();
+        assert_eq!(token.owner_pubkey, Some("bob".into()));
+        assert_eq!(Token::load(token.id, &client).await.unwrap(), token);
+        assert_eq!(Token::count_by_owner(asset.id, "bob", &client).await.unwrap(), 1);
+        assert_eq!(Token::count_by_owner(asset.id, "alice", &client).await.unwrap(), 0);
+        assert_eq!(Token::count_by_asset_state_id(asset.id, &client).await.unwrap(), 1);
+        let errors = token.validate_transfer("bob", "carol").unwrap_err();
+        assert_eq!(errors.0.keys().collect::<Vec<_>>(), vec![&"status"]);
+
+        // replicated state without owner falls back to the initial owner
+        Token::store_append_only_state(
+            &NewTokenStateAppendOnly {
+                token_id: token.token_id.clone(),
+                state_data_json: json!({}),
+                status: TokenStatus::Available,
+                instruction_id: instruction.id,
+                expected_version: None,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let token = Token::load(token.id, &client).await.unwrap();
+        assert_eq!(token.owner_pubkey, Some("alice".into()));
+    }
+
+    #[actix_rt::test]
+    async fn search() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        // owners are unique per test run, as other tests might leave tokens behind
+        let (alice, bob) = (format!("alice-{}", asset.id), format!("bob-{}", asset.id));
+        let mut tokens = vec![];
+        for owner in &[&alice, &alice, &bob] {
+            let token = TokenBuilder {
+                asset_state_id: Some(asset.id),
+                initial_data_json: json!({ "owner_pubkey": owner }),
+                token_id: Test::from_asset(&asset.asset_id),
+                ..TokenBuilder::default()
+            }
+            .build(&client)
+            .await
+            .unwrap();
+            tokens.push(token);
+        }
+        let other = TokenBuilder {
+            initial_data_json: json!({ "owner_pubkey": alice }),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let by_alice = SelectToken {
+            owner_pubkey: Some(alice.clone()),
+            asset_id: Some(asset.asset_id.clone()),
+            ..SelectToken::default()
+        };
+        let found = Token::search(by_alice.clone(), &client).await.unwrap();
+        assert_eq!(found, vec![tokens[0].clone(), tokens[1].clone()]);
+        let all_alice = SelectToken {
+            asset_id: None,
+            ..by_alice.clone()
+        };
+        assert_eq!(Token::search(all_alice, &client).await.unwrap().len(), 3);
+
+        // transferred token is found by the new owner only
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let since = Utc::now();
+        let update = UpdateToken {
+            status: Some(TokenStatus::Active),
+            append_state_data_json: Some(json!({ "owner_pubkey": bob })),
+        };
+        tokens[0].clone().update(update, &instruction, &client).await.unwrap();
+        let found = Token::search(by_alice.clone(), &client).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, tokens[1].id);
+        let by_bob = SelectToken {
+            owner_pubkey: Some(bob.clone()),
+            ..by_alice.clone()
+        };
+        assert_eq!(Token::search(by_bob.clone(), &client).await.unwrap().len(), 2);
+
+        let active = SelectToken {
+            status: Some(TokenStatus::Active),
+            ..by_bob.clone()
+        };
+        let found = Token::search(active, &client).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, tokens[0].id);
+        let updated = SelectToken {
+            owner_pubkey: None,
+            updated_since: Some(since),
+            ..by_alice.clone()
+        };
+        let found = Token::search(updated, &client).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, tokens[0].id);
+
+        let page = SelectToken {
+            limit: Some(1),
+            offset: Some(1),
+            ..by_bob
+        };
+        let found = Token::search(page, &client).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, tokens[2].id);
+        assert!(Token::search(by_alice, &client)
+            .await
+            .unwrap()
+            .iter()
+            .all(|token| token.id != other.id));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::db::utils::errors::DBError;
+use crate::{db::utils::errors::DBError, types::InstructionID};
 use chrono::{DateTime, Utc};
 use deadpool_postgres::{Client, Transaction};
 use serde::{Deserialize, Serialize};
@@ -17,13 +17,48 @@ pub struct Wallet {
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Wallet replaced by this wallet on key rotation
+    pub rotated_from: Option<uuid::Uuid>,
+    /// Set when wallet key was rotated
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// Instruction which created temporary wallet
+    pub instruction_id: Option<InstructionID>,
+    /// Set when temporary wallet was swept into node's main wallet
+    pub retired_at: Option<DateTime<Utc>>,
+    /// Set when key file of retired wallet was removed
+    pub keys_removed_at: Option<DateTime<Utc>>,
+    /// Path temporary wallet key is derived along from the node master key, such wallets have no key file
+    pub derivation_path: Option<String>,
 }
 
+/// Currency of [Wallet::balance], balances in other currencies are kept as [WalletBalance]
+pub const NATIVE_CURRENCY: &'static str = "XTR";
+
+/// Balance of the wallet in currency other than [NATIVE_CURRENCY], currency is an arbitrary code,
+/// e.g. asset ID of tokens paid with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PostgresMapper)]
+#[pg_mapper(table = "wallet_balances")]
+pub struct WalletBalance {
+    pub wallet_id: uuid::Uuid,
+    pub currency: String,
+    pub balance: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Adds balances of wallet $1 in currencies other than [NATIVE_CURRENCY] to wallet $2
+const MOVE_BALANCES: &'static str = "WITH moved AS (DELETE FROM wallet_balances WHERE wallet_id = $1 RETURNING \
+                                     currency, balance) INSERT INTO wallet_balances (wallet_id, currency, balance) \
+                                     SELECT $2, currency, balance FROM moved ON CONFLICT (wallet_id, currency) DO \
+                                     UPDATE SET balance = wallet_balances.balance + EXCLUDED.balance, updated_at = \
+                                     NOW()";
+
 /// Query paramteres for adding new wallet record
 #[derive(Default, Clone, Debug)]
 pub(crate) struct NewWallet {
     pub pub_key: String,
     pub name: String,
+    pub instruction_id: Option<InstructionID>,
+    pub derivation_path: Option<String>,
 }
 
 /// Query paramteres for searching wallet records
@@ -37,11 +72,16 @@ pub struct SelectWallet {
 impl Wallet {
     /// Add wallet record
     pub(crate) async fn insert<'t>(params: NewWallet, client: &Transaction<'t>) -> Result<Wallet, DBError> {
-        const QUERY: &'static str = "INSERT INTO wallet (pub_key, name) VALUES ($1,$2)
-            ON CONFLICT (pub_key) DO UPDATE SET updated_at = NOW() RETURNING *";
+        const QUERY: &'static str = "INSERT INTO wallet (pub_key, name, instruction_id, derivation_path) VALUES \
+                                     ($1,$2,$3,$4) ON CONFLICT (pub_key) DO UPDATE SET updated_at = NOW() RETURNING *";
         let stmt = client.prepare(QUERY).await?;
         Ok(client
-            .query_one(&stmt, &[&params.pub_key, &params.name])
+            .query_one(&stmt, &[
+                &params.pub_key,
+                &params.name,
+                &params.instruction_id,
+                &params.derivation_path,
+            ])
             .await
             .map(|row| Wallet::from_row(row))??)
     }
@@ -74,6 +114,114 @@ impl Wallet {
             .map(|row| Wallet::from_row(row))??)
     }
 
+    /// Link `replacement` wallet to this one, moving the balance over to the replacement
+    pub(crate) async fn rotate<'t>(&self, replacement: &Wallet, client: &Transaction<'t>) -> Result<Wallet, DBError> {
+        const QUERY: &'static str = "WITH old AS (SELECT id, balance FROM wallet WHERE id = $1 AND rotated_at IS NULL \
+                                     FOR UPDATE), retired AS (UPDATE wallet SET updated_at = NOW(), rotated_at = \
+                                     NOW(), balance = 0 FROM old WHERE wallet.id = old.id) UPDATE wallet SET \
+                                     updated_at = NOW(), rotated_from = old.id, balance = old.balance FROM old WHERE \
+                                     wallet.id = $2 RETURNING wallet.*";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client
+            .query_opt(&stmt, &[&self.id, &replacement.id])
+            .await?
+            .ok_or_else(|| DBError::bad_query("Wallet is already rotated"))?;
+        let stmt = client.prepare_typed(MOVE_BALANCES, &[Type::UUID, Type::UUID]).await?;
+        client.execute(&stmt, &[&self.id, &replacement.id]).await?;
+        Ok(Self::from_row(row)?)
+    }
+
+    /// Temporary wallets of completed or invalidated instructions which were not swept yet
+    pub async fn find_sweepable(client: &Client) -> Result<Vec<Wallet>, DBError> {
+        const QUERY: &'static str = "SELECT wallet.* FROM wallet JOIN instructions ON instructions.id = \
+                                     wallet.instruction_id WHERE wallet.retired_at IS NULL AND instructions.status \
+                                     IN ('Commit', 'Invalid') ORDER BY wallet.created_at";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[])
+            .await?
+            .into_iter()
+            .map(Wallet::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Retired wallets with key files still on disk, retired before `before`
+    pub async fn find_retired(before: DateTime<Utc>, client: &Client) -> Result<Vec<Wallet>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM wallet WHERE retired_at < $1 AND keys_removed_at IS NULL ORDER BY \
+                                     retired_at";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&before])
+            .await?
+            .into_iter()
+            .map(Wallet::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Move balance into `main` wallet and mark this wallet retired, returns swept amount
+    pub async fn sweep(&self, main: &Wallet, client: &Client) -> Result<i64, DBError> {
+        const QUERY: &'static str = "WITH temp AS (SELECT id, COALESCE(balance, 0) AS balance FROM wallet WHERE id = \
+                                     $1 AND retired_at IS NULL FOR UPDATE), retired AS (UPDATE wallet SET updated_at \
+                                     = NOW(), retired_at = NOW(), balance = 0 FROM temp WHERE wallet.id = temp.id) \
+                                     UPDATE wallet SET updated_at = NOW(), balance = COALESCE(wallet.balance, 0) + \
+                                     temp.balance FROM temp WHERE wallet.id = $2 RETURNING temp.balance";
+        if self.id == main.id {
+            return Err(DBError::bad_query("Can't sweep wallet into itself"));
+        }
+        // moved first, so balances left by interrupted sweep are picked up by the next one
+        let stmt = client.prepare_typed(MOVE_BALANCES, &[Type::UUID, Type::UUID]).await?;
+        client.execute(&stmt, &[&self.id, &main.id]).await?;
+        let stmt = client.prepare(QUERY).await?;
+        let row = client
+            .query_opt(&stmt, &[&self.id, &main.id])
+            .await?
+            .ok_or_else(|| DBError::bad_query("Wallet is already retired"))?;
+        Ok(row.get(0))
+    }
+
+    /// Move `amount` from this wallet to `to`, returns both wallets updated
+    ///
+    /// Fails with conflict if balance is insufficient or either wallet is retired or rotated,
+    /// `client` transaction has to be rolled back then
+    pub async fn transfer<'t>(
+        &self,
+        to: &Wallet,
+        amount: i64,
+        client: &Transaction<'t>,
+    ) -> Result<(Wallet, Wallet), DBError>
+    {
+        const DEBIT: &'static str = "UPDATE wallet SET updated_at = NOW(), balance = balance - $2 WHERE id = $1 AND \
+                                     balance >= $2 AND retired_at IS NULL AND rotated_at IS NULL RETURNING *";
+        const CREDIT: &'static str = "UPDATE wallet SET updated_at = NOW(), balance = COALESCE(balance, 0) + $2 WHERE \
+                                      id = $1 AND retired_at IS NULL AND rotated_at IS NULL RETURNING *";
+        if self.id == to.id {
+            return Err(DBError::bad_query("Can't transfer to the same wallet"));
+        }
+        if amount <= 0 {
+            return Err(DBError::bad_query("Transfer amount should be positive"));
+        }
+        let stmt = client.prepare_typed(DEBIT, &[Type::UUID, Type::INT8]).await?;
+        let from = client
+            .query_opt(&stmt, &[&self.id, &amount])
+            .await?
+            .ok_or_else(|| DBError::conflict("Insufficient balance or sender wallet is retired"))?;
+        let stmt = client.prepare_typed(CREDIT, &[Type::UUID, Type::INT8]).await?;
+        let to = client
+            .query_opt(&stmt, &[&to.id, &amount])
+            .await?
+            .ok_or_else(|| DBError::conflict("Recipient wallet is retired"))?;
+        Ok((Self::from_row(from)?, Self::from_row(to)?))
+    }
+
+    /// Record removal of retired wallet key file
+    pub async fn set_keys_removed(&self, client: &Client) -> Result<Wallet, DBError> {
+        const QUERY: &'static str = "UPDATE wallet SET updated_at = NOW(), keys_removed_at = NOW() WHERE id = $1 \
+                                     RETURNING *";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[&self.id]).await?;
+        Ok(Self::from_row(row)?)
+    }
+
     /// Update wallet's balance
     // TODO: the whole wallet thing might get info from base layer instead in the future...
     #[allow(dead_code)]
@@ -83,11 +231,58 @@ impl Wallet {
         let row = client.query_one(&stmt, &[&self.id, &balance]).await?;
         Ok(Self::from_row(row)?)
     }
+
+    /// Balance of the wallet in `currency`, 0 if the wallet never had the currency
+    pub async fn balance_in(&self, currency: &str, client: &Client) -> Result<i64, DBError> {
+        const QUERY: &'static str = "SELECT COALESCE(CASE WHEN $2 = $3 THEN w.balance ELSE b.balance END, 0) FROM \
+                                     wallet w LEFT JOIN wallet_balances b ON b.wallet_id = w.id AND b.currency = $2 \
+                                     WHERE w.id = $1";
+        let stmt = client
+            .prepare_typed(QUERY, &[Type::UUID, Type::TEXT, Type::TEXT])
+            .await?;
+        let row = client.query_one(&stmt, &[&self.id, &currency, &NATIVE_CURRENCY]).await?;
+        Ok(row.get(0))
+    }
+
+    /// Update wallet's balance in `currency`, same as [Wallet::set_balance] for [NATIVE_CURRENCY]
+    pub async fn set_balance_in(&self, currency: &str, balance: i64, client: &Client) -> Result<Wallet, DBError> {
+        const QUERY: &'static str = "WITH balance AS (INSERT INTO wallet_balances (wallet_id, currency, balance) \
+                                     VALUES ($1, $2, $3) ON CONFLICT (wallet_id, currency) DO UPDATE SET balance = \
+                                     EXCLUDED.balance, updated_at = NOW()) UPDATE wallet SET updated_at = NOW() \
+                                     WHERE id = $1 RETURNING *";
+        if currency == NATIVE_CURRENCY {
+            return self.set_balance(balance, client).await;
+        }
+        let stmt = client
+            .prepare_typed(QUERY, &[Type::UUID, Type::TEXT, Type::INT8])
+            .await?;
+        let row = client.query_one(&stmt, &[&self.id, &currency, &balance]).await?;
+        Ok(Self::from_row(row)?)
+    }
+
+    /// Balances of the wallet in currencies other than [NATIVE_CURRENCY]
+    pub async fn balances(&self, client: &Client) -> Result<Vec<WalletBalance>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM wallet_balances WHERE wallet_id = $1 ORDER BY currency";
+        let stmt = client.prepare_typed(QUERY, &[Type::UUID]).await?;
+        Ok(client
+            .query(&stmt, &[&self.id])
+            .await?
+            .into_iter()
+            .map(WalletBalance::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Number of wallets not retired yet, including temporary wallets of instructions
+    pub async fn count_active(client: &Client) -> Result<i64, DBError> {
+        const QUERY: &'static str = "SELECT count(*) FROM wallet WHERE retired_at IS NULL";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.query_one(&stmt, &[]).await?.get(0))
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{NewWallet, SelectWallet, Wallet};
+    use super::{NewWallet, SelectWallet, Wallet, NATIVE_CURRENCY};
     use crate::test::utils::{load_env, test_db_client};
 
     const PUBKEY: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
@@ -188,4 +383,140 @@ mod test {
         let wallet = Wallet::select_by_key(&wallet.pub_key, &client).await.unwrap();
         assert_eq!(wallet.balance, 100);
     }
+
+    #[actix_rt::test]
+    async fn currency_balances() {
+        load_env();
+        let (mut client, _lock) = test_db_client().await;
+
+        let transaction = client.transaction().await.unwrap();
+        let temp = Wallet::insert(
+            NewWallet {
+                pub_key: PUBKEY.to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        let main = Wallet::insert(
+            NewWallet {
+                pub_key: "main".to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+        assert_eq!(temp.balance_in("USD", &client).await.unwrap(), 0);
+
+        let temp = temp.set_balance_in(NATIVE_CURRENCY, 100, &client).await.unwrap();
+        assert_eq!(temp.balance, 100);
+        temp.set_balance_in("USD", 10, &client).await.unwrap();
+        temp.set_balance_in("USD", 20, &client).await.unwrap();
+        temp.set_balance_in("EUR", 30, &client).await.unwrap();
+        main.set_balance_in("USD", 5, &client).await.unwrap();
+        assert_eq!(temp.balance_in(NATIVE_CURRENCY, &client).await.unwrap(), 100);
+        assert_eq!(temp.balance_in("USD", &client).await.unwrap(), 20);
+        let currencies: Vec<_> = temp
+            .balances(&client)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|balance| (balance.currency, balance.balance))
+            .collect();
+        assert_eq!(currencies, vec![("EUR".to_owned(), 30), ("USD".to_owned(), 20)]);
+
+        // all currencies are swept
+        assert_eq!(temp.sweep(&main, &client).await.unwrap(), 100);
+        assert!(temp.balances(&client).await.unwrap().is_empty());
+        assert_eq!(main.balance_in(NATIVE_CURRENCY, &client).await.unwrap(), 100);
+        assert_eq!(main.balance_in("USD", &client).await.unwrap(), 25);
+        assert_eq!(main.balance_in("EUR", &client).await.unwrap(), 30);
+    }
+
+    #[actix_rt::test]
+    async fn rotate() {
+        load_env();
+        let (mut client, _lock) = test_db_client().await;
+
+        let transaction = client.transaction().await.unwrap();
+        let old = Wallet::insert(
+            NewWallet {
+                pub_key: PUBKEY.to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        let replacement = Wallet::insert(
+            NewWallet {
+                pub_key: "new".to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+        let old = old.set_balance(100, &client).await.unwrap();
+
+        let transaction = client.transaction().await.unwrap();
+        let replacement = old.rotate(&replacement, &transaction).await.unwrap();
+        transaction.commit().await.unwrap();
+        assert_eq!(replacement.rotated_from, Some(old.id));
+        assert_eq!(replacement.balance, 100);
+        let old = Wallet::select_by_key(&old.pub_key, &client).await.unwrap();
+        assert!(old.rotated_at.is_some());
+        assert_eq!(old.balance, 0);
+
+        let transaction = client.transaction().await.unwrap();
+        assert!(old.rotate(&replacement, &transaction).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn transfer() {
+        load_env();
+        let (mut client, _lock) = test_db_client().await;
+
+        let transaction = client.transaction().await.unwrap();
+        let from = Wallet::insert(
+            NewWallet {
+                pub_key: PUBKEY.to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        let to = Wallet::insert(
+            NewWallet {
+                pub_key: "recipient".to_owned(),
+                ..NewWallet::default()
+            },
+            &transaction,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+        let from = from.set_balance(100, &client).await.unwrap();
+
+        let transaction = client.transaction().await.unwrap();
+        let (from, to) = from.transfer(&to, 40, &transaction).await.unwrap();
+        transaction.commit().await.unwrap();
+        assert_eq!(from.balance, 60);
+        assert_eq!(to.balance, 40);
+
+        let transaction = client.transaction().await.unwrap();
+        assert!(from.transfer(&to, 61, &transaction).await.is_err());
+        drop(transaction);
+        let transaction = client.transaction().await.unwrap();
+        assert!(from.transfer(&from, 1, &transaction).await.is_err());
+        assert!(from.transfer(&to, 0, &transaction).await.is_err());
+        drop(transaction);
+        let from = Wallet::select_by_key(&from.pub_key, &client).await.unwrap();
+        assert_eq!(from.balance, 60);
+    }
 }
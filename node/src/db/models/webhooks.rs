@@ -0,0 +1,257 @@
+use super::WebhookDeliveryStatus;
+use crate::{db::utils::errors::DBError, types::AssetID};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+
+/// Endpoint notified about instructions and proposals of the asset
+///
+/// Payloads are signed with `secret`, see [crate::webhooks]
+#[derive(Debug, Clone, Serialize, PostgresMapper)]
+#[pg_mapper(table = "webhooks")]
+pub struct Webhook {
+    pub id: uuid::Uuid,
+    pub asset_id: AssetID,
+    pub url: String,
+    #[serde(skip)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewWebhook {
+    pub asset_id: AssetID,
+    pub url: String,
+    pub secret: String,
+}
+
+impl Webhook {
+    /// Register webhook for the asset
+    pub async fn insert(params: NewWebhook, client: &Client) -> Result<Webhook, DBError> {
+        const QUERY: &'static str = "INSERT INTO webhooks (asset_id, url, secret) VALUES ($1, $2, $3) RETURNING *";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client
+            .query_one(&stmt, &[&params.asset_id, &params.url, &params.secret])
+            .await?;
+        Ok(Webhook::from_row(row)?)
+    }
+
+    /// Remove webhook along with its delivery log
+    pub async fn delete(id: uuid::Uuid, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "DELETE FROM webhooks WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.execute(&stmt, &[&id]).await?)
+    }
+
+    pub async fn load(id: uuid::Uuid, client: &Client) -> Result<Webhook, DBError> {
+        const QUERY: &'static str = "SELECT * FROM webhooks WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[&id]).await?;
+        Ok(Webhook::from_row(row)?)
+    }
+
+    /// Find webhooks of the asset
+    pub async fn find_by_asset_id(asset_id: &AssetID, client: &Client) -> Result<Vec<Webhook>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM webhooks WHERE asset_id = $1 ORDER BY created_at";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&asset_id])
+            .await?
+            .into_iter()
+            .map(Webhook::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+/// Delivery of a single event to a webhook, retried until delivered or attempts are exhausted
+#[derive(Debug, Clone, Serialize, PostgresMapper)]
+#[pg_mapper(table = "webhook_deliveries")]
+pub struct WebhookDelivery {
+    pub id: uuid::Uuid,
+    pub webhook_id: uuid::Uuid,
+    pub event: String,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookDelivery {
+    /// Enqueue event for every webhook of the asset, returns number of enqueued deliveries
+    ///
+    /// Should be called within the transaction changing the state, so event is delivered only if change is committed
+    pub async fn enqueue(asset_id: &AssetID, event: &str, payload: &Value, client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "INSERT INTO webhook_deliveries (webhook_id, event, payload) SELECT id, $2, $3 \
+                                     FROM webhooks WHERE asset_id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.execute(&stmt, &[asset_id, &event, payload]).await?)
+    }
+
+    /// Pending deliveries due for the next attempt, oldest first
+    pub async fn find_due(limit: i64, client: &Client) -> Result<Vec<WebhookDelivery>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM webhook_deliveries WHERE status = 'Pending' AND next_attempt_at <= \
+                                     now() ORDER BY next_attempt_at LIMIT $1";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&limit])
+            .await?
+            .into_iter()
+            .map(WebhookDelivery::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Latest deliveries of the webhook
+    pub async fn find_by_webhook_id(
+        webhook_id: uuid::Uuid,
+        limit: i64,
+        client: &Client,
+    ) -> Result<Vec<WebhookDelivery>, DBError>
+    {
+        const QUERY: &'static str =
+            "SELECT * FROM webhook_deliveries WHERE webhook_id = $1 ORDER BY created_at DESC LIMIT $2";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[&webhook_id, &limit])
+            .await?
+            .into_iter()
+            .map(WebhookDelivery::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn mark_delivered(&self, client: &Client) -> Result<WebhookDelivery, DBError> {
+        const QUERY: &'static str = "UPDATE webhook_deliveries SET status = 'Delivered', attempts = attempts + 1, \
+                                     delivered_at = now(), last_error = NULL, updated_at = now() WHERE id = $1 \
+                                     RETURNING *";
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[&self.id]).await?;
+        Ok(WebhookDelivery::from_row(row)?)
+    }
+
+    /// Records failed attempt, delivery is retried at `retry_at` or marked Failed if it is None
+    pub async fn mark_attempt_failed(
+        &self,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+        client: &Client,
+    ) -> Result<WebhookDelivery, DBError>
+    {
+        const QUERY: &'static str = "UPDATE webhook_deliveries SET status = $2, attempts = attempts + 1, last_error = \
+                                     $3, next_attempt_at = COALESCE($4, next_attempt_at), updated_at = now() WHERE \
+                                     id = $1 RETURNING *";
+        let status = match retry_at {
+            Some(_) => WebhookDeliveryStatus::Pending,
+            None => WebhookDeliveryStatus::Failed,
+        };
+        let stmt = client.prepare(QUERY).await?;
+        let row = client.query_one(&stmt, &[&self.id, &status, &error, &retry_at]).await?;
+        Ok(WebhookDelivery::from_row(row)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{builders::AssetStateBuilder, test_db_client};
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn crud() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let webhook = Webhook::insert(
+            NewWebhook {
+                asset_id: asset.asset_id.clone(),
+                url: "http://localhost/hook".into(),
+                secret: "secret".into(),
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let webhooks = Webhook::find_by_asset_id(&asset.asset_id, &client).await.unwrap();
+        assert_eq!(webhooks.len(), 1);
+        assert_eq!(webhooks[0].url, "http://localhost/hook");
+        assert_eq!(Webhook::load(webhook.id, &client).await.unwrap().secret, "secret");
+
+        assert_eq!(Webhook::delete(webhook.id, &client).await.unwrap(), 1);
+        assert!(Webhook::find_by_asset_id(&asset.asset_id, &client)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn deliveries() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let other = AssetStateBuilder::default().build(&client).await.unwrap();
+        let payload = json!({"event": "instruction.commit"});
+        assert_eq!(
+            WebhookDelivery::enqueue(&asset.asset_id, "instruction.commit", &payload, &client)
+                .await
+                .unwrap(),
+            0
+        );
+        for url in &["http://localhost/1", "http://localhost/2"] {
+            Webhook::insert(
+                NewWebhook {
+                    asset_id: asset.asset_id.clone(),
+                    url: url.to_string(),
+                    secret: "secret".into(),
+                },
+                &client,
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(
+            WebhookDelivery::enqueue(&asset.asset_id, "instruction.commit", &payload, &client)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            WebhookDelivery::enqueue(&other.asset_id, "instruction.commit", &payload, &client)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let due = WebhookDelivery::find_due(10, &client).await.unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].payload, payload);
+        assert_eq!(due[0].status, WebhookDeliveryStatus::Pending);
+
+        let delivered = due[0].mark_delivered(&client).await.unwrap();
+        assert_eq!(delivered.status, WebhookDeliveryStatus::Delivered);
+        assert_eq!(delivered.attempts, 1);
+        assert!(delivered.delivered_at.is_some());
+
+        let retry = due[1]
+            .mark_attempt_failed("timeout", Some(Utc::now() + Duration::minutes(1)), &client)
+            .await
+            .unwrap();
+        assert_eq!(retry.status, WebhookDeliveryStatus::Pending);
+        assert_eq!(retry.last_error, Some("timeout".into()));
+        assert!(WebhookDelivery::find_due(10, &client).await.unwrap().is_empty());
+
+        let failed = retry.mark_attempt_failed("timeout", None, &client).await.unwrap();
+        assert_eq!(failed.status, WebhookDeliveryStatus::Failed);
+        assert_eq!(failed.attempts, 2);
+        assert_eq!(
+            WebhookDelivery::find_by_webhook_id(failed.webhook_id, 10, &client)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{db::utils::errors::DBError, types::AssetID};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio_pg_mapper::{FromTokioPostgresRow, PostgresMapper};
+use tokio_postgres::{types::Type, Client};
+
+/// Validator process of a clustered node, see [crate::consensus::ClusterWorker]
+#[derive(Serialize, PostgresMapper, PartialEq, Debug, Clone)]
+#[pg_mapper(table = "workers")]
+pub struct Worker {
+    pub id: String,
+    pub node_id: String,
+    pub heartbeat_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Worker {
+    /// Registers worker or prolongs its membership by `ttl` seconds
+    pub async fn heartbeat(id: &str, node_id: &str, ttl: u64, client: &Client) -> Result<Worker, DBError> {
+        const QUERY: &'static str = "
+            INSERT INTO workers (id, node_id, expires_at) VALUES ($1, $2, now() + make_interval(secs => $3))
+            ON CONFLICT (id) DO UPDATE
+            SET node_id = EXCLUDED.node_id, heartbeat_at = now(), expires_at = EXCLUDED.expires_at
+            RETURNING *";
+        let stmt = client
+            .prepare_typed(QUERY, &[Type::TEXT, Type::TEXT, Type::FLOAT8])
+            .await?;
+        let row = client.query_one(&stmt, &[&id, &node_id, &(ttl as f64)]).await?;
+        Ok(Worker::from_row(row)?)
+    }
+
+    /// Workers assets are partitioned over
+    pub async fn find_live(client: &Client) -> Result<Vec<Worker>, DBError> {
+        const QUERY: &'static str = "SELECT * FROM workers WHERE expires_at > now() ORDER BY id";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client
+            .query(&stmt, &[])
+            .await?
+            .into_iter()
+            .map(Worker::from_row)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Live worker processing the asset, None if there are no live workers
+    pub async fn owner_of(asset_id: &AssetID, client: &Client) -> Result<Option<String>, DBError> {
+        const QUERY: &'static str = "
+            SELECT id FROM workers
+            WHERE worker_owns_asset(id, $1)
+            AND expires_at > now()";
+        let stmt = client.prepare_typed(QUERY, &[Type::TEXT]).await?;
+        Ok(client
+            .query_opt(&stmt, &[&asset_id.to_string()])
+            .await?
+            .map(|row| row.get(0)))
+    }
+
+    /// Removes worker from partitioning right away, e.g. on graceful shutdown
+    pub async fn deregister(id: &str, client: &Client) -> Result<(), DBError> {
+        const QUERY: &'static str = "DELETE FROM workers WHERE id = $1";
+        let stmt = client.prepare(QUERY).await?;
+        client.execute(&stmt, &[&id]).await?;
+        Ok(())
+    }
+
+    /// Forgets workers which stopped heartbeating, returns number of removed workers
+    pub async fn reap(client: &Client) -> Result<u64, DBError> {
+        const QUERY: &'static str = "DELETE FROM workers WHERE expires_at <= now()";
+        let stmt = client.prepare(QUERY).await?;
+        Ok(client.execute(&stmt, &[]).await?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::{test_db_client, Test};
+
+    #[actix_rt::test]
+    async fn partition_assets() {
+        let (client, _lock) = test_db_client().await;
+        let asset_ids: Vec<AssetID> = (0..20).map(|_| Test::<AssetID>::new()).collect();
+        assert_eq!(Worker::owner_of(&asset_ids[0], &client).await.unwrap(), None);
+
+        Worker::heartbeat("worker1", "node", 60, &client).await.unwrap();
+        Worker::heartbeat("worker2", "node", 60, &client).await.unwrap();
+        let live: Vec<_> = Worker::find_live(&client).await.unwrap().into_iter().map(|w| w.id).collect();
+        assert_eq!(live, vec!["worker1", "worker2"]);
+
+        let mut owners = vec![];
+        for asset_id in asset_ids.iter() {
+            owners.push(Worker::owner_of(asset_id, &client).await.unwrap().unwrap());
+        }
+        assert!(owners.contains(&"worker1".to_string()));
+        assert!(owners.contains(&"worker2".to_string()));
+
+        // assets of the remaining worker stay in place when another worker joins
+        Worker::heartbeat("worker3", "node", 60, &client).await.unwrap();
+        for (asset_id, owner) in asset_ids.iter().zip(owners.iter()) {
+            let new_owner = Worker::owner_of(asset_id, &client).await.unwrap().unwrap();
+            assert!(new_owner == *owner || new_owner == "worker3");
+        }
+
+        // expired worker leaves partitioning
+        Worker::heartbeat("worker3", "node", 0, &client).await.unwrap();
+        Worker::deregister("worker2", &client).await.unwrap();
+        for asset_id in asset_ids.iter() {
+            assert_eq!(Worker::owner_of(asset_id, &client).await.unwrap().unwrap(), "worker1");
+        }
+        assert_eq!(Worker::reap(&client).await.unwrap(), 1);
+        assert_eq!(Worker::find_live(&client).await.unwrap().len(), 1);
+    }
+}
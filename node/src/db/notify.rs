@@ -0,0 +1,235 @@
+//! Instruction status propagation via Postgres LISTEN/NOTIFY
+//!
+//! Instructions trigger notifies `instruction_status` channel on every insert and status change, [InstructionNotifier]
+//! listens on a dedicated connection and fans notifications out to subscribers, so consensus processor and contracts
+//! waiting for dependencies wake up on change instead of polling instructions table.
+
+use super::models::InstructionStatus;
+use crate::{
+    metrics::{InstructionEvent, MetricEvent, Metrics},
+    types::{AssetID, InstructionID, TemplateID},
+};
+use actix::Addr;
+use futures::StreamExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::{
+    sync::broadcast::{self, RecvError},
+    task::JoinHandle,
+};
+use tokio_postgres::{AsyncMessage, NoTls};
+
+const LOG_TARGET: &'static str = "tari_validator_node::db::notify";
+
+/// Channel notified by instructions trigger
+pub const INSTRUCTION_STATUS_CHANNEL: &'static str = "instruction_status";
+
+/// Notifications not received by slow subscribers are dropped after this many newer ones
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NotifyConfig {
+    /// Components fall back to polling when disabled
+    pub enabled: bool,
+    /// Delay before reconnecting lost listener connection in seconds
+    pub reconnect_period: u64,
+}
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            reconnect_period: 5,
+        }
+    }
+}
+
+/// Payload of `instruction_status` notification
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstructionNotification {
+    pub id: InstructionID,
+    pub template_id: TemplateID,
+    pub asset_id: AssetID,
+    pub status: InstructionStatus,
+}
+
+impl From<InstructionNotification> for MetricEvent {
+    fn from(notification: InstructionNotification) -> Self {
+        Self::Notification(InstructionEvent {
+            id: notification.id,
+            template_id: notification.template_id,
+            asset_id: notification.asset_id,
+            status: notification.status,
+        })
+    }
+}
+
+/// Connection listening to `instruction_status` channel, notifications are delivered while it is alive
+pub struct InstructionListener {
+    _client: tokio_postgres::Client,
+    connection: JoinHandle<()>,
+}
+
+impl InstructionListener {
+    /// Resolves once the connection is lost
+    pub async fn closed(self) {
+        let _ = self.connection.await;
+    }
+}
+
+/// Fans out instruction notifications to subscribers
+#[derive(Clone)]
+pub struct InstructionNotifier {
+    sender: broadcast::Sender<InstructionNotification>,
+}
+
+impl InstructionNotifier {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Notifications received after subscribing
+    pub fn subscribe(&self) -> broadcast::Receiver<InstructionNotification> {
+        self.sender.subscribe()
+    }
+
+    /// Opens dedicated connection and issues LISTEN, notifications are published once this resolves
+    pub async fn connect(
+        &self,
+        pg_config: &tokio_postgres::Config,
+    ) -> Result<InstructionListener, tokio_postgres::Error>
+    {
+        let (client, mut connection) = pg_config.connect(NoTls).await?;
+        let sender = self.sender.clone();
+        let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+        let connection = tokio::spawn(async move {
+            while let Some(message) = messages.next().await {
+                match message {
+                    Ok(AsyncMessage::Notification(notification)) => {
+                        match serde_json::from_str::<InstructionNotification>(notification.payload()) {
+                            // no subscribers is not an error
+                            Ok(notification) => {
+                                let _ = sender.send(notification);
+                            },
+                            Err(err) => log::warn!(
+                                target: LOG_TARGET,
+                                "Malformed {} notification {}: {}",
+                                INSTRUCTION_STATUS_CHANNEL,
+                                notification.payload(),
+                                err
+                            ),
+                        }
+                    },
+                    Ok(_) => {},
+                    Err(err) => {
+                        log::error!(target: LOG_TARGET, "Listener connection failed: {}", err);
+                        break;
+                    },
+                }
+            }
+        });
+        client
+            .batch_execute(&format!("LISTEN {}", INSTRUCTION_STATUS_CHANNEL))
+            .await?;
+        Ok(InstructionListener {
+            _client: client,
+            connection,
+        })
+    }
+
+    /// Keeps listening in background, reconnecting after `reconnect_period` once connection is lost,
+    /// notifications are reported to [Metrics] if provided
+    pub fn spawn(&self, config: NotifyConfig, pg_config: tokio_postgres::Config, metrics_addr: Option<Addr<Metrics>>) {
+        let notifier = self.clone();
+        actix_rt::spawn(async move {
+            loop {
+                match notifier.connect(&pg_config).await {
+                    Ok(listener) => {
+                        log::info!(target: LOG_TARGET, "Listening to {}", INSTRUCTION_STATUS_CHANNEL);
+                        listener.closed().await;
+                    },
+                    Err(err) => log::error!(target: LOG_TARGET, "Failed to listen to notifications: {}", err),
+                }
+                tokio::time::delay_for(Duration::from_secs(config.reconnect_period.max(1))).await;
+            }
+        });
+        if let Some(metrics_addr) = metrics_addr {
+            let mut notifications = self.subscribe();
+            actix_rt::spawn(async move {
+                loop {
+                    match notifications.recv().await {
+                        Ok(notification) => {
+                            let msg: MetricEvent = notification.into();
+                            metrics_addr.do_send(msg);
+                        },
+                        Err(RecvError::Lagged(count)) => {
+                            log::warn!(target: LOG_TARGET, "Metrics missed {} notifications", count)
+                        },
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+    }
+
+    /// Waits for the next notification matching the filter, None on timeout or if the receiver lagged behind
+    /// and notification might have been missed, callers are expected to re-check the state either way
+    pub async fn wait(
+        notifications: &mut broadcast::Receiver<InstructionNotification>,
+        timeout: Duration,
+        filter: impl Fn(&InstructionNotification) -> bool,
+    ) -> Option<InstructionNotification>
+    {
+        let wait = async {
+            loop {
+                match notifications.recv().await {
+                    Ok(notification) if filter(&notification) => return Some(notification),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => return None,
+                    // sender lives as long as the notifier, fall back to polling
+                    Err(RecvError::Closed) => futures::future::pending::<()>().await,
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.ok().flatten()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::consensus::Instruction,
+        test::utils::{build_test_config, builders::consensus::InstructionBuilder, test_db_client},
+    };
+
+    #[actix_rt::test]
+    async fn notifies_status_changes() {
+        let (client, _lock) = test_db_client().await;
+        let config = build_test_config().unwrap();
+        let notifier = InstructionNotifier::new();
+        let mut notifications = notifier.subscribe();
+        let _listener = notifier
+            .connect(&config.postgres.get_pg_config().unwrap())
+            .await
+            .unwrap();
+
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        let notification = notifications.recv().await.unwrap();
+        assert_eq!(notification.id, instruction.id);
+        assert_eq!(notification.asset_id, instruction.asset_id);
+        assert_eq!(notification.template_id, instruction.template_id);
+        assert_eq!(notification.status, instruction.status);
+
+        Instruction::update_instructions_status(&[instruction.id], None, InstructionStatus::Invalid, None, &client)
+            .await
+            .unwrap();
+        let notification = InstructionNotifier::wait(&mut notifications, Duration::from_secs(1), |n| {
+            n.id == instruction.id
+        })
+        .await
+        .unwrap();
+        assert_eq!(notification.status, InstructionStatus::Invalid);
+    }
+}
@@ -0,0 +1,180 @@
+//! Retention of append only state
+//!
+//! Every committed instruction appends token and asset state records, so history of long-lived assets
+//! grows without bound. Pruning rolls up records older than a cut-off date into [StateSnapshot]s,
+//! keeping the latest `keep_checkpoints` records of every token and asset intact.
+//! Pruning is run by `tvnc prune` or periodically by [RetentionJanitor] when enabled in
+//! `[validator.retention]` section of config or via RETENTION_* env vars.
+
+use super::{
+    models::{StateSnapshot, StateSnapshotKind},
+    utils::{db::in_transaction, errors::DBError},
+};
+use actix::prelude::*;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use deadpool_postgres::{Client, Pool};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+const LOG_TARGET: &'static str = "tari_validator_node::db::retention";
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RetentionConfig {
+    /// Background pruning is disabled unless explicitly enabled by node operator
+    pub enabled: bool,
+    /// Number of latest append only records kept for every token and asset
+    pub keep_checkpoints: u32,
+    /// Records older than this are pruned
+    pub max_age_days: u32,
+    /// Period between pruning runs in seconds
+    pub period: u64,
+}
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keep_checkpoints: 10,
+            max_age_days: 30,
+            period: 3600,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Cut-off date of pruning
+    pub fn before(&self) -> DateTime<Utc> {
+        Utc::now() - ChronoDuration::days(self.max_age_days as i64)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PruneStats {
+    pub token_snapshots: usize,
+    pub token_records: i64,
+    pub asset_snapshots: usize,
+    pub asset_records: i64,
+}
+
+/// Rolls up token and asset append only records created before `before` into snapshots within single transaction
+pub async fn prune(before: DateTime<Utc>, keep_checkpoints: u32, client: &Client) -> Result<PruneStats, DBError> {
+    in_transaction(client, async {
+        let keep_checkpoints = keep_checkpoints as i64;
+        let tokens = StateSnapshot::compact(StateSnapshotKind::Token, before, keep_checkpoints, client).await?;
+        let assets = StateSnapshot::compact(StateSnapshotKind::Asset, before, keep_checkpoints, client).await?;
+        Ok(PruneStats {
+            token_snapshots: tokens.len(),
+            token_records: tokens.iter().map(|snapshot| snapshot.rows_count).sum(),
+            asset_snapshots: assets.len(),
+            asset_records: assets.iter().map(|snapshot| snapshot.rows_count).sum(),
+        })
+    })
+    .await
+}
+
+/// Parses RFC3339 datetime or `YYYY-MM-DD` date (midnight UTC)
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(datetime) => Ok(datetime.with_timezone(&Utc)),
+        Err(_) => Ok(DateTime::from_utc(
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")?.and_hms(0, 0, 0),
+            Utc,
+        )),
+    }
+}
+
+/// Actor pruning append only state every `period` seconds, stops immediately if retention is disabled
+pub struct RetentionJanitor {
+    config: RetentionConfig,
+    pool: Arc<Pool>,
+}
+
+impl RetentionJanitor {
+    pub fn new(config: RetentionConfig, pool: Arc<Pool>) -> Self {
+        Self { config, pool }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let config = self.config.clone();
+        let pool = self.pool.clone();
+        ctx.spawn(
+            async move {
+                let res = match pool.get().await {
+                    Ok(client) => prune(config.before(), config.keep_checkpoints, &client).await,
+                    Err(err) => Err(err.into()),
+                };
+                match res {
+                    Ok(stats) => log::info!(target: LOG_TARGET, "Pruned append only state: {:?}", stats),
+                    Err(err) => log::error!(target: LOG_TARGET, "Failed to prune append only state: {}", err),
+                }
+            }
+            .into_actor(self),
+        );
+    }
+}
+
+impl Actor for RetentionJanitor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.config.enabled {
+            ctx.stop();
+            return;
+        }
+        log::info!(
+            target: LOG_TARGET,
+            "Retention enabled, pruning state older than {} days every {}s",
+            self.config.max_age_days,
+            self.config.period
+        );
+        ctx.run_interval(Duration::from_secs(self.config.period.max(1)), Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{Token, UpdateToken},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, TokenBuilder},
+            test_db_client,
+        },
+    };
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2020-06-01").unwrap(), Utc.ymd(2020, 6, 1).and_hms(0, 0, 0));
+        assert_eq!(
+            parse_date("2020-06-01T10:00:00+02:00").unwrap(),
+            Utc.ymd(2020, 6, 1).and_hms(8, 0, 0)
+        );
+        assert!(parse_date("yesterday").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn prune_state() {
+        let (client, _lock) = test_db_client().await;
+        let mut token = TokenBuilder::default().build(&client).await.unwrap();
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        for step in 0..4 {
+            let update = UpdateToken {
+                append_state_data_json: Some(json!({ "step": step })),
+                ..UpdateToken::default()
+            };
+            token = token.update(update, &instruction, &client).await.unwrap();
+        }
+
+        let stats = prune(Utc::now(), 1, &client).await.unwrap();
+        assert_eq!(stats, PruneStats {
+            token_snapshots: 1,
+            token_records: 3,
+            ..PruneStats::default()
+        });
+        let token = Token::load(token.id, &client).await.unwrap();
+        assert_eq!(token.additional_data_json, json!({ "step": 3 }));
+        assert_eq!(prune(Utc::now(), 1, &client).await.unwrap(), PruneStats::default());
+    }
+}
@@ -0,0 +1,219 @@
+use super::*;
+use crate::db::models::{
+    consensus::{
+        instruction_results::{json_size, truncate},
+        InstructionResult,
+        NewInstruction,
+    },
+    NewToken,
+    TokenStatus,
+};
+use chrono::Utc;
+use futures::future;
+use serde_json::json;
+use std::sync::RwLock;
+
+/// In-memory backend for tests, records are lost when the store is dropped
+///
+/// Records are added with inherent methods mirroring the models, e.g. [MemoryStore::insert_instruction].
+/// Token data starts from `initial_data_json` as in the current state view, constraints beyond unique IDs
+/// are not enforced.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: RwLock<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    instructions: Vec<Instruction>,
+    progress: Vec<InstructionProgress>,
+    results: Vec<InstructionResult>,
+    tokens: Vec<Token>,
+    /// Sequence of generated record IDs
+    next_id: u128,
+}
+
+impl MemoryState {
+    fn next_id(&mut self) -> uuid::Uuid {
+        self.next_id += 1;
+        uuid::Uuid::from_u128(self.next_id)
+    }
+
+    fn instruction_mut(&mut self, id: InstructionID) -> Result<&mut Instruction, DBError> {
+        self.instructions
+            .iter_mut()
+            .find(|instruction| instruction.id == id)
+            .ok_or(DBError::NotFound)
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add instruction record, ID of `params` is kept as is
+    pub fn insert_instruction(&self, params: NewInstruction) -> Result<Instruction, DBError> {
+        let mut state = self.state.write().unwrap();
+        if state.instructions.iter().any(|instruction| instruction.id == params.id) {
+            return Err(DBError::conflict(&format!("Instruction {} already exists", params.id)));
+        }
+        let now = Utc::now();
+        let instruction = Instruction {
+            id: params.id,
+            parent_id: params.parent_id,
+            initiating_node_id: params.initiating_node_id,
+            signature: params.signature,
+            asset_id: params.asset_id,
+            token_id: params.token_id,
+            template_id: params.template_id,
+            contract_name: params.contract_name,
+            status: params.status,
+            params: params.params,
+            result: json!({}),
+            result_ref: None,
+            created_at: now,
+            updated_at: now,
+            proposal_id: None,
+            depends_on: params.depends_on,
+            signer_pubkey: params.signer_pubkey,
+            nonce: params.nonce,
+            priority: params.priority,
+            attempts: 0,
+            batch_index: params.batch_index,
+        };
+        state.instructions.push(instruction.clone());
+        Ok(instruction)
+    }
+
+    /// Appends progress entry to the instruction, see [InstructionProgress::append]
+    pub fn append_progress(&self, instruction_id: InstructionID, data: Value) -> Result<InstructionProgress, DBError> {
+        let mut state = self.state.write().unwrap();
+        state.instruction_mut(instruction_id)?;
+        let progress = InstructionProgress {
+            id: state.progress.len() as i64 + 1,
+            instruction_id,
+            data,
+            created_at: Utc::now(),
+        };
+        state.progress.push(progress.clone());
+        Ok(progress)
+    }
+
+    /// Sets result of the instruction, result exceeding `max_size` bytes is truncated and kept complete
+    /// aside, see [InstructionResult::limit]
+    pub fn limit_result(&self, instruction_id: InstructionID, result: Value, max_size: usize) -> Result<(), DBError> {
+        let mut state = self.state.write().unwrap();
+        state.instruction_mut(instruction_id)?;
+        let size = json_size(&result);
+        if max_size == 0 || size <= max_size {
+            state.instruction_mut(instruction_id)?.result = result;
+            return Ok(());
+        }
+        let complete = InstructionResult {
+            id: state.next_id(),
+            instruction_id,
+            result: result.clone(),
+            size: size as i64,
+            created_at: Utc::now(),
+        };
+        let instruction = state.instruction_mut(instruction_id)?;
+        instruction.result = truncate(result, max_size);
+        instruction.result_ref = Some(complete.id);
+        state.results.push(complete);
+        Ok(())
+    }
+
+    pub fn insert_token(&self, params: NewToken) -> Result<Token, DBError> {
+        let mut state = self.state.write().unwrap();
+        if state.tokens.iter().any(|token| token.token_id == params.token_id) {
+            return Err(DBError::conflict(&format!("Token {} already exists", params.token_id)));
+        }
+        let issue_number = state
+            .tokens
+            .iter()
+            .filter(|token| token.asset_state_id == params.asset_state_id)
+            .map(|token| token.issue_number)
+            .max()
+            .unwrap_or(0) +
+            1;
+        let now = Utc::now();
+        let token = Token {
+            id: state.next_id(),
+            issue_number,
+            status: TokenStatus::Available,
+            token_id: params.token_id,
+            asset_state_id: params.asset_state_id,
+            owner_pubkey: params
+                .initial_data_json
+                .get("owner_pubkey")
+                .and_then(Value::as_str)
+                .map(String::from),
+            additional_data_json: params.initial_data_json.clone(),
+            initial_data_json: params.initial_data_json,
+            created_at: now,
+            updated_at: now,
+            version: 0,
+        };
+        state.tokens.push(token.clone());
+        Ok(token)
+    }
+
+    fn read<R, F>(&self, f: F) -> BoxFuture<'_, Result<R, DBError>>
+    where
+        R: Send + 'static,
+        F: FnOnce(&MemoryState) -> Result<R, DBError>,
+    {
+        Box::pin(future::ready(f(&self.state.read().unwrap())))
+    }
+}
+
+impl InstructionStore for MemoryStore {
+    fn find_instruction(&self, id: InstructionID) -> BoxFuture<'_, Result<Option<Instruction>, DBError>> {
+        self.read(|state| Ok(state.instructions.iter().find(|instruction| instruction.id == id).cloned()))
+    }
+
+    fn find_instruction_progress(&self, id: InstructionID) -> BoxFuture<'_, Result<Vec<InstructionProgress>, DBError>> {
+        self.read(|state| {
+            Ok(state
+                .progress
+                .iter()
+                .filter(|progress| progress.instruction_id == id)
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn find_subinstructions<'a>(
+        &'a self,
+        instruction: &'a Instruction,
+    ) -> BoxFuture<'a, Result<Vec<Instruction>, DBError>>
+    {
+        self.read(|state| {
+            Ok(state
+                .instructions
+                .iter()
+                .filter(|subinstruction| subinstruction.parent_id == Some(instruction.id))
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn complete_result<'a>(&'a self, instruction: &'a Instruction) -> BoxFuture<'a, Result<Value, DBError>> {
+        self.read(|state| match instruction.result_ref {
+            Some(id) => state
+                .results
+                .iter()
+                .find(|result| result.id == id)
+                .map(|result| result.result.clone())
+                .ok_or(DBError::NotFound),
+            None => Ok(instruction.result.clone()),
+        })
+    }
+}
+
+impl TokenStore for MemoryStore {
+    fn find_token<'a>(&'a self, token_id: &'a TokenID) -> BoxFuture<'a, Result<Option<Token>, DBError>> {
+        self.read(|state| Ok(state.tokens.iter().find(|token| &token.token_id == token_id).cloned()))
+    }
+}
@@ -0,0 +1,163 @@
+//! Read-only storage backend of the API
+//!
+//! Instruction and token controllers read records through [InstructionStore] and [TokenStore], any type
+//! implementing both is a [Store]. [PgStore] delegates to the models on top of the read pool, [MemoryStore]
+//! keeps records in memory for controller tests.
+//!
+//! Stores cover only these lookups: templates, consensus and instruction lifecycle write within Postgres
+//! transactions on pool clients and use the models directly, so Postgres is the only backend of a node.
+
+use super::{
+    models::{
+        consensus::{Instruction, InstructionProgress},
+        Token,
+    },
+    utils::errors::DBError,
+};
+use crate::types::{InstructionID, TokenID};
+use futures::future::BoxFuture;
+use serde_json::Value;
+
+mod memory;
+mod postgres;
+
+pub use memory::MemoryStore;
+pub use postgres::PgStore;
+
+pub trait InstructionStore: Send + Sync {
+    fn find_instruction(&self, id: InstructionID) -> BoxFuture<'_, Result<Option<Instruction>, DBError>>;
+    /// Progress entries reported by contract of the instruction, see [InstructionProgress]
+    fn find_instruction_progress(&self, id: InstructionID) -> BoxFuture<'_, Result<Vec<InstructionProgress>, DBError>>;
+    /// Instructions created by contract of the instruction, see [Instruction::load_subinstructions]
+    fn find_subinstructions<'a>(
+        &'a self,
+        instruction: &'a Instruction,
+    ) -> BoxFuture<'a, Result<Vec<Instruction>, DBError>>;
+    /// Complete result of the instruction, see [InstructionResult::complete]
+    ///
+    /// [InstructionResult::complete]: crate::db::models::consensus::InstructionResult::complete
+    fn complete_result<'a>(&'a self, instruction: &'a Instruction) -> BoxFuture<'a, Result<Value, DBError>>;
+}
+
+pub trait TokenStore: Send + Sync {
+    fn find_token<'a>(&'a self, token_id: &'a TokenID) -> BoxFuture<'a, Result<Option<Token>, DBError>>;
+}
+
+/// Storage backend implementing every store
+pub trait Store: InstructionStore + TokenStore {}
+
+impl<T: InstructionStore + TokenStore> Store for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{
+            consensus::{InstructionResult, NewInstruction, UpdateInstruction},
+            NewToken,
+        },
+        test::utils::{
+            actix_test_pool,
+            builders::{consensus::InstructionBuilder, TokenBuilder},
+            test_db_client,
+            Test,
+        },
+    };
+    use serde_json::json;
+
+    const MAX_RESULT_SIZE: usize = 20;
+
+    fn result() -> Value {
+        json!({ "tokens": ["a", "b", "c", "d", "e", "f"] })
+    }
+
+    /// Same lookups are run against every backend, both have the instruction with two progress entries,
+    /// a subinstruction and result exceeding [MAX_RESULT_SIZE], and the token
+    async fn exercise(store: &dyn Store, id: InstructionID, token_id: TokenID) {
+        let instruction = store.find_instruction(id).await.unwrap().unwrap();
+        assert!(instruction.result_ref.is_some());
+        assert_ne!(instruction.result, result());
+        assert_eq!(store.complete_result(&instruction).await.unwrap(), result());
+        assert!(store.find_instruction(Test::<InstructionID>::new()).await.unwrap().is_none());
+
+        let progress = store.find_instruction_progress(id).await.unwrap();
+        let data: Vec<_> = progress.into_iter().map(|progress| progress.data).collect();
+        assert_eq!(data, vec![json!({ "step": 1 }), json!({ "step": 2 })]);
+
+        let subinstructions = store.find_subinstructions(&instruction).await.unwrap();
+        assert_eq!(subinstructions.len(), 1);
+        assert_eq!(subinstructions[0].parent_id, Some(id));
+        assert!(store.find_subinstructions(&subinstructions[0]).await.unwrap().is_empty());
+
+        let token = store.find_token(&token_id).await.unwrap().unwrap();
+        assert_eq!(token.token_id, token_id);
+        assert!(store.find_token(&Test::<TokenID>::new()).await.unwrap().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn memory_store() {
+        let store = MemoryStore::new();
+        let instruction = store
+            .insert_instruction(NewInstruction {
+                id: Test::<InstructionID>::new(),
+                ..NewInstruction::default()
+            })
+            .unwrap();
+        store
+            .insert_instruction(NewInstruction {
+                id: Test::<InstructionID>::new(),
+                parent_id: Some(instruction.id),
+                ..NewInstruction::default()
+            })
+            .unwrap();
+        store.append_progress(instruction.id, json!({ "step": 1 })).unwrap();
+        store.append_progress(instruction.id, json!({ "step": 2 })).unwrap();
+        store.limit_result(instruction.id, result(), MAX_RESULT_SIZE).unwrap();
+        let token_id = Test::<TokenID>::new();
+        store
+            .insert_token(NewToken {
+                token_id: token_id.clone(),
+                asset_state_id: uuid::Uuid::nil(),
+                initial_data_json: json!({}),
+            })
+            .unwrap();
+
+        exercise(&store, instruction.id, token_id).await;
+    }
+
+    #[actix_rt::test]
+    async fn pg_store() {
+        let (client, _lock) = test_db_client().await;
+        let instruction = InstructionBuilder::default().build(&client).await.unwrap();
+        InstructionBuilder {
+            parent_id: Some(instruction.id),
+            asset_id: Some(instruction.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        InstructionProgress::append(instruction.id, json!({ "step": 1 }), &client)
+            .await
+            .unwrap();
+        InstructionProgress::append(instruction.id, json!({ "step": 2 }), &client)
+            .await
+            .unwrap();
+        let truncated = InstructionResult::limit(instruction.id, result(), MAX_RESULT_SIZE, &client)
+            .await
+            .unwrap();
+        let instruction = instruction
+            .update(
+                UpdateInstruction {
+                    result: Some(truncated),
+                    ..UpdateInstruction::default()
+                },
+                &client,
+            )
+            .await
+            .unwrap();
+        let token = TokenBuilder::default().build(&client).await.unwrap();
+
+        exercise(&PgStore::new(actix_test_pool()), instruction.id, token.token_id).await;
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+use crate::db::{models::consensus::InstructionResult, utils::instrument::get_client};
+use deadpool_postgres::{Client, Pool};
+use std::sync::Arc;
+
+/// Postgres backend delegating to the models, every call takes a client from the pool
+#[derive(Clone)]
+pub struct PgStore {
+    pool: Arc<Pool>,
+}
+
+impl PgStore {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        Self { pool }
+    }
+
+    async fn client(&self) -> Result<Client, DBError> {
+        Ok(get_client(&self.pool).await?)
+    }
+}
+
+impl InstructionStore for PgStore {
+    fn find_instruction(&self, id: InstructionID) -> BoxFuture<'_, Result<Option<Instruction>, DBError>> {
+        Box::pin(async move { Instruction::find(id, &self.client().await?).await })
+    }
+
+    fn find_instruction_progress(&self, id: InstructionID) -> BoxFuture<'_, Result<Vec<InstructionProgress>, DBError>> {
+        Box::pin(async move { InstructionProgress::find_by_instruction_id(id, &self.client().await?).await })
+    }
+
+    fn find_subinstructions<'a>(
+        &'a self,
+        instruction: &'a Instruction,
+    ) -> BoxFuture<'a, Result<Vec<Instruction>, DBError>>
+    {
+        Box::pin(async move { instruction.load_subinstructions(&self.client().await?).await })
+    }
+
+    fn complete_result<'a>(&'a self, instruction: &'a Instruction) -> BoxFuture<'a, Result<Value, DBError>> {
+        Box::pin(async move { InstructionResult::complete(instruction, &self.client().await?).await })
+    }
+}
+
+impl TokenStore for PgStore {
+    fn find_token<'a>(&'a self, token_id: &'a TokenID) -> BoxFuture<'a, Result<Option<Token>, DBError>> {
+        Box::pin(async move { Token::find_by_token_id(token_id, &self.client().await?).await })
+    }
+}
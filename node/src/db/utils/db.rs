@@ -1,12 +1,46 @@
-use super::errors::DBError;
+use super::{errors::DBError, instrument::get_client};
 use crate::{config::NodeConfig, db::migrations::migrate};
-use deadpool_postgres::{config::Config as DeadpoolConfig, Pool};
-use tokio_postgres::{Config as PgConfig, NoTls};
+use deadpool_postgres::{config::Config as DeadpoolConfig, Client, Pool};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::delay_for;
+use tokio_postgres::{
+    config::Host,
+    error::{Error as PgError, SqlState},
+    Config as PgConfig,
+    NoTls,
+};
+
+const LOG_TARGET: &'static str = "tari_validator_node::db::utils::db";
+/// First delay between connection attempts, doubled after every failed attempt
+const RETRY_DELAY_INITIAL: Duration = Duration::from_millis(250);
+const RETRY_DELAY_MAX: Duration = Duration::from_secs(5);
 
 pub fn build_pool(config: &DeadpoolConfig) -> Result<Pool, DBError> {
     Ok(config.create_pool(NoTls)?)
 }
 
+/// Pool of read replica connections, see [NodeConfig::postgres_read]
+#[derive(Clone)]
+pub struct ReadPool(pub Arc<Pool>);
+
+impl ReadPool {
+    /// Builds replica pool if configured, otherwise reads share `primary` pool
+    pub fn build(config: &NodeConfig, primary: Arc<Pool>) -> Result<Self, DBError> {
+        match config.postgres_read.as_ref() {
+            Some(pg_read) => Ok(Self(Arc::new(build_pool(pg_read)?))),
+            None => Ok(Self(primary)),
+        }
+    }
+
+    pub async fn get(&self) -> Result<Client, DBError> {
+        Ok(get_client(&self.0).await?)
+    }
+}
+
 /// Creates to postgres database without the pool
 pub async fn connect_raw(pg: PgConfig) -> Result<tokio_postgres::Client, DBError> {
     let (client, connection) = pg.connect(NoTls).await?;
@@ -20,6 +54,81 @@ pub async fn connect_raw(pg: PgConfig) -> Result<tokio_postgres::Client, DBError
     Ok(client)
 }
 
+/// Waits for Postgres to accept connections, retrying with exponential backoff up to `timeout`
+///
+/// Pools connect lazily, so without waiting node would fail on first query when Postgres starts after it,
+/// e.g. in docker compose. Rejected credentials and missing database are not retried.
+pub async fn wait_for_postgres(mut pg: PgConfig, timeout: Duration) -> Result<(), DBError> {
+    let target = describe_target(&pg);
+    let started = Instant::now();
+    let mut delay = RETRY_DELAY_INITIAL;
+    loop {
+        let remaining = timeout.checked_sub(started.elapsed()).unwrap_or_default();
+        pg.connect_timeout(remaining.max(RETRY_DELAY_INITIAL));
+        let err = match pg.connect(NoTls).await {
+            Ok(_) => return Ok(()),
+            Err(err) => err,
+        };
+        match err.code() {
+            Some(code) if is_auth_failure(code) => {
+                return Err(DBError::Authentication {
+                    target,
+                    msg: format!("{}, check PG_USER and PG_PASSWORD", err),
+                });
+            },
+            Some(code) if code == &SqlState::INVALID_CATALOG_NAME => {
+                log::error!(target: LOG_TARGET, "Database does not exist, run `tvnc init` to create it");
+                return Err(err.into());
+            },
+            _ => {},
+        }
+        if started.elapsed() + delay > timeout {
+            return Err(DBError::Unreachable {
+                target,
+                msg: format!("{} (gave up after {}s)", cause(&err), started.elapsed().as_secs()),
+            });
+        }
+        log::warn!(
+            target: LOG_TARGET,
+            "Postgres at {} is not available yet: {}, retrying in {}ms",
+            target,
+            cause(&err),
+            delay.as_millis()
+        );
+        delay_for(delay).await;
+        delay = (delay * 2).min(RETRY_DELAY_MAX);
+    }
+}
+
+/// Hosts and ports connection is attempted to, e.g. `database:5432`
+fn describe_target(pg: &PgConfig) -> String {
+    let ports = pg.get_ports();
+    pg.get_hosts()
+        .iter()
+        .enumerate()
+        .map(|(i, host)| {
+            let port = ports.get(i).or(ports.first()).copied().unwrap_or(5432);
+            match host {
+                Host::Tcp(host) => format!("{}:{}", host, port),
+                Host::Unix(path) => format!("{}:{}", path.display(), port),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn is_auth_failure(code: &SqlState) -> bool {
+    code == &SqlState::INVALID_PASSWORD || code == &SqlState::INVALID_AUTHORIZATION_SPECIFICATION
+}
+
+/// Connection errors are wrapped, source tells refused connection from failed DNS lookup
+fn cause(err: &PgError) -> String {
+    match std::error::Error::source(err) {
+        Some(source) => format!("{}: {}", err, source),
+        None => err.to_string(),
+    }
+}
+
 pub async fn db_client_raw(config: &NodeConfig) -> Result<tokio_postgres::Client, DBError> {
     let pg_config = config.postgres.get_pg_config()?;
     connect_raw(pg_config).await
@@ -31,6 +140,36 @@ pub async fn db_client(config: &NodeConfig) -> Result<deadpool_postgres::Client,
     Ok(pool.get().await?)
 }
 
+/// Runs future within a DB transaction, committing on success and rolling back on error.
+/// All the queries of the future should be executed via the same `client`.
+/// If node crashes midway, postgres rolls back transaction on disconnect, so no partial state is left.
+pub async fn in_transaction<T, E, F>(client: &Client, fut: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<DBError>,
+{
+    #[cfg(feature = "chaos")]
+    crate::chaos::delay_query().await;
+    client.batch_execute("BEGIN").await.map_err(DBError::from)?;
+    match fut.await {
+        #[cfg(feature = "chaos")]
+        Ok(_) if crate::chaos::fail_transaction() => {
+            client.batch_execute("ROLLBACK").await.map_err(DBError::from)?;
+            Err(DBError::Injected("transaction failure".into()).into())
+        },
+        Ok(res) => {
+            client.batch_execute("COMMIT").await.map_err(DBError::from)?;
+            Ok(res)
+        },
+        Err(err) => {
+            if let Err(rollback_err) = client.batch_execute("ROLLBACK").await {
+                log::error!("Failed to rollback transaction: {}", rollback_err);
+            }
+            Err(err)
+        },
+    }
+}
+
 /// Creates database for validator node.
 /// Dataase name specified either as `PG_DBNAME` env
 /// or `validator.postgres.dbname` config parameter
@@ -76,8 +215,22 @@ pub async fn reset_database(config: NodeConfig) -> Result<(), DBError> {
 
 #[cfg(test)]
 mod test {
-    use super::reset_database;
-    use crate::test::utils::{build_test_config, load_env, test_pool};
+    use super::{in_transaction, reset_database, wait_for_postgres, DBError, PgConfig, ReadPool};
+    use crate::{
+        db::models::AssetState,
+        test::utils::{
+            actix_test_pool,
+            build_test_config,
+            builders::AssetStateBuilder,
+            load_env,
+            test_db_client,
+            test_pool,
+        },
+    };
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
     #[actix_rt::test]
     async fn test_reset_database() -> anyhow::Result<()> {
@@ -87,4 +240,72 @@ mod test {
         reset_database(config).await?;
         Ok(())
     }
+
+    #[actix_rt::test]
+    async fn transaction_rollback() -> anyhow::Result<()> {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await?;
+
+        let res: Result<(), DBError> = in_transaction(&client, async {
+            let mut asset = AssetState::load(asset.id, &client).await?;
+            assert!(asset.acquire_lock(60, &client).await?);
+            Err(DBError::NotFound)
+        })
+        .await;
+        assert!(res.is_err());
+        let rolled_back = AssetState::load(asset.id, &client).await?;
+        assert_eq!(rolled_back.blocked_until, asset.blocked_until);
+
+        in_transaction(&client, async {
+            let mut asset = AssetState::load(asset.id, &client).await?;
+            assert!(asset.acquire_lock(60, &client).await?);
+            Ok::<_, DBError>(())
+        })
+        .await?;
+        let committed = AssetState::load(asset.id, &client).await?;
+        assert!(committed.blocked_until > asset.blocked_until);
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn read_pool() -> anyhow::Result<()> {
+        load_env();
+        let mut config = build_test_config().unwrap();
+        let primary = actix_test_pool();
+        let read_pool = ReadPool::build(&config, primary.clone())?;
+        assert!(Arc::ptr_eq(&read_pool.0, &primary));
+
+        config.postgres_read = Some(config.postgres.clone());
+        let read_pool = ReadPool::build(&config, primary.clone())?;
+        assert!(!Arc::ptr_eq(&read_pool.0, &primary));
+        let client = read_pool.get().await?;
+        assert_eq!(client.query_one("SELECT 1", &[]).await?.get::<_, i32>(0), 1);
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn wait_for_postgres_diagnostics() -> anyhow::Result<()> {
+        load_env();
+        let config = build_test_config().unwrap();
+        let pg = config.postgres.get_pg_config()?;
+        wait_for_postgres(pg.clone(), Duration::from_secs(5)).await?;
+
+        let mut wrong_password = pg.clone();
+        wrong_password.password("definitely not the password");
+        let started = Instant::now();
+        match wait_for_postgres(wrong_password, Duration::from_secs(5)).await {
+            // trust authentication of local test server accepts any password
+            Ok(()) => {},
+            Err(DBError::Authentication { .. }) => assert!(started.elapsed() < Duration::from_secs(1)),
+            Err(err) => panic!("Unexpected error: {}", err),
+        }
+
+        let mut unreachable = PgConfig::new();
+        unreachable.host("127.0.0.1").port(1).user("postgres");
+        let started = Instant::now();
+        let err = wait_for_postgres(unreachable, Duration::from_secs(1)).await.unwrap_err();
+        assert!(matches!(err, DBError::Unreachable { ref target, .. } if target == "127.0.0.1:1"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        Ok(())
+    }
 }
@@ -25,14 +25,47 @@ pub enum DBError {
     BadQuery { msg: String },
     #[error("Not found")]
     NotFound,
+    #[error("Conflict with concurrent update: {msg}")]
+    Conflict { msg: String },
     #[error("UUID error: {0}")]
     UUIDError(#[from] UUIDError),
+    #[error("Postgres at {target} rejected credentials: {msg}")]
+    Authentication { target: String, msg: String },
+    #[error("Postgres at {target} is unreachable: {msg}")]
+    Unreachable { target: String, msg: String },
     #[error("Validation error: {0}")]
     Validation(#[from] ValidationErrors),
+    #[cfg(feature = "chaos")]
+    #[error("Injected fault: {0}")]
+    Injected(String),
 }
 
 impl DBError {
     pub fn bad_query(msg: &str) -> Self {
         Self::BadQuery { msg: msg.into() }
     }
+
+    pub fn conflict(msg: &str) -> Self {
+        Self::Conflict { msg: msg.into() }
+    }
+
+    /// Failures which might not repeat on retry: lost connection, exhausted pool,
+    /// serialization failure, deadlock or server shutdown
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Pool(_) | Self::Unreachable { .. } => true,
+            Self::Postgres(err) => match err.code().map(|code| code.code()) {
+                Some("40001") | Some("40P01") | Some("57P01") => true,
+                Some(code) => code.starts_with("08"),
+                None => {
+                    // connection errors carry IO error as the source
+                    let io_error = std::error::Error::source(err).map_or(false, |source| source.is::<std::io::Error>());
+                    err.is_closed() || io_error
+                },
+            },
+            #[cfg(feature = "chaos")]
+            Self::Injected(_) => true,
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,160 @@
+//! DB pool and query instrumentation
+//!
+//! Clients taken via [get_client] record how long the pool made the caller wait, [Metrics](crate::metrics::Metrics)
+//! collects the waits every second along with pool size and available connections.
+//! Queries wrapped into [timed_query!](crate::timed_query) are logged with the calling module when they take
+//! longer than `threshold_ms` of `[validator.slow_query]` config section, overloaded with SLOW_QUERY_* env vars.
+
+use deadpool_postgres::{Client, Pool, PoolError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+const LOG_TARGET: &'static str = "tari_validator_node::db::slow_query";
+/// Logged statements are cut to this many characters
+const QUERY_LOG_MAX_LEN: usize = 200;
+
+/// Slow query threshold in milliseconds, 0 disables logging
+static SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(500);
+static POOL_WAITS: AtomicU64 = AtomicU64::new(0);
+static POOL_WAIT_TOTAL_US: AtomicU64 = AtomicU64::new(0);
+static POOL_WAIT_MAX_US: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SlowQueryConfig {
+    pub enabled: bool,
+    /// Queries taking longer than this are logged as warnings
+    pub threshold_ms: u64,
+}
+impl Default for SlowQueryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_ms: 500,
+        }
+    }
+}
+
+impl SlowQueryConfig {
+    /// Applies config to queries of the whole process
+    pub fn apply(&self) {
+        let threshold_ms = if self.enabled { self.threshold_ms } else { 0 };
+        SLOW_QUERY_THRESHOLD_MS.store(threshold_ms, Ordering::Relaxed);
+    }
+}
+
+/// Waits for DB connections since previous [PoolWait::take]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct PoolWait {
+    pub count: u64,
+    pub avg_ms: u64,
+    pub max_ms: u64,
+}
+
+impl PoolWait {
+    /// Collects recorded waits and resets counters
+    pub fn take() -> Self {
+        let count = POOL_WAITS.swap(0, Ordering::Relaxed);
+        let total_us = POOL_WAIT_TOTAL_US.swap(0, Ordering::Relaxed);
+        let max_us = POOL_WAIT_MAX_US.swap(0, Ordering::Relaxed);
+        Self {
+            count,
+            avg_ms: if count > 0 { total_us / count / 1000 } else { 0 },
+            max_ms: max_us / 1000,
+        }
+    }
+}
+
+/// Takes client from the pool recording wait time
+pub async fn get_client(pool: &Pool) -> Result<Client, PoolError> {
+    let started = Instant::now();
+    let client = pool.get().await;
+    let waited_us = started.elapsed().as_micros() as u64;
+    POOL_WAITS.fetch_add(1, Ordering::Relaxed);
+    POOL_WAIT_TOTAL_US.fetch_add(waited_us, Ordering::Relaxed);
+    let mut max_us = POOL_WAIT_MAX_US.load(Ordering::Relaxed);
+    while waited_us > max_us {
+        match POOL_WAIT_MAX_US.compare_exchange_weak(max_us, waited_us, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => break,
+            Err(current) => max_us = current,
+        }
+    }
+    client
+}
+
+/// Awaits query future logging `query` if it took longer than slow query threshold,
+/// use [timed_query!](crate::timed_query) to fill in the calling module
+pub async fn log_slow<F: Future>(module: &str, query: &str, fut: F) -> F::Output {
+    let threshold_ms = SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed);
+    if threshold_ms == 0 {
+        return fut.await;
+    }
+    let started = Instant::now();
+    let res = fut.await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if elapsed_ms >= threshold_ms {
+        log::warn!(
+            target: LOG_TARGET,
+            "Slow query in {} took {}ms: {}",
+            module,
+            elapsed_ms,
+            compact_query(query)
+        );
+    }
+    res
+}
+
+/// Query text on a single line, cut to [QUERY_LOG_MAX_LEN]
+fn compact_query(query: &str) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    match query.char_indices().nth(QUERY_LOG_MAX_LEN) {
+        Some((idx, _)) => format!("{}...", &query[..idx]),
+        None => query,
+    }
+}
+
+/// Runs query future logging slow ones with the calling module, see [log_slow]
+///
+/// ```ignore
+/// let rows = timed_query!(QUERY, client.query(&stmt, &[&id])).await?;
+/// ```
+#[macro_export]
+macro_rules! timed_query {
+    ($query:expr, $fut:expr) => {
+        $crate::db::utils::instrument::log_slow(module_path!(), $query, $fut)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::actix_test_pool;
+
+    #[test]
+    fn compact_long_queries() {
+        assert_eq!(
+            compact_query("SELECT *\n    FROM instructions\n    WHERE id = $1"),
+            "SELECT * FROM instructions WHERE id = $1"
+        );
+        let long = format!("SELECT {}", "a, ".repeat(100));
+        let compacted = compact_query(&long);
+        assert_eq!(compacted.len(), QUERY_LOG_MAX_LEN + 3);
+        assert!(compacted.ends_with("..."));
+    }
+
+    #[actix_rt::test]
+    async fn pool_waits() {
+        let pool = actix_test_pool();
+        let _ = PoolWait::take();
+        let client = get_client(&pool).await.unwrap();
+        let rows = timed_query!("SELECT 1", client.query("SELECT 1", &[])).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        let wait = PoolWait::take();
+        assert!(wait.count >= 1);
+        assert!(wait.max_ms >= wait.avg_ms);
+    }
+}
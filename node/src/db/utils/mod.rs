@@ -1,3 +1,5 @@
 pub mod db;
 pub mod errors;
+pub mod instrument;
+pub mod statements;
 pub mod validation;
@@ -0,0 +1,129 @@
+//! Prepared statements reuse
+//!
+//! Pooled [deadpool_postgres::Client] keeps a per-connection statement cache keyed by query text,
+//! so `client.prepare(QUERY)` on a pooled client prepares the query only once per connection.
+//! The cache is bypassed though when client is dereferenced into [tokio_postgres::Client]
+//! or a raw connection is used (e.g. by CLI). Queries accepting both kinds of clients should be
+//! generic over [CachedStatements], raw connections are wrapped into [CachedClient].
+
+use super::errors::DBError;
+use futures::future::BoxFuture;
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    sync::{Arc, RwLock},
+};
+use tokio_postgres::Statement;
+
+/// Client preparing statements once per connection
+pub trait CachedStatements: Sync {
+    /// Connection executing prepared statements
+    fn connection(&self) -> &tokio_postgres::Client;
+    /// Prepare `query` or return statement prepared earlier on this connection
+    fn prepare_cached<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Statement, DBError>>;
+}
+
+impl CachedStatements for deadpool_postgres::Client {
+    fn connection(&self) -> &tokio_postgres::Client {
+        &***self
+    }
+
+    fn prepare_cached<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Statement, DBError>> {
+        // deadpool ClientWrapper::prepare is cached
+        Box::pin(async move {
+            #[cfg(feature = "chaos")]
+            crate::chaos::delay_query().await;
+            Ok(self.prepare(query).await?)
+        })
+    }
+}
+
+impl<T: CachedStatements + Send> CachedStatements for Arc<T> {
+    fn connection(&self) -> &tokio_postgres::Client {
+        (**self).connection()
+    }
+
+    fn prepare_cached<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Statement, DBError>> {
+        (**self).prepare_cached(query)
+    }
+}
+
+impl<T: CachedStatements> CachedStatements for &T {
+    fn connection(&self) -> &tokio_postgres::Client {
+        (**self).connection()
+    }
+
+    fn prepare_cached<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Statement, DBError>> {
+        (**self).prepare_cached(query)
+    }
+}
+
+/// Raw connection with statements cache, derefs into [tokio_postgres::Client]
+pub struct CachedClient {
+    client: tokio_postgres::Client,
+    statements: RwLock<HashMap<String, Statement>>,
+}
+
+impl CachedClient {
+    pub fn new(client: tokio_postgres::Client) -> Self {
+        Self {
+            client,
+            statements: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Number of statements prepared on this connection
+    pub fn cached_count(&self) -> usize {
+        self.statements.read().unwrap().len()
+    }
+}
+
+impl Deref for CachedClient {
+    type Target = tokio_postgres::Client;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl CachedStatements for CachedClient {
+    fn connection(&self) -> &tokio_postgres::Client {
+        &self.client
+    }
+
+    fn prepare_cached<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Statement, DBError>> {
+        Box::pin(async move {
+            #[cfg(feature = "chaos")]
+            crate::chaos::delay_query().await;
+            let cached = self.statements.read().unwrap().get(query).cloned();
+            if let Some(stmt) = cached {
+                return Ok(stmt);
+            }
+            let stmt = self.client.prepare(query).await?;
+            self.statements.write().unwrap().insert(query.to_string(), stmt.clone());
+            Ok(stmt)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::utils::db::db_client_raw,
+        test::utils::{build_test_config, load_env},
+    };
+
+    #[actix_rt::test]
+    async fn reuse_statements() {
+        load_env();
+        let config = build_test_config().unwrap();
+        let client = CachedClient::new(db_client_raw(&config).await.unwrap());
+        let stmt = client.prepare_cached("SELECT $1::TEXT").await.unwrap();
+        client.prepare_cached("SELECT $1::TEXT").await.unwrap();
+        client.prepare_cached("SELECT 1").await.unwrap();
+        assert_eq!(client.cached_count(), 2);
+        let row = client.query_one(&stmt, &[&"cached"]).await.unwrap();
+        assert_eq!(row.get::<_, String>(0), "cached");
+    }
+}
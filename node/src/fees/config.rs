@@ -0,0 +1,71 @@
+use crate::types::TemplateID;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FeesConfig {
+    /// Committed instructions are not charged when disabled
+    pub enabled: bool,
+    /// Fee of contracts missing in the schedule
+    pub default_fee: i64,
+    /// Fee schedule keyed by template type, e.g. `[validator.fees.templates.1]`
+    #[serde(default)]
+    pub templates: HashMap<String, TemplateFees>,
+}
+impl Default for FeesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_fee: 0,
+            templates: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TemplateFees {
+    /// Fee of template contracts missing in `contracts`, falls back to `default_fee` if not set
+    pub fee: Option<i64>,
+    /// Per-contract fees keyed by contract name
+    #[serde(default)]
+    pub contracts: HashMap<String, i64>,
+}
+
+impl FeesConfig {
+    /// Fee charged for committed instruction of the contract, the most specific schedule entry wins
+    pub fn fee(&self, template_id: &TemplateID, contract_name: &str) -> i64 {
+        let template = self.templates.get(&template_id.template_type().to_string());
+        template
+            .and_then(|template| template.contracts.get(contract_name).cloned().or(template.fee))
+            .unwrap_or(self.default_fee)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fee_schedule() {
+        let mut templates = HashMap::new();
+        templates.insert("1".to_string(), TemplateFees {
+            fee: Some(5),
+            contracts: vec![("transfer_token".to_string(), 10)].into_iter().collect(),
+        });
+        templates.insert("2".to_string(), TemplateFees {
+            fee: None,
+            contracts: vec![("sell_token".to_string(), 20)].into_iter().collect(),
+        });
+        let config = FeesConfig {
+            enabled: true,
+            default_fee: 1,
+            templates,
+        };
+        assert_eq!(config.fee(&1.into(), "transfer_token"), 10);
+        assert_eq!(config.fee(&1.into(), "issue_tokens"), 5);
+        assert_eq!(config.fee(&2.into(), "sell_token"), 20);
+        assert_eq!(config.fee(&2.into(), "issue_tokens"), 1);
+        assert_eq!(config.fee(&3.into(), "transfer_token"), 1);
+    }
+}
@@ -0,0 +1,71 @@
+//! Fees charged for instructions committed by consensus
+//!
+//! Fee schedule is configured per template and per contract in `[validator.fees]` section of config,
+//! only `enabled` and `default_fee` can be set via FEES_* env vars:
+//! ```toml
+//! [validator.fees]
+//! enabled = true
+//! default_fee = 1
+//! templates = { 1 = { fee = 5, contracts = { transfer_token = 10 } } }
+//! ```
+//!
+//! Fee is debited from the wallet of the instruction signer within the same DB transaction which commits
+//! the instruction and is recorded in `fee_ledger` along with asset issuer, see [FeeLedger].
+//! Instructions which signer has no wallet or not enough balance are recorded as Unpaid.
+//! Accumulated fees per asset issuer are served by `/admin/fees` endpoint.
+
+mod config;
+pub use config::{FeesConfig, TemplateFees};
+
+use crate::{
+    db::{
+        models::{consensus::Instruction, AssetState, FeeLedger, NewFeeLedger},
+        utils::errors::DBError,
+    },
+    types::{AssetID, InstructionID},
+};
+use deadpool_postgres::Client;
+
+pub const LOG_TARGET: &'static str = "tari_validator_node::fees";
+
+/// Charges fees of committed instructions of the asset, instructions charged before are skipped
+pub async fn charge_instructions(
+    config: &FeesConfig,
+    asset_id: &AssetID,
+    instruction_ids: &[InstructionID],
+    client: &Client,
+) -> Result<(), DBError>
+{
+    if !config.enabled || instruction_ids.is_empty() {
+        return Ok(());
+    }
+    let asset = AssetState::find_by_asset_id(asset_id, client)
+        .await?
+        .ok_or_else(|| DBError::NotFound)?;
+    for id in instruction_ids {
+        let instruction = Instruction::load(*id, client).await?;
+        let amount = config.fee(&instruction.template_id, &instruction.contract_name);
+        if amount <= 0 {
+            continue;
+        }
+        let params = NewFeeLedger {
+            instruction_id: instruction.id,
+            asset_id: asset.asset_id.clone(),
+            asset_issuer_pub_key: asset.asset_issuer_pub_key.clone(),
+            payer_pubkey: instruction.signer_pubkey,
+            template_id: instruction.template_id,
+            contract_name: instruction.contract_name,
+            amount,
+        };
+        if let Some(fee) = FeeLedger::charge(params, client).await? {
+            log::debug!(
+                target: LOG_TARGET,
+                "Instruction {} fee {} is {}",
+                fee.instruction_id,
+                fee.amount,
+                fee.status
+            );
+        }
+    }
+    Ok(())
+}
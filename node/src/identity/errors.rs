@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+/// Errors of node identity management
+#[derive(Error, Debug)]
+pub enum IdentityError {
+    #[error("FS error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Json parsing error: {0}")]
+    JSON(#[from] serde_json::Error),
+    #[error("Node identity not found at {path}, run `tvnc identity init` to create one")]
+    NotFound { path: String },
+    #[error("Node identity already exists at {path}")]
+    AlreadyExists { path: String },
+    #[error("Invalid node identity keys: {0}")]
+    InvalidKeys(String),
+    #[error("Signing failed: {0}")]
+    Signature(String),
+}
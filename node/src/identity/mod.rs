@@ -0,0 +1,291 @@
+//! Node identity: persistent keypair of the validator node
+//!
+//! [NodeIdentity] is generated once via `tvnc identity init` and stored under the data dir,
+//! it defines [NodeID] of the node which is embedded in every [crate::types::InstructionID]
+//! and used to sign consensus messages.
+
+use crate::types::NodeID;
+use digest::Digest;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryInto, fmt, path::Path};
+use tari_core::transactions::{
+    crypto::{
+        common::Blake256,
+        keys::{PublicKey as PK, SecretKey as SK},
+        tari_utilities::{hex::Hex, ByteArray},
+    },
+    types::{PrivateKey, PublicKey, Signature},
+};
+
+mod errors;
+pub use errors::IdentityError;
+
+pub const LOG_TARGET: &'static str = "tari_validator_node::identity";
+
+/// Serialized form of [NodeIdentity] on disk
+#[derive(Serialize, Deserialize)]
+struct NodeIdentityFile {
+    secret_key: String,
+    public_key: String,
+}
+
+/// Validator node keypair and [NodeID] derived from the public key
+#[derive(Clone)]
+pub struct NodeIdentity {
+    node_id: NodeID,
+    secret_key: PrivateKey,
+    public_key: PublicKey,
+}
+
+impl NodeIdentity {
+    /// Generate new random identity
+    pub fn random() -> Self {
+        let secret_key = PrivateKey::random(&mut OsRng);
+        Self::from_secret_key(secret_key)
+    }
+
+    fn from_secret_key(secret_key: PrivateKey) -> Self {
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let node_id = Self::derive_node_id(&public_key);
+        Self {
+            node_id,
+            secret_key,
+            public_key,
+        }
+    }
+
+    /// NodeID is first 6 bytes of public key hash
+    pub(crate) fn derive_node_id(public_key: &PublicKey) -> NodeID {
+        let hash = Blake256::digest(public_key.as_bytes());
+        // Blake256 output is 32 bytes, so slice conversion never fails
+        NodeID(hash[..6].try_into().unwrap())
+    }
+
+    #[inline]
+    pub fn node_id(&self) -> NodeID {
+        self.node_id
+    }
+
+    #[inline]
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    #[inline]
+    pub fn public_key_hex(&self) -> String {
+        self.public_key.to_hex()
+    }
+
+    /// Schnorr signature of the message, hex encoded as `public_nonce || signature`
+    pub fn sign(&self, message: &[u8]) -> Result<String, IdentityError> {
+        let (nonce, public_nonce) = PublicKey::random_keypair(&mut OsRng);
+        let challenge = Self::challenge(&public_nonce, &self.public_key, message);
+        let signature = Signature::sign(self.secret_key.clone(), nonce, &challenge)
+            .map_err(|err| IdentityError::Signature(format!("{:?}", err)))?;
+        Ok(format!(
+            "{}{}",
+            signature.get_public_nonce().to_hex(),
+            signature.get_signature().to_hex()
+        ))
+    }
+
+    /// Verify signature produced by [NodeIdentity::sign] against the public key
+    pub fn verify(public_key: &PublicKey, signature: &str, message: &[u8]) -> bool {
+        match Self::parse_signature(signature) {
+            Some((public_nonce, sig)) => {
+                let challenge = Self::challenge(&public_nonce, public_key, message);
+                Signature::new(public_nonce, sig).verify_challenge(public_key, &challenge)
+            },
+            None => false,
+        }
+    }
+
+    /// Verify many signatures produced by [NodeIdentity::sign] at once
+    ///
+    /// Checks a random linear combination of signature equations `s*G = R + e*P` with a single
+    /// multiscalar multiplication, which is considerably faster than one by one verification for
+    /// large committees. Returns false if any signature is invalid, without telling which one.
+    pub fn verify_batch(signatures: &[(&PublicKey, &str, &[u8])]) -> bool {
+        let mut scalars = Vec::with_capacity(signatures.len() * 2);
+        let mut points = Vec::with_capacity(signatures.len() * 2);
+        let mut sum = PrivateKey::default();
+        for (public_key, signature, message) in signatures {
+            let (public_nonce, sig) = match Self::parse_signature(signature) {
+                Some(parsed) => parsed,
+                None => return false,
+            };
+            let challenge = match PrivateKey::from_bytes(&Self::challenge(&public_nonce, public_key, message)) {
+                Ok(challenge) => challenge,
+                Err(_) => return false,
+            };
+            // Random weights prevent invalid signatures from cancelling each other out
+            let weight = PrivateKey::random(&mut OsRng);
+            sum = sum + &weight * &sig;
+            scalars.push(&weight * &challenge);
+            points.push((*public_key).clone());
+            scalars.push(weight);
+            points.push(public_nonce);
+        }
+        PublicKey::from_secret_key(&sum) == PublicKey::batch_mul(&scalars, &points)
+    }
+
+    /// Splits hex encoded signature into public nonce and signature scalar
+    fn parse_signature(signature: &str) -> Option<(PublicKey, PrivateKey)> {
+        if signature.len() != 128 || !signature.is_char_boundary(64) {
+            return None;
+        }
+        let (public_nonce, sig) = signature.split_at(64);
+        match (PublicKey::from_hex(public_nonce), PrivateKey::from_hex(sig)) {
+            (Ok(public_nonce), Ok(sig)) => Some((public_nonce, sig)),
+            _ => None,
+        }
+    }
+
+    /// Symmetric secret bound to the node key, distinct `domain` produces unrelated secrets
+    pub fn derive_secret(&self, domain: &str) -> Vec<u8> {
+        Blake256::new()
+            .chain(domain.as_bytes())
+            .chain(self.secret_key.as_bytes())
+            .result()
+            .to_vec()
+    }
+
+    fn challenge(public_nonce: &PublicKey, public_key: &PublicKey, message: &[u8]) -> Vec<u8> {
+        Blake256::new()
+            .chain(public_nonce.as_bytes())
+            .chain(public_key.as_bytes())
+            .chain(message)
+            .result()
+            .to_vec()
+    }
+
+    /// Load identity from file
+    pub fn load(path: &Path) -> Result<Self, IdentityError> {
+        if !path.exists() {
+            return Err(IdentityError::NotFound {
+                path: path.display().to_string(),
+            });
+        }
+        let data = std::fs::read_to_string(path)?;
+        let file: NodeIdentityFile = serde_json::from_str(&data)?;
+        let secret_key =
+            PrivateKey::from_hex(&file.secret_key).map_err(|err| IdentityError::InvalidKeys(format!("{:?}", err)))?;
+        let identity = Self::from_secret_key(secret_key);
+        if identity.public_key_hex() != file.public_key {
+            return Err(IdentityError::InvalidKeys(
+                "public key does not match secret key".into(),
+            ));
+        }
+        log::info!(target: LOG_TARGET, "Node identity loaded, NodeID {}", identity.node_id);
+        Ok(identity)
+    }
+
+    /// Store identity to file, won't overwrite existing file unless `force` is set
+    pub fn save(&self, path: &Path, force: bool) -> Result<(), IdentityError> {
+        if path.exists() && !force {
+            return Err(IdentityError::AlreadyExists {
+                path: path.display().to_string(),
+            });
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = NodeIdentityFile {
+            secret_key: self.secret_key.to_hex(),
+            public_key: self.public_key_hex(),
+        };
+        std::fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_node_id(mut self, node_id: NodeID) -> Self {
+        self.node_id = node_id;
+        self
+    }
+}
+
+impl fmt::Debug for NodeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print secret key
+        f.debug_struct("NodeIdentity")
+            .field("node_id", &self.node_id.to_string())
+            .field("public_key", &self.public_key_hex())
+            .finish()
+    }
+}
+
+impl fmt::Display for NodeIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NodeID: {}\n", self.node_id)?;
+        write!(f, "Public key: {}", self.public_key_hex())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::Test;
+    use tempdir::TempDir;
+
+    #[test]
+    fn sign_and_verify() {
+        let identity = NodeIdentity::random();
+        let signature = identity.sign(b"message").unwrap();
+        assert!(NodeIdentity::verify(identity.public_key(), &signature, b"message"));
+        assert!(!NodeIdentity::verify(identity.public_key(), &signature, b"other message"));
+        assert!(!NodeIdentity::verify(identity.public_key(), "stub-signature", b"message"));
+        let other = NodeIdentity::random();
+        assert!(!NodeIdentity::verify(other.public_key(), &signature, b"message"));
+    }
+
+    #[test]
+    fn verify_batch() {
+        let identities: Vec<_> = (0..4).map(|_| NodeIdentity::random()).collect();
+        let signatures: Vec<_> = identities.iter().map(|i| i.sign(b"message").unwrap()).collect();
+        let batch: Vec<_> = identities
+            .iter()
+            .zip(signatures.iter())
+            .map(|(i, s)| (i.public_key(), s.as_str(), &b"message"[..]))
+            .collect();
+        assert!(NodeIdentity::verify_batch(&batch));
+
+        let mut invalid = batch.clone();
+        invalid[2].2 = b"other message";
+        assert!(!NodeIdentity::verify_batch(&invalid));
+        let mut invalid = batch.clone();
+        invalid[1].0 = identities[0].public_key();
+        assert!(!NodeIdentity::verify_batch(&invalid));
+        let mut invalid = batch;
+        invalid[3].1 = "stub-signature";
+        assert!(!NodeIdentity::verify_batch(&invalid));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = Test::<TempDir>::get_path_buf().join("node_id.json");
+        let identity = NodeIdentity::random();
+        identity.save(&path, false).unwrap();
+        assert!(identity.save(&path, false).is_err());
+
+        let loaded = NodeIdentity::load(&path).unwrap();
+        assert_eq!(loaded.node_id(), identity.node_id());
+        assert_eq!(loaded.public_key_hex(), identity.public_key_hex());
+
+        let identity2 = NodeIdentity::random();
+        identity2.save(&path, true).unwrap();
+        let loaded = NodeIdentity::load(&path).unwrap();
+        assert_eq!(loaded.node_id(), identity2.node_id());
+        assert_ne!(loaded.node_id(), identity.node_id());
+    }
+
+    #[test]
+    fn load_missing() {
+        let path = Test::<TempDir>::get_path_buf().join("missing.json");
+        match NodeIdentity::load(&path) {
+            Err(IdentityError::NotFound { .. }) => {},
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+}
@@ -11,14 +11,22 @@
 
 // TODO: think of moving api to separate crate
 pub mod api;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 // TODO: think of moving config to separate crate
 pub mod config;
 pub mod consensus;
 pub mod db;
+pub mod fees;
+pub mod identity;
 pub mod metrics;
+pub mod raid;
+pub mod sync;
+pub mod telemetry;
 pub mod template;
 pub mod types;
 pub mod wallet;
+pub mod webhooks;
 
 #[cfg(test)]
 pub(crate) mod test;
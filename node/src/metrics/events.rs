@@ -9,8 +9,9 @@
 //! ```
 
 use crate::{
+    consensus::{ConsensusGcStats, MempoolStats},
     db::models::InstructionStatus,
-    types::{InstructionID, TemplateID},
+    types::{AssetID, InstructionID, TemplateID},
 };
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -24,6 +25,15 @@ use serde::{Deserialize, Serialize};
 pub enum MetricEvent {
     Call(ContractCallEvent),
     Instruction(InstructionEvent),
+    /// Instruction status change observed via DB notification, see [crate::db::notify],
+    /// ignored if the status was already reported by [MetricEvent::Instruction]
+    Notification(InstructionEvent),
+    Consensus(ConsensusEvent),
+    Mailbox(MailboxEvent),
+    Sync(SyncEvent),
+    Lock(LockEvent),
+    Mempool(MempoolStats),
+    ConsensusGc(ConsensusGcStats),
 }
 
 /// Contract initiated via HTTP
@@ -43,6 +53,7 @@ impl From<ContractCallEvent> for MetricEvent {
 pub struct InstructionEvent {
     pub id: InstructionID,
     pub template_id: TemplateID,
+    pub asset_id: AssetID,
     pub status: InstructionStatus,
 }
 
@@ -51,3 +62,73 @@ impl From<InstructionEvent> for MetricEvent {
         Self::Instruction(req)
     }
 }
+
+/// Consensus worker performs a step of asset committee's round
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConsensusEvent {
+    pub asset_id: AssetID,
+    /// Name of committee state, see [CommitteeState::name](crate::types::consensus::CommitteeState::name)
+    pub state: String,
+}
+
+impl From<ConsensusEvent> for MetricEvent {
+    fn from(req: ConsensusEvent) -> Self {
+        Self::Consensus(req)
+    }
+}
+
+/// Number of messages queued or being processed by an actor
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MailboxEvent {
+    pub actor: String,
+    pub depth: usize,
+}
+
+impl From<MailboxEvent> for MetricEvent {
+    fn from(req: MailboxEvent) -> Self {
+        Self::Mailbox(req)
+    }
+}
+
+/// Batch of asset state imported from a peer, see [crate::sync]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SyncEvent {
+    pub asset_id: AssetID,
+    /// Number of append only records imported so far
+    pub records: u64,
+    pub complete: bool,
+}
+
+impl From<SyncEvent> for MetricEvent {
+    fn from(req: SyncEvent) -> Self {
+        Self::Sync(req)
+    }
+}
+
+/// Asset lock contention, see [crate::consensus::LockRegistry]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LockEvent {
+    pub asset_id: AssetID,
+    /// Stale lock was reclaimed if true, otherwise worker found asset locked by another worker
+    pub reclaimed: bool,
+}
+
+impl From<LockEvent> for MetricEvent {
+    fn from(req: LockEvent) -> Self {
+        Self::Lock(req)
+    }
+}
+
+/// Pending instructions across all assets, sent by consensus worker every poll, see [crate::consensus::Mempool]
+impl From<MempoolStats> for MetricEvent {
+    fn from(req: MempoolStats) -> Self {
+        Self::Mempool(req)
+    }
+}
+
+/// Consensus records deleted by a run of [crate::consensus::ConsensusJanitor]
+impl From<ConsensusGcStats> for MetricEvent {
+    fn from(req: ConsensusGcStats) -> Self {
+        Self::ConsensusGc(req)
+    }
+}
@@ -5,11 +5,16 @@
 //! on actor reset, though this should be fine for displaying realtime stats in CLI UI.
 
 use super::{events::*, LOG_TARGET};
-use crate::{db::models::InstructionStatus, types::InstructionID};
+use crate::{
+    consensus::{ConsensusGcStats, MempoolStats},
+    db::{models::InstructionStatus, utils::instrument::PoolWait},
+    types::{AssetID, InstructionID},
+};
 use actix::{Context, Message, MessageResponse};
 use deadpool_postgres::Pool;
+use serde::Serialize;
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, VecDeque},
     sync::Arc,
 };
 
@@ -28,8 +33,19 @@ pub struct Metrics {
     instructions_commit_spark: Sparkline,
     current_processing_instructions: u64,
     current_pending_instructions: u64,
-    unique_instructions_counter: HashSet<InstructionID>,
+    // last known status of every instruction
+    instruction_statuses: HashMap<InstructionID, InstructionStatus>,
     calls_counter: HashMap<String, u64>,
+    assets: HashMap<AssetID, AssetMetrics>,
+    mailboxes: HashMap<String, usize>,
+    // longest wait for DB connection every second, in milliseconds
+    pool_wait_spark: Sparkline,
+    pool_wait: PoolWait,
+    // mempool depth reported by consensus worker every poll
+    mempool_depth_spark: Sparkline,
+    mempool: MempoolStats,
+    // consensus records deleted since node start
+    consensus_gc: ConsensusGcStats,
     // TODO: instruction_time_in_status: HashMap<(InstructionStatus,InstructionID),
 }
 
@@ -52,6 +68,8 @@ impl Metrics {
             .set_max_size(config.instructions_spark_sizes);
         self.instructions_commit_spark
             .set_max_size(config.instructions_spark_sizes);
+        self.pool_wait_spark.set_max_size(config.instructions_spark_sizes);
+        self.mempool_depth_spark.set_max_size(config.instructions_spark_sizes);
     }
 
     // Supposed to be called every second and shifting sparkline data
@@ -62,6 +80,13 @@ impl Metrics {
         self.instructions_scheduled_spark.shift();
         self.instructions_invalid_spark.shift();
         self.instructions_commit_spark.shift();
+        if self.pool.is_some() {
+            self.pool_wait = PoolWait::take();
+            self.pool_wait_spark.set(self.pool_wait.max_ms);
+        }
+        self.pool_wait_spark.shift();
+        self.mempool_depth_spark.shift();
+        self.mempool_depth_spark.set(self.mempool.depth as u64);
     }
 
     pub(super) fn process_event(&mut self, event: MetricEvent) {
@@ -73,7 +98,9 @@ impl Metrics {
                     self.calls_counter.insert(contract_name, 1);
                 }
             },
-            MetricEvent::Instruction(InstructionEvent { id, status, .. }) => {
+            MetricEvent::Instruction(InstructionEvent {
+                id, asset_id, status, ..
+            }) => {
                 match status {
                     InstructionStatus::Scheduled => self.instructions_scheduled_spark.inc(),
                     InstructionStatus::Processing => {
@@ -96,19 +123,89 @@ impl Metrics {
                         self.current_pending_instructions = self.current_pending_instructions.saturating_sub(1);
                     },
                 };
-                self.unique_instructions_counter.insert(id);
+                let asset = self.assets.entry(asset_id).or_default();
+                if let Some(previous) = self.instruction_statuses.insert(id, status) {
+                    if let Some(count) = asset.instructions.get_mut(&previous) {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+                *asset.instructions.entry(status).or_insert(0) += 1;
+            },
+            MetricEvent::Notification(event) => {
+                if self.instruction_statuses.get(&event.id) != Some(&event.status) {
+                    self.process_event(MetricEvent::Instruction(event));
+                }
+            },
+            MetricEvent::Consensus(ConsensusEvent { asset_id, state }) => {
+                let asset = self.assets.entry(asset_id).or_default();
+                asset.consensus_state = Some(state);
+                asset.consensus_steps += 1;
+            },
+            MetricEvent::Mailbox(MailboxEvent { actor, depth }) => {
+                self.mailboxes.insert(actor, depth);
+            },
+            MetricEvent::Sync(SyncEvent {
+                asset_id,
+                records,
+                complete,
+            }) => {
+                let asset = self.assets.entry(asset_id).or_default();
+                asset.synced_records = records;
+                asset.sync_complete = complete;
+            },
+            MetricEvent::Lock(LockEvent { asset_id, reclaimed }) => {
+                let asset = self.assets.entry(asset_id).or_default();
+                if reclaimed {
+                    asset.locks_reclaimed += 1;
+                } else {
+                    asset.lock_conflicts += 1;
+                }
+            },
+            MetricEvent::Mempool(stats) => {
+                self.mempool = stats;
+                self.mempool_depth_spark.set(stats.depth as u64);
+            },
+            MetricEvent::ConsensusGc(stats) => {
+                self.consensus_gc += stats;
             },
         }
     }
 }
 
+/// Instructions breakdown and consensus round state of an asset
+#[derive(Clone, Default, Debug, PartialEq, Serialize)]
+pub struct AssetMetrics {
+    /// Number of asset instructions by their current status
+    pub instructions: HashMap<InstructionStatus, u64>,
+    /// Committee state of the last consensus step performed on the asset
+    pub consensus_state: Option<String>,
+    pub consensus_steps: u64,
+    /// Times a worker found the asset locked by another worker
+    pub lock_conflicts: u64,
+    /// Stale locks of dead workers released on the asset
+    pub locks_reclaimed: u64,
+    /// Append only records imported from peers while catching up, see [crate::sync]
+    pub synced_records: u64,
+    pub sync_complete: bool,
+}
+
+impl AssetMetrics {
+    pub fn count(&self, status: InstructionStatus) -> u64 {
+        self.instructions.get(&status).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.instructions.values().sum()
+    }
+}
+
 #[derive(Message)]
 #[rtype(result = "MetricsSnapshot")]
 /// Get current state of metrics counters,
 /// will return MetricsSnapshot back
 pub struct GetMetrics;
 
-#[derive(MessageResponse)]
+#[derive(MessageResponse, Serialize)]
 /// Representation of [Metrics] data snapshot suitable for display
 pub struct MetricsSnapshot {
     // Note: this should work much faster than HashMap<InstructionStatus..>
@@ -121,11 +218,36 @@ pub struct MetricsSnapshot {
     pub current_pending_instructions: u64,
     pub total_unique_instructions: u64,
     pub total_calls: HashMap<String, u64>,
+    #[serde(skip)]
     pub pool_status: Option<deadpool::Status>,
+    /// Waits for DB connection during the last second, see [crate::db::utils::instrument]
+    pub pool_wait: PoolWait,
+    pub pool_wait_spark: Vec<u64>,
+    /// Pending instructions waiting for consensus, see [crate::consensus::Mempool]
+    pub mempool: MempoolStats,
+    pub mempool_depth_spark: Vec<u64>,
+    /// Consensus records deleted since node start, see [crate::consensus::ConsensusJanitor]
+    pub consensus_gc: ConsensusGcStats,
+    /// Per asset metrics ordered by AssetID
+    pub assets: Vec<(AssetID, AssetMetrics)>,
+    /// Messages queued or being processed by actors, ordered by actor name
+    pub mailboxes: Vec<(String, usize)>,
 }
 
 impl From<&Metrics> for MetricsSnapshot {
     fn from(metrics: &Metrics) -> Self {
+        let mut assets: Vec<_> = metrics
+            .assets
+            .iter()
+            .map(|(asset_id, asset)| (asset_id.clone(), asset.clone()))
+            .collect();
+        assets.sort_by(|(a, _), (b, _)| a.to_string().cmp(&b.to_string()));
+        let mut mailboxes: Vec<_> = metrics
+            .mailboxes
+            .iter()
+            .map(|(actor, depth)| (actor.clone(), *depth))
+            .collect();
+        mailboxes.sort();
         Self {
             instructions_scheduled_spark: metrics.instructions_scheduled_spark.to_vec(),
             instructions_processing_spark: metrics.instructions_processing_spark.to_vec(),
@@ -134,9 +256,16 @@ impl From<&Metrics> for MetricsSnapshot {
             instructions_commit_spark: metrics.instructions_commit_spark.to_vec(),
             current_processing_instructions: metrics.current_processing_instructions,
             current_pending_instructions: metrics.current_pending_instructions,
-            total_unique_instructions: metrics.unique_instructions_counter.len() as u64,
+            total_unique_instructions: metrics.instruction_statuses.len() as u64,
             total_calls: metrics.calls_counter.clone(),
             pool_status: metrics.pool.as_ref().map(|p| p.status()),
+            pool_wait: metrics.pool_wait,
+            pool_wait_spark: metrics.pool_wait_spark.to_vec(),
+            mempool: metrics.mempool,
+            mempool_depth_spark: metrics.mempool_depth_spark.to_vec(),
+            consensus_gc: metrics.consensus_gc,
+            assets,
+            mailboxes,
         }
     }
 }
@@ -171,6 +300,10 @@ impl Sparkline {
         *self.data.back_mut().unwrap() += 1;
     }
 
+    fn set(&mut self, value: u64) {
+        *self.data.back_mut().unwrap() = value;
+    }
+
     fn shift(&mut self) {
         if self.data.len() >= self.max_size {
             let _ = self.data.pop_front();
@@ -199,7 +332,7 @@ mod test {
     use super::*;
     use crate::{
         test::utils::Test,
-        types::{InstructionID, TemplateID},
+        types::{AssetID, InstructionID, TemplateID},
     };
 
     #[test]
@@ -271,10 +404,12 @@ mod test {
         let mut metrics = Metrics::default();
         let id = Test::<InstructionID>::new();
         let template_id = Test::<TemplateID>::new();
+        let asset_id = Test::<AssetID>::new();
         metrics.process_event(
             InstructionEvent {
                 id: id.clone(),
                 template_id: template_id.clone(),
+                asset_id: asset_id.clone(),
                 status: InstructionStatus::Scheduled,
             }
             .into(),
@@ -290,6 +425,7 @@ mod test {
             InstructionEvent {
                 id: id.clone(),
                 template_id: template_id.clone(),
+                asset_id: asset_id.clone(),
                 status: InstructionStatus::Processing,
             }
             .into(),
@@ -305,6 +441,7 @@ mod test {
             InstructionEvent {
                 id: id.clone(),
                 template_id: template_id.clone(),
+                asset_id: asset_id.clone(),
                 status: InstructionStatus::Pending,
             }
             .into(),
@@ -320,6 +457,7 @@ mod test {
             InstructionEvent {
                 id: id.clone(),
                 template_id: template_id.clone(),
+                asset_id: asset_id.clone(),
                 status: InstructionStatus::Commit,
             }
             .into(),
@@ -339,10 +477,12 @@ mod test {
         let mut metrics = Metrics::default();
         let id = Test::<InstructionID>::new();
         let template_id = Test::<TemplateID>::new();
+        let asset_id = Test::<AssetID>::new();
         metrics.process_event(
             InstructionEvent {
                 id: id.clone(),
                 template_id: template_id.clone(),
+                asset_id: asset_id.clone(),
                 status: InstructionStatus::Processing,
             }
             .into(),
@@ -356,6 +496,7 @@ mod test {
             InstructionEvent {
                 id: id.clone(),
                 template_id: template_id.clone(),
+                asset_id: asset_id.clone(),
                 status: InstructionStatus::Invalid,
             }
             .into(),
@@ -375,6 +516,7 @@ mod test {
     fn instructions_unique() {
         let mut metrics = Metrics::default();
         let template_id = Test::<TemplateID>::new();
+        let asset_id = Test::<AssetID>::new();
         let statuses = [
             InstructionStatus::Scheduled,
             InstructionStatus::Processing,
@@ -387,6 +529,7 @@ mod test {
                 InstructionEvent {
                     id: Test::<InstructionID>::new(),
                     template_id: template_id.clone(),
+                    asset_id: asset_id.clone(),
                     status: statuses[c % 5],
                 }
                 .into(),
@@ -395,4 +538,129 @@ mod test {
             assert_eq!(snapshot.total_unique_instructions, c as u64);
         }
     }
+
+    #[test]
+    fn notifications_reported_once() {
+        let mut metrics = Metrics::default();
+        let event = InstructionEvent {
+            id: Test::<InstructionID>::new(),
+            template_id: Test::<TemplateID>::new(),
+            asset_id: Test::<AssetID>::new(),
+            status: InstructionStatus::Processing,
+        };
+        metrics.process_event(MetricEvent::Instruction(event.clone()));
+        metrics.process_event(MetricEvent::Notification(event.clone()));
+        let snapshot = MetricsSnapshot::from(&metrics);
+        assert_eq!(snapshot.current_processing_instructions, 1);
+        assert_eq!(snapshot.instructions_processing_spark.last(), Some(&1));
+
+        metrics.process_event(MetricEvent::Notification(InstructionEvent {
+            status: InstructionStatus::Pending,
+            ..event
+        }));
+        let snapshot = MetricsSnapshot::from(&metrics);
+        assert_eq!(snapshot.current_processing_instructions, 0);
+        assert_eq!(snapshot.current_pending_instructions, 1);
+    }
+
+    #[test]
+    fn asset_breakdown() {
+        let mut metrics = Metrics::default();
+        let template_id = Test::<TemplateID>::new();
+        let asset_id = Test::<AssetID>::new();
+        let asset_id2 = Test::<AssetID>::new();
+        let id = Test::<InstructionID>::new();
+        for (id, asset_id, status) in &[
+            (id, &asset_id, InstructionStatus::Scheduled),
+            (id, &asset_id, InstructionStatus::Processing),
+            (Test::<InstructionID>::new(), &asset_id, InstructionStatus::Scheduled),
+            (Test::<InstructionID>::new(), &asset_id2, InstructionStatus::Pending),
+        ] {
+            metrics.process_event(
+                InstructionEvent {
+                    id: *id,
+                    template_id,
+                    asset_id: (*asset_id).clone(),
+                    status: *status,
+                }
+                .into(),
+            );
+        }
+        metrics.process_event(
+            ConsensusEvent {
+                asset_id: asset_id2.clone(),
+                state: "PreparingView".into(),
+            }
+            .into(),
+        );
+        for reclaimed in &[false, false, true] {
+            metrics.process_event(
+                LockEvent {
+                    asset_id: asset_id2.clone(),
+                    reclaimed: *reclaimed,
+                }
+                .into(),
+            );
+        }
+        metrics.process_event(
+            SyncEvent {
+                asset_id: asset_id.clone(),
+                records: 42,
+                complete: true,
+            }
+            .into(),
+        );
+        metrics.process_event(
+            MempoolStats {
+                depth: 4,
+                assets: 2,
+                max_age_ms: 1500,
+            }
+            .into(),
+        );
+        for views in &[2, 3] {
+            metrics.process_event(
+                ConsensusGcStats {
+                    views: *views,
+                    proposals: 1,
+                    ..ConsensusGcStats::default()
+                }
+                .into(),
+            );
+        }
+        metrics.process_event(
+            MailboxEvent {
+                actor: "template 1".into(),
+                depth: 3,
+            }
+            .into(),
+        );
+
+        let snapshot = MetricsSnapshot::from(&metrics);
+        assert_eq!(snapshot.total_unique_instructions, 3);
+        assert_eq!(snapshot.mailboxes, vec![("template 1".to_string(), 3)]);
+        assert_eq!(snapshot.mempool.depth, 4);
+        assert_eq!(snapshot.mempool.max_age_ms, 1500);
+        assert_eq!(snapshot.mempool_depth_spark.last(), Some(&4));
+        assert_eq!(snapshot.consensus_gc, ConsensusGcStats {
+            views: 5,
+            proposals: 2,
+            ..ConsensusGcStats::default()
+        });
+        let asset = &snapshot.assets.iter().find(|(id, _)| *id == asset_id).unwrap().1;
+        assert_eq!(asset.count(InstructionStatus::Scheduled), 1);
+        assert_eq!(asset.count(InstructionStatus::Processing), 1);
+        assert_eq!(asset.total(), 2);
+        assert_eq!(asset.consensus_state, None);
+        assert_eq!(asset.synced_records, 42);
+        assert!(asset.sync_complete);
+        let asset2 = &snapshot.assets.iter().find(|(id, _)| *id == asset_id2).unwrap().1;
+        assert_eq!(asset2.count(InstructionStatus::Pending), 1);
+        assert_eq!(asset2.consensus_state, Some("PreparingView".into()));
+        assert_eq!(asset2.consensus_steps, 1);
+        assert_eq!(asset2.lock_conflicts, 2);
+        assert_eq!(asset2.locks_reclaimed, 1);
+        assert_eq!(asset.lock_conflicts, 0);
+        assert!(!asset2.sync_complete);
+    }
 }
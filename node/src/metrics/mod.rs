@@ -2,8 +2,8 @@ pub mod actor;
 pub mod events;
 pub mod metrics;
 
-pub use events::{ContractCallEvent, InstructionEvent, MetricEvent};
-pub use metrics::{GetMetrics, Metrics, MetricsConfig, MetricsSnapshot};
+pub use events::{ConsensusEvent, ContractCallEvent, InstructionEvent, LockEvent, MailboxEvent, MetricEvent, SyncEvent};
+pub use metrics::{AssetMetrics, GetMetrics, Metrics, MetricsConfig, MetricsSnapshot};
 
 pub const LOG_TARGET: &'static str = "tari_validator_node::metrics";
 
@@ -13,7 +13,7 @@ mod test {
     use crate::{
         db::models::InstructionStatus,
         test::utils::Test,
-        types::{InstructionID, TemplateID},
+        types::{AssetID, InstructionID, TemplateID},
     };
     use actix::Actor;
     use std::time::Duration;
@@ -50,10 +50,12 @@ mod test {
 
         let id = Test::<InstructionID>::new();
         let template_id = Test::<TemplateID>::new();
+        let asset_id = Test::<AssetID>::new();
 
         let event: MetricEvent = InstructionEvent {
             id,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Pending,
         }
         .into();
@@ -64,6 +66,7 @@ mod test {
         let event2: MetricEvent = InstructionEvent {
             id,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Processing,
         }
         .into();
@@ -75,6 +78,7 @@ mod test {
         let event3: MetricEvent = InstructionEvent {
             id: id2,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Pending,
         }
         .into();
@@ -99,9 +103,11 @@ mod test {
 
         let id = Test::<InstructionID>::new();
         let template_id = Test::<TemplateID>::new();
+        let asset_id = Test::<AssetID>::new();
         let event: MetricEvent = InstructionEvent {
             id,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Scheduled,
         }
         .into();
@@ -117,6 +123,7 @@ mod test {
         let event: MetricEvent = InstructionEvent {
             id,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Scheduled,
         }
         .into();
@@ -124,6 +131,7 @@ mod test {
         let event2: MetricEvent = InstructionEvent {
             id,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Processing,
         }
         .into();
@@ -137,6 +145,7 @@ mod test {
         let event3: MetricEvent = InstructionEvent {
             id,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Pending,
         }
         .into();
@@ -144,6 +153,7 @@ mod test {
         let event4: MetricEvent = InstructionEvent {
             id: id2,
             template_id,
+            asset_id: asset_id.clone(),
             status: InstructionStatus::Scheduled,
         }
         .into();
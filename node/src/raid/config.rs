@@ -0,0 +1,18 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RaidConfig {
+    /// Verify RAID TXT record of the asset domain on asset creation, requires `raid-dns` feature
+    pub enabled: bool,
+    /// DNS lookup timeout in seconds
+    pub timeout: u64,
+}
+impl Default for RaidConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: 5,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Verification of Registered Asset Issuer Domain (RAID)
+//!
+//! Asset created with `fqdn` is verified by looking up TXT records of the domain in OpenAlias format,
+//! record binds RAID ID of the asset to the pubkey of its issuer:
+//! ```text
+//! oa1:tari id=<raid_id>; pk=<issuer pubkey>;
+//! ```
+//! Verification status is stored with the digital asset, see [RaidStatus]. DNS lookup is compiled in
+//! with `raid-dns` feature and is enabled in `[validator.raid]` section of config or via RAID_* env vars,
+//! assets stay Unverified otherwise.
+
+mod config;
+pub use config::RaidConfig;
+
+use crate::{
+    db::models::RaidStatus,
+    types::{Pubkey, RaidID},
+};
+use thiserror::Error;
+
+pub const LOG_TARGET: &'static str = "tari_validator_node::raid";
+const OPENALIAS_PREFIX: &'static str = "oa1:tari";
+
+#[derive(Error, Debug)]
+pub enum RaidError {
+    #[error("RAID verification requires node built with raid-dns feature")]
+    Disabled,
+    #[cfg(feature = "raid-dns")]
+    #[error("DNS lookup failed: {0}")]
+    Lookup(#[from] trust_dns_resolver::error::ResolveError),
+}
+
+/// RAID TXT record of the domain
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaidRecord {
+    pub raid_id: String,
+    pub pubkey: String,
+}
+
+impl RaidRecord {
+    /// Parses OpenAlias TXT record, None if record is not a Tari RAID record
+    pub fn parse(txt: &str) -> Option<Self> {
+        let txt = txt.trim();
+        if !txt.starts_with(OPENALIAS_PREFIX) {
+            return None;
+        }
+        let fields = &txt[OPENALIAS_PREFIX.len()..];
+        if !fields.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let mut raid_id = None;
+        let mut pubkey = None;
+        for field in fields.split(';') {
+            let mut pair = field.trim().splitn(2, '=');
+            match (pair.next(), pair.next()) {
+                (Some("id"), Some(value)) => raid_id = Some(value.trim().to_string()),
+                (Some("pk"), Some(value)) => pubkey = Some(value.trim().to_string()),
+                _ => {},
+            }
+        }
+        Some(Self {
+            raid_id: raid_id?,
+            pubkey: pubkey?,
+        })
+    }
+
+    pub fn matches(&self, raid_id: &RaidID, issuer: &Pubkey) -> bool {
+        self.raid_id == raid_id.to_base58() && &self.pubkey == issuer
+    }
+}
+
+/// Status of TXT records lookup: Verified if any record matches RAID ID and issuer of the asset
+pub fn check_records<S: AsRef<str>>(records: &[S], raid_id: &RaidID, issuer: &Pubkey) -> RaidStatus {
+    let verified = records
+        .iter()
+        .filter_map(|txt| RaidRecord::parse(txt.as_ref()))
+        .any(|record| record.matches(raid_id, issuer));
+    if verified {
+        RaidStatus::Verified
+    } else {
+        RaidStatus::Failed
+    }
+}
+
+/// Looks up RAID TXT records of `fqdn` and checks them against the asset, Unverified if lookup is disabled
+pub async fn verify(config: &RaidConfig, fqdn: &str, raid_id: &RaidID, issuer: &Pubkey) -> RaidStatus {
+    if !config.enabled {
+        return RaidStatus::Unverified;
+    }
+    match lookup_txt(config, fqdn).await {
+        Ok(records) => check_records(&records, raid_id, issuer),
+        Err(RaidError::Disabled) => {
+            log::warn!(target: LOG_TARGET, "{}, asset of {} stays unverified", RaidError::Disabled, fqdn);
+            RaidStatus::Unverified
+        },
+        #[allow(unreachable_patterns)]
+        Err(err) => {
+            log::warn!(target: LOG_TARGET, "RAID verification of {} failed: {}", fqdn, err);
+            RaidStatus::Failed
+        },
+    }
+}
+
+#[cfg(feature = "raid-dns")]
+async fn lookup_txt(config: &RaidConfig, fqdn: &str) -> Result<Vec<String>, RaidError> {
+    use std::time::Duration;
+    use trust_dns_resolver::{
+        config::{ResolverConfig, ResolverOpts},
+        TokioAsyncResolver,
+    };
+
+    let opts = ResolverOpts {
+        timeout: Duration::from_secs(config.timeout.max(1)),
+        ..ResolverOpts::default()
+    };
+    let resolver = TokioAsyncResolver::tokio(ResolverConfig::default(), opts).await?;
+    let lookup = resolver.txt_lookup(fqdn).await?;
+    Ok(lookup
+        .iter()
+        .map(|txt| {
+            txt.txt_data()
+                .iter()
+                .map(|data| String::from_utf8_lossy(data).into_owned())
+                .collect::<String>()
+        })
+        .collect())
+}
+
+#[cfg(not(feature = "raid-dns"))]
+async fn lookup_txt(_config: &RaidConfig, _fqdn: &str) -> Result<Vec<String>, RaidError> {
+    Err(RaidError::Disabled)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PUBKEY: &'static str = "7e6f4b801170db0bf86c9257fe562492469439556cba069a12afd1c72c585b0f";
+
+    #[test]
+    fn parse_record() {
+        let record = RaidRecord::parse(&format!("oa1:tari id=123456789012345; pk={};", PUBKEY)).unwrap();
+        assert_eq!(record, RaidRecord {
+            raid_id: "123456789012345".into(),
+            pubkey: PUBKEY.into(),
+        });
+        assert!(RaidRecord::parse("oa1:xmr recipient_address=abc;").is_none());
+        assert!(RaidRecord::parse("oa1:tari id=123456789012345;").is_none());
+        assert!(RaidRecord::parse("v=spf1 -all").is_none());
+    }
+
+    #[test]
+    fn check() {
+        let raid_id: RaidID = "123456789012345".parse().unwrap();
+        let issuer: Pubkey = PUBKEY.into();
+        let records = vec![
+            "v=spf1 -all".to_string(),
+            format!("oa1:tari id=123456789012345; pk={}", PUBKEY),
+        ];
+        assert_eq!(check_records(&records, &raid_id, &issuer), RaidStatus::Verified);
+        let other: Pubkey = "other".into();
+        assert_eq!(check_records(&records, &raid_id, &other), RaidStatus::Failed);
+        assert_eq!(check_records::<String>(&[], &raid_id, &issuer), RaidStatus::Failed);
+    }
+
+    #[actix_rt::test]
+    async fn disabled() {
+        let raid_id = RaidID::default();
+        let issuer: Pubkey = PUBKEY.into();
+        let status = verify(&RaidConfig::default(), "tari.com", &raid_id, &issuer).await;
+        assert_eq!(status, RaidStatus::Unverified);
+    }
+}
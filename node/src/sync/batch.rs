@@ -0,0 +1,434 @@
+use super::SyncError;
+use crate::{
+    db::{
+        models::{
+            consensus::{Instruction, NewInstruction},
+            AssetState,
+            DigitalAsset,
+            NewAssetState,
+            NewDigitalAsset,
+            NewToken,
+            StateDelta,
+            Token,
+        },
+        utils::{db::in_transaction, errors::DBError},
+    },
+    identity::{IdentityError, NodeIdentity},
+    types::{AssetID, CommitteeMode, InstructionID, NodeID},
+    wallet::Signer,
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tari_core::transactions::{crypto::tari_utilities::hex::Hex, types::PublicKey};
+
+/// Registration data of the asset, record IDs are local to the node and are not synced
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SyncedAsset {
+    pub template_type: u32,
+    pub committee_mode: CommitteeMode,
+    pub fqdn: Option<String>,
+    pub raid_id: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub limit_per_wallet: Option<u32>,
+    pub max_supply: Option<u32>,
+    pub allow_transfers: bool,
+    pub asset_issuer_pub_key: String,
+    pub authorized_signers: Vec<String>,
+    pub expiry_date: Option<DateTime<Utc>>,
+    pub initial_permission_bitflag: i64,
+    pub initial_data_json: Value,
+}
+
+impl SyncedAsset {
+    fn new(digital_asset: DigitalAsset, asset: &AssetState) -> Self {
+        Self {
+            template_type: digital_asset.template_type,
+            committee_mode: digital_asset.committee_mode,
+            fqdn: digital_asset.fqdn,
+            raid_id: digital_asset.raid_id,
+            name: asset.name.clone(),
+            description: asset.description.clone(),
+            limit_per_wallet: asset.limit_per_wallet,
+            max_supply: asset.max_supply,
+            allow_transfers: asset.allow_transfers,
+            asset_issuer_pub_key: asset.asset_issuer_pub_key.clone(),
+            authorized_signers: asset.authorized_signers.clone(),
+            expiry_date: asset.expiry_date,
+            initial_permission_bitflag: asset.initial_permission_bitflag,
+            initial_data_json: asset.initial_data_json.clone(),
+        }
+    }
+
+    /// Registers the asset on the node, returns its asset state
+    async fn insert(self, asset_id: &AssetID, client: &Client) -> Result<AssetState, DBError> {
+        let digital_asset_id = DigitalAsset::insert(
+            NewDigitalAsset {
+                template_type: self.template_type,
+                committee_mode: self.committee_mode,
+                fqdn: self.fqdn,
+                raid_id: self.raid_id,
+            },
+            client,
+        )
+        .await?;
+        let id = AssetState::insert(
+            NewAssetState {
+                name: self.name,
+                description: self.description,
+                limit_per_wallet: self.limit_per_wallet,
+                max_supply: self.max_supply,
+                allow_transfers: self.allow_transfers,
+                asset_issuer_pub_key: self.asset_issuer_pub_key,
+                authorized_signers: self.authorized_signers,
+                expiry_date: self.expiry_date,
+                initial_permission_bitflag: self.initial_permission_bitflag,
+                initial_data_json: self.initial_data_json,
+                asset_id: asset_id.clone(),
+                digital_asset_id,
+            },
+            client,
+        )
+        .await?;
+        AssetState::load(id, client).await
+    }
+}
+
+/// State of the asset exported by a peer, see [export_batch]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct StateBatch {
+    pub asset_id: AssetID,
+    /// Node which exported the batch
+    pub node_id: NodeID,
+    /// Registration data, present in checkpoint batch only
+    pub asset: Option<SyncedAsset>,
+    /// Tokens of the records, `asset_state_id` is replaced by local one on import
+    pub tokens: Vec<NewToken>,
+    /// Instructions referenced by the records along with their parents, oldest first
+    pub instructions: Vec<Instruction>,
+    pub state: StateDelta,
+    /// Checkpoint hash of the asset state on the peer at the time of export
+    pub checkpoint_hash: String,
+}
+
+/// Batch signed by the peer node identity
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedStateBatch {
+    pub batch: StateBatch,
+    /// [Signer::sign] of the JSON serialized batch
+    pub signature: String,
+}
+
+impl SignedStateBatch {
+    pub async fn sign(batch: StateBatch, signer: &dyn Signer) -> Result<Self, SyncError> {
+        let message = serde_json::to_vec(&batch).map_err(IdentityError::from)?;
+        let signature = signer.sign(&message).await?;
+        Ok(Self { batch, signature })
+    }
+
+    /// Batch if it is signed by `pubkey`
+    pub fn verify(self, pubkey: &str) -> Result<StateBatch, SyncError> {
+        let message = serde_json::to_vec(&self.batch).map_err(IdentityError::from)?;
+        match PublicKey::from_hex(pubkey) {
+            Ok(public_key) if NodeIdentity::verify(&public_key, &self.signature, &message) => Ok(self.batch),
+            _ => Err(SyncError::Signature {
+                asset_id: self.batch.asset_id,
+                pubkey: pubkey.into(),
+            }),
+        }
+    }
+}
+
+/// Instructions referenced by the records of the state delta
+fn instruction_ids(state: &StateDelta) -> Vec<InstructionID> {
+    state
+        .asset_state
+        .iter()
+        .map(|state| state.instruction_id)
+        .chain(state.token_state.iter().map(|state| state.instruction_id))
+        .collect()
+}
+
+/// Instructions along with their parents, oldest first so parents go first
+async fn load_instructions(mut ids: Vec<InstructionID>, client: &Client) -> Result<Vec<Instruction>, DBError> {
+    let mut instructions: Vec<Instruction> = vec![];
+    while let Some(id) = ids.pop() {
+        if instructions.iter().any(|instruction| instruction.id == id) {
+            continue;
+        }
+        let instruction = Instruction::load(id, client).await?;
+        if let Some(parent_id) = instruction.parent_id {
+            ids.push(parent_id);
+        }
+        instructions.push(instruction);
+    }
+    instructions.sort_by_key(|instruction| instruction.created_at);
+    Ok(instructions)
+}
+
+async fn new_batch(
+    asset_state: &AssetState,
+    asset: Option<SyncedAsset>,
+    tokens: Vec<Token>,
+    instructions: Vec<Instruction>,
+    state: StateDelta,
+    node_id: NodeID,
+    client: &Client,
+) -> Result<StateBatch, DBError>
+{
+    Ok(StateBatch {
+        asset_id: asset_state.asset_id.clone(),
+        node_id,
+        asset,
+        tokens: tokens
+            .into_iter()
+            .map(|token| NewToken {
+                token_id: token.token_id,
+                asset_state_id: uuid::Uuid::nil(),
+                initial_data_json: token.initial_data_json,
+            })
+            .collect(),
+        instructions,
+        state,
+        checkpoint_hash: asset_state.checkpoint_hash(client).await?,
+    })
+}
+
+/// Exports checkpoint of the asset if `since` is not provided, otherwise at most `limit` records
+/// created after `since`
+pub async fn export_batch(
+    asset_id: &AssetID,
+    since: Option<DateTime<Utc>>,
+    limit: i64,
+    signer: &dyn Signer,
+    client: &Client,
+) -> Result<SignedStateBatch, SyncError>
+{
+    let asset_state = AssetState::find_by_asset_id(asset_id, client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    let (asset, state, tokens) = match since {
+        None => {
+            let digital_asset = DigitalAsset::load(asset_state.digital_asset_id, client).await?;
+            let state = StateDelta::checkpoint(asset_id, client).await?;
+            // tokens without records are part of the checkpoint too
+            let tokens = Token::find_by_asset_state_id(asset_state.id, client).await?;
+            (Some(SyncedAsset::new(digital_asset, &asset_state)), state, tokens)
+        },
+        Some(since) => {
+            let state = StateDelta::since(asset_id, since, limit, client).await?;
+            let mut tokens = vec![];
+            for token_id in state.token_ids() {
+                tokens.push(
+                    Token::find_by_token_id(&token_id, client)
+                        .await?
+                        .ok_or(DBError::NotFound)?,
+                );
+            }
+            (None, state, tokens)
+        },
+    };
+    let instructions = load_instructions(instruction_ids(&state), client).await?;
+    let batch = new_batch(&asset_state, asset, tokens, instructions, state, signer.node_id(), client).await?;
+    SignedStateBatch::sign(batch, signer).await
+}
+
+/// Exports whole history of the asset along with all its instructions as a single checkpoint batch,
+/// see [write_snapshot](super::write_snapshot)
+pub async fn export_snapshot(asset_id: &AssetID, node_id: NodeID, client: &Client) -> Result<StateBatch, SyncError> {
+    let asset_state = AssetState::find_by_asset_id(asset_id, client)
+        .await?
+        .ok_or(DBError::NotFound)?;
+    let digital_asset = DigitalAsset::load(asset_state.digital_asset_id, client).await?;
+    let state = StateDelta::history(asset_id, client).await?;
+    let tokens = Token::find_by_asset_state_id(asset_state.id, client).await?;
+    let mut ids = instruction_ids(&state);
+    ids.extend(
+        Instruction::find_by_asset_id(asset_id, client)
+            .await?
+            .into_iter()
+            .map(|instruction| instruction.id),
+    );
+    let instructions = load_instructions(ids, client).await?;
+    let asset = Some(SyncedAsset::new(digital_asset, &asset_state));
+    Ok(new_batch(&asset_state, asset, tokens, instructions, state, node_id, client).await?)
+}
+
+/// Imports batches of the asset missing on the node, checkpoint batch goes first
+///
+/// Batches are imported within a single DB transaction, which is rolled back unless checkpoint hash
+/// of the imported state matches the one of the last batch. Returns number of imported records
+pub async fn import_batches(batches: Vec<StateBatch>, client: &Client) -> Result<i64, SyncError> {
+    let (asset_id, expected) = match batches.last() {
+        Some(batch) => (batch.asset_id.clone(), batch.checkpoint_hash.clone()),
+        None => return Err(SyncError::InvalidBatch("nothing to import".into())),
+    };
+    if AssetState::find_by_asset_id(&asset_id, client).await?.is_some() {
+        return Err(SyncError::AlreadySynced(asset_id));
+    }
+    in_transaction(client, async move {
+        let mut asset_state: Option<AssetState> = None;
+        let mut records = 0;
+        for batch in batches {
+            if batch.asset_id != asset_id {
+                return Err(SyncError::InvalidBatch(format!("batch of asset {}", batch.asset_id)));
+            }
+            if let Some(asset) = batch.asset {
+                if asset_state.is_some() {
+                    return Err(SyncError::InvalidBatch("asset is registered twice".into()));
+                }
+                asset_state = Some(asset.insert(&asset_id, client).await?);
+            }
+            let asset_state = asset_state
+                .as_ref()
+                .ok_or_else(|| SyncError::InvalidBatch("checkpoint has to go first".into()))?;
+            for instruction in batch.instructions {
+                if Instruction::find(instruction.id, client).await?.is_none() {
+                    Instruction::insert(
+                        NewInstruction {
+                            id: instruction.id,
+                            parent_id: instruction.parent_id,
+                            initiating_node_id: instruction.initiating_node_id,
+                            signature: instruction.signature,
+                            asset_id: instruction.asset_id,
+                            token_id: instruction.token_id,
+                            template_id: instruction.template_id,
+                            contract_name: instruction.contract_name,
+                            status: instruction.status,
+                            params: instruction.params,
+                            depends_on: instruction.depends_on,
+                            signer_pubkey: instruction.signer_pubkey,
+                            nonce: instruction.nonce,
+                            priority: instruction.priority,
+                            batch_index: instruction.batch_index,
+                        },
+                        client,
+                    )
+                    .await?;
+                }
+            }
+            for token in batch.tokens {
+                if Token::find_by_token_id(&token.token_id, client).await?.is_none() {
+                    Token::insert(
+                        NewToken {
+                            asset_state_id: asset_state.id,
+                            ..token
+                        },
+                        client,
+                    )
+                    .await?;
+                }
+            }
+            batch.state.store(client).await?;
+            records += batch.state.records;
+        }
+        let asset_state = asset_state.ok_or_else(|| SyncError::InvalidBatch("checkpoint is missing".into()))?;
+        let actual = asset_state.checkpoint_hash(client).await?;
+        if actual != expected {
+            return Err(SyncError::Checkpoint {
+                asset_id,
+                expected,
+                actual,
+            });
+        }
+        Ok(records)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{NewTokenStateAppendOnly, TokenStatus},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetStateBuilder, TokenBuilder},
+            test_db_client,
+            Test,
+        },
+        types::TokenID,
+    };
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn export_and_import() {
+        let (client, _lock) = test_db_client().await;
+        let identity = NodeIdentity::random();
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let token = TokenBuilder {
+            asset_state_id: Some(asset.id),
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        Token::store_append_only_state(
+            &NewTokenStateAppendOnly {
+                token_id: token.token_id.clone(),
+                instruction_id: instruction.id,
+                status: TokenStatus::Active,
+                state_data_json: json!({ "value": 1 }),
+                expected_version: None,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+
+        let signed = export_batch(&asset.asset_id, None, 10, &identity, &client).await.unwrap();
+        let other = NodeIdentity::random();
+        assert!(matches!(
+            signed.clone().verify(&other.public_key_hex()),
+            Err(SyncError::Signature { .. })
+        ));
+        let checkpoint = signed.verify(&identity.public_key_hex()).unwrap();
+        assert_eq!(checkpoint.tokens.len(), 1);
+        assert_eq!(checkpoint.instructions.len(), 1);
+        assert_eq!(checkpoint.state.records, 1);
+        assert_eq!(checkpoint.checkpoint_hash, asset.checkpoint_hash(&client).await.unwrap());
+        let delta = export_batch(&asset.asset_id, checkpoint.state.cursor, 10, &identity, &client)
+            .await
+            .unwrap()
+            .verify(&identity.public_key_hex())
+            .unwrap();
+        assert!(delta.asset.is_none());
+        assert_eq!(delta.state.records, 0);
+
+        // asset is present on the node already
+        assert!(matches!(
+            import_batches(vec![checkpoint.clone(), delta.clone()], &client).await,
+            Err(SyncError::AlreadySynced(_))
+        ));
+
+        // import into the same DB as a different asset, tampered checkpoint hash rolls the import back
+        let asset_id = Test::<AssetID>::new();
+        let token_id = Test::<TokenID>::from_asset(&asset_id);
+        let mut batch = checkpoint.clone();
+        batch.asset_id = asset_id.clone();
+        batch.instructions = vec![];
+        batch.tokens[0].token_id = token_id.clone();
+        batch.state.token_state[0].token_id = token_id.clone();
+        batch.checkpoint_hash = "0".repeat(64);
+        let actual = match import_batches(vec![batch.clone()], &client).await {
+            Err(SyncError::Checkpoint { actual, .. }) => actual,
+            res => panic!("unexpected result {:?}", res),
+        };
+        assert!(AssetState::find_by_asset_id(&asset_id, &client).await.unwrap().is_none());
+
+        batch.checkpoint_hash = actual;
+        assert_eq!(import_batches(vec![batch], &client).await.unwrap(), 1);
+        let imported = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert_eq!(imported.additional_data_json, json!({ "value": 1 }));
+        assert_eq!(imported.status, TokenStatus::Active);
+    }
+}
@@ -0,0 +1,168 @@
+use super::{import_batches, SignedStateBatch, StateBatch, SyncConfig, SyncError, SyncPeer, LOG_TARGET};
+use crate::{
+    api::config::TlsConfig,
+    db::models::AssetState,
+    metrics::{MetricEvent, Metrics, SyncEvent},
+    types::AssetID,
+};
+use actix::Addr;
+use actix_web::client::{Client as HttpClient, Connector};
+use chrono::{DateTime, SecondsFormat, Utc};
+use deadpool_postgres::{Client, Pool};
+use std::time::Duration;
+
+/// Batches are limited by `batch_size` records, though checkpoint carries all tokens of the asset
+const MAX_BATCH_BYTES: usize = 64 * 1024 * 1024;
+
+async fn fetch(
+    http: &HttpClient,
+    peer: &SyncPeer,
+    asset_id: &AssetID,
+    since: Option<DateTime<Utc>>,
+    limit: i64,
+) -> Result<StateBatch, SyncError>
+{
+    let mut url = format!(
+        "{}/admin/sync/assets/{}?limit={}",
+        peer.url.trim_end_matches('/'),
+        asset_id,
+        limit
+    );
+    if let Some(since) = since {
+        url.push_str(&format!("&since={}", since.to_rfc3339_opts(SecondsFormat::Nanos, true)));
+    }
+    let mut res = http
+        .get(url.as_str())
+        .header("authorization", format!("Bearer {}", peer.token))
+        .send()
+        .await
+        .map_err(|err| SyncError::peer(&peer.url, err))?;
+    if !res.status().is_success() {
+        return Err(SyncError::peer(&peer.url, format!("peer responded with {}", res.status())));
+    }
+    let signed: SignedStateBatch = res
+        .json()
+        .limit(MAX_BATCH_BYTES)
+        .await
+        .map_err(|err| SyncError::peer(&peer.url, err))?;
+    signed.verify(&peer.pubkey)
+}
+
+/// Fetches checkpoint followed by deltas until peer has no more records
+async fn fetch_all(
+    http: &HttpClient,
+    config: &SyncConfig,
+    peer: &SyncPeer,
+    asset_id: &AssetID,
+    metrics_addr: Option<&Addr<Metrics>>,
+) -> Result<Vec<StateBatch>, SyncError>
+{
+    let mut batches = vec![fetch(http, peer, asset_id, None, config.batch_size).await?];
+    let mut records = batches[0].state.records;
+    let mut cursor = batches[0].state.cursor;
+    while let Some(since) = cursor {
+        if let Some(metrics_addr) = metrics_addr {
+            let msg: MetricEvent = SyncEvent {
+                asset_id: asset_id.clone(),
+                records: records as u64,
+                complete: false,
+            }
+            .into();
+            metrics_addr.do_send(msg);
+        }
+        let batch = fetch(http, peer, asset_id, Some(since), config.batch_size).await?;
+        records += batch.state.records;
+        cursor = if batch.state.records < config.batch_size {
+            None
+        } else {
+            batch.state.cursor
+        };
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+/// Imports state of the asset from the first peer which succeeds, returns number of imported records
+///
+/// With `tls` configured node authenticates to peers with its certificate, see [TlsConfig]
+pub async fn sync_asset(
+    config: &SyncConfig,
+    tls: Option<&TlsConfig>,
+    asset_id: &AssetID,
+    metrics_addr: Option<Addr<Metrics>>,
+    client: &Client,
+) -> Result<i64, SyncError>
+{
+    if AssetState::find_by_asset_id(asset_id, client).await?.is_some() {
+        return Err(SyncError::AlreadySynced(asset_id.clone()));
+    }
+    let mut http = HttpClient::builder().timeout(Duration::from_secs(config.timeout.max(1)));
+    if let Some(tls) = tls {
+        http = http.connector(Connector::new().rustls(tls.client_config()?).finish());
+    }
+    let http = http.finish();
+    let mut last_error = SyncError::NoPeers;
+    for peer in config.peers.iter() {
+        let res = match fetch_all(&http, config, peer, asset_id, metrics_addr.as_ref()).await {
+            Ok(batches) => import_batches(batches, client).await,
+            Err(err) => Err(err),
+        };
+        match res {
+            Ok(records) => {
+                if let Some(metrics_addr) = metrics_addr.as_ref() {
+                    let msg: MetricEvent = SyncEvent {
+                        asset_id: asset_id.clone(),
+                        records: records as u64,
+                        complete: true,
+                    }
+                    .into();
+                    metrics_addr.do_send(msg);
+                }
+                return Ok(records);
+            },
+            Err(err @ SyncError::AlreadySynced(_)) => return Err(err),
+            Err(err) => {
+                log::warn!(
+                    target: LOG_TARGET,
+                    "Failed to sync asset {} from {}: {}",
+                    asset_id,
+                    peer.url,
+                    err
+                );
+                last_error = err;
+            },
+        }
+    }
+    Err(last_error)
+}
+
+/// Catches up configured assets on node start, failures are logged and do not prevent node from starting
+pub async fn catch_up(
+    config: &SyncConfig,
+    tls: Option<&TlsConfig>,
+    metrics_addr: Option<Addr<Metrics>>,
+    pool: &Pool,
+)
+{
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(err) => {
+            log::error!(target: LOG_TARGET, "Failed to catch up assets: {}", err);
+            return;
+        },
+    };
+    for asset_id in config.assets.iter() {
+        match sync_asset(config, tls, asset_id, metrics_addr.clone(), &client).await {
+            Ok(records) => log::info!(
+                target: LOG_TARGET,
+                "Asset {} synced, {} records imported",
+                asset_id,
+                records
+            ),
+            Err(SyncError::AlreadySynced(_)) => {
+                log::debug!(target: LOG_TARGET, "Asset {} is already present, skipping sync", asset_id)
+            },
+            Err(err) => log::error!(target: LOG_TARGET, "Failed to sync asset {}: {}", asset_id, err),
+        }
+    }
+}
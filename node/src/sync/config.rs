@@ -0,0 +1,41 @@
+use crate::types::AssetID;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SyncConfig {
+    /// Catch up `assets` from `peers` on node start, before consensus is started
+    pub enabled: bool,
+    /// Assets synced on node start, assets already present on the node are skipped
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub assets: Vec<AssetID>,
+    #[serde(default)]
+    pub peers: Vec<SyncPeer>,
+    /// Max number of append only records per batch
+    pub batch_size: i64,
+    /// Peer request timeout in seconds
+    pub timeout: u64,
+}
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            assets: vec![],
+            peers: vec![],
+            batch_size: 500,
+            timeout: 10,
+        }
+    }
+}
+
+/// Node serving asset state, peers are tried in order until one succeeds
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct SyncPeer {
+    /// Base URL of the peer API, e.g. `http://10.0.0.2:3001`
+    pub url: String,
+    /// Public key of the peer node identity, batches signed by any other key are rejected
+    pub pubkey: String,
+    /// Admin access token of the peer
+    pub token: String,
+}
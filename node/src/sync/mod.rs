@@ -0,0 +1,77 @@
+//! Catch-up of asset state from peer nodes
+//!
+//! Node joining asset committee has no state of the asset. It fetches state from peers configured
+//! in `[validator.sync]` section of config, on node start before consensus is started (`enabled = true`)
+//! or on demand with `tvnc asset sync`:
+//! ```toml
+//! [validator.sync]
+//! enabled = true
+//! assets = ["<asset-id>"]
+//! peers = [{ url = "http://10.0.0.2:3001", pubkey = "<peer identity pubkey>", token = "<peer admin token>" }]
+//! ```
+//! Peers serve `GET /admin/sync/assets/{id}`, without `since` the batch is a checkpoint: registration data
+//! and current state of the asset and its tokens, with `since` it carries append only records created
+//! after that moment (deltas), at most `batch_size` of them. Every batch is signed by the peer node identity
+//! and is verified against `pubkey` of the peer. Batches are imported within a single DB transaction
+//! which is rolled back unless checkpoint hash of the imported state matches the one reported by the peer,
+//! see [AssetState::checkpoint_hash](crate::db::models::AssetState::checkpoint_hash).
+//! Progress is reported to [Metrics](crate::metrics::Metrics) as [SyncEvent](crate::metrics::SyncEvent).
+//! Peers with `[validator.actix.tls]` peer listener serve batches on `peer_port` only, node authenticates
+//! there with its own certificate, see [TlsConfig](crate::api::config::TlsConfig).
+//!
+//! The same batch carrying whole history of the asset is exported to a file by [export_snapshot] and
+//! [write_snapshot] for backups and migrations (`tvnc asset export`), see [read_snapshot].
+
+mod batch;
+pub use batch::{export_batch, export_snapshot, import_batches, SignedStateBatch, StateBatch, SyncedAsset};
+
+mod client;
+pub use client::{catch_up, sync_asset};
+
+mod config;
+pub use config::{SyncConfig, SyncPeer};
+
+mod snapshot;
+pub use snapshot::{read_snapshot, write_snapshot, SnapshotManifest, SNAPSHOT_VERSION};
+
+use crate::{api::config::TlsError, db::utils::errors::DBError, identity::IdentityError, types::AssetID};
+use thiserror::Error;
+
+pub const LOG_TARGET: &'static str = "tari_validator_node::sync";
+
+#[derive(Error, Debug)]
+pub enum SyncError {
+    #[error("DB error: {0}")]
+    DB(#[from] DBError),
+    #[error("Identity error: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Peer {url} request failed: {msg}")]
+    Peer { url: String, msg: String },
+    #[error("Batch of asset {asset_id} is not signed by peer {pubkey}")]
+    Signature { asset_id: AssetID, pubkey: String },
+    #[error("Checkpoint hash of asset {asset_id} mismatch: peer {expected}, local {actual}")]
+    Checkpoint {
+        asset_id: AssetID,
+        expected: String,
+        actual: String,
+    },
+    #[error("Asset {0} is already present on the node")]
+    AlreadySynced(AssetID),
+    #[error("Invalid batch: {0}")]
+    InvalidBatch(String),
+    #[error("No peers configured")]
+    NoPeers,
+    #[error("TLS error: {0}")]
+    Tls(#[from] TlsError),
+}
+
+impl SyncError {
+    pub fn peer(url: &str, msg: impl ToString) -> Self {
+        Self::Peer {
+            url: url.into(),
+            msg: msg.to_string(),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::{StateBatch, SyncError};
+use crate::types::{AssetID, NodeID};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+/// Bumped on incompatible changes of the archive layout
+pub const SNAPSHOT_VERSION: u32 = 1;
+const ZSTD_LEVEL: i32 = 3;
+
+const MANIFEST: &'static str = "manifest.json";
+const ASSET: &'static str = "asset.json";
+const TOKENS: &'static str = "tokens.json";
+const INSTRUCTIONS: &'static str = "instructions.json";
+const STATE: &'static str = "state.json";
+
+/// Summary of the snapshot, first entry of the archive
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotManifest {
+    pub version: u32,
+    pub asset_id: AssetID,
+    /// Node which exported the snapshot
+    pub node_id: NodeID,
+    pub exported_at: DateTime<Utc>,
+    pub checkpoint_hash: String,
+    pub tokens: usize,
+    pub instructions: usize,
+    pub records: i64,
+}
+
+fn append<W: Write, T: Serialize>(builder: &mut tar::Builder<W>, name: &str, value: &T) -> Result<(), SyncError> {
+    let data = serde_json::to_vec_pretty(value).map_err(|err| SyncError::InvalidBatch(err.to_string()))?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data.as_slice())?;
+    Ok(())
+}
+
+/// Writes snapshot exported by [export_snapshot](super::export_snapshot) as zstd compressed tar archive
+/// of pretty printed JSON files, so it can be inspected with `tar --zstd -xf`
+pub fn write_snapshot(batch: &StateBatch, path: &Path) -> Result<SnapshotManifest, SyncError> {
+    let manifest = SnapshotManifest {
+        version: SNAPSHOT_VERSION,
+        asset_id: batch.asset_id.clone(),
+        node_id: batch.node_id,
+        exported_at: Utc::now(),
+        checkpoint_hash: batch.checkpoint_hash.clone(),
+        tokens: batch.tokens.len(),
+        instructions: batch.instructions.len(),
+        records: batch.state.records,
+    };
+    let encoder = zstd::stream::write::Encoder::new(File::create(path)?, ZSTD_LEVEL)?;
+    let mut builder = tar::Builder::new(encoder);
+    append(&mut builder, MANIFEST, &manifest)?;
+    append(&mut builder, ASSET, &batch.asset)?;
+    append(&mut builder, TOKENS, &batch.tokens)?;
+    append(&mut builder, INSTRUCTIONS, &batch.instructions)?;
+    append(&mut builder, STATE, &batch.state)?;
+    builder.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+/// Reads snapshot written by [write_snapshot], import it with [import_batches](super::import_batches)
+pub fn read_snapshot(path: &Path) -> Result<(SnapshotManifest, StateBatch), SyncError> {
+    let decoder = zstd::stream::read::Decoder::new(File::open(path)?)?;
+    let mut archive = tar::Archive::new(decoder);
+    let mut manifest: Option<SnapshotManifest> = None;
+    let mut asset = None;
+    let mut tokens = vec![];
+    let mut instructions = vec![];
+    let mut state = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut data = vec![];
+        entry.read_to_end(&mut data)?;
+        let invalid = |err: serde_json::Error| SyncError::InvalidBatch(format!("{}: {}", name, err));
+        match name.as_str() {
+            MANIFEST => manifest = Some(serde_json::from_slice(&data).map_err(invalid)?),
+            ASSET => asset = serde_json::from_slice(&data).map_err(invalid)?,
+            TOKENS => tokens = serde_json::from_slice(&data).map_err(invalid)?,
+            INSTRUCTIONS => instructions = serde_json::from_slice(&data).map_err(invalid)?,
+            STATE => state = Some(serde_json::from_slice(&data).map_err(invalid)?),
+            _ => {},
+        }
+    }
+    let manifest = manifest.ok_or_else(|| SyncError::InvalidBatch(format!("{} is missing", MANIFEST)))?;
+    if manifest.version != SNAPSHOT_VERSION {
+        return Err(SyncError::InvalidBatch(format!(
+            "unsupported snapshot version {}",
+            manifest.version
+        )));
+    }
+    let batch = StateBatch {
+        asset_id: manifest.asset_id.clone(),
+        node_id: manifest.node_id,
+        asset,
+        tokens,
+        instructions,
+        state: state.ok_or_else(|| SyncError::InvalidBatch(format!("{} is missing", STATE)))?,
+        checkpoint_hash: manifest.checkpoint_hash.clone(),
+    };
+    Ok((manifest, batch))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{NewTokenStateAppendOnly, Token, TokenStatus},
+        sync::{export_snapshot, import_batches},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetStateBuilder, TokenBuilder},
+            test_db_client,
+            Test,
+        },
+        types::TokenID,
+    };
+    use serde_json::json;
+    use tempdir::TempDir;
+
+    #[actix_rt::test]
+    async fn write_and_read() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let token = TokenBuilder {
+            asset_state_id: Some(asset.id),
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            ..TokenBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let instruction = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        for value in 1..=2 {
+            Token::store_append_only_state(
+                &NewTokenStateAppendOnly {
+                    token_id: token.token_id.clone(),
+                    instruction_id: instruction.id,
+                    status: TokenStatus::Active,
+                    state_data_json: json!({ "value": value }),
+                    expected_version: None,
+                },
+                &client,
+            )
+            .await
+            .unwrap();
+        }
+        // instruction without records is part of the snapshot too
+        InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let batch = export_snapshot(&asset.asset_id, Test::<NodeID>::new(), &client)
+            .await
+            .unwrap();
+        assert_eq!(batch.state.records, 2);
+        assert_eq!(batch.instructions.len(), 2);
+        assert!(batch.asset.is_some());
+
+        let path = Test::<TempDir>::get_path_buf().join(format!("{}.tar.zst", asset.asset_id));
+        let manifest = write_snapshot(&batch, &path).unwrap();
+        assert_eq!(manifest.version, SNAPSHOT_VERSION);
+        assert_eq!(manifest.records, 2);
+        assert_eq!(manifest.tokens, 1);
+        let (read, snapshot) = read_snapshot(&path).unwrap();
+        assert_eq!(read, manifest);
+        assert_eq!(snapshot, batch);
+
+        assert!(matches!(
+            import_batches(vec![snapshot], &client).await,
+            Err(SyncError::AlreadySynced(_))
+        ));
+        assert!(matches!(
+            read_snapshot(&path.with_extension("missing")),
+            Err(SyncError::IO(_))
+        ));
+    }
+}
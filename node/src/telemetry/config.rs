@@ -0,0 +1,48 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_REPORT_PERIOD: u64 = 3600;
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TelemetryConfig {
+    /// Reporting is disabled unless explicitly enabled by node operator
+    pub enabled: bool,
+    /// URL receiving reports as JSON POST requests
+    pub endpoint: Option<String>,
+    /// Period between reports in seconds
+    pub report_period: u64,
+}
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            report_period: DEFAULT_REPORT_PERIOD,
+        }
+    }
+}
+
+pub const DEFAULT_OTLP_ENDPOINT: &'static str = "localhost:55680";
+pub const DEFAULT_SERVICE_NAME: &'static str = "tari_validator_node";
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TracingConfig {
+    /// Spans are exported only when enabled, otherwise tracing is a no-op
+    pub enabled: bool,
+    /// OTLP collector gRPC endpoint
+    pub endpoint: String,
+    /// `service.name` resource attribute of exported spans
+    pub service_name: String,
+    /// Share of root spans sampled for export, from 0.0 to 1.0
+    pub sample_ratio: f64,
+}
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: DEFAULT_OTLP_ENDPOINT.into(),
+            service_name: DEFAULT_SERVICE_NAME.into(),
+            sample_ratio: 1.0,
+        }
+    }
+}
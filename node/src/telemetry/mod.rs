@@ -0,0 +1,21 @@
+//! Opt-in telemetry, periodically reports anonymized operational stats of the node.
+//!
+//! Report contains only node version, uptime and instructions counters, no asset, token or wallet data
+//! is ever sent. Telemetry is disabled by default, enable it in `[validator.telemetry]` section of config
+//! or via TELEMETRY_* env vars.
+//!
+//! Instruction lifecycle tracing is configured separately in `[validator.tracing]` or via TRACING_* env vars.
+//! Spans cover web handler → TemplateRunner actor → contract execution, while consensus steps and instruction
+//! status transitions are traced by consensus workers with `instruction` ids for correlation.
+//! Spans are exported to OpenTelemetry collector over OTLP, see [init_tracing].
+
+mod config;
+pub use config::{TelemetryConfig, TracingConfig};
+
+mod reporter;
+pub use reporter::{TelemetryReport, TelemetryReporter};
+
+mod traces;
+pub use traces::{init_tracing, TracingGuard};
+
+pub const LOG_TARGET: &'static str = "tari_validator_node::telemetry";
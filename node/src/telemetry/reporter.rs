@@ -0,0 +1,167 @@
+use super::{TelemetryConfig, LOG_TARGET};
+use crate::metrics::{GetMetrics, Metrics, MetricsSnapshot};
+use actix::prelude::*;
+use actix_web::client::Client;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Anonymized node stats sent to telemetry endpoint
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TelemetryReport {
+    pub version: String,
+    pub uptime_secs: u64,
+    pub total_instructions: u64,
+    pub total_calls: u64,
+    /// Instructions committed per minute, averaged over metrics window
+    pub commit_rate: f64,
+    /// Share of invalid instructions among all finalized within metrics window
+    pub error_rate: f64,
+}
+
+impl TelemetryReport {
+    pub fn new(snapshot: &MetricsSnapshot, uptime: Duration) -> Self {
+        let commits: u64 = snapshot.instructions_commit_spark.iter().sum();
+        let invalid: u64 = snapshot.instructions_invalid_spark.iter().sum();
+        // sparklines are keeping a counter per second
+        let window_secs = snapshot.instructions_commit_spark.len().max(1) as f64;
+        let error_rate = if commits + invalid > 0 {
+            invalid as f64 / (commits + invalid) as f64
+        } else {
+            0.0
+        };
+        Self {
+            version: env!("CARGO_PKG_VERSION").into(),
+            uptime_secs: uptime.as_secs(),
+            total_instructions: snapshot.total_unique_instructions,
+            total_calls: snapshot.total_calls.values().sum(),
+            commit_rate: commits as f64 * 60.0 / window_secs,
+            error_rate,
+        }
+    }
+}
+
+/// Actor sending [TelemetryReport] every `report_period` seconds,
+/// stops immediately if telemetry is disabled or endpoint is not configured
+pub struct TelemetryReporter {
+    config: TelemetryConfig,
+    metrics_addr: Addr<Metrics>,
+    started_at: Instant,
+}
+
+impl TelemetryReporter {
+    pub fn new(config: TelemetryConfig, metrics_addr: Addr<Metrics>) -> Self {
+        Self {
+            config,
+            metrics_addr,
+            started_at: Instant::now(),
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let endpoint = match self.config.endpoint.clone() {
+            Some(endpoint) => endpoint,
+            None => return,
+        };
+        let metrics_addr = self.metrics_addr.clone();
+        let uptime = self.started_at.elapsed();
+        ctx.spawn(
+            async move {
+                if let Err(err) = Self::send_report(endpoint, metrics_addr, uptime).await {
+                    log::warn!(target: LOG_TARGET, "Failed to send telemetry report: {}", err);
+                }
+            }
+            .into_actor(self),
+        );
+    }
+
+    async fn send_report(endpoint: String, metrics_addr: Addr<Metrics>, uptime: Duration) -> anyhow::Result<()> {
+        let snapshot = metrics_addr.send(GetMetrics).await?;
+        let report = TelemetryReport::new(&snapshot, uptime);
+        log::debug!(target: LOG_TARGET, "Sending telemetry report {:?}", report);
+        let res = Client::default()
+            .post(endpoint)
+            .send_json(&report)
+            .await
+            .map_err(|err| anyhow::anyhow!("{}", err))?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!("telemetry endpoint responded with {}", res.status()));
+        }
+        Ok(())
+    }
+}
+
+impl Actor for TelemetryReporter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.config.enabled {
+            ctx.stop();
+            return;
+        }
+        if self.config.endpoint.is_none() {
+            log::warn!(target: LOG_TARGET, "Telemetry is enabled but endpoint is not configured");
+            ctx.stop();
+            return;
+        }
+        log::info!(
+            target: LOG_TARGET,
+            "Telemetry enabled, reporting to {:?} every {}s",
+            self.config.endpoint,
+            self.config.report_period
+        );
+        ctx.run_interval(Duration::from_secs(self.config.report_period.max(1)), Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        consensus::{ConsensusGcStats, MempoolStats},
+        db::utils::instrument::PoolWait,
+    };
+    use std::collections::HashMap;
+
+    fn snapshot(commits: Vec<u64>, invalid: Vec<u64>) -> MetricsSnapshot {
+        let mut total_calls = HashMap::new();
+        total_calls.insert("sell_token".to_string(), 3);
+        total_calls.insert("transfer_token".to_string(), 2);
+        MetricsSnapshot {
+            instructions_scheduled_spark: vec![],
+            instructions_processing_spark: vec![],
+            instructions_pending_spark: vec![],
+            instructions_invalid_spark: invalid,
+            instructions_commit_spark: commits,
+            current_processing_instructions: 0,
+            current_pending_instructions: 0,
+            total_unique_instructions: 12,
+            total_calls,
+            pool_status: None,
+            pool_wait: PoolWait::default(),
+            pool_wait_spark: vec![],
+            mempool: MempoolStats::default(),
+            mempool_depth_spark: vec![],
+            consensus_gc: ConsensusGcStats::default(),
+            assets: vec![],
+            mailboxes: vec![],
+        }
+    }
+
+    #[test]
+    fn report_rates() {
+        let report = TelemetryReport::new(&snapshot(vec![1, 2, 3, 0], vec![0, 1, 0, 1]), Duration::from_secs(61));
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(report.uptime_secs, 61);
+        assert_eq!(report.total_instructions, 12);
+        assert_eq!(report.total_calls, 5);
+        assert_eq!(report.commit_rate, 90.0);
+        assert_eq!(report.error_rate, 0.25);
+    }
+
+    #[test]
+    fn report_idle_node() {
+        let report = TelemetryReport::new(&snapshot(vec![], vec![]), Duration::from_secs(0));
+        assert_eq!(report.commit_rate, 0.0);
+        assert_eq!(report.error_rate, 0.0);
+    }
+}
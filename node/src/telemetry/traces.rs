@@ -0,0 +1,40 @@
+use super::{TracingConfig, LOG_TARGET};
+use opentelemetry::{
+    api::KeyValue,
+    sdk::{self, Resource},
+};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/// Keeps OTLP exporter running, pending spans are flushed when guard is dropped
+pub struct TracingGuard {
+    _uninstall: opentelemetry_otlp::Uninstall,
+}
+
+/// Installs global [tracing] subscriber exporting spans to OTLP collector
+///
+/// Returns None if tracing is disabled, spans are then recorded by no subscriber at all
+pub fn init_tracing(config: &TracingConfig) -> anyhow::Result<Option<TracingGuard>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let trace_config = sdk::Config::default()
+        .with_default_sampler(sdk::Sampler::Probability(config.sample_ratio.max(0.0).min(1.0)))
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", config.service_name.clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]));
+    let (tracer, uninstall) = opentelemetry_otlp::new_pipeline()
+        .with_endpoint(config.endpoint.as_str())
+        .with_trace_config(trace_config)
+        .install();
+    let subscriber = Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)?;
+    log::info!(
+        target: LOG_TARGET,
+        "Tracing enabled, exporting {} spans to {} (sample ratio {})",
+        config.service_name,
+        config.endpoint,
+        config.sample_ratio
+    );
+    Ok(Some(TracingGuard { _uninstall: uninstall }))
+}
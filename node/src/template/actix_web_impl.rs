@@ -1,8 +1,8 @@
 use super::{Contracts, Template, LOG_TARGET};
-use crate::types::{errors::TypeError, AssetID, TemplateID, TokenID};
+use crate::types::{consensus::AppendOnlyState, errors::TypeError, AssetID, TemplateID, TokenID};
 use actix_web::web;
 use log::info;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct AssetCallParams {
@@ -43,6 +43,27 @@ impl From<&TokenCallParams> for AssetCallParams {
     }
 }
 
+/// Query of contract call, `?dry_run=true` executes contract without creating an instruction
+#[derive(Deserialize, Default, Debug)]
+pub struct DryRunQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Single contract call of a batch, params are the same as of the contract route
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct BatchCall {
+    pub contract: String,
+    pub params: serde_json::Value,
+}
+
+/// Would-be result of the contract call and state it would append, returned by dry run
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DryRunResult {
+    pub result: serde_json::Value,
+    pub append_only_state: AppendOnlyState,
+}
+
 pub fn asset_call_path(asset_id: &AssetID, instruction: &str) -> String {
     format!(
         "/asset_call/{}/{:04X}/{}/{}/{}",
@@ -180,6 +201,9 @@ mod test {
             log::info!("template={}, registering asset routes", tpl);
             scope.service(web::resource("test").route(web::post().to(asset_handler)));
         }
+        fn routes_catalog() -> Vec<ContractRoute> {
+            vec![]
+        }
     }
     // Token contracts
     async fn token_handler(path: web::Path<TokenCallParams>, tpl: web::Data<TemplateID>) -> Result<HttpResponse> {
@@ -190,16 +214,24 @@ mod test {
         fn setup_actix_routes(_: TemplateID, scope: &mut web::ServiceConfig) {
             scope.service(web::resource("test").route(web::post().to(token_handler)));
         }
+        fn routes_catalog() -> Vec<ContractRoute> {
+            vec![]
+        }
     }
     #[derive(Clone)]
     struct TestTemplate;
     impl Template for TestTemplate {
         type AssetContracts = AssetConracts;
         type TokenContracts = TokenConracts;
+        type Config = ();
 
         fn id() -> TemplateID {
             65536.into()
         }
+
+        fn config_section() -> &'static str {
+            "test"
+        }
     }
     // *** End of Test template implementation *****
 
@@ -329,16 +361,24 @@ mod test {
             scope.service(web::resource("test").route(web::post().to(asset_handler_context)));
             scope.service(web::resource("test_body").route(web::post().to(asset_handler_context_with_body)));
         }
+        fn routes_catalog() -> Vec<ContractRoute> {
+            vec![]
+        }
     }
     #[derive(Clone)]
     struct TestTemplateContext;
     impl Template for TestTemplateContext {
         type AssetContracts = AssetConractsContext;
         type TokenContracts = ();
+        type Config = ();
 
         fn id() -> TemplateID {
             65537.into()
         }
+
+        fn config_section() -> &'static str {
+            "test"
+        }
     }
     //*** End of Test template implementation *****
 
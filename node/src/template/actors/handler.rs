@@ -1,11 +1,13 @@
 use crate::{
     db::models::consensus::instructions::Instruction,
     template::{context::*, Template, TemplateError, TemplateRunner, LOG_TARGET},
+    timeout_err,
 };
 use actix::prelude::*;
 use futures::future::TryFutureExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing_futures::Instrument;
 
 pub type ContractCallResult<C> = Result<(Value, C), TemplateError>;
 pub type MessageResult = Result<(), TemplateError>;
@@ -31,6 +33,8 @@ pub trait ContractCallMsg: Clone + Message + Send {
     fn params(&self) -> Self::Params;
     fn call(self, context: Self::Context) -> Self::CallResult;
     fn init_context(self, ctx: TemplateContext<Self::Template>) -> Self::ContextFuture;
+    /// Span of the caller (web handler or parent contract), instruction execution is traced as its child
+    fn span(&self) -> tracing::Span;
 }
 
 /// Actor is accepting TokenCallMsg and tries to perform activity
@@ -42,7 +46,7 @@ where
 {
     type Result = ResponseFuture<M::Result>;
 
-    fn handle(&mut self, msg: M, _ctx: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: M, ctx: &mut Context<Self>) -> Self::Result {
         let context = self.context();
         let instruction = msg.instruction();
         log::info!(
@@ -53,38 +57,96 @@ where
             msg.params()
         );
         let client_opt = self.get_shared_db_client();
-        // Do not block subinstructions
-        let permit = if instruction.parent_id.is_none() {
+        // Do not block subinstructions and operator instructions of the priority lane
+        let permit = if instruction.parent_id.is_none() && instruction.priority <= 0 {
             Some(self.bandwidth.clone().acquire_owned())
         } else {
             None
         };
         let token_context_fut = msg.clone().init_context(self.context());
+        let span = tracing::info_span!(
+            parent: &msg.span(),
+            "template_runner",
+            template = %Self::template_id(),
+            instruction = %instruction.id,
+            contract = %instruction.contract_name
+        );
+
+        let switch_context = context.clone();
+        let contract_name = instruction.contract_name.clone();
+        let dependent = instruction.clone();
+        let retry = (ctx.address(), msg.clone());
 
         let fut = async move {
+            switch_context.check_contract_enabled(&contract_name).await?;
+            // Dependants are held in Scheduled status without taking runner bandwidth
+            let dependencies = if dependent.depends_on.is_empty() {
+                Ok(())
+            } else {
+                switch_context
+                    .wait_dependencies(&dependent)
+                    .instrument(tracing::info_span!("dependencies_wait"))
+                    .await
+            };
             let _lock = if permit.is_some() {
-                Some(permit.unwrap().await)
+                Some(permit.unwrap().instrument(tracing::info_span!("bandwidth_wait")).await)
             } else {
                 None
             };
-            let mut context = token_context_fut.await?;
+            let mut context = token_context_fut.instrument(tracing::info_span!("init_context")).await?;
             if let Some(client) = client_opt {
                 context.set_db_client(client);
             }
             context.transition(ContextEvent::StartProcessing).await?;
+            // Failing after StartProcessing, so instruction with failed dependencies becomes Invalid
+            dependencies?;
+            let timeout = switch_context.config().instruction_timeout(&contract_name);
+            let transaction = if switch_context.config().transactional_execution {
+                Some(context.begin_transaction().await?)
+            } else {
+                None
+            };
             // TODO: instruction needs to be able to run in an encapsulated way and return
             // NewTokenStateAppendOnly and NewAssetStateAppendOnly vecs as the
             // consensus workers need to be able to run an instruction set and confirm the
             // resulting state matches run contract
-            let (result, mut context) = msg.call(context).await?;
-            context.transition(ContextEvent::ProcessingResult { result }).await?;
-            // TODO: commit DB transaction
-            Ok(())
+            let execution = async move {
+                let call = msg.call(context).instrument(tracing::info_span!("contract_execution"));
+                // Timed out contract is dropped, instruction becomes Invalid with timeout error
+                let (result, mut context) = match timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                        Ok(res) => res?,
+                        Err(_) => return timeout_err!("Contract {} timed out after {:?}", contract_name, timeout),
+                    },
+                    None => call.await?,
+                };
+                context.transition(ContextEvent::ProcessingResult { result }).await
+            };
+            // Result is committed along with contract state, failed contract leaves instruction in Processing
+            match transaction {
+                Some(transaction) => transaction.finish(execution.await).await,
+                None => execution.await,
+            }
         }
         .or_else(move |err: TemplateError| async move {
-            let _ = context.instruction_failed(instruction, err.to_string()).await;
+            tracing::error!(error = %err, "instruction failed");
+            // Rescheduled instruction is sent to the runner again after backoff
+            if let Ok(Some(delay)) = context.instruction_error(instruction, &err).await {
+                let (addr, msg) = retry;
+                actix_rt::spawn(async move {
+                    tokio::time::delay_for(delay).await;
+                    addr.do_send(msg);
+                });
+            }
             Err(err)
-        });
-        Box::pin(fut)
+        })
+        .instrument(span);
+        let mailbox = self.mailbox.clone();
+        mailbox.inc();
+        Box::pin(async move {
+            let res = fut.await;
+            mailbox.dec();
+            res
+        })
     }
 }
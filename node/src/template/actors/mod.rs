@@ -24,6 +24,20 @@
 //!     TransferToken(TransferTokenParams),
 //! }
 //! ```
+//!
+//! Params are single type per variant, e.g. `Option<Params>` or `Vec<TokenID>`.
+//! Contracts may borrow params, e.g. `Rename(&'a str)` or `Tag { tags: Option<Vec<&'a str>> }` on
+//! `enum AssetContracts<'a>`: params are deserialized into owned types and stored in generated
+//! `AssetContractsOwned`, which is sent to the actor, and contract is called with borrows of them.
+//! Template's associated type is then `AssetContracts<'static>`.
+//! Template can be any type path, e.g. `template = "crate::template::nft::NftTemplate"`.
+//! Struct and unit variants get generated `{Variant}Params` struct with the same fields,
+//! e.g. `Resolve { release: bool }` is called with `ResolveParams { release }`, unit variant accepts `{}`.
+//! Struct variant with borrowed fields is called with `{Variant}Params<'a>`, request is deserialized into
+//! `{Variant}ParamsOwned`.
+//!
+//! Variants marked `#[contract(method = "...", view)]` are read-only views, they are served via GET
+//! and executed right in the request handler, so they never reach [TemplateRunner].
 
 pub use handler::*;
 pub use runner::*;
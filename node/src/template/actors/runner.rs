@@ -1,13 +1,18 @@
 use crate::{
     config::NodeConfig,
-    metrics::Metrics,
-    template::{Template, TemplateContext},
+    metrics::{MailboxEvent, MetricEvent, Metrics},
+    db::{notify::InstructionNotifier, utils::db::ReadPool},
+    template::{config::ConfigUpdate, Template, TemplateContext, LOG_TARGET},
     types::TemplateID,
     wallet::WalletStore,
 };
 use actix::{fut, prelude::*};
 use deadpool_postgres::{Client, Pool};
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+    RwLock,
+};
 use tokio::sync::{Mutex, Semaphore};
 
 /// Implements [Actor] for Template
@@ -17,6 +22,38 @@ pub struct TemplateRunner<T: Template + Clone + 'static> {
     // This DB client is available for non-transactional operations
     client: Option<Arc<Client>>,
     pub(super) bandwidth: Arc<Semaphore>,
+    // Total permits of `bandwidth`, some of them might be taken by running jobs
+    max_jobs: usize,
+    pub(super) mailbox: MailboxGauge,
+}
+
+/// Reports number of instructions queued or being executed by [TemplateRunner] to [Metrics]
+#[derive(Clone)]
+pub(super) struct MailboxGauge {
+    actor: String,
+    depth: Arc<AtomicUsize>,
+    metrics_addr: Option<Addr<Metrics>>,
+}
+
+impl MailboxGauge {
+    pub(super) fn inc(&self) {
+        self.report(self.depth.fetch_add(1, Ordering::SeqCst) + 1);
+    }
+
+    pub(super) fn dec(&self) {
+        self.report(self.depth.fetch_sub(1, Ordering::SeqCst).saturating_sub(1));
+    }
+
+    fn report(&self, depth: usize) {
+        if let Some(metrics_addr) = self.metrics_addr.as_ref() {
+            let msg: MetricEvent = MailboxEvent {
+                actor: self.actor.clone(),
+                depth,
+            }
+            .into();
+            metrics_addr.do_send(msg);
+        }
+    }
 }
 
 impl<T: Template + Clone> TemplateRunner<T> {
@@ -36,11 +73,12 @@ impl<T: Template + Clone> TemplateRunner<T> {
     /// Creates TemplateRunner
     ///
     /// ## Panics
-    /// It will panic if NodeConfig.public_address is missing or failed to create WalletStore,
+    /// It will panic if NodeConfig.public_address is missing, node identity is not loaded,
+    /// read replica config or template config section is invalid or failed to create WalletStore,
     /// as TemplateRunner won't be able to function properly
-    pub fn create(pool: Arc<Pool>, config: NodeConfig, metrics_addr: Option<Addr<Metrics>>) -> Self {
+    pub fn create(pool: Arc<Pool>, mut config: NodeConfig, metrics_addr: Option<Addr<Metrics>>) -> Self {
         let path = config.wallets_keys_path.clone();
-        let wallets = WalletStore::init(path.clone()).expect(
+        let mut wallets = WalletStore::init(path.clone(), config.wallets_passphrase.clone()).expect(
             format!(
                 "Failed to create TemplateRunner {}: WalletStore at {:?}:",
                 T::id(),
@@ -48,6 +86,10 @@ impl<T: Template + Clone> TemplateRunner<T> {
             )
             .as_str(),
         );
+        // without node identity, e.g. with external signer, temp wallets keys are random
+        if let Ok(master_key) = config.wallets_master_key() {
+            wallets = wallets.with_master_key(master_key);
+        }
         let wallets = Arc::new(Mutex::new(wallets));
         let node_address = config.public_address.clone().expect(
             format!(
@@ -57,21 +99,52 @@ impl<T: Template + Clone> TemplateRunner<T> {
             )
             .as_str(),
         );
+        let read_pool = ReadPool::build(&config, pool.clone()).expect(
+            format!(
+                "Failed to create TemplateRunner {}, invalid postgres_read config: {:?}",
+                T::id(),
+                config.postgres_read
+            )
+            .as_str(),
+        );
         let context = TemplateContext {
             pool,
+            read_pool,
             wallets,
             node_address,
+            node_id: config.node_id(),
             actor_addr: None,
+            metrics_addr: metrics_addr.clone(),
+            config: Arc::new(RwLock::new(config.template.clone())),
+            notifier: None,
+            payload: config.payload.clone(),
+            state_buffers: Default::default(),
+        };
+        if let Err(err) = context.template_config::<T::Config>() {
+            panic!("Failed to create TemplateRunner {}: {}", T::id(), err);
+        }
+        let max_jobs = config.template.runner_max_jobs;
+        let bandwidth = Arc::new(Semaphore::new(max_jobs));
+        let mailbox = MailboxGauge {
+            actor: format!("TemplateRunner {}", T::id()),
+            depth: Arc::new(AtomicUsize::new(0)),
             metrics_addr,
         };
-        let bandwidth = Arc::new(Semaphore::new(config.template.runner_max_jobs));
         Self {
             context,
             client: None,
             bandwidth,
+            max_jobs,
+            mailbox,
         }
     }
 
+    /// Wakes up contracts waiting for dependencies on instruction status notifications instead of polling
+    pub fn with_notifier(mut self, notifier: InstructionNotifier) -> Self {
+        self.context.notifier = Some(notifier);
+        self
+    }
+
     /// Start Actor returning TemplateContext
     ///
     /// ## Panics
@@ -150,3 +223,76 @@ where T: Template + 'static
         }
     }
 }
+
+/// Applies updated template config, running jobs are not interrupted when max jobs are decreased.
+/// Update with invalid options of the template is rejected
+impl<T> Handler<ConfigUpdate> for TemplateRunner<T>
+where T: Template + 'static
+{
+    type Result = ();
+
+    fn handle(&mut self, ConfigUpdate(config): ConfigUpdate, ctx: &mut Context<Self>) -> Self::Result {
+        // running contracts would fail on invalid options, so previous config is kept
+        if let Err(err) = config.template_section::<T::Config>(T::config_section()) {
+            log::error!(
+                target: LOG_TARGET,
+                "template={}, config update rejected, invalid [validator.template.{}]: {}",
+                Self::template_id(),
+                T::config_section(),
+                err
+            );
+            return;
+        }
+        let max_jobs = config.runner_max_jobs;
+        if max_jobs > self.max_jobs {
+            self.bandwidth.add_permits(max_jobs - self.max_jobs);
+        } else if max_jobs < self.max_jobs {
+            // take away permits as soon as running jobs release them
+            let bandwidth = self.bandwidth.clone();
+            let excess = self.max_jobs - max_jobs;
+            ctx.spawn(fut::wrap_future(async move {
+                for _ in 0..excess {
+                    bandwidth.acquire().await.forget();
+                }
+            }));
+        }
+        log::info!(
+            target: LOG_TARGET,
+            "template={}, config updated, runner_max_jobs {} -> {}",
+            Self::template_id(),
+            self.max_jobs,
+            max_jobs
+        );
+        self.max_jobs = max_jobs;
+        *self.context.config.write().unwrap() = config;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        template::config::{ContractConfig, TemplateConfig},
+        test::utils::{actix_test_pool, build_test_config, load_env, TestTemplate},
+    };
+
+    #[actix_rt::test]
+    async fn config_update() {
+        load_env();
+        let config = build_test_config().unwrap();
+        let context = TemplateRunner::<TestTemplate>::create(actix_test_pool(), config, None).start();
+        assert!(context.config().is_enabled("test_contract"));
+
+        let mut template = TemplateConfig {
+            runner_max_jobs: 1,
+            ..TemplateConfig::default()
+        };
+        template.contracts.insert("test_contract".into(), ContractConfig {
+            enabled: false,
+            ..ContractConfig::default()
+        });
+        context.addr().send(ConfigUpdate(template)).await.unwrap();
+        assert!(!context.config().is_enabled("test_contract"));
+        assert_eq!(context.config().runner_max_jobs, 1);
+    }
+}
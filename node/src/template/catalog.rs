@@ -0,0 +1,46 @@
+//! Catalog of contract routes installed by templates
+//!
+//! Routes are generated by `#[derive(Contracts)]` so the catalog is the only place
+//! where contract params are described for API clients, see [crate::api::openapi]
+
+use super::{Contracts, Template};
+use crate::types::TemplateID;
+use schemars::{gen::SchemaGenerator, schema::Schema};
+
+/// Contract route with JSON schema of its params
+#[derive(Clone)]
+pub struct ContractRoute {
+    pub name: &'static str,
+    /// Registers params schema in the generator, returns schema or reference to it
+    pub params_schema: fn(&mut SchemaGenerator) -> Schema,
+    /// Read-only contract served via GET, params are passed in query string
+    pub view: bool,
+}
+
+/// Asset and token contract routes of the template
+#[derive(Clone)]
+pub struct TemplateRoutes {
+    pub template_id: TemplateID,
+    pub asset: Vec<ContractRoute>,
+    pub token: Vec<ContractRoute>,
+}
+
+impl TemplateRoutes {
+    pub fn of<T: Template>() -> Self {
+        Self {
+            template_id: T::id(),
+            asset: <T::AssetContracts as Contracts>::routes_catalog(),
+            token: <T::TokenContracts as Contracts>::routes_catalog(),
+        }
+    }
+
+    /// Path of asset contract routes, contract name is appended as the last segment
+    pub fn asset_root(&self) -> String {
+        format!("/asset_call/{}/{{features}}/{{raid_id}}/{{hash}}", self.template_id)
+    }
+
+    /// Path of token contract routes, contract name is appended as the last segment
+    pub fn token_root(&self) -> String {
+        format!("/token_call/{}/{{features}}/{{raid_id}}/{{hash}}/{{uid}}", self.template_id)
+    }
+}
@@ -1,13 +1,343 @@
-use serde::{Deserialize, Serialize};
+use super::LOG_TARGET;
+use crate::config::NodeConfig;
+use actix::prelude::*;
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use tari_common::GlobalConfig;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Period of checking config file for changes, see [TemplateConfigWatcher]
+pub const CONFIG_WATCH_PERIOD: Duration = Duration::from_secs(5);
+/// Sections of `[validator.template]` which are fields of [TemplateConfig] rather than template options
+const FIELD_SECTIONS: &[&str] = &["contracts", "retry"];
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TemplateConfig {
     pub runner_max_jobs: usize,
+    /// Seconds instruction is held waiting for its dependencies to commit before it fails
+    pub dependency_timeout: u64,
+    /// Seconds contract is allowed to run before instruction becomes Invalid, 0 disables the timeout.
+    /// Overridden per contract by [ContractConfig::timeout_secs]
+    pub instruction_timeout_secs: u64,
+    /// Run contracts within a single DB transaction, committed along with instruction result,
+    /// so state of failed contract is rolled back instead of being partially visible.
+    /// Contracts deferring subinstructions which update the same tokens would deadlock in this mode
+    #[serde(default)]
+    pub transactional_execution: bool,
+    /// Buffer token and asset state appended by contracts in [super::InstructionContext],
+    /// state is stored along with instruction result and persisted by consensus when instruction is committed.
+    /// Buffered token state carries the version it was built on, so instructions racing for a token are rejected
+    /// by consensus rather than by the contract.
+    ///
+    /// Subinstructions read through the buffer of their parent, state of a subinstruction is moved to the parent
+    /// once it succeeds and is committed along with the parent.
+    ///
+    /// Off by default: clients see the state only once instruction is committed, and tokens buffered
+    /// by pending instructions are not counted against `max_supply` and `limit_per_wallet` of the asset
+    #[serde(default)]
+    pub buffered_state: bool,
+    /// Bytes of serialized instruction result stored in instructions table, larger results are truncated,
+    /// complete result is stored separately and served by `GET /instructions/{id}/result`. 0 disables the limit
+    pub max_result_size: usize,
+    /// Retries of instructions failed with transient errors
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Per-contract settings keyed by contract name, contracts missing here are enabled
+    #[serde(default)]
+    pub contracts: HashMap<String, ContractConfig>,
+    /// Options of templates keyed by section name, e.g. `[validator.template.single_use_tokens]`,
+    /// see [Template::config_section](super::Template::config_section).
+    /// Sections are typed by templates, so they are picked from the loaded config by [TemplateConfig::set_sections]
+    #[serde(skip)]
+    pub templates: HashMap<String, Value>,
 }
 impl Default for TemplateConfig {
     fn default() -> Self {
         Self {
             runner_max_jobs: num_cpus::get() * 10,
+            dependency_timeout: 600,
+            instruction_timeout_secs: 3600,
+            transactional_execution: false,
+            buffered_state: false,
+            max_result_size: 65536,
+            retry: RetryPolicy::default(),
+            contracts: HashMap::new(),
+            templates: HashMap::new(),
+        }
+    }
+}
+
+impl TemplateConfig {
+    /// Whether contract is allowed to run
+    pub fn is_enabled(&self, contract_name: &str) -> bool {
+        self.contracts
+            .get(contract_name)
+            .map(|contract| contract.enabled)
+            .unwrap_or(true)
+    }
+
+    /// Time contract is allowed to run, None if it is not limited
+    pub fn instruction_timeout(&self, contract_name: &str) -> Option<Duration> {
+        let secs = self
+            .contracts
+            .get(contract_name)
+            .and_then(|contract| contract.timeout_secs)
+            .unwrap_or(self.instruction_timeout_secs);
+        if secs > 0 {
+            Some(Duration::from_secs(secs))
+        } else {
+            None
+        }
+    }
+
+    /// Request body limit of the contract, None if it is not lowered for the contract
+    pub fn max_body_bytes(&self, contract_name: &str) -> Option<usize> {
+        self.contracts
+            .get(contract_name)
+            .and_then(|contract| contract.max_body_bytes)
+    }
+
+    /// Keeps tables of `[validator.template]` which are not [TemplateConfig] fields as template sections
+    pub fn set_sections(&mut self, template: HashMap<String, Value>) {
+        self.templates = template
+            .into_iter()
+            .filter(|(name, options)| options.is_object() && !FIELD_SECTIONS.contains(&name.as_str()))
+            .collect();
+    }
+
+    /// Typed options of template config `section`, defaults if the section is missing
+    pub fn template_section<C: DeserializeOwned + Default>(&self, section: &str) -> Result<C, serde_json::Error> {
+        match self.templates.get(section) {
+            Some(options) => serde_json::from_value(options.clone()),
+            None => Ok(C::default()),
+        }
+    }
+
+    /// Names of contracts disabled by configuration
+    pub fn disabled_contracts(&self) -> Vec<String> {
+        self.contracts
+            .iter()
+            .filter(|(_, contract)| !contract.enabled)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// Instruction failed with [retriable](super::TemplateError::is_retriable) error is Scheduled again
+/// and re-executed after backoff, instead of becoming Invalid right away
+///
+/// Failures after the contract started are retried only with [TemplateConfig::transactional_execution],
+/// otherwise state appended by the failed attempt would be applied twice
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RetryPolicy {
+    /// Executions of the instruction including the first one, 1 disables retries
+    pub max_attempts: u32,
+    /// Milliseconds before the first retry, doubled for every next one
+    pub backoff_ms: u64,
+    /// Upper bound of the delay between retries in milliseconds
+    pub max_backoff_ms: u64,
+}
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying instruction which failed `attempts` times, None if attempts are exhausted
+    pub fn backoff(&self, attempts: u32) -> Option<Duration> {
+        if attempts == 0 || attempts >= self.max_attempts {
+            return None;
+        }
+        let factor = 2u64.checked_pow(attempts - 1).unwrap_or(u64::MAX);
+        Some(Duration::from_millis(self.backoff_ms.saturating_mul(factor).min(self.max_backoff_ms)))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ContractConfig {
+    pub enabled: bool,
+    /// Overrides [TemplateConfig::instruction_timeout_secs] for the contract, 0 disables the timeout
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Bytes of request body of the contract call, can only lower `[validator.payload] max_body_bytes`
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+}
+impl Default for ContractConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_secs: None,
+            max_body_bytes: None,
+        }
+    }
+}
+
+/// Updated [TemplateConfig] pushed into running [super::TemplateRunner]s
+#[derive(Message, Clone, Debug)]
+#[rtype(result = "()")]
+pub struct ConfigUpdate(pub TemplateConfig);
+
+/// Actor watching node config file, reloads `[validator.template]` section on file change
+/// and pushes it to the template runners with [ConfigUpdate]
+pub struct TemplateConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    runners: Vec<Recipient<ConfigUpdate>>,
+}
+
+impl TemplateConfigWatcher {
+    pub fn new(path: PathBuf, runners: Vec<Recipient<ConfigUpdate>>) -> Self {
+        let modified = Self::modified(&path);
+        Self {
+            path,
+            modified,
+            runners,
+        }
+    }
+
+    /// Loads template config from config file, overloaded with TEMPLATE_* env vars
+    pub fn load(&self) -> anyhow::Result<TemplateConfig> {
+        let mut config = config::Config::new();
+        config.merge(config::File::from(self.path.clone()))?;
+        let global = GlobalConfig::convert_from(config.clone())?;
+        Ok(NodeConfig::load_from(&config, &global, true)?.template)
+    }
+
+    fn modified(path: &PathBuf) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    fn tick(&mut self, _: &mut Context<Self>) {
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        match self.load() {
+            Ok(config) => {
+                log::info!(target: LOG_TARGET, "Reloaded template config {:?}", config);
+                for runner in self.runners.iter() {
+                    if let Err(err) = runner.do_send(ConfigUpdate(config.clone())) {
+                        log::warn!(target: LOG_TARGET, "Failed to update template config: {}", err);
+                    }
+                }
+            },
+            Err(err) => log::error!(target: LOG_TARGET, "Failed to reload template config: {}", err),
+        }
+    }
+}
+
+impl Actor for TemplateConfigWatcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        log::info!(target: LOG_TARGET, "Watching {:?} for template config changes", self.path);
+        ctx.run_interval(CONFIG_WATCH_PERIOD, Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn contracts_enabled_by_default() {
+        let mut config = TemplateConfig::default();
+        assert!(config.is_enabled("sell_token"));
+        config.contracts.insert("sell_token".into(), ContractConfig {
+            enabled: false,
+            ..ContractConfig::default()
+        });
+        assert!(!config.is_enabled("sell_token"));
+        assert!(config.is_enabled("transfer_token"));
+    }
+
+    #[test]
+    fn instruction_timeout_override() {
+        let mut config = TemplateConfig {
+            instruction_timeout_secs: 60,
+            ..TemplateConfig::default()
+        };
+        assert_eq!(config.instruction_timeout("sell_token"), Some(Duration::from_secs(60)));
+        config.contracts.insert("sell_token".into(), ContractConfig {
+            timeout_secs: Some(0),
+            ..ContractConfig::default()
+        });
+        config.contracts.insert("transfer_token".into(), ContractConfig {
+            timeout_secs: Some(5),
+            ..ContractConfig::default()
+        });
+        assert_eq!(config.instruction_timeout("sell_token"), None);
+        assert_eq!(config.instruction_timeout("transfer_token"), Some(Duration::from_secs(5)));
+        config.instruction_timeout_secs = 0;
+        assert_eq!(config.instruction_timeout("issue_tokens"), None);
+    }
+
+    #[test]
+    fn max_body_bytes_override() {
+        let mut config = TemplateConfig::default();
+        assert_eq!(config.max_body_bytes("issue_tokens"), None);
+        config.contracts.insert("issue_tokens".into(), ContractConfig {
+            max_body_bytes: Some(4096),
+            ..ContractConfig::default()
+        });
+        assert_eq!(config.max_body_bytes("issue_tokens"), Some(4096));
+        assert_eq!(config.max_body_bytes("sell_token"), None);
+    }
+
+    #[test]
+    fn retry_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            backoff_ms: 100,
+            max_backoff_ms: 350,
+        };
+        assert_eq!(policy.backoff(0), None);
+        assert_eq!(policy.backoff(1), Some(Duration::from_millis(100)));
+        assert_eq!(policy.backoff(2), Some(Duration::from_millis(200)));
+        assert_eq!(policy.backoff(3), Some(Duration::from_millis(350)));
+        assert_eq!(policy.backoff(5), None);
+        let disabled = RetryPolicy {
+            max_attempts: 1,
+            ..policy
+        };
+        assert_eq!(disabled.backoff(1), None);
+    }
+
+    #[test]
+    fn template_sections() {
+        #[derive(Debug, Default, Deserialize, PartialEq)]
+        #[serde(default)]
+        struct Options {
+            sell_timeout_secs: u64,
+            label: String,
         }
+        let mut config = TemplateConfig::default();
+        let template = serde_json::json!({
+            "runner_max_jobs": 1,
+            "contracts": { "sell_token": { "enabled": false } },
+            "single_use_tokens": { "sell_timeout_secs": 30 },
+            "escrow": { "sell_timeout_secs": "soon" },
+        });
+        config.set_sections(serde_json::from_value(template).unwrap());
+        assert_eq!(config.templates.len(), 2);
+        let options: Options = config.template_section("single_use_tokens").unwrap();
+        assert_eq!(options, Options {
+            sell_timeout_secs: 30,
+            label: String::new(),
+        });
+        assert_eq!(config.template_section::<Options>("nft").unwrap(), Options::default());
+        assert!(config.template_section::<Options>("escrow").is_err());
     }
 }
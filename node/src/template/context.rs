@@ -2,32 +2,62 @@
 //!
 //! InstructionContext is always supplied as first parameter to Smart Contract implementation
 
-use super::{Template, TemplateError, TemplateRunner, LOG_TARGET};
+use super::{config::TemplateConfig, Template, TemplateError, TemplateRunner, LOG_TARGET};
 use crate::{
+    api::{config::PayloadConfig, errors::ErrorCode},
+    conflict_err,
     consensus::{instruction_state, instruction_state::InstructionTransitionContext},
     db::{
         models::{
-            consensus::instructions::*,
+            consensus::{instructions::*, InstructionProgress, InstructionResult},
             tokens::{NewToken, Token, UpdateToken},
-            wallet::Wallet,
+            TokenReservation,
+            wallet::{SelectWallet, Wallet, NATIVE_CURRENCY},
+            Access,
+            AccessResource,
             AssetState,
+            AssetStatus,
+            AuditLog,
+            ContractPermission,
+            ContractSwitch,
+            NewAssetStateAppendOnly,
+            NewAuditLog,
+            NewTokenStateAppendOnly,
+            SelectAccess,
+        },
+        notify::InstructionNotifier,
+        utils::{
+            db::{in_transaction, ReadPool},
+            errors::DBError,
+            instrument::get_client,
         },
-        utils::errors::DBError,
     },
     metrics::{InstructionEvent, MetricEvent, Metrics},
+    not_found_err,
     processing_err,
-    types::*,
+    timeout_err,
+    types::{consensus::AppendOnlyState, *},
     validation_err,
-    wallet::{NodeWallet, WalletStore},
+    wallet::WalletStore,
 };
 use actix::Addr;
+use chrono::Utc;
 use deadpool_postgres::{Client, Pool};
+use futures::{stream, Future, StreamExt, TryStreamExt};
 use multiaddr::Multiaddr;
+use serde::de::DeserializeOwned;
 use std::{
+    collections::HashMap,
     ops::{Deref, DerefMut},
-    sync::Arc,
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, Instant},
 };
-use tokio::sync::Mutex;
+use tokio::{sync::Mutex, time::delay_for};
+
+/// Period of checking status of instruction dependencies, see [TemplateContext::wait_dependencies]
+const DEPENDENCIES_POLL_PERIOD: Duration = Duration::from_millis(500);
+/// Period of re-checking dependencies when notified about their status changes, guards against missed notifications
+const DEPENDENCIES_NOTIFIED_POLL_PERIOD: Duration = Duration::from_secs(5);
 
 /// TemplateContext, is factory for [Instruction] and [InstructionContext]
 /// It also holding address of [TemplateRunner] actor, which executes
@@ -45,11 +75,22 @@ pub struct TemplateContext<T: Template + Clone + 'static> {
     // To make it safe our templates should be completely sandboxed, e.g. via WASM etc
     // having only access to the context methods...
     pub(super) pool: Arc<Pool>,
+    // Read replica pool, same as `pool` unless replica is configured
+    pub(super) read_pool: ReadPool,
     pub(super) wallets: Arc<Mutex<WalletStore>>,
     pub(super) node_address: Multiaddr,
+    pub(super) node_id: NodeID,
     // TODO: Implement Actors registry to decouple addresses
     pub(super) actor_addr: Option<Addr<TemplateRunner<T>>>,
     pub(super) metrics_addr: Option<Addr<Metrics>>,
+    // Shared with the runner, updated on the fly by [super::config::ConfigUpdate]
+    pub(super) config: Arc<RwLock<TemplateConfig>>,
+    // Instruction status notifications, dependencies are polled if not set
+    pub(super) notifier: Option<InstructionNotifier>,
+    // Limits of contract call request bodies
+    pub(super) payload: PayloadConfig,
+    // Buffers of running instructions, subinstructions read through the buffer of their parent
+    pub(super) state_buffers: StateBuffers,
 }
 
 impl<T: Template + Clone + 'static> TemplateContext<T> {
@@ -59,12 +100,96 @@ impl<T: Template + Clone + 'static> TemplateContext<T> {
         T::id()
     }
 
-    /// Creates [Instruction]
-    pub async fn create_instruction(&self, mut data: NewInstruction) -> Result<Instruction, TemplateError> {
+    /// [NodeID] of the node running this TemplateContext
+    #[inline]
+    pub fn node_id(&self) -> NodeID {
+        self.node_id
+    }
+
+    /// Creates [Instruction], initiated by this node unless initiating node is provided
+    ///
+    /// Instruction with nonce is rejected with conflict unless nonce follows the last nonce of the signer,
+    /// so client's instructions are accepted in order even when submissions are retried.
+    /// Instructions of assets frozen by committee handover are rejected with conflict
+    pub async fn create_instruction(&self, data: NewInstruction) -> Result<Instruction, TemplateError> {
+        let client = self.get_db_client().await?;
+        let instruction = self.insert_instruction(data, &client).await?;
+        self.metrics_update(&instruction);
+        Ok(instruction)
+    }
+
+    /// Creates instructions along with their audit log records in a single DB transaction,
+    /// either all of them are created and recorded or none
+    ///
+    /// Every instruction is checked as by [TemplateContext::create_instruction] in order, so nonces of the batch
+    /// should follow each other and instructions can depend on the preceding ones
+    pub async fn create_instructions(
+        &self,
+        data: Vec<NewInstruction>,
+        access_pubkey: Option<String>,
+    ) -> Result<Vec<Instruction>, TemplateError>
+    {
+        let client = self.get_db_client().await?;
+        let instructions = in_transaction(&client, async {
+            let mut instructions = Vec::with_capacity(data.len());
+            for data in data {
+                let instruction = self.insert_instruction(data, &client).await?;
+                AuditLog::record(NewAuditLog::contract_call(&instruction, access_pubkey.clone()), &client).await?;
+                instructions.push(instruction);
+            }
+            Ok::<_, TemplateError>(instructions)
+        })
+        .await?;
+        for instruction in instructions.iter() {
+            self.metrics_update(instruction);
+        }
+        Ok(instructions)
+    }
+
+    /// Marks created instructions which were never sent to the actor as Invalid with `error`,
+    /// otherwise they would stay Scheduled as nothing is going to process them
+    pub async fn instructions_undispatched(
+        &self,
+        instructions: &[Instruction],
+        error: &TemplateError,
+    ) -> Result<(), TemplateError>
+    {
+        let asset_id = match instructions.first() {
+            Some(instruction) => instruction.asset_id.clone(),
+            None => return Ok(()),
+        };
+        let client = self.get_db_client().await?;
+        instruction_state::transition(
+            InstructionTransitionContext {
+                template_id: self.template_id(),
+                asset_id,
+                instruction_ids: instructions.iter().map(|instruction| instruction.id).collect(),
+                proposal_id: None,
+                current_status: InstructionStatus::Scheduled,
+                status: InstructionStatus::Invalid,
+                result: Some(error.code().body(&error.to_string(), serde_json::Value::Null)),
+                metrics_addr: self.metrics_addr.clone(),
+            },
+            &client,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_instruction(
+        &self,
+        mut data: NewInstruction,
+        client: &Client,
+    ) -> Result<Instruction, TemplateError>
+    {
+        if data.initiating_node_id == NodeID::default() {
+            data.initiating_node_id = self.node_id;
+        }
         if data.id == InstructionID::default() {
-            // TODO: NodeID should be provided in context
-            // TODO: There should be better way
-            data.id = InstructionID::new(NodeID::stub()).map_err(anyhow::Error::from)?;
+            data.id = match data.content_id() {
+                Some(id) => id,
+                None => InstructionID::new(self.node_id).map_err(anyhow::Error::from)?,
+            };
         }
         if data.status != InstructionStatus::Scheduled {
             return processing_err!(
@@ -72,25 +197,243 @@ impl<T: Template + Clone + 'static> TemplateContext<T> {
                 data.status
             );
         }
+        if Instruction::find(data.id, client).await?.is_some() {
+            return conflict_err!("Instruction {} has been submitted already", data.id);
+        }
+        if let Some(asset) = AssetState::find_by_asset_id(&data.asset_id, client).await? {
+            if asset.status == AssetStatus::Frozen {
+                return conflict_err!("Asset {} is frozen by committee handover", data.asset_id);
+            }
+        }
+        if let Some(nonce) = data.nonce {
+            let signer_pubkey = match data.signer_pubkey.as_ref() {
+                Some(signer_pubkey) => signer_pubkey,
+                None => return validation_err!("Instruction nonce requires signer pubkey"),
+            };
+            // concurrent submissions of the same nonce are rejected by unique index
+            let expected = Instruction::last_nonce(signer_pubkey, client).await?.unwrap_or(0) + 1;
+            if nonce != expected {
+                return conflict_err!(
+                    "Nonce {} of signer {} is out of sequence, expected {}",
+                    nonce,
+                    signer_pubkey,
+                    expected
+                );
+            }
+        }
+        data.depends_on.sort();
+        data.depends_on.dedup();
+        if data.depends_on.contains(&data.id.0) ||
+            Instruction::has_dependency_cycle(data.id, &data.depends_on, client).await?
+        {
+            return validation_err!("Instruction {} dependencies form a cycle", data.id);
+        }
+        Ok(Instruction::insert(data, client).await?)
+    }
+
+    /// Records API-initiated mutation in audit log, see [AuditLog]
+    pub async fn audit(&self, entry: NewAuditLog) -> Result<AuditLog, TemplateError> {
         let client = self.get_db_client().await?;
-        let instruction = Instruction::insert(data, &client).await?;
-        self.metrics_update(&instruction);
-        Ok(instruction)
+        Ok(AuditLog::record(entry, &client).await?)
+    }
+
+    /// Waits until every instruction `instruction` depends on is committed, checking the primary DB
+    /// whenever a dependency changes status or polling it if notifications are not available
+    ///
+    /// Fails if any dependency becomes Invalid or dependencies are not committed within `dependency_timeout`
+    pub async fn wait_dependencies(&self, instruction: &Instruction) -> Result<(), TemplateError> {
+        let started = Instant::now();
+        let timeout = Duration::from_secs(self.config().dependency_timeout);
+        // subscribed before the first check, so changes in between are not missed
+        let mut notifications = self.notifier.as_ref().map(InstructionNotifier::subscribe);
+        loop {
+            let client = self.get_db_client().await?;
+            let dependencies = instruction.load_dependencies(&client).await?;
+            if let Some(invalid) = dependencies.iter().find(|dep| dep.status == InstructionStatus::Invalid) {
+                return processing_err!("Dependency {} of instruction {} is invalid", invalid.id, instruction.id);
+            }
+            let committed = dependencies
+                .iter()
+                .filter(|dep| dep.status == InstructionStatus::Commit)
+                .count();
+            if committed == instruction.depends_on.len() {
+                return Ok(());
+            }
+            if started.elapsed() >= timeout {
+                return timeout_err!(
+                    "Instruction {} timed out waiting for dependencies, {} of {} committed",
+                    instruction.id,
+                    committed,
+                    instruction.depends_on.len()
+                );
+            }
+            drop(client);
+            match notifications.as_mut() {
+                Some(notifications) => {
+                    InstructionNotifier::wait(notifications, DEPENDENCIES_NOTIFIED_POLL_PERIOD, |notification| {
+                        instruction.depends_on.contains(&notification.id.0)
+                    })
+                    .await;
+                },
+                None => delay_for(DEPENDENCIES_POLL_PERIOD).await,
+            }
+        }
+    }
+
+    /// Checks contract is enabled and `caller` pubkey is permitted to call the contract, see [ContractPermission]
+    pub async fn authorize_contract(
+        &self,
+        caller: Option<&str>,
+        asset_id: &AssetID,
+        token_id: Option<&TokenID>,
+        contract_name: &str,
+    ) -> Result<(), TemplateError>
+    {
+        self.check_contract_enabled(contract_name).await?;
+        let client = self.get_db_client().await?;
+        if ContractPermission::is_permitted(caller, asset_id, token_id, contract_name, &client).await? {
+            Ok(())
+        } else {
+            Err(TemplateError::Forbidden(format!(
+                "{} of asset {} by {}",
+                contract_name,
+                asset_id,
+                caller.unwrap_or("anonymous caller")
+            )))
+        }
+    }
+
+    /// Checks `caller` pubkey has admin access if instruction is prioritized, priority above 0 is reserved
+    /// for operator instructions, see [crate::consensus::Mempool]
+    pub async fn authorize_priority(&self, caller: &str, priority: i32) -> Result<(), TemplateError> {
+        if priority <= 0 {
+            return Ok(());
+        }
+        let client = self.get_db_client().await?;
+        let query = SelectAccess {
+            pub_key: Some(caller.to_string()),
+            resource: AccessResource::Admin,
+            ..SelectAccess::default()
+        };
+        if Access::select(query, &client).await?.is_empty() {
+            return Err(TemplateError::Forbidden(format!(
+                "priority {} requires admin access of {}",
+                priority, caller
+            )));
+        }
+        Ok(())
     }
 
     /// Creates [InstructionContext] which can be used by [InstructionRunner] to process [Instruction]
     pub async fn instruction_context(&self, instruction: Instruction) -> Result<InstructionContext<T>, TemplateError> {
         let client = self.get_db_client().await?;
         let instruction = Instruction::load(instruction.id, &client).await?;
+        let buffer = if self.config().buffered_state {
+            Some(self.state_buffers.create(&instruction))
+        } else {
+            None
+        };
         Ok(InstructionContext {
             instruction,
             template_context: self.clone(),
             client: None,
+            in_transaction: false,
+            buffer,
+            mode: ExecutionMode::Instruction,
         })
     }
 
+    /// Creates [InstructionContext] for view contract around transient [Instruction]
+    ///
+    /// Instruction is never stored, so view contracts should only use read methods of the context
+    pub fn view_context(
+        &self,
+        asset_id: AssetID,
+        token_id: Option<TokenID>,
+        contract_name: &str,
+    ) -> InstructionContext<T>
+    {
+        let now = Utc::now();
+        let instruction = Instruction {
+            id: InstructionID::default(),
+            parent_id: None,
+            initiating_node_id: self.node_id,
+            signature: String::new(),
+            asset_id,
+            token_id,
+            template_id: T::id(),
+            contract_name: contract_name.into(),
+            status: InstructionStatus::Scheduled,
+            params: serde_json::Value::Null,
+            result: serde_json::Value::Null,
+            result_ref: None,
+            created_at: now,
+            updated_at: now,
+            proposal_id: None,
+            depends_on: vec![],
+            signer_pubkey: None,
+            nonce: None,
+            priority: 0,
+            attempts: 0,
+            batch_index: None,
+        };
+        InstructionContext {
+            instruction,
+            template_context: self.clone(),
+            client: None,
+            in_transaction: false,
+            buffer: None,
+            mode: ExecutionMode::Instruction,
+        }
+    }
+
+    /// Creates [InstructionContext] for dry run of the contract around transient [Instruction]
+    ///
+    /// State is always buffered and never stored, subinstructions are rejected and temp wallets aren't stored
+    pub fn dry_run_context(
+        &self,
+        asset_id: AssetID,
+        token_id: Option<TokenID>,
+        contract_name: &str,
+        params: serde_json::Value,
+    ) -> InstructionContext<T>
+    {
+        let mut context = self.view_context(asset_id, token_id, contract_name);
+        context.instruction.params = params;
+        context.buffer = Some(Arc::new(StateBuffer::default()));
+        context.mode = ExecutionMode::DryRun;
+        context
+    }
+
+    /// Creates [InstructionContext] re-executing committed [Instruction] on `client` running replay transaction
+    ///
+    /// State is buffered, subinstructions and temp wallets created by the instruction are loaded rather than created,
+    /// subcontracts are not called as subinstructions are replayed on their own, see [crate::template::replay]
+    pub(crate) fn replay_context(&self, instruction: Instruction, client: Arc<Client>) -> InstructionContext<T> {
+        InstructionContext {
+            instruction,
+            template_context: self.clone(),
+            client: Some(client),
+            in_transaction: true,
+            buffer: Some(Arc::new(StateBuffer::default())),
+            mode: ExecutionMode::Replay,
+        }
+    }
+
     /// Utility handler for actors when Instruction has failed
     pub async fn instruction_failed(self, instruction: Instruction, error: String) -> Result<(), TemplateError> {
+        self.instruction_failed_with_code(instruction, error, ErrorCode::Processing)
+            .await
+    }
+
+    /// Same as [TemplateContext::instruction_failed], `code` is stored along with error in instruction result
+    pub async fn instruction_failed_with_code(
+        self,
+        instruction: Instruction,
+        error: String,
+        code: ErrorCode,
+    ) -> Result<(), TemplateError>
+    {
         log::error!(
             target: LOG_TARGET,
             "template={}, instruction={}, Instruction processing failed {}",
@@ -102,7 +445,7 @@ impl<T: Template + Clone + 'static> TemplateContext<T> {
         let error = match context {
             Ok(mut context) => context
                 .transition(ContextEvent::ProcessingFailed {
-                    result: serde_json::json!({ "error": error }),
+                    result: code.body(&error, serde_json::Value::Null),
                 })
                 .await
                 .err(),
@@ -121,6 +464,121 @@ impl<T: Template + Clone + 'static> TemplateContext<T> {
         Ok(())
     }
 
+    /// Utility handler for actors when contract call failed with `err`
+    ///
+    /// [Retriable](TemplateError::is_retriable) failure schedules the instruction again according to
+    /// [RetryPolicy](super::config::RetryPolicy) and returns delay before it should be re-executed,
+    /// otherwise or when attempts are exhausted instruction becomes Invalid
+    pub async fn instruction_error(
+        self,
+        instruction: Instruction,
+        err: &TemplateError,
+    ) -> Result<Option<Duration>, TemplateError>
+    {
+        if err.is_retriable() {
+            match self.schedule_retry(&instruction, err).await {
+                Ok(Some(delay)) => return Ok(Some(delay)),
+                Ok(None) => {},
+                Err(retry_err) => log::error!(
+                    target: LOG_TARGET,
+                    "template={}, instruction={}, Failed to schedule retry {}",
+                    instruction.template_id,
+                    instruction.id,
+                    retry_err
+                ),
+            }
+        }
+        self.instruction_failed_with_code(instruction, err.to_string(), err.code())
+            .await?;
+        Ok(None)
+    }
+
+    /// Counts failed attempt and reschedules instruction, None if instruction can't be retried
+    async fn schedule_retry(
+        &self,
+        instruction: &Instruction,
+        err: &TemplateError,
+    ) -> Result<Option<Duration>, TemplateError>
+    {
+        let mut context = self.instruction_context(instruction.clone()).await?;
+        let config = self.config();
+        match context.instruction.status {
+            // failed before contract started, e.g. while loading the context
+            InstructionStatus::Scheduled => {},
+            // state of the failed attempt is rolled back along with the transaction
+            InstructionStatus::Processing if config.transactional_execution => {},
+            _ => return Ok(None),
+        }
+        let client = self.get_db_client().await?;
+        let attempts = Instruction::add_attempt(instruction.id, &client).await?;
+        let delay = match config.retry.backoff(attempts as u32) {
+            Some(delay) => delay,
+            None => return Ok(None),
+        };
+        if context.instruction.status == InstructionStatus::Processing {
+            let details = serde_json::json!({ "attempts": attempts });
+            context
+                .transition(ContextEvent::ProcessingRetry {
+                    result: err.code().body(&err.to_string(), details),
+                })
+                .await?;
+        }
+        log::warn!(
+            target: LOG_TARGET,
+            "template={}, instruction={}, Attempt {} failed {}, retrying in {}ms",
+            instruction.template_id,
+            instruction.id,
+            attempts,
+            err,
+            delay.as_millis()
+        );
+        Ok(Some(delay))
+    }
+
+    /// Current template configuration
+    pub fn config(&self) -> TemplateConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Typed options of the template from `[validator.template.<section>]` section, see [Template::config_section],
+    /// usually `context.template_config::<T::Config>()`. Defaults are used if the section is missing,
+    /// options are reloaded along with the rest of [TemplateConfig]
+    pub fn template_config<C: DeserializeOwned + Default>(&self) -> Result<C, TemplateError> {
+        let section = T::config_section();
+        let options = self.config.read().unwrap().template_section(section);
+        options.map_err(|err| {
+            TemplateError::Internal(anyhow::anyhow!("Invalid [validator.template.{}] config: {}", section, err))
+        })
+    }
+
+    /// Limits of request body of the contract call, [PayloadConfig::max_body_bytes] lowered for the contract
+    /// by [ContractConfig::max_body_bytes](super::config::ContractConfig::max_body_bytes)
+    pub fn payload_config(&self, contract_name: &str) -> PayloadConfig {
+        let mut payload = self.payload.clone();
+        if let Some(max_body_bytes) = self.config.read().unwrap().max_body_bytes(contract_name) {
+            payload.max_body_bytes = payload.max_body_bytes.min(max_body_bytes);
+        }
+        payload
+    }
+
+    /// Whether contract is enabled, [ContractSwitch] set by operator takes precedence over template configuration
+    pub async fn is_contract_enabled(&self, contract_name: &str) -> Result<bool, TemplateError> {
+        let client = self.get_db_client().await?;
+        Ok(match ContractSwitch::find(&T::id(), contract_name, &client).await? {
+            Some(switch) => switch.enabled,
+            None => self.config.read().unwrap().is_enabled(contract_name),
+        })
+    }
+
+    /// Fails with [TemplateError::Forbidden] if contract is disabled
+    pub async fn check_contract_enabled(&self, contract_name: &str) -> Result<(), TemplateError> {
+        if self.is_contract_enabled(contract_name).await? {
+            Ok(())
+        } else {
+            Err(TemplateError::Forbidden(format!("contract {} is disabled", contract_name)))
+        }
+    }
+
     /// [TemplateRunner] Actor's address, which is responsible for processing [Instruction]s
     #[inline]
     pub fn addr(&self) -> &Addr<TemplateRunner<T>> {
@@ -133,6 +591,7 @@ impl<T: Template + Clone + 'static> TemplateContext<T> {
             let msg: MetricEvent = InstructionEvent {
                 id: instruction.id,
                 template_id: T::id(),
+                asset_id: instruction.asset_id.clone(),
                 status: instruction.status,
             }
             .into();
@@ -140,8 +599,18 @@ impl<T: Template + Clone + 'static> TemplateContext<T> {
         }
     }
 
-    async fn get_db_client(&self) -> Result<Client, TemplateError> {
-        Ok(self.pool.get().await.map_err(DBError::from)?)
+    pub(crate) async fn get_db_client(&self) -> Result<Client, TemplateError> {
+        Ok(get_client(&self.pool).await.map_err(DBError::from)?)
+    }
+
+    /// DB client for load/list/find queries, might lag behind primary when read replica is configured
+    pub(crate) async fn get_read_client(&self) -> Result<Client, TemplateError> {
+        Ok(self.read_pool.get().await?)
+    }
+
+    /// Whether reads are served by separate replica pool
+    fn has_read_replica(&self) -> bool {
+        !Arc::ptr_eq(&self.pool, &self.read_pool.0)
     }
 }
 
@@ -150,6 +619,124 @@ pub struct InstructionContext<T: Template + Clone + 'static> {
     template_context: TemplateContext<T>,
     instruction: Instruction,
     client: Option<Arc<Client>>,
+    // `client` is running [ExecutionTransaction]
+    in_transaction: bool,
+    // State buffered until instruction is committed, see [TemplateConfig::buffered_state]
+    buffer: Option<Arc<StateBuffer>>,
+    mode: ExecutionMode,
+}
+
+/// State buffered by the instruction until it's committed, see [TemplateConfig::buffered_state]
+///
+/// Buffer of a subinstruction is layered over the buffer of its running parent: reads go through both,
+/// and once the subinstruction succeeds its state is moved to the parent and committed along with it,
+/// so the parent builds on it. State of a failed subinstruction is discarded without reaching the parent
+#[derive(Default)]
+struct StateBuffer {
+    state: RwLock<AppendOnlyState>,
+    parent: Option<Arc<StateBuffer>>,
+}
+
+impl StateBuffer {
+    /// The latest state of the token buffered by the instruction or its ancestors
+    fn token_state(&self, token_id: &TokenID) -> Option<NewTokenStateAppendOnly> {
+        let state = self.state.read().unwrap();
+        match state.token_state.iter().rev().find(|state| &state.token_id == token_id) {
+            Some(token_state) => Some(token_state.clone()),
+            None => self.parent.as_ref().and_then(|parent| parent.token_state(token_id)),
+        }
+    }
+
+    /// The latest state of the asset buffered by the instruction or its ancestors
+    fn asset_state(&self, asset_id: &AssetID) -> Option<NewAssetStateAppendOnly> {
+        let state = self.state.read().unwrap();
+        match state.asset_state.iter().rev().find(|state| &state.asset_id == asset_id) {
+            Some(asset_state) => Some(asset_state.clone()),
+            None => self.parent.as_ref().and_then(|parent| parent.asset_state(asset_id)),
+        }
+    }
+}
+
+/// Buffers of running instructions by their IDs, see [StateBuffer]
+///
+/// Registry keeps weak references, buffer is dropped along with context of the instruction
+#[derive(Clone, Default)]
+pub(super) struct StateBuffers(Arc<std::sync::Mutex<HashMap<InstructionID, Weak<StateBuffer>>>>);
+
+impl StateBuffers {
+    /// Buffer of the instruction, layered over the buffer of its parent if the parent is running
+    fn create(&self, instruction: &Instruction) -> Arc<StateBuffer> {
+        let mut buffers = self.0.lock().unwrap();
+        buffers.retain(|_, buffer| buffer.strong_count() > 0);
+        let parent = instruction
+            .parent_id
+            .and_then(|parent_id| buffers.get(&parent_id))
+            .and_then(Weak::upgrade);
+        let buffer = Arc::new(StateBuffer {
+            state: RwLock::default(),
+            parent,
+        });
+        // retried instruction replaces the buffer of the failed attempt
+        buffers.insert(instruction.id, Arc::downgrade(&buffer));
+        buffer
+    }
+}
+
+/// How [InstructionContext] handles effects of the contract beyond its state
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ExecutionMode {
+    /// Contract is executed for the stored instruction
+    Instruction,
+    /// Contract is executed by [TemplateContext::dry_run_context], nothing is persisted
+    DryRun,
+    /// Committed instruction is re-executed by [TemplateContext::replay_context]
+    Replay,
+}
+
+/// DB transaction wrapping contract execution, see [TemplateConfig::transactional_execution]
+///
+/// Transaction is rolled back unless committed, including when execution is dropped midway
+pub(crate) struct ExecutionTransaction {
+    client: Option<Arc<Client>>,
+}
+
+impl ExecutionTransaction {
+    /// Commits transaction if execution `result` is Ok, otherwise rolls it back returning the execution error
+    pub async fn finish(mut self, result: Result<(), TemplateError>) -> Result<(), TemplateError> {
+        let client = self.client.take().expect("ExecutionTransaction is finished once");
+        match result {
+            Ok(()) => {
+                client.batch_execute("COMMIT").await.map_err(DBError::from)?;
+                Ok(())
+            },
+            Err(err) => {
+                if let Err(rollback_err) = client.batch_execute("ROLLBACK").await {
+                    log::error!(target: LOG_TARGET, "Failed to rollback execution transaction: {}", rollback_err);
+                }
+                Err(err)
+            },
+        }
+    }
+
+    /// Rolls back transaction regardless of execution result, used by dry run
+    pub async fn rollback(mut self) -> Result<(), TemplateError> {
+        let client = self.client.take().expect("ExecutionTransaction is finished once");
+        client.batch_execute("ROLLBACK").await.map_err(DBError::from)?;
+        Ok(())
+    }
+}
+
+impl Drop for ExecutionTransaction {
+    fn drop(&mut self) {
+        // client returns to the pool once rolled back
+        if let Some(client) = self.client.take() {
+            actix_rt::spawn(async move {
+                if let Err(err) = client.batch_execute("ROLLBACK").await {
+                    log::error!(target: LOG_TARGET, "Failed to rollback dropped execution transaction: {}", err);
+                }
+            });
+        }
+    }
 }
 
 use super::actors::{ContractCallMsg, MessageResult};
@@ -162,6 +749,11 @@ pub enum ContextEvent {
     StartProcessing,
     ProcessingResult { result: serde_json::Value },
     ProcessingFailed { result: serde_json::Value },
+    /// Failed execution is retried, see [TemplateContext::instruction_error]
+    ProcessingRetry { result: serde_json::Value },
+    /// Partial progress of the running instruction, appended without changing its status,
+    /// see [InstructionProgress]
+    Progress { data: serde_json::Value },
     Commit,
 }
 
@@ -173,36 +765,114 @@ impl<T: Template + Clone> InstructionContext<T> {
 
     #[inline]
     pub fn node_id(&self) -> NodeID {
-        NodeID::stub()
+        self.template_context.node_id()
+    }
+
+    /// Options of the template, see [TemplateContext::template_config]
+    pub fn template_config<C: DeserializeOwned + Default>(&self) -> Result<C, TemplateError> {
+        self.template_context.template_config()
     }
 
     /// Create and return token
     pub async fn create_token(&self, data: NewToken) -> Result<(), TemplateError> {
+        self.create_tokens(vec![data]).await
+    }
+
+    /// Create tokens in a single batch, either all of them are created or none
+    ///
+    /// Buffered tokens are created once instruction is committed, so they can't be loaded by the instruction
+    pub async fn create_tokens(&self, data: Vec<NewToken>) -> Result<(), TemplateError> {
+        if let Some(buffer) = self.buffer.as_ref() {
+            buffer.state.write().unwrap().tokens.extend(data);
+            return Ok(());
+        }
         let client = self.get_db_client().await?;
-        let _ = Token::insert(data, &client).await?;
+        let _ = Token::insert_batch(data, &client).await?;
         Ok(())
     }
 
+    /// Runs `f` for every item with up to `limit` futures in flight, results are returned in order of `items`
+    /// regardless of completion order, so instruction results stay deterministic
+    ///
+    /// Fails with the first error, writes of already completed futures are discarded only if the context
+    /// [is_atomic](InstructionContext::is_atomic)
+    pub async fn for_each_concurrent<I, F, Fut, R>(&self, items: I, limit: usize, f: F) -> Result<Vec<R>, TemplateError>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Fut,
+        Fut: Future<Output = Result<R, TemplateError>>,
+    {
+        stream::iter(items).map(f).buffered(limit.max(1)).try_collect().await
+    }
+
+    /// Instruction runs again after a failed attempt, see [TemplateContext::instruction_error]
+    ///
+    /// Writes made outside of [ExecutionTransaction] by previous attempts are kept: subinstructions
+    /// (and the state they changed unless it's buffered along with the failed attempt), temporary wallets
+    /// and token reservations
+    pub fn is_retry(&self) -> bool {
+        self.instruction.attempts > 0
+    }
+
+    /// Writes of the instruction are discarded as a whole on failure:
+    /// it runs within [ExecutionTransaction] or its state is buffered until commit
+    pub fn is_atomic(&self) -> bool {
+        self.in_transaction || self.buffer.is_some()
+    }
+
     /// Create token_append_only_state associated with current [Instruction],
-    /// returns updated token
+    /// state is kept within instruction until commit if [TemplateConfig::buffered_state] is on
     pub async fn update_token(&self, token: Token, data: UpdateToken) -> Result<(), TemplateError> {
-        let client = self.get_db_client().await?;
-        // TODO: P1: as part of consensus multi-node this should create append only state within instruction,
-        // not in database. This also requires Instruction::execute impl.
-        token.update(data, &self.instruction, &client).await?;
+        self.append_token_state(token, data).await?;
         Ok(())
     }
 
-    /// Load token by [TokenID]
-    pub async fn load_token(&self, id: TokenID) -> Result<Option<Token>, TemplateError> {
+    /// Appends state on top of `token` returning updated token, buffered state is appended on top of
+    /// the state buffered for the token before and carries the version it was built on,
+    /// so it is rejected on commit if the token was changed by another instruction meanwhile
+    async fn append_token_state(&self, token: Token, data: UpdateToken) -> Result<Token, TemplateError> {
+        if let Some(buffer) = self.buffer.as_ref() {
+            let (token, token_state) = self.with_buffered_state(token).append_state(data, self.instruction.id);
+            buffer.state.write().unwrap().token_state.push(token_state);
+            return Ok(token);
+        }
         let client = self.get_db_client().await?;
-        Ok(Token::find_by_token_id(&id, &client).await?)
+        // TODO: P1: buffered state should become the only mode once consensus runs on every node
+        Ok(token.update(data, &self.instruction, &client).await?)
+    }
+
+    /// Token with the latest state buffered by the instruction or its parents applied,
+    /// stored token is returned as is otherwise
+    fn with_buffered_state(&self, token: Token) -> Token {
+        match self.buffer.as_ref().and_then(|buffer| buffer.token_state(&token.token_id)) {
+            Some(token_state) => token.with_state(&token_state),
+            None => token,
+        }
+    }
+
+    /// State buffered by the instruction, None unless [TemplateConfig::buffered_state] is on
+    pub fn append_only_state(&self) -> Option<AppendOnlyState> {
+        self.buffer.as_ref().map(|buffer| buffer.state.read().unwrap().clone())
     }
 
-    /// Load asset by [AssetID]
+    /// Load token by [TokenID], state buffered by the instruction is applied
+    pub async fn load_token(&self, id: TokenID) -> Result<Option<Token>, TemplateError> {
+        let client = self.get_read_client().await?;
+        let token = Token::find_by_token_id(&id, &client).await?;
+        Ok(token.map(|token| self.with_buffered_state(token)))
+    }
+
+    /// Load asset by [AssetID], state buffered by the instruction is applied
     pub async fn load_asset(&self, id: AssetID) -> Result<Option<AssetState>, TemplateError> {
-        let client = self.get_db_client().await?;
-        Ok(AssetState::find_by_asset_id(&id, &client).await?)
+        let client = self.get_read_client().await?;
+        let mut asset = AssetState::find_by_asset_id(&id, &client).await?;
+        if let (Some(asset), Some(buffer)) = (asset.as_mut(), self.buffer.as_ref()) {
+            if let Some(asset_state) = buffer.asset_state(&id) {
+                asset.status = asset_state.status;
+                asset.additional_data_json = asset_state.state_data_json;
+            }
+        }
+        Ok(asset)
     }
 
     /// Move current context's [Instruction] to a new state applying [ContextEvent]
@@ -215,6 +885,12 @@ impl<T: Template + Clone> InstructionContext<T> {
             (InstructionStatus::Processing, ContextEvent::ProcessingFailed { result }) => {
                 (InstructionStatus::Invalid, Some(result))
             },
+            (InstructionStatus::Processing, ContextEvent::ProcessingRetry { result }) => {
+                (InstructionStatus::Scheduled, Some(result))
+            },
+            (InstructionStatus::Processing, ContextEvent::Progress { data }) => {
+                return self.append_progress(data).await;
+            },
             (InstructionStatus::Pending, ContextEvent::Commit) => (InstructionStatus::Commit, None),
             (a, b) => {
                 return processing_err!(
@@ -226,9 +902,24 @@ impl<T: Template + Clone> InstructionContext<T> {
             },
         };
         let client = self.get_db_client().await?;
+        let max_result_size = self.template_context.config().max_result_size;
+        let result = match result {
+            Some(result) => {
+                Some(InstructionResult::limit(self.instruction.id, result, max_result_size, &client).await?)
+            },
+            None => None,
+        };
+        // state of subinstruction is moved to the parent once it's Pending, it's committed along with the parent
+        let parent_buffer = self.buffer.as_ref().and_then(|buffer| buffer.parent.clone());
+        if status == InstructionStatus::Pending && parent_buffer.is_none() {
+            if let Some(state) = self.append_only_state() {
+                self.instruction.store_append_only_state(&state, &client).await?;
+            }
+        }
         instruction_state::transition(
             InstructionTransitionContext {
                 template_id: T::id(),
+                asset_id: self.instruction.asset_id.clone(),
                 instruction_ids: vec![self.instruction.id],
                 proposal_id: None,
                 current_status: self.instruction.status,
@@ -239,8 +930,28 @@ impl<T: Template + Clone> InstructionContext<T> {
             &client,
         )
         .await?;
+        if let (InstructionStatus::Pending, Some(parent_buffer)) = (status, parent_buffer) {
+            let mut state = self.append_only_state().unwrap_or_default();
+            parent_buffer.state.write().unwrap().append(&mut state);
+        }
         self.instruction = Instruction::load(self.instruction.id, &client).await?;
+        // reservations are kept outside of ExecutionTransaction and survive retries, failed instruction gives them up
+        if status == InstructionStatus::Invalid {
+            let client = self.template_context.get_db_client().await?;
+            TokenReservation::release_by_instruction(self.instruction.id, &client).await?;
+        }
+
+        Ok(())
+    }
 
+    /// Progress is written outside of [ExecutionTransaction], so clients see it while instruction is running,
+    /// dry runs and replays don't report progress
+    async fn append_progress(&self, data: serde_json::Value) -> Result<(), TemplateError> {
+        if self.mode != ExecutionMode::Instruction {
+            return Ok(());
+        }
+        let client = self.template_context.get_db_client().await?;
+        InstructionProgress::append(self.instruction.id, data, &client).await?;
         Ok(())
     }
 
@@ -251,11 +962,13 @@ impl<T: Template + Clone> InstructionContext<T> {
         data: D,
     ) -> Result<Instruction, TemplateError>
     {
+        if self.mode == ExecutionMode::DryRun {
+            return validation_err!("Subinstruction {} can't be created in dry run", contract_name);
+        }
         let initiating_node_id = self.instruction.initiating_node_id;
-        let id = InstructionID::new(initiating_node_id).map_err(anyhow::Error::from)?;
         let params = serde_json::to_value(data).map_err(anyhow::Error::from)?;
+        // ID is derived from parent and content by create_instruction
         let new = NewInstruction {
-            id,
             parent_id: Some(self.instruction.id),
             initiating_node_id,
             asset_id: self.instruction.asset_id.clone(),
@@ -266,6 +979,28 @@ impl<T: Template + Clone> InstructionContext<T> {
             params,
             ..Default::default()
         };
+        if self.mode == ExecutionMode::Replay {
+            // subinstruction IDs are content addressed, so replay finds the one created by the instruction
+            let id = new.content_id().ok_or_else(|| anyhow::anyhow!("Subinstruction ID can't be derived"))?;
+            let client = self.get_db_client().await?;
+            return match Instruction::find(id, &*client).await? {
+                Some(instruction) => Ok(instruction),
+                None => validation_err!(
+                    "Subinstruction {} {} was not created by the instruction",
+                    new.contract_name,
+                    id
+                ),
+            };
+        }
+        if self.is_retry() {
+            // subinstructions created by previous attempts are kept, retried contract gets the same ones
+            if let Some(id) = new.content_id() {
+                let client = self.get_db_client().await?;
+                if let Some(instruction) = Instruction::find(id, &*client).await? {
+                    return Ok(instruction);
+                }
+            }
+        }
         Ok(self.template_context.create_instruction(new).await?)
     }
 
@@ -276,6 +1011,20 @@ impl<T: Template + Clone> InstructionContext<T> {
     /// E::into_message([Instruction]) method
     pub async fn defer<M>(&self, msg: M) -> Result<(), TemplateError>
     where M: ContractCallMsg<Template = T, Result = MessageResult> + std::fmt::Debug + 'static {
+        match self.mode {
+            ExecutionMode::DryRun => return validation_err!("Subcontracts can't be called in dry run"),
+            ExecutionMode::Replay => return Ok(()),
+            ExecutionMode::Instruction => {},
+        }
+        // subinstruction was already run by previous attempt of retried instruction
+        let subinstruction = msg.instruction();
+        match subinstruction.status {
+            InstructionStatus::Scheduled => {},
+            InstructionStatus::Invalid => {
+                return validation_err!("Subinstruction {} is invalid: {}", subinstruction.id, subinstruction.result)
+            },
+            _ => return Ok(()),
+        }
         log::trace!(
             target: LOG_TARGET,
             "template={}, instruction={}, defer message to actor: {:?}",
@@ -295,28 +1044,71 @@ impl<T: Template + Clone> InstructionContext<T> {
     }
 
     /// Create temporary wallet for accepting payment in transaction
-    /// Method will return temp_wallet [Pubkey]
+    /// Method will return temp_wallet [Pubkey], wallet is swept into node's main wallet
+    /// by [WalletJanitor](crate::wallet::janitor::WalletJanitor) once instruction completes.
+    /// Wallet key is derived from node master key along [DerivationPath](crate::wallet::DerivationPath)
+    /// of the template and instruction, so replays and retries get the wallet created by the instruction before
     pub async fn create_temp_wallet(&mut self) -> Result<Pubkey, TemplateError> {
         let wallet_name = self.instruction.id.to_string();
-        let wallet = NodeWallet::new(self.template_context.node_address.clone(), wallet_name)?;
+        if self.mode == ExecutionMode::Replay || self.is_retry() {
+            let client = self.get_db_client().await?;
+            let select = SelectWallet {
+                name: Some(wallet_name),
+                ..SelectWallet::default()
+            };
+            match Wallet::select(select, &client).await?.into_iter().next() {
+                Some(wallet) => return Ok(wallet.pub_key),
+                // previous attempt might have failed before creating the wallet
+                None if self.mode != ExecutionMode::Replay => {},
+                None => return validation_err!("Temp wallet was not created by instruction {}", self.instruction.id),
+            };
+        }
         let mut wallets = self.template_context.wallets.lock().await;
+        let wallet = wallets.new_temp(
+            self.instruction.template_id,
+            self.instruction.id,
+            self.template_context.node_address.clone(),
+        )?;
+        // wallet of dry run is never funded, so it's not stored
+        if self.mode == ExecutionMode::DryRun {
+            return Ok(wallet.public_key_hex());
+        }
 
         let mut client = self.template_context.get_db_client().await?;
         let transaction = client.transaction().await.map_err(DBError::from)?;
-        let wallet = wallets.add(wallet, &transaction).await?;
+        let wallet = wallets.add_temp(wallet, self.instruction.id, &transaction).await?;
         transaction.commit().await.map_err(DBError::from)?;
         Ok(wallet.public_key_hex())
     }
 
     /// Check balance on a wallet identified by wallet_key
     pub async fn check_balance(&self, pubkey: &Pubkey) -> Result<i64, TemplateError> {
-        let client = self.get_db_client().await?;
+        self.check_balance_in(pubkey, NATIVE_CURRENCY).await
+    }
+
+    /// Check balance in `currency` on a wallet identified by wallet_key, e.g. when the payment is another asset's
+    /// tokens rather than Tari, see [WalletBalance](crate::db::models::wallet::WalletBalance)
+    pub async fn check_balance_in(&self, pubkey: &Pubkey, currency: &str) -> Result<i64, TemplateError> {
+        let client = self.get_read_client().await?;
         let wallet = Wallet::select_by_key(pubkey, &client).await?;
-        Ok(wallet.balance)
+        Ok(wallet.balance_in(currency, &client).await?)
     }
 
     pub(crate) fn set_db_client(&mut self, client: Arc<Client>) {
-        self.client = Some(client);
+        if !self.in_transaction {
+            self.client = Some(client);
+        }
+    }
+
+    /// Starts [ExecutionTransaction] on a dedicated DB client, further writes and reads of the context go into it
+    ///
+    /// Instructions created by the context and wallets are stored outside of the transaction
+    pub(crate) async fn begin_transaction(&mut self) -> Result<ExecutionTransaction, TemplateError> {
+        let client = Arc::new(self.template_context.get_db_client().await?);
+        client.batch_execute("BEGIN").await.map_err(DBError::from)?;
+        self.client = Some(client.clone());
+        self.in_transaction = true;
+        Ok(ExecutionTransaction { client: Some(client) })
     }
 
     async fn get_db_client(&self) -> Result<Arc<Client>, TemplateError> {
@@ -326,6 +1118,73 @@ impl<T: Template + Clone> InstructionContext<T> {
             Ok(Arc::new(self.template_context.get_db_client().await?))
         }
     }
+
+    /// Read replica client if configured, otherwise shared client of the instruction,
+    /// reads within [ExecutionTransaction] always see its writes
+    pub(crate) async fn get_read_client(&self) -> Result<Arc<Client>, TemplateError> {
+        if self.template_context.has_read_replica() && !self.in_transaction {
+            Ok(Arc::new(self.template_context.get_read_client().await?))
+        } else {
+            self.get_db_client().await
+        }
+    }
+
+    /// Rejects `issued` new tokens exceeding `max_supply` of the asset and tokens received by `owners`
+    /// exceeding `limit_per_wallet`, tokens buffered by pending instructions are not counted
+    ///
+    /// Issuer is not limited per wallet, as issued tokens are owned by the issuer until transferred.
+    /// Asset is locked until the end of transaction of `client`, so tokens checked within a transaction
+    /// have to be inserted within it
+    async fn check_token_limits(
+        &self,
+        asset: &AssetState,
+        issued: usize,
+        owners: Vec<&str>,
+        client: &Client,
+    ) -> Result<(), TemplateError>
+    {
+        if asset.max_supply.is_none() && asset.limit_per_wallet.is_none() {
+            return Ok(());
+        }
+        AssetState::lock_for_update(asset.id, client).await?;
+        if let Some(max_supply) = asset.max_supply {
+            let supply = Token::count_by_asset_state_id(asset.id, client).await? as usize + issued;
+            if supply > max_supply as usize {
+                return validation_err!(
+                    "Issuing {} tokens exceeds max supply {} of asset {}",
+                    issued,
+                    max_supply,
+                    asset.asset_id
+                );
+            }
+        }
+        if let Some(limit_per_wallet) = asset.limit_per_wallet {
+            let mut received: HashMap<&str, usize> = HashMap::new();
+            for owner in owners.into_iter().filter(|owner| *owner != asset.asset_issuer_pub_key) {
+                *received.entry(owner).or_default() += 1;
+            }
+            for (owner, count) in received {
+                let owned = Token::count_by_owner(asset.id, owner, client).await? as usize + count;
+                if owned > limit_per_wallet as usize {
+                    return validation_err!(
+                        "Wallet {} would own {} tokens of asset {}, limit per wallet is {}",
+                        owner,
+                        owned,
+                        asset.asset_id,
+                        limit_per_wallet
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Initial owners of new tokens, see [InstructionContext::check_token_limits]
+fn token_owners(data: &[NewToken]) -> Vec<&str> {
+    data.iter()
+        .filter_map(|token| token.initial_data_json.get("owner_pubkey").and_then(serde_json::Value::as_str))
+        .collect()
 }
 
 /// Provides environment and methods for Instruction's code on asset to execute
@@ -357,17 +1216,115 @@ impl<T: Template + Clone> AssetInstructionContext<T> {
         &self.asset.asset_id
     }
 
-    /// Initialize from TemplateContext, instruction and asset_id
-    pub async fn init(
-        ctx: TemplateContext<T>,
-        instruction: Instruction,
-        asset_id: AssetID,
-    ) -> Result<Self, TemplateError>
-    {
-        let context = ctx.instruction_context(instruction).await?;
-        // create asset context
-        let asset = match context.load_asset(asset_id).await? {
-            None => return validation_err!("Asset ID not found"),
+    /// Create token of the asset, see [AssetInstructionContext::create_tokens]
+    pub async fn create_token(&self, data: NewToken) -> Result<(), TemplateError> {
+        self.create_tokens(vec![data]).await
+    }
+
+    /// Create tokens of the asset in a single batch, see [InstructionContext::create_tokens]
+    ///
+    /// Fails with validation error if tokens exceed `max_supply` of the asset or `limit_per_wallet` of their owners,
+    /// unless the context [is_atomic](InstructionContext::is_atomic) limits are checked and tokens are inserted
+    /// in a transaction of their own, so concurrent instructions can't exceed the limits
+    pub async fn create_tokens(&self, data: Vec<NewToken>) -> Result<(), TemplateError> {
+        if self.context.is_atomic() {
+            self.check_token_limits(&data).await?;
+            return self.context.create_tokens(data).await;
+        }
+        let client = self.context.template_context.get_db_client().await?;
+        in_transaction(&client, async {
+            self.context
+                .check_token_limits(&self.asset, data.len(), token_owners(&data), &client)
+                .await?;
+            Token::insert_batch(data, &client).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Rejects tokens exceeding `max_supply` of the asset or `limit_per_wallet` of their owners,
+    /// for tokens created in several batches via [InstructionContext::create_tokens] by atomic context,
+    /// see [AssetInstructionContext::create_tokens]
+    pub async fn check_token_limits(&self, data: &[NewToken]) -> Result<(), TemplateError> {
+        let client = self.context.get_db_client().await?;
+        self.context
+            .check_token_limits(&self.asset, data.len(), token_owners(data), &client)
+            .await
+    }
+
+    /// Create asset_state_append_only associated with current [Instruction],
+    /// updates `asset` with stored state
+    pub async fn update_asset(&mut self, state_data_json: serde_json::Value) -> Result<(), TemplateError> {
+        let asset_state = NewAssetStateAppendOnly {
+            asset_id: self.asset.asset_id.clone(),
+            instruction_id: self.context.instruction.id,
+            state_data_json,
+            status: self.asset.status,
+        };
+        if let Some(buffer) = self.context.buffer.as_ref() {
+            self.asset.additional_data_json = asset_state.state_data_json.clone();
+            buffer.state.write().unwrap().asset_state.push(asset_state);
+            return Ok(());
+        }
+        let client = self.context.get_db_client().await?;
+        AssetState::store_append_only_state(&asset_state, &client).await?;
+        self.asset = AssetState::load(self.asset.id, &client).await?;
+        Ok(())
+    }
+
+    /// Initialize from TemplateContext, instruction and asset_id
+    pub async fn init(
+        ctx: TemplateContext<T>,
+        instruction: Instruction,
+        asset_id: AssetID,
+    ) -> Result<Self, TemplateError>
+    {
+        let context = ctx.instruction_context(instruction).await?;
+        // create asset context
+        let asset = match context.load_asset(asset_id).await? {
+            None => return validation_err!("Asset ID not found"),
+            Some(asset) => asset,
+        };
+        Ok(Self::new(context, asset))
+    }
+
+    /// Initialize view contract context from TemplateContext and asset_id, see [TemplateContext::view_context]
+    pub async fn view(ctx: TemplateContext<T>, asset_id: AssetID, contract_name: &str) -> Result<Self, TemplateError> {
+        let context = ctx.view_context(asset_id.clone(), None, contract_name);
+        let asset = match context.load_asset(asset_id).await? {
+            None => return not_found_err!("Asset ID not found"),
+            Some(asset) => asset,
+        };
+        Ok(Self::new(context, asset))
+    }
+
+    /// Initialize dry run context from TemplateContext and asset_id, see [TemplateContext::dry_run_context]
+    pub async fn dry_run(
+        ctx: TemplateContext<T>,
+        asset_id: AssetID,
+        contract_name: &str,
+        params: serde_json::Value,
+    ) -> Result<Self, TemplateError>
+    {
+        let context = ctx.dry_run_context(asset_id.clone(), None, contract_name, params);
+        let asset = match context.load_asset(asset_id).await? {
+            None => return not_found_err!("Asset ID not found"),
+            Some(asset) => asset,
+        };
+        Ok(Self::new(context, asset))
+    }
+
+    /// Initialize replay context of committed instruction, see [TemplateContext::replay_context]
+    pub(crate) async fn replay(
+        ctx: TemplateContext<T>,
+        instruction: Instruction,
+        client: Arc<Client>,
+    ) -> Result<Self, TemplateError>
+    {
+        let asset_id = instruction.asset_id.clone();
+        let context = ctx.replay_context(instruction, client);
+        let asset = match context.load_asset(asset_id).await? {
+            None => return not_found_err!("Asset ID not found"),
             Some(asset) => asset,
         };
         Ok(Self::new(context, asset))
@@ -419,12 +1376,122 @@ impl<T: Template + Clone> TokenInstructionContext<T> {
         Ok(Self::new(context, asset, token))
     }
 
+    /// Initialize view contract context from TemplateContext and token_id, see [TemplateContext::view_context]
+    pub async fn view(ctx: TemplateContext<T>, token_id: TokenID, contract_name: &str) -> Result<Self, TemplateError> {
+        let context = ctx.view_context(token_id.asset_id(), Some(token_id.clone()), contract_name);
+        let asset = match context.load_asset(token_id.asset_id()).await? {
+            None => return not_found_err!("Asset ID not found"),
+            Some(asset) => asset,
+        };
+        let token = match context.load_token(token_id).await? {
+            None => return not_found_err!("Token ID not found"),
+            Some(token) => token,
+        };
+        Ok(Self::new(context, asset, token))
+    }
+
+    /// Initialize dry run context from TemplateContext and token_id, see [TemplateContext::dry_run_context]
+    pub async fn dry_run(
+        ctx: TemplateContext<T>,
+        token_id: TokenID,
+        contract_name: &str,
+        params: serde_json::Value,
+    ) -> Result<Self, TemplateError>
+    {
+        let context = ctx.dry_run_context(token_id.asset_id(), Some(token_id.clone()), contract_name, params);
+        let asset = match context.load_asset(token_id.asset_id()).await? {
+            None => return not_found_err!("Asset ID not found"),
+            Some(asset) => asset,
+        };
+        let token = match context.load_token(token_id).await? {
+            None => return not_found_err!("Token ID not found"),
+            Some(token) => token,
+        };
+        Ok(Self::new(context, asset, token))
+    }
+
+    /// Initialize replay context of committed token instruction, see [TemplateContext::replay_context]
+    pub(crate) async fn replay(
+        ctx: TemplateContext<T>,
+        instruction: Instruction,
+        client: Arc<Client>,
+    ) -> Result<Self, TemplateError>
+    {
+        let token_id = match instruction.token_id.clone() {
+            None => return validation_err!("Instruction {} is not a token instruction", instruction.id),
+            Some(token_id) => token_id,
+        };
+        let context = ctx.replay_context(instruction, client);
+        let asset = match context.load_asset(token_id.asset_id()).await? {
+            None => return not_found_err!("Asset ID not found"),
+            Some(asset) => asset,
+        };
+        let token = match context.load_token(token_id).await? {
+            None => return not_found_err!("Token ID not found"),
+            Some(token) => token,
+        };
+        Ok(Self::new(context, asset, token))
+    }
+
+    /// Reserves the token for current instruction until it is released or `ttl` expires, so other instructions
+    /// can't update it while a multi-step flow completes, subinstructions of current instruction still can.
+    /// Fails with conflict if the token is reserved by another instruction, reserving again extends the reservation
+    ///
+    /// Reservation is visible to other instructions right away, even within [ExecutionTransaction],
+    /// kept when the instruction is retried and released automatically when it fails
+    pub async fn reserve_token(&self, ttl: Duration) -> Result<(), TemplateError> {
+        match self.context.mode {
+            ExecutionMode::Instruction => {},
+            ExecutionMode::DryRun => return self.check_reservation().await,
+            ExecutionMode::Replay => return Ok(()),
+        }
+        let client = self.context.template_context.get_db_client().await?;
+        let token_id = &self.token.token_id;
+        match TokenReservation::reserve(token_id, self.context.instruction.id, ttl, &client).await? {
+            Some(_) => Ok(()),
+            None => conflict_err!("Token {} is reserved by another instruction", token_id),
+        }
+    }
+
+    /// Releases reservation of the token held by current instruction, see [TokenInstructionContext::reserve_token]
+    pub async fn release_token(&self) -> Result<(), TemplateError> {
+        if self.context.mode != ExecutionMode::Instruction {
+            return Ok(());
+        }
+        let client = self.context.template_context.get_db_client().await?;
+        TokenReservation::release(&self.token.token_id, self.context.instruction.id, &client).await?;
+        Ok(())
+    }
+
+    /// Fails with conflict if the token is reserved by another instruction
+    async fn check_reservation(&self) -> Result<(), TemplateError> {
+        if self.context.mode == ExecutionMode::Replay {
+            return Ok(());
+        }
+        let client = self.context.template_context.get_db_client().await?;
+        match TokenReservation::find_active(&self.token.token_id, &client).await? {
+            Some(reservation) if !reservation.is_held_by(&self.context.instruction) => {
+                conflict_err!("Token {} is reserved by another instruction", self.token.token_id)
+            },
+            _ => Ok(()),
+        }
+    }
+
     /// Create token_append_only_state associated with current [Instruction] and token,
-    /// returns updated token
+    /// returns updated token, fails with conflict if the token was changed by concurrent instruction
+    /// or is reserved by another one, see [TokenInstructionContext::reserve_token]
     pub async fn update_token(&mut self, data: UpdateToken) -> Result<(), TemplateError> {
-        let token = self.token.clone();
-        let client = &self.context.get_db_client().await?;
-        token.update(data, &self.context.instruction, &client).await?;
+        self.check_reservation().await?;
+        let token = self.context.with_buffered_state(self.token.clone());
+        // transfer is rejected if the new owner would exceed `limit_per_wallet` of the asset
+        let (updated, _) = token.clone().append_state(data.clone(), self.context.instruction.id);
+        if updated.owner_pubkey != token.owner_pubkey {
+            if let Some(owner) = updated.owner_pubkey.as_deref() {
+                let client = self.context.get_db_client().await?;
+                self.context.check_token_limits(&self.asset, 0, vec![owner], &client).await?;
+            }
+        }
+        self.token = self.context.append_token_state(token, data).await?;
         Ok(())
     }
 }
@@ -432,7 +1499,17 @@ impl<T: Template + Clone> TokenInstructionContext<T> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test::utils::{builders::TokenContextBuilder, test_db_client, TestTemplate};
+    use crate::{
+        db::models::{NewAccess, SelectAuditLog, TokenStatus},
+        template::config::{ContractConfig, RetryPolicy},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetContextBuilder, AssetStateBuilder, TokenContextBuilder},
+            test_db_client,
+            Test,
+            TestTemplate,
+        },
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[actix_rt::test]
     async fn instruction_failed() {
@@ -463,4 +1540,771 @@ mod test {
             .is_ok());
         log::set_max_level(log_level);
     }
+
+    #[actix_rt::test]
+    async fn retry_transient_failures() {
+        let (client, _lock) = test_db_client().await;
+        let mut token_ctx: TokenInstructionContext<TestTemplate> =
+            TokenContextBuilder::default().build().await.unwrap();
+        let instruction = token_ctx.context.instruction.clone();
+        let context = token_ctx.context.template_context.clone();
+        context.config.write().unwrap().retry = RetryPolicy {
+            max_attempts: 3,
+            backoff_ms: 10,
+            max_backoff_ms: 15,
+        };
+        let transient = TemplateError::from(DBError::Unreachable {
+            target: "database:5432".into(),
+            msg: "connection refused".into(),
+        });
+        assert!(transient.is_retriable());
+        assert!(!TemplateError::Processing("insufficient funds".into()).is_retriable());
+
+        // failed before contract started, instruction stays Scheduled
+        let delay = context.clone().instruction_error(instruction.clone(), &transient).await.unwrap();
+        assert_eq!(delay, Some(Duration::from_millis(10)));
+        let stored = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!((stored.status, stored.attempts), (InstructionStatus::Scheduled, 1));
+
+        // state of failed contract is rolled back with transactional execution, instruction is Scheduled again
+        context.config.write().unwrap().transactional_execution = true;
+        token_ctx.context.transition(ContextEvent::StartProcessing).await.unwrap();
+        let delay = context.clone().instruction_error(instruction.clone(), &transient).await.unwrap();
+        assert_eq!(delay, Some(Duration::from_millis(15)));
+        let stored = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!((stored.status, stored.attempts), (InstructionStatus::Scheduled, 2));
+        assert_eq!(stored.result["code"], "unavailable");
+        assert_eq!(stored.result["details"]["attempts"], 2);
+
+        // attempts are exhausted
+        let mut retry_ctx = context.instruction_context(instruction.clone()).await.unwrap();
+        retry_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        assert_eq!(context.clone().instruction_error(instruction.clone(), &transient).await.unwrap(), None);
+        let stored = Instruction::load(instruction.id, &client).await.unwrap();
+        assert_eq!((stored.status, stored.attempts), (InstructionStatus::Invalid, 3));
+    }
+
+    #[actix_rt::test]
+    async fn retry_after_subinstruction() {
+        let (client, _lock) = test_db_client().await;
+        let mut token_ctx: TokenInstructionContext<TestTemplate> =
+            TokenContextBuilder::default().build().await.unwrap();
+        let instruction = token_ctx.context.instruction.clone();
+        let context = token_ctx.context.template_context.clone();
+        context.config.write().unwrap().transactional_execution = true;
+        let transient = TemplateError::from(DBError::Unreachable {
+            target: "database:5432".into(),
+            msg: "connection refused".into(),
+        });
+        let params = |value| serde_json::json!({ "value": value });
+
+        token_ctx.context.transition(ContextEvent::StartProcessing).await.unwrap();
+        assert!(!token_ctx.context.is_retry());
+        let sub = token_ctx
+            .context
+            .create_subinstruction("test_contract".into(), params(1))
+            .await
+            .unwrap();
+        let delay = context.clone().instruction_error(instruction.clone(), &transient).await.unwrap();
+        assert!(delay.is_some());
+
+        // second attempt gets subinstruction of the first one instead of conflict
+        let mut retry_ctx = context.instruction_context(instruction).await.unwrap();
+        assert!(retry_ctx.is_retry());
+        retry_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        let retried = retry_ctx
+            .create_subinstruction("test_contract".into(), params(1))
+            .await
+            .unwrap();
+        assert_eq!(retried.id, sub.id);
+        let other = retry_ctx
+            .create_subinstruction("test_contract".into(), params(2))
+            .await
+            .unwrap();
+        assert_ne!(other.id, sub.id);
+        let stored = Instruction::load(sub.id, &client).await.unwrap();
+        assert_eq!(stored.parent_id, Some(retry_ctx.instruction.id));
+    }
+
+    #[actix_rt::test]
+    async fn token_reservation() {
+        let (client, _lock) = test_db_client().await;
+        let mut token_ctx: TokenInstructionContext<TestTemplate> =
+            TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let token_id = token_ctx.token.token_id.clone();
+        let other = InstructionBuilder {
+            asset_id: Some(token_ctx.asset.asset_id.clone()),
+            token_id: Some(token_id.clone()),
+            status: InstructionStatus::Scheduled,
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let mut other_ctx = TokenInstructionContext::init(context.clone(), other, token_id.clone())
+            .await
+            .unwrap();
+        let ttl = Duration::from_secs(60);
+        let update = UpdateToken {
+            status: Some(TokenStatus::Active),
+            ..UpdateToken::default()
+        };
+
+        token_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        token_ctx.reserve_token(ttl).await.unwrap();
+        token_ctx.reserve_token(ttl).await.unwrap();
+        assert!(matches!(other_ctx.reserve_token(ttl).await, Err(TemplateError::Conflict(_))));
+        assert!(matches!(
+            other_ctx.update_token(update.clone()).await,
+            Err(TemplateError::Conflict(_))
+        ));
+
+        // released once the instruction fails
+        let instruction = token_ctx.context.instruction.clone();
+        context.instruction_failed(instruction, "failed".into()).await.unwrap();
+        other_ctx.update_token(update).await.unwrap();
+        other_ctx.reserve_token(ttl).await.unwrap();
+        other_ctx.release_token().await.unwrap();
+        assert!(TokenReservation::find_active(&token_id, &client).await.unwrap().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn progress() {
+        let (client, _lock) = test_db_client().await;
+        let mut token_ctx: TokenInstructionContext<TestTemplate> =
+            TokenContextBuilder::default().build().await.unwrap();
+        let id = token_ctx.context.instruction.id;
+        let progress = |step| ContextEvent::Progress {
+            data: serde_json::json!({ "step": step }),
+        };
+        assert!(token_ctx.context.transition(progress(0)).await.is_err());
+
+        token_ctx.context.transition(ContextEvent::StartProcessing).await.unwrap();
+        let _transaction = token_ctx.context.begin_transaction().await.unwrap();
+        token_ctx.context.transition(progress(1)).await.unwrap();
+        token_ctx.context.transition(progress(2)).await.unwrap();
+
+        // visible outside of running transaction, status is unchanged
+        let stored = Instruction::load(id, &client).await.unwrap();
+        assert_eq!(stored.status, InstructionStatus::Processing);
+        let entries = InstructionProgress::find_by_instruction_id(id, &client).await.unwrap();
+        let data: Vec<_> = entries.into_iter().map(|entry| entry.data["step"].clone()).collect();
+        assert_eq!(data, vec![1, 2]);
+    }
+
+    #[actix_rt::test]
+    async fn content_addressed_instructions() {
+        let (_client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let new = NewInstruction {
+            asset_id: token_ctx.asset.asset_id.clone(),
+            token_id: Some(token_ctx.token.token_id.clone()),
+            template_id: TestTemplate::id(),
+            contract_name: "test_contract".into(),
+            status: InstructionStatus::Scheduled,
+            signature: "client-signature".into(),
+            params: serde_json::json!({ "value": 1 }),
+            ..NewInstruction::default()
+        };
+        let instruction = context.create_instruction(new.clone()).await.unwrap();
+        assert_eq!(Some(instruction.id), new.content_id());
+        assert!(instruction.has_valid_id());
+        assert!(context.create_instruction(new).await.is_err());
+
+        let mut tampered = instruction.clone();
+        tampered.params = serde_json::json!({ "value": 2 });
+        assert!(!tampered.has_valid_id());
+
+        let subinstruction = token_ctx
+            .context
+            .create_subinstruction("test_contract".into(), serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(subinstruction.has_valid_id());
+        assert_eq!(subinstruction.id.get_version(), Some(uuid::Version::Sha1));
+    }
+
+    #[actix_rt::test]
+    async fn undispatched_instructions() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let asset_id = token_ctx.asset.asset_id.clone();
+        let new = |value: i64| NewInstruction {
+            asset_id: asset_id.clone(),
+            template_id: TestTemplate::id(),
+            contract_name: "test_contract".into(),
+            status: InstructionStatus::Scheduled,
+            signature: "client-signature".into(),
+            params: serde_json::json!({ "value": value }),
+            ..NewInstruction::default()
+        };
+        let instructions = context
+            .create_instructions(vec![new(1), new(2)], Some("access-pubkey".into()))
+            .await
+            .unwrap();
+        // audit records are written in the same transaction as instructions
+        let select = SelectAuditLog {
+            asset_id: Some(asset_id.clone()),
+            ..SelectAuditLog::default()
+        };
+        let audit = AuditLog::select(select, &client).await.unwrap();
+        let mut audited: Vec<_> = audit.iter().filter_map(|record| record.instruction_id).collect();
+        audited.sort_by_key(|id| id.0);
+        let mut created: Vec<_> = instructions.iter().map(|instruction| instruction.id).collect();
+        created.sort_by_key(|id| id.0);
+        assert_eq!(audited, created);
+
+        let error = TemplateError::Processing("actor is gone".into());
+        context.instructions_undispatched(&instructions[1..], &error).await.unwrap();
+        let dispatched = Instruction::load(instructions[0].id, &client).await.unwrap();
+        assert_eq!(dispatched.status, InstructionStatus::Scheduled);
+        let undispatched = Instruction::load(instructions[1].id, &client).await.unwrap();
+        assert_eq!(undispatched.status, InstructionStatus::Invalid);
+        assert_eq!(undispatched.result["code"], "processing");
+    }
+
+    #[actix_rt::test]
+    async fn contract_switches() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let asset_id = token_ctx.asset.asset_id.clone();
+        assert!(context.is_contract_enabled("test_contract").await.unwrap());
+
+        context
+            .config
+            .write()
+            .unwrap()
+            .contracts
+            .insert("test_contract".into(), ContractConfig {
+                enabled: false,
+                ..ContractConfig::default()
+            });
+        assert!(!context.is_contract_enabled("test_contract").await.unwrap());
+
+        // switch overrides config
+        ContractSwitch::set(&TestTemplate::id(), "test_contract", true, &client)
+            .await
+            .unwrap();
+        assert!(context.is_contract_enabled("test_contract").await.unwrap());
+        ContractSwitch::set(&TestTemplate::id(), "test_contract", false, &client)
+            .await
+            .unwrap();
+        match context.authorize_contract(None, &asset_id, None, "test_contract").await {
+            Err(TemplateError::Forbidden(_)) => {},
+            res => panic!("Expected disabled contract to be forbidden, got {:?}", res),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn dependency_cycles() {
+        let (_client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let new = |id: InstructionID, depends_on: Vec<uuid::Uuid>| NewInstruction {
+            id,
+            asset_id: token_ctx.asset.asset_id.clone(),
+            template_id: TestTemplate::id(),
+            contract_name: "test_contract".into(),
+            status: InstructionStatus::Scheduled,
+            depends_on,
+            ..NewInstruction::default()
+        };
+        let lock = InstructionID::new(NodeID::stub()).unwrap();
+        let pay = InstructionID::new(NodeID::stub()).unwrap();
+        let transfer = InstructionID::new(NodeID::stub()).unwrap();
+
+        assert!(context.create_instruction(new(lock, vec![lock.0])).await.is_err());
+        // dependencies might be submitted later than dependants
+        context.create_instruction(new(pay, vec![lock.0])).await.unwrap();
+        context
+            .create_instruction(new(transfer, vec![pay.0, lock.0, pay.0]))
+            .await
+            .unwrap();
+        assert!(context.create_instruction(new(lock, vec![transfer.0])).await.is_err());
+        let lock = context.create_instruction(new(lock, vec![])).await.unwrap();
+        assert!(lock.depends_on.is_empty());
+        let transfer = Instruction::load(transfer, &context.get_db_client().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(transfer.depends_on.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn execution_transaction() {
+        let (client, _lock) = test_db_client().await;
+        let mut token_ctx: TokenInstructionContext<TestTemplate> =
+            TokenContextBuilder::default().build().await.unwrap();
+        let token_id = token_ctx.token.token_id.clone();
+        let update = |value: i32| UpdateToken {
+            append_state_data_json: Some(serde_json::json!({ "value": value })),
+            ..UpdateToken::default()
+        };
+
+        let transaction = token_ctx.begin_transaction().await.unwrap();
+        token_ctx.update_token(update(1)).await.unwrap();
+        let token = token_ctx.load_token(token_id.clone()).await.unwrap().unwrap();
+        assert_eq!(token.additional_data_json["value"], 1);
+        // not visible outside of the transaction
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert!(token.additional_data_json["value"].is_null());
+        let res = transaction.finish(processing_err!("contract failed")).await;
+        assert!(matches!(res, Err(TemplateError::Processing(_))));
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert!(token.additional_data_json["value"].is_null());
+
+        token_ctx.token = token;
+        let transaction = token_ctx.begin_transaction().await.unwrap();
+        token_ctx.update_token(update(2)).await.unwrap();
+        transaction.finish(Ok(())).await.unwrap();
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert_eq!(token.additional_data_json["value"], 2);
+    }
+
+    #[actix_rt::test]
+    async fn for_each_concurrent() {
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        assert!(!token_ctx.is_atomic());
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let run = |i: u64| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(running, Ordering::SeqCst);
+                // later items complete first
+                delay_for(Duration::from_millis(20 - i)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                if i == 15 {
+                    return validation_err!("item {} failed", i);
+                }
+                Ok(i * 2)
+            }
+        };
+
+        let res = token_ctx.for_each_concurrent(0..10, 3, run).await.unwrap();
+        assert_eq!(res, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+        let res = token_ctx.for_each_concurrent(10..20, 0, run).await;
+        assert!(matches!(res, Err(TemplateError::Validation(_))), "{:?}", res);
+    }
+
+    #[actix_rt::test]
+    async fn buffered_state() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let template_context = token_ctx.context.template_context.clone();
+        template_context.config.write().unwrap().buffered_state = true;
+        let context = template_context
+            .instruction_context(token_ctx.context.instruction.clone())
+            .await
+            .unwrap();
+        let mut token_ctx = TokenInstructionContext::new(context, token_ctx.asset, token_ctx.token);
+        let token_id = token_ctx.token.token_id.clone();
+
+        let update = UpdateToken {
+            append_state_data_json: Some(serde_json::json!({ "value": 1 })),
+            ..UpdateToken::default()
+        };
+        token_ctx.update_token(update).await.unwrap();
+        assert_eq!(token_ctx.token.additional_data_json["value"], 1);
+        // reads are served from the buffer and updates of a stale token build on the buffered state
+        let stored = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        let update = UpdateToken {
+            append_state_data_json: Some(serde_json::json!({ "other": 2 })),
+            ..UpdateToken::default()
+        };
+        token_ctx.context.update_token(stored.clone(), update).await.unwrap();
+        let token = token_ctx.load_token(token_id.clone()).await.unwrap().unwrap();
+        assert_eq!(token.additional_data_json["value"], 1);
+        assert_eq!(token.additional_data_json["other"], 2);
+        assert_eq!(token.version, stored.version + 2);
+        let new_token = NewToken {
+            token_id: Test::<TokenID>::from_asset(&token_ctx.asset.asset_id),
+            asset_state_id: token_ctx.asset.id,
+            ..NewToken::default()
+        };
+        token_ctx.create_token(new_token.clone()).await.unwrap();
+        // nothing is stored until instruction is committed
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert!(token.additional_data_json["value"].is_null());
+        assert!(Token::find_by_token_id(&new_token.token_id, &client)
+            .await
+            .unwrap()
+            .is_none());
+
+        token_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        token_ctx
+            .transition(ContextEvent::ProcessingResult {
+                result: serde_json::json!({}),
+            })
+            .await
+            .unwrap();
+        let state = token_ctx.context.instruction.execute(&client).await.unwrap();
+        assert_eq!(Some(state.clone()), token_ctx.append_only_state());
+        assert_eq!(state.token_state.len(), 2);
+        assert_eq!(state.token_state[0].state_data_json["value"], 1);
+        assert_eq!(state.token_state[0].expected_version, Some(stored.version));
+        assert_eq!(state.token_state[1].expected_version, Some(stored.version + 1));
+        assert_eq!(state.tokens, vec![new_token]);
+    }
+
+    #[actix_rt::test]
+    async fn buffered_subinstruction_state() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let template_context = token_ctx.context.template_context.clone();
+        template_context.config.write().unwrap().buffered_state = true;
+        let context = template_context
+            .instruction_context(token_ctx.context.instruction.clone())
+            .await
+            .unwrap();
+        let mut parent_ctx = TokenInstructionContext::new(context, token_ctx.asset, token_ctx.token);
+        let stored = parent_ctx.token.clone();
+        let token_id = stored.token_id.clone();
+        parent_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        parent_ctx
+            .update_token(UpdateToken {
+                append_state_data_json: Some(serde_json::json!({ "value": 1 })),
+                ..UpdateToken::default()
+            })
+            .await
+            .unwrap();
+        let subinstruction = parent_ctx
+            .create_subinstruction("test_contract".into(), serde_json::json!({}))
+            .await
+            .unwrap();
+
+        // subinstruction builds on state buffered by the parent
+        let context = template_context.instruction_context(subinstruction.clone()).await.unwrap();
+        let mut sub_ctx = TokenInstructionContext::new(context, parent_ctx.asset.clone(), stored.clone());
+        sub_ctx
+            .update_token(UpdateToken {
+                status: Some(TokenStatus::Locked),
+                ..UpdateToken::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(sub_ctx.token.additional_data_json["value"], 1);
+        let token = parent_ctx.load_token(token_id.clone()).await.unwrap().unwrap();
+        assert_ne!(token.status, TokenStatus::Locked);
+
+        // state of succeeded subinstruction is moved to the parent
+        sub_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        sub_ctx
+            .transition(ContextEvent::ProcessingResult {
+                result: serde_json::json!({}),
+            })
+            .await
+            .unwrap();
+        assert!(subinstruction.execute(&client).await.unwrap().is_empty());
+        let token = parent_ctx.load_token(token_id.clone()).await.unwrap().unwrap();
+        assert_eq!(token.status, TokenStatus::Locked);
+
+        parent_ctx
+            .update_token(UpdateToken {
+                status: Some(TokenStatus::Active),
+                ..UpdateToken::default()
+            })
+            .await
+            .unwrap();
+        parent_ctx
+            .transition(ContextEvent::ProcessingResult {
+                result: serde_json::json!({}),
+            })
+            .await
+            .unwrap();
+        let state = parent_ctx.context.instruction.execute(&client).await.unwrap();
+        let versions: Vec<_> = state.token_state.iter().map(|state| state.expected_version).collect();
+        assert_eq!(versions, vec![
+            Some(stored.version),
+            Some(stored.version + 1),
+            Some(stored.version + 2)
+        ]);
+        let ids: Vec<_> = state.token_state.iter().map(|state| state.instruction_id).collect();
+        assert_eq!(ids, vec![parent_ctx.context.instruction.id, subinstruction.id, parent_ctx.context.instruction.id]);
+        assert_eq!(state.token_state[2].status, TokenStatus::Active);
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert_eq!(token.version, stored.version);
+    }
+
+    #[actix_rt::test]
+    async fn dry_run() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let template_context = token_ctx.context.template_context.clone();
+        let token_id = token_ctx.token.token_id.clone();
+        let params = serde_json::json!({ "value": 1 });
+        let mut dry_run =
+            TokenInstructionContext::dry_run(template_context.clone(), token_id.clone(), "test_contract", params)
+                .await
+                .unwrap();
+        assert_eq!(dry_run.instruction.params["value"], 1);
+
+        let transaction = dry_run.begin_transaction().await.unwrap();
+        dry_run
+            .update_token(UpdateToken {
+                append_state_data_json: Some(serde_json::json!({ "value": 1 })),
+                ..UpdateToken::default()
+            })
+            .await
+            .unwrap();
+        let pubkey = dry_run.create_temp_wallet().await.unwrap();
+        assert!(matches!(
+            dry_run.create_subinstruction("test_contract".into(), serde_json::json!({})).await,
+            Err(TemplateError::Validation(_))
+        ));
+        transaction.rollback().await.unwrap();
+
+        let state = dry_run.append_only_state().unwrap();
+        assert_eq!(state.token_state.len(), 1);
+        assert_eq!(state.token_state[0].state_data_json["value"], 1);
+        // neither state, instruction nor wallet are stored
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert!(token.additional_data_json["value"].is_null());
+        assert!(Instruction::find(dry_run.instruction.id, &client).await.unwrap().is_none());
+        assert!(Wallet::select_by_key(&pubkey, &client).await.is_err());
+        assert!(matches!(
+            TokenInstructionContext::dry_run(
+                template_context,
+                Test::<TokenID>::from_asset(&token_id.asset_id()),
+                "test_contract",
+                serde_json::json!({})
+            )
+            .await,
+            Err(TemplateError::NotFound(_))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn nonces() {
+        let (_client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let new = |signature: &str, nonce: i64| NewInstruction {
+            asset_id: token_ctx.asset.asset_id.clone(),
+            template_id: TestTemplate::id(),
+            contract_name: "test_contract".into(),
+            status: InstructionStatus::Scheduled,
+            signature: signature.into(),
+            signer_pubkey: Some("wallet".into()),
+            nonce: Some(nonce),
+            ..NewInstruction::default()
+        };
+        let is_conflict = |res: Result<Instruction, TemplateError>| match res {
+            Err(err) => err.code() == ErrorCode::Conflict,
+            Ok(_) => false,
+        };
+
+        assert!(is_conflict(context.create_instruction(new("lock", 2)).await));
+        let lock = context.create_instruction(new("lock", 1)).await.unwrap();
+        assert_eq!(lock.nonce, Some(1));
+        // retry of the transfer with reused nonce
+        assert!(is_conflict(context.create_instruction(new("transfer", 1)).await));
+        context.create_instruction(new("transfer", 2)).await.unwrap();
+        assert!(is_conflict(context.create_instruction(new("pay", 4)).await));
+
+        let unsigned = NewInstruction {
+            signer_pubkey: None,
+            ..new("pay", 3)
+        };
+        assert!(context.create_instruction(unsigned).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn wait_dependencies() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        context.config.write().unwrap().dependency_timeout = 0;
+        let dependency = |status| InstructionBuilder {
+            status,
+            ..InstructionBuilder::default()
+        };
+        let commit = dependency(InstructionStatus::Commit).build(&client).await.unwrap();
+        let pending = dependency(InstructionStatus::Pending).build(&client).await.unwrap();
+        let invalid = dependency(InstructionStatus::Invalid).build(&client).await.unwrap();
+
+        let mut instruction = token_ctx.context.instruction.clone();
+        instruction.depends_on = vec![commit.id.0];
+        assert!(context.wait_dependencies(&instruction).await.is_ok());
+        instruction.depends_on = vec![commit.id.0, pending.id.0];
+        assert!(context.wait_dependencies(&instruction).await.is_err());
+        instruction.depends_on = vec![commit.id.0, invalid.id.0];
+        assert!(context.wait_dependencies(&instruction).await.is_err());
+        // not yet submitted dependency
+        instruction.depends_on = vec![InstructionID::new(NodeID::stub()).unwrap().0];
+        assert!(context.wait_dependencies(&instruction).await.is_err());
+
+        context.config.write().unwrap().dependency_timeout = 5;
+        instruction.depends_on = vec![pending.id.0];
+        let pending_id = pending.id;
+        actix_rt::spawn(async move {
+            delay_for(Duration::from_millis(100)).await;
+            Instruction::update_instructions_status(&[pending_id], None, InstructionStatus::Commit, None, &client)
+                .await
+                .unwrap();
+        });
+        assert!(context.wait_dependencies(&instruction).await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn token_limits() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder {
+            asset_id: Test::<AssetID>::from_template(TestTemplate::id()),
+            max_supply: Some(2),
+            limit_per_wallet: Some(1),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let asset_ctx: AssetInstructionContext<TestTemplate> = AssetContextBuilder {
+            asset: Some(asset.clone()),
+            ..Default::default()
+        }
+        .build()
+        .await
+        .unwrap();
+        let new_token = |owner: &str| NewToken {
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            asset_state_id: asset.id,
+            initial_data_json: serde_json::json!({ "owner_pubkey": owner }),
+        };
+
+        let res = asset_ctx
+            .create_tokens(vec![new_token("alice"), new_token("bob"), new_token("carol")])
+            .await;
+        assert!(matches!(res, Err(TemplateError::Validation(_))), "{:?}", res);
+        let res = asset_ctx.create_tokens(vec![new_token("alice"), new_token("alice")]).await;
+        assert!(matches!(res, Err(TemplateError::Validation(_))), "{:?}", res);
+        let alice_token = new_token("alice");
+        asset_ctx
+            .create_tokens(vec![alice_token.clone(), new_token("bob")])
+            .await
+            .unwrap();
+        let res = asset_ctx.create_token(new_token("carol")).await;
+        assert!(matches!(res, Err(TemplateError::Validation(_))), "{:?}", res);
+
+        let token = Token::find_by_token_id(&alice_token.token_id, &client).await.unwrap();
+        let mut token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder {
+            token,
+            ..Default::default()
+        }
+        .build()
+        .await
+        .unwrap();
+        let transfer = |owner: &str| UpdateToken {
+            append_state_data_json: Some(serde_json::json!({ "owner_pubkey": owner })),
+            ..UpdateToken::default()
+        };
+        let res = token_ctx.update_token(transfer("bob")).await;
+        assert!(matches!(res, Err(TemplateError::Validation(_))), "{:?}", res);
+        token_ctx.update_token(transfer("carol")).await.unwrap();
+        assert_eq!(token_ctx.token.owner_pubkey, Some("carol".into()));
+        // issuer is not limited per wallet
+        token_ctx.update_token(transfer(&asset.asset_issuer_pub_key)).await.unwrap();
+
+        let asset = AssetStateBuilder {
+            asset_id: Test::<AssetID>::from_template(TestTemplate::id()),
+            max_supply: Some(3),
+            limit_per_wallet: Some(1),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let asset_ctx: AssetInstructionContext<TestTemplate> = AssetContextBuilder {
+            asset: Some(asset.clone()),
+            ..Default::default()
+        }
+        .build()
+        .await
+        .unwrap();
+        let issuer = asset.asset_issuer_pub_key.clone();
+        let new_token = |owner: &str| NewToken {
+            token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+            asset_state_id: asset.id,
+            initial_data_json: serde_json::json!({ "owner_pubkey": owner }),
+        };
+        asset_ctx
+            .create_tokens(vec![new_token(&issuer), new_token(&issuer), new_token(&issuer)])
+            .await
+            .unwrap();
+        let res = asset_ctx.create_token(new_token(&issuer)).await;
+        assert!(matches!(res, Err(TemplateError::Validation(_))), "{:?}", res);
+    }
+
+    #[actix_rt::test]
+    async fn concurrent_issuance() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder {
+            asset_id: Test::<AssetID>::from_template(TestTemplate::id()),
+            max_supply: Some(2),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let mut contexts = vec![];
+        for _ in 0..2 {
+            let asset_ctx: AssetInstructionContext<TestTemplate> = AssetContextBuilder {
+                asset: Some(asset.clone()),
+                ..Default::default()
+            }
+            .build()
+            .await
+            .unwrap();
+            assert!(!asset_ctx.is_atomic());
+            contexts.push(asset_ctx);
+        }
+        let new_tokens = || {
+            (0..2)
+                .map(|_| NewToken {
+                    token_id: Test::<TokenID>::from_asset(&asset.asset_id),
+                    asset_state_id: asset.id,
+                    initial_data_json: serde_json::json!({ "owner_pubkey": asset.asset_issuer_pub_key }),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let (res1, res2) = futures::join!(
+            contexts[0].create_tokens(new_tokens()),
+            contexts[1].create_tokens(new_tokens()),
+        );
+        match (res1, res2) {
+            (Ok(_), Err(TemplateError::Validation(_))) | (Err(TemplateError::Validation(_)), Ok(_)) => {},
+            res => panic!("Expected exactly one issuance to succeed, got {:?}", res),
+        }
+        assert_eq!(Token::count_by_asset_state_id(asset.id, &client).await.unwrap(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn authorize_priority() {
+        let (client, _lock) = test_db_client().await;
+        let token_ctx: TokenInstructionContext<TestTemplate> = TokenContextBuilder::default().build().await.unwrap();
+        let context = token_ctx.context.template_context.clone();
+        let operator = "operator-pubkey";
+        context.authorize_priority(operator, 0).await.unwrap();
+        let res = context.authorize_priority(operator, 10).await;
+        assert!(matches!(res, Err(TemplateError::Forbidden(_))), "{:?}", res);
+
+        Access::grant(
+            NewAccess {
+                pub_key: operator.into(),
+                resource: AccessResource::Admin,
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        context.authorize_priority(operator, 10).await.unwrap();
+        let res = context.authorize_priority("user-pubkey", 1).await;
+        assert!(matches!(res, Err(TemplateError::Forbidden(_))), "{:?}", res);
+    }
 }
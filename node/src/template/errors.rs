@@ -1,4 +1,4 @@
-use crate::{consensus::errors::ConsensusError, db::utils::errors::DBError, wallet::WalletError};
+use crate::{api::errors::ErrorCode, consensus::errors::ConsensusError, db::utils::errors::DBError, wallet::WalletError};
 use std::backtrace::Backtrace;
 use thiserror::Error;
 
@@ -18,8 +18,16 @@ pub enum TemplateError {
     },
     #[error("Template processing failed: {0}")]
     Processing(String),
+    #[error("Contract call is not permitted: {0}")]
+    Forbidden(String),
     #[error("Contract parameters validation failed: {0}")]
     Validation(#[from] anyhow::Error),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Conflict with current state: {0}")]
+    Conflict(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
     #[error("Failed to send message {params} to actor {name}: {source}")]
     ActorSend {
         params: String,
@@ -39,6 +47,38 @@ pub enum TemplateError {
     ConsensusError(#[from] ConsensusError),
 }
 
+impl TemplateError {
+    /// Machine readable code of the error, returned to API clients and stored in failed instruction result
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::DB { source, .. } => source.into(),
+            Self::Wallet {
+                source: WalletError::NotFound { .. },
+                ..
+            } => ErrorCode::NotFound,
+            Self::Processing(_) => ErrorCode::Processing,
+            Self::Forbidden(_) => ErrorCode::Forbidden,
+            Self::Validation(_) => ErrorCode::Validation,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::Conflict(_) => ErrorCode::Conflict,
+            Self::Timeout(_) => ErrorCode::Timeout,
+            Self::ActorSend { .. } | Self::ActorResponse { .. } => ErrorCode::Unavailable,
+            Self::ConsensusError(ConsensusError::DBError(source)) => source.into(),
+            Self::Wallet { .. } | Self::Internal(_) | Self::ConsensusError(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// Transient failures, e.g. DB connection lost mid-contract, instruction failed with them is rescheduled
+    /// according to [RetryPolicy](super::config::RetryPolicy). Other errors are terminal, instruction becomes Invalid
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Self::DB { source, .. } | Self::ConsensusError(ConsensusError::DBError(source)) => source.is_transient(),
+            Self::ActorSend { .. } | Self::ActorResponse { .. } => true,
+            _ => false,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! internal_err {
     ($msg:literal $(,)?) => {
@@ -68,3 +108,33 @@ macro_rules! validation_err {
         Err(TemplateError::Validation(anyhow::anyhow!($fmt, $($arg)*)))
     };
 }
+
+#[macro_export]
+macro_rules! not_found_err {
+    ($msg:literal $(,)?) => {
+        Err(TemplateError::NotFound($msg.into()))
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        Err(TemplateError::NotFound(format!($fmt, $($arg)*)))
+    };
+}
+
+#[macro_export]
+macro_rules! conflict_err {
+    ($msg:literal $(,)?) => {
+        Err(TemplateError::Conflict($msg.into()))
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        Err(TemplateError::Conflict(format!($fmt, $($arg)*)))
+    };
+}
+
+#[macro_export]
+macro_rules! timeout_err {
+    ($msg:literal $(,)?) => {
+        Err(TemplateError::Timeout($msg.into()))
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        Err(TemplateError::Timeout(format!($fmt, $($arg)*)))
+    };
+}
@@ -0,0 +1,694 @@
+//! Escrow template: asset is a deal between buyer, seller and arbiter
+//!
+//! 1. `open_escrow` sets the parties, `price` and delivery timeout
+//! 2. buyer calls `deposit`, which locks escrow in `deposit_lock` subinstruction and waits for `price` in a temp
+//! wallet
+//! 3. seller calls `deliver` with delivery proof before delivery deadline
+//! 4. buyer calls `release` once delivered, or `refund` if delivery deadline expired
+//! 5. arbiter can `resolve` funded or delivered escrow in favour of either party
+//!
+//! Deal state is stored in asset state, closed escrow records `payee_pubkey` deposit belongs to.
+//! Parties are not known to contracts, calls should be restricted to parties by contract permissions
+//! (e.g. `deposit` to buyer's pubkey), see [crate::db::models::ContractPermission].
+
+use crate::{
+    db::utils::validation::ValidationErrors,
+    template::{actix_web_impl::*, *},
+    types::{Pubkey, TemplateID},
+    validation_err,
+};
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tari_template_derive::Contracts;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, JsonSchema)]
+pub enum EscrowStatus {
+    Open,
+    AwaitingDeposit,
+    Funded,
+    Delivered,
+    Released,
+    Refunded,
+}
+
+/// Escrow deal stored in asset state
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct EscrowData {
+    pub buyer_pubkey: Pubkey,
+    pub seller_pubkey: Pubkey,
+    pub arbiter_pubkey: Pubkey,
+    pub price: i64,
+    pub delivery_timeout_secs: u64,
+    pub status: EscrowStatus,
+    pub wallet_key: Option<Pubkey>,
+    pub delivery_deadline: Option<DateTime<Utc>>,
+    pub delivery_proof: Option<String>,
+    /// Party deposit belongs to once escrow is closed
+    pub payee_pubkey: Option<Pubkey>,
+}
+
+impl EscrowData {
+    pub fn is_closed(&self) -> bool {
+        self.status == EscrowStatus::Released || self.status == EscrowStatus::Refunded
+    }
+
+    fn is_overdue(&self) -> bool {
+        self.delivery_deadline
+            .map(|deadline| deadline < Utc::now())
+            .unwrap_or(false)
+    }
+}
+
+/// **************** TEMPLATE ************
+#[derive(Clone)]
+pub struct EscrowTemplate;
+impl Template for EscrowTemplate {
+    type AssetContracts = AssetContracts;
+    type TokenContracts = ();
+    type Config = ();
+
+    fn id() -> TemplateID {
+        2.into()
+    }
+
+    fn config_section() -> &'static str {
+        "escrow"
+    }
+}
+
+/// ***************** Asset contracts *******************
+
+#[derive(Contracts, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[contracts(template = "EscrowTemplate", asset)]
+/// Asset contracts for EscrowTemplate
+pub enum AssetContracts {
+    /// open_escrow sets parties and terms of the deal
+    #[contract(method = "open_escrow")]
+    OpenEscrow(OpenEscrowParams),
+    /// deposit waits for `price` to appear in the temp wallet, or `timeout_secs`
+    #[contract(method = "deposit")]
+    Deposit(DepositParams),
+    /// deposit_lock transitions escrow to AwaitingDeposit for while deposit did not complete
+    #[contract(method = "deposit_lock")]
+    DepositLock(DepositLockParams),
+    /// deliver records delivery proof by seller
+    #[contract(method = "deliver")]
+    Deliver(DeliverParams),
+    /// release closes delivered escrow in favour of seller
+    #[contract(method = "release")]
+    Release,
+    /// refund closes escrow in favour of buyer once delivery deadline expired
+    #[contract(method = "refund")]
+    Refund,
+    /// resolve closes disputed escrow by arbiter decision
+    #[contract(method = "resolve")]
+    Resolve {
+        /// Release deposit to seller if true, refund to buyer otherwise
+        release: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct OpenEscrowParams {
+    pub buyer_pubkey: Pubkey,
+    pub seller_pubkey: Pubkey,
+    pub arbiter_pubkey: Pubkey,
+    pub price: i64,
+    pub delivery_timeout_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct DepositParams {
+    pub timeout_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct DepositLockParams {
+    pub wallet_key: Pubkey,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct DeliverParams {
+    pub proof: String,
+}
+
+impl ContractParams for OpenEscrowParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.price <= 0 {
+            errors.append_validation_error("range", "price", "price should be positive");
+        }
+        if self.delivery_timeout_secs == 0 {
+            errors.append_validation_error(
+                "range",
+                "delivery_timeout_secs",
+                "delivery_timeout_secs should be positive",
+            );
+        }
+        errors.validate()
+    }
+}
+impl ContractParams for DepositParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.timeout_secs == 0 {
+            errors.append_validation_error("range", "timeout_secs", "timeout_secs should be positive");
+        }
+        errors.validate()
+    }
+}
+impl ContractParams for DepositLockParams {}
+impl ContractParams for DeliverParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.proof.trim().is_empty() {
+            errors.append_validation_error("required", "proof", "proof should not be empty");
+        }
+        errors.validate()
+    }
+}
+
+impl AssetContracts {
+    async fn open_escrow(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        OpenEscrowParams {
+            buyer_pubkey,
+            seller_pubkey,
+            arbiter_pubkey,
+            price,
+            delivery_timeout_secs,
+        }: OpenEscrowParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        if let Some(data) = Self::escrow(context) {
+            if !data.is_closed() {
+                return validation_err!("Can't open: escrow is already {:?}", data.status);
+            }
+        }
+        if price <= 0 {
+            return validation_err!("Can't open: price should be positive");
+        }
+        let data = EscrowData {
+            buyer_pubkey,
+            seller_pubkey,
+            arbiter_pubkey,
+            price,
+            delivery_timeout_secs,
+            status: EscrowStatus::Open,
+            wallet_key: None,
+            delivery_deadline: None,
+            delivery_proof: None,
+            payee_pubkey: None,
+        };
+        context.update_asset(json!(data)).await?;
+        Ok(data)
+    }
+
+    /// Deposit `price` of XTR by buyer
+    ///
+    /// # Caveats:
+    /// - Instruction is creating subinstruction with a wallet key,
+    /// - Client need to retrieve wallet key from subinstruction and transfer amount
+    async fn deposit(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        DepositParams { timeout_secs }: DepositParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        // escrow locked by previous attempt of retried deposit keeps awaiting the same wallet
+        let statuses: &[EscrowStatus] = if context.is_retry() {
+            &[EscrowStatus::Open, EscrowStatus::AwaitingDeposit]
+        } else {
+            &[EscrowStatus::Open]
+        };
+        let mut data = match Self::validate_escrow(context, statuses) {
+            Ok(data) => data,
+            Err(err) => return validation_err!("Can't deposit: {}", err),
+        };
+        let wallet_key = match data.wallet_key.clone() {
+            Some(wallet_key) if data.status == EscrowStatus::AwaitingDeposit => {
+                // state the escrow is reopened with on timeout
+                data.status = EscrowStatus::Open;
+                data.wallet_key = None;
+                wallet_key
+            },
+            _ => context.create_temp_wallet().await?,
+        };
+        let subcontract: Self = DepositLockParams {
+            wallet_key: wallet_key.clone(),
+        }
+        .into();
+        let subinstruction = context
+            .create_subinstruction("deposit_lock".into(), subcontract.clone())
+            .await?;
+        let message = subcontract.into_message(subinstruction);
+        let _ = context.defer(message).await?;
+        let timeout = std::time::Instant::now();
+        let timeout_secs = std::time::Duration::from_secs(timeout_secs);
+        tracing::info!(wallet = %wallet_key, price = data.price, "waiting for deposit");
+        while context.check_balance(&wallet_key).await? < data.price {
+            tokio::time::delay_for(std::time::Duration::from_secs(1)).await;
+            if timeout.elapsed() > timeout_secs {
+                // reopen escrow, so buyer can deposit again
+                let _ = context.update_asset(json!(data)).await;
+                return validation_err!("Timeout expired for deposit");
+            }
+        }
+        tracing::info!(wallet = %wallet_key, "deposit received");
+        data.status = EscrowStatus::Funded;
+        data.wallet_key = Some(wallet_key);
+        data.delivery_deadline = Some(Utc::now() + Duration::seconds(data.delivery_timeout_secs as i64));
+        context.update_asset(json!(data)).await?;
+        Ok(data)
+    }
+
+    /// Subcontract for deposit
+    async fn deposit_lock(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        DepositLockParams { wallet_key }: DepositLockParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        let mut data = match Self::validate_escrow(context, &[EscrowStatus::Open]) {
+            Ok(data) => data,
+            Err(err) => return validation_err!("Can't lock: {}", err),
+        };
+        data.status = EscrowStatus::AwaitingDeposit;
+        data.wallet_key = Some(wallet_key);
+        context.update_asset(json!(data)).await?;
+        Ok(data)
+    }
+
+    async fn deliver(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        DeliverParams { proof }: DeliverParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        let mut data = match Self::validate_escrow(context, &[EscrowStatus::Funded]) {
+            Ok(data) => data,
+            Err(err) => return validation_err!("Can't deliver: {}", err),
+        };
+        if data.is_overdue() {
+            return validation_err!("Can't deliver: delivery deadline expired");
+        }
+        data.status = EscrowStatus::Delivered;
+        data.delivery_proof = Some(proof);
+        context.update_asset(json!(data)).await?;
+        Ok(data)
+    }
+
+    async fn release(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        _: ReleaseParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        let data = match Self::validate_escrow(context, &[EscrowStatus::Delivered]) {
+            Ok(data) => data,
+            Err(err) => return validation_err!("Can't release: {}", err),
+        };
+        Self::close(context, data, true).await
+    }
+
+    async fn refund(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        _: RefundParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        let data = match Self::validate_escrow(context, &[EscrowStatus::Funded]) {
+            Ok(data) => data,
+            Err(err) => return validation_err!("Can't refund: {}", err),
+        };
+        if !data.is_overdue() {
+            return validation_err!("Can't refund: delivery deadline is not expired");
+        }
+        Self::close(context, data, false).await
+    }
+
+    async fn resolve(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        ResolveParams { release }: ResolveParams,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        let data = match Self::validate_escrow(context, &[EscrowStatus::Funded, EscrowStatus::Delivered]) {
+            Ok(data) => data,
+            Err(err) => return validation_err!("Can't resolve: {}", err),
+        };
+        Self::close(context, data, release).await
+    }
+
+    async fn close(
+        context: &mut AssetInstructionContext<EscrowTemplate>,
+        mut data: EscrowData,
+        release: bool,
+    ) -> Result<EscrowData, TemplateError>
+    {
+        if release {
+            data.status = EscrowStatus::Released;
+            data.payee_pubkey = Some(data.seller_pubkey.clone());
+        } else {
+            data.status = EscrowStatus::Refunded;
+            data.payee_pubkey = Some(data.buyer_pubkey.clone());
+        }
+        context.update_asset(json!(data)).await?;
+        Ok(data)
+    }
+
+    fn escrow(context: &AssetInstructionContext<EscrowTemplate>) -> Option<EscrowData> {
+        serde_json::from_value(context.asset.additional_data_json.clone()).ok()
+    }
+
+    fn validate_escrow(
+        context: &AssetInstructionContext<EscrowTemplate>,
+        statuses: &[EscrowStatus],
+    ) -> Result<EscrowData, String>
+    {
+        let data = Self::escrow(context).ok_or_else(|| "escrow is not open".to_string())?;
+        if !statuses.contains(&data.status) {
+            return Err(format!("expected escrow status {:?}, got {:?}", statuses, data.status));
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{asset_states::*, consensus::instructions::*, wallet::*, AssetStatus},
+        test::utils::{actix::TestAPIServer, builders::*, test_db_client, watcher::InstructionWatcher, Test},
+        types::AssetID,
+    };
+    use deadpool_postgres::Client;
+
+    fn open_params() -> OpenEscrowParams {
+        OpenEscrowParams {
+            buyer_pubkey: Test::<Pubkey>::new(),
+            seller_pubkey: Test::<Pubkey>::new(),
+            arbiter_pubkey: Test::<Pubkey>::new(),
+            price: 10,
+            delivery_timeout_secs: 3600,
+        }
+    }
+
+    async fn test_asset(client: &Client) -> AssetID {
+        let asset_id = Test::<AssetID>::from_template(EscrowTemplate::id());
+        AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(client)
+        .await
+        .unwrap();
+        asset_id
+    }
+
+    /// Calls contract directly with fresh context of the asset
+    async fn call(asset_id: &AssetID, contract: AssetContracts, client: &Client) -> Result<EscrowData, TemplateError> {
+        let asset = AssetState::find_by_asset_id(asset_id, client).await.unwrap().unwrap();
+        let context = AssetContextBuilder {
+            template_id: EscrowTemplate::id(),
+            asset: Some(asset),
+            ..Default::default()
+        }
+        .build()
+        .await
+        .unwrap();
+        let (value, _) = contract.call(context).await?;
+        Ok(serde_json::from_value(value).unwrap())
+    }
+
+    async fn escrow_state(asset_id: &AssetID, client: &Client) -> EscrowData {
+        let asset = AssetState::find_by_asset_id(asset_id, client).await.unwrap().unwrap();
+        serde_json::from_value(asset.additional_data_json).unwrap()
+    }
+
+    /// Overrides escrow state bypassing contracts
+    async fn set_escrow_state(asset_id: &AssetID, data: &EscrowData, client: &Client) {
+        let instruction = consensus::InstructionBuilder {
+            asset_id: Some(asset_id.clone()),
+            status: InstructionStatus::Commit,
+            ..Default::default()
+        }
+        .build(client)
+        .await
+        .unwrap();
+        AssetState::store_append_only_state(
+            &NewAssetStateAppendOnly {
+                asset_id: asset_id.clone(),
+                instruction_id: instruction.id,
+                state_data_json: json!(data),
+                status: AssetStatus::Active,
+            },
+            client,
+        )
+        .await
+        .unwrap();
+    }
+
+    async fn funded_escrow(asset_id: &AssetID, deadline: DateTime<Utc>, client: &Client) -> EscrowData {
+        let mut data = call(asset_id, open_params().into(), client).await.unwrap();
+        data.status = EscrowStatus::Funded;
+        data.wallet_key = Some(Test::<Pubkey>::new());
+        data.delivery_deadline = Some(deadline);
+        set_escrow_state(asset_id, &data, client).await;
+        data
+    }
+
+    #[actix_rt::test]
+    async fn open_escrow() {
+        let (client, _lock) = test_db_client().await;
+        let asset_id = test_asset(&client).await;
+        let params = open_params();
+        let data = call(&asset_id, params.clone().into(), &client).await.unwrap();
+        assert_eq!(data.status, EscrowStatus::Open);
+        assert_eq!(data.buyer_pubkey, params.buyer_pubkey);
+        assert_eq!(escrow_state(&asset_id, &client).await, data);
+
+        assert!(call(&asset_id, params.clone().into(), &client).await.is_err());
+        let asset_id = test_asset(&client).await;
+        let params = OpenEscrowParams { price: 0, ..params };
+        assert!(call(&asset_id, params.into(), &client).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn deliver_and_release() {
+        let (client, _lock) = test_db_client().await;
+        let asset_id = test_asset(&client).await;
+        assert!(call(&asset_id, ReleaseParams {}.into(), &client).await.is_err());
+        let escrow = funded_escrow(&asset_id, Utc::now() + Duration::hours(1), &client).await;
+        assert!(call(&asset_id, ReleaseParams {}.into(), &client).await.is_err());
+
+        let proof = DeliverParams {
+            proof: "tracking number".into(),
+        };
+        let data = call(&asset_id, proof.clone().into(), &client).await.unwrap();
+        assert_eq!(data.status, EscrowStatus::Delivered);
+        assert_eq!(data.delivery_proof, Some(proof.proof.clone()));
+        assert!(call(&asset_id, proof.into(), &client).await.is_err());
+        assert!(call(&asset_id, RefundParams {}.into(), &client).await.is_err());
+
+        let data = call(&asset_id, ReleaseParams {}.into(), &client).await.unwrap();
+        assert_eq!(data.status, EscrowStatus::Released);
+        assert_eq!(data.payee_pubkey, Some(escrow.seller_pubkey));
+        assert_eq!(escrow_state(&asset_id, &client).await, data);
+        // closed escrow can be reopened
+        assert!(call(&asset_id, open_params().into(), &client).await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn refund_after_deadline() {
+        let (client, _lock) = test_db_client().await;
+        let asset_id = test_asset(&client).await;
+        funded_escrow(&asset_id, Utc::now() + Duration::hours(1), &client).await;
+        assert!(call(&asset_id, RefundParams {}.into(), &client).await.is_err());
+
+        let asset_id = test_asset(&client).await;
+        let escrow = funded_escrow(&asset_id, Utc::now() - Duration::seconds(1), &client).await;
+        let proof = DeliverParams { proof: "late".into() };
+        assert!(call(&asset_id, proof.into(), &client).await.is_err());
+        let data = call(&asset_id, RefundParams {}.into(), &client).await.unwrap();
+        assert_eq!(data.status, EscrowStatus::Refunded);
+        assert_eq!(data.payee_pubkey, Some(escrow.buyer_pubkey));
+    }
+
+    #[actix_rt::test]
+    async fn resolve() {
+        let (client, _lock) = test_db_client().await;
+        let asset_id = test_asset(&client).await;
+        call(&asset_id, open_params().into(), &client).await.unwrap();
+        assert!(call(&asset_id, ResolveParams { release: true }.into(), &client)
+            .await
+            .is_err());
+
+        let escrow = funded_escrow(&asset_id, Utc::now() + Duration::hours(1), &client).await;
+        let proof = DeliverParams {
+            proof: "disputed".into(),
+        };
+        call(&asset_id, proof.into(), &client).await.unwrap();
+        let data = call(&asset_id, ResolveParams { release: false }.into(), &client)
+            .await
+            .unwrap();
+        assert_eq!(data.status, EscrowStatus::Refunded);
+        assert_eq!(data.payee_pubkey, Some(escrow.buyer_pubkey));
+        assert!(call(&asset_id, ResolveParams { release: true }.into(), &client)
+            .await
+            .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn deposit_timeout() {
+        let (client, _lock) = test_db_client().await;
+        let asset_id = test_asset(&client).await;
+        let escrow = call(&asset_id, open_params().into(), &client).await.unwrap();
+        assert!(call(&asset_id, DepositParams { timeout_secs: 0 }.into(), &client)
+            .await
+            .is_err());
+        assert_eq!(escrow_state(&asset_id, &client).await, escrow);
+    }
+
+    #[actix_rt::test]
+    async fn deposit_full_stack() {
+        let srv = TestAPIServer::<EscrowTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        let asset_id = test_asset(&client).await;
+        let escrow = call(&asset_id, open_params().into(), &client).await.unwrap();
+
+        let params = DepositParams { timeout_secs: 10 };
+        let mut resp = srv
+            .signed_asset_call(&asset_id, "deposit", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+        let instruction: Instruction = resp.json().await.unwrap();
+        assert_eq!(instruction.status, InstructionStatus::Scheduled);
+        assert_eq!(instruction.token_id, None);
+        let params2: AssetContracts = serde_json::from_value(instruction.params).unwrap();
+        assert_eq!(params2, params.into());
+
+        let id = instruction.id;
+        let timeout = std::time::Duration::from_secs(10);
+        let mut watcher = InstructionWatcher::listen().await;
+        let instruction = watcher
+            .wait_until(id, timeout, &client, |instruction| {
+                instruction.status == InstructionStatus::Processing
+            })
+            .await;
+        let sub = watcher.wait_subinstruction(&instruction, timeout, &client).await;
+        let params: AssetContracts = serde_json::from_value(sub.params).unwrap();
+        if let AssetContracts::DepositLock(DepositLockParams { wallet_key }) = &params {
+            let wallet = Wallet::select_by_key(wallet_key, &client).await.unwrap();
+            wallet.set_balance(escrow.price, &client).await.unwrap();
+        } else {
+            panic!("Incorrect params in subcontract {:?}", params)
+        }
+        watcher
+            .wait_until(id, timeout, &client, |instruction| {
+                instruction.status == InstructionStatus::Pending
+            })
+            .await;
+        let data = escrow_state(&asset_id, &client).await;
+        assert_eq!(data.status, EscrowStatus::Funded);
+        assert!(data.wallet_key.is_some());
+        assert!(data.delivery_deadline.is_some());
+    }
+
+    #[actix_rt::test]
+    async fn batch_access_scopes() {
+        use crate::{
+            api::models::{AccessToken, JwtSecret, ACCESS_TOKEN_ISSUER},
+            db::models::{Access, NewAccess},
+        };
+        use actix_web::http::StatusCode;
+
+        let secret = JwtSecret(b"secret".to_vec());
+        let srv = TestAPIServer::<EscrowTemplate>::builder()
+            .authentication(secret.clone())
+            .build();
+        let (client, _lock) = test_db_client().await;
+        let pubkey = Test::<Pubkey>::new();
+        Access::grant(
+            NewAccess {
+                pub_key: pubkey.clone(),
+                scopes: vec![format!("{}/open_escrow", EscrowTemplate::id())],
+                ..NewAccess::default()
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let token = AccessToken::new(pubkey, ACCESS_TOKEN_ISSUER.into(), 10)
+            .encode(&secret)
+            .unwrap();
+        let batch = |asset_id: &AssetID, calls: &serde_json::Value| {
+            srv.signed_asset_call(asset_id, "batch", calls)
+                .header("authorization", format!("Bearer {}", token))
+                .send_json(calls)
+        };
+
+        // key scoped to open_escrow can't batch other contracts
+        let asset_id = test_asset(&client).await;
+        let calls = json!([
+            { "contract": "open_escrow", "params": open_params() },
+            { "contract": "deposit", "params": DepositParams { timeout_secs: 10 } },
+        ]);
+        let resp = batch(&asset_id, &calls).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(Instruction::find_by_asset_id(&asset_id, &client).await.unwrap().is_empty());
+
+        // but can batch calls of its contract, although the route is not in its scopes
+        let calls = json!([{ "contract": "open_escrow", "params": open_params() }]);
+        let resp = batch(&asset_id, &calls).await.unwrap();
+        assert!(resp.status().is_success(), "{:?}", resp);
+        assert_eq!(Instruction::find_by_asset_id(&asset_id, &client).await.unwrap().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn negative_paths() {
+        let srv = TestAPIServer::<EscrowTemplate>::new();
+        let (_client, _lock) = test_db_client().await;
+        srv.assert_negative_paths().await;
+    }
+
+    #[actix_rt::test]
+    async fn composed_server() {
+        use crate::{template::single_use_tokens::SingleUseTokenTemplate, types::TokenID};
+        use actix_web::{web, HttpResponse};
+
+        let srv = TestAPIServer::<EscrowTemplate>::builder()
+            .template::<SingleUseTokenTemplate>()
+            .scope(|| web::scope("/ping").route("", web::get().to(|| async { HttpResponse::Ok() })))
+            .build();
+        let (client, _lock) = test_db_client().await;
+        assert!(srv.context_of::<SingleUseTokenTemplate>().is_some());
+        assert!(srv.context_of::<EscrowTemplate>().is_none());
+
+        let asset_id = test_asset(&client).await;
+        let params = open_params();
+        let resp = srv
+            .signed_asset_call(&asset_id, "open_escrow", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+
+        let tokens_asset_id = Test::<AssetID>::from_template(SingleUseTokenTemplate::id());
+        AssetStateBuilder {
+            asset_id: tokens_asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let params = json!({ "token_ids": vec![Test::<TokenID>::from_asset(&tokens_asset_id)] });
+        let resp = srv
+            .signed_asset_call(&tokens_asset_id, "issue_tokens", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+
+        let resp = srv.get("/ping").send().await.unwrap();
+        assert!(resp.status().is_success());
+    }
+}
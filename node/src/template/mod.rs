@@ -10,13 +10,14 @@
 //!
 //! Contracts trait derived on enums matching variants to contract implementation (as async fn),
 //! all the boilerplate largely is provided via macros `#[derive(Contracts)]` and `#[contract]`.
-//! See [actors] module for details and single_use_tokens for example of TokenContracts implementation.
+//! See [actors] module for details, single_use_tokens for example of TokenContracts implementation
+//! and escrow for example of AssetContracts implementation.
 //!
 //! NOTE: coupled with actix at Phase1 for API simplicity, might be decoupled later.
 //!
 //! ### Notes:
 //! - [TemplateRunner] implements [actix::Actor] and is executing Contract code in async Actor
-//! - Derive macros from [tari_validator_derive] allow token contracts auto-implementation,
+//! - Derive macros from [tari_validator_derive] allow token and asset contracts auto-implementation,
 //! generating actix-web interface and Actor Handler with Message implementation
 //! - [ContractCallMsg] trait is a message triggering contract execution: Handler for TemplateRunner
 //! is auto-implemented for all Messages which implement ContractCallMsg
@@ -25,6 +26,9 @@
 //! - Instruction states transition via InstructionContext method, transitioning happens automatically
 //! based on contract execution result. See impl [`actix::Handler`] for [TemplateRunner]
 //! - Contracts can use tokio::delay_for to wait for external event
+//! - Contract params derive [schemars::JsonSchema], schemas are collected into [TemplateRoutes] catalog
+//! and published as OpenAPI spec at `/api/spec.json`
+//! - Contract params implement [ContractParams], invalid params are rejected before instruction is created
 //!
 //! ### Caveats:
 //! - Contract Actors sharing thread pool with actix_web
@@ -38,15 +42,25 @@
 
 use crate::types::TemplateID;
 use actix_web::web;
+use serde::de::DeserializeOwned;
 
 pub mod errors;
 pub use errors::TemplateError;
 
 pub mod actix_web_impl;
-pub use actix_web_impl::{asset_call_path, token_call_path};
+pub use actix_web_impl::{asset_call_path, token_call_path, BatchCall, DryRunQuery, DryRunResult};
 pub mod actors;
 pub use actors::{ContractCallMsg, TemplateRunner};
 
+pub mod catalog;
+pub use catalog::{ContractRoute, TemplateRoutes};
+pub mod params;
+pub use params::ContractParams;
+pub mod replay;
+pub use replay::ReplayContracts;
+
+pub mod escrow;
+pub mod nft;
 pub mod single_use_tokens;
 
 pub mod config;
@@ -64,14 +78,26 @@ const LOG_TARGET: &'static str = "tari_validator_node::template";
 
 pub trait Contracts {
     fn setup_actix_routes(tpl: TemplateID, scope: &mut web::ServiceConfig);
+
+    /// Routes installed by [Contracts::setup_actix_routes] with params schemas
+    fn routes_catalog() -> Vec<ContractRoute>;
 }
 impl Contracts for () {
     fn setup_actix_routes(_: TemplateID, _: &mut web::ServiceConfig) {}
+
+    fn routes_catalog() -> Vec<ContractRoute> {
+        vec![]
+    }
 }
 
 pub trait Template: Clone {
     type AssetContracts: Contracts;
     type TokenContracts: Contracts;
+    /// Options of the template, `()` if it has none, see [TemplateContext::template_config]
+    type Config: DeserializeOwned + Default;
 
     fn id() -> TemplateID;
+
+    /// Name of `[validator.template.<section>]` config section with [Template::Config] options
+    fn config_section() -> &'static str;
 }
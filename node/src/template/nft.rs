@@ -0,0 +1,419 @@
+//! NFT template: non-fungible collectibles carrying immutable metadata
+//!
+//! Metadata (name, URI and SHA-256 hash of off-chain content) is validated by `issue_nfts` and stored in
+//! token initial data along with the first owner. Initial data is never changed, transfers only append
+//! the new owner to token state.
+//! Metadata is served by read-only query [NftTemplate::token_metadata] at `GET /tokens/{id}/metadata`
+//! and by `token_metadata` view contract, which is subject to contract permissions.
+
+use crate::{
+    db::{
+        models::{NewToken, Token, TokenStatus, UpdateToken},
+        utils::validation::ValidationErrors,
+    },
+    conflict_err,
+    template::{actix_web_impl::*, *},
+    types::{Pubkey, TemplateID, TokenID},
+    validation_err,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use tari_template_derive::Contracts;
+
+/// URI schemes of off-chain content accepted by `issue_nfts`
+pub const URI_SCHEMES: &[&str] = &["https", "http", "ipfs", "ar"];
+/// Max length of NFT name
+pub const MAX_NAME_LEN: usize = 256;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct NftMetadata {
+    pub name: String,
+    pub uri: String,
+    /// Hex encoded SHA-256 of content at `uri`
+    pub content_hash: String,
+}
+
+impl NftMetadata {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.len() > MAX_NAME_LEN {
+            return Err(format!("name should be 1 to {} characters long", MAX_NAME_LEN));
+        }
+        match self.uri.split("://").collect::<Vec<_>>().as_slice() {
+            [scheme, rest] if URI_SCHEMES.contains(scheme) && !rest.is_empty() => {},
+            _ => return Err(format!("uri {} should be absolute with one of {:?} schemes", self.uri, URI_SCHEMES)),
+        }
+        if self.content_hash.len() != 64 || !self.content_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("content_hash {} should be hex encoded SHA-256", self.content_hash));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct NftOwner {
+    pub owner_pubkey: Pubkey,
+}
+
+/// Initial data of issued token
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct NftData {
+    #[serde(flatten)]
+    pub metadata: NftMetadata,
+    pub owner_pubkey: Pubkey,
+}
+
+/// Metadata of the token resolved from its immutable initial data and current owner
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TokenMetadata {
+    pub token_id: TokenID,
+    pub status: TokenStatus,
+    pub owner_pubkey: Pubkey,
+    #[serde(flatten)]
+    pub metadata: NftMetadata,
+}
+
+/// **************** TEMPLATE ************
+#[derive(Clone)]
+pub struct NftTemplate;
+impl Template for NftTemplate {
+    type AssetContracts = AssetContracts;
+    type TokenContracts = TokenContracts;
+    type Config = ();
+
+    fn id() -> TemplateID {
+        3.into()
+    }
+
+    fn config_section() -> &'static str {
+        "nft"
+    }
+}
+
+impl NftTemplate {
+    /// Read-only query resolving token metadata, None if token was not issued by NFT template
+    pub fn token_metadata(token: &Token) -> Option<TokenMetadata> {
+        if token.token_id.asset_id().template_id() != Self::id() {
+            return None;
+        }
+        let metadata = serde_json::from_value(token.initial_data_json.clone()).ok()?;
+        let NftOwner { owner_pubkey } = serde_json::from_value(token.additional_data_json.clone()).ok()?;
+        Some(TokenMetadata {
+            token_id: token.token_id.clone(),
+            status: token.status,
+            owner_pubkey,
+            metadata,
+        })
+    }
+}
+
+/// ***************** Asset contracts *******************
+
+#[derive(Contracts, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[contracts(template = "NftTemplate", asset)]
+/// Asset contracts for NftTemplate
+pub enum AssetContracts {
+    /// issue_nfts creates a token owned by asset issuer for every metadata record
+    #[contract(method = "issue_nfts")]
+    IssueNfts(IssueNftsParams),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct IssueNftsParams {
+    pub tokens: Vec<NftMetadata>,
+}
+
+impl ContractParams for IssueNftsParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.tokens.is_empty() {
+            errors.append_validation_error("length", "tokens", "tokens should not be empty");
+        }
+        if self.tokens.iter().any(|metadata| metadata.validate().is_err()) {
+            errors.append_validation_error(
+                "format",
+                "tokens",
+                "tokens should have name, absolute uri and hex encoded SHA-256 content_hash",
+            );
+        }
+        errors.validate()
+    }
+}
+
+impl AssetContracts {
+    async fn issue_nfts(
+        context: &mut AssetInstructionContext<NftTemplate>,
+        IssueNftsParams { tokens }: IssueNftsParams,
+    ) -> Result<Vec<TokenID>, TemplateError>
+    {
+        if tokens.is_empty() {
+            return validation_err!("Can't issue: tokens should not be empty");
+        }
+        let mut hashes = HashSet::new();
+        for metadata in tokens.iter() {
+            if let Err(err) = metadata.validate() {
+                return validation_err!("Can't issue: {}", err);
+            }
+            if !hashes.insert(metadata.content_hash.to_lowercase()) {
+                return validation_err!("Can't issue: duplicate content_hash {}", metadata.content_hash);
+            }
+        }
+        let asset = &context.asset;
+        let mut token_ids = vec![];
+        let mut new_tokens = vec![];
+        for metadata in tokens {
+            let token_id = TokenID::new(&asset.asset_id, &context.node_id()).map_err(anyhow::Error::from)?;
+            let data = NftData {
+                metadata,
+                owner_pubkey: asset.asset_issuer_pub_key.clone(),
+            };
+            new_tokens.push(NewToken {
+                token_id: token_id.clone(),
+                asset_state_id: asset.id,
+                initial_data_json: json!(data),
+            });
+            token_ids.push(token_id);
+        }
+        context.create_tokens(new_tokens).await?;
+        Ok(token_ids)
+    }
+}
+
+/// ***************** Token contracts *******************
+
+#[derive(Contracts, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[contracts(template = "NftTemplate", token)]
+/// Token contracts for NftTemplate
+pub enum TokenContracts {
+    /// transfer_nft is moving token to new owner, metadata is kept intact
+    #[contract(method = "transfer_nft")]
+    TransferNft(TransferNftParams),
+    /// token_metadata is a view returning current token metadata without creating an instruction
+    #[contract(method = "token_metadata", view)]
+    TokenMetadata(TokenMetadataParams),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct TransferNftParams {
+    pub user_pubkey: Pubkey,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct TokenMetadataParams {}
+
+impl ContractParams for TransferNftParams {}
+impl ContractParams for TokenMetadataParams {}
+
+impl TokenContracts {
+    async fn transfer_nft(
+        context: &mut TokenInstructionContext<NftTemplate>,
+        TransferNftParams { user_pubkey }: TransferNftParams,
+    ) -> Result<TokenMetadata, TemplateError>
+    {
+        if context.token.status != TokenStatus::Available {
+            return conflict_err!(
+                "Can't transfer: expected token status {}, got {}",
+                TokenStatus::Available,
+                context.token.status
+            );
+        }
+        let data = UpdateToken {
+            append_state_data_json: Some(json!(NftOwner { owner_pubkey: user_pubkey })),
+            ..Default::default()
+        };
+        context.update_token(data).await?;
+        Self::token_metadata(context, TokenMetadataParams {}).await
+    }
+
+    async fn token_metadata(
+        context: &TokenInstructionContext<NftTemplate>,
+        _: TokenMetadataParams,
+    ) -> Result<TokenMetadata, TemplateError>
+    {
+        match NftTemplate::token_metadata(&context.token) {
+            Some(metadata) => Ok(metadata),
+            None => validation_err!("Token {} has no NFT metadata", context.token.token_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::consensus::instructions::*,
+        test::utils::{actix::TestAPIServer, builders::*, test_db_client, Test},
+        types::AssetID,
+    };
+    use std::time::Duration;
+
+    fn metadata(n: u8) -> NftMetadata {
+        NftMetadata {
+            name: format!("Collectible #{}", n),
+            uri: format!("ipfs://QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbd{}", n),
+            content_hash: format!("{:064x}", n),
+        }
+    }
+
+    #[test]
+    fn validate_metadata() {
+        assert!(metadata(1).validate().is_ok());
+        let invalid = vec![
+            NftMetadata {
+                name: " ".into(),
+                ..metadata(1)
+            },
+            NftMetadata {
+                uri: "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".into(),
+                ..metadata(1)
+            },
+            NftMetadata {
+                uri: "ftp://example.com/1.png".into(),
+                ..metadata(1)
+            },
+            NftMetadata {
+                content_hash: "abc".into(),
+                ..metadata(1)
+            },
+            NftMetadata {
+                content_hash: "z".repeat(64),
+                ..metadata(1)
+            },
+        ];
+        for metadata in invalid {
+            assert!(metadata.validate().is_err(), "{:?}", metadata);
+        }
+    }
+
+    async fn build_context() -> AssetInstructionContext<NftTemplate> {
+        AssetContextBuilder {
+            template_id: NftTemplate::id(),
+            ..Default::default()
+        }
+        .build()
+        .await
+        .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn issue_nfts() {
+        let (client, _lock) = test_db_client().await;
+        let context = build_context().await;
+        let issuer = context.asset.asset_issuer_pub_key.clone();
+        let contract: AssetContracts = IssueNftsParams {
+            tokens: vec![metadata(1), metadata(2)],
+        }
+        .into();
+        let (value, _) = contract.call(context).await.unwrap();
+        let token_ids: Vec<TokenID> = serde_json::from_value(value).unwrap();
+        assert_eq!(token_ids.len(), 2);
+        for (n, token_id) in token_ids.iter().enumerate() {
+            let token = Token::find_by_token_id(token_id, &client).await.unwrap().unwrap();
+            let resolved = NftTemplate::token_metadata(&token).unwrap();
+            assert_eq!(resolved.metadata, metadata(n as u8 + 1));
+            assert_eq!(resolved.owner_pubkey, issuer);
+        }
+
+        for tokens in vec![vec![], vec![metadata(1), metadata(1)], vec![NftMetadata {
+            uri: "".into(),
+            ..metadata(1)
+        }]] {
+            let contract: AssetContracts = IssueNftsParams { tokens }.into();
+            assert!(contract.call(build_context().await).await.is_err());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn transfer_nft_full_stack() {
+        let srv = TestAPIServer::<NftTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        let asset_id = Test::<AssetID>::from_template(NftTemplate::id());
+        let asset = AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let context = AssetContextBuilder {
+            template_id: NftTemplate::id(),
+            asset: Some(asset),
+            ..Default::default()
+        }
+        .build()
+        .await
+        .unwrap();
+        let contract: AssetContracts = IssueNftsParams {
+            tokens: vec![metadata(1)],
+        }
+        .into();
+        let (value, _) = contract.call(context).await.unwrap();
+        let token_ids: Vec<TokenID> = serde_json::from_value(value).unwrap();
+
+        let params = TransferNftParams {
+            user_pubkey: Test::<Pubkey>::new(),
+        };
+        let mut resp = srv
+            .signed_token_call(&token_ids[0], "transfer_nft", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+        let instruction: Instruction = resp.json().await.unwrap();
+        Test::<Instruction>::wait_status(
+            instruction.id,
+            InstructionStatus::Pending,
+            Duration::from_secs(1),
+            &client,
+        )
+        .await;
+        let token = Token::find_by_token_id(&token_ids[0], &client).await.unwrap().unwrap();
+        let resolved = NftTemplate::token_metadata(&token).unwrap();
+        assert_eq!(resolved.owner_pubkey, params.user_pubkey);
+        assert_eq!(resolved.metadata, metadata(1));
+    }
+
+    #[actix_rt::test]
+    async fn token_metadata_view() {
+        let srv = TestAPIServer::<NftTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        let context = build_context().await;
+        let issuer = context.asset.asset_issuer_pub_key.clone();
+        let contract: AssetContracts = IssueNftsParams {
+            tokens: vec![metadata(1)],
+        }
+        .into();
+        let (value, _) = contract.call(context).await.unwrap();
+        let token_ids: Vec<TokenID> = serde_json::from_value(value).unwrap();
+        let token_id = &token_ids[0];
+
+        let mut resp = srv
+            .get(crate::template::token_call_path(token_id, "token_metadata"))
+            .send()
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+        let resolved: TokenMetadata = resp.json().await.unwrap();
+        assert_eq!(&resolved.token_id, token_id);
+        assert_eq!(resolved.owner_pubkey, issuer);
+        assert_eq!(resolved.metadata, metadata(1));
+
+        // views are not served via POST and never create instructions
+        let resp = srv
+            .signed_token_call(token_id, "token_metadata", &TokenMetadataParams {})
+            .send_json(&TokenMetadataParams {})
+            .await
+            .unwrap();
+        assert!(!resp.status().is_success());
+        let instructions = Instruction::find_recent(100, &client).await.unwrap();
+        assert!(instructions.iter().all(|i| i.token_id.as_ref() != Some(token_id)));
+    }
+
+    #[actix_rt::test]
+    async fn negative_paths() {
+        let srv = TestAPIServer::<NftTemplate>::new();
+        let (_client, _lock) = test_db_client().await;
+        srv.assert_negative_paths().await;
+    }
+}
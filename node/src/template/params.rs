@@ -0,0 +1,48 @@
+//! Validation of contract params
+//!
+//! Params are deserialized by serde, which only checks types, so value constraints
+//! (e.g. positive price) are checked by [ContractParams::validate] in generated web handler
+//! before instruction is created. Errors are returned as 422 with errors per field.
+
+use crate::db::utils::validation::ValidationErrors;
+
+/// Params of contract call, implemented for every params struct of `#[derive(Contracts)]` enum
+pub trait ContractParams {
+    /// Field level validation of params, accepts any value by default
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
+
+/// Optional params are validated only if provided
+impl<P: ContractParams> ContractParams for Option<P> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            Some(params) => params.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Positive(i64);
+    impl ContractParams for Positive {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::default();
+            if self.0 <= 0 {
+                errors.append_validation_error("range", "value", "value should be positive");
+            }
+            errors.validate()
+        }
+    }
+
+    #[test]
+    fn optional_params() {
+        assert!(None::<Positive>.validate().is_ok());
+        assert!(Some(Positive(1)).validate().is_ok());
+        assert!(Some(Positive(0)).validate().is_err());
+    }
+}
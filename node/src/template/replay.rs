@@ -0,0 +1,275 @@
+//! Deterministic replay of committed instructions
+//!
+//! Committed instructions of the asset are re-executed in order of creation on a scratch copy of the asset state:
+//! replay runs in a DB transaction which is always rolled back, records of replayed instructions are removed
+//! from the scratch history first and stored records of every instruction are appended back once it's replayed,
+//! so each instruction sees the same state as when it was executed and divergences don't cascade.
+//!
+//! Tokens are not removed from scratch state, so instructions see tokens issued later on. Temp wallets
+//! report current balance, contracts waiting for payment are cut by `timeout` and reported as diverged.
+
+use super::{DryRunResult, Template, TemplateContext, TemplateError, LOG_TARGET};
+use crate::{
+    db::{
+        models::{consensus::instructions::Instruction, StateDelta},
+        utils::errors::DBError,
+    },
+    types::{consensus::AppendOnlyState, AssetID, InstructionID},
+    validation_err,
+};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Client;
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+/// Contracts re-executed from params of committed [Instruction], implemented by `#[derive(Contracts)]`
+pub trait ReplayContracts<T: Template + 'static> {
+    fn replay(
+        context: TemplateContext<T>,
+        instruction: Instruction,
+        client: Arc<Client>,
+    ) -> LocalBoxFuture<'static, Result<DryRunResult, TemplateError>>;
+}
+
+impl<T: Template + 'static> ReplayContracts<T> for () {
+    fn replay(
+        _: TemplateContext<T>,
+        instruction: Instruction,
+        _: Arc<Client>,
+    ) -> LocalBoxFuture<'static, Result<DryRunResult, TemplateError>>
+    {
+        Box::pin(async move {
+            validation_err!(
+                "Template {} has no contracts for instruction {}",
+                T::id(),
+                instruction.id
+            )
+        })
+    }
+}
+
+/// Instruction which state differs from the stored one or which failed to replay
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Divergence {
+    pub instruction_id: InstructionID,
+    pub contract_name: String,
+    pub stored: AppendOnlyState,
+    pub replayed: Option<AppendOnlyState>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReplayReport {
+    pub asset_id: AssetID,
+    pub from: DateTime<Utc>,
+    /// Number of replayed instructions
+    pub instructions: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Replays committed instructions of the asset created at or after `from`, every instruction is given `timeout`
+pub async fn replay_instructions<T>(
+    context: TemplateContext<T>,
+    asset_id: &AssetID,
+    from: DateTime<Utc>,
+    timeout: Duration,
+) -> Result<ReplayReport, TemplateError>
+where
+    T: Template + 'static,
+    T::AssetContracts: ReplayContracts<T>,
+    T::TokenContracts: ReplayContracts<T>,
+{
+    let client = Arc::new(context.get_db_client().await?);
+    client.batch_execute("BEGIN").await.map_err(DBError::from)?;
+    let report = replay_scratch(context, asset_id, from, timeout, client.clone()).await;
+    // scratch state is never kept
+    client.batch_execute("ROLLBACK").await.map_err(DBError::from)?;
+    report
+}
+
+async fn replay_scratch<T>(
+    context: TemplateContext<T>,
+    asset_id: &AssetID,
+    from: DateTime<Utc>,
+    timeout: Duration,
+    client: Arc<Client>,
+) -> Result<ReplayReport, TemplateError>
+where
+    T: Template + 'static,
+    T::AssetContracts: ReplayContracts<T>,
+    T::TokenContracts: ReplayContracts<T>,
+{
+    let instructions = Instruction::find_committed_since(asset_id, from, &*client).await?;
+    let ids: Vec<InstructionID> = instructions.iter().map(|instruction| instruction.id).collect();
+    let removed = StateDelta::rewind(asset_id, &ids, &client).await?;
+    let mut report = ReplayReport {
+        asset_id: asset_id.clone(),
+        from,
+        instructions: instructions.len(),
+        divergences: vec![],
+    };
+    for instruction in instructions {
+        // buffered instructions keep whole state, otherwise state is known from records only,
+        // buffer of the parent keeps state of its subinstructions as well, which are replayed on their own
+        let buffered = instruction.execute(&client).await?;
+        let (asset_state, token_state) = if buffered.is_empty() {
+            (&removed.asset_state, &removed.token_state)
+        } else {
+            (&buffered.asset_state, &buffered.token_state)
+        };
+        let stored = AppendOnlyState {
+            asset_state: asset_state
+                .iter()
+                .filter(|state| state.instruction_id == instruction.id)
+                .cloned()
+                .collect(),
+            token_state: token_state
+                .iter()
+                .filter(|state| state.instruction_id == instruction.id)
+                .cloned()
+                .collect(),
+            tokens: buffered.tokens.clone(),
+            committee_handover: buffered.committee_handover.clone(),
+        };
+        let replay = if instruction.token_id.is_some() {
+            <T::TokenContracts as ReplayContracts<T>>::replay(context.clone(), instruction.clone(), client.clone())
+        } else {
+            <T::AssetContracts as ReplayContracts<T>>::replay(context.clone(), instruction.clone(), client.clone())
+        };
+        let divergence = match tokio::time::timeout(timeout, replay).await {
+            Ok(Ok(DryRunResult { mut append_only_state, .. })) => {
+                if buffered.is_empty() {
+                    // tokens and handovers are not recorded by instructions which stored state directly
+                    append_only_state.tokens = vec![];
+                    append_only_state.committee_handover = None;
+                }
+                if append_only_state == stored {
+                    None
+                } else {
+                    Some((Some(append_only_state), None))
+                }
+            },
+            Ok(Err(err)) => Some((None, Some(err.to_string()))),
+            Err(_) => Some((None, Some(format!("Replay timed out after {:?}", timeout)))),
+        };
+        if let Some((replayed, error)) = divergence {
+            log::warn!(
+                target: LOG_TARGET,
+                "Instruction {} {} diverged on replay",
+                instruction.id,
+                instruction.contract_name
+            );
+            report.divergences.push(Divergence {
+                instruction_id: instruction.id,
+                contract_name: instruction.contract_name.clone(),
+                stored: stored.clone(),
+                replayed,
+                error,
+            });
+        }
+        // following instructions see stored state regardless of the replay result
+        StateDelta {
+            asset_state: stored.asset_state,
+            token_state: stored.token_state,
+            ..StateDelta::default()
+        }
+        .store(&client)
+        .await?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{AssetState, InstructionStatus, NewAssetStateAppendOnly},
+        template::{
+            single_use_tokens::{AssetContracts, IssueTokensParams, SingleUseTokenTemplate},
+            TemplateRunner,
+        },
+        test::utils::{
+            actix_test_pool,
+            build_test_config,
+            builders::{consensus::InstructionBuilder, AssetStateBuilder},
+            test_db_client,
+            Test,
+        },
+        types::TokenID,
+    };
+    use actix::Actor;
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn replay() {
+        let (client, _lock) = test_db_client().await;
+        let context =
+            TemplateRunner::<SingleUseTokenTemplate>::create(actix_test_pool(), build_test_config().unwrap(), None)
+                .start();
+        let asset_id = Test::<AssetID>::from_template(SingleUseTokenTemplate::id());
+        let asset = AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let from = Utc::now();
+        let instruction = |params: serde_json::Value| InstructionBuilder {
+            asset_id: Some(asset_id.clone()),
+            template_id: SingleUseTokenTemplate::id(),
+            contract_name: "issue_tokens".into(),
+            status: InstructionStatus::Commit,
+            params,
+            ..Default::default()
+        };
+        let issue = |token_id: TokenID| {
+            let contract: AssetContracts = IssueTokensParams {
+                token_ids: Some(vec![token_id]),
+                quantity: None,
+            }
+            .into();
+            serde_json::to_value(contract).unwrap()
+        };
+        instruction(issue(Test::<TokenID>::from_asset(&asset_id)))
+            .build(&client)
+            .await
+            .unwrap();
+        // issue_tokens never changes asset state
+        let tampered = instruction(issue(Test::<TokenID>::from_asset(&asset_id)))
+            .build(&client)
+            .await
+            .unwrap();
+        AssetState::store_append_only_state(
+            &NewAssetStateAppendOnly {
+                asset_id: asset_id.clone(),
+                instruction_id: tampered.id,
+                status: asset.status,
+                state_data_json: json!({ "tampered": true }),
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        let invalid = instruction(json!({ "Unknown": {} })).build(&client).await.unwrap();
+
+        let report = replay_instructions(context, &asset_id, from, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(report.instructions, 3);
+        assert_eq!(report.divergences.len(), 2);
+        let divergence = &report.divergences[0];
+        assert_eq!(divergence.instruction_id, tampered.id);
+        assert_eq!(divergence.stored.asset_state.len(), 1);
+        assert_eq!(divergence.replayed, Some(AppendOnlyState::default()));
+        let divergence = &report.divergences[1];
+        assert_eq!(divergence.instruction_id, invalid.id);
+        assert!(divergence.replayed.is_none());
+        assert!(divergence.error.is_some());
+
+        // scratch state is rolled back
+        let asset = AssetState::find_by_asset_id(&asset_id, &client).await.unwrap().unwrap();
+        assert_eq!(asset.additional_data_json["tampered"], true);
+    }
+}
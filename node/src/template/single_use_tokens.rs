@@ -1,9 +1,13 @@
 use crate::{
-    db::models::{NewToken, Token, TokenStatus, UpdateToken},
+    db::{
+        models::{NewToken, Token, TokenStatus, UpdateToken},
+        utils::validation::ValidationErrors,
+    },
     template::{actix_web_impl::*, *},
     types::{Pubkey, TemplateID, TokenID},
     validation_err,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tari_template_derive::Contracts;
@@ -20,10 +24,28 @@ pub struct SingleUseTokenTemplate;
 impl Template for SingleUseTokenTemplate {
     type AssetContracts = AssetContracts;
     type TokenContracts = TokenContracts;
+    type Config = SingleUseTokensConfig;
 
     fn id() -> TemplateID {
         1.into()
     }
+
+    fn config_section() -> &'static str {
+        "single_use_tokens"
+    }
+}
+
+/// Options of `[validator.template.single_use_tokens]` config section
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SingleUseTokensConfig {
+    /// Seconds sell_token waits for payment when the call has no `timeout_secs`
+    pub sell_timeout_secs: u64,
+}
+impl Default for SingleUseTokensConfig {
+    fn default() -> Self {
+        Self { sell_timeout_secs: 600 }
+    }
 }
 
 /// ***************** Asset contracts *******************
@@ -35,12 +57,51 @@ pub enum AssetContracts {
     IssueTokens(IssueTokensParams),
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Upper bound of tokens issued by a single issue_tokens instruction
+pub const MAX_ISSUE_QUANTITY: u16 = 1000;
+/// Tokens inserted by a single statement when issued tokens are split into batches, see [AssetContracts::issue_tokens]
+const ISSUE_BATCH_SIZE: usize = 100;
+/// Token batches inserted concurrently by a single instruction
+const ISSUE_CONCURRENCY: usize = 4;
+
+/// Tokens are issued either with explicit `token_ids` or `quantity` of IDs generated by the node
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct IssueTokensParams {
     pub token_ids: Option<Vec<TokenID>>,
     pub quantity: Option<u16>,
 }
 
+impl ContractParams for IssueTokensParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        match (self.token_ids.as_ref(), self.quantity) {
+            (Some(_), Some(_)) => {
+                errors.append_validation_error("exclusive", "quantity", "either token_ids or quantity is allowed");
+            },
+            (None, None) => {
+                errors.append_validation_error("required", "quantity", "either token_ids or quantity is required");
+            },
+            _ => {},
+        }
+        if let Some(token_ids) = self.token_ids.as_ref() {
+            if token_ids.is_empty() {
+                errors.append_validation_error("length", "token_ids", "token_ids should not be empty");
+            }
+            if token_ids.len() > MAX_ISSUE_QUANTITY as usize {
+                errors.append_validation_error("length", "token_ids", "token_ids should not exceed 1000 tokens");
+            }
+        }
+        match self.quantity {
+            Some(0) => errors.append_validation_error("range", "quantity", "quantity should be positive"),
+            Some(quantity) if quantity > MAX_ISSUE_QUANTITY => {
+                errors.append_validation_error("range", "quantity", "quantity should not exceed 1000 tokens")
+            },
+            _ => {},
+        }
+        errors.validate()
+    }
+}
+
 // TODO: return type is converted to ContextEvent with Value parameter,
 // constrain return type
 // TODO: probably we can automate boilerplate via higher level traits
@@ -52,17 +113,14 @@ impl AssetContracts {
         IssueTokensParams { token_ids, quantity }: IssueTokensParams,
     ) -> Result<Vec<TokenID>, TemplateError>
     {
-        let token_ids: Vec<TokenID> = if let Some(token_ids) = token_ids {
-            token_ids
-        } else {
-            if let Some(quantity) = quantity {
-                (0..quantity)
-                    .map(|_| TokenID::new(context.asset_id(), &context.node_id()))
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(anyhow::Error::from)?
-            } else {
-                return validation_err!("Either token_ids or quantity should be specified in request json body");
-            }
+        // token IDs are generated by the node when quantity is requested
+        let token_ids: Vec<TokenID> = match (token_ids, quantity) {
+            (Some(token_ids), None) => token_ids,
+            (None, Some(quantity)) => (0..quantity)
+                .map(|_| TokenID::new(context.asset_id(), &context.node_id()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::from)?,
+            _ => return validation_err!("Either token_ids or quantity should be specified in request json body"),
         };
         let asset = &context.asset;
         let data = TokenData {
@@ -75,12 +133,21 @@ impl AssetContracts {
             initial_data_json: json!(data),
             ..NewToken::default()
         };
-        for data in token_ids.iter().map(new_token) {
-            if data.token_id.asset_id() != asset.asset_id {
-                return validation_err!("Token ID {} does not match asset {}", data.token_id, asset.asset_id);
-            }
-            context.create_token(data).await?;
+        if let Some(token_id) = token_ids.iter().find(|token_id| token_id.asset_id() != asset.asset_id) {
+            return validation_err!("Token ID {} does not match asset {}", token_id, asset.asset_id);
         }
+        let tokens: Vec<NewToken> = token_ids.iter().map(new_token).collect();
+        // a single batch is atomic on its own, several batches only if the instruction is discarded as a whole
+        if !context.is_atomic() {
+            context.create_tokens(tokens).await?;
+            return Ok(token_ids);
+        }
+        context.check_token_limits(&tokens).await?;
+        let batches = tokens.chunks(ISSUE_BATCH_SIZE).map(<[NewToken]>::to_vec);
+        let instruction_context: &InstructionContext<SingleUseTokenTemplate> = context;
+        instruction_context
+            .for_each_concurrent(batches, ISSUE_CONCURRENCY, |batch| instruction_context.create_tokens(batch))
+            .await?;
         Ok(token_ids)
     }
 }
@@ -113,33 +180,51 @@ pub enum TokenContracts {
     RedeemToken(RedeemTokenParams),
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct SellTokenParams {
     pub price: i64,
-    pub timeout_secs: u64,
+    /// Defaults to [SingleUseTokensConfig::sell_timeout_secs]
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
     pub user_pubkey: Pubkey,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct SellTokenLockParams {
     pub wallet_key: Pubkey,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct TransferTokenParams {
     pub user_pubkey: Pubkey,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct RedeemTokenParams;
 
+impl ContractParams for SellTokenParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.price <= 0 {
+            errors.append_validation_error("range", "price", "price should be positive");
+        }
+        if self.timeout_secs == Some(0) {
+            errors.append_validation_error("range", "timeout_secs", "timeout_secs should be positive");
+        }
+        errors.validate()
+    }
+}
+impl ContractParams for SellTokenLockParams {}
+impl ContractParams for TransferTokenParams {}
+impl ContractParams for RedeemTokenParams {}
+
 impl TokenContracts {
     /// Sell token for a `price` of XTR to user with `user_pubkey`
     ///
     /// ### Input Parameters:
     /// - price - quantity of XTR
     /// - user_pubkey - new owner of a token
-    /// - timeout_secs - timeout before Instruction is cancelled as expired
+    /// - timeout_secs - timeout before Instruction is cancelled as expired, configured default if missing
     ///
     /// # Caveats:
     /// - Instruction is creating subinstruction with a wallet key,
@@ -153,9 +238,22 @@ impl TokenContracts {
         }: SellTokenParams,
     ) -> Result<Token, TemplateError>
     {
-        if let Err(err) = Self::validate_token(context, TokenStatus::Available) {
+        // token locked by previous attempt of retried sale stays reserved for it
+        let status = match context.token.status {
+            TokenStatus::Locked if context.is_retry() => TokenStatus::Locked,
+            _ => TokenStatus::Available,
+        };
+        if let Err(err) = Self::validate_token(context, status) {
             return validation_err!("Can't sell: {}", err);
         };
+        let timeout_secs = match timeout_secs {
+            Some(timeout_secs) => timeout_secs,
+            None => context.template_config::<SingleUseTokensConfig>()?.sell_timeout_secs,
+        };
+        let timeout_secs = std::time::Duration::from_secs(timeout_secs);
+        // concurrent calls can't sell or transfer the token while waiting for payment,
+        // reservation is released if the sale fails
+        context.reserve_token(timeout_secs).await?;
         let wallet_key = context.create_temp_wallet().await?;
         let subcontract: Self = SellTokenLockParams {
             wallet_key: wallet_key.clone(),
@@ -168,7 +266,9 @@ impl TokenContracts {
         let _ = context.defer(message).await?;
         // TODO: should start timeout timer once subinstruction moves to Commit
         let timeout = std::time::Instant::now();
-        let timeout_secs = std::time::Duration::from_secs(timeout_secs);
+        tracing::info!(wallet = %wallet_key, price, "waiting for payment");
+        let data = json!({ "waiting_for_payment": { "wallet_key": wallet_key, "price": price } });
+        context.transition(ContextEvent::Progress { data }).await?;
         // TODO: implement better strategies for waiting for temporal events like subscriptions
         while context.check_balance(&wallet_key).await? < price {
             tokio::time::delay_for(std::time::Duration::from_secs(1)).await;
@@ -182,6 +282,7 @@ impl TokenContracts {
                 return validation_err!("Timeout expired for sell_token");
             }
         }
+        tracing::info!(wallet = %wallet_key, "payment received");
         let token_data = TokenData {
             owner_pubkey: user_pubkey,
             used: false,
@@ -192,6 +293,7 @@ impl TokenContracts {
             ..Default::default()
         };
         context.update_token(data).await?;
+        context.release_token().await?;
         Ok(context.token.clone())
     }
 
@@ -280,13 +382,17 @@ impl TokenContracts {
 pub mod asset_contracts_actix {
     use super::*;
     use crate::{
-        api::errors::ApiError,
-        db::models::consensus::instructions::*,
-        template::{actors::*, context::*},
+        api::{
+            errors::{ApiError, ApplicationError},
+            middleware::{authorize_batch_call, RequestAuthenticationContext},
+            models::{RequestSignature, SignedCall},
+        },
+        db::models::{consensus::instructions::*, NewAuditLog},
+        template::{actors::*, context::*, BatchCall, DryRunQuery, DryRunResult, ReplayContracts},
         types::AssetID,
     };
     use actix::prelude::*;
-    use actix_web::web;
+    use actix_web::{web, HttpRequest, HttpResponse};
 
     ////// impl #[derive(Contracts)] for AssetContracts
 
@@ -294,6 +400,14 @@ pub mod asset_contracts_actix {
         fn setup_actix_routes(tpl: TemplateID, scope: &mut web::ServiceConfig) {
             log::info!("template={}, installing assets API issue_tokens", tpl);
             scope.service(web::resource("/issue_tokens").route(web::post().to(asset_contracts_actix::web_handler)));
+            scope.service(web::resource("/batch").route(web::post().to(asset_contracts_actix::batch_web_handler)));
+        }
+        fn routes_catalog() -> Vec<ContractRoute> {
+            vec![ContractRoute {
+                name: "issue_tokens",
+                params_schema: |gen| gen.subschema_for::<IssueTokensParams>(),
+                view: false,
+            }]
         }
     }
 
@@ -321,6 +435,7 @@ pub mod asset_contracts_actix {
                 params: self,
                 asset_id: instruction.asset_id.clone(),
                 instruction,
+                span: tracing::Span::current(),
             }
         }
     }
@@ -332,6 +447,7 @@ pub mod asset_contracts_actix {
         asset_id: AssetID,
         params: AssetContracts,
         instruction: Instruction,
+        span: tracing::Span,
     }
 
     impl ContractCallMsg for Msg {
@@ -357,6 +473,31 @@ pub mod asset_contracts_actix {
         fn init_context(self, ctx: TemplateContext<Self::Template>) -> Self::ContextFuture {
             AssetInstructionContext::init(ctx, self.instruction, self.asset_id)
         }
+
+        fn span(&self) -> tracing::Span {
+            self.span.clone()
+        }
+    }
+
+    impl ReplayContracts<SingleUseTokenTemplate> for AssetContracts {
+        fn replay(
+            context: TemplateContext<SingleUseTokenTemplate>,
+            instruction: Instruction,
+            client: std::sync::Arc<deadpool_postgres::Client>,
+        ) -> futures::future::LocalBoxFuture<'static, Result<DryRunResult, TemplateError>>
+        {
+            Box::pin(async move {
+                let contract: Self = serde_json::from_value(instruction.params.clone())
+                    .map_err(|err| TemplateError::Validation(err.into()))?;
+                let context = AssetInstructionContext::replay(context, instruction, client).await?;
+                let (result, context) = contract.call(context).await?;
+                let append_only_state = context.append_only_state().unwrap_or_default();
+                Ok(DryRunResult {
+                    result,
+                    append_only_state,
+                })
+            })
+        }
     }
 
     ////// end of #[derive(Contracts)]
@@ -368,26 +509,73 @@ pub mod asset_contracts_actix {
     // returning instruction
     // Instruction is created here to return it immediately to the client
     // so client can keep polling for result.
+    #[tracing::instrument(skip(req, params, query, body, context), fields(contract = "issue_tokens"))]
     pub async fn web_handler(
+        req: HttpRequest,
         params: web::Path<AssetCallParams>,
-        data: web::Json<IssueTokensParams>,
+        query: web::Query<DryRunQuery>,
+        body: web::Payload,
         context: web::Data<TemplateContext<SingleUseTokenTemplate>>,
-    ) -> Result<web::Json<Instruction>, ApiError>
+    ) -> Result<HttpResponse, ApiError>
     {
         // extract and transform parameters
         let asset_id = params.asset_id(context.template_id())?;
-        let data: AssetContracts = data.into_inner().into();
+        // body is limited before it's parsed, per contract limit is applied
+        let mut data = context.payload_config("issue_tokens").read_json(body).await?;
+        // verify params are signed by the caller for this call
+        let signature = RequestSignature::verify(&req, &SignedCall::asset(&asset_id, "issue_tokens"), &data)?;
+        // dependencies are signed along with params, but aren't passed to the contract
+        let depends_on = take_depends_on(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Dependencies error: {}", err).as_str()))?;
+        let nonce = take_nonce(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Nonce error: {}", err).as_str()))?;
+        let priority = take_priority(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Priority error: {}", err).as_str()))?;
+        // check caller is permitted to call contract
+        context
+            .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, None, "issue_tokens")
+            .await?;
+        context.authorize_priority(&signature.pubkey, priority).await?;
+        let data: IssueTokensParams = serde_json::from_value(data)
+            .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?;
+        // reject invalid params before instruction is created
+        data.validate()?;
+        let data: AssetContracts = data.into();
+        // TODO: proper handling of unlikely error
+        let contract_params = serde_json::to_value(&data).unwrap();
+        // dry run executes contract right in the request against buffered state in rolled back transaction
+        if query.dry_run {
+            let mut dry_run_context =
+                AssetInstructionContext::dry_run(context.get_ref().clone(), asset_id, "issue_tokens", contract_params)
+                    .await?;
+            let transaction = dry_run_context.begin_transaction().await?;
+            let (result, dry_run_context) = data.call(dry_run_context).await?;
+            let append_only_state = dry_run_context.append_only_state().unwrap_or_default();
+            transaction.rollback().await?;
+            return Ok(HttpResponse::Ok().json(DryRunResult {
+                result,
+                append_only_state,
+            }));
+        }
         // start instruction
         let instruction = NewInstruction {
             asset_id: asset_id.clone(),
             template_id: context.template_id(),
-            // TODO: proper handling of unlikely error
-            params: serde_json::to_value(&data).unwrap(),
+            params: contract_params,
             contract_name: "issue_tokens".to_string(),
             status: InstructionStatus::Scheduled,
+            signature: signature.signature,
+            depends_on,
+            signer_pubkey: Some(signature.pubkey.clone()),
+            nonce,
+            priority,
             ..NewInstruction::default()
         };
         let instruction = context.create_instruction(instruction).await?;
+        // instruction is not dispatched unless the call is recorded in audit log
+        context
+            .audit(NewAuditLog::contract_call(&instruction, req.access_pubkey()))
+            .await?;
         let message = data.clone().into_message(instruction.clone());
         context
             .addr()
@@ -399,21 +587,117 @@ pub mod asset_contracts_actix {
                 name: "issue_tokens".into(),
             })?;
         // There must be instruction - otherwise we would fail on previous call
-        Ok(web::Json(instruction))
+        Ok(HttpResponse::Ok().json(instruction))
     }
     /////// end of impl #[contract]
+
+    ////// impl batch of #[derive(Contracts)]
+
+    // Every call of the batch is authorized and validated as by its own route,
+    // instructions are created and recorded in audit log in a single DB transaction and dispatched in order
+    #[tracing::instrument(skip(req, params, body, context), fields(contract = "batch"))]
+    pub async fn batch_web_handler(
+        req: HttpRequest,
+        params: web::Path<AssetCallParams>,
+        body: web::Payload,
+        context: web::Data<TemplateContext<SingleUseTokenTemplate>>,
+    ) -> Result<HttpResponse, ApiError>
+    {
+        let asset_id = params.asset_id(context.template_id())?;
+        let data = context.payload_config("batch").read_json(body).await?;
+        // whole batch is signed at once
+        let signature = RequestSignature::verify(&req, &SignedCall::asset(&asset_id, "batch"), &data)?;
+        let calls: Vec<BatchCall> = serde_json::from_value(data)
+            .map_err(|err| ApplicationError::bad_request(format!("Batch error: {}", err).as_str()))?;
+        if calls.is_empty() {
+            return Err(ApplicationError::bad_request("Batch error: no contract calls").into());
+        }
+        let client = context.get_db_client().await?;
+        let mut contracts: Vec<AssetContracts> = Vec::with_capacity(calls.len());
+        let mut instructions = Vec::with_capacity(calls.len());
+        for (index, BatchCall { contract, params: mut data }) in calls.into_iter().enumerate() {
+            let bad_request = |err: String| {
+                ApplicationError::bad_request(format!("Batch call {} {}: {}", index, contract, err).as_str())
+            };
+            let depends_on =
+                take_depends_on(&mut data).map_err(|err| bad_request(format!("Dependencies error: {}", err)))?;
+            let nonce = take_nonce(&mut data).map_err(|err| bad_request(format!("Nonce error: {}", err)))?;
+            let priority = take_priority(&mut data).map_err(|err| bad_request(format!("Priority error: {}", err)))?;
+            // check caller and access key are permitted to call contract before its params are parsed
+            context
+                .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, None, contract.as_str())
+                .await?;
+            authorize_batch_call(&req, context.template_id(), contract.as_str(), &asset_id, &client).await?;
+            context.authorize_priority(&signature.pubkey, priority).await?;
+            let data: AssetContracts = match contract.as_str() {
+                "issue_tokens" => {
+                    let data: IssueTokensParams = serde_json::from_value(data)
+                        .map_err(|err| bad_request(format!("Contract params error: {}", err)))?;
+                    data.validate()?;
+                    data.into()
+                },
+                _ => return Err(bad_request("unknown contract".into()).into()),
+            };
+            // TODO: proper handling of unlikely error
+            let contract_params = serde_json::to_value(&data).unwrap();
+            instructions.push(NewInstruction {
+                asset_id: asset_id.clone(),
+                template_id: context.template_id(),
+                params: contract_params,
+                contract_name: contract,
+                status: InstructionStatus::Scheduled,
+                signature: signature.signature.clone(),
+                depends_on,
+                signer_pubkey: Some(signature.pubkey.clone()),
+                nonce,
+                priority,
+                // calls share the signature, so their content IDs are told apart by the index
+                batch_index: Some(index as i32),
+                ..NewInstruction::default()
+            });
+            contracts.push(data);
+        }
+        // either all the instructions are created and recorded in audit log or none
+        let instructions = context.create_instructions(instructions, req.access_pubkey()).await?;
+        for (index, (data, instruction)) in contracts.into_iter().zip(instructions.iter()).enumerate() {
+            // TODO: proper handling of unlikely error
+            let params = serde_json::to_string(&data).unwrap();
+            if let Err(err) = context.addr().try_send(data.into_message(instruction.clone())) {
+                let error = TemplateError::ActorSend {
+                    source: err.into(),
+                    params,
+                    name: instruction.contract_name.clone(),
+                };
+                // the rest of the batch is never going to be processed
+                context.instructions_undispatched(&instructions[index..], &error).await?;
+                return Err(error.into());
+            }
+        }
+        Ok(HttpResponse::Ok().json(instructions))
+    }
+    /////// end of impl batch
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        db::models::{asset_states::*, consensus::instructions::*, wallet::*},
-        test::utils::{actix::TestAPIServer, builders::*, test_db_client, Test},
+        db::models::{
+            asset_states::*,
+            audit_log::*,
+            consensus::{instructions::*, InstructionProgress},
+            contract_permissions::*,
+            wallet::*,
+            ContractPermissionRule,
+            TokenReservation,
+        },
+        template::config::{ConfigUpdate, ContractConfig},
+        test::utils::{actix::TestAPIServer, builders::*, test_db_client, watcher::InstructionWatcher, Test},
         types::AssetID,
     };
     use deadpool_postgres::Client;
     use serde_json::json;
+    use std::time::Duration;
 
     async fn build_context() -> AssetInstructionContext<SingleUseTokenTemplate> {
         let template_id = SingleUseTokenTemplate::id();
@@ -478,6 +762,92 @@ mod test {
         assert!(contract.call(context).await.is_err());
     }
 
+    #[test]
+    fn issue_tokens_params() {
+        let params = |token_ids: Option<usize>, quantity: Option<u16>| IssueTokensParams {
+            token_ids: token_ids.map(|len| (0..len).map(|_| Test::<TokenID>::new()).collect()),
+            quantity,
+        };
+        assert!(params(Some(1), None).validate().is_ok());
+        assert!(params(None, Some(MAX_ISSUE_QUANTITY)).validate().is_ok());
+        assert!(params(None, None).validate().is_err());
+        assert!(params(Some(1), Some(1)).validate().is_err());
+        assert!(params(Some(0), None).validate().is_err());
+        assert!(params(None, Some(0)).validate().is_err());
+        assert!(params(None, Some(MAX_ISSUE_QUANTITY + 1)).validate().is_err());
+        assert!(params(Some(MAX_ISSUE_QUANTITY as usize + 1), None).validate().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn issue_tokens_quantity_full_stack() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+
+        let asset_id = Test::<AssetID>::from_template(SingleUseTokenTemplate::id());
+        AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let params = json!({ "quantity": MAX_ISSUE_QUANTITY + 1 });
+        let resp = srv
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNPROCESSABLE_ENTITY);
+
+        // huge arrays are rejected before they are parsed into contract params
+        let params = json!({ "token_ids": vec![0; 10_001] });
+        let mut resp = srv
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["details"]["fields"]["body"][0]["code"], "array_len");
+
+        srv.context()
+            .config
+            .write()
+            .unwrap()
+            .contracts
+            .insert("issue_tokens".into(), ContractConfig {
+                max_body_bytes: Some(1024),
+                ..ContractConfig::default()
+            });
+        let token_ids: Vec<TokenID> = (0..20).map(|_| Test::<TokenID>::from_asset(&asset_id)).collect();
+        let params = json!({ "token_ids": token_ids });
+        let resp = srv
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+        srv.context().config.write().unwrap().contracts.clear();
+
+        let params = json!({ "quantity": 3 });
+        let mut resp = srv
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success(), "{:?}", resp);
+        let instruction: Instruction = resp.json().await.unwrap();
+        let id = instruction.id;
+        let instruction =
+            Test::<Instruction>::wait_status(id, InstructionStatus::Pending, Duration::from_secs(1), &client).await;
+        let token_ids: Vec<TokenID> = serde_json::from_value(instruction.result).unwrap();
+        assert_eq!(token_ids.len(), 3);
+        for token_id in token_ids.iter() {
+            assert_eq!(token_id.asset_id(), asset_id);
+        }
+    }
+
     #[actix_rt::test]
     async fn issue_tokens_full_stack() {
         let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
@@ -492,31 +862,190 @@ mod test {
         };
         asset_builder.build(&client).await.unwrap();
 
+        let params = json!({ "token_ids": token_ids });
         let mut resp = srv
-            .asset_call(&asset_id, "issue_tokens")
-            .send_json(&json!({ "token_ids": token_ids }))
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .send_json(&params)
             .await
             .unwrap();
 
         assert!(resp.status().is_success());
         let instruction: Instruction = resp.json().await.unwrap();
         assert_eq!(instruction.status, InstructionStatus::Scheduled);
+        assert_eq!(instruction.signature.len(), 128);
         assert!(srv.context().addr().connected());
         let id = instruction.id;
-        // TODO: need better solution for async Actor tests, some Test wrapper for actor
-        for _ in 0..10 {
-            tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
-            let instruction = Instruction::load(id, &client).await.unwrap();
-            assert_ne!(instruction.status, InstructionStatus::Invalid);
-            if instruction.status == InstructionStatus::Pending {
-                return;
-            }
+        let audit = AuditLog::select(SelectAuditLog::default(), &client).await.unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].instruction_id, Some(id));
+        assert_eq!(audit[0].signer_pubkey, instruction.signer_pubkey);
+        Test::<Instruction>::wait_status(id, InstructionStatus::Pending, Duration::from_secs(1), &client).await;
+    }
+
+    #[actix_rt::test]
+    async fn issue_tokens_dry_run() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+
+        let tpl = SingleUseTokenTemplate::id();
+        let asset_id = Test::<AssetID>::from_template(tpl);
+        let token_ids: Vec<_> = (0..10).map(|_| Test::<TokenID>::from_asset(&asset_id)).collect();
+        AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+
+        let params = json!({ "token_ids": token_ids });
+        let mut resp = srv
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .query(&[("dry_run", true)])
+            .unwrap()
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success(), "{:?}", resp);
+        let dry_run: DryRunResult = resp.json().await.unwrap();
+        let result: Vec<TokenID> = serde_json::from_value(dry_run.result).unwrap();
+        assert_eq!(result, token_ids);
+        assert_eq!(dry_run.append_only_state.tokens.len(), 10);
+        // neither instruction nor tokens are created
+        assert!(Instruction::find_by_asset_id(&asset_id, &client).await.unwrap().is_empty());
+        assert!(Token::find_by_token_id(&token_ids[0], &client).await.unwrap().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn issue_tokens_forbidden() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+
+        let asset_id = Test::<AssetID>::from_template(SingleUseTokenTemplate::id());
+        AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        ContractPermission::insert(
+            NewContractPermission {
+                asset_id: asset_id.clone(),
+                contract_name: "issue_tokens".into(),
+                rule: ContractPermissionRule::AssetIssuer,
+                pub_key: None,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+
+        // Unsigned call is rejected before permissions check
+        let params = json!({ "quantity": 1 });
+        let resp = srv
+            .asset_call(&asset_id, "issue_tokens")
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        // Signer of the request doesn't match issuer rule
+        let resp = srv
+            .signed_asset_call(&asset_id, "issue_tokens", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        // Batch call is authorized before its params are parsed
+        let batch = json!([{ "contract": "issue_tokens", "params": { "quantity": "not a number" } }]);
+        let resp = srv
+            .signed_asset_call(&asset_id, "batch", &batch)
+            .send_json(&batch)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        assert!(Instruction::find_by_asset_id(&asset_id, &client).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn issue_tokens_batch() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+
+        let asset_id = Test::<AssetID>::from_template(SingleUseTokenTemplate::id());
+        AssetStateBuilder {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let token_ids: Vec<_> = (0..4).map(|_| Test::<TokenID>::from_asset(&asset_id)).collect();
+
+        // out of sequence nonce of the second call rolls back the first one
+        let batch = json!([
+            { "contract": "issue_tokens", "params": { "token_ids": &token_ids[..2], "nonce": 1 } },
+            { "contract": "issue_tokens", "params": { "token_ids": &token_ids[2..], "nonce": 3 } },
+        ]);
+        let resp = srv
+            .signed_asset_call(&asset_id, "batch", &batch)
+            .send_json(&batch)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        assert!(Instruction::find_by_asset_id(&asset_id, &client).await.unwrap().is_empty());
+
+        let batch = json!([
+            { "contract": "issue_tokens", "params": { "token_ids": &token_ids[..2] } },
+            { "contract": "unknown", "params": {} },
+        ]);
+        let resp = srv
+            .signed_asset_call(&asset_id, "batch", &batch)
+            .send_json(&batch)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let batch = json!([
+            { "contract": "issue_tokens", "params": { "token_ids": &token_ids[..2], "nonce": 1 } },
+            { "contract": "issue_tokens", "params": { "token_ids": &token_ids[2..], "nonce": 2 } },
+        ]);
+        let mut resp = srv
+            .signed_asset_call(&asset_id, "batch", &batch)
+            .send_json(&batch)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success(), "{:?}", resp);
+        let instructions: Vec<Instruction> = resp.json().await.unwrap();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].nonce, Some(1));
+        assert_eq!(instructions[1].nonce, Some(2));
+        let audit = AuditLog::select(SelectAuditLog::default(), &client).await.unwrap();
+        assert_eq!(audit.len(), 2);
+        for instruction in instructions {
+            assert_eq!(instruction.contract_name, "issue_tokens");
+            let id = instruction.id;
+            Test::<Instruction>::wait_status(id, InstructionStatus::Pending, Duration::from_secs(1), &client).await;
         }
-        let instruction = Instruction::load(id, &client).await.unwrap();
-        panic!(
-            "Waiting for Actor to process Instruction longer than 1s {:?}",
-            instruction
-        );
+
+        // identical calls share the signature, but not instruction IDs
+        let batch = json!([
+            { "contract": "issue_tokens", "params": { "quantity": 1 } },
+            { "contract": "issue_tokens", "params": { "quantity": 1 } },
+        ]);
+        let mut resp = srv
+            .signed_asset_call(&asset_id, "batch", &batch)
+            .send_json(&batch)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success(), "{:?}", resp);
+        let instructions: Vec<Instruction> = resp.json().await.unwrap();
+        assert_ne!(instructions[0].id, instructions[1].id);
+        assert_eq!(instructions[0].signature, instructions[1].signature);
+        assert_eq!(instructions[1].batch_index, Some(1));
+        assert!(instructions.iter().all(Instruction::has_valid_id));
     }
 
     async fn test_token(client: &Client) -> TokenID {
@@ -539,11 +1068,11 @@ mod test {
         let user_pubkey = Test::<Pubkey>::new();
         let params = SellTokenParams {
             user_pubkey,
-            timeout_secs: 1,
+            timeout_secs: Some(1),
             price: 1,
         };
         let mut resp = srv
-            .token_call(&token_id, "sell_token")
+            .signed_token_call(&token_id, "sell_token", &params)
             .send_json(&params)
             .await
             .unwrap();
@@ -559,13 +1088,14 @@ mod test {
         let (client, _lock) = test_db_client().await;
         let token_id = test_token(&client).await;
         let user_pubkey = Test::<Pubkey>::new();
+        let params = SellTokenParams {
+            user_pubkey,
+            timeout_secs: Some(10),
+            price: 1,
+        };
         let mut resp = srv
-            .token_call(&token_id, "sell_token")
-            .send_json(&SellTokenParams {
-                user_pubkey,
-                timeout_secs: 10,
-                price: 1,
-            })
+            .signed_token_call(&token_id, "sell_token", &params)
+            .send_json(&params)
             .await
             .unwrap();
 
@@ -574,41 +1104,142 @@ mod test {
         assert_eq!(instruction.status, InstructionStatus::Scheduled);
 
         let id = instruction.id;
-        let wallet: Option<Wallet> = None;
-        // TODO: need better solution for async Actor tests, some Test wrapper for actor
-        for _ in 0u8..100 {
-            tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
-            let instruction = Instruction::load(id, &client).await.unwrap();
-            assert_ne!(
-                instruction.status,
-                InstructionStatus::Invalid,
-                "Instruction: {:?}",
-                instruction
-            );
-            if instruction.status == InstructionStatus::Processing && wallet.is_none() {
-                let subinstructions = instruction.load_subinstructions(&client).await.unwrap();
-                if subinstructions.len() == 0 {
-                    continue;
-                }
-                assert_eq!(subinstructions.len(), 1);
-                let sub = subinstructions.first().unwrap();
-                let params: TokenContracts = serde_json::from_value(sub.params.clone()).unwrap();
-                if let TokenContracts::SellTokenLock(SellTokenLockParams { wallet_key }) = &params {
-                    let wallet = Some(Wallet::select_by_key(wallet_key, &client).await.unwrap());
-                    // top up money in wallet
-                    wallet.as_ref().unwrap().set_balance(1, &client).await.unwrap();
-                } else {
-                    panic!("Incorrect params in subcontract {:?}", params)
-                }
-            } else if instruction.status == InstructionStatus::Pending {
-                return;
-            }
+        let timeout = Duration::from_secs(10);
+        let mut watcher = InstructionWatcher::listen().await;
+        let instruction = watcher
+            .wait_until(id, timeout, &client, |instruction| {
+                instruction.status == InstructionStatus::Processing
+            })
+            .await;
+        let sub = watcher.wait_subinstruction(&instruction, timeout, &client).await;
+        let params: TokenContracts = serde_json::from_value(sub.params.clone()).unwrap();
+        if let TokenContracts::SellTokenLock(SellTokenLockParams { wallet_key }) = &params {
+            let wallet = Wallet::select_by_key(wallet_key, &client).await.unwrap();
+            // top up money in wallet
+            wallet.set_balance(1, &client).await.unwrap();
+        } else {
+            panic!("Incorrect params in subcontract {:?}", params)
         }
-        let instruction = Instruction::load(id, &client).await.unwrap();
-        panic!(
-            "Waiting for Actor to process Instruction longer than 10s {:?}",
-            instruction
-        );
+        watcher
+            .wait_until(id, timeout, &client, |instruction| {
+                instruction.status == InstructionStatus::Pending
+            })
+            .await;
+        let progress = InstructionProgress::find_by_instruction_id(id, &client).await.unwrap();
+        assert_eq!(progress.len(), 1);
+        assert_eq!(progress[0].data["waiting_for_payment"]["price"], 1);
+        assert!(TokenReservation::find_active(&token_id, &client)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn sell_token_buffered() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        srv.context().config.write().unwrap().buffered_state = true;
+        let (client, _lock) = test_db_client().await;
+        let token_id = test_token(&client).await;
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        let params = SellTokenParams {
+            user_pubkey: Test::<Pubkey>::new(),
+            timeout_secs: Some(10),
+            price: 1,
+        };
+        let mut resp = srv
+            .signed_token_call(&token_id, "sell_token", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+        let instruction: Instruction = resp.json().await.unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let mut watcher = InstructionWatcher::listen().await;
+        let instruction = watcher
+            .wait_until(instruction.id, timeout, &client, |instruction| {
+                instruction.status == InstructionStatus::Processing
+            })
+            .await;
+        let sub = watcher.wait_subinstruction(&instruction, timeout, &client).await;
+        if let TokenContracts::SellTokenLock(SellTokenLockParams { wallet_key }) =
+            serde_json::from_value(sub.params.clone()).unwrap()
+        {
+            let wallet = Wallet::select_by_key(&wallet_key, &client).await.unwrap();
+            wallet.set_balance(1, &client).await.unwrap();
+        }
+        let instruction = watcher
+            .wait_until(instruction.id, timeout, &client, |instruction| {
+                instruction.status == InstructionStatus::Pending
+            })
+            .await;
+
+        // lock of the subinstruction is committed along with the sale, which builds on it
+        assert!(sub.execute(&client).await.unwrap().is_empty());
+        let state = instruction.execute(&client).await.unwrap();
+        let statuses: Vec<_> = state.token_state.iter().map(|state| state.status).collect();
+        assert_eq!(statuses, vec![TokenStatus::Locked, TokenStatus::Active]);
+        assert_eq!(state.token_state[0].instruction_id, sub.id);
+        assert_eq!(state.token_state[1].expected_version, Some(token.version + 1));
+        assert_eq!(state.token_state[1].state_data_json["owner_pubkey"], json!(params.user_pubkey));
+        let stored = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        assert_eq!(stored.status, token.status);
+    }
+
+    #[actix_rt::test]
+    async fn sell_token_retry() {
+        use crate::db::utils::errors::DBError;
+
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        let token_id = test_token(&client).await;
+        let params = SellTokenParams {
+            user_pubkey: Test::<Pubkey>::new(),
+            timeout_secs: Some(10),
+            price: 1,
+        };
+        let instruction = consensus::InstructionBuilder {
+            asset_id: Some(token_id.asset_id()),
+            token_id: Some(token_id.clone()),
+            contract_name: "sell_token".into(),
+            params: json!(params),
+            status: InstructionStatus::Scheduled,
+            ..Default::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let context = srv.context().clone();
+
+        // first attempt fails with transient error after the temp wallet is created
+        let mut token_ctx = TokenInstructionContext::init(context.clone(), instruction.clone(), token_id.clone())
+            .await
+            .unwrap();
+        token_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        let wallet_key = token_ctx.create_temp_wallet().await.unwrap();
+        let transient = TemplateError::from(DBError::Unreachable {
+            target: "database:5432".into(),
+            msg: "connection refused".into(),
+        });
+        let delay = context.clone().instruction_error(instruction.clone(), &transient).await.unwrap();
+        assert!(delay.is_some());
+        let wallet = Wallet::select_by_key(&wallet_key, &client).await.unwrap();
+        wallet.set_balance(1, &client).await.unwrap();
+
+        // retried sale waits for payment to the wallet of the first attempt
+        let mut retry_ctx = TokenInstructionContext::init(context, instruction.clone(), token_id.clone())
+            .await
+            .unwrap();
+        assert!(retry_ctx.is_retry());
+        retry_ctx.transition(ContextEvent::StartProcessing).await.unwrap();
+        let contract: TokenContracts = params.clone().into();
+        let (result, _) = contract.call(retry_ctx).await.unwrap();
+        let token: Token = serde_json::from_value(result).unwrap();
+        assert_eq!(token.owner_pubkey, Some(params.user_pubkey));
+        let subinstructions = instruction.load_subinstructions(&client).await.unwrap();
+        assert_eq!(subinstructions.len(), 1);
+        let sub: TokenContracts = serde_json::from_value(subinstructions[0].params.clone()).unwrap();
+        assert_eq!(sub, SellTokenLockParams { wallet_key }.into());
     }
 
     async fn update_token(token_id: &TokenID, update: UpdateToken, client: &Client) {
@@ -639,31 +1270,102 @@ mod test {
         )
         .await;
         let user_pubkey = Test::<Pubkey>::new();
+        let params = SellTokenParams {
+            user_pubkey,
+            timeout_secs: Some(1),
+            price: 1,
+        };
         let mut resp = srv
-            .token_call(&token_id, "sell_token")
-            .send_json(&SellTokenParams {
-                user_pubkey,
-                timeout_secs: 1,
-                price: 1,
-            })
+            .signed_token_call(&token_id, "sell_token", &params)
+            .send_json(&params)
             .await
             .unwrap();
         let instruction: Instruction = resp.json().await.unwrap();
-        let id = instruction.id;
-        for _ in 0u8..10 {
-            tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
-            let instruction = Instruction::load(id, &client).await.unwrap();
-            if instruction.status != InstructionStatus::Scheduled && instruction.status != InstructionStatus::Processing
-            {
-                assert_eq!(instruction.status, InstructionStatus::Invalid);
-                return;
-            }
+        Test::<Instruction>::wait_status(
+            instruction.id,
+            InstructionStatus::Invalid,
+            Duration::from_secs(2),
+            &client,
+        )
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn sell_token_timeout() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        srv.context()
+            .config
+            .write()
+            .unwrap()
+            .contracts
+            .insert("sell_token".into(), ContractConfig {
+                timeout_secs: Some(1),
+                ..ContractConfig::default()
+            });
+        let token_id = test_token(&client).await;
+        let params = SellTokenParams {
+            user_pubkey: Test::<Pubkey>::new(),
+            timeout_secs: Some(10),
+            price: 1,
+        };
+        let mut resp = srv
+            .signed_token_call(&token_id, "sell_token", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        let instruction: Instruction = resp.json().await.unwrap();
+        let instruction = Test::<Instruction>::wait_status(
+            instruction.id,
+            InstructionStatus::Invalid,
+            Duration::from_secs(5),
+            &client,
+        )
+        .await;
+        assert_eq!(instruction.result["code"], "timeout");
+        // reservation of the failed sale is released
+        assert!(TokenReservation::find_active(&token_id, &client)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn sell_token_invalid_params() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        let token_id = test_token(&client).await;
+        let params = SellTokenParams {
+            user_pubkey: Test::<Pubkey>::new(),
+            timeout_secs: Some(0),
+            price: -1,
+        };
+        let mut resp = srv
+            .signed_token_call(&token_id, "sell_token", &params)
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["code"], "validation");
+        assert_eq!(body["details"]["fields"]["price"][0]["code"], "range");
+        assert_eq!(body["details"]["fields"]["timeout_secs"][0]["code"], "range");
+        let instructions = Instruction::find_recent(100, &client).await.unwrap();
+        assert!(instructions.iter().all(|i| i.token_id.as_ref() != Some(&token_id)));
+
+        assert!(SellTokenParams {
+            price: 1,
+            ..params.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(SellTokenParams {
+            price: 1,
+            timeout_secs: Some(1),
+            ..params
         }
-        let instruction = Instruction::load(id, &client).await.unwrap();
-        panic!(
-            "Waiting for Actor to process Instruction longer than 1s {:?}",
-            instruction
-        );
+        .validate()
+        .is_ok());
     }
 
     #[actix_rt::test]
@@ -684,7 +1386,7 @@ mod test {
             user_pubkey: Test::<Pubkey>::new(),
         };
         let mut resp = srv
-            .token_call(&token_id, "transfer_token")
+            .signed_token_call(&token_id, "transfer_token", &params)
             .send_json(&params)
             .await
             .unwrap();
@@ -694,29 +1396,52 @@ mod test {
         assert_eq!(instruction.status, InstructionStatus::Scheduled);
         let _: TokenContracts = serde_json::from_value(instruction.params).unwrap();
 
-        let id = instruction.id;
-        // TODO: need better solution for async Actor tests, some Test wrapper for actor
-        for _ in 0u8..10 {
-            tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
-            let instruction = Instruction::load(id, &client).await.unwrap();
-            assert_ne!(
-                instruction.status,
-                InstructionStatus::Invalid,
-                "Instruction: {:?}",
-                instruction
-            );
-            if instruction.status == InstructionStatus::Pending {
-                let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
-                let data: TokenData = serde_json::from_value(token.additional_data_json).unwrap();
-                assert_eq!(data.owner_pubkey, params.user_pubkey);
-                return;
-            }
-        }
-        let instruction = Instruction::load(id, &client).await.unwrap();
-        panic!(
-            "Waiting for Actor to process Instruction longer than 1s {:?}",
-            instruction
-        );
+        Test::<Instruction>::wait_status(
+            instruction.id,
+            InstructionStatus::Pending,
+            Duration::from_secs(1),
+            &client,
+        )
+        .await;
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        let data: TokenData = serde_json::from_value(token.additional_data_json).unwrap();
+        assert_eq!(data.owner_pubkey, params.user_pubkey);
+    }
+
+    #[actix_rt::test]
+    async fn transfer_token_replayed_to_another_token() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (client, _lock) = test_db_client().await;
+        // Signature is verified before tokens are loaded, so the other token doesn't need to exist
+        let token_id = test_token(&client).await;
+        let other_token_id: TokenID = Test::from_asset(&token_id.asset_id());
+        let params = TransferTokenParams {
+            user_pubkey: Test::<Pubkey>::new(),
+        };
+
+        // Signature for token_id is presented on the route of other_token_id
+        let resp = srv
+            .sign(
+                srv.token_call(&other_token_id, "transfer_token"),
+                &SignedCall::token(&token_id, "transfer_token"),
+                &params,
+            )
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        // Same for another contract of the same token
+        let resp = srv
+            .sign(
+                srv.token_call(&token_id, "transfer_token"),
+                &SignedCall::token(&token_id, "redeem_token"),
+                &params,
+            )
+            .send_json(&params)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
     }
 
     #[actix_rt::test]
@@ -733,7 +1458,7 @@ mod test {
         };
         update_token(&token_id, update, &client).await;
         let mut resp = srv
-            .token_call(&token_id, "redeem_token")
+            .signed_token_call(&token_id, "redeem_token", &RedeemTokenParams)
             .send_json(&RedeemTokenParams)
             .await
             .unwrap();
@@ -743,32 +1468,47 @@ mod test {
         assert_eq!(instruction.status, InstructionStatus::Scheduled);
         let _: TokenContracts = serde_json::from_value(instruction.params).unwrap();
 
-        let id = instruction.id;
-        // TODO: need better solution for async Actor tests, some Test wrapper for actor
-        for _ in 0u8..10 {
-            tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
-            let instruction = Instruction::load(id, &client).await.unwrap();
-            assert_ne!(
-                instruction.status,
-                InstructionStatus::Invalid,
-                "Instruction: {:?}",
-                instruction
-            );
-            if instruction.status == InstructionStatus::Pending {
-                let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
-                let asset = AssetState::find_by_asset_id(&instruction.asset_id, &client)
-                    .await
-                    .unwrap()
-                    .unwrap();
-                let data: TokenData = serde_json::from_value(token.additional_data_json).unwrap();
-                assert_eq!(data.owner_pubkey, asset.asset_issuer_pub_key);
-                return;
-            }
-        }
-        let instruction = Instruction::load(id, &client).await.unwrap();
-        panic!(
-            "Waiting for Actor to process Instruction longer than 1s {:?}",
-            instruction
-        );
+        Test::<Instruction>::wait_status(
+            instruction.id,
+            InstructionStatus::Pending,
+            Duration::from_secs(1),
+            &client,
+        )
+        .await;
+        let token = Token::find_by_token_id(&token_id, &client).await.unwrap().unwrap();
+        let asset = AssetState::find_by_asset_id(&instruction.asset_id, &client)
+            .await
+            .unwrap()
+            .unwrap();
+        let data: TokenData = serde_json::from_value(token.additional_data_json).unwrap();
+        assert_eq!(data.owner_pubkey, asset.asset_issuer_pub_key);
+    }
+
+    #[actix_rt::test]
+    async fn negative_paths() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let (_client, _lock) = test_db_client().await;
+        srv.assert_negative_paths().await;
+    }
+
+    #[actix_rt::test]
+    async fn sell_timeout_config() {
+        let srv = TestAPIServer::<SingleUseTokenTemplate>::new();
+        let context = srv.context();
+        let sell_timeout_secs = || context.template_config::<SingleUseTokensConfig>().unwrap().sell_timeout_secs;
+        assert_eq!(sell_timeout_secs(), 600);
+
+        let mut template = context.config();
+        template
+            .templates
+            .insert("single_use_tokens".into(), json!({ "sell_timeout_secs": 30 }));
+        context.addr().send(ConfigUpdate(template.clone())).await.unwrap();
+        assert_eq!(sell_timeout_secs(), 30);
+        // update with invalid options is rejected
+        template
+            .templates
+            .insert("single_use_tokens".into(), json!({ "sell_timeout_secs": "soon" }));
+        context.addr().send(ConfigUpdate(template)).await.unwrap();
+        assert_eq!(sell_timeout_secs(), 30);
     }
 }
@@ -0,0 +1,217 @@
+//! Multi-node test harness
+//!
+//! [TestCluster] runs N validator nodes in-process, every node has its own database
+//! (`<test dbname>_cluster_<index>`), node identity and [ConsensusWorker]. Consensus messages are delivered
+//! between nodes over in-process channels and stored to recipient's database via [communications::receive].
+
+use crate::{
+    config::NodeConfig,
+    consensus::{
+        communications::{self, ConsensusMessage, ConsensusTransport},
+        errors::ConsensusError,
+        ConsensusCommittee,
+        ConsensusWorker,
+    },
+    db::{
+        models::{consensus::Instruction, InstructionStatus},
+        utils::db::{build_pool, create_database, reset_database},
+    },
+    identity::NodeIdentity,
+    test::utils::{
+        build_test_config,
+        builders::{consensus::InstructionBuilder, AssetStateBuilder},
+        load_env,
+        test_pool,
+    },
+    types::{AssetID, InstructionID, NodeID},
+};
+use deadpool_postgres::{Client, Pool};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        MutexGuard,
+    },
+    time::delay_for,
+};
+
+const LOG_TARGET: &'static str = "tari_validator_node::test::cluster";
+const POLL_PERIOD: Duration = Duration::from_millis(100);
+
+/// Delivers messages to every other node of the cluster
+struct LocalTransport {
+    node_id: NodeID,
+    peers: Vec<(NodeID, UnboundedSender<ConsensusMessage>)>,
+}
+
+impl ConsensusTransport for LocalTransport {
+    fn send(&self, _committee: &ConsensusCommittee, message: ConsensusMessage) -> Result<(), ConsensusError> {
+        for (node_id, peer) in self.peers.iter() {
+            if *node_id != self.node_id {
+                peer.send(message.clone())
+                    .map_err(|_| ConsensusError::error("Cluster node is stopped"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validator node of the cluster
+pub struct TestNode {
+    pub config: NodeConfig,
+    pub pool: Arc<Pool>,
+    worker: ConsensusWorker,
+}
+
+impl TestNode {
+    pub fn node_id(&self) -> NodeID {
+        self.config.node_id()
+    }
+
+    pub async fn client(&self) -> Client {
+        self.pool.get().await.unwrap()
+    }
+}
+
+/// Cluster of in-process validator nodes, holds DB lock for the lifetime of the cluster
+pub struct TestCluster {
+    pub nodes: Vec<TestNode>,
+    _lock: MutexGuard<'static, Pool>,
+}
+
+impl TestCluster {
+    /// Creates databases and starts `size` nodes with consensus channels wired together
+    pub async fn start(size: usize) -> anyhow::Result<Self> {
+        load_env();
+        let lock = test_pool().await;
+        let base = build_test_config()?;
+        let dbname = base.postgres.dbname.clone().unwrap_or_else(|| "validator".into());
+
+        let mut configs = Vec::with_capacity(size);
+        for index in 0..size {
+            let mut config = base.clone();
+            config.postgres.dbname = Some(format!("{}_cluster_{}", dbname, index));
+            config.identity = Some(NodeIdentity::random());
+            create_database(config.clone()).await?;
+            reset_database(config.clone()).await?;
+            configs.push(config);
+        }
+
+        let channels: Vec<_> = configs.iter().map(|_| unbounded_channel()).collect();
+        let peers: Vec<_> = configs
+            .iter()
+            .zip(channels.iter())
+            .map(|(config, (sender, _))| (config.node_id(), sender.clone()))
+            .collect();
+
+        let mut nodes = Vec::with_capacity(size);
+        for (config, (_, receiver)) in configs.into_iter().zip(channels.into_iter()) {
+            let pool = Arc::new(build_pool(&config.postgres)?);
+            let transport = LocalTransport {
+                node_id: config.node_id(),
+                peers: peers.clone(),
+            };
+            let worker = ConsensusWorker::new(config.clone(), None)?.with_transport(Arc::new(transport));
+            Self::spawn_receiver(config.node_id(), receiver, pool.clone());
+            nodes.push(TestNode { config, pool, worker });
+        }
+        Ok(Self { nodes, _lock: lock })
+    }
+
+    fn spawn_receiver(node_id: NodeID, mut receiver: UnboundedReceiver<ConsensusMessage>, pool: Arc<Pool>) {
+        actix_rt::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                let res = match pool.get().await {
+                    Ok(client) => communications::receive(message, &client).await,
+                    Err(err) => Err(ConsensusError::error(&err.to_string())),
+                };
+                if let Err(err) = res {
+                    log::error!(target: LOG_TARGET, "Node {} failed to receive message: {}", node_id, err);
+                }
+            }
+        });
+    }
+
+    /// Creates the same asset on every node
+    pub async fn create_asset(&self) -> anyhow::Result<AssetID> {
+        let asset_id = AssetStateBuilder::default().asset_id;
+        for node in self.nodes.iter() {
+            AssetStateBuilder {
+                asset_id: asset_id.clone(),
+                ..AssetStateBuilder::default()
+            }
+            .build(&node.client().await)
+            .await?;
+        }
+        Ok(asset_id)
+    }
+
+    /// Submits pending instruction for `asset_id` to node with `index`
+    pub async fn submit_instruction(&self, index: usize, asset_id: &AssetID) -> anyhow::Result<Instruction> {
+        let node = &self.nodes[index];
+        InstructionBuilder {
+            asset_id: Some(asset_id.clone()),
+            initiating_node_id: node.node_id(),
+            status: InstructionStatus::Pending,
+            ..InstructionBuilder::default()
+        }
+        .build(&node.client().await)
+        .await
+    }
+
+    /// Status of instruction on every node, None if node doesn't have it
+    pub async fn statuses(&self, id: InstructionID) -> anyhow::Result<Vec<Option<InstructionStatus>>> {
+        let mut statuses = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let instruction = Instruction::find(id, &node.client().await).await?;
+            statuses.push(instruction.map(|instruction| instruction.status));
+        }
+        Ok(statuses)
+    }
+
+    /// Runs consensus on all nodes until instruction is committed on every node
+    ///
+    /// ## Panics
+    /// If instruction is not committed on all nodes within `timeout`
+    pub async fn assert_committed(&self, id: InstructionID, timeout: Duration) -> anyhow::Result<()> {
+        let started = Instant::now();
+        loop {
+            let statuses = self.statuses(id).await?;
+            if statuses.iter().all(|status| *status == Some(InstructionStatus::Commit)) {
+                return Ok(());
+            }
+            if started.elapsed() > timeout {
+                panic!("Instruction {} is not committed on all nodes: {:?}", id, statuses);
+            }
+            for node in self.nodes.iter() {
+                node.worker.work().await?;
+            }
+            delay_for(POLL_PERIOD).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn submit_to_one_commit_on_all() -> anyhow::Result<()> {
+        let cluster = TestCluster::start(3).await?;
+        let asset_id = cluster.create_asset().await?;
+        let instruction = cluster.submit_instruction(1, &asset_id).await?;
+        assert_eq!(cluster.statuses(instruction.id).await?, vec![
+            None,
+            Some(InstructionStatus::Pending),
+            None
+        ]);
+
+        cluster
+            .assert_committed(instruction.id, Duration::from_secs(10))
+            .await?;
+        Ok(())
+    }
+}
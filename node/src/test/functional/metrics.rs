@@ -22,9 +22,10 @@ async fn fullstack_metrics() {
     };
     asset_builder.build(&client).await.unwrap();
 
+    let params = json!({ "token_ids": vec![token_id.clone()] });
     let resp = srv
-        .asset_call(&asset_id, "issue_tokens")
-        .send_json(&json!({ "token_ids": vec![token_id.clone()] }))
+        .signed_asset_call(&asset_id, "issue_tokens", &params)
+        .send_json(&params)
         .await
         .unwrap();
 
@@ -40,9 +41,10 @@ async fn fullstack_metrics() {
     assert_eq!(metrics.instructions_processing_spark.into_iter().sum::<u64>(), 1);
     assert_eq!(metrics.instructions_invalid_spark.into_iter().sum::<u64>(), 0);
 
+    let params = json!({ "token_ids": vec![token_id] });
     let resp2 = srv
-        .asset_call(&asset_id, "issue_tokens")
-        .send_json(&json!({ "token_ids": vec![token_id] }))
+        .signed_asset_call(&asset_id, "issue_tokens", &params)
+        .send_json(&params)
         .await
         .unwrap();
 
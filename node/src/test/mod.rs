@@ -1,5 +1,9 @@
 pub mod utils;
 
+#[cfg(test)]
+// Multi-node harness running several validator nodes in-process
+pub mod cluster;
+
 #[cfg(test)]
 // Functional tests involiving whole stack of modules
 mod functional;
@@ -1,45 +1,151 @@
-use super::{actix_test_pool, build_test_config, load_env};
+use super::{actix_test_pool, build_test_config, load_env, negative_paths::ParamsSchema, Test};
 use crate::{
+    api::{
+        middleware::Authentication,
+        models::{JwtSecret, RequestSignature, SignedCall},
+    },
+    config::NodeConfig,
+    identity::NodeIdentity,
     metrics::Metrics,
-    template::{self, actix_web_impl::ActixTemplate, Template, TemplateContext, TemplateRunner},
+    template::{self, actix_web_impl::ActixTemplate, Template, TemplateContext, TemplateRoutes, TemplateRunner},
     types::{AssetID, TokenID},
 };
 use actix::{Actor, Addr};
-use actix_web::{client::ClientRequest, middleware::Logger, test, App};
-use std::ops::Deref;
+use actix_web::{
+    client::ClientRequest,
+    http::StatusCode,
+    middleware::{Condition, Logger},
+    test,
+    web,
+    App,
+    Scope,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{any::Any, ops::Deref, sync::Arc};
+
+/// Creates scopes for every worker of the test server
+type ScopesFactory = Arc<dyn Fn() -> Vec<Scope> + Send + Sync>;
 
 /// Full stack API server for templates testing purposes
 ///
-/// Supports methods for posting assets and tokens instructions
+/// Supports methods for posting assets and tokens instructions,
+/// signed calls are signed by the random client [TestAPIServer::signer] key
 /// Also impls Deref into actix [test::TestServer]
+///
+/// Server mounts template `T`, other templates and custom scopes are composed with [TestAPIServerBuilder]
 pub struct TestAPIServer<T: Template + 'static> {
     server: test::TestServer,
     context: TemplateContext<T>,
+    /// Contexts of templates mounted along with `T`, see [TestAPIServer::context_of]
+    contexts: Vec<Box<dyn Any>>,
     pub metrics: Addr<Metrics>,
+    pub signer: NodeIdentity,
 }
 
-impl<T: Template + 'static> TestAPIServer<T> {
-    pub fn new() -> Self {
+/// Composes multiple templates and custom scopes in a single [TestAPIServer],
+/// so cross-template flows can be tested in-process:
+/// ```ignore
+/// let srv = TestAPIServer::<EscrowTemplate>::builder()
+///     .template::<SingleUseTokenTemplate>()
+///     .scope(|| web::scope("/admin").wrap(Authentication::new()).configure(admin_routes))
+///     .build();
+/// ```
+pub struct TestAPIServerBuilder<T: Template + 'static> {
+    config: NodeConfig,
+    metrics: Addr<Metrics>,
+    context: TemplateContext<T>,
+    contexts: Vec<Box<dyn Any>>,
+    scopes: Vec<ScopesFactory>,
+    /// Access tokens are required if set, see [TestAPIServerBuilder::authentication]
+    jwt_secret: Option<JwtSecret>,
+}
+
+impl<T: Template + 'static> TestAPIServerBuilder<T> {
+    fn new() -> Self {
         load_env();
         let _ = pretty_env_logger::try_init();
-        let pool = actix_test_pool();
         let config = build_test_config().unwrap();
         let metrics = Metrics::default().start();
-        let runner = TemplateRunner::<T>::create(pool, config, Some(metrics.clone()));
+        let context = TemplateRunner::<T>::create(actix_test_pool(), config.clone(), Some(metrics.clone())).start();
+        Self {
+            scopes: vec![template_scopes(context.clone())],
+            config,
+            metrics,
+            context,
+            contexts: vec![],
+            jwt_secret: None,
+        }
+    }
+
+    /// Requires access tokens signed with `secret` as the node does with authentication enabled
+    pub fn authentication(mut self, secret: JwtSecret) -> Self {
+        self.jwt_secret = Some(secret);
+        self
+    }
+
+    /// Mounts routes of template `U` served by its own [TemplateRunner]
+    pub fn template<U: Template + 'static>(mut self) -> Self {
+        let runner = TemplateRunner::<U>::create(actix_test_pool(), self.config.clone(), Some(self.metrics.clone()));
         let context = runner.start();
-        let srv_context = context.clone();
+        self.scopes.push(template_scopes(context.clone()));
+        self.contexts.push(Box::new(context));
+        self
+    }
+
+    /// Mounts custom scope, middleware is applied by wrapping the scope
+    pub fn scope<F>(mut self, factory: F) -> Self
+    where F: Fn() -> Scope + Send + Sync + 'static {
+        self.scopes.push(Arc::new(move || vec![factory()]));
+        self
+    }
+
+    pub fn build(self) -> TestAPIServer<T> {
+        let scopes = self.scopes;
+        let jwt_secret = self.jwt_secret;
         let server = test::start(move || {
-            let app = App::new().wrap(Logger::default());
-            T::actix_scopes()
-                .into_iter()
-                .fold(app, |app, scope| app.service(scope.data(srv_context.clone())))
+            let mut app = App::new()
+                .wrap(Condition::new(jwt_secret.is_some(), Authentication::new()))
+                .wrap(Logger::default());
+            if let Some(secret) = jwt_secret.clone() {
+                app = app
+                    .app_data(web::Data::new(secret))
+                    .app_data(web::Data::new(actix_test_pool()));
+            }
+            scopes
+                .iter()
+                .flat_map(|factory| factory())
+                .fold(app, |app, scope| app.service(scope))
         });
-        Self {
-            context,
+        TestAPIServer {
             server,
-            metrics,
+            context: self.context,
+            contexts: self.contexts,
+            metrics: self.metrics,
+            signer: NodeIdentity::random(),
         }
     }
+}
+
+/// Routes of the template with its context attached
+fn template_scopes<T: Template + 'static>(context: TemplateContext<T>) -> ScopesFactory {
+    Arc::new(move || {
+        T::actix_scopes()
+            .into_iter()
+            .map(|scope| scope.data(context.clone()))
+            .collect()
+    })
+}
+
+impl<T: Template + 'static> TestAPIServer<T> {
+    /// Server mounting template `T` only
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> TestAPIServerBuilder<T> {
+        TestAPIServerBuilder::new()
+    }
 
     pub fn asset_call(&self, id: &AssetID, instruction: &str) -> ClientRequest {
         let uri = template::asset_call_path(id, instruction);
@@ -51,9 +157,84 @@ impl<T: Template + 'static> TestAPIServer<T> {
         self.server.post(uri)
     }
 
+    pub fn signed_asset_call<P: Serialize>(&self, id: &AssetID, instruction: &str, params: &P) -> ClientRequest {
+        self.sign(self.asset_call(id, instruction), &SignedCall::asset(id, instruction), params)
+    }
+
+    pub fn signed_token_call<P: Serialize>(&self, id: &TokenID, instruction: &str, params: &P) -> ClientRequest {
+        self.sign(self.token_call(id, instruction), &SignedCall::token(id, instruction), params)
+    }
+
+    /// Request signed for `call`, which might differ from the request path
+    pub fn sign<P: Serialize>(&self, request: ClientRequest, call: &SignedCall, params: &P) -> ClientRequest {
+        let params = serde_json::to_value(params).unwrap();
+        RequestSignature::sign(&self.signer, call, &params).unwrap().apply(request)
+    }
+
     pub fn context(&self) -> &TemplateContext<T> {
         &self.context
     }
+
+    /// Context of template mounted with [TestAPIServerBuilder::template]
+    pub fn context_of<U: Template + 'static>(&self) -> Option<&TemplateContext<U>> {
+        self.contexts.iter().find_map(|context| context.downcast_ref())
+    }
+
+    /// Calls every contract of the template with negative-path params generated from its params schema
+    /// and asserts they are rejected with 400 or 422 `validation` error body, see [super::negative_paths].
+    /// Route with malformed AssetID is called with valid params.
+    ///
+    /// Calls fail before asset or token is loaded, so they don't need to exist.
+    /// View contracts are skipped, as their params come in query string
+    pub async fn assert_negative_paths(&self) {
+        let asset_id = Test::<AssetID>::from_template(T::id());
+        let token_id = Test::<TokenID>::from_asset(&asset_id);
+        let routes = TemplateRoutes::of::<T>();
+        let asset_calls = routes
+            .asset
+            .iter()
+            .map(|route| {
+                let call = SignedCall::asset(&asset_id, route.name);
+                (route, call, template::asset_call_path(&asset_id, route.name))
+            });
+        let token_calls = routes
+            .token
+            .iter()
+            .map(|route| {
+                let call = SignedCall::token(&token_id, route.name);
+                (route, call, template::token_call_path(&token_id, route.name))
+            });
+        for (route, call, path) in asset_calls.chain(token_calls).filter(|(route, ..)| !route.view) {
+            let schema = ParamsSchema::new(route.params_schema);
+            for case in schema.cases() {
+                let case_name = format!("{} with {}", route.name, case.description);
+                self.assert_rejected(self.server.post(path.clone()), &call, &case.params, &case_name)
+                    .await;
+            }
+            // features segment of AssetID is not hex
+            let mut segments: Vec<_> = path.split('/').map(String::from).collect();
+            segments[3] = "ZZZZ".into();
+            let case_name = format!("{} with malformed AssetID", route.name);
+            self.assert_rejected(self.server.post(segments.join("/")), &call, &schema.sample(), &case_name)
+                .await;
+        }
+    }
+
+    async fn assert_rejected(&self, request: ClientRequest, call: &SignedCall, params: &Value, case_name: &str) {
+        let mut resp = self.sign(request, call, params).send_json(params).await.unwrap();
+        let status = resp.status();
+        assert!(
+            status == StatusCode::BAD_REQUEST || status == StatusCode::UNPROCESSABLE_ENTITY,
+            "{}: expected 400 or 422, got {}",
+            case_name,
+            status
+        );
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["code"], "validation", "{}: {}", case_name, body);
+        assert!(body["error"].is_string(), "{}: {}", case_name, body);
+        assert_eq!(body["message"], body["error"], "{}: {}", case_name, body);
+        assert!(body.get("details").is_some(), "{}: {}", case_name, body);
+    }
 }
 
 impl<T: Template + 'static> Deref for TestAPIServer<T> {
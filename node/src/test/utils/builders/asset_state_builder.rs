@@ -15,6 +15,7 @@ pub struct AssetStateBuilder {
     pub name: String,
     pub description: String,
     pub limit_per_wallet: Option<u32>,
+    pub max_supply: Option<u32>,
     pub allow_transfers: bool,
     pub asset_issuer_pub_key: Pubkey,
     pub authorized_signers: Vec<String>,
@@ -34,6 +35,7 @@ impl Default for AssetStateBuilder {
             name: format!("Asset-{}", x).into(),
             description: "Description of asset".to_string(),
             limit_per_wallet: None,
+            max_supply: None,
             allow_transfers: true,
             asset_issuer_pub_key: Test::<Pubkey>::new(),
             authorized_signers: Vec::new(),
@@ -58,6 +60,7 @@ impl AssetStateBuilder {
             name: self.name.to_owned(),
             description: self.description.to_owned(),
             limit_per_wallet: self.limit_per_wallet,
+            max_supply: self.max_supply,
             allow_transfers: self.allow_transfers,
             asset_issuer_pub_key: self.asset_issuer_pub_key.to_owned(),
             authorized_signers: self.authorized_signers.to_owned(),
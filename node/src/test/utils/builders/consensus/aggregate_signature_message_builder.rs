@@ -11,6 +11,7 @@ use serde_json::json;
 pub struct AggregateSignatureMessageBuilder {
     pub proposal_id: Option<ProposalID>,
     pub signature_data: SignatureData,
+    pub status: AggregateSignatureMessageStatus,
     #[doc(hidden)]
     pub __non_exhaustive: (),
 }
@@ -22,6 +23,7 @@ impl Default for AggregateSignatureMessageBuilder {
             signature_data: SignatureData {
                 signatures: serde_json::from_value(json!([[Test::<NodeID>::new(), "stub-signature"]])).unwrap(),
             },
+            status: AggregateSignatureMessageStatus::Pending,
             __non_exhaustive: (),
         }
     }
@@ -45,7 +47,7 @@ impl AggregateSignatureMessageBuilder {
         let params = NewAggregateSignatureMessage {
             proposal_id,
             signature_data: self.signature_data,
-            status: AggregateSignatureMessageStatus::Pending,
+            status: self.status,
         };
         Ok(AggregateSignatureMessage::insert(params, client).await?)
     }
@@ -9,6 +9,7 @@ use serde_json::{json, Value};
 #[allow(dead_code)]
 pub struct InstructionBuilder {
     pub id: Option<InstructionID>,
+    pub parent_id: Option<InstructionID>,
     pub initiating_node_id: NodeID,
     pub signature: String,
     pub asset_id: Option<AssetID>,
@@ -25,6 +26,7 @@ impl Default for InstructionBuilder {
     fn default() -> Self {
         Self {
             id: None,
+            parent_id: None,
             initiating_node_id: Test::<NodeID>::new(),
             signature: "stub-signature".to_string(),
             asset_id: None,
@@ -53,6 +55,7 @@ impl InstructionBuilder {
 
         let params = NewInstruction {
             id,
+            parent_id: self.parent_id,
             asset_id,
             initiating_node_id: self.initiating_node_id,
             signature: self.signature,
@@ -55,6 +55,7 @@ impl ViewBuilder {
             append_only_state: AppendOnlyState {
                 asset_state: self.asset_state_append_only.clone(),
                 token_state: self.token_state_append_only.clone(),
+                ..AppendOnlyState::default()
             },
         })
     }
@@ -7,7 +7,7 @@ pub struct WalletStoreBuilder;
 
 impl WalletStoreBuilder {
     pub fn build() -> anyhow::Result<Arc<Mutex<WalletStore>>> {
-        let wallets = WalletStore::init(Test::<TempDir>::get_path_buf())?;
+        let wallets = WalletStore::init(Test::<TempDir>::get_path_buf(), None)?;
         Ok(Arc::new(Mutex::new(wallets)))
     }
 }
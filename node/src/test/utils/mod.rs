@@ -1,4 +1,4 @@
-use crate::{config::NodeConfig, db::migrations::migrate};
+use crate::{config::NodeConfig, db::migrations::migrate, identity::NodeIdentity};
 use config::Source;
 use deadpool_postgres::{Client, Pool};
 use std::sync::Arc;
@@ -8,7 +8,9 @@ use tokio_postgres::NoTls;
 
 pub mod actix;
 pub mod builders;
+pub mod negative_paths;
 mod types;
+pub mod watcher;
 pub use types::{Test, TestTemplate};
 
 lazy_static::lazy_static! {
@@ -53,7 +55,8 @@ pub fn build_test_config() -> anyhow::Result<NodeConfig> {
         "validator.wallets_keys_path",
         default_path("wallets", Some(&bootstrap.base_path)).to_str(),
     )?;
-    let config = NodeConfig::load_from(&config, &global, false)?;
+    let mut config = NodeConfig::load_from(&config, &global, false)?;
+    config.identity = Some(Test::<NodeIdentity>::new());
     log::trace!(target: "test_utils", "Load test config: {:?}", config);
     Ok(config)
 }
@@ -0,0 +1,162 @@
+//! Negative-path contract calls generated from JSON schema of contract params,
+//! see [TestAPIServer::assert_negative_paths](super::actix::TestAPIServer::assert_negative_paths)
+
+use chrono::Utc;
+use schemars::{gen::SchemaGenerator, schema::Schema};
+use serde_json::{json, Map, Value};
+
+/// Contract call params expected to be rejected
+#[derive(Clone, Debug)]
+pub struct NegativeCase {
+    pub description: String,
+    pub params: Value,
+}
+
+/// Params schema of a contract route, `$ref`s are resolved against definitions of the generator
+pub struct ParamsSchema {
+    schema: Value,
+    definitions: Map<String, Value>,
+}
+
+impl ParamsSchema {
+    pub fn new(params_schema: fn(&mut SchemaGenerator) -> Schema) -> Self {
+        let mut gen = SchemaGenerator::default();
+        let schema = serde_json::to_value(params_schema(&mut gen)).unwrap_or(Value::Null);
+        let definitions = serde_json::to_value(gen.definitions())
+            .ok()
+            .and_then(|definitions| definitions.as_object().cloned())
+            .unwrap_or_default();
+        Self { schema, definitions }
+    }
+
+    /// Type-correct params, only required fields are filled in
+    pub fn sample(&self) -> Value {
+        self.sample_of(&self.schema)
+    }
+
+    /// Params not being an object, every required field missing and every field of a wrong type,
+    /// fields are mutated one at a time on top of [Self::sample]
+    pub fn cases(&self) -> Vec<NegativeCase> {
+        let mut cases = vec![NegativeCase {
+            description: "params which are not an object".into(),
+            params: json!("__invalid__"),
+        }];
+        let object = match self.object() {
+            Some(object) => object,
+            None => return cases,
+        };
+        let sample = self.sample_of(object);
+        for name in required(object) {
+            let mut params = sample.clone();
+            if let Some(params) = params.as_object_mut() {
+                params.remove(&name);
+            }
+            cases.push(NegativeCase {
+                description: format!("missing field `{}`", name),
+                params,
+            });
+        }
+        for (name, schema) in object["properties"].as_object().into_iter().flatten() {
+            if let Some(value) = wrong_type(self.resolve(schema)) {
+                let mut params = sample.clone();
+                params[name.as_str()] = value;
+                cases.push(NegativeCase {
+                    description: format!("wrong type of field `{}`", name),
+                    params,
+                });
+            }
+        }
+        cases
+    }
+
+    fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        match schema["$ref"].as_str().and_then(|path| path.rsplit('/').next()) {
+            Some(name) => self
+                .definitions
+                .get(name)
+                .map(|definition| self.resolve(definition))
+                .unwrap_or(schema),
+            None => schema,
+        }
+    }
+
+    /// Object schema of params, `Option<Params>` resolves to schema of `Params`
+    fn object(&self) -> Option<&Value> {
+        let schema = self.resolve(&self.schema);
+        if schema["properties"].is_object() {
+            return Some(schema);
+        }
+        schema["anyOf"]
+            .as_array()?
+            .iter()
+            .map(|schema| self.resolve(schema))
+            .find(|schema| schema["properties"].is_object())
+    }
+
+    fn sample_of(&self, schema: &Value) -> Value {
+        let schema = self.resolve(schema);
+        if let Some(value) = schema["enum"].as_array().and_then(|values| values.first()) {
+            return value.clone();
+        }
+        for key in &["anyOf", "oneOf", "allOf"] {
+            if let Some(schema) = schema[*key].as_array().and_then(|schemas| schemas.first()) {
+                return self.sample_of(schema);
+            }
+        }
+        match (types(schema).first().map(String::as_str), schema["format"].as_str()) {
+            (Some("string"), Some("date-time")) => json!(Utc::now()),
+            (Some("string"), Some("uuid")) => json!(uuid::Uuid::nil()),
+            (Some("string"), _) => json!("sample"),
+            (Some("integer"), _) => json!(1),
+            (Some("number"), _) => json!(1.0),
+            (Some("boolean"), _) => json!(false),
+            (Some("array"), _) => json!([]),
+            (Some("object"), _) => {
+                let mut object = Map::new();
+                for name in required(schema) {
+                    let value = self.sample_of(&schema["properties"][name.as_str()]);
+                    object.insert(name, value);
+                }
+                Value::Object(object)
+            },
+            _ => Value::Null,
+        }
+    }
+}
+
+/// Types allowed by the schema, `type` is either a single type or a list of types
+fn types(schema: &Value) -> Vec<String> {
+    match &schema["type"] {
+        Value::String(name) => vec![name.clone()],
+        Value::Array(names) => names.iter().filter_map(|name| name.as_str().map(String::from)).collect(),
+        _ => vec![],
+    }
+}
+
+fn required(schema: &Value) -> Vec<String> {
+    schema["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.as_str().map(String::from))
+        .collect()
+}
+
+/// Value of a type the schema doesn't allow, None if the schema doesn't restrict types
+fn wrong_type(schema: &Value) -> Option<Value> {
+    let allowed = types(schema);
+    if allowed.is_empty() {
+        return None;
+    }
+    let allows = |name: &str| allowed.iter().any(|allowed| allowed == name);
+    let candidates = vec![
+        (json!("__invalid__"), allows("string")),
+        (json!(12345), allows("integer") || allows("number")),
+        (json!(true), allows("boolean")),
+        (json!({}), allows("object")),
+    ];
+    candidates
+        .into_iter()
+        .find(|(_, allowed)| !allowed)
+        .map(|(value, _)| value)
+}
@@ -1,4 +1,4 @@
-use crate::types::*;
+use crate::{identity::NodeIdentity, types::*};
 use rand::prelude::random;
 
 #[derive(Clone)]
@@ -51,6 +51,13 @@ impl Test<NodeID> {
     }
 }
 
+impl Test<NodeIdentity> {
+    /// Random keypair identity having [Test<NodeID>] node id
+    pub fn new() -> NodeIdentity {
+        NodeIdentity::random().with_node_id(Test::<NodeID>::new())
+    }
+}
+
 impl Test<InstructionID> {
     /// Generate new unique test instruction
     pub fn new() -> InstructionID {
@@ -101,8 +108,13 @@ pub struct TestTemplate;
 impl Template for TestTemplate {
     type AssetContracts = ();
     type TokenContracts = ();
+    type Config = ();
 
     fn id() -> TemplateID {
         Test::<TemplateID>::new()
     }
+
+    fn config_section() -> &'static str {
+        "test"
+    }
 }
@@ -0,0 +1,113 @@
+//! Awaiting instructions processed by template actors, replaces polling of [Instruction::load] in tests
+
+use super::{build_test_config, Test};
+use crate::{
+    db::{
+        models::{consensus::Instruction, InstructionStatus},
+        notify::{InstructionListener, InstructionNotification, InstructionNotifier},
+    },
+    types::InstructionID,
+};
+use deadpool_postgres::Client;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Listens to instruction status changes via DB LISTEN/NOTIFY on a dedicated connection, see [InstructionNotifier]
+pub struct InstructionWatcher {
+    notifications: broadcast::Receiver<InstructionNotification>,
+    // Connection stops listening once dropped
+    _listener: InstructionListener,
+}
+
+impl InstructionWatcher {
+    pub async fn listen() -> Self {
+        let config = build_test_config().unwrap();
+        let notifier = InstructionNotifier::new();
+        let notifications = notifier.subscribe();
+        let listener = notifier
+            .connect(&config.postgres.get_pg_config().unwrap())
+            .await
+            .unwrap();
+        Self {
+            notifications,
+            _listener: listener,
+        }
+    }
+
+    /// Id of the next inserted or changed instruction, None on timeout
+    pub async fn next(&mut self, timeout: Duration) -> Option<InstructionID> {
+        InstructionNotifier::wait(&mut self.notifications, timeout, |_| true)
+            .await
+            .map(|notification| notification.id)
+    }
+
+    /// Waits for the instruction to satisfy the condition, panics on timeout
+    /// or if instruction becomes Invalid without satisfying the condition
+    pub async fn wait_until<F>(&mut self, id: InstructionID, timeout: Duration, client: &Client, done: F) -> Instruction
+    where F: Fn(&Instruction) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let instruction = Instruction::load(id, client).await.unwrap();
+            if done(&instruction) {
+                return instruction;
+            }
+            assert_ne!(
+                instruction.status,
+                InstructionStatus::Invalid,
+                "Instruction: {:?}",
+                instruction
+            );
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match self.next(remaining).await {
+                    Some(changed) if changed == id => break,
+                    Some(_) => continue,
+                    None => panic!(
+                        "Waiting for Actor to process Instruction longer than {:?} {:?}",
+                        timeout, instruction
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Waits for the first subinstruction of the instruction to be created, panics on timeout
+    pub async fn wait_subinstruction(
+        &mut self,
+        parent: &Instruction,
+        timeout: Duration,
+        client: &Client,
+    ) -> Instruction
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(sub) = parent.load_subinstructions(client).await.unwrap().into_iter().next() {
+                return sub;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if self.next(remaining).await.is_none() {
+                panic!(
+                    "Waiting for subinstruction longer than {:?} {:?}",
+                    timeout, parent
+                );
+            }
+        }
+    }
+}
+
+impl Test<Instruction> {
+    /// Waits for the instruction to reach the status, panics on timeout or if instruction becomes Invalid,
+    /// see [InstructionWatcher::wait_until]
+    pub async fn wait_status(
+        id: InstructionID,
+        status: InstructionStatus,
+        timeout: Duration,
+        client: &Client,
+    ) -> Instruction
+    {
+        InstructionWatcher::listen()
+            .await
+            .wait_until(id, timeout, client, |instruction| instruction.status == status)
+            .await
+    }
+}
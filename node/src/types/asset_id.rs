@@ -1,6 +1,11 @@
 //! AssetID type in accordance with [RFC-0311](https://rfc.tari.com/RFC-0311_AssetTemplates.html#asset-identification) entity
 
-use super::{errors::TypeError, RaidID, TemplateID};
+use super::{
+    bech32,
+    errors::{check_hex, TypeError},
+    RaidID,
+    TemplateID,
+};
 use bytes::BytesMut;
 use postgres_protocol::types::text_from_sql;
 use serde::{Deserialize, Serialize};
@@ -32,6 +37,10 @@ impl Default for AssetID {
 impl AssetID {
     /// AssetID stored as BPCHAR, it might change in the future
     pub const SQL_TYPE: Type = Type::BPCHAR;
+    /// Human readable prefix of bech32 encoding
+    pub const BECH32_HRP: &'static str = "tasset";
+    /// Length of binary representation: template_id, features, raid_id and hash
+    pub(crate) const BYTES_LEN: usize = 39;
 
     pub fn new(template_id: TemplateID, features: u16, raid_id: RaidID, hash: String) -> Self {
         Self {
@@ -74,6 +83,48 @@ impl AssetID {
     pub fn template_id(&self) -> TemplateID {
         self.template_id.clone()
     }
+
+    /// Checksummed bech32 encoding, also available as alternate format `{:#}`
+    pub fn to_bech32(&self) -> String {
+        bech32::encode(Self::BECH32_HRP, &self.to_bytes())
+    }
+
+    pub fn from_bech32(raw: &str) -> Result<Self, TypeError> {
+        let bytes = bech32::decode("AssetID", Self::BECH32_HRP, raw)?;
+        Self::from_bytes(raw, &bytes)
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        // hash is validated by FromStr and produced by generate_hash
+        let hash = u128::from_str_radix(&self.hash, 16).expect("AssetID hash is 32-char hex");
+        let mut bytes = Vec::with_capacity(Self::BYTES_LEN);
+        bytes.extend_from_slice(&self.template_id.template_type().to_be_bytes());
+        bytes.extend_from_slice(&self.template_id.template_version().to_be_bytes());
+        bytes.extend_from_slice(&self.features.to_be_bytes());
+        bytes.extend_from_slice(self.raid_id.to_base58().as_bytes());
+        bytes.extend_from_slice(&hash.to_be_bytes());
+        bytes
+    }
+
+    /// Restores AssetID from [AssetID::to_bytes], `raw` is the source string for error reporting
+    pub(crate) fn from_bytes(raw: &str, bytes: &[u8]) -> Result<Self, TypeError> {
+        if bytes.len() != Self::BYTES_LEN {
+            return Err(TypeError::parse_field_raw("AssetID", raw));
+        }
+        let mut head = [0u8; 8];
+        head.copy_from_slice(&bytes[0..8]);
+        let raid_id = std::str::from_utf8(&bytes[8..23])
+            .map_err(|err| TypeError::parse_field("AssetID::raid_id", err.into()))?;
+        let mut hash = [0u8; 16];
+        hash.copy_from_slice(&bytes[23..39]);
+        format!(
+            "{:016X}{}.{:032X}",
+            u64::from_be_bytes(head),
+            raid_id,
+            u128::from_be_bytes(hash)
+        )
+        .parse()
+    }
 }
 
 impl<'a> FromSql<'a> for AssetID {
@@ -95,8 +146,13 @@ impl<'a> ToSql for AssetID {
 }
 
 /// Converts AssetID to string according to rfc https://rfc.tari.com/RFC-0311_AssetTemplates.html#asset-identification
+///
+/// Alternate format `{:#}` displays checksummed bech32 encoding
 impl fmt::Display for AssetID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.to_bech32());
+        }
         write!(
             f,
             "{}{:04X}{}.{}",
@@ -115,42 +171,36 @@ impl From<AssetID> for String {
 }
 
 /// Converts AssetID from string according to rfc https://rfc.tari.com/RFC-0311_AssetTemplates.html#asset-identification
+/// or from bech32 encoding with [AssetID::BECH32_HRP] prefix
 impl FromStr for AssetID {
     type Err = TypeError;
 
     fn from_str(hex: &str) -> Result<Self, TypeError> {
+        if bech32::has_prefix(Self::BECH32_HRP, hex) {
+            return Self::from_bech32(hex);
+        }
         if hex.len() != 64 {
             return Err(TypeError::source_len("AssetID", 64, hex));
         }
+        if let Some(pos) = hex.chars().position(|c| !c.is_ascii()) {
+            return Err(TypeError::parse_at("AssetID", pos, hex, "non-ASCII character"));
+        }
 
-        let template_id = match hex.get(0..12) {
-            None => Err(TypeError::parse_field_raw("AssetID::template_id", hex)),
-            Some(buf) => TemplateID::from_hex(buf),
-        }?;
+        check_hex("AssetID::template_id", hex, 0..12)?;
+        let template_id = TemplateID::from_hex(&hex[0..12])?;
 
-        let features = match hex.get(12..16) {
-            None => Err(TypeError::parse_field_raw("AssetID::features", hex)),
-            Some(buf) => {
-                u16::from_str_radix(buf, 16).map_err(|err| TypeError::parse_field("AssetID::features", err.into()))
-            },
-        }?;
+        check_hex("AssetID::features", hex, 12..16)?;
+        let features =
+            u16::from_str_radix(&hex[12..16], 16).map_err(|err| TypeError::parse_field("AssetID::features", err.into()))?;
 
-        let raid_id = match hex.get(16..31) {
-            None => Err(TypeError::parse_field_raw("AssetID::raid_id", hex)),
-            Some(buf) => RaidID::from_base58(buf),
-        }?;
+        let raid_id = RaidID::from_base58(&hex[16..31])?;
 
-        if hex.get(31..32) != Some(".") {
-            return Err(TypeError::parse_field_raw("AssetID::'.'", hex));
+        if &hex[31..32] != "." {
+            return Err(TypeError::parse_at("AssetID", 31, hex, "expected '.'"));
         }
 
-        let hash = match hex.get(32..64) {
-            None => Err(TypeError::parse_field_raw("AssetID::hash", hex))?,
-            Some(buf) => {
-                u128::from_str_radix(buf, 16).map_err(|err| TypeError::parse_field("AssetID::hash", err.into()))?;
-                buf.to_string()
-            },
-        };
+        check_hex("AssetID::hash", hex, 32..64)?;
+        let hash = hex[32..64].to_string();
 
         Ok(Self {
             template_id,
@@ -172,7 +222,7 @@ impl TryFrom<String> for AssetID {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test::utils::test_db_client;
+    use crate::test::utils::{test_db_client, Test};
 
     #[test]
     fn asset_default() {
@@ -197,6 +247,34 @@ mod test {
         }
     }
 
+    #[test]
+    fn asset_error_position() {
+        let mut raw = format!("{:031X}.{:032X}", 0, 0);
+        raw.replace_range(40..41, "Z");
+        match raw.parse::<AssetID>() {
+            Err(TypeError::ParseAt { field, pos, .. }) => {
+                assert_eq!(field, "AssetID::hash");
+                assert_eq!(pos, 40);
+            },
+            res => panic!("Expected ParseAt error, got {:?}", res),
+        }
+        let err = "A".parse::<AssetID>().unwrap_err();
+        assert_eq!(err.to_string(), "AssetID should be 64-char string, got 1-char A instead");
+    }
+
+    #[test]
+    fn asset_bech32() {
+        let id = Test::<AssetID>::new();
+        let encoded = id.to_bech32();
+        assert!(encoded.starts_with("tasset1"));
+        assert_eq!(format!("{:#}", id), encoded);
+        assert_eq!(encoded.parse::<AssetID>().unwrap(), id);
+        assert_eq!(encoded.to_uppercase().parse::<AssetID>().unwrap(), id);
+
+        let typo = format!("{}{}", &encoded[..encoded.len() - 1], if encoded.ends_with('q') { 'p' } else { 'q' });
+        assert!(matches!(typo.parse::<AssetID>(), Err(TypeError::Checksum { .. })));
+    }
+
     #[test]
     fn asset_from_to_string() {
         let mut raw = vec!["A"; 64];
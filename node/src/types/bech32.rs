@@ -0,0 +1,148 @@
+//! Checksummed human-friendly encoding of identifiers in
+//! [BIP-0173](https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki) bech32 style
+//!
+//! Unlike BIP-0173 there is no 90 chars limit, as TokenID does not fit into it.
+//! Encoded string is `{hrp}1{data}{checksum}`, case insensitive, typos are detected by 6-char checksum.
+
+use super::errors::TypeError;
+use std::iter::once;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const CHECKSUM_LEN: usize = 6;
+const SEPARATOR: char = '1';
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes()
+        .map(|b| b >> 5)
+        .chain(once(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+}
+
+/// Regroups bits, e.g. 8-bit bytes into 5-bit groups, None if non-zero padding is left on decoding
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let max_value: u32 = (1 << to) - 1;
+    let max_acc: u32 = (1 << (from + to - 1)) - 1;
+    let mut result = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for value in data {
+        acc = ((acc << from) | *value as u32) & max_acc;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((acc >> bits) & max_value) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            result.push(((acc << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max_value) != 0 {
+        return None;
+    }
+    Some(result)
+}
+
+/// Whether `raw` looks like bech32 string with `hrp` prefix, regardless of checksum
+pub(crate) fn has_prefix(hrp: &str, raw: &str) -> bool {
+    match (raw.get(..hrp.len()), raw.get(hrp.len()..)) {
+        (Some(prefix), Some(rest)) => prefix.eq_ignore_ascii_case(hrp) && rest.starts_with(SEPARATOR),
+        _ => false,
+    }
+}
+
+/// Encodes bytes into lowercase bech32 string
+pub(crate) fn encode(hrp: &str, data: &[u8]) -> String {
+    let values = convert_bits(data, 8, 5, true).expect("padding is allowed on encoding");
+    let checksum = polymod(
+        hrp_expand(hrp)
+            .chain(values.iter().cloned())
+            .chain([0u8; CHECKSUM_LEN].iter().cloned()),
+    ) ^ 1;
+    let mut encoded = String::with_capacity(hrp.len() + 1 + values.len() + CHECKSUM_LEN);
+    encoded.push_str(hrp);
+    encoded.push(SEPARATOR);
+    encoded.extend(values.iter().map(|value| CHARSET[*value as usize] as char));
+    encoded.extend((0..CHECKSUM_LEN).map(|i| CHARSET[((checksum >> (5 * (5 - i))) & 31) as usize] as char));
+    encoded
+}
+
+/// Decodes bech32 string with lowercase `hrp` prefix into bytes, errors point to position of the problem in `raw`
+pub(crate) fn decode(field: &'static str, hrp: &str, raw: &str) -> Result<Vec<u8>, TypeError> {
+    if !has_prefix(hrp, raw) {
+        return Err(TypeError::parse_at(field, 0, raw, "expected bech32 prefix"));
+    }
+    if raw.chars().any(|c| c.is_ascii_lowercase()) {
+        if let Some(pos) = raw.chars().position(|c| c.is_ascii_uppercase()) {
+            return Err(TypeError::parse_at(field, pos, raw, "mixed case"));
+        }
+    }
+    let data_start = hrp.len() + 1;
+    let mut values = Vec::with_capacity(raw.len() - data_start);
+    for (i, c) in raw[data_start..].chars().enumerate() {
+        let c = c.to_ascii_lowercase();
+        match CHARSET.iter().position(|symbol| *symbol as char == c) {
+            Some(value) => values.push(value as u8),
+            None => return Err(TypeError::parse_at(field, data_start + i, raw, "invalid bech32 character")),
+        }
+    }
+    if values.len() < CHECKSUM_LEN {
+        return Err(TypeError::parse_at(field, raw.len(), raw, "checksum is missing"));
+    }
+    if polymod(hrp_expand(hrp).chain(values.iter().cloned())) != 1 {
+        return Err(TypeError::checksum(field, raw));
+    }
+    values.truncate(values.len() - CHECKSUM_LEN);
+    convert_bits(&values, 5, 8, false)
+        .ok_or_else(|| TypeError::parse_at(field, raw.len() - CHECKSUM_LEN - 1, raw, "invalid padding"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bip173_checksum() {
+        // valid BIP-0173 test vectors with empty data
+        assert!(decode("test", "a", "a12uel5l").unwrap().is_empty());
+        assert!(decode("test", "a", "A12UEL5L").unwrap().is_empty());
+        assert_eq!(encode("a", &[]), "a12uel5l");
+    }
+
+    #[test]
+    fn roundtrip_and_errors() {
+        let data: Vec<u8> = (0..55).collect();
+        let encoded = encode("tst", &data);
+        assert_eq!(decode("test", "tst", &encoded).unwrap(), data);
+        assert_eq!(decode("test", "tst", &encoded.to_uppercase()).unwrap(), data);
+
+        let mut typo = encoded.clone().into_bytes();
+        typo[10] = if typo[10] == b'q' { b'p' } else { b'q' };
+        let typo = String::from_utf8(typo).unwrap();
+        assert!(matches!(decode("test", "tst", &typo), Err(TypeError::Checksum { .. })));
+
+        let invalid = format!("{}b{}", &encoded[..10], &encoded[11..]);
+        match decode("test", "tst", &invalid) {
+            Err(TypeError::ParseAt { pos, .. }) => assert_eq!(pos, 10),
+            res => panic!("Expected ParseAt error, got {:?}", res),
+        }
+        let mixed = format!("{}{}", &encoded[..20], &encoded[20..].to_uppercase());
+        assert!(matches!(decode("test", "tst", &mixed), Err(TypeError::ParseAt { .. })));
+        assert!(decode("test", "xyz", &encoded).is_err());
+    }
+}
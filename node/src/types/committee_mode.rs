@@ -5,18 +5,25 @@ use serde_json::{json, Value};
 use std::error::Error;
 use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
 
+/// How committee of Public asset is picked among nodes meeting collateral requirement
 #[derive(Clone, Serialize, PartialEq, Debug, Deserialize)]
 pub enum NodeSelectionStrategy {
-    RegisterAll = 1,
+    /// Every eligible node joins the committee
+    RegisterAll,
+    /// `node_threshold` nodes are picked randomly among eligible nodes of the whitelist
+    RandomFromWhitelist { whitelist: Vec<String> },
 }
 
+/// Committee of the asset, nodes are selected on asset registration, see [crate::consensus::select_committee]
 #[derive(Clone, Serialize, PartialEq, Debug, Deserialize)]
 pub enum CommitteeMode {
+    /// Committee of nodes with at least `minimum_collateral` staked, at least `node_threshold` nodes
     Public {
         node_threshold: u32,
         minimum_collateral: i64,
         node_selection_strategy: NodeSelectionStrategy,
     },
+    /// Committee of nodes trusted by asset creator, node registering the asset if the set is empty
     Creator {
         trusted_node_set: Vec<String>,
     },
@@ -1,14 +1,42 @@
-use crate::db::models::{NewAssetStateAppendOnly, NewTokenStateAppendOnly};
+use crate::{
+    consensus::CommitteeHandover,
+    db::models::{tokens::NewToken, NewAssetStateAppendOnly, NewTokenStateAppendOnly},
+};
 use bytes::BytesMut;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::error::Error;
 use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, Json, ToSql, Type};
 
-#[derive(Clone, Serialize, PartialEq, Deserialize, Debug)]
+#[derive(Clone, Serialize, PartialEq, Deserialize, Debug, Default)]
 pub struct AppendOnlyState {
     pub asset_state: Vec<NewAssetStateAppendOnly>,
     pub token_state: Vec<NewTokenStateAppendOnly>,
+    /// Tokens created by instructions, stored before token state
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<NewToken>,
+    /// New committee of the asset, replaces committee members on commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub committee_handover: Option<CommitteeHandover>,
+}
+
+impl AppendOnlyState {
+    pub fn is_empty(&self) -> bool {
+        self.asset_state.is_empty() &&
+            self.token_state.is_empty() &&
+            self.tokens.is_empty() &&
+            self.committee_handover.is_none()
+    }
+
+    /// Moves state of `other` to the end of this state
+    pub fn append(&mut self, other: &mut Self) {
+        self.asset_state.append(&mut other.asset_state);
+        self.token_state.append(&mut other.token_state);
+        self.tokens.append(&mut other.tokens);
+        if other.committee_handover.is_some() {
+            self.committee_handover = other.committee_handover.take();
+        }
+    }
 }
 
 impl<'a> ToSql for AppendOnlyState {
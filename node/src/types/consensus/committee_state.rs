@@ -21,3 +21,16 @@ pub enum CommitteeState {
         aggregate_signature_message: AggregateSignatureMessage,
     },
 }
+
+impl CommitteeState {
+    /// State name without data, used as span and log field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PreparingView { .. } => "PreparingView",
+            Self::ViewThresholdReached { .. } => "ViewThresholdReached",
+            Self::ReceivedLeaderProposal { .. } => "ReceivedLeaderProposal",
+            Self::SignedProposalThresholdReached { .. } => "SignedProposalThresholdReached",
+            Self::LeaderFinalizedProposalReceived { .. } => "LeaderFinalizedProposalReceived",
+        }
+    }
+}
@@ -1,9 +1,10 @@
-use crate::types::NodeID;
+use crate::{identity::NodeIdentity, types::NodeID};
 use bytes::BytesMut;
 use postgres_types::Json;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::error::Error;
+use std::{collections::HashMap, error::Error};
+use tari_core::transactions::types::PublicKey;
 use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
 
 #[derive(Clone, Serialize, PartialEq, Debug, Deserialize)]
@@ -11,6 +12,33 @@ pub struct SignatureData {
     pub signatures: Vec<(NodeID, String)>,
 }
 
+impl SignatureData {
+    /// Verifies committee signatures of the message in a single batch,
+    /// fails if there are no signatures or any signer is not in `public_keys`
+    pub fn verify(&self, message: &[u8], public_keys: &HashMap<NodeID, PublicKey>) -> bool {
+        let mut batch = Vec::with_capacity(self.signatures.len());
+        for (node_id, signature) in self.signatures.iter() {
+            match public_keys.get(node_id) {
+                Some(public_key) => batch.push((public_key, signature.as_str(), message)),
+                None => return false,
+            }
+        }
+        !batch.is_empty() && NodeIdentity::verify_batch(&batch)
+    }
+
+    /// Signers whose signature of the message does not verify one by one, e.g. after batch verification failed
+    pub fn invalid_signers(&self, message: &[u8], public_keys: &HashMap<NodeID, PublicKey>) -> Vec<NodeID> {
+        self.signatures
+            .iter()
+            .filter(|(node_id, signature)| match public_keys.get(node_id) {
+                Some(public_key) => !NodeIdentity::verify(public_key, signature, message),
+                None => true,
+            })
+            .map(|(node_id, _)| *node_id)
+            .collect()
+    }
+}
+
 impl Default for SignatureData {
     fn default() -> SignatureData {
         SignatureData { signatures: Vec::new() }
@@ -10,8 +10,22 @@ pub enum TypeError {
     },
     #[error("Failed to parse {field} from source string {raw}")]
     ParseFieldRaw { field: &'static str, raw: String },
-    #[error("{obj} should be {len}-char string, got {raw} instead")]
-    SourceLen { obj: &'static str, len: usize, raw: String },
+    #[error("Failed to parse {field} at position {pos} of {raw}: {reason}")]
+    ParseAt {
+        field: &'static str,
+        pos: usize,
+        raw: String,
+        reason: &'static str,
+    },
+    #[error("Invalid checksum of {field} {raw}, check it for typos")]
+    Checksum { field: &'static str, raw: String },
+    #[error("{obj} should be {len}-char string, got {actual}-char {raw} instead")]
+    SourceLen {
+        obj: &'static str,
+        len: usize,
+        actual: usize,
+        raw: String,
+    },
     #[error("Failed to generate uuid {0}")]
     Uuid(#[from] uuid::Error),
     #[error(transparent)]
@@ -30,15 +44,56 @@ impl TypeError {
         }
     }
 
+    pub(crate) fn parse_at(field: &'static str, pos: usize, raw: &str, reason: &'static str) -> Self {
+        Self::ParseAt {
+            field,
+            pos,
+            raw: raw.to_owned(),
+            reason,
+        }
+    }
+
+    pub(crate) fn checksum(field: &'static str, raw: &str) -> Self {
+        Self::Checksum {
+            field,
+            raw: raw.to_owned(),
+        }
+    }
+
     pub(crate) fn source_len(obj: &'static str, len: usize, raw: &str) -> Self {
         Self::SourceLen {
             obj,
             len,
+            actual: raw.chars().count(),
             raw: raw.to_owned(),
         }
     }
+
+    /// Structured `details` of JSON error body: offending field and position of the parse error
+    pub fn details(&self) -> serde_json::Value {
+        match self {
+            Self::ParseAt { field, pos, .. } => serde_json::json!({ "field": field, "position": pos }),
+            Self::ParseField { field, .. } | Self::ParseFieldRaw { field, .. } | Self::Checksum { field, .. } => {
+                serde_json::json!({ "field": field })
+            },
+            Self::SourceLen { obj, len, actual, .. } => {
+                serde_json::json!({ "field": obj, "len": len, "actual": actual })
+            },
+            Self::Uuid(_) | Self::Other(_) => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Checks `raw[range]` is hex, error points to the first non-hex char, `raw` should be ASCII
+pub(crate) fn check_hex(field: &'static str, raw: &str, range: std::ops::Range<usize>) -> Result<(), TypeError> {
+    let start = range.start;
+    match raw[range].chars().position(|c| !c.is_ascii_hexdigit()) {
+        Some(pos) => Err(TypeError::parse_at(field, start + pos, raw, "expected hex digit")),
+        None => Ok(()),
+    }
 }
 
+use crate::api::errors::ErrorCode;
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 
 impl ResponseError for TypeError {
@@ -48,6 +103,6 @@ impl ResponseError for TypeError {
 
     fn error_response(&self) -> HttpResponse {
         log::debug!("TypeError: {}", self.to_string());
-        HttpResponse::BadRequest().json(serde_json::json!({"error": self.to_string()}))
+        HttpResponse::BadRequest().json(ErrorCode::Validation.body(&self.to_string(), self.details()))
     }
 }
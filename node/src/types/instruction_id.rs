@@ -1,8 +1,11 @@
-use crate::types::{errors::TypeError, identity::generate_uuid_v1, NodeID};
+use crate::types::{errors::TypeError, identity::generate_uuid_v1, AssetID, NodeID, TokenID};
+use digest::Digest;
 use serde::{Deserialize, Serialize};
-use std::{fmt, ops::Deref, str::FromStr};
+use serde_json::Value;
+use std::{convert::TryInto, fmt, ops::Deref, str::FromStr};
+use tari_core::transactions::crypto::common::Blake256;
 use tokio_postgres::types::{FromSql, ToSql, Type};
-use uuid::Uuid;
+use uuid::{Builder, Uuid, Variant, Version};
 
 #[derive(Default, Copy, Clone, PartialEq, Eq, Hash, Debug, ToSql, FromSql, Deserialize, Serialize)]
 pub struct InstructionID(pub(crate) Uuid);
@@ -10,9 +13,51 @@ pub struct InstructionID(pub(crate) Uuid);
 impl InstructionID {
     pub const SQL_TYPE: Type = Type::UUID;
 
+    /// Unique time based ID, see [InstructionID::derive] for content addressed ID
     pub fn new(node_id: NodeID) -> Result<Self, TypeError> {
         Ok(Self(generate_uuid_v1(&node_id)?))
     }
+
+    /// Content addressed ID, every node receiving the same instruction derives the same ID
+    ///
+    /// ID is the first 16 bytes of Blake256 hash of length-prefixed instruction fields,
+    /// laid out as name-based UUID. `nonce` distinguishes otherwise identical instructions,
+    /// e.g. client signature of the request or parent instruction ID.
+    /// JSON object keys are serialized sorted, so `params` hash does not depend on keys order.
+    pub fn derive(
+        asset_id: &AssetID,
+        token_id: Option<&TokenID>,
+        contract_name: &str,
+        params: &Value,
+        nonce: &[u8],
+        initiating_node_id: NodeID,
+    ) -> Self
+    {
+        let asset_id = asset_id.to_string();
+        let token_id = token_id.map(|token_id| token_id.to_string()).unwrap_or_default();
+        let params = params.to_string();
+        let node_id = initiating_node_id.inner();
+        let fields: [&[u8]; 6] = [
+            asset_id.as_bytes(),
+            token_id.as_bytes(),
+            contract_name.as_bytes(),
+            params.as_bytes(),
+            nonce,
+            &node_id,
+        ];
+        let mut hasher = Blake256::new();
+        for field in fields.iter() {
+            hasher.input((field.len() as u64).to_le_bytes());
+            hasher.input(field);
+        }
+        let hash = hasher.result();
+        // Blake256 output is 32 bytes, so slice conversion never fails
+        let uuid = Builder::from_bytes(hash[..16].try_into().unwrap())
+            .set_variant(Variant::RFC4122)
+            .set_version(Version::Sha1)
+            .build();
+        Self(uuid)
+    }
 }
 
 impl fmt::Display for InstructionID {
@@ -36,3 +81,35 @@ impl Deref for InstructionID {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::Test;
+    use serde_json::json;
+
+    #[test]
+    fn derive() {
+        let asset_id = Test::<AssetID>::new();
+        let token_id = Test::<TokenID>::from_asset(&asset_id);
+        let node_id = NodeID::stub();
+        let params = json!({"price": 1, "user_pubkey": "abc"});
+        let id = InstructionID::derive(&asset_id, Some(&token_id), "sell_token", &params, b"nonce", node_id);
+        let same_params = json!({"user_pubkey": "abc", "price": 1});
+        assert_eq!(
+            id,
+            InstructionID::derive(&asset_id, Some(&token_id), "sell_token", &same_params, b"nonce", node_id)
+        );
+        assert_eq!(id.get_version(), Some(Version::Sha1));
+
+        for other in &[
+            InstructionID::derive(&asset_id, None, "sell_token", &params, b"nonce", node_id),
+            InstructionID::derive(&asset_id, Some(&token_id), "transfer_token", &params, b"nonce", node_id),
+            InstructionID::derive(&asset_id, Some(&token_id), "sell_token", &json!({}), b"nonce", node_id),
+            InstructionID::derive(&asset_id, Some(&token_id), "sell_token", &params, b"other", node_id),
+            InstructionID::derive(&asset_id, Some(&token_id), "sell_token", &params, b"nonce", NodeID::default()),
+        ] {
+            assert_ne!(&id, other);
+        }
+    }
+}
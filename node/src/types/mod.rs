@@ -1,3 +1,4 @@
+pub(crate) mod bech32;
 pub mod consensus;
 pub mod errors;
 
@@ -1,7 +1,7 @@
 //! Stub
 use bytes::BytesMut;
 use serde::{Deserialize, Serialize};
-use std::{convert::TryInto, error::Error};
+use std::{convert::TryInto, error::Error, fmt};
 use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
 
 #[derive(Serialize, Hash, Eq, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
@@ -20,6 +20,15 @@ impl NodeID {
     }
 }
 
+impl fmt::Display for NodeID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> FromSql<'a> for NodeID {
     accepts!(BYTEA);
 
@@ -25,7 +25,7 @@ impl FromStr for RaidID {
 impl RaidID {
     pub fn from_base58(raw: &str) -> Result<Self, TypeError> {
         if raw.len() != 15 {
-            return Err(TypeError::source_len("RaidID", 12, raw));
+            return Err(TypeError::source_len("RaidID", 15, raw));
         }
         Ok(Self(raw.to_owned()))
     }
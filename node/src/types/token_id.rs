@@ -7,10 +7,20 @@
 
 // TODO: think - should we store our IDs as base58 perhaps in database rather than our string?
 
-use super::{errors::TypeError, AssetID, NodeID};
+use super::{
+    bech32,
+    errors::{check_hex, TypeError},
+    AssetID,
+    NodeID,
+};
 use crate::types::identity::generate_uuid_v1;
 use bytes::BytesMut;
 use postgres_protocol::types::text_from_sql;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, error::Error, fmt, str::FromStr};
 use tokio_postgres::types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
@@ -22,21 +32,33 @@ pub struct TokenID {
     uid: uuid::Uuid,
 }
 
+/// Converts TokenID from 96-char string or from bech32 encoding with [TokenID::BECH32_HRP] prefix
 impl FromStr for TokenID {
     type Err = TypeError;
 
     fn from_str(hex: &str) -> Result<Self, TypeError> {
+        if bech32::has_prefix(Self::BECH32_HRP, hex) {
+            return Self::from_bech32(hex);
+        }
         if hex.len() != 96 {
             return Err(TypeError::source_len("TokenID", 96, hex));
         }
+        if let Some(pos) = hex.chars().position(|c| !c.is_ascii()) {
+            return Err(TypeError::parse_at("TokenID", pos, hex, "non-ASCII character"));
+        }
         let asset_id: AssetID = hex[0..64].parse()?;
+        check_hex("TokenID::uid", hex, 64..96)?;
         let uid = hex[64..96].parse()?;
         Ok(Self { asset_id, uid })
     }
 }
 
+/// Alternate format `{:#}` displays checksummed bech32 encoding
 impl fmt::Display for TokenID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.to_bech32());
+        }
         write!(f, "{}{:X}", self.asset_id, self.uid.to_simple())
     }
 }
@@ -55,9 +77,32 @@ impl TryFrom<String> for TokenID {
     }
 }
 
+/// Serialized as 96 chars string: [AssetID] followed by hex of uid,
+/// 101 chars bech32 encoding is accepted too
+impl JsonSchema for TokenID {
+    fn schema_name() -> String {
+        "TokenID".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                min_length: Some(96),
+                max_length: Some(101),
+                pattern: None,
+            })),
+            ..SchemaObject::default()
+        }
+        .into()
+    }
+}
+
 impl TokenID {
     /// TokenID stored as BPCHAR, it might change in the future
     pub const SQL_TYPE: Type = Type::BPCHAR;
+    /// Human readable prefix of bech32 encoding
+    pub const BECH32_HRP: &'static str = "ttoken";
 
     /// Generate TokenID for AssetID on a node
     ///
@@ -79,6 +124,24 @@ impl TokenID {
     pub fn uid(&self) -> uuid::Uuid {
         self.uid.clone()
     }
+
+    /// Checksummed bech32 encoding of [AssetID] bytes followed by uid, also available as alternate format `{:#}`
+    pub fn to_bech32(&self) -> String {
+        let mut bytes = self.asset_id.to_bytes();
+        bytes.extend_from_slice(self.uid.as_bytes());
+        bech32::encode(Self::BECH32_HRP, &bytes)
+    }
+
+    pub fn from_bech32(raw: &str) -> Result<Self, TypeError> {
+        let bytes = bech32::decode("TokenID", Self::BECH32_HRP, raw)?;
+        if bytes.len() != AssetID::BYTES_LEN + 16 {
+            return Err(TypeError::parse_field_raw("TokenID", raw));
+        }
+        Ok(Self {
+            asset_id: AssetID::from_bytes(raw, &bytes[..AssetID::BYTES_LEN])?,
+            uid: uuid::Uuid::from_slice(&bytes[AssetID::BYTES_LEN..])?,
+        })
+    }
 }
 
 impl<'a> FromSql<'a> for TokenID {
@@ -102,7 +165,7 @@ impl<'a> ToSql for TokenID {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test::utils::test_db_client;
+    use crate::test::utils::{test_db_client, Test};
 
     #[test]
     fn token_default() {
@@ -123,6 +186,27 @@ mod test {
         }
     }
 
+    #[test]
+    fn token_bech32() {
+        let id = TokenID::new(&Test::<AssetID>::new(), &NodeID([0, 1, 2, 3, 4, 5])).unwrap();
+        let encoded = format!("{:#}", id);
+        assert!(encoded.starts_with("ttoken1"));
+        assert_eq!(encoded.len(), 101);
+        assert_eq!(encoded.parse::<TokenID>().unwrap(), id);
+        assert_eq!(serde_json::from_value::<TokenID>(encoded.into()).unwrap(), id);
+        assert!(id.asset_id().to_bech32().parse::<TokenID>().is_err());
+
+        let mut raw = id.to_string();
+        raw.replace_range(70..71, "X");
+        match raw.parse::<TokenID>() {
+            Err(TypeError::ParseAt { field, pos, .. }) => {
+                assert_eq!(field, "TokenID::uid");
+                assert_eq!(pos, 70);
+            },
+            res => panic!("Expected ParseAt error, got {:?}", res),
+        }
+    }
+
     #[test]
     fn token_from_to_string() {
         let mut raw = vec!["A"; 96];
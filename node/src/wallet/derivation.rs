@@ -0,0 +1,90 @@
+use super::WalletError;
+use crate::types::{InstructionID, TemplateID};
+use digest::Digest;
+use std::{fmt, str::FromStr};
+use tari_core::transactions::{
+    crypto::{common::Blake256, tari_utilities::ByteArray},
+    types::PrivateKey,
+};
+
+/// Domain of the node master key, see [NodeIdentity::derive_secret](crate::identity::NodeIdentity::derive_secret)
+pub const MASTER_KEY_DOMAIN: &'static str = "temp-wallets";
+
+/// Path of temporary wallet key derived from the node master key: `m/<template id hex>/<instruction id>`
+///
+/// Every level is a hardened hash of the parent key and the level index, so keys of one template reveal
+/// nothing about the master key or keys of other templates
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivationPath {
+    pub template_id: TemplateID,
+    pub instruction_id: InstructionID,
+}
+
+impl DerivationPath {
+    pub fn new(template_id: TemplateID, instruction_id: InstructionID) -> Self {
+        Self {
+            template_id,
+            instruction_id,
+        }
+    }
+
+    /// Private key at the path
+    pub fn derive(&self, master_key: &[u8]) -> Result<PrivateKey, WalletError> {
+        let template_key = Self::child(master_key, "template", &self.template_id.to_hex());
+        let key = Self::child(&template_key, "instruction", &self.instruction_id.to_string());
+        PrivateKey::from_bytes(&key).map_err(|err| WalletError::Derivation(format!("{:?}", err)))
+    }
+
+    fn child(parent: &[u8], level: &str, index: &str) -> Vec<u8> {
+        Blake256::new()
+            .chain(parent)
+            .chain(level.as_bytes())
+            .chain(index.as_bytes())
+            .result()
+            .to_vec()
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m/{}/{}", self.template_id.to_hex(), self.instruction_id)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = WalletError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let invalid = || WalletError::Derivation(format!("invalid derivation path {}", path));
+        match path.split('/').collect::<Vec<_>>().as_slice() {
+            ["m", template_id, instruction_id] => Ok(Self {
+                template_id: TemplateID::from_hex(template_id).map_err(|_| invalid())?,
+                instruction_id: instruction_id.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::Test;
+
+    #[test]
+    fn derive_keys() {
+        let template_id = TemplateID::from(65536u64);
+        let path = DerivationPath::new(template_id, Test::<InstructionID>::new());
+        let parsed: DerivationPath = path.to_string().parse().unwrap();
+        assert_eq!(parsed, path);
+        assert!("m/00010000".parse::<DerivationPath>().is_err());
+
+        let key = path.derive(b"master").unwrap();
+        assert!(key == parsed.derive(b"master").unwrap());
+        assert!(key != path.derive(b"other master").unwrap());
+        let other = DerivationPath::new(template_id, Test::<InstructionID>::new());
+        assert!(key != other.derive(b"master").unwrap());
+        let other = DerivationPath::new(TemplateID::from(2u64), path.instruction_id);
+        assert!(key != other.derive(b"master").unwrap());
+    }
+}
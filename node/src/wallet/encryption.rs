@@ -0,0 +1,97 @@
+//! Passphrase encryption of wallet key files
+//!
+//! Key is derived from passphrase with Argon2 and random salt, file content is sealed
+//! with XChaCha20-Poly1305 under random nonce. Encrypted file is a JSON envelope with hex encoded fields.
+
+use super::WalletError;
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    Key,
+    XChaCha20Poly1305,
+    XNonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tari_crypto::tari_utilities::hex::{from_hex, to_hex};
+
+const VERSION: u8 = 1;
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+
+/// Encrypted wallet key file envelope
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EncryptedKeyFile {
+    pub version: u8,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl EncryptedKeyFile {
+    /// Parse envelope, returns None for plaintext key files
+    pub fn parse(content: &str) -> Option<Self> {
+        serde_json::from_str(content).ok()
+    }
+
+    /// Encrypt `plaintext` with key derived from `passphrase`
+    pub fn seal(plaintext: &[u8], passphrase: &str) -> Result<Self, WalletError> {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut nonce = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let cipher = Self::cipher(passphrase, &salt)?;
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|_| WalletError::encryption("encryption failed"))?;
+        Ok(Self {
+            version: VERSION,
+            salt: to_hex(&salt),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        })
+    }
+
+    /// Decrypt content with key derived from `passphrase`
+    pub fn open(&self, passphrase: &str) -> Result<Vec<u8>, WalletError> {
+        if self.version != VERSION {
+            return Err(WalletError::encryption(&format!(
+                "unsupported key file version {}",
+                self.version
+            )));
+        }
+        let salt = Self::decode(&self.salt)?;
+        let nonce = Self::decode(&self.nonce)?;
+        if nonce.len() != NONCE_SIZE {
+            return Err(WalletError::encryption("invalid nonce"));
+        }
+        let ciphertext = Self::decode(&self.ciphertext)?;
+        Self::cipher(passphrase, &salt)?
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| WalletError::InvalidPassphrase)
+    }
+
+    fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, WalletError> {
+        let key = argon2::hash_raw(passphrase.as_bytes(), salt, &argon2::Config::default())
+            .map_err(|err| WalletError::encryption(&err.to_string()))?;
+        Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn decode(hex: &str) -> Result<Vec<u8>, WalletError> {
+        from_hex(hex).map_err(|err| WalletError::encryption(&format!("{:?}", err)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let file = EncryptedKeyFile::seal(b"secret key", "passphrase").unwrap();
+        assert_ne!(file.ciphertext, to_hex(b"secret key"));
+        let file = EncryptedKeyFile::parse(&serde_json::to_string(&file).unwrap()).unwrap();
+        assert_eq!(file.open("passphrase").unwrap(), b"secret key");
+        assert!(matches!(file.open("wrong"), Err(WalletError::InvalidPassphrase)));
+        assert!(EncryptedKeyFile::parse("{\"identity\": {}, \"name\": \"taris\"}").is_none());
+    }
+}
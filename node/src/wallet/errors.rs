@@ -14,9 +14,25 @@ pub enum WalletError {
     NotFound { pubkey: String },
     #[error("DB error: {0}")]
     DBError(#[from] DBError),
+    #[error("Wallet key file encryption error: {0}")]
+    Encryption(String),
+    #[error("Invalid wallets passphrase")]
+    InvalidPassphrase,
+    #[error("Wallet {pubkey} key file is encrypted, wallets passphrase is not configured")]
+    PassphraseRequired { pubkey: String },
+    #[error("Wallet {pubkey} is already rotated")]
+    AlreadyRotated { pubkey: String },
+    #[error("Wallet key derivation error: {0}")]
+    Derivation(String),
+    #[error("Wallet {pubkey} key is derived from node master key, node identity is not loaded")]
+    MasterKeyRequired { pubkey: String },
 }
 impl WalletError {
     pub(crate) fn not_found(pubkey: String) -> Self {
         Self::NotFound { pubkey }
     }
+
+    pub(crate) fn encryption(msg: &str) -> Self {
+        Self::Encryption(msg.into())
+    }
 }
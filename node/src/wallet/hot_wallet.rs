@@ -1,4 +1,4 @@
-use super::WalletError;
+use super::{DerivationPath, WalletError};
 use crate::{db::models::wallet::*, types::Pubkey};
 use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,9 @@ use tari_wallet::util::emoji::EmojiId;
 pub struct NodeWallet {
     identity: NodeIdentity,
     name: String,
+    /// Set for keys derived from the node master key, which are not stored in key files
+    #[serde(skip)]
+    derivation_path: Option<DerivationPath>,
 }
 
 impl std::fmt::Display for NodeWallet {
@@ -32,7 +35,33 @@ impl NodeWallet {
     pub fn new(public_addr: Multiaddr, name: String) -> Result<Self, WalletError> {
         let private_key = PrivateKey::random(&mut OsRng);
         let identity = NodeIdentity::new(private_key, public_addr, PeerFeatures::COMMUNICATION_CLIENT)?;
-        Ok(Self { identity, name })
+        Ok(Self {
+            identity,
+            name,
+            derivation_path: None,
+        })
+    }
+
+    /// Wallet with the key derived from `master_key` along the `path`, see [DerivationPath]
+    pub fn derive(
+        master_key: &[u8],
+        path: DerivationPath,
+        public_addr: Multiaddr,
+        name: String,
+    ) -> Result<Self, WalletError>
+    {
+        let private_key = path.derive(master_key)?;
+        let identity = NodeIdentity::new(private_key, public_addr, PeerFeatures::COMMUNICATION_CLIENT)?;
+        Ok(Self {
+            identity,
+            name,
+            derivation_path: Some(path),
+        })
+    }
+
+    #[inline]
+    pub fn derivation_path(&self) -> Option<&DerivationPath> {
+        self.derivation_path.as_ref()
     }
 
     /// Generated public key hex
@@ -47,6 +76,8 @@ impl From<&NodeWallet> for NewWallet {
         Self {
             pub_key: source.public_key_hex(),
             name: source.name.clone(),
+            instruction_id: None,
+            derivation_path: source.derivation_path.as_ref().map(ToString::to_string),
         }
     }
 }
@@ -0,0 +1,204 @@
+//! Temporary wallets cleanup
+//!
+//! Contracts accepting payments create a temporary wallet per instruction. Once instruction is committed
+//! or invalidated (e.g. payment timeout expired) [WalletJanitor] sweeps the wallet balance into node's
+//! main wallet and marks it retired, key files of retired wallets are removed after `retention_hours`.
+//! Janitor is enabled in `[validator.wallet_janitor]` section of config or via WALLET_JANITOR_* env vars.
+
+use super::{WalletError, WalletStore};
+use crate::{db::models::wallet::Wallet, types::Pubkey};
+use actix::prelude::*;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use deadpool_postgres::{Client, Pool};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+const LOG_TARGET: &'static str = "tari_validator_node::wallet::janitor";
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletJanitorConfig {
+    /// Temporary wallets cleanup is disabled unless explicitly enabled by node operator
+    pub enabled: bool,
+    /// Public key of the node's wallet receiving swept balances
+    pub main_wallet: Option<Pubkey>,
+    /// Key files of retired wallets are kept for this number of hours
+    pub retention_hours: u32,
+    /// Period between cleanup runs in seconds
+    pub period: u64,
+}
+impl Default for WalletJanitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            main_wallet: None,
+            retention_hours: 168,
+            period: 300,
+        }
+    }
+}
+
+impl WalletJanitorConfig {
+    /// Cut-off date of key files removal
+    pub fn before(&self) -> DateTime<Utc> {
+        Utc::now() - ChronoDuration::hours(self.retention_hours as i64)
+    }
+}
+
+/// Sweeps temporary wallets of completed instructions into `main_wallet`, returns swept wallets
+pub async fn sweep_temp_wallets(main_wallet: &Pubkey, client: &Client) -> Result<Vec<Wallet>, WalletError> {
+    let main = Wallet::select_by_key(main_wallet, client).await?;
+    let mut swept = vec![];
+    for wallet in Wallet::find_sweepable(client).await? {
+        let amount = wallet.sweep(&main, client).await?;
+        log::info!(
+            target: LOG_TARGET,
+            "Wallet {} retired, swept {} into main wallet",
+            wallet.pub_key,
+            amount
+        );
+        swept.push(wallet);
+    }
+    Ok(swept)
+}
+
+/// Removes key files of wallets retired before `before`, returns wallets with removed keys
+pub async fn remove_retired_keys(
+    before: DateTime<Utc>,
+    store: &mut WalletStore,
+    client: &Client,
+) -> Result<Vec<Wallet>, WalletError>
+{
+    let mut removed = vec![];
+    for wallet in Wallet::find_retired(before, client).await? {
+        store.remove_keys(&wallet.pub_key)?;
+        removed.push(wallet.set_keys_removed(client).await?);
+    }
+    Ok(removed)
+}
+
+/// Actor cleaning up temporary wallets every `period` seconds, stops immediately if main wallet is not configured
+pub struct WalletJanitor {
+    config: WalletJanitorConfig,
+    store: Arc<Mutex<WalletStore>>,
+    pool: Arc<Pool>,
+}
+
+impl WalletJanitor {
+    pub fn new(config: WalletJanitorConfig, store: WalletStore, pool: Arc<Pool>) -> Self {
+        Self {
+            config,
+            store: Arc::new(Mutex::new(store)),
+            pool,
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let config = self.config.clone();
+        let store = self.store.clone();
+        let pool = self.pool.clone();
+        ctx.spawn(
+            async move {
+                let client = match pool.get().await {
+                    Ok(client) => client,
+                    Err(err) => {
+                        log::error!(target: LOG_TARGET, "Failed to clean up wallets: {}", err);
+                        return;
+                    },
+                };
+                if let Some(main_wallet) = config.main_wallet.as_ref() {
+                    if let Err(err) = sweep_temp_wallets(main_wallet, &client).await {
+                        log::error!(target: LOG_TARGET, "Failed to sweep temporary wallets: {}", err);
+                    }
+                }
+                let mut store = store.lock().await;
+                if let Err(err) = remove_retired_keys(config.before(), &mut store, &client).await {
+                    log::error!(target: LOG_TARGET, "Failed to remove retired wallets keys: {}", err);
+                }
+            }
+            .into_actor(self),
+        );
+    }
+}
+
+impl Actor for WalletJanitor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.config.main_wallet.is_none() {
+            log::error!(
+                target: LOG_TARGET,
+                "Wallet janitor is enabled, but main_wallet is not configured"
+            );
+            ctx.stop();
+            return;
+        }
+        log::info!(
+            target: LOG_TARGET,
+            "Wallet janitor enabled, sweeping temporary wallets every {}s",
+            self.config.period
+        );
+        ctx.run_interval(Duration::from_secs(self.config.period.max(1)), Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{consensus::instructions::UpdateInstruction, InstructionStatus},
+        test::utils::{builders::consensus::InstructionBuilder, test_db_client, Test},
+        wallet::NodeWallet,
+    };
+    use multiaddr::Multiaddr;
+    use tempdir::TempDir;
+
+    #[actix_rt::test]
+    async fn sweep_and_remove_keys() -> anyhow::Result<()> {
+        let (mut client, _lock) = test_db_client().await;
+        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf(), None)?;
+        let instruction = InstructionBuilder::default().build(&client).await?;
+
+        let transaction = client.transaction().await?;
+        let main = store
+            .add(NodeWallet::new(Multiaddr::empty(), "main".into())?, &transaction)
+            .await?;
+        let temp = store
+            .add_temp(
+                NodeWallet::new(Multiaddr::empty(), instruction.id.to_string())?,
+                instruction.id,
+                &transaction,
+            )
+            .await?;
+        transaction.commit().await?;
+        temp.data().set_balance(100, &client).await?;
+
+        // instruction is still pending
+        assert!(sweep_temp_wallets(&main.public_key_hex(), &client).await?.is_empty());
+
+        let update = UpdateInstruction {
+            status: Some(InstructionStatus::Commit),
+            ..UpdateInstruction::default()
+        };
+        instruction.update(update, &client).await?;
+        let swept = sweep_temp_wallets(&main.public_key_hex(), &client).await?;
+        assert_eq!(swept.len(), 1);
+        assert_eq!(swept[0].pub_key, temp.public_key_hex());
+        assert!(sweep_temp_wallets(&main.public_key_hex(), &client).await?.is_empty());
+        let main = Wallet::select_by_key(&main.public_key_hex(), &client).await?;
+        assert_eq!(main.balance, 100);
+
+        assert!(remove_retired_keys(Utc::now() - ChronoDuration::hours(1), &mut store, &client)
+            .await?
+            .is_empty());
+        let removed = remove_retired_keys(Utc::now(), &mut store, &client).await?;
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].keys_removed_at.is_some());
+        assert!(matches!(
+            store.get(temp.public_key_hex(), &client).await,
+            Err(WalletError::NotFound { .. })
+        ));
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 //! Wallet operations
 
-use crate::db::models::wallet::*;
+use crate::{
+    db::{models::wallet::*, utils::errors::DBError},
+    types::{InstructionID, TemplateID},
+};
 use deadpool_postgres::{Client, Transaction};
 use log::info;
+use multiaddr::Multiaddr;
 use std::{collections::HashMap, path::PathBuf};
 
 mod hot_wallet;
@@ -11,41 +15,174 @@ pub use hot_wallet::{HotWallet, NodeWallet};
 mod errors;
 pub use errors::WalletError;
 
+mod encryption;
+pub use encryption::EncryptedKeyFile;
+
+mod derivation;
+pub use derivation::{DerivationPath, MASTER_KEY_DOMAIN};
+
+pub mod janitor;
+
+mod signer;
+pub use signer::{ExternalSigner, Signer, SignerConfig, SignerKind};
+
 const LOG_TARGET: &'static str = "tari_validator_node::wallet";
 
 // TODO: convert to interior mutability?
 /// Handles wallet storage operations, keeping FS and DB in sync
 /// [`WalletStore`] is the only way to access [`HotWallet`] object
+///
+/// When `passphrase` is set key files are stored encrypted, plaintext key files
+/// are re-written encrypted on first load
+///
+/// When `master_key` is set temporary wallets are derived from it per template and instruction,
+/// see [DerivationPath], their keys are re-derived on load so no key files are written for them
 pub struct WalletStore {
     wallets_keys_path: PathBuf,
+    passphrase: Option<String>,
+    master_key: Option<Vec<u8>>,
     cache: HashMap<String, HotWallet>,
 }
 
 impl WalletStore {
     /// Initialize store
-    pub fn init(wallets_keys_path: PathBuf) -> Result<Self, WalletError> {
+    pub fn init(wallets_keys_path: PathBuf, passphrase: Option<String>) -> Result<Self, WalletError> {
         if !wallets_keys_path.exists() {
             std::fs::create_dir(&wallets_keys_path)?;
         }
         Ok(Self {
             wallets_keys_path,
+            passphrase,
+            master_key: None,
             cache: HashMap::new(),
         })
     }
 
+    /// Sets node master key temporary wallets are derived from, see [MASTER_KEY_DOMAIN]
+    pub fn with_master_key(self, master_key: Vec<u8>) -> Self {
+        Self {
+            master_key: Some(master_key),
+            ..self
+        }
+    }
+
+    /// New temporary wallet of the instruction, derived from master key if it is set, random otherwise
+    ///
+    /// ## Parameters
+    /// `public_addr` - Network address of the base node
+    pub fn new_temp(
+        &self,
+        template_id: TemplateID,
+        instruction_id: InstructionID,
+        public_addr: Multiaddr,
+    ) -> Result<NodeWallet, WalletError>
+    {
+        let name = instruction_id.to_string();
+        match self.master_key.as_ref() {
+            Some(master_key) => {
+                let path = DerivationPath::new(template_id, instruction_id);
+                NodeWallet::derive(master_key, path, public_addr, name)
+            },
+            None => NodeWallet::new(public_addr, name),
+        }
+    }
+
     /// Add wallet to the file store and database
     pub async fn add<'t>(&mut self, wallet: NodeWallet, trans: &Transaction<'t>) -> Result<HotWallet, WalletError> {
         let data = NewWallet::from(&wallet);
+        self.insert(wallet, data, trans).await
+    }
+
+    /// Add temporary wallet created by instruction, it is swept and retired by
+    /// [WalletJanitor](janitor::WalletJanitor) once instruction completes
+    pub async fn add_temp<'t>(
+        &mut self,
+        wallet: NodeWallet,
+        instruction_id: InstructionID,
+        trans: &Transaction<'t>,
+    ) -> Result<HotWallet, WalletError>
+    {
+        let data = NewWallet {
+            instruction_id: Some(instruction_id),
+            ..NewWallet::from(&wallet)
+        };
+        self.insert(wallet, data, trans).await
+    }
+
+    async fn insert<'t>(
+        &mut self,
+        wallet: NodeWallet,
+        data: NewWallet,
+        trans: &Transaction<'t>,
+    ) -> Result<HotWallet, WalletError>
+    {
         let model = Wallet::insert(data, trans).await?;
         let wallet = HotWallet::new(wallet, model);
         let pubkey = wallet.public_key_hex();
-        let path = self.wallet_path(&pubkey);
-        let writer = std::fs::File::create(path)?;
-        serde_json::to_writer(writer, wallet.identity())?;
+        if wallet.identity().derivation_path().is_none() {
+            self.write_id(&pubkey, wallet.identity())?;
+        }
         self.cache.insert(pubkey, wallet.clone());
         Ok(wallet)
     }
 
+    /// Replace wallet with a newly generated key, new wallet is linked to the old one in DB
+    /// and receives its balance, old wallet is marked as rotated
+    ///
+    /// ## Parameters
+    /// `pubkey` - Public key of the wallet being rotated
+    /// `public_addr` - Network address of the base node
+    pub async fn rotate(
+        &mut self,
+        pubkey: String,
+        public_addr: Multiaddr,
+        client: &mut Client,
+    ) -> Result<HotWallet, WalletError>
+    {
+        let old = self.get(pubkey.clone(), client).await?;
+        if old.data().rotated_at.is_some() {
+            return Err(WalletError::AlreadyRotated { pubkey });
+        }
+        let wallet = NodeWallet::new(public_addr, old.name().clone())?;
+        let transaction = client.transaction().await.map_err(DBError::from)?;
+        let model = Wallet::insert(NewWallet::from(&wallet), &transaction).await?;
+        let model = old.data().rotate(&model, &transaction).await?;
+        let wallet = HotWallet::new(wallet, model);
+        let new_pubkey = wallet.public_key_hex();
+        self.write_id(&new_pubkey, wallet.identity())?;
+        transaction.commit().await.map_err(DBError::from)?;
+        info!(target: LOG_TARGET, "Wallet {} rotated, new public key {}", pubkey, new_pubkey);
+        self.cache.remove(&pubkey);
+        self.cache.insert(new_pubkey, wallet.clone());
+        Ok(wallet)
+    }
+
+    /// Move `amount` between wallets of this node, returns both wallets with updated balances
+    ///
+    /// ## Parameters
+    /// `from` - Public key of the wallet being debited
+    /// `to` - Public key of the wallet being credited
+    pub async fn transfer(
+        &mut self,
+        from: String,
+        to: String,
+        amount: i64,
+        client: &mut Client,
+    ) -> Result<(HotWallet, HotWallet), WalletError>
+    {
+        let sender = self.get(from.clone(), client).await?;
+        let recipient = self.get(to.clone(), client).await?;
+        let transaction = client.transaction().await.map_err(DBError::from)?;
+        let (sender_data, recipient_data) = sender.data().transfer(recipient.data(), amount, &transaction).await?;
+        transaction.commit().await.map_err(DBError::from)?;
+        info!(target: LOG_TARGET, "Transferred {} from wallet {} to {}", amount, from, to);
+        let sender = HotWallet::new(sender.identity().clone(), sender_data);
+        let recipient = HotWallet::new(recipient.identity().clone(), recipient_data);
+        self.cache.insert(from, sender.clone());
+        self.cache.insert(to, recipient.clone());
+        Ok((sender, recipient))
+    }
+
     /// Load and return wallet, will try to load wallet from disk if not found in cache.
     ///
     /// ## Parameters
@@ -55,14 +192,16 @@ impl WalletStore {
             return Ok(wallet.clone());
         }
 
-        let path = self.wallet_path(&pubkey);
-        if !path.exists() {
-            return Err(WalletError::not_found(pubkey));
-        }
-        let id_str = std::fs::read_to_string(path)?;
-        let id: NodeWallet = serde_json::from_str(&id_str)?;
-        let model = Wallet::select_by_key(&pubkey, client).await?;
-        let wallet = HotWallet::new(id, model);
+        let select = SelectWallet {
+            pub_key: Some(pubkey.clone()),
+            ..SelectWallet::default()
+        };
+        let model = Wallet::select(select, client)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| WalletError::not_found(pubkey.clone()))?;
+        let wallet = HotWallet::new(self.keys(&model)?, model);
         info!(
             target: LOG_TARGET,
             "Wallet loaded with public key {}",
@@ -79,25 +218,90 @@ impl WalletStore {
         let wallets = Wallet::select(all, client).await?;
         let mut res = Vec::with_capacity(wallets.len());
         for wallet in wallets.into_iter() {
-            let id = self.load_id(&wallet.pub_key).await?;
+            let id = self.load_id(&wallet).await?;
             res.push(HotWallet::new(id, wallet));
         }
         Ok(res)
     }
 
     /// Load [`NodeWallet`] from disk
-    async fn load_id(&mut self, pubkey: &String) -> Result<NodeWallet, WalletError> {
-        if let Some(wallet) = self.cache.get(pubkey) {
+    async fn load_id(&mut self, wallet: &Wallet) -> Result<NodeWallet, WalletError> {
+        if let Some(wallet) = self.cache.get(&wallet.pub_key) {
             return Ok(wallet.identity().clone());
         }
+        let id = self.keys(wallet)?;
+        info!(target: LOG_TARGET, "NodeWallet loaded with public key {}", wallet.pub_key);
+        Ok(id)
+    }
+
+    /// Keys of the wallet, read from key file or re-derived from master key along the wallet derivation path
+    fn keys(&self, wallet: &Wallet) -> Result<NodeWallet, WalletError> {
+        let derivation_path = match wallet.derivation_path.as_ref() {
+            Some(derivation_path) => derivation_path,
+            None => return self.read_id(&wallet.pub_key),
+        };
+        // derived keys are "removed" for retired wallets the same way as key files are
+        if wallet.keys_removed_at.is_some() {
+            return Err(WalletError::not_found(wallet.pub_key.clone()));
+        }
+        let master_key = self.master_key.as_ref().ok_or_else(|| WalletError::MasterKeyRequired {
+            pubkey: wallet.pub_key.clone(),
+        })?;
+        let path = derivation_path.parse()?;
+        // address of temporary wallets is not used, keys are the only thing which matters
+        let id = NodeWallet::derive(master_key, path, Multiaddr::empty(), wallet.name.clone())?;
+        if id.public_key_hex() != wallet.pub_key {
+            return Err(WalletError::Derivation(format!(
+                "key derived along {} does not match wallet {}",
+                derivation_path, wallet.pub_key
+            )));
+        }
+        Ok(id)
+    }
+
+    /// Remove wallet key file, wallet can't be loaded afterwards
+    pub fn remove_keys(&mut self, pubkey: &String) -> Result<(), WalletError> {
+        self.cache.remove(pubkey);
+        let path = self.wallet_path(pubkey);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        info!(target: LOG_TARGET, "Wallet {} key file removed", pubkey);
+        Ok(())
+    }
+
+    /// Read key file, plaintext files are encrypted in place if passphrase is configured
+    fn read_id(&self, pubkey: &String) -> Result<NodeWallet, WalletError> {
         let path = self.wallet_path(pubkey);
         if !path.exists() {
             return Err(WalletError::not_found(pubkey.clone()));
         }
         let id_str = std::fs::read_to_string(path)?;
-        let id = serde_json::from_str(&id_str)?;
-        info!(target: LOG_TARGET, "NodeWallet loaded with public key {}", pubkey);
-        Ok(id)
+        match (EncryptedKeyFile::parse(&id_str), self.passphrase.as_ref()) {
+            (Some(file), Some(passphrase)) => Ok(serde_json::from_slice(&file.open(passphrase)?)?),
+            (Some(_), None) => Err(WalletError::PassphraseRequired { pubkey: pubkey.clone() }),
+            (None, passphrase) => {
+                let id: NodeWallet = serde_json::from_str(&id_str)?;
+                if passphrase.is_some() {
+                    self.write_id(pubkey, &id)?;
+                    info!(target: LOG_TARGET, "Wallet {} key file encrypted", pubkey);
+                }
+                Ok(id)
+            },
+        }
+    }
+
+    /// Write key file, encrypted if passphrase is configured
+    fn write_id(&self, pubkey: &String, id: &NodeWallet) -> Result<(), WalletError> {
+        let writer = std::fs::File::create(self.wallet_path(pubkey))?;
+        match self.passphrase.as_ref() {
+            Some(passphrase) => {
+                let file = EncryptedKeyFile::seal(&serde_json::to_vec(id)?, passphrase)?;
+                serde_json::to_writer(writer, &file)?;
+            },
+            None => serde_json::to_writer(writer, id)?,
+        };
+        Ok(())
     }
 
     fn wallet_path(&self, pubkey: &String) -> PathBuf {
@@ -109,8 +313,7 @@ impl WalletStore {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test::utils::{test_db_client, Test};
-    use multiaddr::Multiaddr;
+    use crate::test::utils::{builders::consensus::InstructionBuilder, test_db_client, Test};
     use tari_core::tari_utilities::hex::Hex;
     use tempdir::TempDir;
 
@@ -119,7 +322,7 @@ mod test {
         let (mut client, _lock) = test_db_client().await;
         let address = Multiaddr::empty();
 
-        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf())?;
+        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf(), None)?;
         let wallet = NodeWallet::new(address, "taris".into())?;
         let pubkey = wallet.public_key_hex();
         let transaction = client.transaction().await?;
@@ -139,7 +342,7 @@ mod test {
         let (mut client, _lock) = test_db_client().await;
         let address = Multiaddr::empty();
 
-        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf())?;
+        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf(), None)?;
         let wallet = NodeWallet::new(address, "taris".to_string())?;
 
         let transaction = client.transaction().await?;
@@ -153,4 +356,118 @@ mod test {
         assert_eq!(count, 1);
         Ok(())
     }
+
+    #[actix_rt::test]
+    async fn encrypt_plaintext_key_files() -> anyhow::Result<()> {
+        let (mut client, _lock) = test_db_client().await;
+        let path = Test::<TempDir>::get_path_buf();
+
+        let mut store = WalletStore::init(path.clone(), None)?;
+        let wallet = NodeWallet::new(Multiaddr::empty(), "taris".into())?;
+        let pubkey = wallet.public_key_hex();
+        let transaction = client.transaction().await?;
+        store.add(wallet, &transaction).await?;
+        transaction.commit().await?;
+        let key_file = path.join(format!("{}.json", pubkey));
+        assert!(EncryptedKeyFile::parse(&std::fs::read_to_string(&key_file)?).is_none());
+
+        let mut store = WalletStore::init(path.clone(), Some("passphrase".into()))?;
+        store.get(pubkey.clone(), &client).await?;
+        assert!(EncryptedKeyFile::parse(&std::fs::read_to_string(&key_file)?).is_some());
+
+        let mut store = WalletStore::init(path.clone(), Some("passphrase".into()))?;
+        assert_eq!(store.get(pubkey.clone(), &client).await?.name(), "taris");
+        let mut store = WalletStore::init(path.clone(), None)?;
+        assert!(matches!(store.get(pubkey.clone(), &client).await, Err(WalletError::PassphraseRequired { .. })));
+        let mut store = WalletStore::init(path, Some("wrong".into()))?;
+        assert!(matches!(store.get(pubkey, &client).await, Err(WalletError::InvalidPassphrase)));
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn derived_temp_wallets() -> anyhow::Result<()> {
+        let (mut client, _lock) = test_db_client().await;
+        let path = Test::<TempDir>::get_path_buf();
+        let instruction = InstructionBuilder::default().build(&client).await?;
+
+        let mut store = WalletStore::init(path.clone(), None)?.with_master_key(b"master".to_vec());
+        let wallet = store.new_temp(instruction.template_id, instruction.id, Multiaddr::empty())?;
+        let pubkey = wallet.public_key_hex();
+        let transaction = client.transaction().await?;
+        let wallet = store.add_temp(wallet, instruction.id, &transaction).await?;
+        transaction.commit().await?;
+        assert_eq!(
+            wallet.data().derivation_path,
+            Some(format!("m/{}/{}", instruction.template_id.to_hex(), instruction.id))
+        );
+        // key file count stays constant
+        assert_eq!(std::fs::read_dir(&path)?.count(), 0);
+
+        // keys are re-derived after key files and cache are lost
+        let mut store = WalletStore::init(path.clone(), None)?.with_master_key(b"master".to_vec());
+        assert_eq!(store.get(pubkey.clone(), &client).await?.public_key_hex(), pubkey);
+        assert_eq!(store.load(&client).await?.len(), 1);
+        let mut store = WalletStore::init(path.clone(), None)?.with_master_key(b"other".to_vec());
+        assert!(matches!(store.get(pubkey.clone(), &client).await, Err(WalletError::Derivation(_))));
+        let mut store = WalletStore::init(path, None)?;
+        assert!(matches!(
+            store.get(pubkey, &client).await,
+            Err(WalletError::MasterKeyRequired { .. })
+        ));
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn rotate() -> anyhow::Result<()> {
+        let (mut client, _lock) = test_db_client().await;
+        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf(), Some("passphrase".into()))?;
+        let wallet = NodeWallet::new(Multiaddr::empty(), "taris".into())?;
+        let pubkey = wallet.public_key_hex();
+        let transaction = client.transaction().await?;
+        let wallet = store.add(wallet, &transaction).await?;
+        transaction.commit().await?;
+        wallet.data().set_balance(100, &client).await?;
+
+        let rotated = store.rotate(pubkey.clone(), Multiaddr::empty(), &mut client).await?;
+        assert_ne!(rotated.public_key_hex(), pubkey);
+        assert_eq!(rotated.name(), "taris");
+        assert_eq!(rotated.balance(), 100);
+        assert_eq!(rotated.data().rotated_from, Some(wallet.data().id));
+
+        let old = store.get(pubkey.clone(), &client).await?;
+        assert!(old.data().rotated_at.is_some());
+        assert_eq!(old.balance(), 0);
+        assert!(matches!(
+            store.rotate(pubkey, Multiaddr::empty(), &mut client).await,
+            Err(WalletError::AlreadyRotated { .. })
+        ));
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn transfer() -> anyhow::Result<()> {
+        let (mut client, _lock) = test_db_client().await;
+        let mut store = WalletStore::init(Test::<TempDir>::get_path_buf(), None)?;
+        let transaction = client.transaction().await?;
+        let from = store
+            .add(NodeWallet::new(Multiaddr::empty(), "hot".into())?, &transaction)
+            .await?;
+        let to = store
+            .add(NodeWallet::new(Multiaddr::empty(), "cold".into())?, &transaction)
+            .await?;
+        transaction.commit().await?;
+        from.data().set_balance(100, &client).await?;
+
+        let (from, to) = store
+            .transfer(from.public_key_hex(), to.public_key_hex(), 30, &mut client)
+            .await?;
+        assert_eq!(from.balance(), 70);
+        assert_eq!(to.balance(), 30);
+        assert_eq!(store.get(to.public_key_hex(), &client).await?.balance(), 30);
+        assert!(store
+            .transfer(from.public_key_hex(), to.public_key_hex(), 71, &mut client)
+            .await
+            .is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,243 @@
+//! Signers of consensus messages and state batches exported to peers
+//!
+//! By default node signs with [NodeIdentity] loaded from `identity_path`, so the secret key lives on disk.
+//! Production deployments can keep the key in an HSM instead: with `kind = "external"` every signature is
+//! requested from a signing bridge, which talks to the HSM via PKCS#11, gRPC or whatever it supports:
+//! ```toml
+//! [validator.signer]
+//! kind = "external"
+//! url = "http://127.0.0.1:7000/sign"
+//! public_key = "<hex public key of the HSM key>"
+//! ```
+//! Bridge receives `POST {"public_key": "<hex>", "message": "<hex>"}` and responds with
+//! `{"signature": "<hex>"}`, the same Schnorr scheme as [NodeIdentity::sign]. Signatures are verified
+//! against `public_key` before use, [NodeID] of the node is derived from `public_key` as well.
+
+use crate::{
+    identity::{IdentityError, NodeIdentity},
+    types::NodeID,
+};
+use actix_web::client::Client as HttpClient;
+use futures::future::{self, FutureExt, LocalBoxFuture};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{fmt, time::Duration};
+use tari_core::transactions::{
+    crypto::tari_utilities::hex::{to_hex, Hex},
+    types::PublicKey,
+};
+
+/// Source of node signatures, see [module docs](self)
+pub trait Signer: fmt::Debug + Send + Sync {
+    fn public_key(&self) -> &PublicKey;
+
+    fn node_id(&self) -> NodeID;
+
+    /// Signature of the message verifiable with [NodeIdentity::verify]
+    fn sign<'a>(&'a self, message: &'a [u8]) -> LocalBoxFuture<'a, Result<String, IdentityError>>;
+}
+
+/// Local keys, signs with the secret key loaded from disk
+impl Signer for NodeIdentity {
+    fn public_key(&self) -> &PublicKey {
+        NodeIdentity::public_key(self)
+    }
+
+    fn node_id(&self) -> NodeID {
+        NodeIdentity::node_id(self)
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> LocalBoxFuture<'a, Result<String, IdentityError>> {
+        future::ready(NodeIdentity::sign(self, message)).boxed_local()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerKind {
+    /// Node identity keys from `identity_path`
+    Local,
+    /// Signing bridge in front of an HSM at `url`
+    External,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SignerConfig {
+    pub kind: SignerKind,
+    /// Signing endpoint of the external signer bridge
+    pub url: Option<String>,
+    /// Hex public key of the key held by the external signer
+    pub public_key: Option<String>,
+    /// External signer request timeout in seconds
+    pub timeout: u64,
+}
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self {
+            kind: SignerKind::Local,
+            url: None,
+            public_key: None,
+            timeout: 5,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SignRequest<'a> {
+    public_key: &'a str,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+/// Requests signatures from the signing bridge, secret key never leaves the HSM
+#[derive(Clone, Debug)]
+pub struct ExternalSigner {
+    url: String,
+    public_key: PublicKey,
+    node_id: NodeID,
+    timeout: Duration,
+}
+
+impl ExternalSigner {
+    pub fn new(url: &str, public_key: PublicKey, timeout: Duration) -> Self {
+        Self {
+            url: url.to_string(),
+            node_id: NodeIdentity::derive_node_id(&public_key),
+            public_key,
+            timeout,
+        }
+    }
+
+    pub fn from_config(config: &SignerConfig) -> Result<Self, IdentityError> {
+        let url = config
+            .url
+            .as_ref()
+            .ok_or_else(|| IdentityError::InvalidKeys("signer.url is required for external signer".into()))?;
+        let public_key = config
+            .public_key
+            .as_ref()
+            .ok_or_else(|| IdentityError::InvalidKeys("signer.public_key is required for external signer".into()))?;
+        let public_key =
+            PublicKey::from_hex(public_key).map_err(|err| IdentityError::InvalidKeys(format!("{:?}", err)))?;
+        Ok(Self::new(url, public_key, Duration::from_secs(config.timeout.max(1))))
+    }
+
+    async fn request(&self, message: &[u8]) -> Result<String, IdentityError> {
+        // actix client is not Send, while signer is shared by consensus workers and API handlers
+        let http = HttpClient::builder().timeout(self.timeout).finish();
+        let public_key = self.public_key.to_hex();
+        let mut res = http
+            .post(self.url.as_str())
+            .send_json(&SignRequest {
+                public_key: &public_key,
+                message: to_hex(message),
+            })
+            .await
+            .map_err(|err| IdentityError::Signature(format!("external signer {}: {}", self.url, err)))?;
+        if !res.status().is_success() {
+            return Err(IdentityError::Signature(format!(
+                "external signer {} responded with {}",
+                self.url,
+                res.status()
+            )));
+        }
+        let body: SignResponse = res
+            .json()
+            .await
+            .map_err(|err| IdentityError::Signature(format!("external signer {}: {}", self.url, err)))?;
+        if !NodeIdentity::verify(&self.public_key, &body.signature, message) {
+            return Err(IdentityError::Signature(format!(
+                "external signer {} returned signature not matching public key {}",
+                self.url, public_key
+            )));
+        }
+        Ok(body.signature)
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    fn node_id(&self) -> NodeID {
+        self.node_id
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> LocalBoxFuture<'a, Result<String, IdentityError>> {
+        self.request(message).boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::utils::Test;
+    use actix_web::{test, web, App, HttpResponse};
+    use serde_json::{json, Value};
+    use tari_core::transactions::crypto::tari_utilities::hex::from_hex;
+
+    #[actix_rt::test]
+    async fn external_signer() {
+        let hsm = Test::<NodeIdentity>::new();
+        let other = Test::<NodeIdentity>::new();
+        let bridge = hsm.clone();
+        let srv = test::start(move || {
+            let hsm = bridge.clone();
+            let other = other.clone();
+            App::new()
+                .route(
+                    "/sign",
+                    web::post().to(move |body: web::Json<Value>| {
+                        assert_eq!(body["public_key"], hsm.public_key_hex());
+                        let message = from_hex(body["message"].as_str().unwrap()).unwrap();
+                        let signature = hsm.sign(&message).unwrap();
+                        async move { HttpResponse::Ok().json(json!({ "signature": signature })) }
+                    }),
+                )
+                .route(
+                    "/other",
+                    web::post().to(move |body: web::Json<Value>| {
+                        let message = from_hex(body["message"].as_str().unwrap()).unwrap();
+                        let signature = other.sign(&message).unwrap();
+                        async move { HttpResponse::Ok().json(json!({ "signature": signature })) }
+                    }),
+                )
+        });
+
+        let config = SignerConfig {
+            kind: SignerKind::External,
+            url: Some(srv.url("/sign")),
+            public_key: Some(hsm.public_key_hex()),
+            ..SignerConfig::default()
+        };
+        let signer = ExternalSigner::from_config(&config).unwrap();
+        assert_eq!(Signer::node_id(&signer), hsm.node_id());
+        let signature = Signer::sign(&signer, b"message").await.unwrap();
+        assert!(NodeIdentity::verify(hsm.public_key(), &signature, b"message"));
+
+        let signer = ExternalSigner::new(&srv.url("/other"), hsm.public_key().clone(), Duration::from_secs(1));
+        assert!(Signer::sign(&signer, b"message").await.is_err());
+        let signer = ExternalSigner::new(&srv.url("/missing"), hsm.public_key().clone(), Duration::from_secs(1));
+        assert!(Signer::sign(&signer, b"message").await.is_err());
+
+        let config = SignerConfig {
+            url: None,
+            ..config
+        };
+        assert!(ExternalSigner::from_config(&config).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn local_signer() {
+        let identity = Test::<NodeIdentity>::new();
+        let signer: &dyn Signer = &identity;
+        let signature = signer.sign(b"message").await.unwrap();
+        assert!(NodeIdentity::verify(signer.public_key(), &signature, b"message"));
+        assert_eq!(signer.node_id(), identity.node_id());
+    }
+}
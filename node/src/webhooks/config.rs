@@ -0,0 +1,76 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Backoff between delivery attempts is never longer than an hour
+const MAX_BACKOFF_SECS: u64 = 3600;
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WebhooksConfig {
+    /// Deliveries are still logged when dispatcher is disabled and will be sent once it is enabled
+    pub enabled: bool,
+    /// Period between polls of pending deliveries in seconds
+    pub period: u64,
+    /// Max number of deliveries sent per poll
+    pub batch_size: i64,
+    /// Delivery is marked Failed after this number of attempts
+    pub max_attempts: u32,
+    /// Delay before the first retry in seconds, doubled on every next attempt
+    pub backoff: u64,
+    /// Webhook request timeout in seconds
+    pub timeout: u64,
+}
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            period: 5,
+            batch_size: 100,
+            max_attempts: 10,
+            backoff: 10,
+            timeout: 10,
+        }
+    }
+}
+
+impl WebhooksConfig {
+    /// Time of the next attempt after `attempts` failed ones, None if attempts are exhausted
+    pub fn retry_at(&self, attempts: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        let delay = self
+            .backoff
+            .saturating_mul(1 << attempts.saturating_sub(1).min(32))
+            .min(MAX_BACKOFF_SECS);
+        Some(now + ChronoDuration::seconds(delay as i64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let config = WebhooksConfig {
+            max_attempts: 4,
+            backoff: 10,
+            ..WebhooksConfig::default()
+        };
+        let now = Utc::now();
+        assert_eq!(config.retry_at(1, now), Some(now + ChronoDuration::seconds(10)));
+        assert_eq!(config.retry_at(2, now), Some(now + ChronoDuration::seconds(20)));
+        assert_eq!(config.retry_at(3, now), Some(now + ChronoDuration::seconds(40)));
+        assert_eq!(config.retry_at(4, now), None);
+
+        let config = WebhooksConfig {
+            max_attempts: 100,
+            ..WebhooksConfig::default()
+        };
+        assert_eq!(
+            config.retry_at(50, now),
+            Some(now + ChronoDuration::seconds(MAX_BACKOFF_SECS as i64))
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use super::{sign, WebhooksConfig, LOG_TARGET};
+use crate::db::{
+    models::{Webhook, WebhookDelivery},
+    utils::errors::DBError,
+};
+use actix::prelude::*;
+use actix_web::client::Client as HttpClient;
+use chrono::Utc;
+use deadpool_postgres::{Client, Pool};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Actor sending pending webhook deliveries every `period` seconds, stops immediately if dispatcher is disabled
+pub struct WebhookDispatcher {
+    config: WebhooksConfig,
+    pool: Arc<Pool>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: WebhooksConfig, pool: Arc<Pool>) -> Self {
+        Self { config, pool }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let config = self.config.clone();
+        let pool = self.pool.clone();
+        // waiting for the batch before the next tick, so the same delivery is never sent concurrently
+        ctx.wait(
+            async move {
+                let res = match pool.get().await {
+                    Ok(client) => dispatch(&config, &client).await,
+                    Err(err) => Err(err.into()),
+                };
+                match res {
+                    Ok(0) => {},
+                    Ok(count) => log::debug!(target: LOG_TARGET, "Dispatched {} webhook deliveries", count),
+                    Err(err) => log::error!(target: LOG_TARGET, "Failed to dispatch webhook deliveries: {}", err),
+                }
+            }
+            .into_actor(self),
+        );
+    }
+}
+
+/// Sends deliveries due for the next attempt, returns number of attempted deliveries
+pub async fn dispatch(config: &WebhooksConfig, client: &Client) -> Result<usize, DBError> {
+    let deliveries = WebhookDelivery::find_due(config.batch_size, client).await?;
+    let http = HttpClient::builder()
+        .timeout(Duration::from_secs(config.timeout.max(1)))
+        .finish();
+    let mut webhooks: HashMap<uuid::Uuid, Webhook> = HashMap::new();
+    for delivery in deliveries.iter() {
+        if !webhooks.contains_key(&delivery.webhook_id) {
+            webhooks.insert(delivery.webhook_id, Webhook::load(delivery.webhook_id, client).await?);
+        }
+        let webhook = &webhooks[&delivery.webhook_id];
+        match send(&http, webhook, delivery).await {
+            Ok(()) => {
+                delivery.mark_delivered(client).await?;
+            },
+            Err(error) => {
+                let attempts = delivery.attempts as u32 + 1;
+                let retry_at = config.retry_at(attempts, Utc::now());
+                log::warn!(
+                    target: LOG_TARGET,
+                    "Webhook {} delivery {} attempt {} failed: {}{}",
+                    webhook.url,
+                    delivery.id,
+                    attempts,
+                    error,
+                    if retry_at.is_none() { ", giving up" } else { "" }
+                );
+                delivery.mark_attempt_failed(&error, retry_at, client).await?;
+            },
+        }
+    }
+    Ok(deliveries.len())
+}
+
+async fn send(http: &HttpClient, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<(), String> {
+    let body = delivery.payload.to_string();
+    let res = http
+        .post(webhook.url.as_str())
+        .content_type("application/json")
+        .header("X-Tari-Event", delivery.event.as_str())
+        .header("X-Tari-Delivery", delivery.id.to_string())
+        .header(
+            "X-Tari-Signature",
+            format!("sha256={}", sign(&webhook.secret, body.as_bytes())),
+        )
+        .send_body(body)
+        .await
+        .map_err(|err| err.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("webhook responded with {}", res.status()));
+    }
+    Ok(())
+}
+
+impl Actor for WebhookDispatcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.config.enabled {
+            ctx.stop();
+            return;
+        }
+        log::info!(
+            target: LOG_TARGET,
+            "Webhooks enabled, dispatching deliveries every {}s",
+            self.config.period
+        );
+        ctx.run_interval(Duration::from_secs(self.config.period.max(1)), Self::tick);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{NewWebhook, WebhookDeliveryStatus},
+        test::utils::{builders::AssetStateBuilder, test_db_client},
+    };
+    use actix_web::{test, web, App, HttpRequest, HttpResponse};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[actix_rt::test]
+    async fn deliver_and_retry() {
+        let (client, _lock) = test_db_client().await;
+        let received = Arc::new(Mutex::new(vec![]));
+        let server_received = received.clone();
+        let srv = test::start(move || {
+            let received = server_received.clone();
+            App::new()
+                .route(
+                    "/ok",
+                    web::post().to(move |req: HttpRequest, body: String| {
+                        let signature = req.headers()["X-Tari-Signature"].to_str().unwrap().to_string();
+                        received.lock().unwrap().push((signature, body));
+                        async { HttpResponse::Ok().finish() }
+                    }),
+                )
+                .route("/fail", web::post().to(|| async { HttpResponse::InternalServerError().finish() }))
+        });
+
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        for (path, secret) in &[("/ok", "secret"), ("/fail", "other")] {
+            Webhook::insert(
+                NewWebhook {
+                    asset_id: asset.asset_id.clone(),
+                    url: srv.url(path),
+                    secret: secret.to_string(),
+                },
+                &client,
+            )
+            .await
+            .unwrap();
+        }
+        let payload = json!({"event": "proposal.finalized"});
+        WebhookDelivery::enqueue(&asset.asset_id, "proposal.finalized", &payload, &client)
+            .await
+            .unwrap();
+
+        let config = WebhooksConfig {
+            max_attempts: 1,
+            ..WebhooksConfig::default()
+        };
+        assert_eq!(dispatch(&config, &client).await.unwrap(), 2);
+        assert_eq!(dispatch(&config, &client).await.unwrap(), 0);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (signature, body) = &received[0];
+        assert_eq!(signature, &format!("sha256={}", sign("secret", body.as_bytes())));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(body).unwrap(), payload);
+
+        for webhook in Webhook::find_by_asset_id(&asset.asset_id, &client).await.unwrap() {
+            let deliveries = WebhookDelivery::find_by_webhook_id(webhook.id, 10, &client).await.unwrap();
+            let expected = if webhook.url.ends_with("/ok") {
+                WebhookDeliveryStatus::Delivered
+            } else {
+                WebhookDeliveryStatus::Failed
+            };
+            assert_eq!(deliveries[0].status, expected);
+            assert_eq!(deliveries[0].attempts, 1);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    db::{
+        models::{
+            consensus::{Instruction, Proposal},
+            InstructionStatus,
+            Webhook,
+            WebhookDelivery,
+        },
+        utils::errors::DBError,
+    },
+    types::{AssetID, InstructionID, ProposalID},
+};
+use deadpool_postgres::Client;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tari_core::transactions::crypto::tari_utilities::hex::to_hex;
+
+/// Payload POSTed to webhooks, `event` field holds event name
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event")]
+pub enum WebhookEvent {
+    #[serde(rename = "instruction.commit")]
+    InstructionCommit { instruction: Instruction },
+    #[serde(rename = "instruction.invalid")]
+    InstructionInvalid { instruction: Instruction },
+    #[serde(rename = "proposal.finalized")]
+    ProposalFinalized {
+        proposal_id: ProposalID,
+        asset_id: AssetID,
+        instructions: Vec<InstructionID>,
+        invalid_instructions: Vec<InstructionID>,
+    },
+}
+
+impl WebhookEvent {
+    /// Event for instruction which reached final status, None for other statuses
+    pub fn instruction(instruction: Instruction) -> Option<Self> {
+        match instruction.status {
+            InstructionStatus::Commit => Some(Self::InstructionCommit { instruction }),
+            InstructionStatus::Invalid => Some(Self::InstructionInvalid { instruction }),
+            _ => None,
+        }
+    }
+
+    pub fn proposal_finalized(proposal: &Proposal) -> Self {
+        let ids = |ids: &[uuid::Uuid]| ids.iter().map(|id| InstructionID(*id)).collect();
+        Self::ProposalFinalized {
+            proposal_id: proposal.id,
+            asset_id: proposal.asset_id.clone(),
+            instructions: ids(&proposal.new_view.instruction_set),
+            invalid_instructions: ids(&proposal.new_view.invalid_instruction_set),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::InstructionCommit { .. } => "instruction.commit",
+            Self::InstructionInvalid { .. } => "instruction.invalid",
+            Self::ProposalFinalized { .. } => "proposal.finalized",
+        }
+    }
+
+    pub fn asset_id(&self) -> &AssetID {
+        match self {
+            Self::InstructionCommit { instruction } | Self::InstructionInvalid { instruction } => &instruction.asset_id,
+            Self::ProposalFinalized { asset_id, .. } => asset_id,
+        }
+    }
+}
+
+/// Enqueues event delivery to every webhook of the asset
+pub async fn notify(event: WebhookEvent, client: &Client) -> Result<u64, DBError> {
+    let payload = serde_json::to_value(&event).map_err(|err| DBError::bad_query(&err.to_string()))?;
+    WebhookDelivery::enqueue(event.asset_id(), event.name(), &payload, client).await
+}
+
+/// Enqueues events of instructions which reached Commit or Invalid status,
+/// instructions are loaded only if the asset has webhooks registered
+pub async fn notify_instructions(
+    asset_id: &AssetID,
+    instruction_ids: &[InstructionID],
+    client: &Client,
+) -> Result<(), DBError>
+{
+    if instruction_ids.is_empty() || Webhook::find_by_asset_id(asset_id, client).await?.is_empty() {
+        return Ok(());
+    }
+    for id in instruction_ids {
+        if let Some(event) = WebhookEvent::instruction(Instruction::load(*id, client).await?) {
+            notify(event, client).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Hex encoded HMAC-SHA256 of the payload keyed by webhook secret
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    // HMAC accepts keys of any size, so it never fails
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.input(body);
+    to_hex(&mac.result().code())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        db::models::{NewWebhook, WebhookDeliveryStatus},
+        test::utils::{
+            builders::{consensus::InstructionBuilder, AssetStateBuilder},
+            test_db_client,
+        },
+    };
+
+    #[test]
+    fn signature() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn notify_final_statuses() {
+        let (client, _lock) = test_db_client().await;
+        let asset = AssetStateBuilder::default().build(&client).await.unwrap();
+        let commit = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            status: InstructionStatus::Commit,
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let pending = InstructionBuilder {
+            asset_id: Some(asset.asset_id.clone()),
+            status: InstructionStatus::Pending,
+            ..InstructionBuilder::default()
+        }
+        .build(&client)
+        .await
+        .unwrap();
+        let ids = [commit.id, pending.id];
+        notify_instructions(&asset.asset_id, &ids, &client).await.unwrap();
+        assert!(WebhookDelivery::find_due(10, &client).await.unwrap().is_empty());
+
+        let webhook = Webhook::insert(
+            NewWebhook {
+                asset_id: asset.asset_id.clone(),
+                url: "http://localhost/hook".into(),
+                secret: "secret".into(),
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        notify_instructions(&asset.asset_id, &ids, &client).await.unwrap();
+        let deliveries = WebhookDelivery::find_due(10, &client).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].webhook_id, webhook.id);
+        assert_eq!(deliveries[0].event, "instruction.commit");
+        assert_eq!(deliveries[0].status, WebhookDeliveryStatus::Pending);
+        assert_eq!(deliveries[0].payload["event"], "instruction.commit");
+        assert_eq!(
+            deliveries[0].payload["instruction"]["id"],
+            serde_json::to_value(commit.id).unwrap()
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Webhook notifications about asset instructions and proposals
+//!
+//! Node operator registers webhooks per asset (`tvnc asset webhook-add`), every registered webhook is notified
+//! when asset instruction reaches Commit or Invalid status and when asset proposal is finalized, so marketplaces
+//! can react to token sales without polling the node.
+//!
+//! Events are enqueued to `webhook_deliveries` log within the same DB transaction as the state change
+//! and are POSTed as JSON by [WebhookDispatcher], see [WebhookEvent] for payloads. Every request carries headers:
+//! - `X-Tari-Event`: event name, e.g. `instruction.commit`
+//! - `X-Tari-Delivery`: delivery id, repeated on retries so receivers can deduplicate
+//! - `X-Tari-Signature`: `sha256=<hex HMAC-SHA256 of the body keyed by webhook secret>`
+//!
+//! Failed deliveries are retried with exponential backoff until `max_attempts` is reached.
+//! Dispatcher is configured in `[validator.webhooks]` section of config or via WEBHOOKS_* env vars.
+
+mod config;
+pub use config::WebhooksConfig;
+
+mod dispatcher;
+pub use dispatcher::WebhookDispatcher;
+
+mod events;
+pub use events::{notify, notify_instructions, sign, WebhookEvent};
+
+pub const LOG_TARGET: &'static str = "tari_validator_node::webhooks";
@@ -1,25 +1,44 @@
 pub mod sell_token_actix {
     use super::*;
     use crate::{
-        api::errors::{ApiError, ApplicationError},
+        api::errors::ApiError,
         db::models::consensus::instructions::*,
-        template::{actors::*, context::*},
+        template::{actors::*, context::*, ContractParams},
     };
-    use actix_web::web;
+    use crate::api::{
+        errors::ApplicationError,
+        models::{RequestSignature, SignedCall},
+    };
+    use actix_web::{web, HttpRequest};
     impl From<SellTokenParams> for TokenContracts {
         fn from(params: SellTokenParams) -> Self {
             TokenContracts::SellToken(params)
         }
     }
+    #[tracing::instrument(skip(req, params, data, context), fields(contract = "sell_token"))]
     pub async fn web_handler(
+        req: HttpRequest,
         params: web::Path<TokenCallParams>,
-        data: web::Json<SellTokenParams>,
+        data: web::Json<serde_json::Value>,
         context: web::Data<TemplateContext<SingleUseTokenTemplate>>,
     ) -> Result<web::Json<Instruction>, ApiError>
     {
         let asset_id = params.asset_id(context.template_id())?;
         let token_id = params.token_id(context.template_id())?;
-        let data = data.into_inner();
+        let mut data = data.into_inner();
+        let signature = RequestSignature::verify(&req, &SignedCall::token(&token_id, "sell_token"), &data)?;
+        let depends_on = take_depends_on(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Dependencies error: {}", err).as_str()))?;
+        let nonce = take_nonce(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Nonce error: {}", err).as_str()))?;
+        let priority = take_priority(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Priority error: {}", err).as_str()))?;
+        context
+            .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, Some(&token_id), "sell_token")
+            .await?;
+        let data: SellTokenParams = serde_json::from_value(data)
+            .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?;
+        ContractParams::validate(&data)?;
         let instruction = NewInstruction {
             asset_id: asset_id.clone(),
             token_id: Some(token_id.clone()),
@@ -28,6 +47,11 @@ pub mod sell_token_actix {
                 .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?,
             contract_name: "sell_token".into(),
             status: InstructionStatus::Scheduled,
+            signature: signature.signature,
+            depends_on,
+            signer_pubkey: Some(signature.pubkey.clone()),
+            nonce,
+            priority,
             ..NewInstruction::default()
         };
         let instruction = context.create_instruction(instruction).await?;
@@ -47,25 +71,44 @@ pub mod sell_token_actix {
 pub mod sell_token_lock_actix {
     use super::*;
     use crate::{
-        api::errors::{ApiError, ApplicationError},
+        api::errors::ApiError,
         db::models::consensus::instructions::*,
-        template::{actors::*, context::*},
+        template::{actors::*, context::*, ContractParams},
     };
-    use actix_web::web;
+    use crate::api::{
+        errors::ApplicationError,
+        models::{RequestSignature, SignedCall},
+    };
+    use actix_web::{web, HttpRequest};
     impl From<SellTokenLockParams> for TokenContracts {
         fn from(params: SellTokenLockParams) -> Self {
             TokenContracts::SellTokenLock(params)
         }
     }
+    #[tracing::instrument(skip(req, params, data, context), fields(contract = "sell_token_lock"))]
     pub async fn web_handler(
+        req: HttpRequest,
         params: web::Path<TokenCallParams>,
-        data: web::Json<SellTokenLockParams>,
+        data: web::Json<serde_json::Value>,
         context: web::Data<TemplateContext<SingleUseTokenTemplate>>,
     ) -> Result<web::Json<Instruction>, ApiError>
     {
         let asset_id = params.asset_id(context.template_id())?;
         let token_id = params.token_id(context.template_id())?;
-        let data = data.into_inner();
+        let mut data = data.into_inner();
+        let signature = RequestSignature::verify(&req, &SignedCall::token(&token_id, "sell_token_lock"), &data)?;
+        let depends_on = take_depends_on(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Dependencies error: {}", err).as_str()))?;
+        let nonce = take_nonce(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Nonce error: {}", err).as_str()))?;
+        let priority = take_priority(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Priority error: {}", err).as_str()))?;
+        context
+            .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, Some(&token_id), "sell_token_lock")
+            .await?;
+        let data: SellTokenLockParams = serde_json::from_value(data)
+            .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?;
+        ContractParams::validate(&data)?;
         let instruction = NewInstruction {
             asset_id: asset_id.clone(),
             token_id: Some(token_id.clone()),
@@ -74,6 +117,11 @@ pub mod sell_token_lock_actix {
                 .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?,
             contract_name: "sell_token_lock".into(),
             status: InstructionStatus::Scheduled,
+            signature: signature.signature,
+            depends_on,
+            signer_pubkey: Some(signature.pubkey.clone()),
+            nonce,
+            priority,
             ..NewInstruction::default()
         };
         let instruction = context.create_instruction(instruction).await?;
@@ -93,25 +141,44 @@ pub mod sell_token_lock_actix {
 pub mod transfer_token_actix {
     use super::*;
     use crate::{
-        api::errors::{ApiError, ApplicationError},
+        api::errors::ApiError,
         db::models::consensus::instructions::*,
-        template::{actors::*, context::*},
+        template::{actors::*, context::*, ContractParams},
     };
-    use actix_web::web;
+    use crate::api::{
+        errors::ApplicationError,
+        models::{RequestSignature, SignedCall},
+    };
+    use actix_web::{web, HttpRequest};
     impl From<TransferTokenParams> for TokenContracts {
         fn from(params: TransferTokenParams) -> Self {
             TokenContracts::TransferToken(params)
         }
     }
+    #[tracing::instrument(skip(req, params, data, context), fields(contract = "transfer_token"))]
     pub async fn web_handler(
+        req: HttpRequest,
         params: web::Path<TokenCallParams>,
-        data: web::Json<TransferTokenParams>,
+        data: web::Json<serde_json::Value>,
         context: web::Data<TemplateContext<SingleUseTokenTemplate>>,
     ) -> Result<web::Json<Instruction>, ApiError>
     {
         let asset_id = params.asset_id(context.template_id())?;
         let token_id = params.token_id(context.template_id())?;
-        let data = data.into_inner();
+        let mut data = data.into_inner();
+        let signature = RequestSignature::verify(&req, &SignedCall::token(&token_id, "transfer_token"), &data)?;
+        let depends_on = take_depends_on(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Dependencies error: {}", err).as_str()))?;
+        let nonce = take_nonce(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Nonce error: {}", err).as_str()))?;
+        let priority = take_priority(&mut data)
+            .map_err(|err| ApplicationError::bad_request(format!("Priority error: {}", err).as_str()))?;
+        context
+            .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, Some(&token_id), "transfer_token")
+            .await?;
+        let data: TransferTokenParams = serde_json::from_value(data)
+            .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?;
+        ContractParams::validate(&data)?;
         let instruction = NewInstruction {
             asset_id: asset_id.clone(),
             token_id: Some(token_id.clone()),
@@ -120,6 +187,11 @@ pub mod transfer_token_actix {
                 .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?,
             contract_name: "transfer_token".into(),
             status: InstructionStatus::Scheduled,
+            signature: signature.signature,
+            depends_on,
+            signer_pubkey: Some(signature.pubkey.clone()),
+            nonce,
+            priority,
             ..NewInstruction::default()
         };
         let instruction = context.create_instruction(instruction).await?;
@@ -141,7 +213,7 @@ pub mod tokencontracts_impl {
     use crate::{
         api::errors::ApiError,
         db::models::consensus::instructions::*,
-        template::{actors::*, context::*},
+        template::{actors::*, context::*, ContractRoute},
         types::{TemplateID, TokenID},
     };
     use actix::prelude::*;
@@ -153,6 +225,26 @@ pub mod tokencontracts_impl {
             scope.service(web::resource("/sell_token_lock").route(web::post().to(sell_token_lock_actix::web_handler)));
             scope.service(web::resource("/transfer_token").route(web::post().to(transfer_token_actix::web_handler)));
         }
+
+        fn routes_catalog() -> Vec<ContractRoute> {
+            vec![
+                ContractRoute {
+                    name: "sell_token",
+                    params_schema: |gen| gen.subschema_for::<SellTokenParams>(),
+                    view: false,
+                },
+                ContractRoute {
+                    name: "sell_token_lock",
+                    params_schema: |gen| gen.subschema_for::<SellTokenLockParams>(),
+                    view: false,
+                },
+                ContractRoute {
+                    name: "transfer_token",
+                    params_schema: |gen| gen.subschema_for::<TransferTokenParams>(),
+                    view: false,
+                },
+            ]
+        }
     }
     impl TokenContracts {
         pub async fn call(
@@ -162,15 +254,15 @@ pub mod tokencontracts_impl {
         {
             let value = match self {
                 TokenContracts::SellToken(params) => {
-                    let result = Self::sell_token(&mut context, params).await?;
+                    let result = TokenContracts::sell_token(&mut context, params).await?;
                     serde_json::to_value(result).map_err(|err| TemplateError::Processing(err.to_string()))?
                 },
                 TokenContracts::SellTokenLock(params) => {
-                    let result = Self::sell_token_lock(&mut context, params).await?;
+                    let result = TokenContracts::sell_token_lock(&mut context, params).await?;
                     serde_json::to_value(result).map_err(|err| TemplateError::Processing(err.to_string()))?
                 },
                 TokenContracts::TransferToken(params) => {
-                    let result = Self::transfer_token(&mut context, params).await?;
+                    let result = TokenContracts::transfer_token(&mut context, params).await?;
                     serde_json::to_value(result).map_err(|err| TemplateError::Processing(err.to_string()))?
                 },
             };
@@ -182,6 +274,7 @@ pub mod tokencontracts_impl {
                 params: self,
                 id: instruction.token_id.clone().unwrap(),
                 instruction,
+                span: tracing::Span::current(),
             }
         }
     }
@@ -192,6 +285,7 @@ pub mod tokencontracts_impl {
         id: TokenID,
         params: TokenContracts,
         instruction: Instruction,
+        span: tracing::Span,
     }
     impl ContractCallMsg for Msg {
         type Context = TokenInstructionContext<Self::Template>;
@@ -212,5 +306,9 @@ pub mod tokencontracts_impl {
         fn init_context(self, ctx: TemplateContext<Self::Template>) -> Self::ContextFuture {
             TokenInstructionContext::init(ctx, self.instruction, self.id)
         }
+
+        fn span(&self) -> tracing::Span {
+            self.span.clone()
+        }
     }
 }
@@ -1,37 +1,83 @@
 use super::*;
-use quote::{format_ident, quote};
+use darling::ast::Style;
+use quote::{format_ident, quote, quote_spanned};
 use syn::Type;
 
 pub(crate) struct ContractImpl {
     pub method: syn::Ident,
-    pub variant_ident: Type,
+    pub variant: syn::Ident,
+    /// Pattern matching the variant, binds `params` for tuple variant or every field for struct variant
+    pub variant_pattern: proc_macro2::TokenStream,
+    /// Params built from bindings of `variant_pattern`
+    pub params_expr: proc_macro2::TokenStream,
+    /// Owned params deserialized from request
     pub params: Type,
     pub tokens: proc_macro2::TokenStream,
-    pub web_handler: Type,
+    pub web_handler: proc_macro2::TokenStream,
+    pub view: bool,
+    /// Span of the variant, errors in generated contract calls point to it
+    pub span: proc_macro2::Span,
 }
 
 impl ContractImpl {
     pub(crate) fn generate(variant: &ContractsVariant, opts: &ContractsOpt) -> Self {
-        let method = format_ident!("{}", variant.method.as_ref().unwrap());
-        let template: Type = syn::parse_str(opts.template.as_str()).unwrap();
+        let method = format_ident!("{}", variant.method.as_ref().unwrap().as_str());
+        let view = variant.view;
+        let template = opts.template_type();
         let mod_name = format_ident!("{}_actix", method);
-        let web_handler: Type = syn::parse_str(format!("{}::web_handler", mod_name).as_str()).unwrap();
-        let params = variant.fields.fields.get(0).unwrap().ty.clone();
-        let variant_ident = syn::parse_str(format!("{}::{}", opts.ident, variant.ident).as_str()).unwrap();
-
-        let web = generate_web_body(&method, &template, &params, &opts.ident);
-        let from_impl = generate_from_params(&params, &variant_ident, &opts.ident);
+        let web_handler = quote! { #mod_name::web_handler };
+        let VariantParams {
+            params,
+            params_struct,
+            variant_pattern,
+            params_expr,
+            contract_arg,
+            from_impl,
+        } = VariantParams::generate(variant, opts);
+        let variant = variant.ident.clone();
 
+        let (web, imports) = if view {
+            (
+                generate_view_body(
+                    &method,
+                    &template,
+                    &params,
+                    &contract_arg,
+                    &opts.ident,
+                    opts.token,
+                    variant.span(),
+                ),
+                quote! { use actix_web::web; },
+            )
+        } else {
+            (
+                generate_web_body(&method, &template, &params, &opts.owned_ident(), opts.token),
+                quote! {
+                    use crate::{
+                        api::{
+                            errors::ApplicationError,
+                            middleware::RequestAuthenticationContext,
+                            models::{RequestSignature, SignedCall},
+                        },
+                        db::models::NewAuditLog,
+                        template::{DryRunQuery, DryRunResult},
+                    };
+                    use actix_web::{web, HttpRequest, HttpResponse};
+                },
+            )
+        };
         let tokens = quote! {
+            #params_struct
+
             pub mod #mod_name {
                 use super::*;
                 // TODO: fix this to let using in outer crates
                 use crate::{
-                    api::errors::{ApiError, ApplicationError},
+                    api::errors::ApiError,
                     db::models::consensus::instructions::*,
-                    template::{context::*, actors::*},
+                    template::{context::*, actors::*, ContractParams},
                 };
-                use actix_web::web;
+                #imports
 
                 #from_impl
 
@@ -43,8 +89,12 @@ impl ContractImpl {
             web_handler,
             tokens,
             method,
+            span: variant.span(),
+            variant,
             params,
-            variant_ident,
+            variant_pattern,
+            params_expr,
+            view,
         }
     }
 }
@@ -54,31 +104,98 @@ fn generate_web_body(
     template: &Type,
     params: &Type,
     contracts: &syn::Ident,
+    token: bool,
 ) -> proc_macro2::TokenStream
 {
     let fn_name_string = format!("{}", fn_name);
+    // asset contracts are called on asset path and create instructions without token
+    let (call_params, token_id, contract_token_id, instruction_token_id, signed_call, dry_run_context) = if token {
+        (
+            quote! { TokenCallParams },
+            quote! { let token_id = params.token_id(context.template_id())?; },
+            quote! { Some(&token_id) },
+            quote! { Some(token_id.clone()) },
+            quote! { SignedCall::token(&token_id, #fn_name_string) },
+            quote! {
+                TokenInstructionContext::dry_run(context.get_ref().clone(), token_id, #fn_name_string, contract_params)
+            },
+        )
+    } else {
+        (
+            quote! { AssetCallParams },
+            quote! {},
+            quote! { None },
+            quote! { None },
+            quote! { SignedCall::asset(&asset_id, #fn_name_string) },
+            quote! {
+                AssetInstructionContext::dry_run(context.get_ref().clone(), asset_id, #fn_name_string, contract_params)
+            },
+        )
+    };
     quote! {
+        #[tracing::instrument(skip(req, params, query, body, context), fields(contract = #fn_name_string))]
         pub async fn web_handler (
-            params: web::Path<TokenCallParams>,
-            data: web::Json<#params>,
+            req: HttpRequest,
+            params: web::Path<#call_params>,
+            query: web::Query<DryRunQuery>,
+            body: web::Payload,
             context: web::Data<TemplateContext<#template>>,
-        ) -> Result<web::Json<Instruction>, ApiError> {
+        ) -> Result<HttpResponse, ApiError> {
             // extract and transform parameters
             let asset_id = params.asset_id(context.template_id())?;
-            let token_id = params.token_id(context.template_id())?;
-            let data: #contracts = data.into_inner().into();
+            #token_id
+            // body is limited before it's parsed, per contract limit is applied
+            let mut data = context.payload_config(#fn_name_string).read_json(body).await?;
+            // verify params are signed by the caller for this call
+            let signature = RequestSignature::verify(&req, &#signed_call, &data)?;
+            // dependencies are signed along with params, but aren't passed to the contract
+            let depends_on = take_depends_on(&mut data)
+                .map_err(|err| ApplicationError::bad_request(format!("Dependencies error: {}", err).as_str()))?;
+            let nonce = take_nonce(&mut data)
+                .map_err(|err| ApplicationError::bad_request(format!("Nonce error: {}", err).as_str()))?;
+            let priority = take_priority(&mut data)
+                .map_err(|err| ApplicationError::bad_request(format!("Priority error: {}", err).as_str()))?;
+            // check caller is permitted to call contract
+            context
+                .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, #contract_token_id, #fn_name_string)
+                .await?;
+            context.authorize_priority(&signature.pubkey, priority).await?;
+            let data: #params = serde_json::from_value(data)
+                .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?;
+            // reject invalid params before instruction is created
+            ContractParams::validate(&data)?;
+            let data: #contracts = data.into();
+            let contract_params = serde_json::to_value(&data)
+                .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?;
+            // dry run executes contract right in the request against buffered state in rolled back transaction
+            if query.dry_run {
+                let mut dry_run_context = #dry_run_context.await?;
+                let transaction = dry_run_context.begin_transaction().await?;
+                let (result, dry_run_context) = data.call(dry_run_context).await?;
+                let append_only_state = dry_run_context.append_only_state().unwrap_or_default();
+                transaction.rollback().await?;
+                return Ok(HttpResponse::Ok().json(DryRunResult { result, append_only_state }));
+            }
             // create transaction
             let instruction = NewInstruction {
                 asset_id: asset_id.clone(),
-                token_id: Some(token_id.clone()),
+                token_id: #instruction_token_id,
                 template_id: context.template_id(),
-                params: serde_json::to_value(&data)
-                    .map_err(|err| ApplicationError::bad_request(format!("Contract params error: {}", err).as_str()))?,
+                params: contract_params,
                 contract_name: #fn_name_string .into(),
                 status: InstructionStatus::Scheduled,
+                signature: signature.signature,
+                depends_on,
+                signer_pubkey: Some(signature.pubkey.clone()),
+                nonce,
+                priority,
                 ..NewInstruction::default()
             };
             let instruction = context.create_instruction(instruction).await?;
+            // instruction is not dispatched unless the call is recorded in audit log
+            context
+                .audit(NewAuditLog::contract_call(&instruction, req.access_pubkey()))
+                .await?;
             let message = data.clone().into_message(instruction.clone());
             context
                 .addr()
@@ -90,17 +207,222 @@ fn generate_web_body(
                     name: #fn_name_string .into(),
                 })?;
             // There must be transaction - otherwise we would fail on previous call
-            return Ok(web::Json(instruction));
+            return Ok(HttpResponse::Ok().json(instruction));
         }
     }
 }
 
-fn generate_from_params(params: &Type, variant_ident: &Type, contracts: &syn::Ident) -> proc_macro2::TokenStream {
+/// View contracts are executed right in the request against read methods of the context,
+/// result is returned to the caller without creating an instruction
+fn generate_view_body(
+    fn_name: &syn::Ident,
+    template: &Type,
+    params: &Type,
+    contract_arg: &proc_macro2::TokenStream,
+    contracts: &syn::Ident,
+    token: bool,
+    span: proc_macro2::Span,
+) -> proc_macro2::TokenStream
+{
+    let fn_name_string = format!("{}", fn_name);
+    // wrong signature of view function is reported on the variant
+    let call = quote_spanned! { span => #contracts::#fn_name(&view_context, #contract_arg).await? };
+    let (call_params, token_id, contract_token_id, view_context, view_id) = if token {
+        (
+            quote! { TokenCallParams },
+            quote! { let token_id = params.token_id(context.template_id())?; },
+            quote! { Some(&token_id) },
+            quote! { TokenInstructionContext },
+            quote! { token_id },
+        )
+    } else {
+        (
+            quote! { AssetCallParams },
+            quote! {},
+            quote! { None },
+            quote! { AssetInstructionContext },
+            quote! { asset_id },
+        )
+    };
     quote! {
-        impl From<#params> for #contracts {
-            fn from(params: #params) -> Self {
-                #variant_ident(params)
+        #[tracing::instrument(skip(params, query, context), fields(contract = #fn_name_string))]
+        pub async fn web_handler (
+            params: web::Path<#call_params>,
+            query: web::Query<#params>,
+            context: web::Data<TemplateContext<#template>>,
+        ) -> Result<web::Json<serde_json::Value>, ApiError> {
+            // extract and transform parameters
+            let asset_id = params.asset_id(context.template_id())?;
+            #token_id
+            // views are not signed, so only contracts open to anonymous callers are served
+            context
+                .authorize_contract(None, &asset_id, #contract_token_id, #fn_name_string)
+                .await?;
+            let params = query.into_inner();
+            ContractParams::validate(&params)?;
+            let view_context = #view_context::view(context.get_ref().clone(), #view_id, #fn_name_string).await?;
+            let result = #call;
+            let value = serde_json::to_value(result).map_err(|err| TemplateError::Processing(err.to_string()))?;
+            return Ok(web::Json(value));
+        }
+    }
+}
+
+/// Params of the variant and conversions between params and the variant
+struct VariantParams {
+    params: Type,
+    /// Params struct generated for struct and unit variants
+    params_struct: Option<proc_macro2::TokenStream>,
+    variant_pattern: proc_macro2::TokenStream,
+    params_expr: proc_macro2::TokenStream,
+    /// Contract argument built from `params`
+    contract_arg: proc_macro2::TokenStream,
+    from_impl: proc_macro2::TokenStream,
+}
+
+impl VariantParams {
+    /// Single field of tuple variant is the params type, for struct and unit variants `{Variant}Params` struct
+    /// is generated with the same fields
+    fn generate(variant: &ContractsVariant, opts: &ContractsOpt) -> Self {
+        if opts.borrowed() {
+            return Self::generate_borrowed(variant, opts);
+        }
+        let contracts = &opts.ident;
+        let ident = &variant.ident;
+        if variant.fields.is_tuple() && variant.fields.len() == 1 {
+            let params = variant.fields.fields[0].ty.clone();
+            return Self {
+                from_impl: quote! {
+                    impl From<#params> for #contracts {
+                        fn from(params: #params) -> Self {
+                            #contracts::#ident(params)
+                        }
+                    }
+                },
+                params,
+                params_struct: None,
+                variant_pattern: quote! { #contracts::#ident(params) },
+                params_expr: quote! { params },
+                contract_arg: quote! { params },
+            };
+        }
+        let params_ident = format_ident!("{}Params", ident);
+        let names: Vec<_> = variant.fields.fields.iter().filter_map(|f| f.ident.clone()).collect();
+        let variant_pattern = match variant.fields.style {
+            Style::Struct => quote! { #contracts::#ident { #( #names ),* } },
+            Style::Tuple => quote! { #contracts::#ident() },
+            Style::Unit => quote! { #contracts::#ident },
+        };
+        Self {
+            params: syn::parse_quote! { #params_ident },
+            params_struct: Some(params_struct(&params_ident, variant, opts)),
+            params_expr: quote! { #params_ident { #( #names ),* } },
+            contract_arg: quote! { params },
+            from_impl: quote! {
+                impl From<#params_ident> for #contracts {
+                    fn from(#params_ident { #( #names ),* }: #params_ident) -> Self {
+                        #variant_pattern
+                    }
+                }
+            },
+            variant_pattern,
+        }
+    }
+
+    /// Borrowed params are deserialized into owned params, which are stored in `{Contracts}Owned` tuple variant,
+    /// and borrowed when contract is called. Struct variant with borrowed fields gets `{Variant}Params<'a>`
+    /// passed to the contract and `{Variant}ParamsOwned` deserialized from request
+    fn generate_borrowed(variant: &ContractsVariant, opts: &ContractsOpt) -> Self {
+        let contracts = opts.owned_ident();
+        let ident = &variant.ident;
+        let variant_pattern = quote! { #contracts::#ident(params) };
+        let (params, params_struct, contract_arg) = if variant.fields.is_tuple() && variant.fields.len() == 1 {
+            let ty = &variant.fields.fields[0].ty;
+            let params = owned::owned_type(ty).expect("params types are validated before code generation");
+            (params, None, owned::contract_arg(ty, quote! { params }))
+        } else {
+            let params_ident = format_ident!("{}Params", ident);
+            if variant.fields.fields.iter().all(|f| !owned::is_borrowed(&f.ty)) {
+                let params_struct = params_struct(&params_ident, variant, opts);
+                (syn::parse_quote! { #params_ident }, Some(params_struct), quote! { params })
+            } else {
+                let owned_ident = owned::owned_ident(&params_ident);
+                let schema_name = params_ident.to_string();
+                let vis = &opts.vis;
+                let mut lifetimes = vec![];
+                for field in variant.fields.fields.iter() {
+                    owned::lifetimes(&field.ty, &mut lifetimes);
+                }
+                let fields = variant.fields.fields.iter().map(|f| {
+                    let docs = f.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+                    let name = &f.ident;
+                    let ty = &f.ty;
+                    quote! { #( #docs )* pub #name: #ty }
+                });
+                let owned_fields = variant.fields.fields.iter().map(|f| {
+                    let attrs = &f.attrs;
+                    let name = &f.ident;
+                    let ty = owned::owned_type(&f.ty).expect("params types are validated before code generation");
+                    quote! { #( #attrs )* pub #name: #ty }
+                });
+                let names = variant.fields.fields.iter().map(|f| &f.ident);
+                let args = variant.fields.fields.iter().map(|f| {
+                    let name = &f.ident;
+                    owned::contract_arg(&f.ty, quote! { params.#name })
+                });
+                let params_struct = quote! {
+                    #[derive(Clone, Debug)]
+                    #vis struct #params_ident<#( #lifetimes ),*> {
+                        #( #fields ),*
+                    }
+
+                    #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, schemars::JsonSchema)]
+                    #[serde(rename = #schema_name)]
+                    #vis struct #owned_ident {
+                        #( #owned_fields ),*
+                    }
+                    impl crate::template::ContractParams for #owned_ident {}
+                };
+                let contract_arg = quote! { #params_ident { #( #names: #args ),* } };
+                (syn::parse_quote! { #owned_ident }, Some(params_struct), contract_arg)
             }
+        };
+        Self {
+            from_impl: quote! {
+                impl From<#params> for #contracts {
+                    fn from(params: #params) -> Self {
+                        #variant_pattern
+                    }
+                }
+            },
+            params,
+            params_struct,
+            variant_pattern,
+            params_expr: contract_arg.clone(),
+            contract_arg,
+        }
+    }
+}
+
+/// `{Variant}Params` struct with the same fields as the variant
+fn params_struct(
+    params_ident: &syn::Ident,
+    variant: &ContractsVariant,
+    opts: &ContractsOpt,
+) -> proc_macro2::TokenStream
+{
+    let vis = &opts.vis;
+    let fields = variant.fields.fields.iter().map(|f| {
+        let attrs = &f.attrs;
+        let name = &f.ident;
+        let ty = &f.ty;
+        quote! { #( #attrs )* pub #name: #ty }
+    });
+    quote! {
+        #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, schemars::JsonSchema)]
+        #vis struct #params_ident {
+            #( #fields ),*
         }
+        impl crate::template::ContractParams for #params_ident {}
     }
 }
@@ -1,29 +1,57 @@
 use super::*;
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
 use syn::Type;
 
 pub(crate) fn generate(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> proc_macro2::TokenStream {
     let mod_name = format_ident!("{}_impl", opts.ident.to_string().to_lowercase());
     let actix_routes = generate_actix_routes(contracts, opts);
+    let batch = generate_batch(contracts, opts);
     let contracts_impls = generate_contracts_impls(contracts, opts);
     let actor = generate_actor_msg(opts);
+    let replay = generate_replay(opts);
+    let owned = generate_owned(contracts, opts);
 
     quote! {
+        #owned
+
         pub mod #mod_name {
             use super::*;
             use crate::{
                 api::errors::ApiError,
                 db::models::consensus::instructions::*,
-                template::{context::*, actors::*},
-                types::{TokenID, TemplateID},
+                template::{context::*, actors::*, ContractRoute},
+                types::{AssetID, TokenID, TemplateID},
             };
             use actix::prelude::*;
 
             #actix_routes
 
+            #batch
+
             #contracts_impls
 
             #actor
+
+            #replay
+        }
+    }
+}
+
+/// Owned counterpart of contracts enum with borrowed params, see `crate::owned`
+fn generate_owned(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> proc_macro2::TokenStream {
+    if !opts.borrowed() {
+        return quote! {};
+    }
+    let vis = &opts.vis;
+    let doc = format!("Owned params of [{}], deserialized from request and sent to the actor", opts.ident);
+    let owned = opts.owned_ident();
+    let variants = contracts.iter().map(|c| &c.variant);
+    let params = contracts.iter().map(|c| &c.params);
+    quote! {
+        #[doc = #doc]
+        #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+        #vis enum #owned {
+            #( #variants(#params) ),*
         }
     }
 }
@@ -31,23 +59,175 @@ pub(crate) fn generate(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> pr
 fn generate_actix_routes(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> proc_macro2::TokenStream {
     let entity = if opts.token { "token" } else { "asset" };
     let ident = &opts.ident;
+    let (impl_generics, ty_generics, where_clause) = opts.generics.split_for_impl();
     let urls = contracts.iter().map(|c| format!("/{}", c.method));
     let handlers = contracts.iter().map(|c| c.web_handler.clone());
+    // views are read-only, served via GET
+    let methods: Vec<_> = contracts
+        .iter()
+        .map(|c| if c.view { quote! { web::get() } } else { quote! { web::post() } })
+        .collect();
+    let names = contracts.iter().map(|c| c.method.to_string());
+    let params = contracts.iter().map(|c| c.params.clone());
+    let views = contracts.iter().map(|c| c.view);
+    let batch_route = if has_batch(contracts, opts) {
+        quote! { scope.service(web::resource(BATCH_PATH).route(web::post().to(batch_actix::web_handler))); }
+    } else {
+        quote! {}
+    };
     quote! {
         use actix_web::web;
-        impl Contracts for #ident {
+        impl #impl_generics Contracts for #ident #ty_generics #where_clause {
             fn setup_actix_routes(tpl: TemplateID, scope: &mut web::ServiceConfig) {
                 log::info!("template={}, installing {} APIs", #entity, tpl);
-                #( scope.service(web::resource(#urls).route(web::post().to(#handlers))) );* ;
+                #( scope.service(web::resource(#urls).route(#methods.to(#handlers))) );* ;
+                #batch_route
+            }
+
+            fn routes_catalog() -> Vec<ContractRoute> {
+                vec![
+                    #( ContractRoute {
+                        name: #names,
+                        params_schema: |gen| gen.subschema_for::<#params>(),
+                        view: #views,
+                    } ),*
+                ]
+            }
+        }
+    }
+}
+
+/// Asset contracts are also served in batches: every call of the batch is authorized and validated as by its own
+/// route, instructions are created and recorded in audit log in a single DB transaction and dispatched in order
+fn generate_batch(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> proc_macro2::TokenStream {
+    if !has_batch(contracts, opts) {
+        return quote! {};
+    }
+    let template = opts.template_type();
+    let ident = opts.owned_ident();
+    let calls: Vec<_> = contracts.iter().filter(|c| !c.view).collect();
+    let names = calls.iter().map(|c| c.method.to_string());
+    let params = calls.iter().map(|c| c.params.clone());
+    quote! {
+        /// Path of the batch route, reserved for contract names
+        pub const BATCH_PATH: &'static str = "/batch";
+
+        pub mod batch_actix {
+            use super::*;
+            use crate::{
+                api::{
+                    errors::ApplicationError,
+                    middleware::{authorize_batch_call, RequestAuthenticationContext},
+                    models::{RequestSignature, SignedCall},
+                },
+                template::{BatchCall, ContractParams},
+            };
+            use actix_web::{web, HttpRequest, HttpResponse};
+
+            #[tracing::instrument(skip(req, params, body, context), fields(contract = "batch"))]
+            pub async fn web_handler (
+                req: HttpRequest,
+                params: web::Path<AssetCallParams>,
+                body: web::Payload,
+                context: web::Data<TemplateContext<#template>>,
+            ) -> Result<HttpResponse, ApiError> {
+                let asset_id = params.asset_id(context.template_id())?;
+                let data = context.payload_config("batch").read_json(body).await?;
+                // whole batch is signed at once
+                let signature = RequestSignature::verify(&req, &SignedCall::asset(&asset_id, "batch"), &data)?;
+                let calls: Vec<BatchCall> = serde_json::from_value(data)
+                    .map_err(|err| ApplicationError::bad_request(format!("Batch error: {}", err).as_str()))?;
+                if calls.is_empty() {
+                    return Err(ApplicationError::bad_request("Batch error: no contract calls").into());
+                }
+                let client = context.get_db_client().await?;
+                let mut contracts: Vec<#ident> = Vec::with_capacity(calls.len());
+                let mut instructions = Vec::with_capacity(calls.len());
+                for (index, BatchCall { contract, params: mut data }) in calls.into_iter().enumerate() {
+                    let bad_request = |err: String| {
+                        ApplicationError::bad_request(format!("Batch call {} {}: {}", index, contract, err).as_str())
+                    };
+                    let depends_on = take_depends_on(&mut data)
+                        .map_err(|err| bad_request(format!("Dependencies error: {}", err)))?;
+                    let nonce = take_nonce(&mut data).map_err(|err| bad_request(format!("Nonce error: {}", err)))?;
+                    let priority = take_priority(&mut data)
+                        .map_err(|err| bad_request(format!("Priority error: {}", err)))?;
+                    // check caller and access key are permitted to call contract before its params are parsed
+                    context
+                        .authorize_contract(Some(signature.pubkey.as_str()), &asset_id, None, contract.as_str())
+                        .await?;
+                    authorize_batch_call(&req, context.template_id(), contract.as_str(), &asset_id, &client).await?;
+                    context.authorize_priority(&signature.pubkey, priority).await?;
+                    let data: #ident = match contract.as_str() {
+                        #( #names => {
+                            let data: #params = serde_json::from_value(data)
+                                .map_err(|err| bad_request(format!("Contract params error: {}", err)))?;
+                            ContractParams::validate(&data)?;
+                            data.into()
+                        } ),*
+                        _ => return Err(bad_request("unknown contract".into()).into()),
+                    };
+                    let contract_params = serde_json::to_value(&data)
+                        .map_err(|err| bad_request(format!("Contract params error: {}", err)))?;
+                    instructions.push(NewInstruction {
+                        asset_id: asset_id.clone(),
+                        template_id: context.template_id(),
+                        params: contract_params,
+                        contract_name: contract,
+                        status: InstructionStatus::Scheduled,
+                        signature: signature.signature.clone(),
+                        depends_on,
+                        signer_pubkey: Some(signature.pubkey.clone()),
+                        nonce,
+                        priority,
+                        // calls share the signature, so their content IDs are told apart by the index
+                        batch_index: Some(index as i32),
+                        ..NewInstruction::default()
+                    });
+                    contracts.push(data);
+                }
+                // either all the instructions are created and recorded in audit log or none
+                let instructions = context.create_instructions(instructions, req.access_pubkey()).await?;
+                for (index, (data, instruction)) in contracts.into_iter().zip(instructions.iter()).enumerate() {
+                    let params = serde_json::to_string(&data).unwrap();
+                    if let Err(err) = context.addr().try_send(data.into_message(instruction.clone())) {
+                        let error = TemplateError::ActorSend {
+                            source: err.into(),
+                            params,
+                            name: instruction.contract_name.clone(),
+                        };
+                        // the rest of the batch is never going to be processed
+                        context.instructions_undispatched(&instructions[index..], &error).await?;
+                        return Err(error.into());
+                    }
+                }
+                return Ok(HttpResponse::Ok().json(instructions));
             }
         }
     }
 }
 
+/// Batches are served for asset contracts creating instructions
+fn has_batch(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> bool {
+    !opts.token && contracts.iter().any(|c| !c.view)
+}
+
 fn generate_contracts_impls(contracts: &Vec<ContractImpl>, opts: &ContractsOpt) -> proc_macro2::TokenStream {
-    let template: Type = syn::parse_str(opts.template.as_str()).unwrap();
-    let variants = contracts.iter().map(|c| c.variant_ident.clone());
-    let methods = contracts.iter().map(|c| c.method.clone());
+    let template = opts.template_type();
+    let contracts_ident = &opts.ident;
+    let ident = opts.owned_ident();
+    // wrong signature of contract function is reported on the variant, rather than on the derive
+    let arms = contracts.iter().map(|c| {
+        let pattern = &c.variant_pattern;
+        let params = &c.params_expr;
+        let method = &c.method;
+        quote_spanned! { c.span =>
+            #pattern => {
+                let result = #contracts_ident::#method(&mut context, #params).await?;
+                serde_json::to_value(result).map_err(|err| TemplateError::Processing(err.to_string()))?
+            }
+        }
+    });
     let instruction_context = instruction_context(opts);
     let call_result = call_result(opts);
     let id_gen: syn::Expr = if opts.token {
@@ -56,15 +236,10 @@ fn generate_contracts_impls(contracts: &Vec<ContractImpl>, opts: &ContractsOpt)
         syn::parse_str("instruction.asset_id.clone()").unwrap()
     };
     quote! {
-        impl TokenContracts {
+        impl #ident {
             pub async fn call(self, mut context: #instruction_context<#template>) -> #call_result {
                 let value = match self {
-                    #(
-                        #variants ( params ) => {
-                            let result = Self::#methods(&mut context, params).await?;
-                            serde_json::to_value(result).map_err(|err| TemplateError::Processing(err.to_string()))?
-                        }
-                    ),*
+                    #( #arms ),*
                 };
                 Ok((value, context))
             }
@@ -72,7 +247,8 @@ fn generate_contracts_impls(contracts: &Vec<ContractImpl>, opts: &ContractsOpt)
                 Msg {
                     params: self,
                     id: #id_gen,
-                    instruction
+                    instruction,
+                    span: tracing::Span::current(),
                 }
             }
         }
@@ -80,8 +256,8 @@ fn generate_contracts_impls(contracts: &Vec<ContractImpl>, opts: &ContractsOpt)
 }
 
 fn generate_actor_msg(opts: &ContractsOpt) -> proc_macro2::TokenStream {
-    let template: Type = syn::parse_str(opts.template.as_str()).unwrap();
-    let ident = &opts.ident;
+    let template = opts.template_type();
+    let ident = opts.owned_ident();
     let id_type: Type = if opts.token {
         syn::parse_str("TokenID").unwrap()
     } else {
@@ -97,6 +273,7 @@ fn generate_actor_msg(opts: &ContractsOpt) -> proc_macro2::TokenStream {
             id: #id_type,
             params: #ident,
             instruction: Instruction,
+            span: tracing::Span,
         }
 
         impl ContractCallMsg for Msg {
@@ -118,6 +295,36 @@ fn generate_actor_msg(opts: &ContractsOpt) -> proc_macro2::TokenStream {
             fn init_context(self, ctx: TemplateContext<Self::Template>) -> Self::ContextFuture {
                 #instruction_context::init(ctx, self.instruction, self.id)
             }
+            fn span(&self) -> tracing::Span {
+                self.span.clone()
+            }
+        }
+    }
+}
+
+/// Committed instructions are replayed from their params, see `crate::template::replay`
+fn generate_replay(opts: &ContractsOpt) -> proc_macro2::TokenStream {
+    let template = opts.template_type();
+    let ident = &opts.ident;
+    let owned = opts.owned_ident();
+    let (impl_generics, ty_generics, where_clause) = opts.generics.split_for_impl();
+    let instruction_context = instruction_context(opts);
+    quote! {
+        impl #impl_generics crate::template::ReplayContracts<#template> for #ident #ty_generics #where_clause {
+            fn replay(
+                context: TemplateContext<#template>,
+                instruction: Instruction,
+                client: std::sync::Arc<deadpool_postgres::Client>,
+            ) -> futures::future::LocalBoxFuture<'static, Result<crate::template::DryRunResult, TemplateError>> {
+                Box::pin(async move {
+                    let contract: #owned = serde_json::from_value(instruction.params.clone())
+                        .map_err(|err| TemplateError::Validation(err.into()))?;
+                    let context = #instruction_context::replay(context, instruction, client).await?;
+                    let (result, context) = contract.call(context).await?;
+                    let append_only_state = context.append_only_state().unwrap_or_default();
+                    Ok(crate::template::DryRunResult { result, append_only_state })
+                })
+            }
         }
     }
 }
@@ -132,10 +339,11 @@ fn instruction_context(opts: &ContractsOpt) -> Type {
     }
 }
 
-fn call_result(opts: &ContractsOpt) -> Type {
+fn call_result(opts: &ContractsOpt) -> proc_macro2::TokenStream {
+    let template = opts.template_type();
     if opts.token {
-        syn::parse_str(format!("TokenCallResult<{}>", opts.template).as_str()).unwrap()
+        quote! { TokenCallResult<#template> }
     } else {
-        syn::parse_str(format!("AssetCallResult<{}>", opts.template).as_str()).unwrap()
+        quote! { AssetCallResult<#template> }
     }
 }
@@ -1,32 +1,70 @@
-use darling::{ast::Data, Error, FromDeriveInput, FromField, FromVariant};
+use darling::{ast::Data, util::SpannedValue, Error, FromDeriveInput, FromField, FromVariant};
 use proc_macro::TokenStream;
 use quote::quote;
+use std::collections::HashSet;
 use syn::{parse_macro_input, DeriveInput};
 
 #[derive(Debug, FromDeriveInput)]
 #[darling(attributes(contracts), supports(enum_any), forward_attrs(allow, doc, cfg))]
 struct ContractsOpt {
     ident: syn::Ident,
+    vis: syn::Visibility,
+    generics: syn::Generics,
     data: darling::ast::Data<ContractsVariant, darling::util::Ignored>,
-    template: String,
+    template: SpannedValue<String>,
     #[darling(default)]
     token: bool,
     #[darling(default)]
     asset: bool,
 }
 
+impl ContractsOpt {
+    /// Template type, validated by [derive_contracts_impl] before code generation
+    fn template_type(&self) -> syn::Type {
+        syn::parse_str(self.template.as_str()).expect("template is validated before code generation")
+    }
+
+    /// Contracts have borrowed params, so they are deserialized and sent to the actor as `{Contracts}Owned`
+    fn borrowed(&self) -> bool {
+        let variants = match &self.data {
+            Data::Enum(variants) => variants.as_slice(),
+            _ => &[],
+        };
+        !self.generics.params.is_empty() ||
+            variants
+                .iter()
+                .flat_map(|variant| variant.fields.fields.iter())
+                .any(|field| owned::is_borrowed(&field.ty))
+    }
+
+    /// Enum created from request and sent to the actor: contracts enum itself or its owned counterpart
+    fn owned_ident(&self) -> syn::Ident {
+        if self.borrowed() {
+            owned::owned_ident(&self.ident)
+        } else {
+            self.ident.clone()
+        }
+    }
+}
+
 #[derive(Debug, FromVariant)]
 #[darling(attributes(contract))]
 struct ContractsVariant {
     ident: syn::Ident,
     fields: darling::ast::Fields<ContractsVariantFields>,
     #[darling(default)]
-    method: Option<String>,
+    method: Option<SpannedValue<String>>,
+    /// Read-only contract served via GET without creating an Instruction
+    #[darling(default)]
+    view: bool,
 }
 
 #[derive(Debug, FromField)]
+#[darling(forward_attrs(doc, serde))]
 struct ContractsVariantFields {
+    ident: Option<syn::Ident>,
     ty: syn::Type,
+    attrs: Vec<syn::Attribute>,
 }
 
 #[proc_macro_derive(Contracts, attributes(contracts, contract))]
@@ -40,39 +78,92 @@ fn derive_contracts_impl(input: DeriveInput) -> proc_macro2::TokenStream {
         Ok(attrs) => attrs,
         Err(e) => return e.write_errors().into(),
     };
+    let variants = match &opts.data {
+        Data::Enum(variants) => variants,
+        _ => {
+            return Error::unexpected_type("#[derive(Contracts)] can only be applied to enum")
+                .with_span(&opts.ident)
+                .write_errors()
+                .into();
+        },
+    };
+    // all the errors are collected, so every misconfigured variant is reported at once
+    let mut errors = vec![];
     if !(opts.asset ^ opts.token) {
-        let msg = format!(
-            "#[derive(Contracts)]: contract type #[contracts(..)] attribute: one of token or asset should be specified"
+        errors.push(
+            Error::custom(
+                "#[derive(Contracts)]: contract type #[contracts(..)] attribute: one of token or asset should be \
+                 specified",
+            )
+            .with_span(&opts.ident),
+        );
+    }
+    // borrowed params are declared with lifetimes of the enum
+    if opts.generics.lifetimes().count() != opts.generics.params.len() {
+        errors.push(
+            Error::custom("#[derive(Contracts)]: contracts enum can have only lifetime parameters")
+                .with_span(&opts.generics),
+        );
+    }
+    if syn::parse_str::<syn::Type>(opts.template.as_str()).is_err() {
+        errors.push(
+            Error::custom(format!(
+                "#[derive(Contracts)]: template `{}` should be a Template type",
+                opts.template.as_str()
+            ))
+            .with_span(&spanned(&opts.template)),
         );
-        return Error::custom(msg.as_str()).with_span(&opts.ident).write_errors().into();
-    } else if opts.asset {
-        let msg = format!("#[derive(Contracts)]: contract type `asset` is not supprted yet");
-        return Error::custom(msg.as_str()).with_span(&opts.ident).write_errors().into();
     }
-    let mut web_handlers = vec![];
-    if let Data::Enum(variants) = &opts.data {
-        for contract in variants {
-            if contract.method.is_none() {
-                return Error::custom(
-                    "#[derive(Contracts)]: variant requires attribute #[contract(method=..)] to be defined",
+    let mut methods = HashSet::new();
+    for contract in variants {
+        match &contract.method {
+            None => errors.push(
+                Error::custom("#[derive(Contracts)]: variant requires attribute #[contract(method=..)] to be defined")
+                    .with_span(&contract.ident),
+            ),
+            Some(method) if syn::parse_str::<syn::Ident>(method.as_str()).is_err() => errors.push(
+                Error::custom(format!(
+                    "#[derive(Contracts)]: method `{}` should be a valid function name",
+                    method.as_str()
+                ))
+                .with_span(&spanned(method)),
+            ),
+            Some(method) if opts.asset && method.as_str() == "batch" => errors.push(
+                Error::custom("#[derive(Contracts)]: method `batch` is reserved for batch calls of asset contracts")
+                    .with_span(&spanned(method)),
+            ),
+            Some(method) if !methods.insert(method.as_str()) => errors.push(
+                Error::custom(format!(
+                    "#[derive(Contracts)]: method `{}` is defined by more than one variant",
+                    method.as_str()
+                ))
+                .with_span(&spanned(method)),
+            ),
+            Some(_) => {},
+        }
+        if contract.fields.is_tuple() && contract.fields.len() > 1 {
+            errors.push(
+                Error::custom(
+                    "#[derive(Contracts)]: tuple variant can have only single params element, use struct variant for \
+                     multiple fields",
                 )
-                .with_span(&contract.ident)
-                .write_errors()
-                .into();
-            } else if !contract.fields.is_tuple() || contract.fields.len() > 1 {
-                return Error::custom("#[derive(Contracts)]: variant can be defined only on single element tuple")
-                    .with_span(&contract.ident)
-                    .write_errors()
-                    .into();
+                .with_span(&contract.ident),
+            );
+            continue;
+        }
+        for field in contract.fields.fields.iter() {
+            if let Err(err) = owned::owned_type(&field.ty) {
+                errors.push(err);
             }
-            web_handlers.push(ContractImpl::generate(contract, &opts));
         }
-    } else {
-        return Error::unexpected_type("#[derive(Contracts)] can only be applied to enum")
-            .with_span(&opts.ident)
-            .write_errors()
-            .into();
-    };
+    }
+    if !errors.is_empty() {
+        return Error::multiple(errors).write_errors().into();
+    }
+    let web_handlers: Vec<_> = variants
+        .iter()
+        .map(|contract| ContractImpl::generate(contract, &opts))
+        .collect();
 
     let contracts_impl = contracts::generate(&web_handlers, &opts);
 
@@ -85,9 +176,15 @@ fn derive_contracts_impl(input: DeriveInput) -> proc_macro2::TokenStream {
     }
 }
 
+/// Attribute value as string literal, so errors point to the value
+fn spanned(value: &SpannedValue<String>) -> syn::LitStr {
+    syn::LitStr::new(value.as_str(), value.span())
+}
+
 mod contract;
 pub(crate) use contract::ContractImpl;
 mod contracts;
+mod owned;
 
 #[cfg(test)]
 mod test {
@@ -156,6 +253,82 @@ enum Supported {
     OptionOne(String),
     #[contract(method="option_two")]
     OptionTwo(String),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",asset)]
+enum Supported {
+    #[contract(method="option_one")]
+    OptionOne(String),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum Supported {
+    #[contract(method="option_one")]
+    OptionOne(String),
+    #[contract(method="option_view", view)]
+    OptionView(Option<Vec<String>>),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum Supported {
+    #[contract(method="option_one")]
+    OptionOne,
+    #[contract(method="option_two")]
+    OptionTwo(),
+    #[contract(method="option_three")]
+    OptionThree {},
+    #[contract(method="option_four")]
+    OptionFour {
+        /// Field doc goes to params schema
+        price: i64,
+        #[serde(default)]
+        user_pubkey: Option<String>,
+    },
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="crate::template::Template",asset)]
+enum Supported {
+    #[contract(method="option_one")]
+    OptionOne(Option<params::OptionOneParams>),
+    #[contract(method="option_two")]
+    OptionTwo((String, [u8; 32])),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum Supported {
+    #[contract(method="option_one")]
+    OptionOne(&'static str),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",asset)]
+enum Supported<'a> {
+    #[contract(method="option_one")]
+    OptionOne(Option<Params<'a>>),
+    #[contract(method="option_two")]
+    OptionTwo(&'a OptionTwoParams),
+    #[contract(method="option_three")]
+    OptionThree {
+        name: &'a str,
+        tags: Option<Vec<&'a str>>,
+        #[serde(default)]
+        price: i64,
+    },
+    #[contract(method="option_four", view)]
+    OptionFour((&'a [TokenID], Cow<'a, str>)),
+    #[contract(method="option_five")]
+    OptionFive,
 }
         "###,
     ];
@@ -166,6 +339,9 @@ enum Supported {
             let parsed: syn::DeriveInput = syn::parse_str(*input).expect(format!("Failed to parse {}", input).as_str());
             let result = ContractsOpt::from_derive_input(&parsed);
             assert!(result.is_ok(), "{} -> {:?}", input, result);
+            let tokens = derive_contracts_impl(parsed);
+            let ident = tokens.into_iter().next().unwrap().to_string();
+            assert_ne!(ident, "compile_error", "{}", input);
         }
     }
 
@@ -199,7 +375,7 @@ pub enum TokenContracts {
 #[contracts(template="Template",token)]
 enum NotSupported {
     #[contract(method="option_one")]
-    OptionOne,
+    OptionOne(String, u64),
 }
         "###,
         r###"
@@ -220,6 +396,74 @@ enum NotSupported {
         r###"
 #[derive(Contracts)]
 #[contracts(template="Template")]
+enum NotSupported {
+    #[contract(method="option_one")]
+    OptionOne(String),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum NotSupported {
+    #[contract(method="option_one")]
+    OptionOne(),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum NotSupported {
+    #[contract(method="option-one")]
+    OptionOne(String),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum NotSupported {
+    #[contract(method="option_one")]
+    OptionOne(String),
+    #[contract(method="option_one")]
+    OptionTwo(String),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum NotSupported<'a> {
+    #[contract(method="option_one")]
+    OptionOne(&'a mut Params),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",asset)]
+enum NotSupported<'a> {
+    #[contract(method="option_one")]
+    OptionOne {
+        prices: HashMap<&'a str, i64>,
+    },
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",token)]
+enum NotSupported<T> {
+    #[contract(method="option_one")]
+    OptionOne(Option<T>),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="Template",asset)]
+enum NotSupported {
+    #[contract(method="batch")]
+    Batch(String),
+}
+        "###,
+        r###"
+#[derive(Contracts)]
+#[contracts(template="not a template",token)]
 enum NotSupported {
     #[contract(method="option_one")]
     OptionOne(String),
@@ -0,0 +1,286 @@
+//! Borrowed params of contracts
+//!
+//! Params are deserialized from request and moved into actor message, so they are owned,
+//! while contract may accept references, e.g. `&str`, `Option<&str>` or `(&[TokenID], u64)`:
+//! - reference `&T` is deserialized into `<T as ToOwned>::Owned` and borrowed back when contract is called
+//! - references are supported within `Option`, `Vec` and tuples
+//! - other types are moved or cloned, their lifetimes become `'static`, e.g. `Cow<'a, str>` is deserialized
+//!   as `Cow<'static, str>`
+use darling::Error;
+use quote::{format_ident, quote};
+use syn::Type;
+
+/// Type has references or lifetimes at any depth, so it's not the type params are deserialized into
+pub(crate) fn is_borrowed(ty: &Type) -> bool {
+    contains(ty, true)
+}
+
+/// Type has references at any depth, so contract is called with borrowed params
+fn has_reference(ty: &Type) -> bool {
+    contains(ty, false)
+}
+
+fn contains(ty: &Type, lifetimes: bool) -> bool {
+    match ty {
+        Type::Reference(_) => true,
+        Type::Path(path) => path.path.segments.iter().any(|segment| match &segment.arguments {
+            syn::PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| match arg {
+                syn::GenericArgument::Type(ty) => contains(ty, lifetimes),
+                syn::GenericArgument::Lifetime(lifetime) => lifetimes && lifetime.ident != "static",
+                _ => false,
+            }),
+            _ => false,
+        }),
+        Type::Tuple(tuple) => tuple.elems.iter().any(|ty| contains(ty, lifetimes)),
+        Type::Array(array) => contains(&array.elem, lifetimes),
+        Type::Slice(slice) => contains(&slice.elem, lifetimes),
+        Type::Paren(paren) => contains(&paren.elem, lifetimes),
+        Type::Group(group) => contains(&group.elem, lifetimes),
+        _ => false,
+    }
+}
+
+/// Lifetimes of the type, so generated params struct declares them
+pub(crate) fn lifetimes(ty: &Type, found: &mut Vec<syn::Lifetime>) {
+    fn push(found: &mut Vec<syn::Lifetime>, lifetime: &syn::Lifetime) {
+        if lifetime.ident != "static" && !found.contains(lifetime) {
+            found.push(lifetime.clone());
+        }
+    }
+    match ty {
+        Type::Reference(reference) => {
+            if let Some(lifetime) = &reference.lifetime {
+                push(found, lifetime);
+            }
+            lifetimes(&reference.elem, found);
+        },
+        Type::Path(path) => {
+            for segment in path.path.segments.iter() {
+                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                    for arg in args.args.iter() {
+                        match arg {
+                            syn::GenericArgument::Type(ty) => lifetimes(ty, found),
+                            syn::GenericArgument::Lifetime(lifetime) => push(found, lifetime),
+                            _ => {},
+                        }
+                    }
+                }
+            }
+        },
+        Type::Tuple(tuple) => tuple.elems.iter().for_each(|ty| lifetimes(ty, found)),
+        Type::Array(array) => lifetimes(&array.elem, found),
+        Type::Slice(slice) => lifetimes(&slice.elem, found),
+        Type::Paren(paren) => lifetimes(&paren.elem, found),
+        Type::Group(group) => lifetimes(&group.elem, found),
+        _ => {},
+    }
+}
+
+/// Container of references, which is borrowed element by element
+fn container(path: &syn::TypePath) -> Option<&syn::Ident> {
+    path.path
+        .segments
+        .last()
+        .map(|segment| &segment.ident)
+        .filter(|ident| *ident == "Option" || *ident == "Vec")
+}
+
+/// Owned type params are deserialized into, unsupported types are reported with spanned errors
+pub(crate) fn owned_type(ty: &Type) -> Result<Type, Error> {
+    match ty {
+        Type::Reference(reference) => {
+            if reference.mutability.is_some() {
+                return Err(Error::custom("#[derive(Contracts)]: params can't be mutable references").with_span(ty));
+            }
+            if is_borrowed(&reference.elem) {
+                return Err(Error::custom(
+                    "#[derive(Contracts)]: params can't be references to borrowed types, e.g. use `&[String]` \
+                     instead of `&[&str]`",
+                )
+                .with_span(ty));
+            }
+            let elem = owned_type(&reference.elem)?;
+            Ok(syn::parse_quote! { <#elem as std::borrow::ToOwned>::Owned })
+        },
+        Type::Path(path) => {
+            let mut owned = path.clone();
+            let container = container(path).is_some();
+            for segment in owned.path.segments.iter_mut() {
+                if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
+                    for arg in args.args.iter_mut() {
+                        match arg {
+                            syn::GenericArgument::Type(ty) if has_reference(ty) && !container => {
+                                return Err(Error::custom(
+                                    "#[derive(Contracts)]: references in params are supported within Option, Vec and \
+                                     tuples",
+                                )
+                                .with_span(ty));
+                            },
+                            syn::GenericArgument::Type(ty) => *ty = owned_type(ty)?,
+                            syn::GenericArgument::Lifetime(lifetime) => {
+                                *lifetime = syn::Lifetime::new("'static", lifetime.apostrophe)
+                            },
+                            _ => {},
+                        }
+                    }
+                }
+            }
+            Ok(Type::Path(owned))
+        },
+        Type::Tuple(tuple) => {
+            let elems = tuple.elems.iter().map(owned_type).collect::<Result<Vec<_>, _>>()?;
+            Ok(syn::parse_quote! { ( #( #elems, )* ) })
+        },
+        Type::Array(array) if has_reference(&array.elem) => Err(Error::custom(
+            "#[derive(Contracts)]: references in params are supported within Option, Vec and tuples",
+        )
+        .with_span(ty)),
+        Type::Array(array) => {
+            let mut owned = array.clone();
+            *owned.elem = owned_type(&array.elem)?;
+            Ok(Type::Array(owned))
+        },
+        Type::Slice(slice) if has_reference(&slice.elem) => Err(Error::custom(
+            "#[derive(Contracts)]: references in params are supported within Option, Vec and tuples",
+        )
+        .with_span(ty)),
+        Type::Slice(slice) => {
+            let mut owned = slice.clone();
+            *owned.elem = owned_type(&slice.elem)?;
+            Ok(Type::Slice(owned))
+        },
+        Type::Paren(paren) => owned_type(&paren.elem),
+        Type::Group(group) => owned_type(&group.elem),
+        _ => Err(Error::custom("#[derive(Contracts)]: unsupported params type").with_span(ty)),
+    }
+}
+
+/// Contract argument of type `ty` from `value` of [owned_type], which is moved unless `ty` has references
+pub(crate) fn contract_arg(ty: &Type, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    if has_reference(ty) {
+        borrow(ty, quote! { &#value })
+    } else {
+        value
+    }
+}
+
+/// Borrows `value: &owned_type(ty)` as `ty`, owned parts of the type are cloned
+fn borrow(ty: &Type, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    if !has_reference(ty) {
+        return quote! { std::clone::Clone::clone(#value) };
+    }
+    match ty {
+        Type::Reference(reference) => {
+            let elem = &reference.elem;
+            quote! { std::borrow::Borrow::<#elem>::borrow(#value) }
+        },
+        Type::Path(path) => {
+            let elem = path
+                .path
+                .segments
+                .last()
+                .and_then(|segment| match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    }),
+                    _ => None,
+                })
+                .expect("containers of references are validated by owned_type");
+            let elem = borrow(elem, quote! { value });
+            match container(path) {
+                Some(ident) if ident == "Option" => quote! { (#value).as_ref().map(|value| #elem) },
+                _ => quote! { (#value).iter().map(|value| #elem).collect::<Vec<_>>() },
+            }
+        },
+        Type::Tuple(tuple) => {
+            let elems = tuple.elems.iter().enumerate().map(|(index, ty)| {
+                let index = syn::Index::from(index);
+                borrow(ty, quote! { &value.#index })
+            });
+            quote! {{
+                let value = #value;
+                ( #( #elems, )* )
+            }}
+        },
+        Type::Paren(paren) => borrow(&paren.elem, value),
+        Type::Group(group) => borrow(&group.elem, value),
+        _ => unreachable!("params types are validated by owned_type"),
+    }
+}
+
+/// Name of generated owned counterpart, e.g. `AssetContractsOwned` or `RenameParamsOwned`
+pub(crate) fn owned_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("{}Owned", ident)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn owned_types() {
+        let types = [
+            ("String", "String"),
+            ("Option<Vec<String>>", "Option<Vec<String>>"),
+            ("&'a str", "<str as std::borrow::ToOwned>::Owned"),
+            ("&'static [TokenID]", "<[TokenID] as std::borrow::ToOwned>::Owned"),
+            ("Option<&'a str>", "Option<<str as std::borrow::ToOwned>::Owned>"),
+            ("Vec<&'a Params>", "Vec<<Params as std::borrow::ToOwned>::Owned>"),
+            ("(&'a str, u64)", "(<str as std::borrow::ToOwned>::Owned, u64,)"),
+            ("Cow<'a, str>", "Cow<'static, str>"),
+            ("Option<Params<'a>>", "Option<Params<'static>>"),
+        ];
+        for (ty, expected) in types.iter() {
+            let ty: Type = syn::parse_str(ty).unwrap();
+            let owned = owned_type(&ty).unwrap();
+            let expected: Type = syn::parse_str(expected).unwrap();
+            assert_eq!(quote!(#owned).to_string(), quote!(#expected).to_string());
+        }
+    }
+
+    #[test]
+    fn unsupported_types() {
+        let types = [
+            "&'a mut String",
+            "&'a [&'a str]",
+            "&'a Params<'a>",
+            "HashMap<&'a str, u64>",
+            "[&'a str; 2]",
+            "fn(&str)",
+            "impl Serialize",
+        ];
+        for ty in types.iter() {
+            let ty: Type = syn::parse_str(ty).unwrap();
+            assert!(owned_type(&ty).is_err(), "{}", ty_str(&ty));
+        }
+    }
+
+    #[test]
+    fn contract_args() {
+        let args = [
+            ("String", "params"),
+            ("Cow<'a, str>", "params"),
+            ("&'a str", "std::borrow::Borrow::<str>::borrow(&params)"),
+            (
+                "Option<&'a str>",
+                "(&params).as_ref().map(|value| std::borrow::Borrow::<str>::borrow(value))",
+            ),
+            (
+                "(&'a str, u64)",
+                "{ let value = &params; (std::borrow::Borrow::<str>::borrow(&value.0), \
+                 std::clone::Clone::clone(&value.1),) }",
+            ),
+        ];
+        for (ty, expected) in args.iter() {
+            let ty: Type = syn::parse_str(ty).unwrap();
+            let expected: syn::Expr = syn::parse_str(expected).unwrap();
+            let arg = contract_arg(&ty, quote!(params));
+            assert_eq!(arg.to_string(), quote!(#expected).to_string(), "{}", ty_str(&ty));
+        }
+    }
+
+    fn ty_str(ty: &Type) -> String {
+        quote!(#ty).to_string()
+    }
+}
@@ -0,0 +1,7 @@
+/// Misconfigured `#[derive(Contracts)]` should fail with errors pointing to the source of the problem,
+/// expected compiler output is in `tests/ui/*.stderr`, regenerate it with `TRYBUILD=overwrite cargo test`
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,12 @@
+use tari_template_derive::Contracts;
+
+#[derive(Contracts)]
+#[contracts(template = "Template", token)]
+enum TokenContracts {
+    #[contract(method = "option_one")]
+    OptionOne(String),
+    #[contract(method = "option_one")]
+    OptionTwo(String),
+}
+
+fn main() {}
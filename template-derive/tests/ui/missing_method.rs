@@ -0,0 +1,9 @@
+use tari_template_derive::Contracts;
+
+#[derive(Contracts)]
+#[contracts(template = "Template", token)]
+enum TokenContracts {
+    OptionOne(String),
+}
+
+fn main() {}
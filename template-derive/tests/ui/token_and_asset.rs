@@ -0,0 +1,10 @@
+use tari_template_derive::Contracts;
+
+#[derive(Contracts)]
+#[contracts(template = "Template", token, asset)]
+enum TokenContracts {
+    #[contract(method = "option_one")]
+    OptionOne(String),
+}
+
+fn main() {}